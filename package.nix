@@ -30,7 +30,5 @@ craneLib.buildPackage {
   nativeBuildInputs = lib.optionals stdenv.isLinux [
     pkg-config
   ];
-
-  SQLX_OFFLINE = "true";
 }
 
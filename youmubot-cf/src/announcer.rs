@@ -5,7 +5,7 @@ use crate::{
 use announcer::MemberToChannels;
 use chrono::Utc;
 use codeforces::{RatingChange, User};
-use serenity::{builder::CreateMessage, http::CacheHttp, model::id::UserId};
+use serenity::{builder::CreateMessage, model::id::UserId};
 use youmubot_prelude::{announcer::CacheAndHttp, *};
 
 type Client = <CFClient as TypeMapKey>::Value;
@@ -23,6 +23,7 @@ impl youmubot_prelude::Announcer for Announcer {
     ) -> Result<()> {
         let data = data.read().await;
         let client = data.get::<CFClient>().unwrap();
+        let send_queue = data.get::<SendQueue>().unwrap();
         let mut users = CfSavedUsers::open(&data).borrow()?.clone();
         users
             .iter_mut()
@@ -30,7 +31,9 @@ impl youmubot_prelude::Announcer for Announcer {
                 let http = http.clone();
                 let channels = &channels;
                 async move {
-                    if let Err(e) = update_user(http, channels, client, *user_id, cfu).await {
+                    if let Err(e) =
+                        update_user(http, channels, client, send_queue, *user_id, cfu).await
+                    {
                         cfu.failures += 1;
                         eprintln!(
                             "Codeforces: cannot update user {}: {} [{} failures]",
@@ -70,6 +73,7 @@ async fn update_user(
     http: CacheAndHttp,
     channels: &MemberToChannels,
     client: &Client,
+    send_queue: &SendQueue,
     user_id: UserId,
     cfu: &mut CfUser,
 ) -> Result<()> {
@@ -113,6 +117,7 @@ async fn update_user(
             let channels = channels_list.clone();
             let http = http.clone();
             let info = info.clone();
+            let send_queue = send_queue.clone();
             async move {
                 if channels.is_empty() {
                     return Ok(());
@@ -123,8 +128,9 @@ async fn update_user(
                 channels
                     .iter()
                     .map(|channel| {
-                        channel.send_message(
-                            http.http(),
+                        send_queue.send(
+                            http.clone(),
+                            *channel,
                             CreateMessage::new()
                                 .content(format!("Rating change for {}!", user_id.mention()))
                                 .embed(crate::embed::rating_change_embed(
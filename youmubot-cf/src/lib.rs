@@ -14,6 +14,7 @@ use serenity::{
 use db::{CfSavedUsers, CfUser};
 pub use hook::InfoHook;
 use youmubot_prelude::announcer::AnnouncerHandler;
+use youmubot_prelude::permissions::MANAGE_CHANNELS_CHECK;
 use youmubot_prelude::table_format::table_formatting_unsafe;
 use youmubot_prelude::table_format::Align::{Left, Right};
 use youmubot_prelude::{
@@ -29,6 +30,12 @@ mod hook;
 /// Live-commentating a Codeforces round.
 mod live;
 
+/// The hook on Codeforces links. Registered by whoever adds the hook.
+pub const HOOKS_MODULE: Module = Module::new(
+    "codeforces_hooks",
+    "show the contests and problems of Codeforces links",
+);
+
 /// The TypeMapKey holding the Client.
 struct CFClient;
 
@@ -37,14 +44,32 @@ impl TypeMapKey for CFClient {
 }
 
 /// Sets up the CF databases.
-pub async fn setup(path: &std::path::Path, data: &mut TypeMap, announcers: &mut AnnouncerHandler) {
-    CfSavedUsers::insert_into(data, path.join("cf_saved_users.yaml"))
-        .expect("Must be able to set up DB");
+///
+/// Fails if the contest list cannot be fetched, which is usually worth retrying.
+pub async fn setup(
+    path: &std::path::Path,
+    data: &mut TypeMap,
+    announcers: &mut AnnouncerHandler,
+) -> Result<(), SetupError> {
+    let users_path = path.join("cf_saved_users.yaml");
+    CfSavedUsers::insert_into(data, &users_path).map_err(|e| SetupError::Storage {
+        what: users_path.display().to_string(),
+        source: e.into(),
+    })?;
     let client = Arc::new(codeforces::Client::new());
-    data.insert::<hook::ContestCache>(hook::ContestCache::new(client.clone()).await.unwrap());
+    let contests =
+        hook::ContestCache::new(client.clone())
+            .await
+            .map_err(|e| SetupError::Unavailable {
+                service: "Codeforces",
+                transient: true,
+                source: e,
+            })?;
+    data.insert::<hook::ContestCache>(contests);
     data.insert::<CFClient>(client);
     data.insert::<live::WatchData>(live::WatchData::new());
     announcers.add("codeforces", announcer::Announcer);
+    Ok(())
 }
 
 #[group]
@@ -287,7 +312,7 @@ pub async fn contestranks(ctx: &Context, m: &Message, mut args: Args) -> Command
 #[description = "Watch a contest and announce any change on the members of the server assigned to the contest."]
 #[usage = "[the contest id]"]
 #[num_args(1)]
-#[required_permissions(MANAGE_CHANNELS)]
+#[checks(manage_channels)]
 #[only_in(guilds)]
 pub async fn watch(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
     let contest_id: u64 = args.single()?;
@@ -8,6 +8,7 @@ use serenity::{
     model::channel::{Channel, Message},
 };
 use soft_ban::{SOFT_BAN_COMMAND, SOFT_BAN_INIT_COMMAND};
+use youmubot_prelude::permissions::{ADMINISTRATOR_CHECK, MANAGE_MESSAGES_CHECK};
 use youmubot_prelude::*;
 
 mod soft_ban;
@@ -20,7 +21,7 @@ struct Admin;
 
 #[command]
 #[aliases("cleanall")]
-#[required_permissions(MANAGE_MESSAGES)]
+#[checks(manage_messages)]
 #[description = "Clean at most X latest messages from the current channel (only clean Youmu's messages in DMs). Defaults to 10."]
 #[usage = "clean 50"]
 #[min_args(0)]
@@ -59,7 +60,7 @@ async fn clean(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
 }
 
 #[command]
-#[required_permissions(ADMINISTRATOR)]
+#[checks(administrator)]
 #[description = "Ban an user with a certain reason."]
 #[usage = "tag user/[reason = none]/[days of messages to delete = 0]"]
 #[min_args(1)]
@@ -96,7 +97,7 @@ async fn ban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
 }
 
 #[command]
-#[required_permissions(ADMINISTRATOR)]
+#[checks(administrator)]
 #[description = "Kick an user."]
 #[usage = "@user#1234"]
 #[num_args(1)]
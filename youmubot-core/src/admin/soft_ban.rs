@@ -5,10 +5,11 @@ use serenity::{
     framework::standard::{macros::command, Args, CommandResult},
     model::{channel::Message, id},
 };
+use youmubot_prelude::permissions::ADMINISTRATOR_CHECK;
 use youmubot_prelude::*;
 
 #[command]
-#[required_permissions(ADMINISTRATOR)]
+#[checks(administrator)]
 #[description = "Soft-ban an user, might be with a certain amount of time. Re-banning an user removes the ban itself."]
 #[usage = "user#1234 [time]"]
 #[example = "user#1234 5s"]
@@ -72,7 +73,7 @@ pub async fn soft_ban(ctx: &Context, msg: &Message, mut args: Args) -> CommandRe
 }
 
 #[command]
-#[required_permissions(ADMINISTRATOR)]
+#[checks(administrator)]
 #[description = "Sets up the soft-ban command. This command can only be run once.\nThe soft-ban command assigns a role, temporarily, to a user."]
 #[usage = "{soft_ban_role_id}"]
 #[num_args(1)]
@@ -10,6 +10,7 @@ use serenity::{
 };
 
 pub use reaction_watcher::Watchers as ReactionWatchers;
+use youmubot_prelude::permissions::MANAGE_ROLES_CHECK;
 use youmubot_prelude::table_format::Align::Right;
 use youmubot_prelude::table_format::{table_formatting, Align};
 use youmubot_prelude::*;
@@ -138,7 +139,7 @@ async fn toggle(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
 #[example = "hd820 / Headphones role / 🎧"]
 #[min_args(2)]
 #[max_args(3)]
-#[required_permissions(MANAGE_ROLES)]
+#[checks(manage_roles)]
 #[only_in(guilds)]
 async fn add(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
     let role = args.single_quoted::<String>()?;
@@ -191,7 +192,7 @@ async fn add(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
 #[usage = "{role-name-or-id}"]
 #[example = "hd820"]
 #[num_args(1)]
-#[required_permissions(MANAGE_ROLES)]
+#[checks(manage_roles)]
 #[only_in(guilds)]
 async fn remove(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
     let role = args.single_quoted::<String>()?;
@@ -242,7 +243,7 @@ fn role_from_string(role: &str, roles: &std::collections::HashMap<RoleId, Role>)
 #[usage = "{title}/[role]/[role]/..."]
 #[example = "Game Roles/Genshin/osu!"]
 #[min_args(1)]
-#[required_permissions(MANAGE_ROLES)]
+#[checks(manage_roles)]
 #[only_in(guilds)]
 async fn rolemessage(ctx: &Context, m: &Message, args: Args) -> CommandResult {
     let (title, roles) = match parse(ctx, m, args).await? {
@@ -319,7 +320,7 @@ async fn parse(
 #[usage = "{title}/[role]/[role]/..."]
 #[example = "Game Roles/Genshin/osu!"]
 #[min_args(1)]
-#[required_permissions(MANAGE_ROLES)]
+#[checks(manage_roles)]
 #[only_in(guilds)]
 async fn updaterolemessage(ctx: &Context, m: &Message, args: Args) -> CommandResult {
     let (title, roles) = match parse(ctx, m, args).await? {
@@ -361,7 +362,7 @@ async fn updaterolemessage(ctx: &Context, m: &Message, args: Args) -> CommandRes
 #[description = "Delete a role message handler."]
 #[usage = "(reply to the message to delete)"]
 #[num_args(0)]
-#[required_permissions(MANAGE_ROLES)]
+#[checks(manage_roles)]
 #[only_in(guilds)]
 async fn rmrolemessage(ctx: &Context, m: &Message, _args: Args) -> CommandResult {
     let data = ctx.data.read().await;
@@ -18,19 +18,24 @@ mod db;
 pub mod fun;
 
 /// Sets up all databases in the client.
-pub fn setup(
-    path: &std::path::Path,
-    data: &mut TypeMap,
-) -> serenity::framework::standard::CommandResult {
-    db::SoftBans::insert_into(&mut *data, &path.join("soft_bans.yaml"))?;
+pub fn setup(path: &std::path::Path, data: &mut TypeMap) -> Result<(), SetupError> {
+    let storage = |what: &str, source: Error| SetupError::Storage {
+        what: path.join(what).display().to_string(),
+        source,
+    };
+    db::SoftBans::insert_into(&mut *data, &path.join("soft_bans.yaml"))
+        .map_err(|e| storage("soft_bans.yaml", e.into()))?;
     db::load_role_list(
         &mut *data,
         &path.join("roles_v2.yaml"),
         &path.join("roles.yaml"),
-    )?;
+    )
+    .map_err(|e| storage("roles_v2.yaml", e))?;
 
     // Start reaction handlers
-    data.insert::<community::ReactionWatchers>(community::ReactionWatchers::new(&*data)?);
+    let watchers =
+        community::ReactionWatchers::new(&*data).map_err(|e| storage("roles_v2.yaml", e))?;
+    data.insert::<community::ReactionWatchers>(watchers);
 
     Ok(())
 }
@@ -0,0 +1,12 @@
+-- Add migration script here
+
+CREATE TABLE osu_users (
+    user_id     BIGINT   NOT NULL PRIMARY KEY,
+    id          BIGINT   NOT NULL UNIQUE,
+    last_update TIMESTAMPTZ NOT NULL,
+    pp_std      DOUBLE PRECISION NULL,
+    pp_taiko    DOUBLE PRECISION NULL,
+    pp_mania    DOUBLE PRECISION NULL,
+    pp_catch    DOUBLE PRECISION NULL,
+    failures    BIGINT   NOT NULL DEFAULT 0
+);
@@ -0,0 +1,45 @@
+-- Add migration script here
+
+CREATE TABLE osu_last_beatmaps (
+    channel_id    BIGINT NOT NULL PRIMARY KEY,
+    beatmap       BYTEA  NOT NULL,
+    mode          BIGINT NOT NULL
+);
+
+CREATE TABLE osu_user_best_scores (
+    beatmap_id BIGINT NOT NULL,
+    mode       BIGINT NOT NULL,
+    user_id    BIGINT NOT NULL REFERENCES osu_users(user_id) ON DELETE CASCADE,
+    mods       BIGINT NOT NULL,
+
+    cached_at TIMESTAMPTZ NOT NULL,
+    score     BYTEA    NOT NULL,
+
+    PRIMARY KEY (beatmap_id, mode, user_id, mods)
+);
+
+CREATE TABLE osu_cached_beatmaps (
+    beatmap_id BIGINT NOT NULL,
+    mode       BIGINT NOT NULL,
+    
+    cached_at TIMESTAMPTZ NOT NULL,
+    beatmap   BYTEA    NOT NULL,
+
+    PRIMARY KEY (beatmap_id, mode)
+);
+
+CREATE TABLE osu_cached_beatmapsets (
+    beatmapset_id BIGINT NOT NULL,
+    beatmap_id    BIGINT NOT NULL,
+    mode          BIGINT NOT NULL,
+
+    PRIMARY KEY (beatmapset_id, beatmap_id, mode),
+    FOREIGN KEY (beatmap_id, mode) REFERENCES osu_cached_beatmaps (beatmap_id, mode)
+);
+
+CREATE TABLE osu_cached_beatmap_contents (
+    beatmap_id BIGINT NOT NULL PRIMARY KEY,
+
+    cached_at TIMESTAMPTZ NOT NULL,
+    content   BYTEA    NOT NULL
+);
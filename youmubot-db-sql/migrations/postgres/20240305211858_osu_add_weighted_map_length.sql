@@ -0,0 +1,5 @@
+-- Add migration script here
+
+ALTER TABLE osu_users
+    ADD COLUMN std_weighted_map_length DOUBLE PRECISION NULL DEFAULT NULL;
+
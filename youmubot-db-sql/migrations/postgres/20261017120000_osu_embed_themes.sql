@@ -0,0 +1,7 @@
+-- Add migration script here
+
+CREATE TABLE osu_embed_themes (
+    guild_id      BIGINT  NOT NULL PRIMARY KEY,
+    accent_color  BIGINT  NULL     DEFAULT NULL,
+    show_images   BOOLEAN NOT NULL DEFAULT TRUE
+);
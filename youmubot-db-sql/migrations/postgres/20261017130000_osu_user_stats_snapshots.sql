@@ -0,0 +1,11 @@
+-- Add migration script here
+
+CREATE TABLE osu_user_stats_snapshots (
+    user_id   BIGINT   NOT NULL,
+    mode      BIGINT   NOT NULL,
+    taken_at  TIMESTAMPTZ NOT NULL,
+    rank      BIGINT   NULL,
+    pp        DOUBLE PRECISION NULL,
+    accuracy  DOUBLE PRECISION NULL,
+    PRIMARY KEY (user_id, mode, taken_at)
+);
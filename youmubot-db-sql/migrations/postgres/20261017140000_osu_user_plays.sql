@@ -0,0 +1,9 @@
+-- Add migration script here
+
+CREATE TABLE osu_user_plays (
+    user_id     BIGINT   NOT NULL,
+    mode        BIGINT   NOT NULL,
+    played_at   TIMESTAMPTZ NOT NULL,
+    beatmap_id  BIGINT   NOT NULL,
+    PRIMARY KEY (user_id, mode, played_at)
+);
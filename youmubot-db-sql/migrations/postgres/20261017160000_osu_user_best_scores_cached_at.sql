@@ -0,0 +1,3 @@
+-- Add migration script here
+
+CREATE INDEX osu_user_best_scores_cached_at ON osu_user_best_scores (cached_at);
@@ -0,0 +1,4 @@
+-- Add migration script here
+
+ALTER TABLE osu_user_stats_snapshots ADD COLUMN play_count BIGINT NULL;
+ALTER TABLE osu_user_stats_snapshots ADD COLUMN map_length DOUBLE PRECISION NULL;
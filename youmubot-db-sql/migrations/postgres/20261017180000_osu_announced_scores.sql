@@ -0,0 +1,26 @@
+-- Add migration script here
+
+CREATE TABLE osu_announced_scores (
+    guild_id        BIGINT   NOT NULL,
+    channel_id      BIGINT   NOT NULL,
+    message_id      BIGINT   NOT NULL,
+
+    discord_user_id BIGINT   NOT NULL,
+    osu_user_id     BIGINT   NOT NULL,
+    beatmap_id      BIGINT   NOT NULL,
+    mode            BIGINT   NOT NULL,
+    mods            BIGINT   NOT NULL,
+    pp              DOUBLE PRECISION NULL,
+    -- Whether it was a top record (0) or a leaderboard record (1), and its rank.
+    kind            BIGINT   NOT NULL,
+    rank            BIGINT   NOT NULL,
+    played_at       TIMESTAMPTZ NOT NULL,
+    announced_at    TIMESTAMPTZ NOT NULL,
+    -- To be deserialized by `bincode`
+    score           BYTEA    NOT NULL,
+
+    PRIMARY KEY (channel_id, message_id)
+);
+
+CREATE INDEX osu_announced_scores_guild ON osu_announced_scores (guild_id, played_at);
+CREATE INDEX osu_announced_scores_user ON osu_announced_scores (osu_user_id, played_at);
@@ -0,0 +1,18 @@
+-- Add migration script here
+
+-- Keep a history of the beatmaps requested in each channel, instead of only the last one.
+CREATE TABLE osu_channel_beatmaps (
+    id            BIGINT   GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    channel_id    BIGINT   NOT NULL,
+    -- To be deserialized by `bincode`
+    beatmap       BYTEA    NOT NULL,
+    mode          BIGINT   NOT NULL,
+    saved_at      TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX osu_channel_beatmaps_channel ON osu_channel_beatmaps (channel_id, id);
+
+INSERT INTO osu_channel_beatmaps (channel_id, beatmap, mode, saved_at)
+    SELECT channel_id, beatmap, mode, CURRENT_TIMESTAMP FROM osu_last_beatmaps;
+
+DROP TABLE osu_last_beatmaps;
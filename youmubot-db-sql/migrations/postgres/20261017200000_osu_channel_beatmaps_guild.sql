@@ -0,0 +1,7 @@
+-- Add migration script here
+
+-- Key the channel beatmaps by guild too, so that a guild's data can be removed at once.
+-- NULL for direct messages, and for beatmaps saved before this migration.
+ALTER TABLE osu_channel_beatmaps ADD COLUMN guild_id BIGINT NULL;
+
+CREATE INDEX osu_channel_beatmaps_guild ON osu_channel_beatmaps (guild_id);
@@ -0,0 +1,15 @@
+-- Add migration script here
+
+-- The .osu files are now stored on disk, named by their beatmap id and checksum.
+-- Only their index is kept in the database.
+DROP TABLE osu_cached_beatmap_contents;
+
+CREATE TABLE osu_cached_beatmap_files (
+    beatmap_id BIGINT   NOT NULL PRIMARY KEY,
+    file_md5   TEXT     NOT NULL,
+    -- The size of the file, in bytes.
+    size       BIGINT   NOT NULL,
+    cached_at  TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX osu_cached_beatmap_files_cached_at ON osu_cached_beatmap_files (cached_at);
@@ -0,0 +1,8 @@
+-- Per-channel switches for the message hooks, e.g. expanding osu! profile links.
+
+CREATE TABLE hook_settings (
+    channel_id BIGINT  NOT NULL,
+    hook       TEXT    NOT NULL,
+    enabled    BOOLEAN NOT NULL,
+    PRIMARY KEY (channel_id, hook)
+);
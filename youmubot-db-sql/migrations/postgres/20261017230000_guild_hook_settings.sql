@@ -0,0 +1,8 @@
+-- Guild-wide switches for the message hooks. Channel switches take priority over these.
+
+CREATE TABLE guild_hook_settings (
+    guild_id BIGINT  NOT NULL,
+    hook     TEXT    NOT NULL,
+    enabled  BOOLEAN NOT NULL,
+    PRIMARY KEY (guild_id, hook)
+);
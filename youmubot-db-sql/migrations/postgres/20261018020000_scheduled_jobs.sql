@@ -0,0 +1,6 @@
+-- When each scheduled job last ran, so that runs missed while the bot was down are caught up.
+
+CREATE TABLE scheduled_jobs (
+    name     TEXT     NOT NULL PRIMARY KEY,
+    last_run TIMESTAMPTZ NOT NULL
+);
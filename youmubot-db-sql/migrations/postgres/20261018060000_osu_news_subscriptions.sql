@@ -0,0 +1,12 @@
+-- The channels the osu! news and the lazer changelogs are posted to, one per server.
+
+CREATE TABLE osu_news_subscriptions (
+    guild_id      BIGINT   NOT NULL PRIMARY KEY,
+    channel_id    BIGINT   NOT NULL,
+    news          BOOLEAN  NOT NULL,
+    changelog     BOOLEAN  NOT NULL,
+    -- When the latest news article posted was published, or when the server subscribed.
+    last_news_at  TIMESTAMPTZ NOT NULL,
+    -- When the latest lazer release posted was built, or when the server subscribed.
+    last_build_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,20 @@
+-- The embed themes and the guild hook switches are now guild configuration values.
+-- The channel hook switches are moved by the bot, as it needs to look up the guild of each channel.
+
+INSERT INTO guild_config (guild_id, key, value)
+SELECT guild_id, 'embed_color', '#' || lpad(to_hex(accent_color), 6, '0')
+FROM osu_embed_themes
+WHERE accent_color IS NOT NULL;
+
+INSERT INTO guild_config (guild_id, key, value)
+SELECT guild_id, 'embed_images', 'false'
+FROM osu_embed_themes
+WHERE NOT show_images;
+
+INSERT INTO guild_config (guild_id, key, value)
+SELECT guild_id, 'hook_switches', string_agg(hook || '=' || CASE WHEN enabled THEN 'on' ELSE 'off' END, ',' ORDER BY hook)
+FROM guild_hook_settings
+GROUP BY guild_id;
+
+DROP TABLE osu_embed_themes;
+DROP TABLE guild_hook_settings;
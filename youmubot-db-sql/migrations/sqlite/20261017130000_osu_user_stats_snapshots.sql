@@ -0,0 +1,11 @@
+-- Add migration script here
+
+CREATE TABLE osu_user_stats_snapshots (
+    user_id   BIGINT   NOT NULL,
+    mode      INT      NOT NULL,
+    taken_at  DATETIME NOT NULL,
+    rank      BIGINT   NULL,
+    pp        REAL     NULL,
+    accuracy  REAL     NULL,
+    PRIMARY KEY (user_id, mode, taken_at)
+);
@@ -0,0 +1,14 @@
+-- Add migration script here
+
+CREATE TABLE osu_cached_difficulties (
+    beatmap_id BIGINT NOT NULL,
+    mode       INT    NOT NULL,
+    mods       BIGINT NOT NULL,
+    -- The md5 of the .osu file the attributes were computed from.
+    file_md5   TEXT   NOT NULL,
+
+    cached_at  DATETIME NOT NULL,
+    attributes BLOB     NOT NULL,
+
+    PRIMARY KEY (beatmap_id, mode, mods)
+);
@@ -0,0 +1,18 @@
+-- Add migration script here
+
+-- Keep a history of the beatmaps requested in each channel, instead of only the last one.
+CREATE TABLE osu_channel_beatmaps (
+    id            INTEGER  NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel_id    BIGINT   NOT NULL,
+    -- To be deserialized by `bincode`
+    beatmap       BLOB     NOT NULL,
+    mode          INT      NOT NULL,
+    saved_at      DATETIME NOT NULL
+);
+
+CREATE INDEX osu_channel_beatmaps_channel ON osu_channel_beatmaps (channel_id, id);
+
+INSERT INTO osu_channel_beatmaps (channel_id, beatmap, mode, saved_at)
+    SELECT channel_id, beatmap, mode, CURRENT_TIMESTAMP FROM osu_last_beatmaps;
+
+DROP TABLE osu_last_beatmaps;
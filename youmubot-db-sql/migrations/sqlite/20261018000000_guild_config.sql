@@ -0,0 +1,8 @@
+-- Generic per-guild configuration values, stored as text and parsed by their typed keys.
+
+CREATE TABLE guild_config (
+    guild_id BIGINT NOT NULL,
+    key      TEXT   NOT NULL,
+    value    TEXT   NOT NULL,
+    PRIMARY KEY (guild_id, key)
+);
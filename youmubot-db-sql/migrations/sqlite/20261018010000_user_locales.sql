@@ -0,0 +1,6 @@
+-- The language each user chose for the bot's messages, overriding their guild's.
+
+CREATE TABLE user_locales (
+    user_id BIGINT NOT NULL PRIMARY KEY,
+    locale  TEXT   NOT NULL
+);
@@ -0,0 +1,16 @@
+-- The server ranks posted automatically to a channel, on a cron schedule.
+
+CREATE TABLE osu_leaderboard_posts (
+    id          INTEGER  NOT NULL PRIMARY KEY AUTOINCREMENT,
+    guild_id    BIGINT   NOT NULL,
+    channel_id  BIGINT   NOT NULL,
+    -- A cron expression, in UTC.
+    schedule    TEXT     NOT NULL,
+    -- The ranking, as given to the `ranks` command: a mode, `total` or `map-length`.
+    ranking     TEXT     NOT NULL,
+    as_image    BOOLEAN  NOT NULL,
+    created_at  DATETIME NOT NULL,
+    last_posted DATETIME
+);
+
+CREATE INDEX osu_leaderboard_posts_guild ON osu_leaderboard_posts (guild_id);
@@ -0,0 +1,9 @@
+-- The Twitch channels of the users, whose streams are announced.
+
+CREATE TABLE osu_twitch_channels (
+    user_id        BIGINT NOT NULL PRIMARY KEY,
+    -- The Twitch login, e.g. `natsukagami`.
+    twitch_login   TEXT   NOT NULL,
+    -- The stream last announced, if the channel is still live, so that it is announced once.
+    live_stream_id TEXT
+);
@@ -0,0 +1,22 @@
+-- The daily challenge scores of the saved users, recorded to rank them in their servers and count their streaks.
+
+CREATE TABLE osu_daily_challenge_scores (
+    room_id     BIGINT   NOT NULL,
+    -- The osu! user id.
+    user_id     BIGINT   NOT NULL,
+    -- When the daily challenge started, at midnight UTC.
+    starts_at   DATETIME NOT NULL,
+    beatmap_id  BIGINT   NOT NULL,
+    total_score BIGINT   NOT NULL,
+    -- Between 0 and 100.
+    accuracy    REAL     NOT NULL,
+    max_combo   BIGINT   NOT NULL,
+    -- The mods, as acronyms, e.g. `HDHR`.
+    mods        TEXT     NOT NULL,
+    -- The grade, e.g. `S`.
+    rank        TEXT     NOT NULL,
+    pp          REAL,
+    PRIMARY KEY (room_id, user_id)
+);
+
+CREATE INDEX osu_daily_challenge_scores_user ON osu_daily_challenge_scores (user_id, starts_at);
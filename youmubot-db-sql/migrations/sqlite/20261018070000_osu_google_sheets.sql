@@ -0,0 +1,8 @@
+-- The Google Sheet each server pushes its exports to, with the service account writing to it.
+
+CREATE TABLE osu_google_sheets (
+    guild_id       BIGINT NOT NULL PRIMARY KEY,
+    spreadsheet_id TEXT   NOT NULL,
+    -- The service account's key, as downloaded from Google Cloud, in JSON.
+    credentials    TEXT   NOT NULL
+);
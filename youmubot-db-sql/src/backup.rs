@@ -0,0 +1,306 @@
+//! Export and import of the bot's own data, as opposed to caches that can be refetched.
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgArguments, query::Query, Column, Postgres, Row, TypeInfo, ValueRef};
+
+use crate::*;
+
+/// The tables included in backups, in an order that satisfies their foreign keys.
+/// Caches are excluded, as they can be refetched.
+/// So are the Google service account keys, as backups are posted to the channel.
+pub const TABLES: &[&str] = &[
+    "osu_users",
+    "osu_channel_beatmaps",
+    "osu_user_best_scores",
+    "osu_user_stats_snapshots",
+    "osu_user_plays",
+    "osu_announced_scores",
+    "osu_leaderboard_posts",
+    "osu_twitch_channels",
+    "osu_daily_challenge_scores",
+    "osu_news_subscriptions",
+    "hook_settings",
+    "guild_config",
+    "user_locales",
+];
+
+/// A single value, as stored by SQLite.
+///
+/// Postgres booleans are stored as integers and timestamps as RFC 3339 text,
+/// so that the backups of both databases are the same.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+/// All the rows of a table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableDump {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// The version of the latest migration applied to the database.
+/// Backups can only be restored into a database with the same version.
+pub async fn schema_version(pool: &Pool) -> Result<i64> {
+    const SQL: &str = "SELECT COALESCE(MAX(version), 0) FROM _sqlx_migrations WHERE success";
+    Ok(match pool {
+        Pool::Sqlite(p) => sqlx::query_scalar(SQL).fetch_one(p).await?,
+        Pool::Postgres(p) => sqlx::query_scalar(SQL).fetch_one(p).await?,
+    })
+}
+
+/// Dump all the backed up tables, in a single transaction.
+pub async fn dump(pool: &Pool) -> Result<Vec<TableDump>> {
+    let mut t = pool.begin().await?;
+    let mut tables = Vec::with_capacity(TABLES.len());
+    for &name in TABLES {
+        let sql = format!("SELECT * FROM {}", name);
+        let (columns, rows) = match &mut *t {
+            Connection::Sqlite(c) => read_rows(
+                sqlx::query(&sql).fetch_all(&mut **c).await?,
+                read_sqlite_value,
+            )?,
+            Connection::Postgres(c) => read_rows(
+                sqlx::query(&sql).fetch_all(&mut **c).await?,
+                read_postgres_value,
+            )?,
+        };
+        tables.push(TableDump {
+            name: name.to_owned(),
+            columns,
+            rows,
+        });
+    }
+    t.commit().await?;
+    Ok(tables)
+}
+
+/// Replace the content of the backed up tables with the given dumps, in a single transaction.
+/// Nothing is changed if any of the rows cannot be inserted.
+pub async fn restore(pool: &Pool, tables: &[TableDump]) -> Result<()> {
+    for table in tables {
+        if !TABLES.contains(&&table.name[..]) {
+            return Err(Error::InvalidBackup(format!(
+                "unknown table {}",
+                table.name
+            )));
+        }
+    }
+    let mut t = pool.begin().await?;
+    for name in TABLES.iter().rev() {
+        let sql = format!("DELETE FROM {}", name);
+        match &mut *t {
+            Connection::Sqlite(c) => {
+                sqlx::query(&sql).execute(&mut **c).await?;
+            }
+            Connection::Postgres(c) => {
+                sqlx::query(&sql).execute(&mut **c).await?;
+            }
+        }
+    }
+    for &name in TABLES {
+        let Some(table) = tables.iter().find(|t| t.name == name) else {
+            continue;
+        };
+        if table.rows.is_empty() {
+            continue;
+        }
+        if let Some(c) = table
+            .columns
+            .iter()
+            .find(|c| !c.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        {
+            return Err(Error::InvalidBackup(format!("invalid column name {}", c)));
+        }
+        if let Some(row) = table.rows.iter().find(|r| r.len() != table.columns.len()) {
+            return Err(Error::InvalidBackup(format!(
+                "a row of {} has {} values, expected {}",
+                name,
+                row.len(),
+                table.columns.len()
+            )));
+        }
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            name,
+            table.columns.join(", "),
+            (1..=table.columns.len())
+                .map(|i| format!("${}", i))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        match &mut *t {
+            Connection::Sqlite(c) => {
+                for row in &table.rows {
+                    let mut q = sqlx::query(&sql);
+                    for value in row {
+                        q = match value {
+                            Value::Null => q.bind(None::<i64>),
+                            Value::Integer(v) => q.bind(*v),
+                            Value::Real(v) => q.bind(*v),
+                            Value::Text(v) => q.bind(v.clone()),
+                            Value::Blob(v) => q.bind(v.clone()),
+                        };
+                    }
+                    q.execute(&mut **c).await?;
+                }
+            }
+            Connection::Postgres(c) => {
+                let types = postgres_columns(name, c).await?;
+                let columns = table
+                    .columns
+                    .iter()
+                    .map(|col| {
+                        types.get(col).map(|ty| (col, ty)).ok_or_else(|| {
+                            Error::InvalidBackup(format!("unknown column {}.{}", name, col))
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                for row in &table.rows {
+                    let mut q = sqlx::query(&sql);
+                    for (value, (col, ty)) in row.iter().zip(&columns) {
+                        q = bind_postgres(q, value, &ty.data_type).ok_or_else(|| {
+                            Error::InvalidBackup(format!(
+                                "a value of {}.{} cannot be stored as {}",
+                                name, col, ty.data_type
+                            ))
+                        })?;
+                    }
+                    q.execute(&mut **c).await?;
+                }
+                // The identity columns don't advance when their values are given.
+                for (col, _) in columns.iter().filter(|(_, ty)| ty.is_identity) {
+                    sqlx::query(&format!(
+                        "SELECT setval(pg_get_serial_sequence('{0}', '{1}'), MAX({1})) FROM {0}",
+                        name, col
+                    ))
+                    .execute(&mut **c)
+                    .await?;
+                }
+            }
+        }
+    }
+    t.commit().await?;
+    Ok(())
+}
+
+fn read_rows<R: Row>(
+    rows: Vec<R>,
+    read_value: impl Fn(&R, usize) -> Result<Value>,
+) -> Result<(Vec<String>, Vec<Vec<Value>>)> {
+    let columns = match rows.first() {
+        Some(row) => row.columns().iter().map(|c| c.name().to_owned()).collect(),
+        None => vec![],
+    };
+    let rows = rows
+        .iter()
+        .map(|row| (0..row.len()).map(|i| read_value(row, i)).collect())
+        .collect::<Result<Vec<_>>>()?;
+    Ok((columns, rows))
+}
+
+fn read_sqlite_value(row: &sqlite::SqliteRow, i: usize) -> Result<Value> {
+    let raw = row.try_get_raw(i)?;
+    if raw.is_null() {
+        return Ok(Value::Null);
+    }
+    Ok(match raw.type_info().name() {
+        "INTEGER" => Value::Integer(row.try_get(i)?),
+        "REAL" => Value::Real(row.try_get(i)?),
+        "TEXT" => Value::Text(row.try_get(i)?),
+        _ => Value::Blob(row.try_get(i)?),
+    })
+}
+
+fn read_postgres_value(row: &postgres::PgRow, i: usize) -> Result<Value> {
+    let raw = row.try_get_raw(i)?;
+    if raw.is_null() {
+        return Ok(Value::Null);
+    }
+    Ok(match raw.type_info().name() {
+        "INT8" => Value::Integer(row.try_get(i)?),
+        "INT4" => Value::Integer(row.try_get::<i32, _>(i)?.into()),
+        "INT2" => Value::Integer(row.try_get::<i16, _>(i)?.into()),
+        "BOOL" => Value::Integer(row.try_get::<bool, _>(i)?.into()),
+        "FLOAT8" => Value::Real(row.try_get(i)?),
+        "FLOAT4" => Value::Real(row.try_get::<f32, _>(i)?.into()),
+        "TIMESTAMPTZ" => Value::Text(
+            row.try_get::<models::DateTime, _>(i)?
+                .to_rfc3339_opts(SecondsFormat::AutoSi, false),
+        ),
+        "BYTEA" => Value::Blob(row.try_get(i)?),
+        _ => Value::Text(row.try_get(i)?),
+    })
+}
+
+struct PostgresColumn {
+    data_type: String,
+    is_identity: bool,
+}
+
+async fn postgres_columns(
+    table: &str,
+    conn: &mut sqlx::PgConnection,
+) -> Result<HashMap<String, PostgresColumn>> {
+    let columns: Vec<(String, String, String)> = sqlx::query_as(
+        r#"SELECT column_name::TEXT, data_type::TEXT, is_identity::TEXT
+        FROM information_schema.columns
+        WHERE table_schema = current_schema() AND table_name = $1"#,
+    )
+    .bind(table)
+    .fetch_all(conn)
+    .await?;
+    Ok(columns
+        .into_iter()
+        .map(|(name, data_type, is_identity)| {
+            (
+                name,
+                PostgresColumn {
+                    data_type,
+                    is_identity: is_identity == "YES",
+                },
+            )
+        })
+        .collect())
+}
+
+/// Bind the value as the type of its column, or `None` if it cannot be stored in it.
+fn bind_postgres<'q>(
+    q: Query<'q, Postgres, PgArguments>,
+    value: &Value,
+    data_type: &str,
+) -> Option<Query<'q, Postgres, PgArguments>> {
+    Some(match (data_type, value) {
+        ("boolean", Value::Null) => q.bind(None::<bool>),
+        ("boolean", Value::Integer(v)) => q.bind(*v != 0),
+        ("timestamp with time zone", Value::Null) => q.bind(None::<models::DateTime>),
+        ("timestamp with time zone", Value::Text(v)) => q.bind(parse_datetime(v)?),
+        ("double precision", Value::Null) => q.bind(None::<f64>),
+        ("double precision", Value::Integer(v)) => q.bind(*v as f64),
+        ("double precision", Value::Real(v)) => q.bind(*v),
+        ("bytea", Value::Null) => q.bind(None::<Vec<u8>>),
+        ("bytea", Value::Blob(v)) => q.bind(v.clone()),
+        ("text", Value::Null) => q.bind(None::<String>),
+        ("text", Value::Text(v)) => q.bind(v.clone()),
+        ("bigint", Value::Null) => q.bind(None::<i64>),
+        ("bigint", Value::Integer(v)) => q.bind(*v),
+        _ => return None,
+    })
+}
+
+/// Parse a timestamp stored by SQLite, either by sqlx or by `CURRENT_TIMESTAMP`.
+fn parse_datetime(s: &str) -> Option<models::DateTime> {
+    chrono::DateTime::parse_from_rfc3339(s)
+        .or_else(|_| chrono::DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f%:z"))
+        .map(|d| d.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f").map(|d| d.and_utc()))
+        .ok()
+}
@@ -1,31 +1,282 @@
-use sqlx::sqlite;
-use std::path::Path;
+use futures_util::future::BoxFuture;
+use sqlx::{
+    pool::{PoolConnection, PoolOptions},
+    postgres, sqlite, Postgres, Sqlite, TransactionManager,
+};
+use std::{
+    ops::{Deref, DerefMut},
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
 
 pub use errors::*;
 
-/// The DB constructs that will be used in the package.
-pub use sqlite::{SqliteConnection as Connection, SqliteError, SqlitePool as Pool};
-pub use sqlx::Sqlite as Database;
-
 /// Models defined in the database.
 pub mod models;
 
+/// Backups of the database.
+pub mod backup;
+
+/// How long a connection waits for another one to release its lock.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+/// How many times a transaction is retried when the database is busy.
+const BUSY_RETRIES: u32 = 8;
+
+/// Configuration of the connection pool.
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// The maximum number of open connections.
+    pub max_connections: u32,
+    /// The number of connections kept open when idle.
+    pub min_connections: u32,
+    /// How long to wait for a connection before failing.
+    pub acquire_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 10,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A pool of connections to the database, either SQLite or Postgres.
+///
+/// The queries are written in the SQL understood by both, with `$N` placeholders.
+#[derive(Debug, Clone)]
+pub enum Pool {
+    Sqlite(sqlx::SqlitePool),
+    Postgres(sqlx::PgPool),
+}
+
+/// A connection taken from a [Pool].
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)] // Short-lived, not worth the extra allocation
+pub enum Connection {
+    Sqlite(PoolConnection<Sqlite>),
+    Postgres(PoolConnection<Postgres>),
+}
+
+/// A transaction on a [Connection], rolled back if dropped before being committed.
+#[derive(Debug)]
+pub struct Transaction {
+    conn: Connection,
+    open: bool,
+}
+
+/// Where a query is run: on a connection taken from a pool, or on the given connection.
+pub enum Conn<'c> {
+    Pool(&'c Pool),
+    Connection(&'c mut Connection),
+}
+
+impl<'c> From<&'c Pool> for Conn<'c> {
+    fn from(pool: &'c Pool) -> Self {
+        Conn::Pool(pool)
+    }
+}
+
+impl<'c> From<&'c mut Connection> for Conn<'c> {
+    fn from(conn: &'c mut Connection) -> Self {
+        Conn::Connection(conn)
+    }
+}
+
+impl Pool {
+    /// Take a connection from the pool.
+    pub async fn acquire(&self) -> Result<Connection> {
+        Ok(match self {
+            Pool::Sqlite(p) => Connection::Sqlite(p.acquire().await?),
+            Pool::Postgres(p) => Connection::Postgres(p.acquire().await?),
+        })
+    }
+
+    /// Begin a transaction on a connection taken from the pool.
+    pub async fn begin(&self) -> Result<Transaction> {
+        let mut conn = self.acquire().await?;
+        match &mut conn {
+            Connection::Sqlite(c) => {
+                <Sqlite as sqlx::Database>::TransactionManager::begin(c).await?
+            }
+            Connection::Postgres(c) => {
+                <Postgres as sqlx::Database>::TransactionManager::begin(c).await?
+            }
+        }
+        Ok(Transaction { conn, open: true })
+    }
+}
+
+impl Transaction {
+    /// Commit the transaction.
+    pub async fn commit(mut self) -> Result<()> {
+        match &mut self.conn {
+            Connection::Sqlite(c) => {
+                <Sqlite as sqlx::Database>::TransactionManager::commit(c).await?
+            }
+            Connection::Postgres(c) => {
+                <Postgres as sqlx::Database>::TransactionManager::commit(c).await?
+            }
+        }
+        self.open = false;
+        Ok(())
+    }
+}
+
+impl Deref for Transaction {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+impl DerefMut for Transaction {
+    fn deref_mut(&mut self) -> &mut Connection {
+        &mut self.conn
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        if !self.open {
+            return;
+        }
+        match &mut self.conn {
+            Connection::Sqlite(c) => {
+                <Sqlite as sqlx::Database>::TransactionManager::start_rollback(c)
+            }
+            Connection::Postgres(c) => {
+                <Postgres as sqlx::Database>::TransactionManager::start_rollback(c)
+            }
+        }
+    }
+}
+
 /// Create a new pool of sqlite connections to the given database path,
 /// run migrations on it and return the result.
-pub async fn connect(path: impl AsRef<Path>) -> Result<Pool> {
-    let pool = Pool::connect_with(
-        sqlite::SqliteConnectOptions::new()
-            .filename(path)
-            .foreign_keys(true)
-            .create_if_missing(true)
-            .journal_mode(sqlite::SqliteJournalMode::Wal),
-    )
-    .await?;
+pub async fn connect(path: impl AsRef<Path>, config: &PoolConfig) -> Result<Pool> {
+    connect_sqlite(sqlite::SqliteConnectOptions::new().filename(path), config).await
+}
+
+/// Create a new pool of connections to the database at the given URL,
+/// run migrations on it and return the result.
+///
+/// Both `sqlite:` and `postgres:` URLs are supported.
+pub async fn connect_url(url: &str, config: &PoolConfig) -> Result<Pool> {
+    let scheme = url.split_once(':').map(|(s, _)| s).unwrap_or(url);
+    match scheme {
+        "sqlite" => connect_sqlite(sqlite::SqliteConnectOptions::from_str(url)?, config).await,
+        "postgres" | "postgresql" => {
+            connect_postgres(postgres::PgConnectOptions::from_str(url)?, config).await
+        }
+        _ => Err(Error::UnsupportedDatabase(scheme.to_owned())),
+    }
+}
+
+fn pool_options<DB: sqlx::Database>(config: &PoolConfig) -> PoolOptions<DB> {
+    PoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.acquire_timeout)
+}
+
+async fn connect_sqlite(
+    options: sqlite::SqliteConnectOptions,
+    config: &PoolConfig,
+) -> Result<Pool> {
+    let pool = pool_options(config)
+        .connect_with(
+            options
+                .foreign_keys(true)
+                .create_if_missing(true)
+                .journal_mode(sqlite::SqliteJournalMode::Wal)
+                .busy_timeout(BUSY_TIMEOUT),
+        )
+        .await?;
+
+    // Run migration before we return.
+    migration::SQLITE.run(&pool).await?;
+
+    Ok(Pool::Sqlite(pool))
+}
+
+async fn connect_postgres(
+    options: postgres::PgConnectOptions,
+    config: &PoolConfig,
+) -> Result<Pool> {
+    let pool = pool_options(config).connect_with(options).await?;
 
     // Run migration before we return.
-    migration::MIGRATOR.run(&pool).await?;
+    migration::POSTGRES.run(&pool).await?;
 
-    Ok(pool)
+    Ok(Pool::Postgres(pool))
+}
+
+/// Run `f` in a transaction and commit it.
+///
+/// SQLite cannot wait on the lock when a transaction starts writing after reading,
+/// and Postgres aborts one of two conflicting transactions,
+/// so the whole transaction is retried with a backoff when the database is busy.
+/// `f` may therefore be called more than once.
+pub async fn transaction<T, F>(pool: &Pool, mut f: F) -> Result<T>
+where
+    F: for<'c> FnMut(&'c mut Connection) -> BoxFuture<'c, Result<T>>,
+{
+    let mut delay = Duration::from_millis(50);
+    let mut attempt = 0;
+    loop {
+        let result: Result<T> = async {
+            let mut t = pool.begin().await?;
+            let v = f(&mut t).await?;
+            t.commit().await?;
+            Ok(v)
+        }
+        .await;
+        match result {
+            Err(e) if e.is_busy() && attempt < BUSY_RETRIES => {
+                attempt += 1;
+                // Add some jitter, so that the retried transactions don't collide again.
+                let jitter = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.subsec_nanos() as u64 % delay.as_millis() as u64)
+                    .unwrap_or(0);
+                tokio::time::sleep(delay + Duration::from_millis(jitter)).await;
+                delay *= 2;
+            }
+            r => return r,
+        }
+    }
+}
+
+/// Check that the database can be queried.
+pub async fn ping(pool: &Pool) -> Result<()> {
+    match pool {
+        Pool::Sqlite(p) => sqlx::query("SELECT 1").execute(p).await?.rows_affected(),
+        Pool::Postgres(p) => sqlx::query("SELECT 1").execute(p).await?.rows_affected(),
+    };
+    Ok(())
+}
+
+/// Get the size of the database on disk, in bytes.
+pub async fn database_size(pool: &Pool) -> Result<i64> {
+    Ok(match pool {
+        Pool::Sqlite(p) => {
+            sqlx::query_scalar(
+                "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+            )
+            .fetch_one(p)
+            .await?
+        }
+        Pool::Postgres(p) => {
+            sqlx::query_scalar("SELECT pg_database_size(current_database())")
+                .fetch_one(p)
+                .await?
+        }
+    })
 }
 
 pub mod errors {
@@ -38,11 +289,33 @@ pub mod errors {
         SQLx(#[from] sqlx::Error),
         #[error("sqlx migration error: {:?}", .0)]
         Migration(#[from] sqlx::migrate::MigrateError),
+        #[error("unsupported database `{}`, only sqlite and postgres are supported", .0)]
+        UnsupportedDatabase(String),
+        #[error("invalid backup: {}", .0)]
+        InvalidBackup(String),
+    }
+
+    impl Error {
+        /// Whether the database was busy or locked by another connection.
+        pub fn is_busy(&self) -> bool {
+            match self {
+                // SQLITE_BUSY, SQLITE_LOCKED and their extended codes,
+                // and Postgres' serialization failures and deadlocks.
+                Error::SQLx(sqlx::Error::Database(e)) => matches!(
+                    e.code().as_deref(),
+                    Some("5" | "6" | "261" | "262" | "517" | "40001" | "40P01")
+                ),
+                _ => false,
+            }
+        }
     }
 }
 
 mod migration {
     use sqlx::migrate::Migrator;
 
-    pub(crate) static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+    pub(crate) static SQLITE: Migrator = sqlx::migrate!("./migrations/sqlite");
+    /// The same migrations as SQLite's, with the same versions,
+    /// so that backups can be restored from one database to the other.
+    pub(crate) static POSTGRES: Migrator = sqlx::migrate!("./migrations/postgres");
 }
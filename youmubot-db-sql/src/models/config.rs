@@ -0,0 +1,98 @@
+use crate::models::*;
+
+/// A configuration value of a guild, stored as text.
+#[derive(FromRow)]
+pub struct GuildConfigValue {
+    pub guild_id: i64,
+    pub key: String,
+    pub value: String,
+}
+
+impl GuildConfigValue {
+    /// The value of the key in the given guild, if it was set.
+    pub async fn get(
+        guild_id: i64,
+        key: &str,
+        conn: impl Into<Conn<'_>>,
+    ) -> Result<Option<String>> {
+        Ok(on_conn!(conn, |c| {
+            query_scalar("SELECT value FROM guild_config WHERE guild_id = $1 AND key = $2")
+                .bind(guild_id)
+                .bind(key)
+                .fetch_optional(c)
+                .await?
+        }))
+    }
+
+    /// Get all values set in the given guild.
+    pub async fn by_guild(guild_id: i64, conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT guild_id, key, value
+                FROM guild_config
+                WHERE guild_id = $1
+                ORDER BY key"#,
+            )
+            .bind(guild_id)
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    /// Get the values of the key in all guilds.
+    pub async fn by_key(key: &str, conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT guild_id, key, value
+                FROM guild_config
+                WHERE key = $1
+                ORDER BY guild_id"#,
+            )
+            .bind(key)
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    /// Store the value.
+    pub async fn store(&self, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query(
+                r#"INSERT INTO
+                    guild_config (guild_id, key, value)
+                VALUES
+                    ($1, $2, $3)
+                ON CONFLICT (guild_id, key) DO UPDATE
+                    SET value = excluded.value"#,
+            )
+            .bind(self.guild_id)
+            .bind(&self.key)
+            .bind(&self.value)
+            .execute(c)
+            .await?;
+        });
+        Ok(())
+    }
+
+    /// Remove the value, going back to the key's default.
+    pub async fn delete(guild_id: i64, key: &str, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query("DELETE FROM guild_config WHERE guild_id = $1 AND key = $2")
+                .bind(guild_id)
+                .bind(key)
+                .execute(c)
+                .await?;
+        });
+        Ok(())
+    }
+
+    /// Remove all values set in the given guild. Returns the keys that were set.
+    pub async fn delete_by_guild(guild_id: i64, conn: impl Into<Conn<'_>>) -> Result<Vec<String>> {
+        Ok(on_conn!(conn, |c| {
+            query_scalar("DELETE FROM guild_config WHERE guild_id = $1 RETURNING key")
+                .bind(guild_id)
+                .fetch_all(c)
+                .await?
+        }))
+    }
+}
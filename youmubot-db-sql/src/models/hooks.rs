@@ -0,0 +1,37 @@
+use crate::models::*;
+
+/// A switch turning a message hook on or off in a channel, from before the switches were
+/// stored in the guild configuration.
+/// They are moved there once the guild of their channel is known.
+#[derive(FromRow)]
+pub struct HookSetting {
+    pub channel_id: i64,
+    pub hook: String,
+    pub enabled: bool,
+}
+
+impl HookSetting {
+    /// Get all the switches left.
+    pub async fn all(conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT channel_id, hook, enabled
+                FROM hook_settings
+                ORDER BY channel_id, hook"#,
+            )
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    /// Remove all switches set in the given channel.
+    pub async fn delete_by_channel(channel_id: i64, conn: impl Into<Conn<'_>>) -> Result<u64> {
+        Ok(on_conn!(conn, |c| {
+            query("DELETE FROM hook_settings WHERE channel_id = $1")
+                .bind(channel_id)
+                .execute(c)
+                .await?
+                .rows_affected()
+        }))
+    }
+}
@@ -0,0 +1,38 @@
+use crate::models::*;
+
+/// The last run of a scheduled job.
+pub struct ScheduledJob {
+    pub name: String,
+    pub last_run: DateTime,
+}
+
+impl ScheduledJob {
+    /// When the job last ran, if it ever did.
+    pub async fn last_run(name: &str, conn: impl Into<Conn<'_>>) -> Result<Option<DateTime>> {
+        Ok(on_conn!(conn, |c| {
+            query_scalar("SELECT last_run FROM scheduled_jobs WHERE name = $1")
+                .bind(name)
+                .fetch_optional(c)
+                .await?
+        }))
+    }
+
+    /// Store the job's last run.
+    pub async fn store(&self, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query(
+                r#"INSERT INTO
+                    scheduled_jobs (name, last_run)
+                VALUES
+                    ($1, $2)
+                ON CONFLICT (name) DO UPDATE
+                    SET last_run = excluded.last_run"#,
+            )
+            .bind(&self.name)
+            .bind(self.last_run)
+            .execute(c)
+            .await?;
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use crate::models::*;
+
+/// The language a user chose for the bot's messages.
+pub struct UserLocale {
+    pub user_id: i64,
+    pub locale: String,
+}
+
+impl UserLocale {
+    /// The language of the given user, if they chose one.
+    pub async fn by_user_id(user_id: i64, conn: impl Into<Conn<'_>>) -> Result<Option<String>> {
+        Ok(on_conn!(conn, |c| {
+            query_scalar("SELECT locale FROM user_locales WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_optional(c)
+                .await?
+        }))
+    }
+
+    /// Store the user's language.
+    pub async fn store(&self, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query(
+                r#"INSERT INTO
+                    user_locales (user_id, locale)
+                VALUES
+                    ($1, $2)
+                ON CONFLICT (user_id) DO UPDATE
+                    SET locale = excluded.locale"#,
+            )
+            .bind(self.user_id)
+            .bind(&self.locale)
+            .execute(c)
+            .await?;
+        });
+        Ok(())
+    }
+
+    /// Forget the user's language, going back to their guild's.
+    pub async fn delete(user_id: i64, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query("DELETE FROM user_locales WHERE user_id = $1")
+                .bind(user_id)
+                .execute(c)
+                .await?;
+        });
+        Ok(())
+    }
+}
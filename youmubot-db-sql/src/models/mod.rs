@@ -1,22 +1,40 @@
 use crate::*;
-use futures_util::stream::{Stream, StreamExt};
-use sqlx::{query, query_as, Executor};
+use sqlx::{query, query_as, query_scalar, FromRow};
 
 /// The DateTime used in the package.
 pub type DateTime = chrono::DateTime<chrono::Utc>;
 
+/// Run `$body` with `$c` bound to the underlying connection of the database,
+/// taken from the pool if `$conn` is one.
+///
+/// The body is compiled once per database, so the queries in it must be understood by both.
+macro_rules! on_conn {
+    ($conn:expr, |$c:ident| $body:expr) => {{
+        let conn: Conn = $conn.into();
+        let mut acquired;
+        let conn: &mut Connection = match conn {
+            Conn::Pool(pool) => {
+                acquired = pool.acquire().await?;
+                &mut acquired
+            }
+            Conn::Connection(conn) => conn,
+        };
+        match conn {
+            Connection::Sqlite($c) => {
+                let $c = &mut **$c;
+                $body
+            }
+            Connection::Postgres($c) => {
+                let $c = &mut **$c;
+                $body
+            }
+        }
+    }};
+}
+
+pub mod config;
+pub mod hooks;
+pub mod jobs;
+pub mod locale;
 pub mod osu;
 pub mod osu_user;
-
-/// Map a `fetch_many` result to a normal result.
-pub(crate) async fn map_many_result<T, E, W>(
-    item: Result<either::Either<W, T>, E>,
-) -> Option<Result<T>>
-where
-    E: Into<Error>,
-{
-    match item {
-        Ok(v) => v.right().map(Ok),
-        Err(e) => Some(Err(e.into())),
-    }
-}
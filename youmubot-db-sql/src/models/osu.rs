@@ -1,57 +1,141 @@
 use crate::models::*;
 
+#[derive(FromRow)]
 pub struct LastBeatmap {
+    pub id: i64,
     pub channel_id: i64,
+    pub guild_id: Option<i64>,
     pub beatmap: Vec<u8>,
+    #[sqlx(try_from = "i64")]
     pub mode: u8,
+    pub saved_at: DateTime,
 }
 
 impl LastBeatmap {
-    /// Get a [`LastBeatmap`] by the channel id.
+    /// Get the beatmaps requested in the channel, latest first.
     pub async fn by_channel_id(
         id: i64,
-        conn: impl Executor<'_, Database = Database>,
+        limit: i64,
+        conn: impl Into<Conn<'_>>,
+    ) -> Result<Vec<LastBeatmap>> {
+        let m = on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT id, channel_id, guild_id, beatmap, mode, saved_at
+                FROM osu_channel_beatmaps
+                WHERE channel_id = $1
+                ORDER BY id DESC
+                LIMIT $2"#,
+            )
+            .bind(id)
+            .bind(limit)
+            .fetch_all(c)
+            .await?
+        });
+        Ok(m)
+    }
+
+    /// Get the `n`-th latest beatmap requested in the channel, starting from 0.
+    pub async fn nth_by_channel_id(
+        id: i64,
+        n: i64,
+        conn: impl Into<Conn<'_>>,
     ) -> Result<Option<LastBeatmap>> {
-        let m = query_as!(
-            LastBeatmap,
-            r#"SELECT
-                    channel_id as "channel_id: i64",
-                    beatmap,
-                    mode as "mode: u8"
-                FROM osu_last_beatmaps
-                WHERE channel_id = ?"#,
-            id
-        )
-        .fetch_optional(conn)
-        .await?;
+        let m = on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT id, channel_id, guild_id, beatmap, mode, saved_at
+                FROM osu_channel_beatmaps
+                WHERE channel_id = $1
+                ORDER BY id DESC
+                LIMIT 1 OFFSET $2"#,
+            )
+            .bind(id)
+            .bind(n)
+            .fetch_optional(c)
+            .await?
+        });
         Ok(m)
     }
 }
 
 impl LastBeatmap {
-    /// Store the value.
-    pub async fn store(&self, conn: impl Executor<'_, Database = Database>) -> Result<()> {
-        query!(
-            r#"INSERT INTO
-                  osu_last_beatmaps (channel_id, beatmap, mode)
-               VALUES
-                  (?, ?, ?)
-               ON CONFLICT (channel_id) DO UPDATE
-                  SET
-                    beatmap = excluded.beatmap,
-                    mode = excluded.mode"#,
-            self.channel_id,
-            self.beatmap,
-            self.mode,
-        )
-        .execute(conn)
-        .await?;
+    /// Store the value as the latest beatmap of the channel.
+    /// Earlier entries of the same beatmap in the channel are removed.
+    /// The `id` of the value is ignored.
+    pub async fn store(&self, conn: &mut Connection) -> Result<()> {
+        on_conn!(conn, |c| {
+            query(
+                r#"DELETE FROM osu_channel_beatmaps
+                WHERE channel_id = $1 AND beatmap = $2 AND mode = $3"#,
+            )
+            .bind(self.channel_id)
+            .bind(&self.beatmap)
+            .bind(self.mode as i64)
+            .execute(&mut *c)
+            .await?;
+            query(
+                r#"INSERT INTO
+                    osu_channel_beatmaps (channel_id, guild_id, beatmap, mode, saved_at)
+                VALUES
+                    ($1, $2, $3, $4, $5)"#,
+            )
+            .bind(self.channel_id)
+            .bind(self.guild_id)
+            .bind(&self.beatmap)
+            .bind(self.mode as i64)
+            .bind(self.saved_at)
+            .execute(c)
+            .await?;
+        });
         Ok(())
     }
+
+    /// Delete all but the `keep` latest beatmaps of the channel.
+    pub async fn trim(channel_id: i64, keep: i64, conn: impl Into<Conn<'_>>) -> Result<u64> {
+        Ok(on_conn!(conn, |c| {
+            query(
+                r#"DELETE FROM osu_channel_beatmaps
+                WHERE channel_id = $1 AND id NOT IN (
+                    SELECT id FROM osu_channel_beatmaps
+                    WHERE channel_id = $1
+                    ORDER BY id DESC
+                    LIMIT $2
+                )"#,
+            )
+            .bind(channel_id)
+            .bind(keep)
+            .execute(c)
+            .await?
+            .rows_affected()
+        }))
+    }
+
+    /// Delete the beatmaps of the given channel.
+    pub async fn delete_by_channel(channel_id: i64, conn: impl Into<Conn<'_>>) -> Result<u64> {
+        Ok(on_conn!(conn, |c| {
+            query("DELETE FROM osu_channel_beatmaps WHERE channel_id = $1")
+                .bind(channel_id)
+                .execute(c)
+                .await?
+                .rows_affected()
+        }))
+    }
+
+    /// Delete the beatmaps of all channels in the given guild.
+    pub async fn delete_by_guild(guild_id: i64, conn: impl Into<Conn<'_>>) -> Result<u64> {
+        Ok(on_conn!(conn, |c| {
+            query("DELETE FROM osu_channel_beatmaps WHERE guild_id = $1")
+                .bind(guild_id)
+                .execute(c)
+                .await?
+                .rows_affected()
+        }))
+    }
 }
 
+#[derive(FromRow)]
 pub struct UserBestScore {
     pub beatmap_id: i64,
+    #[sqlx(try_from = "i64")]
     pub mode: u8,
     pub user_id: i64,
     pub mods: i64,
@@ -67,101 +151,150 @@ impl UserBestScore {
         beatmap: i64,
         mode: u8,
         user: i64,
-        conn: impl Executor<'_, Database = Database>,
+        conn: impl Into<Conn<'_>>,
     ) -> Result<Vec<Self>> {
-        query_as!(
-            UserBestScore,
-            r#"SELECT
-                beatmap_id as "beatmap_id: i64",
-                mode as "mode: u8",
-                user_id as "user_id: i64",
-                mods as "mods: i64",
-                cached_at as "cached_at: DateTime",
-                score as "score: Vec<u8>"
-            FROM osu_user_best_scores
-            WHERE
-                beatmap_id = ?
-                AND mode = ?
-                AND user_id = ?"#,
-            beatmap,
-            mode,
-            user
-        )
-        .fetch_all(conn)
-        .await
-        .map_err(Error::from)
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT beatmap_id, mode, user_id, mods, cached_at, score
+                FROM osu_user_best_scores
+                WHERE
+                    beatmap_id = $1
+                    AND mode = $2
+                    AND user_id = $3"#,
+            )
+            .bind(beatmap)
+            .bind(mode as i64)
+            .bind(user)
+            .fetch_all(c)
+            .await?
+        }))
     }
     /// Get a list of scores by the given map.
-    pub async fn by_map(
-        beatmap: i64,
-        mode: u8,
-        conn: impl Executor<'_, Database = Database>,
-    ) -> Result<Vec<Self>> {
-        query_as!(
-            UserBestScore,
-            r#"SELECT
-                beatmap_id as "beatmap_id: i64",
-                mode as "mode: u8",
-                user_id as "user_id: i64",
-                mods as "mods: i64",
-                cached_at as "cached_at: DateTime",
-                score as "score: Vec<u8>"
-            FROM osu_user_best_scores
-            WHERE
-                beatmap_id = ?
-                AND mode = ?"#,
-            beatmap,
-            mode
-        )
-        .fetch_all(conn)
-        .await
-        .map_err(Error::from)
+    pub async fn by_map(beatmap: i64, mode: u8, conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT beatmap_id, mode, user_id, mods, cached_at, score
+                FROM osu_user_best_scores
+                WHERE
+                    beatmap_id = $1
+                    AND mode = $2"#,
+            )
+            .bind(beatmap)
+            .bind(mode as i64)
+            .fetch_all(c)
+            .await?
+        }))
     }
 }
 
 impl UserBestScore {
-    pub async fn store(&mut self, conn: impl Executor<'_, Database = Database>) -> Result<()> {
+    pub async fn store(&mut self, conn: impl Into<Conn<'_>>) -> Result<()> {
         self.cached_at = chrono::Utc::now();
-        query!(
-            r#"
+        on_conn!(conn, |c| {
+            query(
+                r#"
                 INSERT INTO
                     osu_user_best_scores (beatmap_id, mode, user_id, mods, cached_at, score)
                 VALUES
-                    (?, ?, ?, ?, ?, ?)
+                    ($1, $2, $3, $4, $5, $6)
                 ON CONFLICT (beatmap_id, mode, user_id, mods)
                 DO UPDATE
                     SET
                         cached_at = excluded.cached_at,
                         score = excluded.score
-            "#,
-            self.beatmap_id,
-            self.mode,
-            self.user_id,
-            self.mods,
-            self.cached_at,
-            self.score
-        )
-        .execute(conn)
-        .await?;
+                "#,
+            )
+            .bind(self.beatmap_id)
+            .bind(self.mode as i64)
+            .bind(self.user_id)
+            .bind(self.mods)
+            .bind(self.cached_at)
+            .bind(&self.score)
+            .execute(c)
+            .await?;
+        });
         Ok(())
     }
 
-    pub async fn clear_user(
-        user_id: i64,
-        conn: impl Executor<'_, Database = Database>,
+    pub async fn clear_user(user_id: i64, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query("DELETE FROM osu_user_best_scores WHERE user_id = $1")
+                .bind(user_id)
+                .execute(c)
+                .await?;
+        });
+        Ok(())
+    }
+
+    /// Delete the scores of the user on the given map, before storing the new ones.
+    pub async fn clear_map_user(
+        beatmap: i64,
+        mode: u8,
+        user: i64,
+        conn: impl Into<Conn<'_>>,
     ) -> Result<()> {
-        query!(
-            "DELETE FROM osu_user_best_scores WHERE user_id = ?",
-            user_id
-        )
-        .execute(conn)
-        .await?;
+        on_conn!(conn, |c| {
+            query(
+                "DELETE FROM osu_user_best_scores WHERE beatmap_id = $1 AND mode = $2 AND user_id = $3",
+            )
+            .bind(beatmap)
+            .bind(mode as i64)
+            .bind(user)
+            .execute(c)
+            .await?;
+        });
         Ok(())
     }
+
+    /// Delete the scores cached before the given time. Returns the number of deleted scores.
+    pub async fn delete_older_than(before: DateTime, conn: impl Into<Conn<'_>>) -> Result<u64> {
+        Ok(on_conn!(conn, |c| {
+            query("DELETE FROM osu_user_best_scores WHERE cached_at < $1")
+                .bind(before)
+                .execute(c)
+                .await?
+                .rows_affected()
+        }))
+    }
+
+    /// Only keep the `per_user` most recently cached scores of each user.
+    /// Returns the number of deleted scores.
+    pub async fn delete_excess(per_user: i64, conn: impl Into<Conn<'_>>) -> Result<u64> {
+        Ok(on_conn!(conn, |c| {
+            query(
+                r#"DELETE FROM osu_user_best_scores
+                WHERE (beatmap_id, mode, user_id, mods) IN (
+                    SELECT beatmap_id, mode, user_id, mods FROM (
+                        SELECT
+                            beatmap_id, mode, user_id, mods,
+                            ROW_NUMBER() OVER (PARTITION BY user_id ORDER BY cached_at DESC) AS rank
+                        FROM osu_user_best_scores
+                    ) AS ranked
+                    WHERE rank > $1
+                )"#,
+            )
+            .bind(per_user)
+            .execute(c)
+            .await?
+            .rows_affected()
+        }))
+    }
+}
+
+/// The size of a cache table.
+#[derive(FromRow)]
+pub struct CacheTableStats {
+    pub entries: i64,
+    /// The total size of the cached data, in bytes.
+    pub bytes: i64,
+    /// When the oldest entry was cached.
+    pub oldest: Option<DateTime>,
 }
 
+#[derive(FromRow)]
 pub struct CachedBeatmap {
     pub beatmap_id: i64,
+    #[sqlx(try_from = "i64")]
     pub mode: u8,
     pub cached_at: DateTime,
     pub beatmap: Vec<u8>,
@@ -169,165 +302,1151 @@ pub struct CachedBeatmap {
 
 impl CachedBeatmap {
     /// Get a cached beatmap by its id.
-    pub async fn by_id(
-        id: i64,
-        mode: u8,
-        conn: impl Executor<'_, Database = Database>,
-    ) -> Result<Option<Self>> {
-        query_as!(
-            Self,
-            r#"SELECT
-                beatmap_id as "beatmap_id: i64",
-                mode as "mode: u8",
-                cached_at as "cached_at: DateTime",
-                beatmap as "beatmap: Vec<u8>"
-            FROM osu_cached_beatmaps
-            WHERE
-                beatmap_id = ?
-                AND mode = ?
-                "#,
-            id,
-            mode
-        )
-        .fetch_optional(conn)
-        .await
-        .map_err(Error::from)
+    pub async fn by_id(id: i64, mode: u8, conn: impl Into<Conn<'_>>) -> Result<Option<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT beatmap_id, mode, cached_at, beatmap
+                FROM osu_cached_beatmaps
+                WHERE
+                    beatmap_id = $1
+                    AND mode = $2"#,
+            )
+            .bind(id)
+            .bind(mode as i64)
+            .fetch_optional(c)
+            .await?
+        }))
     }
 
-    pub async fn by_beatmapset(
-        beatmapset: i64,
-        conn: impl Executor<'_, Database = Database>,
-    ) -> Result<Vec<Self>> {
-        query_as!(
-            Self,
-            r#"SELECT
-                beatmap.beatmap_id as "beatmap_id: i64",
-                beatmap.mode as "mode: u8",
-                beatmap.cached_at as "cached_at: DateTime",
-                beatmap.beatmap as "beatmap: Vec<u8>"
-            FROM osu_cached_beatmapsets
-            INNER JOIN osu_cached_beatmaps AS beatmap
-                ON osu_cached_beatmapsets.beatmap_id = beatmap.beatmap_id
-                AND osu_cached_beatmapsets.mode = beatmap.mode
-            WHERE
-                beatmapset_id = ?
-                "#,
-            beatmapset
-        )
-        .fetch_all(conn)
-        .await
-        .map_err(Error::from)
+    /// Get the cached beatmap with the given id, in all modes it was cached in.
+    pub async fn by_beatmap_id(id: i64, conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT beatmap_id, mode, cached_at, beatmap
+                FROM osu_cached_beatmaps
+                WHERE
+                    beatmap_id = $1"#,
+            )
+            .bind(id)
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    pub async fn by_beatmapset(beatmapset: i64, conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT
+                    beatmap.beatmap_id,
+                    beatmap.mode,
+                    beatmap.cached_at,
+                    beatmap.beatmap
+                FROM osu_cached_beatmapsets
+                INNER JOIN osu_cached_beatmaps AS beatmap
+                    ON osu_cached_beatmapsets.beatmap_id = beatmap.beatmap_id
+                    AND osu_cached_beatmapsets.mode = beatmap.mode
+                WHERE
+                    beatmapset_id = $1"#,
+            )
+            .bind(beatmapset)
+            .fetch_all(c)
+            .await?
+        }))
     }
 
     /// Delete all of the caches.
-    pub async fn clear_all(conn: impl Executor<'_, Database = Database>) -> Result<()> {
-        conn.execute("DELETE FROM osu_cached_beatmapsets; DELETE FROM osu_cached_beatmaps;")
-            .await?;
+    pub async fn clear_all(conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query("DELETE FROM osu_cached_beatmapsets")
+                .execute(&mut *c)
+                .await?;
+            query("DELETE FROM osu_cached_beatmaps").execute(c).await?;
+        });
         Ok(())
     }
+
+    /// Get the size of the cache.
+    pub async fn stats(conn: impl Into<Conn<'_>>) -> Result<CacheTableStats> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT
+                    COUNT(*) AS entries,
+                    CAST(COALESCE(SUM(LENGTH(beatmap)), 0) AS BIGINT) AS bytes,
+                    MIN(cached_at) AS oldest
+                FROM osu_cached_beatmaps"#,
+            )
+            .fetch_one(c)
+            .await?
+        }))
+    }
+
+    /// Delete the beatmaps cached before the given time. Returns the number of deleted beatmaps.
+    pub async fn delete_older_than(before: DateTime, conn: &mut Connection) -> Result<u64> {
+        Ok(on_conn!(conn, |c| {
+            query(
+                r#"DELETE FROM osu_cached_beatmapsets
+                WHERE EXISTS (
+                    SELECT 1 FROM osu_cached_beatmaps AS beatmap
+                    WHERE beatmap.beatmap_id = osu_cached_beatmapsets.beatmap_id
+                        AND beatmap.mode = osu_cached_beatmapsets.mode
+                        AND beatmap.cached_at < $1
+                )"#,
+            )
+            .bind(before)
+            .execute(&mut *c)
+            .await?;
+            query("DELETE FROM osu_cached_beatmaps WHERE cached_at < $1")
+                .bind(before)
+                .execute(c)
+                .await?
+                .rows_affected()
+        }))
+    }
+
+    /// Delete the beatmap with the given id in all modes, e.g. when it was updated.
+    /// Returns the number of deleted beatmaps.
+    pub async fn delete(id: i64, conn: &mut Connection) -> Result<u64> {
+        Ok(on_conn!(conn, |c| {
+            query("DELETE FROM osu_cached_beatmapsets WHERE beatmap_id = $1")
+                .bind(id)
+                .execute(&mut *c)
+                .await?;
+            query("DELETE FROM osu_cached_beatmaps WHERE beatmap_id = $1")
+                .bind(id)
+                .execute(c)
+                .await?
+                .rows_affected()
+        }))
+    }
 }
 
 impl CachedBeatmap {
-    pub async fn store(&mut self, conn: impl Executor<'_, Database = Database>) -> Result<()> {
+    pub async fn store(&mut self, conn: impl Into<Conn<'_>>) -> Result<()> {
         self.cached_at = chrono::Utc::now();
-        query!(
-            r#"
+        on_conn!(conn, |c| {
+            query(
+                r#"
                 INSERT INTO
                     osu_cached_beatmaps (beatmap_id, mode, cached_at, beatmap)
                 VALUES
-                    (?, ?, ?, ?)
+                    ($1, $2, $3, $4)
                 ON CONFLICT (beatmap_id, mode)
                 DO UPDATE
                     SET
                         cached_at = excluded.cached_at,
                         beatmap = excluded.beatmap
-            "#,
-            self.beatmap_id,
-            self.mode,
-            self.cached_at,
-            self.beatmap
-        )
-        .execute(conn)
-        .await?;
+                "#,
+            )
+            .bind(self.beatmap_id)
+            .bind(self.mode as i64)
+            .bind(self.cached_at)
+            .bind(&self.beatmap)
+            .execute(c)
+            .await?;
+        });
         Ok(())
     }
 
     pub async fn link_beatmapset(
         &self,
         beatmapset_id: i64,
-        conn: impl Executor<'_, Database = Database>,
+        conn: impl Into<Conn<'_>>,
     ) -> Result<()> {
-        query!(
-            r#"INSERT INTO osu_cached_beatmapsets(beatmapset_id, beatmap_id, mode)
-                VALUES (?, ?, ?)
+        on_conn!(conn, |c| {
+            query(
+                r#"INSERT INTO osu_cached_beatmapsets(beatmapset_id, beatmap_id, mode)
+                VALUES ($1, $2, $3)
                 ON CONFLICT DO NOTHING"#,
-            beatmapset_id,
-            self.beatmap_id,
-            self.mode,
-        )
-        .execute(conn)
-        .await?;
+            )
+            .bind(beatmapset_id)
+            .bind(self.beatmap_id)
+            .bind(self.mode as i64)
+            .execute(c)
+            .await?;
+        });
         Ok(())
     }
 }
 
-pub struct CachedBeatmapContent {
+/// The index of a .osu file stored on disk.
+#[derive(FromRow)]
+pub struct CachedBeatmapFile {
     pub beatmap_id: i64,
+    pub file_md5: String,
+    /// The size of the file, in bytes.
+    pub size: i64,
     pub cached_at: DateTime,
-    pub content: Vec<u8>,
 }
 
-impl CachedBeatmapContent {
-    /// Get a cached beatmap by its id.
-    pub async fn by_id(
-        id: i64,
-        conn: impl Executor<'_, Database = Database>,
-    ) -> Result<Option<Self>> {
-        query_as!(
-            Self,
-            r#"SELECT
-                beatmap_id as "beatmap_id: i64",
-                cached_at as "cached_at: DateTime",
-                content as "content: Vec<u8>"
-            FROM osu_cached_beatmap_contents
-            WHERE
-                beatmap_id = ? "#,
-            id,
-        )
-        .fetch_optional(conn)
-        .await
-        .map_err(Error::from)
+impl CachedBeatmapFile {
+    /// Get a cached beatmap file by its id.
+    pub async fn by_id(id: i64, conn: impl Into<Conn<'_>>) -> Result<Option<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT beatmap_id, file_md5, size, cached_at
+                FROM osu_cached_beatmap_files
+                WHERE
+                    beatmap_id = $1"#,
+            )
+            .bind(id)
+            .fetch_optional(c)
+            .await?
+        }))
     }
 
-    /// Delete all of the caches.
-    pub async fn clear_all(conn: impl Executor<'_, Database = Database>) -> Result<()> {
-        conn.execute("DELETE FROM osu_cached_beatmap_contents;")
-            .await?;
+    /// Delete a cached beatmap file, e.g. when it was updated.
+    pub async fn delete(id: i64, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query("DELETE FROM osu_cached_beatmap_files WHERE beatmap_id = $1")
+                .bind(id)
+                .execute(c)
+                .await?;
+        });
         Ok(())
     }
+
+    /// Delete all of the caches, returning the deleted entries.
+    pub async fn clear_all(conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"DELETE FROM osu_cached_beatmap_files
+                RETURNING beatmap_id, file_md5, size, cached_at"#,
+            )
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    /// Get the size of the cache.
+    pub async fn stats(conn: impl Into<Conn<'_>>) -> Result<CacheTableStats> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT
+                    COUNT(*) AS entries,
+                    CAST(COALESCE(SUM(size), 0) AS BIGINT) AS bytes,
+                    MIN(cached_at) AS oldest
+                FROM osu_cached_beatmap_files"#,
+            )
+            .fetch_one(c)
+            .await?
+        }))
+    }
+
+    /// Delete the beatmap files cached before the given time, returning the deleted entries.
+    pub async fn delete_older_than(
+        before: DateTime,
+        conn: impl Into<Conn<'_>>,
+    ) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"DELETE FROM osu_cached_beatmap_files
+                WHERE cached_at < $1
+                RETURNING beatmap_id, file_md5, size, cached_at"#,
+            )
+            .bind(before)
+            .fetch_all(c)
+            .await?
+        }))
+    }
 }
 
-impl CachedBeatmapContent {
-    pub async fn store(&mut self, conn: impl Executor<'_, Database = Database>) -> Result<()> {
+impl CachedBeatmapFile {
+    pub async fn store(&mut self, conn: impl Into<Conn<'_>>) -> Result<()> {
         self.cached_at = chrono::Utc::now();
-        query!(
-            r#"
+        on_conn!(conn, |c| {
+            query(
+                r#"
                 INSERT INTO
-                    osu_cached_beatmap_contents (beatmap_id, cached_at, content)
+                    osu_cached_beatmap_files (beatmap_id, file_md5, size, cached_at)
                 VALUES
-                    (?, ?, ?)
+                    ($1, $2, $3, $4)
                 ON CONFLICT (beatmap_id)
                 DO UPDATE
                     SET
+                        file_md5 = excluded.file_md5,
+                        size = excluded.size,
+                        cached_at = excluded.cached_at
+                "#,
+            )
+            .bind(self.beatmap_id)
+            .bind(&self.file_md5)
+            .bind(self.size)
+            .bind(self.cached_at)
+            .execute(c)
+            .await?;
+        });
+        Ok(())
+    }
+}
+
+/// Difficulty attributes computed for a beatmap with some mods.
+#[derive(FromRow)]
+pub struct CachedDifficulty {
+    pub beatmap_id: i64,
+    #[sqlx(try_from = "i64")]
+    pub mode: u8,
+    pub mods: i64,
+    pub file_md5: String,
+    pub cached_at: DateTime,
+    pub attributes: Vec<u8>,
+}
+
+impl CachedDifficulty {
+    /// Get the attributes of a beatmap with the given mode and mods.
+    pub async fn by_key(
+        beatmap_id: i64,
+        mode: u8,
+        mods: i64,
+        conn: impl Into<Conn<'_>>,
+    ) -> Result<Option<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT beatmap_id, mode, mods, file_md5, cached_at, attributes
+                FROM osu_cached_difficulties
+                WHERE
+                    beatmap_id = $1
+                    AND mode = $2
+                    AND mods = $3"#,
+            )
+            .bind(beatmap_id)
+            .bind(mode as i64)
+            .bind(mods)
+            .fetch_optional(c)
+            .await?
+        }))
+    }
+
+    /// Delete the attributes of a beatmap computed from another version of its .osu file.
+    pub async fn delete_outdated(
+        beatmap_id: i64,
+        file_md5: &str,
+        conn: impl Into<Conn<'_>>,
+    ) -> Result<u64> {
+        Ok(on_conn!(conn, |c| {
+            query("DELETE FROM osu_cached_difficulties WHERE beatmap_id = $1 AND file_md5 != $2")
+                .bind(beatmap_id)
+                .bind(file_md5)
+                .execute(c)
+                .await?
+                .rows_affected()
+        }))
+    }
+
+    /// Delete all of the caches.
+    pub async fn clear_all(conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query("DELETE FROM osu_cached_difficulties")
+                .execute(c)
+                .await?;
+        });
+        Ok(())
+    }
+
+    /// Get the size of the cache.
+    pub async fn stats(conn: impl Into<Conn<'_>>) -> Result<CacheTableStats> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT
+                    COUNT(*) AS entries,
+                    CAST(COALESCE(SUM(LENGTH(attributes)), 0) AS BIGINT) AS bytes,
+                    MIN(cached_at) AS oldest
+                FROM osu_cached_difficulties"#,
+            )
+            .fetch_one(c)
+            .await?
+        }))
+    }
+
+    /// Delete the attributes computed before the given time. Returns the number of deleted rows.
+    pub async fn delete_older_than(before: DateTime, conn: impl Into<Conn<'_>>) -> Result<u64> {
+        Ok(on_conn!(conn, |c| {
+            query("DELETE FROM osu_cached_difficulties WHERE cached_at < $1")
+                .bind(before)
+                .execute(c)
+                .await?
+                .rows_affected()
+        }))
+    }
+
+    pub async fn store(&mut self, conn: impl Into<Conn<'_>>) -> Result<()> {
+        self.cached_at = chrono::Utc::now();
+        on_conn!(conn, |c| {
+            query(
+                r#"
+                INSERT INTO
+                    osu_cached_difficulties (beatmap_id, mode, mods, file_md5, cached_at, attributes)
+                VALUES
+                    ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (beatmap_id, mode, mods)
+                DO UPDATE
+                    SET
+                        file_md5 = excluded.file_md5,
                         cached_at = excluded.cached_at,
-                        content = excluded.content
-            "#,
-            self.beatmap_id,
-            self.cached_at,
-            self.content
-        )
-        .execute(conn)
-        .await?;
+                        attributes = excluded.attributes
+                "#,
+            )
+            .bind(self.beatmap_id)
+            .bind(self.mode as i64)
+            .bind(self.mods)
+            .bind(&self.file_md5)
+            .bind(self.cached_at)
+            .bind(&self.attributes)
+            .execute(c)
+            .await?;
+        });
         Ok(())
     }
 }
+
+/// A snapshot of an user's statistics, used to plot their history.
+///
+/// Snapshots are taken hourly, and downsampled to daily and weekly ones as they get older.
+#[derive(FromRow)]
+pub struct UserStatsSnapshot {
+    pub user_id: i64,
+    #[sqlx(try_from = "i64")]
+    pub mode: u8,
+    /// The hour of the snapshot.
+    pub taken_at: DateTime,
+    pub rank: Option<i64>,
+    pub pp: Option<f64>,
+    pub accuracy: Option<f64>,
+    pub play_count: Option<i64>,
+    pub map_length: Option<f64>,
+}
+
+impl UserStatsSnapshot {
+    /// Get all snapshots of the given user and mode, oldest first.
+    pub async fn by_user(user_id: i64, mode: u8, conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT user_id, mode, taken_at, rank, pp, accuracy, play_count, map_length
+                FROM osu_user_stats_snapshots
+                WHERE
+                    user_id = $1
+                    AND mode = $2
+                ORDER BY taken_at ASC"#,
+            )
+            .bind(user_id)
+            .bind(mode as i64)
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    /// Get the latest snapshot of the given user and mode taken before the given time.
+    pub async fn latest_before(
+        user_id: i64,
+        mode: u8,
+        before: DateTime,
+        conn: impl Into<Conn<'_>>,
+    ) -> Result<Option<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT user_id, mode, taken_at, rank, pp, accuracy, play_count, map_length
+                FROM osu_user_stats_snapshots
+                WHERE
+                    user_id = $1
+                    AND mode = $2
+                    AND taken_at < $3
+                ORDER BY taken_at DESC
+                LIMIT 1"#,
+            )
+            .bind(user_id)
+            .bind(mode as i64)
+            .bind(before)
+            .fetch_optional(c)
+            .await?
+        }))
+    }
+
+    /// Store the snapshot, replacing the one taken at the same time.
+    /// A missing map length does not replace a known one.
+    pub async fn store(&self, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query(
+                r#"INSERT INTO
+                    osu_user_stats_snapshots (user_id, mode, taken_at, rank, pp, accuracy, play_count, map_length)
+                VALUES
+                    ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (user_id, mode, taken_at) DO UPDATE
+                    SET
+                        rank = excluded.rank,
+                        pp = excluded.pp,
+                        accuracy = excluded.accuracy,
+                        play_count = excluded.play_count,
+                        map_length = COALESCE(excluded.map_length, osu_user_stats_snapshots.map_length)"#,
+            )
+            .bind(self.user_id)
+            .bind(self.mode as i64)
+            .bind(self.taken_at)
+            .bind(self.rank)
+            .bind(self.pp)
+            .bind(self.accuracy)
+            .bind(self.play_count)
+            .bind(self.map_length)
+            .execute(c)
+            .await?;
+        });
+        Ok(())
+    }
+
+    /// Only keep the latest snapshot of each period among the snapshots taken before the given time.
+    /// The period is given as a [chrono::format::strftime] format, e.g. `%Y-%m-%d` for days.
+    /// Returns the number of deleted snapshots.
+    pub async fn downsample(period: &str, before: DateTime, conn: &mut Connection) -> Result<u64> {
+        Ok(on_conn!(conn, |c| {
+            // The periods are computed here, as the databases format dates differently.
+            let snapshots: Vec<(i64, i64, DateTime)> = query_as(
+                r#"SELECT user_id, mode, taken_at
+                FROM osu_user_stats_snapshots
+                WHERE taken_at < $1
+                ORDER BY user_id, mode, taken_at DESC"#,
+            )
+            .bind(before)
+            .fetch_all(&mut *c)
+            .await?;
+            let mut kept = None;
+            let mut deleted = 0;
+            for (user_id, mode, taken_at) in snapshots {
+                let key = (user_id, mode, taken_at.format(period).to_string());
+                if kept.as_ref() != Some(&key) {
+                    kept = Some(key);
+                    continue;
+                }
+                deleted += query(
+                    "DELETE FROM osu_user_stats_snapshots WHERE user_id = $1 AND mode = $2 AND taken_at = $3",
+                )
+                .bind(user_id)
+                .bind(mode)
+                .bind(taken_at)
+                .execute(&mut *c)
+                .await?
+                .rows_affected();
+            }
+            deleted
+        }))
+    }
+}
+
+/// A play of an user, recorded by the announcer to track their activity.
+#[derive(FromRow)]
+pub struct UserPlay {
+    pub user_id: i64,
+    #[sqlx(try_from = "i64")]
+    pub mode: u8,
+    pub played_at: DateTime,
+    pub beatmap_id: i64,
+}
+
+impl UserPlay {
+    /// Get all recorded plays of the given user, in all modes.
+    pub async fn by_user(user_id: i64, conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT user_id, mode, played_at, beatmap_id
+                FROM osu_user_plays
+                WHERE user_id = $1
+                ORDER BY played_at ASC"#,
+            )
+            .bind(user_id)
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    /// Store the play, if it was not recorded already.
+    pub async fn store(&self, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query(
+                r#"INSERT INTO
+                    osu_user_plays (user_id, mode, played_at, beatmap_id)
+                VALUES
+                    ($1, $2, $3, $4)
+                ON CONFLICT DO NOTHING"#,
+            )
+            .bind(self.user_id)
+            .bind(self.mode as i64)
+            .bind(self.played_at)
+            .bind(self.beatmap_id)
+            .execute(c)
+            .await?;
+        });
+        Ok(())
+    }
+}
+
+/// A score announced by the announcer.
+#[derive(FromRow)]
+pub struct AnnouncedScore {
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub message_id: i64,
+
+    pub discord_user_id: i64,
+    pub osu_user_id: i64,
+    pub beatmap_id: i64,
+    #[sqlx(try_from = "i64")]
+    pub mode: u8,
+    pub mods: i64,
+    pub pp: Option<f64>,
+    /// Whether it was a top record (0) or a leaderboard record (1).
+    #[sqlx(try_from = "i64")]
+    pub kind: u8,
+    pub rank: i64,
+    pub played_at: DateTime,
+    pub announced_at: DateTime,
+    /// To be deserialized by `bincode`
+    pub score: Vec<u8>,
+}
+
+impl AnnouncedScore {
+    /// Get the scores announced in the given server, played since the given time, best pp first.
+    pub async fn by_guild_since(
+        guild_id: i64,
+        since: DateTime,
+        conn: impl Into<Conn<'_>>,
+    ) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT
+                    guild_id, channel_id, message_id,
+                    discord_user_id, osu_user_id, beatmap_id, mode, mods, pp,
+                    kind, rank, played_at, announced_at, score
+                FROM osu_announced_scores
+                WHERE
+                    guild_id = $1
+                    AND played_at >= $2
+                ORDER BY pp DESC"#,
+            )
+            .bind(guild_id)
+            .bind(since)
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    /// Get the scores of the given osu! user announced anywhere, played since the given time,
+    /// oldest first.
+    pub async fn by_user_since(
+        osu_user_id: i64,
+        since: DateTime,
+        conn: impl Into<Conn<'_>>,
+    ) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT
+                    guild_id, channel_id, message_id,
+                    discord_user_id, osu_user_id, beatmap_id, mode, mods, pp,
+                    kind, rank, played_at, announced_at, score
+                FROM osu_announced_scores
+                WHERE
+                    osu_user_id = $1
+                    AND played_at >= $2
+                ORDER BY played_at ASC"#,
+            )
+            .bind(osu_user_id)
+            .bind(since)
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    /// Get the latest scores announced in the given server, newest first.
+    pub async fn recent_by_guild(
+        guild_id: i64,
+        limit: i64,
+        conn: impl Into<Conn<'_>>,
+    ) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT
+                    guild_id, channel_id, message_id,
+                    discord_user_id, osu_user_id, beatmap_id, mode, mods, pp,
+                    kind, rank, played_at, announced_at, score
+                FROM osu_announced_scores
+                WHERE guild_id = $1
+                ORDER BY announced_at DESC
+                LIMIT $2"#,
+            )
+            .bind(guild_id)
+            .bind(limit)
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    pub async fn store(&self, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query(
+                r#"INSERT INTO
+                    osu_announced_scores (
+                        guild_id, channel_id, message_id,
+                        discord_user_id, osu_user_id, beatmap_id, mode, mods, pp,
+                        kind, rank, played_at, announced_at, score
+                    )
+                VALUES
+                    ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+                ON CONFLICT DO NOTHING"#,
+            )
+            .bind(self.guild_id)
+            .bind(self.channel_id)
+            .bind(self.message_id)
+            .bind(self.discord_user_id)
+            .bind(self.osu_user_id)
+            .bind(self.beatmap_id)
+            .bind(self.mode as i64)
+            .bind(self.mods)
+            .bind(self.pp)
+            .bind(self.kind as i64)
+            .bind(self.rank)
+            .bind(self.played_at)
+            .bind(self.announced_at)
+            .bind(&self.score)
+            .execute(c)
+            .await?;
+        });
+        Ok(())
+    }
+
+    /// Delete the announced scores of the given guild.
+    pub async fn delete_by_guild(guild_id: i64, conn: impl Into<Conn<'_>>) -> Result<u64> {
+        Ok(on_conn!(conn, |c| {
+            query("DELETE FROM osu_announced_scores WHERE guild_id = $1")
+                .bind(guild_id)
+                .execute(c)
+                .await?
+                .rows_affected()
+        }))
+    }
+}
+
+/// The server ranks, posted automatically to a channel on a schedule.
+#[derive(Debug, Clone, FromRow)]
+pub struct LeaderboardPost {
+    pub id: i64,
+    pub guild_id: i64,
+    pub channel_id: i64,
+    /// A cron expression, in UTC.
+    pub schedule: String,
+    /// The ranking, as given to the `ranks` command.
+    pub ranking: String,
+    pub as_image: bool,
+    pub created_at: DateTime,
+    pub last_posted: Option<DateTime>,
+}
+
+impl LeaderboardPost {
+    /// All scheduled posts.
+    pub async fn all(conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT id, guild_id, channel_id, schedule, ranking, as_image, created_at, last_posted
+                FROM osu_leaderboard_posts
+                ORDER BY id"#,
+            )
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    /// The scheduled posts of the given guild.
+    pub async fn by_guild(guild_id: i64, conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT id, guild_id, channel_id, schedule, ranking, as_image, created_at, last_posted
+                FROM osu_leaderboard_posts
+                WHERE guild_id = $1
+                ORDER BY id"#,
+            )
+            .bind(guild_id)
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    /// Store a new scheduled post, setting its id.
+    pub async fn insert(&mut self, conn: impl Into<Conn<'_>>) -> Result<()> {
+        self.id = on_conn!(conn, |c| {
+            query_scalar(
+                r#"INSERT INTO
+                    osu_leaderboard_posts (guild_id, channel_id, schedule, ranking, as_image, created_at, last_posted)
+                VALUES
+                    ($1, $2, $3, $4, $5, $6, $7)
+                RETURNING id"#,
+            )
+            .bind(self.guild_id)
+            .bind(self.channel_id)
+            .bind(&self.schedule)
+            .bind(&self.ranking)
+            .bind(self.as_image)
+            .bind(self.created_at)
+            .bind(self.last_posted)
+            .fetch_one(c)
+            .await?
+        });
+        Ok(())
+    }
+
+    /// Record that the post was made.
+    pub async fn set_last_posted(
+        id: i64,
+        last_posted: DateTime,
+        conn: impl Into<Conn<'_>>,
+    ) -> Result<()> {
+        on_conn!(conn, |c| {
+            query("UPDATE osu_leaderboard_posts SET last_posted = $1 WHERE id = $2")
+                .bind(last_posted)
+                .bind(id)
+                .execute(c)
+                .await?;
+        });
+        Ok(())
+    }
+
+    /// Cancel the post of the given guild. Returns whether it existed.
+    pub async fn delete(id: i64, guild_id: i64, conn: impl Into<Conn<'_>>) -> Result<bool> {
+        Ok(on_conn!(conn, |c| {
+            query("DELETE FROM osu_leaderboard_posts WHERE id = $1 AND guild_id = $2")
+                .bind(id)
+                .bind(guild_id)
+                .execute(c)
+                .await?
+                .rows_affected()
+        }) > 0)
+    }
+
+    /// Cancel all posts of the given guild, returning how many there were.
+    pub async fn delete_by_guild(guild_id: i64, conn: impl Into<Conn<'_>>) -> Result<u64> {
+        Ok(on_conn!(conn, |c| {
+            query("DELETE FROM osu_leaderboard_posts WHERE guild_id = $1")
+                .bind(guild_id)
+                .execute(c)
+                .await?
+                .rows_affected()
+        }))
+    }
+}
+
+/// The Twitch channel linked by an user.
+#[derive(Debug, Clone, FromRow)]
+pub struct TwitchChannel {
+    pub user_id: i64,
+    pub twitch_login: String,
+    /// The stream last announced, while the channel is live.
+    pub live_stream_id: Option<String>,
+}
+
+impl TwitchChannel {
+    /// All linked channels.
+    pub async fn all(conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as("SELECT user_id, twitch_login, live_stream_id FROM osu_twitch_channels")
+                .fetch_all(c)
+                .await?
+        }))
+    }
+
+    /// The channel linked by the user, if any.
+    pub async fn by_user_id(user_id: i64, conn: impl Into<Conn<'_>>) -> Result<Option<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                "SELECT user_id, twitch_login, live_stream_id FROM osu_twitch_channels WHERE user_id = $1",
+            )
+            .bind(user_id)
+            .fetch_optional(c)
+            .await?
+        }))
+    }
+
+    /// Link the channel, replacing the user's previous one.
+    pub async fn store(&self, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query(
+                r#"INSERT INTO
+                    osu_twitch_channels (user_id, twitch_login, live_stream_id)
+                VALUES
+                    ($1, $2, $3)
+                ON CONFLICT (user_id) DO UPDATE
+                    SET
+                        twitch_login = excluded.twitch_login,
+                        live_stream_id = excluded.live_stream_id"#,
+            )
+            .bind(self.user_id)
+            .bind(&self.twitch_login)
+            .bind(&self.live_stream_id)
+            .execute(c)
+            .await?;
+        });
+        Ok(())
+    }
+
+    /// Record the stream the user is live with, or that they are offline.
+    pub async fn set_live(
+        user_id: i64,
+        live_stream_id: Option<&str>,
+        conn: impl Into<Conn<'_>>,
+    ) -> Result<()> {
+        on_conn!(conn, |c| {
+            query("UPDATE osu_twitch_channels SET live_stream_id = $1 WHERE user_id = $2")
+                .bind(live_stream_id)
+                .bind(user_id)
+                .execute(c)
+                .await?;
+        });
+        Ok(())
+    }
+
+    /// Unlink the user's channel. Returns whether there was one.
+    pub async fn delete(user_id: i64, conn: impl Into<Conn<'_>>) -> Result<bool> {
+        Ok(on_conn!(conn, |c| {
+            query("DELETE FROM osu_twitch_channels WHERE user_id = $1")
+                .bind(user_id)
+                .execute(c)
+                .await?
+                .rows_affected()
+        }) > 0)
+    }
+}
+
+/// A saved user's best score on a daily challenge.
+#[derive(Debug, Clone, FromRow)]
+pub struct DailyChallengeScore {
+    pub room_id: i64,
+    /// The osu! user id.
+    pub user_id: i64,
+    /// When the daily challenge started.
+    pub starts_at: DateTime,
+    pub beatmap_id: i64,
+    pub total_score: i64,
+    pub accuracy: f64,
+    pub max_combo: i64,
+    pub mods: String,
+    pub rank: String,
+    pub pp: Option<f64>,
+}
+
+impl DailyChallengeScore {
+    /// The scores recorded on the daily challenge, best first.
+    pub async fn by_room(room_id: i64, conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT
+                    room_id, user_id, starts_at, beatmap_id, total_score,
+                    accuracy, max_combo, mods, rank, pp
+                FROM osu_daily_challenge_scores
+                WHERE room_id = $1
+                ORDER BY total_score DESC"#,
+            )
+            .bind(room_id)
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    /// When the daily challenges played by the user started, latest first.
+    pub async fn days_of(user_id: i64, conn: impl Into<Conn<'_>>) -> Result<Vec<DateTime>> {
+        Ok(on_conn!(conn, |c| {
+            query_scalar(
+                r#"SELECT starts_at
+                FROM osu_daily_challenge_scores
+                WHERE user_id = $1
+                ORDER BY starts_at DESC"#,
+            )
+            .bind(user_id)
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    /// Store the score, replacing the user's previous one on the same daily challenge.
+    pub async fn store(&self, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query(
+                r#"INSERT INTO
+                    osu_daily_challenge_scores (room_id, user_id, starts_at, beatmap_id, total_score, accuracy, max_combo, mods, rank, pp)
+                VALUES
+                    ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                ON CONFLICT (room_id, user_id) DO UPDATE
+                    SET
+                        total_score = excluded.total_score,
+                        accuracy = excluded.accuracy,
+                        max_combo = excluded.max_combo,
+                        mods = excluded.mods,
+                        rank = excluded.rank,
+                        pp = excluded.pp"#,
+            )
+            .bind(self.room_id)
+            .bind(self.user_id)
+            .bind(self.starts_at)
+            .bind(self.beatmap_id)
+            .bind(self.total_score)
+            .bind(self.accuracy)
+            .bind(self.max_combo)
+            .bind(&self.mods)
+            .bind(&self.rank)
+            .bind(self.pp)
+            .execute(c)
+            .await?;
+        });
+        Ok(())
+    }
+}
+
+/// A server's subscription to the osu! news and the lazer changelogs.
+#[derive(Debug, Clone, FromRow)]
+pub struct NewsSubscription {
+    pub guild_id: i64,
+    pub channel_id: i64,
+    /// Whether the news articles are posted.
+    pub news: bool,
+    /// Whether the lazer changelogs are posted.
+    pub changelog: bool,
+    pub last_news_at: DateTime,
+    pub last_build_at: DateTime,
+}
+
+impl NewsSubscription {
+    /// All subscriptions.
+    pub async fn all(conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT guild_id, channel_id, news, changelog, last_news_at, last_build_at
+                FROM osu_news_subscriptions"#,
+            )
+            .fetch_all(c)
+            .await?
+        }))
+    }
+
+    /// The subscription of the guild, if any.
+    pub async fn by_guild(guild_id: i64, conn: impl Into<Conn<'_>>) -> Result<Option<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT guild_id, channel_id, news, changelog, last_news_at, last_build_at
+                FROM osu_news_subscriptions
+                WHERE guild_id = $1"#,
+            )
+            .bind(guild_id)
+            .fetch_optional(c)
+            .await?
+        }))
+    }
+
+    /// Store the subscription, replacing the guild's previous one.
+    pub async fn store(&self, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query(
+                r#"INSERT INTO
+                    osu_news_subscriptions (guild_id, channel_id, news, changelog, last_news_at, last_build_at)
+                VALUES
+                    ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (guild_id) DO UPDATE
+                    SET
+                        channel_id = excluded.channel_id,
+                        news = excluded.news,
+                        changelog = excluded.changelog,
+                        last_news_at = excluded.last_news_at,
+                        last_build_at = excluded.last_build_at"#,
+            )
+            .bind(self.guild_id)
+            .bind(self.channel_id)
+            .bind(self.news)
+            .bind(self.changelog)
+            .bind(self.last_news_at)
+            .bind(self.last_build_at)
+            .execute(c)
+            .await?;
+        });
+        Ok(())
+    }
+
+    /// Record the latest news article and lazer release posted to the guild.
+    pub async fn set_seen(
+        guild_id: i64,
+        last_news_at: DateTime,
+        last_build_at: DateTime,
+        conn: impl Into<Conn<'_>>,
+    ) -> Result<()> {
+        on_conn!(conn, |c| {
+            query(
+                "UPDATE osu_news_subscriptions SET last_news_at = $1, last_build_at = $2 WHERE guild_id = $3",
+            )
+            .bind(last_news_at)
+            .bind(last_build_at)
+            .bind(guild_id)
+            .execute(c)
+            .await?;
+        });
+        Ok(())
+    }
+
+    /// Remove the guild's subscription. Returns whether there was one.
+    pub async fn delete(guild_id: i64, conn: impl Into<Conn<'_>>) -> Result<bool> {
+        Ok(on_conn!(conn, |c| {
+            query("DELETE FROM osu_news_subscriptions WHERE guild_id = $1")
+                .bind(guild_id)
+                .execute(c)
+                .await?
+                .rows_affected()
+        }) > 0)
+    }
+}
+
+/// The Google Sheet a guild pushes its exports to.
+#[derive(FromRow)]
+pub struct GoogleSheet {
+    pub guild_id: i64,
+    pub spreadsheet_id: String,
+    /// The service account's key, in JSON.
+    pub credentials: String,
+}
+
+impl GoogleSheet {
+    /// The sheet of the guild, if any.
+    pub async fn by_guild(guild_id: i64, conn: impl Into<Conn<'_>>) -> Result<Option<Self>> {
+        Ok(on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT guild_id, spreadsheet_id, credentials
+                FROM osu_google_sheets
+                WHERE guild_id = $1"#,
+            )
+            .bind(guild_id)
+            .fetch_optional(c)
+            .await?
+        }))
+    }
+
+    /// Store the sheet, replacing the guild's previous one.
+    pub async fn store(&self, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query(
+                r#"INSERT INTO
+                    osu_google_sheets (guild_id, spreadsheet_id, credentials)
+                VALUES
+                    ($1, $2, $3)
+                ON CONFLICT (guild_id) DO UPDATE
+                    SET
+                        spreadsheet_id = excluded.spreadsheet_id,
+                        credentials = excluded.credentials"#,
+            )
+            .bind(self.guild_id)
+            .bind(&self.spreadsheet_id)
+            .bind(&self.credentials)
+            .execute(c)
+            .await?;
+        });
+        Ok(())
+    }
+
+    /// Remove the guild's sheet. Returns whether there was one.
+    pub async fn delete(guild_id: i64, conn: impl Into<Conn<'_>>) -> Result<bool> {
+        Ok(on_conn!(conn, |c| {
+            query("DELETE FROM osu_google_sheets WHERE guild_id = $1")
+                .bind(guild_id)
+                .execute(c)
+                .await?
+                .rows_affected()
+        }) > 0)
+    }
+}
@@ -1,8 +1,7 @@
 use super::*;
-use sqlx::{query, query_as, Executor};
 
 /// An osu user, as represented in the SQL.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, FromRow)]
 pub struct OsuUser {
     pub user_id: i64,
     pub username: Option<String>, // should always be there
@@ -13,6 +12,7 @@ pub struct OsuUser {
     pub pp_mania: Option<f64>,
     pub pp_catch: Option<f64>,
     /// Number of consecutive update failures
+    #[sqlx(try_from = "i64")]
     pub failures: u8,
 
     pub std_weighted_map_length: Option<f64>,
@@ -20,114 +20,99 @@ pub struct OsuUser {
 
 impl OsuUser {
     /// Query an user by their user id.
-    pub async fn by_user_id<'a, E>(user_id: i64, conn: &'a mut E) -> Result<Option<Self>>
-    where
-        &'a mut E: Executor<'a, Database = Database>,
-    {
-        let u = query_as!(
-            Self,
-            r#"SELECT
-                user_id as "user_id: i64",
-                username,
-                id as "id: i64",
-                last_update as "last_update: DateTime",
-                pp_std, pp_taiko, pp_mania, pp_catch,
-                failures as "failures: u8",
-                std_weighted_map_length
-            FROM osu_users WHERE user_id = ?"#,
-            user_id
-        )
-        .fetch_optional(conn)
-        .await?;
+    pub async fn by_user_id(user_id: i64, conn: impl Into<Conn<'_>>) -> Result<Option<Self>> {
+        let u = on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT
+                    user_id, username, id, last_update,
+                    pp_std, pp_taiko, pp_mania, pp_catch,
+                    failures, std_weighted_map_length
+                FROM osu_users WHERE user_id = $1"#,
+            )
+            .bind(user_id)
+            .fetch_optional(c)
+            .await?
+        });
         Ok(u)
     }
 
     /// Query an user by their osu id.
-    pub async fn by_osu_id<'a, E>(osu_id: i64, conn: &'a mut E) -> Result<Option<Self>>
-    where
-        &'a mut E: Executor<'a, Database = Database>,
-    {
-        let u = query_as!(
-            Self,
-            r#"SELECT
-                user_id as "user_id: i64",
-                username,
-                id as "id: i64",
-                last_update as "last_update: DateTime",
-                pp_std, pp_taiko, pp_mania, pp_catch,
-                failures as "failures: u8",
-                std_weighted_map_length
-            FROM osu_users WHERE id = ?"#,
-            osu_id
-        )
-        .fetch_optional(conn)
-        .await?;
+    pub async fn by_osu_id(osu_id: i64, conn: impl Into<Conn<'_>>) -> Result<Option<Self>> {
+        let u = on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT
+                    user_id, username, id, last_update,
+                    pp_std, pp_taiko, pp_mania, pp_catch,
+                    failures, std_weighted_map_length
+                FROM osu_users WHERE id = $1"#,
+            )
+            .bind(osu_id)
+            .fetch_optional(c)
+            .await?
+        });
         Ok(u)
     }
 
     /// Query all users.
-    pub fn all<'a, E>(conn: &'a mut E) -> impl Stream<Item = Result<Self>> + 'a
-    where
-        &'a mut E: Executor<'a, Database = Database>,
-    {
-        query_as!(
-            Self,
-            r#"SELECT
-                user_id as "user_id: i64",
-                username,
-                id as "id: i64",
-                last_update as "last_update: DateTime",
-                pp_std, pp_taiko, pp_mania, pp_catch,
-                failures as "failures: u8",
-                std_weighted_map_length
-            FROM osu_users"#,
-        )
-        .fetch_many(conn)
-        .filter_map(map_many_result)
+    pub async fn all(conn: impl Into<Conn<'_>>) -> Result<Vec<Self>> {
+        let u = on_conn!(conn, |c| {
+            query_as(
+                r#"SELECT
+                    user_id, username, id, last_update,
+                    pp_std, pp_taiko, pp_mania, pp_catch,
+                    failures, std_weighted_map_length
+                FROM osu_users"#,
+            )
+            .fetch_all(c)
+            .await?
+        });
+        Ok(u)
     }
 }
 
 impl OsuUser {
     /// Stores the user.
-    pub async fn store<'a, E>(&self, conn: &'a mut E) -> Result<()>
-    where
-        &'a mut E: Executor<'a, Database = Database>,
-    {
-        query!(
-            r#"INSERT
-               INTO osu_users(user_id, username, id, last_update, pp_std, pp_taiko, pp_mania, pp_catch, failures, std_weighted_map_length)
-               VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-               ON CONFLICT (user_id) WHERE id = ? DO UPDATE
-               SET
-                last_update = excluded.last_update,
-                username = excluded.username,
-                pp_std = excluded.pp_std,
-                pp_taiko = excluded.pp_taiko,
-                pp_mania = excluded.pp_mania,
-                pp_catch = excluded.pp_catch,
-                failures = excluded.failures,
-                std_weighted_map_length = excluded.std_weighted_map_length
-            "#,
-            self.user_id,
-            self.username,
-            self.id,
-            self.last_update,
-            self.pp_std,
-            self.pp_taiko,
-            self.pp_mania,
-            self.pp_catch,
-            self.failures,
-            self.std_weighted_map_length,
-
-            self.user_id,
-        ).execute(conn).await?;
+    pub async fn store(&self, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query(
+                r#"INSERT
+                   INTO osu_users(user_id, username, id, last_update, pp_std, pp_taiko, pp_mania, pp_catch, failures, std_weighted_map_length)
+                   VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                   ON CONFLICT (user_id) DO UPDATE
+                   SET
+                    last_update = excluded.last_update,
+                    username = excluded.username,
+                    pp_std = excluded.pp_std,
+                    pp_taiko = excluded.pp_taiko,
+                    pp_mania = excluded.pp_mania,
+                    pp_catch = excluded.pp_catch,
+                    failures = excluded.failures,
+                    std_weighted_map_length = excluded.std_weighted_map_length
+                "#,
+            )
+            .bind(self.user_id)
+            .bind(&self.username)
+            .bind(self.id)
+            .bind(self.last_update)
+            .bind(self.pp_std)
+            .bind(self.pp_taiko)
+            .bind(self.pp_mania)
+            .bind(self.pp_catch)
+            .bind(self.failures as i64)
+            .bind(self.std_weighted_map_length)
+            .execute(c)
+            .await?;
+        });
         Ok(())
     }
 
-    pub async fn delete(user_id: i64, conn: impl Executor<'_, Database = Database>) -> Result<()> {
-        query!("DELETE FROM osu_users WHERE user_id = ?", user_id)
-            .execute(conn)
-            .await?;
+    pub async fn delete(user_id: i64, conn: impl Into<Conn<'_>>) -> Result<()> {
+        on_conn!(conn, |c| {
+            query("DELETE FROM osu_users WHERE user_id = $1")
+                .bind(user_id)
+                .execute(c)
+                .await?;
+        });
         Ok(())
     }
 }
@@ -0,0 +1,705 @@
+//! Round trips of every model through the database.
+//!
+//! Each test runs on an in-memory SQLite database, and on Postgres if `DATABASE_URL_PG` is set,
+//! in a new schema of that database.
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use chrono::{Duration, TimeZone, Utc};
+use youmubot_db_sql::models::{
+    config::GuildConfigValue, hooks::HookSetting, jobs::ScheduledJob, locale::UserLocale, osu::*,
+    osu_user::OsuUser, DateTime,
+};
+use youmubot_db_sql::{connect_url, transaction, Pool, PoolConfig};
+
+static NEXT_DATABASE: AtomicU32 = AtomicU32::new(0);
+
+/// New empty databases to run a test on.
+async fn pools() -> Vec<Pool> {
+    let n = NEXT_DATABASE.fetch_add(1, Ordering::SeqCst);
+    let name = format!("test_{}_{}", std::process::id(), n);
+    let mut pools = vec![connect_url(
+        &format!("sqlite:file:{}?mode=memory&cache=shared", name),
+        &PoolConfig::default(),
+    )
+    .await
+    .unwrap()];
+    if let Ok(url) = std::env::var("DATABASE_URL_PG") {
+        let admin = connect_url(&url, &PoolConfig::default()).await.unwrap();
+        if let Pool::Postgres(p) = &admin {
+            sqlx::query(&format!("CREATE SCHEMA {}", name))
+                .execute(p)
+                .await
+                .unwrap();
+        }
+        let sep = if url.contains('?') { '&' } else { '?' };
+        let url = format!("{}{}options=-c%20search_path%3D{}", url, sep, name);
+        pools.push(connect_url(&url, &PoolConfig::default()).await.unwrap());
+    }
+    pools
+}
+
+fn now() -> DateTime {
+    Utc.with_ymd_and_hms(2024, 10, 1, 12, 0, 0).unwrap()
+}
+
+#[tokio::test]
+async fn osu_user() {
+    for pool in pools().await {
+        let mut user = OsuUser {
+            user_id: 1 << 50,
+            username: Some("youmu".to_owned()),
+            id: 1 << 40,
+            last_update: now(),
+            pp_std: Some(1.5),
+            pp_taiko: None,
+            pp_mania: None,
+            pp_catch: Some(2.0),
+            failures: 3,
+            std_weighted_map_length: Some(120.5),
+        };
+        user.store(&pool).await.unwrap();
+        user.failures = 4;
+        user.store(&pool).await.unwrap();
+
+        let got = OsuUser::by_user_id(1 << 50, &pool).await.unwrap().unwrap();
+        assert_eq!(got.username.as_deref(), Some("youmu"));
+        assert_eq!((got.id, got.failures), (1 << 40, 4));
+        assert_eq!(got.last_update, now());
+        assert_eq!(
+            (got.pp_std, got.pp_taiko, got.pp_catch),
+            (Some(1.5), None, Some(2.0))
+        );
+        assert_eq!(got.std_weighted_map_length, Some(120.5));
+        let by_osu_id = OsuUser::by_osu_id(1 << 40, &pool).await.unwrap().unwrap();
+        assert_eq!(by_osu_id.user_id, 1 << 50);
+        assert_eq!(OsuUser::all(&pool).await.unwrap().len(), 1);
+
+        OsuUser::delete(1 << 50, &pool).await.unwrap();
+        assert!(OsuUser::by_user_id(1 << 50, &pool).await.unwrap().is_none());
+    }
+}
+
+#[tokio::test]
+async fn last_beatmap() {
+    for pool in pools().await {
+        let mut conn = pool.acquire().await.unwrap();
+        let beatmap = |b: u8| LastBeatmap {
+            id: 0,
+            channel_id: 7,
+            guild_id: Some(9),
+            beatmap: vec![b],
+            mode: 1,
+            saved_at: now(),
+        };
+        for b in 0..5 {
+            beatmap(b).store(&mut conn).await.unwrap();
+        }
+        // Storing a beatmap again moves it to the front.
+        beatmap(0).store(&mut conn).await.unwrap();
+        drop(conn);
+
+        let mut conn = pool.acquire().await.unwrap();
+        let saved = LastBeatmap::by_channel_id(7, 10, &mut conn).await.unwrap();
+        drop(conn);
+        assert_eq!(
+            saved.iter().map(|b| b.beatmap[0]).collect::<Vec<_>>(),
+            [0, 4, 3, 2, 1]
+        );
+        assert_eq!(saved[0].guild_id, Some(9));
+        assert_eq!((saved[0].mode, saved[0].saved_at), (1, now()));
+        let second = LastBeatmap::nth_by_channel_id(7, 1, &pool).await.unwrap();
+        assert_eq!(second.unwrap().beatmap, [4]);
+
+        assert_eq!(LastBeatmap::trim(7, 3, &pool).await.unwrap(), 2);
+        assert_eq!(LastBeatmap::delete_by_channel(7, &pool).await.unwrap(), 3);
+        beatmap(1)
+            .store(&mut pool.acquire().await.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(LastBeatmap::delete_by_guild(9, &pool).await.unwrap(), 1);
+    }
+}
+
+/// A saved user, that scores can be stored for.
+fn saved_user(user_id: i64) -> OsuUser {
+    OsuUser {
+        user_id,
+        username: Some("youmu".to_owned()),
+        id: user_id + 1,
+        last_update: now(),
+        pp_std: None,
+        pp_taiko: None,
+        pp_mania: None,
+        pp_catch: None,
+        failures: 0,
+        std_weighted_map_length: None,
+    }
+}
+
+#[tokio::test]
+async fn user_best_score() {
+    for pool in pools().await {
+        saved_user(1 << 50).store(&pool).await.unwrap();
+        for (beatmap_id, mods) in [(1, 0), (2, 8), (3, 16)] {
+            let mut score = UserBestScore {
+                beatmap_id,
+                mode: 2,
+                user_id: 1 << 50,
+                mods,
+                cached_at: now(),
+                score: vec![beatmap_id as u8],
+            };
+            score.store(&pool).await.unwrap();
+            // Scores are ordered by the time they were cached.
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        let got = UserBestScore::by_map_and_user(2, 2, 1 << 50, &pool)
+            .await
+            .unwrap();
+        assert_eq!(got.len(), 1);
+        assert_eq!((got[0].mods, got[0].score.as_slice()), (8, &[2][..]));
+        assert!(got[0].cached_at > now());
+        assert_eq!(UserBestScore::by_map(2, 2, &pool).await.unwrap().len(), 1);
+        assert!(UserBestScore::by_map(2, 0, &pool).await.unwrap().is_empty());
+
+        // Only the two most recently cached scores are kept.
+        assert_eq!(UserBestScore::delete_excess(2, &pool).await.unwrap(), 1);
+        assert!(UserBestScore::by_map(1, 2, &pool).await.unwrap().is_empty());
+        UserBestScore::clear_map_user(2, 2, 1 << 50, &pool)
+            .await
+            .unwrap();
+        assert!(UserBestScore::by_map(2, 2, &pool).await.unwrap().is_empty());
+        assert_eq!(
+            UserBestScore::delete_older_than(Utc::now() + Duration::hours(1), &pool)
+                .await
+                .unwrap(),
+            1
+        );
+
+        // Scores are deleted with their user.
+        UserBestScore {
+            beatmap_id: 1,
+            mode: 2,
+            user_id: 1 << 50,
+            mods: 0,
+            cached_at: now(),
+            score: vec![],
+        }
+        .store(&pool)
+        .await
+        .unwrap();
+        OsuUser::delete(1 << 50, &pool).await.unwrap();
+        assert!(UserBestScore::by_map(1, 2, &pool).await.unwrap().is_empty());
+        UserBestScore::clear_user(1 << 50, &pool).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn cached_beatmap() {
+    for pool in pools().await {
+        let mut beatmap = CachedBeatmap {
+            beatmap_id: 5,
+            mode: 3,
+            cached_at: now(),
+            beatmap: vec![1, 2, 3],
+        };
+        beatmap.store(&pool).await.unwrap();
+        beatmap.store(&pool).await.unwrap();
+        beatmap.link_beatmapset(77, &pool).await.unwrap();
+        beatmap.link_beatmapset(77, &pool).await.unwrap();
+
+        let got = CachedBeatmap::by_id(5, 3, &pool).await.unwrap().unwrap();
+        assert_eq!((got.mode, got.beatmap.as_slice()), (3, &[1, 2, 3][..]));
+        assert!(CachedBeatmap::by_id(5, 0, &pool).await.unwrap().is_none());
+        assert_eq!(
+            CachedBeatmap::by_beatmap_id(5, &pool).await.unwrap().len(),
+            1
+        );
+        assert_eq!(
+            CachedBeatmap::by_beatmapset(77, &pool).await.unwrap().len(),
+            1
+        );
+        let stats = CachedBeatmap::stats(&pool).await.unwrap();
+        assert_eq!((stats.entries, stats.bytes), (1, 3));
+        assert!(stats.oldest.is_some());
+
+        let mut conn = pool.acquire().await.unwrap();
+        assert_eq!(CachedBeatmap::delete(5, &mut conn).await.unwrap(), 1);
+        beatmap.store(&mut conn).await.unwrap();
+        let later = Utc::now() + Duration::hours(1);
+        assert_eq!(
+            CachedBeatmap::delete_older_than(later, &mut conn)
+                .await
+                .unwrap(),
+            1
+        );
+        beatmap.store(&mut conn).await.unwrap();
+        drop(conn);
+        CachedBeatmap::clear_all(&pool).await.unwrap();
+        let stats = CachedBeatmap::stats(&pool).await.unwrap();
+        assert_eq!((stats.entries, stats.bytes), (0, 0));
+        assert!(stats.oldest.is_none());
+    }
+}
+
+#[tokio::test]
+async fn cached_beatmap_file() {
+    for pool in pools().await {
+        let mut file = CachedBeatmapFile {
+            beatmap_id: 5,
+            file_md5: "abc".to_owned(),
+            size: 100,
+            cached_at: now(),
+        };
+        file.store(&pool).await.unwrap();
+        file.store(&pool).await.unwrap();
+
+        let got = CachedBeatmapFile::by_id(5, &pool).await.unwrap().unwrap();
+        assert_eq!((got.file_md5.as_str(), got.size), ("abc", 100));
+        assert_eq!(CachedBeatmapFile::stats(&pool).await.unwrap().bytes, 100);
+        let later = Utc::now() + Duration::hours(1);
+        let deleted = CachedBeatmapFile::delete_older_than(later, &pool)
+            .await
+            .unwrap();
+        assert_eq!(deleted.len(), 1);
+        file.store(&pool).await.unwrap();
+        assert_eq!(CachedBeatmapFile::clear_all(&pool).await.unwrap().len(), 1);
+        file.store(&pool).await.unwrap();
+        CachedBeatmapFile::delete(5, &pool).await.unwrap();
+        assert!(CachedBeatmapFile::by_id(5, &pool).await.unwrap().is_none());
+    }
+}
+
+#[tokio::test]
+async fn cached_difficulty() {
+    for pool in pools().await {
+        let mut difficulty = CachedDifficulty {
+            beatmap_id: 5,
+            mode: 3,
+            mods: 64,
+            file_md5: "a".to_owned(),
+            cached_at: now(),
+            attributes: vec![1, 2],
+        };
+        difficulty.store(&pool).await.unwrap();
+        difficulty.store(&pool).await.unwrap();
+
+        let got = CachedDifficulty::by_key(5, 3, 64, &pool)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (got.file_md5.as_str(), got.attributes.as_slice()),
+            ("a", &[1, 2][..])
+        );
+        assert!(CachedDifficulty::by_key(5, 3, 0, &pool)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(CachedDifficulty::stats(&pool).await.unwrap().bytes, 2);
+        assert_eq!(
+            CachedDifficulty::delete_outdated(5, "a", &pool)
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            CachedDifficulty::delete_outdated(5, "b", &pool)
+                .await
+                .unwrap(),
+            1
+        );
+        difficulty.store(&pool).await.unwrap();
+        let later = Utc::now() + Duration::hours(1);
+        assert_eq!(
+            CachedDifficulty::delete_older_than(later, &pool)
+                .await
+                .unwrap(),
+            1
+        );
+        difficulty.store(&pool).await.unwrap();
+        CachedDifficulty::clear_all(&pool).await.unwrap();
+        assert_eq!(CachedDifficulty::stats(&pool).await.unwrap().entries, 0);
+    }
+}
+
+#[tokio::test]
+async fn user_stats_snapshot() {
+    for pool in pools().await {
+        for h in 0..48 {
+            UserStatsSnapshot {
+                user_id: 1,
+                mode: 0,
+                taken_at: now() - Duration::hours(h),
+                rank: Some(h),
+                pp: Some(1.0),
+                accuracy: Some(99.5),
+                play_count: None,
+                map_length: (h == 0).then_some(2.0),
+            }
+            .store(&pool)
+            .await
+            .unwrap();
+        }
+
+        let all = UserStatsSnapshot::by_user(1, 0, &pool).await.unwrap();
+        assert_eq!(all.len(), 48);
+        let last = all.last().unwrap();
+        assert_eq!((last.taken_at, last.rank), (now(), Some(0)));
+        assert_eq!((last.accuracy, last.map_length), (Some(99.5), Some(2.0)));
+        assert!(UserStatsSnapshot::by_user(1, 1, &pool)
+            .await
+            .unwrap()
+            .is_empty());
+        let before = UserStatsSnapshot::latest_before(1, 0, now(), &pool)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(before.rank, Some(1));
+
+        // One snapshot is kept per day, over the three days the snapshots span.
+        let mut conn = pool.acquire().await.unwrap();
+        let deleted =
+            UserStatsSnapshot::downsample("%Y-%m-%d", now() + Duration::hours(1), &mut conn)
+                .await
+                .unwrap();
+        drop(conn);
+        assert_eq!(deleted, 45);
+        assert_eq!(
+            UserStatsSnapshot::by_user(1, 0, &pool).await.unwrap().len(),
+            3
+        );
+    }
+}
+
+#[tokio::test]
+async fn user_play() {
+    for pool in pools().await {
+        let play = UserPlay {
+            user_id: 1,
+            mode: 2,
+            played_at: now(),
+            beatmap_id: 3,
+        };
+        play.store(&pool).await.unwrap();
+        play.store(&pool).await.unwrap();
+
+        let got = UserPlay::by_user(1, &pool).await.unwrap();
+        assert_eq!(got.len(), 1);
+        assert_eq!(
+            (got[0].mode, got[0].played_at, got[0].beatmap_id),
+            (2, now(), 3)
+        );
+    }
+}
+
+#[tokio::test]
+async fn announced_score() {
+    for pool in pools().await {
+        let score = AnnouncedScore {
+            guild_id: 1,
+            channel_id: 2,
+            message_id: 3,
+            discord_user_id: 4,
+            osu_user_id: 5,
+            beatmap_id: 6,
+            mode: 1,
+            mods: 72,
+            pp: Some(100.5),
+            kind: 1,
+            rank: 2,
+            played_at: now(),
+            announced_at: now(),
+            score: vec![9],
+        };
+        score.store(&pool).await.unwrap();
+        score.store(&pool).await.unwrap();
+
+        let got = AnnouncedScore::by_guild_since(1, now(), &pool)
+            .await
+            .unwrap();
+        assert_eq!(got.len(), 1);
+        assert_eq!(
+            (got[0].message_id, got[0].mods, got[0].pp),
+            (3, 72, Some(100.5))
+        );
+        assert_eq!(
+            (got[0].kind, got[0].rank, got[0].score.as_slice()),
+            (1, 2, &[9][..])
+        );
+        assert!(
+            AnnouncedScore::by_guild_since(1, now() + Duration::seconds(1), &pool)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            AnnouncedScore::by_user_since(5, now(), &pool)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            AnnouncedScore::recent_by_guild(1, 5, &pool)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(AnnouncedScore::delete_by_guild(1, &pool).await.unwrap(), 1);
+    }
+}
+
+#[tokio::test]
+async fn leaderboard_post() {
+    for pool in pools().await {
+        let mut post = LeaderboardPost {
+            id: 0,
+            guild_id: 1,
+            channel_id: 2,
+            schedule: "0 0 * * *".to_owned(),
+            ranking: "std".to_owned(),
+            as_image: true,
+            created_at: now(),
+            last_posted: None,
+        };
+        post.insert(&pool).await.unwrap();
+        let first = post.id;
+        post.insert(&pool).await.unwrap();
+        assert_eq!(post.id, first + 1);
+
+        LeaderboardPost::set_last_posted(post.id, now(), &pool)
+            .await
+            .unwrap();
+        let got = LeaderboardPost::by_guild(1, &pool).await.unwrap();
+        assert_eq!(got.len(), 2);
+        assert_eq!(
+            (got[0].last_posted, got[1].last_posted),
+            (None, Some(now()))
+        );
+        assert_eq!(
+            (got[1].schedule.as_str(), got[1].as_image),
+            ("0 0 * * *", true)
+        );
+        // Posts are only deleted from their own guild.
+        assert!(!LeaderboardPost::delete(first, 2, &pool).await.unwrap());
+        assert!(LeaderboardPost::delete(first, 1, &pool).await.unwrap());
+        assert_eq!(LeaderboardPost::all(&pool).await.unwrap().len(), 1);
+        assert_eq!(LeaderboardPost::delete_by_guild(1, &pool).await.unwrap(), 1);
+    }
+}
+
+#[tokio::test]
+async fn twitch_channel() {
+    for pool in pools().await {
+        TwitchChannel {
+            user_id: 1,
+            twitch_login: "youmu".to_owned(),
+            live_stream_id: None,
+        }
+        .store(&pool)
+        .await
+        .unwrap();
+        TwitchChannel::set_live(1, Some("stream"), &pool)
+            .await
+            .unwrap();
+
+        let got = TwitchChannel::by_user_id(1, &pool).await.unwrap().unwrap();
+        assert_eq!(got.twitch_login, "youmu");
+        assert_eq!(got.live_stream_id.as_deref(), Some("stream"));
+        assert_eq!(TwitchChannel::all(&pool).await.unwrap().len(), 1);
+        assert!(TwitchChannel::delete(1, &pool).await.unwrap());
+        assert!(TwitchChannel::by_user_id(1, &pool).await.unwrap().is_none());
+    }
+}
+
+#[tokio::test]
+async fn daily_challenge_score() {
+    for pool in pools().await {
+        let score = DailyChallengeScore {
+            room_id: 1,
+            user_id: 2,
+            starts_at: now(),
+            beatmap_id: 3,
+            total_score: 1_000_000,
+            accuracy: 99.5,
+            max_combo: 5,
+            mods: "HD".to_owned(),
+            rank: "S".to_owned(),
+            pp: Some(250.5),
+        };
+        score.store(&pool).await.unwrap();
+        score.store(&pool).await.unwrap();
+
+        assert_eq!(
+            DailyChallengeScore::days_of(2, &pool).await.unwrap(),
+            [now()]
+        );
+        let got = DailyChallengeScore::by_room(1, &pool).await.unwrap();
+        assert_eq!(got.len(), 1);
+        assert_eq!(
+            (got[0].total_score, got[0].accuracy, got[0].pp),
+            (1_000_000, 99.5, Some(250.5))
+        );
+        assert_eq!((got[0].mods.as_str(), got[0].rank.as_str()), ("HD", "S"));
+    }
+}
+
+#[tokio::test]
+async fn news_subscription() {
+    for pool in pools().await {
+        NewsSubscription {
+            guild_id: 1,
+            channel_id: 2,
+            news: true,
+            changelog: false,
+            last_news_at: now(),
+            last_build_at: now(),
+        }
+        .store(&pool)
+        .await
+        .unwrap();
+        NewsSubscription::set_seen(1, now() + Duration::hours(1), now(), &pool)
+            .await
+            .unwrap();
+
+        let got = NewsSubscription::by_guild(1, &pool).await.unwrap().unwrap();
+        assert_eq!((got.channel_id, got.news, got.changelog), (2, true, false));
+        assert_eq!(got.last_news_at, now() + Duration::hours(1));
+        assert_eq!(NewsSubscription::all(&pool).await.unwrap().len(), 1);
+        assert!(NewsSubscription::delete(1, &pool).await.unwrap());
+        assert!(NewsSubscription::by_guild(1, &pool)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
+
+#[tokio::test]
+async fn google_sheet() {
+    for pool in pools().await {
+        GoogleSheet {
+            guild_id: 1,
+            spreadsheet_id: "sheet".to_owned(),
+            credentials: "{}".to_owned(),
+        }
+        .store(&pool)
+        .await
+        .unwrap();
+
+        let got = GoogleSheet::by_guild(1, &pool).await.unwrap().unwrap();
+        assert_eq!(
+            (got.spreadsheet_id.as_str(), got.credentials.as_str()),
+            ("sheet", "{}")
+        );
+        assert!(GoogleSheet::delete(1, &pool).await.unwrap());
+        assert!(GoogleSheet::by_guild(1, &pool).await.unwrap().is_none());
+    }
+}
+
+#[tokio::test]
+async fn guild_config_value() {
+    for pool in pools().await {
+        let value = |guild_id, key: &str, value: &str| GuildConfigValue {
+            guild_id,
+            key: key.to_owned(),
+            value: value.to_owned(),
+        };
+        value(1, "prefix", "y!").store(&pool).await.unwrap();
+        value(1, "prefix", "!").store(&pool).await.unwrap();
+        value(1, "language", "vi").store(&pool).await.unwrap();
+        value(2, "prefix", "?").store(&pool).await.unwrap();
+
+        let prefix = GuildConfigValue::get(1, "prefix", &pool).await.unwrap();
+        assert_eq!(prefix.as_deref(), Some("!"));
+        assert_eq!(GuildConfigValue::by_guild(1, &pool).await.unwrap().len(), 2);
+        assert_eq!(
+            GuildConfigValue::by_key("prefix", &pool)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        GuildConfigValue::delete(1, "language", &pool)
+            .await
+            .unwrap();
+        assert!(GuildConfigValue::get(1, "language", &pool)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            GuildConfigValue::delete_by_guild(2, &pool).await.unwrap(),
+            ["prefix"]
+        );
+    }
+}
+
+#[tokio::test]
+async fn hook_setting() {
+    for pool in pools().await {
+        // Only read to import the switches set before they moved to the guild configuration.
+        assert!(HookSetting::all(&pool).await.unwrap().is_empty());
+        assert_eq!(HookSetting::delete_by_channel(2, &pool).await.unwrap(), 0);
+    }
+}
+
+#[tokio::test]
+async fn user_locale() {
+    for pool in pools().await {
+        UserLocale {
+            user_id: 1,
+            locale: "vi".to_owned(),
+        }
+        .store(&pool)
+        .await
+        .unwrap();
+
+        let got = UserLocale::by_user_id(1, &pool).await.unwrap();
+        assert_eq!(got.as_deref(), Some("vi"));
+        UserLocale::delete(1, &pool).await.unwrap();
+        assert!(UserLocale::by_user_id(1, &pool).await.unwrap().is_none());
+    }
+}
+
+#[tokio::test]
+async fn scheduled_job() {
+    for pool in pools().await {
+        assert!(ScheduledJob::last_run("job", &pool)
+            .await
+            .unwrap()
+            .is_none());
+        for last_run in [now() - Duration::hours(1), now()] {
+            ScheduledJob {
+                name: "job".to_owned(),
+                last_run,
+            }
+            .store(&pool)
+            .await
+            .unwrap();
+        }
+        let got = ScheduledJob::last_run("job", &pool).await.unwrap();
+        assert_eq!(got, Some(now()));
+    }
+}
+
+#[tokio::test]
+async fn transactions() {
+    for pool in pools().await {
+        let locale = || UserLocale {
+            user_id: 2,
+            locale: "en".to_owned(),
+        };
+        let mut t = pool.begin().await.unwrap();
+        locale().store(&mut *t).await.unwrap();
+        drop(t);
+        assert!(UserLocale::by_user_id(2, &pool).await.unwrap().is_none());
+
+        transaction(&pool, |c| Box::pin(async move { locale().store(c).await }))
+            .await
+            .unwrap();
+        let got = UserLocale::by_user_id(2, &pool).await.unwrap();
+        assert_eq!(got.as_deref(), Some("en"));
+    }
+}
@@ -0,0 +1,190 @@
+# The osu! module's messages, in English.
+# Every other language falls back to these for the messages it lacks.
+
+## Beatmap embeds
+
+beatmap-submitted = Submitted { $submitted } | Last updated { $updated }
+beatmap-download = Download: [[Link]]({ $link }) [[No Video]]({ $link }?noVideo=1) [[Bloodcat]]({ $mirror }) [[Beatmapset]]({ $beatmapset })
+beatmap-language-genre = Language: **{ $language }** | Genre: **{ $genre }**
+beatmap-source = Source: **{ $source }**
+beatmap-tags = Tags: { $tags }
+beatmap-information = Information
+beatmap-mods = **Mods**: { $mods }
+beatmap-pp-table = FC pp ({ $mode })
+beatmap-converted = [{ $difficulty }] (converted to { $mode })
+beatmapset-too-many-diffs = This map has { $count } diffs, we are showing the last { $shown }.
+offline-difficulty = ({ $mode }) { $stars }⭐, { $combo }x max combo
+offline-footer = { $count } difficulties, unsubmitted
+
+## Score embeds
+
+score-failed = failed
+score-failed-at = failed at { $progress }%
+score-line-failed = { $accuracy }% { $combo } combo [{ $status }]
+score-line = { $accuracy }% { $combo }x { $misses } miss { $rank } rank
+score-pp-if-fc = { $pp } ({ $fc }pp if FC?)
+score-pp-gained = **pp gained**: **{ $pp }**pp
+score-pp-gained-top = **pp gained**: **{ $pp }**pp (+**{ $effective }**pp | { $before }pp \➡️ { $after }pp)
+score-top-record = #{ $rank } top record!
+score-world-record = #{ $rank } on Global Rankings!
+score-global-rank = Global #{ $rank }
+score-replay = Replay
+score-by = by { $creator }
+score-played = **Played**: { $date }
+score-stats = Score stats
+score-lazer = (lazer)
+score-hits = 300s | 100s | 50s | misses
+score-hit-error = Hit error
+score-hit-error-value = **{ $ur }** UR | **{ $average }**ms avg ({ $timing })
+score-early = early
+score-late = late
+score-map-stats = Map stats
+score-stars-without-mods = Star difficulty does not reflect game mods.
+page-footer = Page { $page }/{ $total }
+
+## User embeds
+
+user-member-since = Member since **{ $date }**
+user-member-since-badges = Member since **{ $date }**, **{ $count }** badge(s)
+user-member-since-plain = Member since { $date }
+user-pp = Performance Points
+user-inactive = Inactive
+user-world-rank = World Rank
+user-country-rank = Country Rank
+user-rank = Rank
+user-accuracy = Accuracy
+user-play-count = Play count
+user-play-count-time = Play count / Play time
+user-play-count-time-value = { $count } / { $hours } hours ({ $time })
+    **{ $hits }** total hits, **{ $combo }**x max combo
+user-rank-history = Rank history (90 days)
+user-rank-history-peak = (peak **#{ $peak }**)
+user-ranks = Ranks
+user-map-length = Weighted Map Length
+user-map-length-value = **{ $minutes }** minutes **{ $seconds }** seconds (**{ $total }**s)
+user-level = Level { $level }
+user-scores = **{ $total }** total score, **{ $ranked }** ranked score
+user-best-record = Best Record
+user-best-record-on = on
+
+## osu!track updates
+
+osutrack-first = This is the first time osu!track sees this user: changes will be shown from the next update.
+osutrack-level = Level
+osutrack-new-highscores = New top plays ({ $count })
+osutrack-new-highscore = #{ $position }: **{ $pp }pp** ({ $grade }) on [/b/{ $beatmap }](https://osu.ppy.sh/b/{ $beatmap })
+osutrack-footer = Changes since the last update, from osu!track
+osutrack-not-found = osu!track cannot find this user.
+
+## Snipes
+
+snipes-untracked-country = huismetbenen does not track the players of { $country }, sorry!
+snipes-none = { $user } has no country #1s in { $country }.
+snipes-count = Country #1s ({ $country })
+snipes-average-pp = Average pp
+snipes-average-stars = Average stars
+
+## Daily challenge
+
+daily-none = There is no daily challenge running right now.
+daily-challenge = Today's daily challenge is { $room }, with **{ $participants }** participants so far! It ends { $ends }.
+daily-no-scores = No daily challenge scores were recorded for the members of this server yet.
+
+## News
+
+news-none = There is no news right now.
+news-footer = osu! news
+news-published = Published
+news-changelog-footer = osu! changelog
+news-released = Released
+news-changelog-more = …and { $count } more changes.
+news-kind-news = the osu! news
+news-kind-changelog = the lazer changelogs
+news-kind-all = the osu! news and the lazer changelogs
+news-subscribed = From now on, { $what } will be posted to { $channel }.
+news-subscription = { $what } are posted to { $channel }.
+news-unsubscribed = The osu! news and lazer changelogs will no longer be posted in this server.
+news-not-subscribed = This server is not subscribed to the osu! news.
+
+## Wiki
+
+wiki-not-found = No osu! wiki article was found for { $query }.
+wiki-footer = osu! wiki
+wiki-not-translated = Not translated yet, shown in English
+
+## Collections
+
+collection-invalid-link = That is not a link to an osu!collector collection.
+collection-not-found = This collection does not exist on osu!collector.
+collection-import = Here is { $name } as a `collection.db` and an `.osdb`. Import either with Collection Manager, or put the `collection.db` in your osu! folder. Careful, that replaces your own collections!
+collection-by = by { $user }
+collection-footer = osu!collector • { $count } beatmaps • { $favourites } favourites • page { $page }/{ $pages }
+
+## Matches
+
+match-invalid-link = That is not a link to an osu! multiplayer match.
+match-not-found = This match does not exist, or its history was removed.
+
+## Google Sheets
+
+sheet-not-set = This server has no Google Sheet, set one with `export sheet set`.
+sheet-status = Exports are pushed to { $link }, written by `{ $account }`.
+sheet-set = { $user }, exports pushed with `--sheet` will now go to { $link }. The key was read, and its message removed.
+sheet-set-key-kept = { $user }, exports pushed with `--sheet` will now go to { $link }.
+sheet-key-not-deleted = I could not remove the message with the key. Please delete it yourself, and consider creating a new key, as anyone in this channel could read it.
+sheet-removed = Exports will no longer be pushed to the Google Sheet, and its key was forgotten.
+sheet-invalid-link = That is not a link to a Google Sheet.
+sheet-no-key = Please attach the JSON key of the service account.
+sheet-no-access = Cannot open the sheet. Is it shared with `{ $account }` as an editor?
+sheet-guild-only = Only the exports in a server can be pushed to its Google Sheet.
+sheet-pushed = Pushed to the Google Sheet: <{ $link }>
+
+## Twitch
+
+twitch-not-configured = Twitch is not set up on this bot, sorry!
+twitch-channel-not-found = There is no Twitch channel named `{ $login }`.
+twitch-linked = Your Twitch channel is now { $channel }. Your osu! streams will be announced!
+twitch-unlinked = Your Twitch channel has been unlinked.
+twitch-none = You have not linked a Twitch channel.
+twitch-show = { $user }'s Twitch channel is { $channel }.
+twitch-show-none = { $user } has not linked a Twitch channel.
+twitch-game = Playing
+twitch-no-game = nothing in particular
+twitch-viewers = Viewers
+twitch-started = Started
+
+## Command replies
+
+user-not-found = 🔍 user not found!
+no-saved-account = No saved account found
+no-such-play = No such play
+no-plays = No plays found
+no-scores = No scores found
+no-beatmap-queried = No beatmap was queried on this channel.
+beatmaps-queried = Beatmaps queried on this channel
+requested-play = Here is the play that you requested
+requested-play-of = { $user }: here is the play that you requested
+requested-user-of = { $user }: here is the user that you requested
+requested-beatmapset = Here is the beatmapset you requested!
+requested-beatmap = Here is the beatmap you requested!
+requested-beatmap-pp = Here is the beatmap you requested! `{ $play }` is worth **{ $pp }pp**.
+save-no-plays = No plays found in this account! Play something first...!
+save-instructions = To set your osu username, please make your most recent play be the following map: `/b/{ $beatmap }` in **{ $mode }** mode! It does **not** have to be a pass, and **NF** can be used! Press **Done** within 5 minutes when you're done!
+save-done = Done
+save-not-played = Your most recent play is not on this map yet, try again once it is!
+save-success = user has been set to `{ $username }`
+servers-list = Private servers available with `--server`: { $servers }
+export-top = Top plays of { $user } ({ $mode })
+export-ranks = Server ranks ({ $mode })
+export-no-users = No saved users in the current server...
+
+## Announcements
+
+announce-top-record = New top record from { $user }!
+announce-leaderboard-record = New leaderboard record from { $user }!
+announce-set = { $announcement } (set { $date })
+announce-snipe = { $sniper } sniped { $victim } on { $map }!
+announce-snipe-new = { $sniper } took the first country #1 on { $map }!
+announce-twitch-live = { $user } is live playing { $game }!
+announce-news = New osu! news!
+announce-changelog = { $version } is out!
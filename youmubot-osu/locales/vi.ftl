@@ -0,0 +1,189 @@
+# The osu! module's messages, in Vietnamese.
+
+## Beatmap embeds
+
+beatmap-submitted = Đăng { $submitted } | Cập nhật lần cuối { $updated }
+beatmap-download = Tải về: [[Link]]({ $link }) [[Không video]]({ $link }?noVideo=1) [[Bloodcat]]({ $mirror }) [[Beatmapset]]({ $beatmapset })
+beatmap-language-genre = Ngôn ngữ: **{ $language }** | Thể loại: **{ $genre }**
+beatmap-source = Nguồn: **{ $source }**
+beatmap-tags = Tag: { $tags }
+beatmap-information = Thông tin
+beatmap-mods = **Mod**: { $mods }
+beatmap-pp-table = pp khi FC ({ $mode })
+beatmap-converted = [{ $difficulty }] (chuyển sang { $mode })
+beatmapset-too-many-diffs = Map này có { $count } độ khó, chỉ hiện { $shown } độ khó cuối.
+offline-difficulty = ({ $mode }) { $stars }⭐, combo tối đa { $combo }x
+offline-footer = { $count } độ khó, chưa đăng
+
+## Score embeds
+
+score-failed = trượt
+score-failed-at = trượt ở { $progress }%
+score-line-failed = { $accuracy }% { $combo } combo [{ $status }]
+score-line = { $accuracy }% { $combo }x { $misses } miss hạng { $rank }
+score-pp-if-fc = { $pp } ({ $fc }pp nếu FC?)
+score-pp-gained = **pp nhận được**: **{ $pp }**pp
+score-pp-gained-top = **pp nhận được**: **{ $pp }**pp (+**{ $effective }**pp | { $before }pp \➡️ { $after }pp)
+score-top-record = kỷ lục cá nhân #{ $rank }!
+score-world-record = #{ $rank } trên bảng xếp hạng toàn cầu!
+score-global-rank = Toàn cầu #{ $rank }
+score-replay = Replay
+score-by = bởi { $creator }
+score-played = **Chơi lúc**: { $date }
+score-stats = Thông số điểm
+score-lazer = (lazer)
+score-hits = 300 | 100 | 50 | miss
+score-hit-error = Sai số đánh
+score-hit-error-value = **{ $ur }** UR | trung bình **{ $average }**ms ({ $timing })
+score-early = sớm
+score-late = trễ
+score-map-stats = Thông số map
+score-stars-without-mods = Độ khó sao không tính đến mod.
+page-footer = Trang { $page }/{ $total }
+
+## User embeds
+
+user-member-since = Thành viên từ **{ $date }**
+user-member-since-badges = Thành viên từ **{ $date }**, **{ $count }** huy hiệu
+user-member-since-plain = Thành viên từ { $date }
+user-pp = Điểm pp
+user-inactive = Không hoạt động
+user-world-rank = Hạng thế giới
+user-country-rank = Hạng quốc gia
+user-rank = Hạng
+user-accuracy = Độ chính xác
+user-play-count = Số lượt chơi
+user-play-count-time = Số lượt chơi / Thời gian chơi
+user-play-count-time-value = { $count } / { $hours } giờ ({ $time })
+    **{ $hits }** lần đánh, combo tối đa **{ $combo }**x
+user-rank-history = Lịch sử hạng (90 ngày)
+user-rank-history-peak = (cao nhất **#{ $peak }**)
+user-ranks = Xếp loại
+user-map-length = Độ dài map trung bình
+user-map-length-value = **{ $minutes }** phút **{ $seconds }** giây (**{ $total }**s)
+user-level = Cấp { $level }
+user-scores = **{ $total }** tổng điểm, **{ $ranked }** điểm xếp hạng
+user-best-record = Kỷ lục tốt nhất
+user-best-record-on = trên
+
+## osu!track updates
+
+osutrack-first = Đây là lần đầu osu!track thấy người chơi này: các thay đổi sẽ được hiển thị từ lần cập nhật sau.
+osutrack-level = Cấp
+osutrack-new-highscores = Kỷ lục cá nhân mới ({ $count })
+osutrack-new-highscore = #{ $position }: **{ $pp }pp** ({ $grade }) trên [/b/{ $beatmap }](https://osu.ppy.sh/b/{ $beatmap })
+osutrack-footer = Thay đổi từ lần cập nhật trước, theo osu!track
+osutrack-not-found = osu!track không tìm thấy người chơi này.
+
+## Snipes
+
+snipes-untracked-country = huismetbenen không theo dõi người chơi của { $country }, xin lỗi!
+snipes-none = { $user } chưa có hạng #1 quốc gia nào ở { $country }.
+snipes-count = Hạng #1 quốc gia ({ $country })
+snipes-average-pp = pp trung bình
+snipes-average-stars = Độ khó trung bình
+
+## Daily challenge
+
+daily-none = Hiện không có daily challenge nào.
+daily-challenge = Daily challenge hôm nay là { $room }, đã có **{ $participants }** người tham gia! Kết thúc { $ends }.
+daily-no-scores = Chưa ghi nhận điểm daily challenge nào của thành viên server này.
+
+## News
+
+news-none = Hiện không có tin tức nào.
+news-footer = Tin tức osu!
+news-published = Đăng
+news-changelog-footer = Changelog osu!
+news-released = Phát hành
+news-changelog-more = …và { $count } thay đổi khác.
+news-kind-news = tin tức osu!
+news-kind-changelog = changelog của lazer
+news-kind-all = tin tức osu! và changelog của lazer
+news-subscribed = Từ giờ, { $what } sẽ được đăng vào { $channel }.
+news-subscription = { $what } được đăng vào { $channel }.
+news-unsubscribed = Tin tức osu! và changelog của lazer sẽ không còn được đăng trong server này.
+news-not-subscribed = Server này chưa đăng ký nhận tin tức osu!.
+
+## Wiki
+
+wiki-not-found = Không tìm thấy bài viết nào trên osu! wiki cho { $query }.
+wiki-footer = osu! wiki
+wiki-not-translated = Chưa được dịch, hiển thị bằng tiếng Anh
+
+## Collections
+
+collection-invalid-link = Đó không phải là link tới một collection trên osu!collector.
+collection-not-found = Collection này không tồn tại trên osu!collector.
+collection-import = Đây là { $name } dưới dạng `collection.db` và `.osdb`. Hãy import một trong hai bằng Collection Manager, hoặc đặt `collection.db` vào thư mục osu!. Cẩn thận, nó sẽ thay thế các collection của bạn!
+collection-by = bởi { $user }
+collection-footer = osu!collector • { $count } beatmap • { $favourites } lượt thích • trang { $page }/{ $pages }
+
+## Matches
+
+match-invalid-link = Đó không phải là link tới một trận multiplayer osu!.
+match-not-found = Trận này không tồn tại, hoặc lịch sử của nó đã bị xoá.
+
+## Google Sheets
+
+sheet-not-set = Server này chưa có Google Sheet, hãy đặt bằng `export sheet set`.
+sheet-status = Dữ liệu xuất được gửi tới { $link }, ghi bởi `{ $account }`.
+sheet-set = { $user }, dữ liệu xuất bằng `--sheet` sẽ được gửi tới { $link }. Khoá đã được đọc, và tin nhắn chứa nó đã bị xoá.
+sheet-set-key-kept = { $user }, dữ liệu xuất bằng `--sheet` sẽ được gửi tới { $link }.
+sheet-key-not-deleted = Không xoá được tin nhắn chứa khoá. Hãy tự xoá nó, và nên tạo một khoá mới, vì mọi người trong kênh này đều có thể đọc được.
+sheet-removed = Dữ liệu xuất sẽ không còn được gửi tới Google Sheet, và khoá đã bị xoá.
+sheet-invalid-link = Đó không phải là link tới một Google Sheet.
+sheet-no-key = Hãy đính kèm khoá JSON của service account.
+sheet-no-access = Không mở được sheet. Sheet đã được chia sẻ với `{ $account }` với quyền chỉnh sửa chưa?
+sheet-guild-only = Chỉ có thể gửi dữ liệu xuất trong server tới Google Sheet của server đó.
+sheet-pushed = Đã gửi tới Google Sheet: <{ $link }>
+
+## Twitch
+
+twitch-not-configured = Bot chưa được thiết lập Twitch, xin lỗi!
+twitch-channel-not-found = Không có kênh Twitch nào tên `{ $login }`.
+twitch-linked = Kênh Twitch của bạn giờ là { $channel }. Các buổi stream osu! của bạn sẽ được thông báo!
+twitch-unlinked = Đã bỏ liên kết kênh Twitch của bạn.
+twitch-none = Bạn chưa liên kết kênh Twitch nào.
+twitch-show = Kênh Twitch của { $user } là { $channel }.
+twitch-show-none = { $user } chưa liên kết kênh Twitch nào.
+twitch-game = Đang chơi
+twitch-no-game = không có gì cụ thể
+twitch-viewers = Người xem
+twitch-started = Bắt đầu
+
+## Command replies
+
+user-not-found = 🔍 không tìm thấy người chơi!
+no-saved-account = Chưa lưu tài khoản osu! nào
+no-such-play = Không có lượt chơi này
+no-plays = Không có lượt chơi nào
+no-scores = Không có điểm nào
+no-beatmap-queried = Chưa có beatmap nào được tra cứu trong kênh này.
+beatmaps-queried = Các beatmap đã tra cứu trong kênh này
+requested-play = Đây là lượt chơi bạn yêu cầu
+requested-play-of = { $user }: đây là lượt chơi bạn yêu cầu
+requested-user-of = { $user }: đây là người chơi bạn yêu cầu
+requested-beatmapset = Đây là beatmapset bạn yêu cầu!
+requested-beatmap = Đây là beatmap bạn yêu cầu!
+requested-beatmap-pp = Đây là beatmap bạn yêu cầu! `{ $play }` đáng giá **{ $pp }pp**.
+save-no-plays = Tài khoản này chưa có lượt chơi nào! Hãy chơi gì đó trước...!
+save-instructions = Để lưu tên osu! của bạn, hãy chơi map sau ở chế độ **{ $mode }**: `/b/{ $beatmap }`, để nó là lượt chơi gần nhất của bạn! **Không** cần phải qua map, và có thể dùng **NF**! Nhấn **Xong** trong vòng 5 phút khi bạn đã chơi xong!
+save-done = Xong
+save-not-played = Lượt chơi gần nhất của bạn chưa phải map này, hãy thử lại khi đã chơi xong!
+save-success = đã lưu tên người chơi là `{ $username }`
+servers-list = Các server riêng có thể chọn bằng `--server`: { $servers }
+export-top = Top play của { $user } ({ $mode })
+export-ranks = Bảng xếp hạng server ({ $mode })
+export-no-users = Chưa có người chơi nào được lưu trong server này...
+
+## Announcements
+
+announce-top-record = Kỷ lục cá nhân mới của { $user }!
+announce-leaderboard-record = Kỷ lục bảng xếp hạng mới của { $user }!
+announce-set = { $announcement } (lập { $date })
+announce-snipe = { $sniper } đã cướp hạng #1 của { $victim } trên { $map }!
+announce-snipe-new = { $sniper } đã lập hạng #1 quốc gia đầu tiên trên { $map }!
+announce-twitch-live = { $user } đang stream { $game }!
+announce-news = Có tin tức osu! mới!
+announce-changelog = { $version } đã ra mắt!
@@ -0,0 +1,410 @@
+//! Requests to the osu! API endpoints that rosu-v2 does not cover.
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{ClientError, Credentials};
+
+const API_URL: &str = "https://osu.ppy.sh/api/v2";
+const TOKEN_URL: &str = "https://osu.ppy.sh/oauth/token";
+
+/// A client for the raw osu! API, with its own token.
+pub(crate) struct RawApi {
+    http: reqwest::Client,
+    credentials: Arc<Credentials>,
+    token: tokio::sync::Mutex<Option<Token>>,
+}
+
+struct Token {
+    access_token: String,
+    expires_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+impl RawApi {
+    pub(crate) fn new(credentials: Arc<Credentials>, timeout: Duration) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .expect("a reqwest client with a timeout should build"),
+            credentials,
+            token: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// Forget the current token, so that a new one is requested next time.
+    pub(crate) async fn reset_token(&self) {
+        *self.token.lock().await = None;
+    }
+
+    async fn token(&self) -> Result<String, ClientError> {
+        let mut token = self.token.lock().await;
+        if let Some(t) = token.as_ref().filter(|t| t.expires_at > Instant::now()) {
+            return Ok(t.access_token.clone());
+        }
+        let resp = self
+            .http
+            .post(TOKEN_URL)
+            .form(&[
+                ("client_id", self.credentials.client_id.to_string()),
+                ("client_secret", self.credentials.client_secret.clone()),
+                ("grant_type", "client_credentials".to_owned()),
+                ("scope", "public".to_owned()),
+            ])
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(ClientError::AuthExpired);
+        }
+        let body = resp.text().await?;
+        let t: TokenResponse =
+            serde_json::from_str(&body).map_err(|_| ClientError::Deserialize { body })?;
+        // Renew the token a minute early, to not use it as it expires.
+        let expires_in = Duration::from_secs(t.expires_in.saturating_sub(60));
+        *token = Some(Token {
+            access_token: t.access_token.clone(),
+            expires_at: Instant::now() + expires_in,
+        });
+        Ok(t.access_token)
+    }
+
+    /// Send a GET request to the given API path, e.g. `rooms/1234`.
+    pub(crate) async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, ClientError> {
+        let token = self.token().await?;
+        let resp = self
+            .http
+            .get(format!("{}/{}", API_URL, path))
+            .bearer_auth(token)
+            .header("x-api-version", "20220705")
+            .query(query)
+            .send()
+            .await?;
+        match resp.status().as_u16() {
+            404 => return Err(ClientError::NotFound),
+            401 => return Err(ClientError::AuthExpired),
+            429 => {
+                let retry_after = resp
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse().ok())
+                    .map(Duration::from_secs);
+                return Err(ClientError::RateLimited { retry_after });
+            }
+            status if status >= 500 => return Err(ClientError::Server { status }),
+            _ => (),
+        }
+        let body = resp.error_for_status()?.text().await?;
+        serde_json::from_str(&body).map_err(|_| ClientError::Deserialize { body })
+    }
+}
+
+/// The shapes of the raw API responses.
+pub(crate) mod raw {
+    use chrono::{DateTime, Utc};
+    use serde::Deserialize;
+
+    use crate::models::beatmapset_events::{BeatmapsetEvent, BeatmapsetEventKind, BeatmapsetInfo};
+    use crate::models::multiplayer::{PlaylistItem, PlaylistScore, Room, RoomCategory};
+    use crate::models::news::{ChangelogBuild, ChangelogEntry, NewsPost};
+    use crate::models::wiki::WikiPage;
+    use crate::models::{Mods, Rank};
+
+    #[derive(Deserialize)]
+    pub(crate) struct RawRoom {
+        id: u64,
+        name: String,
+        category: RoomCategory,
+        host: Option<RawUser>,
+        starts_at: DateTime<Utc>,
+        ends_at: Option<DateTime<Utc>>,
+        #[serde(default)]
+        participant_count: u64,
+        #[serde(default)]
+        playlist: Vec<RawPlaylistItem>,
+    }
+
+    #[derive(Deserialize)]
+    struct RawUser {
+        id: u64,
+        username: String,
+    }
+
+    #[derive(Deserialize)]
+    struct RawMod {
+        acronym: String,
+    }
+
+    fn mods(mods: &[RawMod]) -> Mods {
+        Mods::from_acronyms(mods.iter().map(|m| m.acronym.as_str()))
+    }
+
+    #[derive(Deserialize)]
+    struct RawPlaylistItem {
+        id: u64,
+        beatmap_id: u64,
+        ruleset_id: u8,
+        #[serde(default)]
+        required_mods: Vec<RawMod>,
+        #[serde(default)]
+        expired: bool,
+    }
+
+    #[derive(Deserialize)]
+    pub(crate) struct RawPlaylistScores {
+        pub(crate) scores: Vec<RawPlaylistScore>,
+    }
+
+    #[derive(Deserialize)]
+    pub(crate) struct RawPlaylistScore {
+        id: u64,
+        user_id: u64,
+        user: Option<RawUser>,
+        position: Option<u32>,
+        total_score: u64,
+        accuracy: f64,
+        max_combo: u64,
+        #[serde(default)]
+        mods: Vec<RawMod>,
+        rank: String,
+        passed: bool,
+        pp: Option<f64>,
+        ended_at: DateTime<Utc>,
+        #[serde(default)]
+        statistics: RawStatistics,
+    }
+
+    #[derive(Deserialize, Default)]
+    struct RawStatistics {
+        #[serde(default)]
+        great: u64,
+        #[serde(default)]
+        ok: u64,
+        #[serde(default)]
+        meh: u64,
+        #[serde(default)]
+        miss: u64,
+    }
+
+    #[derive(Deserialize)]
+    pub(crate) struct RawBeatmapsetEvents {
+        pub(crate) events: Vec<RawBeatmapsetEvent>,
+    }
+
+    #[derive(Deserialize)]
+    pub(crate) struct RawBeatmapsetEvent {
+        pub(crate) id: u64,
+        #[serde(rename = "type")]
+        kind: BeatmapsetEventKind,
+        created_at: DateTime<Utc>,
+        user_id: Option<u64>,
+        // Missing if the beatmapset was deleted.
+        beatmapset: Option<BeatmapsetInfo>,
+    }
+
+    impl RawBeatmapsetEvent {
+        pub(crate) fn into_event(self) -> Option<BeatmapsetEvent> {
+            Some(BeatmapsetEvent {
+                id: self.id,
+                kind: self.kind,
+                created_at: self.created_at,
+                user_id: self.user_id,
+                beatmapset: self.beatmapset?,
+            })
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub(crate) struct RawNews {
+        pub(crate) news_posts: Vec<RawNewsPost>,
+    }
+
+    #[derive(Deserialize)]
+    pub(crate) struct RawNewsPost {
+        id: u64,
+        title: String,
+        slug: String,
+        author: String,
+        published_at: DateTime<Utc>,
+        preview: Option<String>,
+        first_image: Option<String>,
+    }
+
+    impl From<RawNewsPost> for NewsPost {
+        fn from(p: RawNewsPost) -> Self {
+            Self {
+                id: p.id,
+                title: p.title,
+                slug: p.slug,
+                author: p.author,
+                published_at: p.published_at,
+                preview: p.preview.filter(|v| !v.is_empty()),
+                // Images hosted on the website are linked without the host.
+                first_image: p.first_image.map(|i| {
+                    if i.starts_with('/') {
+                        format!("https://osu.ppy.sh{}", i)
+                    } else {
+                        i
+                    }
+                }),
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub(crate) struct RawChangelog {
+        pub(crate) builds: Vec<RawBuild>,
+    }
+
+    #[derive(Deserialize)]
+    pub(crate) struct RawBuild {
+        id: u64,
+        version: String,
+        created_at: DateTime<Utc>,
+        update_stream: RawUpdateStream,
+        #[serde(default)]
+        changelog_entries: Vec<RawChangelogEntry>,
+    }
+
+    #[derive(Deserialize)]
+    struct RawUpdateStream {
+        name: String,
+        display_name: String,
+    }
+
+    #[derive(Deserialize)]
+    struct RawChangelogEntry {
+        #[serde(default)]
+        category: String,
+        title: Option<String>,
+        #[serde(default)]
+        major: bool,
+        url: Option<String>,
+    }
+
+    impl From<RawBuild> for ChangelogBuild {
+        fn from(b: RawBuild) -> Self {
+            Self {
+                id: b.id,
+                version: b.version,
+                stream: b.update_stream.name,
+                stream_name: b.update_stream.display_name,
+                created_at: b.created_at,
+                entries: b
+                    .changelog_entries
+                    .into_iter()
+                    .filter_map(|e| {
+                        Some(ChangelogEntry {
+                            category: e.category,
+                            title: e.title?,
+                            major: e.major,
+                            url: e.url,
+                        })
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub(crate) struct RawWikiSearch {
+        pub(crate) wiki_page: Option<RawSearchResults<RawWikiPage>>,
+    }
+
+    #[derive(Deserialize)]
+    pub(crate) struct RawSearchResults<T> {
+        pub(crate) data: Vec<T>,
+    }
+
+    #[derive(Deserialize)]
+    pub(crate) struct RawWikiPage {
+        title: String,
+        subtitle: Option<String>,
+        path: String,
+        locale: String,
+        #[serde(default)]
+        available_locales: Vec<String>,
+        #[serde(default)]
+        markdown: String,
+    }
+
+    impl From<RawWikiPage> for WikiPage {
+        fn from(p: RawWikiPage) -> Self {
+            Self {
+                title: p.title,
+                subtitle: p.subtitle.filter(|v| !v.is_empty()),
+                path: p.path,
+                locale: p.locale,
+                available_locales: p.available_locales,
+                markdown: p.markdown,
+            }
+        }
+    }
+
+    impl From<RawRoom> for Room {
+        fn from(r: RawRoom) -> Self {
+            Self {
+                id: r.id,
+                name: r.name,
+                category: r.category,
+                host: r.host.map(|u| u.username),
+                starts_at: r.starts_at,
+                ends_at: r.ends_at,
+                participant_count: r.participant_count,
+                playlist: r
+                    .playlist
+                    .into_iter()
+                    .map(|i| PlaylistItem {
+                        id: i.id,
+                        beatmap_id: i.beatmap_id,
+                        mode: i.ruleset_id.into(),
+                        required_mods: mods(&i.required_mods),
+                        expired: i.expired,
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    impl From<RawPlaylistScore> for PlaylistScore {
+        fn from(s: RawPlaylistScore) -> Self {
+            Self {
+                id: s.id,
+                user_id: s.user_id,
+                username: s
+                    .user
+                    .filter(|u| u.id == s.user_id)
+                    .map(|u| u.username)
+                    .unwrap_or_else(|| s.user_id.to_string()),
+                position: s.position,
+                total_score: s.total_score,
+                accuracy: s.accuracy * 100.0,
+                max_combo: s.max_combo,
+                mods: mods(&s.mods),
+                rank: if s.passed {
+                    s.rank.parse().unwrap_or(Rank::F)
+                } else {
+                    Rank::F
+                },
+                passed: s.passed,
+                pp: s.pp,
+                ended_at: s.ended_at,
+                count_300: s.statistics.great,
+                count_100: s.statistics.ok,
+                count_50: s.statistics.meh,
+                count_miss: s.statistics.miss,
+            }
+        }
+    }
+}
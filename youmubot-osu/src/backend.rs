@@ -0,0 +1,77 @@
+//! The backend sending the requests of the [Client](crate::Client) to the osu! API.
+//!
+//! The client handles rate limiting, retries and metrics on top of its backend.
+//! Tests can build a client on another backend with [Client::with_backend](crate::Client::with_backend).
+use std::sync::{Arc, RwLock};
+
+use youmubot_prelude::async_trait;
+
+use crate::{
+    models::{Beatmap, Score, User},
+    request::builders::*,
+    ClientError,
+};
+
+/// The requests most commands are built on, one API request per call.
+#[async_trait]
+pub trait OsuApi: Send + Sync {
+    /// Fetch a beatmap, the beatmaps of a beatmapset, or a beatmap by its hash.
+    async fn beatmaps(&self, request: BeatmapRequestBuilder) -> Result<Vec<Beatmap>, ClientError>;
+
+    /// Fetch an user, with their recent events.
+    async fn user(&self, request: UserRequestBuilder) -> Result<Option<User>, ClientError>;
+
+    /// Fetch the scores on a beatmap.
+    async fn scores(&self, request: ScoreRequestBuilder) -> Result<Vec<Score>, ClientError>;
+
+    /// Fetch the recent or best scores of an user.
+    async fn user_scores(
+        &self,
+        request: UserScoreRequestBuilder,
+    ) -> Result<Vec<Score>, ClientError>;
+}
+
+/// The osu! API, through rosu-v2.
+pub(crate) struct RosuApi {
+    osu: RwLock<Arc<rosu_v2::Osu>>,
+}
+
+impl RosuApi {
+    pub(crate) fn new(osu: rosu_v2::Osu) -> Self {
+        Self {
+            osu: RwLock::new(Arc::new(osu)),
+        }
+    }
+
+    /// The rosu-v2 client, for the endpoints outside of [OsuApi].
+    pub(crate) fn osu(&self) -> Arc<rosu_v2::Osu> {
+        self.osu.read().unwrap().clone()
+    }
+
+    /// Replace the rosu-v2 client, e.g. with a new token.
+    pub(crate) fn replace(&self, osu: rosu_v2::Osu) {
+        *self.osu.write().unwrap() = Arc::new(osu);
+    }
+}
+
+#[async_trait]
+impl OsuApi for RosuApi {
+    async fn beatmaps(&self, request: BeatmapRequestBuilder) -> Result<Vec<Beatmap>, ClientError> {
+        request.build(&self.osu()).await
+    }
+
+    async fn user(&self, request: UserRequestBuilder) -> Result<Option<User>, ClientError> {
+        request.build(&self.osu()).await
+    }
+
+    async fn scores(&self, request: ScoreRequestBuilder) -> Result<Vec<Score>, ClientError> {
+        request.build(&self.osu()).await
+    }
+
+    async fn user_scores(
+        &self,
+        request: UserScoreRequestBuilder,
+    ) -> Result<Vec<Score>, ClientError> {
+        request.build(&self.osu()).await
+    }
+}
@@ -0,0 +1,206 @@
+//! Writing of `collection.db` files, which osu! (stable) and Collection Manager can import,
+//! and of `.osdb` files, Collection Manager's own format.
+//!
+//! See https://github.com/ppy/osu/wiki/Legacy-database-file-structure#collectiondb for the `collection.db` format,
+//! and https://github.com/Piotrekol/CollectionManager for the `.osdb` format.
+use std::io::Write;
+
+use chrono::{DateTime, Utc};
+use flate2::{write::GzEncoder, Compression};
+
+use crate::models::Mode;
+
+/// The version of osu! written to the files.
+const VERSION: i32 = 20150203;
+/// The version of the `.osdb` files: the 8th, without the artists, titles and difficulty names.
+const OSDB_VERSION: &str = "o!dm8min";
+/// The editor written to the `.osdb` files.
+const OSDB_EDITOR: &str = "youmubot";
+/// The end of the `.osdb` files.
+const OSDB_FOOTER: &str = "By Piotrekol";
+
+/// A named list of beatmaps.
+#[derive(Debug, Clone)]
+pub struct Collection {
+    pub name: String,
+    pub beatmaps: Vec<CollectionEntry>,
+}
+
+/// A beatmap in a collection.
+#[derive(Debug, Clone)]
+pub struct CollectionEntry {
+    pub beatmap_id: u64,
+    pub beatmapset_id: u64,
+    /// The MD5 hash of the `.osu` file.
+    pub hash: String,
+    /// The mode and star rating, if they are known.
+    pub difficulty: Option<(Mode, f64)>,
+}
+
+/// The content of a `collection.db` with the collections.
+pub fn to_collection_db(collections: &[Collection]) -> Vec<u8> {
+    let mut w = Writer(Vec::new());
+    w.i32(VERSION);
+    w.i32(collections.len() as i32);
+    for c in collections {
+        w.string(&c.name);
+        w.i32(c.beatmaps.len() as i32);
+        for b in &c.beatmaps {
+            w.string(&b.hash);
+        }
+    }
+    w.0
+}
+
+/// The content of an `.osdb` with the collections, saved at `date`.
+///
+/// The file is the version, then the gzipped content, which starts with the version again.
+pub fn to_osdb(collections: &[Collection], date: DateTime<Utc>) -> Vec<u8> {
+    let mut w = Writer(Vec::new());
+    w.dotnet_string(OSDB_VERSION);
+    w.f64(ole_date(date));
+    w.dotnet_string(OSDB_EDITOR);
+    w.i32(collections.len() as i32);
+    for c in collections {
+        w.dotnet_string(&c.name);
+        // Not uploaded to osustats.
+        w.i32(-1);
+        w.i32(c.beatmaps.len() as i32);
+        for b in &c.beatmaps {
+            let (mode, stars) = b.difficulty.unwrap_or((Mode::Std, 0.0));
+            w.i32(b.beatmap_id as i32);
+            w.i32(b.beatmapset_id as i32);
+            w.dotnet_string(&b.hash);
+            // The user's comment.
+            w.dotnet_string("");
+            w.0.push(mode as u8);
+            w.f64(stars);
+        }
+        // The beatmaps only known by their hashes.
+        w.i32(0);
+    }
+    w.dotnet_string(OSDB_FOOTER);
+
+    let mut out = Writer(Vec::new());
+    out.dotnet_string(OSDB_VERSION);
+    let mut gz = GzEncoder::new(out.0, Compression::default());
+    gz.write_all(&w.0)
+        .and_then(|_| gz.finish())
+        .expect("writing to memory cannot fail")
+}
+
+/// The date as an OLE Automation date, the number of days since 1899-12-30, as .NET stores them.
+fn ole_date(date: DateTime<Utc>) -> f64 {
+    /// The days between 1899-12-30 and the Unix epoch.
+    const UNIX_EPOCH_DAYS: f64 = 25569.0;
+    UNIX_EPOCH_DAYS + date.timestamp_millis() as f64 / (24.0 * 60.0 * 60.0 * 1000.0)
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn i32(&mut self, v: i32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn f64(&mut self, v: f64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn uleb128(&mut self, mut v: usize) {
+        loop {
+            let b = (v & 0x7f) as u8;
+            v >>= 7;
+            if v == 0 {
+                self.0.push(b);
+                return;
+            }
+            self.0.push(b | 0x80);
+        }
+    }
+    fn string(&mut self, s: &str) {
+        if s.is_empty() {
+            self.0.push(0x00);
+            return;
+        }
+        self.0.push(0x0b);
+        self.uleb128(s.len());
+        self.0.extend_from_slice(s.as_bytes());
+    }
+    /// A string as .NET's `BinaryWriter` writes them, without osu!'s marker byte.
+    fn dotnet_string(&mut self, s: &str) {
+        self.uleb128(s.len());
+        self.0.extend_from_slice(s.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use chrono::TimeZone;
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    fn entry(hash: &str) -> CollectionEntry {
+        CollectionEntry {
+            beatmap_id: 1,
+            beatmapset_id: 2,
+            hash: hash.to_owned(),
+            difficulty: None,
+        }
+    }
+
+    #[test]
+    fn test_to_collection_db() {
+        let db = to_collection_db(&[Collection {
+            name: "a".repeat(200),
+            beatmaps: vec![entry("abc"), entry("")],
+        }]);
+        let mut expected = vec![];
+        expected.extend_from_slice(&VERSION.to_le_bytes());
+        expected.extend_from_slice(&1i32.to_le_bytes());
+        // 200 = 0b1_1001000
+        expected.extend_from_slice(&[0x0b, 0xc8, 0x01]);
+        expected.extend_from_slice("a".repeat(200).as_bytes());
+        expected.extend_from_slice(&2i32.to_le_bytes());
+        expected.extend_from_slice(&[0x0b, 0x03, b'a', b'b', b'c', 0x00]);
+        assert_eq!(db, expected);
+    }
+
+    #[test]
+    fn test_to_osdb() {
+        let date = Utc.with_ymd_and_hms(1970, 1, 2, 0, 0, 0).unwrap();
+        let osdb = to_osdb(
+            &[Collection {
+                name: "c".to_owned(),
+                beatmaps: vec![CollectionEntry {
+                    difficulty: Some((Mode::Mania, 4.5)),
+                    ..entry("abc")
+                }],
+            }],
+            date,
+        );
+        assert_eq!(&osdb[..9], b"\x08o!dm8min");
+        let mut content = vec![];
+        GzDecoder::new(&osdb[9..])
+            .read_to_end(&mut content)
+            .unwrap();
+
+        let mut expected = vec![];
+        expected.extend_from_slice(b"\x08o!dm8min");
+        expected.extend_from_slice(&25570.0f64.to_le_bytes());
+        expected.extend_from_slice(b"\x08youmubot");
+        expected.extend_from_slice(&1i32.to_le_bytes());
+        expected.extend_from_slice(b"\x01c");
+        expected.extend_from_slice(&(-1i32).to_le_bytes());
+        expected.extend_from_slice(&1i32.to_le_bytes());
+        expected.extend_from_slice(&1i32.to_le_bytes());
+        expected.extend_from_slice(&2i32.to_le_bytes());
+        expected.extend_from_slice(b"\x03abc\x00");
+        expected.push(3);
+        expected.extend_from_slice(&4.5f64.to_le_bytes());
+        expected.extend_from_slice(&0i32.to_le_bytes());
+        expected.extend_from_slice(b"\x0cBy Piotrekol");
+        assert_eq!(content, expected);
+    }
+}
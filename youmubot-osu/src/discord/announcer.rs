@@ -1,34 +1,43 @@
 use std::{convert::TryInto, sync::Arc};
 
 use serenity::builder::CreateMessage;
-use serenity::{
-    http::CacheHttp,
-    model::{
-        channel::Message,
-        id::{ChannelId, UserId},
-    },
+use serenity::model::{
+    channel::Message,
+    id::{ChannelId, GuildId, UserId},
 };
 
 use announcer::MemberToChannels;
+use tracing::Instrument;
+use youmubot_db_sql::models::osu as models;
 use youmubot_prelude::announcer::CacheAndHttp;
+use youmubot_prelude::metrics::Metric;
 use youmubot_prelude::stream::TryStreamExt;
 use youmubot_prelude::*;
 
 use crate::{
-    discord::cache::save_beatmap,
+    discord::cache::publish_beatmap,
     discord::oppai_cache::BeatmapContent,
-    models::{Mode, Score, User, UserEventRank},
+    models::{DiscordTimestamp, Mode, Score, User, UserEventRank},
     request::UserID,
-    Client as Osu,
+    Client as Osu, ClientError,
 };
 
 use super::db::{OsuSavedUsers, OsuUser};
-use super::{calculate_weighted_map_length, OsuEnv};
 use super::{embeds::score_embed, BeatmapWithMode};
+use super::{score_card_attachment, OsuEnv};
 
 /// osu! announcer's unique announcer key.
 pub const ANNOUNCER_KEY: &str = "osu";
 
+/// How long after being set scores are announced.
+const ANNOUNCEMENT_LAG: Metric = Metric::histogram(
+    "youmubot_osu_announcement_lag_seconds",
+    "How long after being set osu! scores are announced.",
+);
+
+/// Users whose lookups keep failing more than this many times in a row are no longer updated.
+const MAX_FAILURES: u8 = 5;
+
 /// The announcer struct implementing youmubot_prelude::Announcer
 pub struct Announcer {
     client: Arc<Osu>,
@@ -49,65 +58,34 @@ impl youmubot_prelude::Announcer for Announcer {
         channels: MemberToChannels,
     ) -> Result<()> {
         // For each user...
-        let users = {
+        let (users, shutdown) = {
             let env = d.read().await.get::<OsuEnv>().unwrap().clone();
-            env.saved_users.all().await?
+            (env.saved_users.all().await?, env.prelude.shutdown.clone())
         };
         let now = chrono::Utc::now();
+        let ctx = Context { c, data: d };
+        let s = &*self;
         users
             .into_iter()
-            .map(|mut osu_user| {
-                let user_id = osu_user.user_id;
+            .filter(|u| u.failures <= MAX_FAILURES)
+            .map(|osu_user| {
+                let ctx = &ctx;
                 let channels = &channels;
-                let ctx = Context {
-                    c: c.clone(),
-                    data: d.clone(),
-                };
-                let s = &*self;
+                let shutdown = &shutdown;
+                let span = tracing::info_span!(
+                    "osu_user",
+                    user_id = osu_user.user_id.get(),
+                    osu_id = osu_user.id
+                );
                 async move {
-                    let channels = channels.channels_of(ctx.c.clone(), user_id).await;
-                    if channels.is_empty() {
-                        return; // We don't wanna update an user without any active server
+                    // Users not started yet are left for the next sweep after a restart.
+                    if shutdown.is_shutting_down() {
+                        return;
                     }
-                    match [Mode::Std, Mode::Taiko, Mode::Catch, Mode::Mania]
-                        .into_iter()
-                        .map(|m| {
-                            s.handle_user_mode(&ctx, now, &osu_user, user_id, channels.clone(), m)
-                        })
-                        .collect::<stream::FuturesOrdered<_>>()
-                        .try_collect::<Vec<_>>()
-                        .await
-                    {
-                        Ok(v) => {
-                            osu_user.pp = v
-                                .iter()
-                                .map(|u| u.pp)
-                                .collect::<Vec<_>>()
-                                .try_into()
-                                .unwrap();
-                            osu_user.username = v.into_iter().next().unwrap().username.into();
-                            osu_user.last_update = now;
-                            osu_user.std_weighted_map_length =
-                                Self::std_weighted_map_length(&ctx, &osu_user)
-                                    .await
-                                    .pls_ok();
-                            let id = osu_user.id;
-                            println!("{:?}", osu_user);
-                            ctx.data
-                                .read()
-                                .await
-                                .get::<OsuSavedUsers>()
-                                .unwrap()
-                                .save(osu_user)
-                                .await
-                                .pls_ok();
-                            println!("updating {} done", id);
-                        }
-                        Err(e) => {
-                            eprintln!("osu: Cannot update {}: {}", osu_user.id, e);
-                        }
-                    };
+                    // Errors are logged by `update_user`.
+                    s.update_user(ctx, now, osu_user, channels).await.ok();
                 }
+                .instrument(span)
             })
             .collect::<stream::FuturesUnordered<_>>()
             .collect::<()>()
@@ -116,6 +94,98 @@ impl youmubot_prelude::Announcer for Announcer {
     }
 }
 
+impl Announcer {
+    /// Poll a single user right away, as a sweep would.
+    /// Returns whether the user was updated: users in no server with the announcer are skipped.
+    pub(crate) async fn poll_user(
+        &self,
+        ctx: &serenity::prelude::Context,
+        osu_user: OsuUser,
+    ) -> Result<bool> {
+        let channels = MemberToChannels::of(&ctx.data, ANNOUNCER_KEY).await?;
+        let ctx = Context {
+            c: CacheAndHttp::from_context(ctx),
+            data: ctx.data.clone(),
+        };
+        let span = tracing::info_span!(
+            "osu_user",
+            user_id = osu_user.user_id.get(),
+            osu_id = osu_user.id
+        );
+        self.update_user(&ctx, chrono::Utc::now(), osu_user, &channels)
+            .instrument(span)
+            .await
+    }
+
+    /// Announce the new scores of the user in all modes, and save their new stats.
+    /// Returns whether the user was updated: users in no server with the announcer are skipped.
+    async fn update_user(
+        &self,
+        ctx: &Context,
+        now: chrono::DateTime<chrono::Utc>,
+        mut osu_user: OsuUser,
+        channels: &MemberToChannels,
+    ) -> Result<bool> {
+        let user_id = osu_user.user_id;
+        let channels = channels.channels_of(ctx.c.clone(), user_id).await;
+        if channels.is_empty() {
+            return Ok(false); // We don't wanna update an user without any active server
+        }
+        let saved_users = ctx
+            .data
+            .read()
+            .await
+            .get::<OsuSavedUsers>()
+            .unwrap()
+            .clone();
+        match [Mode::Std, Mode::Taiko, Mode::Catch, Mode::Mania]
+            .into_iter()
+            .map(|m| self.handle_user_mode(ctx, now, &osu_user, user_id, channels.clone(), m))
+            .collect::<stream::FuturesOrdered<_>>()
+            .try_collect::<Vec<_>>()
+            .await
+        {
+            Ok(v) => {
+                osu_user.pp = v
+                    .iter()
+                    .map(|u| u.pp)
+                    .collect::<Vec<_>>()
+                    .try_into()
+                    .unwrap();
+                osu_user.username = v[0].username.clone().into();
+                osu_user.last_update = now;
+                osu_user.failures = 0;
+                osu_user.std_weighted_map_length =
+                    Self::std_weighted_map_length(ctx, &osu_user).await.pls_ok();
+                Self::record_stats_snapshots(ctx, &osu_user, &v).await;
+                tracing::debug!("{:?}", osu_user);
+                saved_users.save(osu_user).await.pls_ok();
+                tracing::info!("user updated");
+                Ok(true)
+            }
+            Err(e) => {
+                // Look through the chain, as errors shared by concurrent requests wrap the original.
+                match e.chain().find_map(|e| e.downcast_ref::<ClientError>()) {
+                    Some(ClientError::Restricted | ClientError::NotFound) => {
+                        osu_user.failures += 1;
+                        tracing::warn!(
+                            failures = osu_user.failures,
+                            "osu: user is restricted or gone"
+                        );
+                        saved_users.save(osu_user).await.pls_ok();
+                    }
+                    Some(ClientError::RateLimited { .. }) => {
+                        // Not the user's fault, they will be updated on the next run.
+                        tracing::warn!("osu: rate limited while updating the user");
+                    }
+                    _ => tracing::error!("osu: cannot update the user: {}", e),
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
 impl Announcer {
     /// Handles an user/mode scan, announces all possible new scores, return the new pp value.
     async fn handle_user_mode(
@@ -138,13 +208,20 @@ impl Announcer {
                         .event_days(days_since_last_update.min(31) as u8)
                 })
                 .await?
-                .ok_or_else(|| Error::msg("user not found"))?;
+                .ok_or(ClientError::Restricted)?;
             (scores, user)
         };
+        let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+        Self::record_plays(&env, osu_user, mode, &scores, &user)
+            .await
+            .pls_ok();
         let client = self.client.clone();
         let ctx = ctx.clone();
         let _user = user.clone();
+        // The announcements are sent even if shutdown starts, as the user is marked as updated.
+        let work = env.prelude.shutdown.work();
         spawn_future(async move {
+            let _work = work;
             let event_scores = user
                 .events
                 .iter()
@@ -195,13 +272,61 @@ impl Announcer {
         Ok(scores)
     }
 
+    /// Record the plays found by the sweep, from the new top scores and the rank events,
+    /// to keep track of the user's activity.
+    async fn record_plays(
+        env: &OsuEnv,
+        u: &OsuUser,
+        mode: Mode,
+        scores: &[(u8, Score)],
+        user: &User,
+    ) -> Result<()> {
+        let top_plays = scores.iter().map(|(_, s)| (s.date, s.beatmap_id));
+        let event_plays = user
+            .events
+            .iter()
+            .filter_map(|e| e.to_event_rank())
+            .filter(|e| e.mode == mode && e.date >= u.last_update)
+            .map(|e| (e.date, e.beatmap_id));
+        for (played_at, beatmap_id) in top_plays.chain(event_plays) {
+            models::UserPlay {
+                user_id: u.id as i64,
+                mode: mode as u8,
+                played_at,
+                beatmap_id: beatmap_id as i64,
+            }
+            .store(&env.prelude.sql)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Record a snapshot of the user's statistics in each mode.
+    async fn record_stats_snapshots(ctx: &Context, u: &OsuUser, users: &[User]) {
+        let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+        for (user, mode) in users
+            .iter()
+            .zip([Mode::Std, Mode::Taiko, Mode::Catch, Mode::Mania])
+        {
+            let map_length = Some(u.std_weighted_map_length)
+                .filter(|_| mode == Mode::Std)
+                .flatten();
+            env.stats_snapshots
+                .record(user, mode, map_length)
+                .await
+                .pls_ok();
+        }
+    }
+
     async fn std_weighted_map_length(ctx: &Context, u: &OsuUser) -> Result<f64> {
         let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
         let scores = env
             .client
             .user_best(UserID::ID(u.id), |f| f.mode(Mode::Std).limit(100))
             .await?;
-        calculate_weighted_map_length(&scores, &env.beatmaps, Mode::Std).await
+        env.map_lengths
+            .get(u.id, &scores, &env.beatmaps, Mode::Std)
+            .await
     }
 }
 
@@ -270,15 +395,23 @@ impl<'a> CollectedScore<'a> {
 impl<'a> CollectedScore<'a> {
     async fn send_message(self, ctx: &Context) -> Result<Vec<Message>> {
         let (bm, content) = self.get_beatmap(ctx).await?;
-        self.channels
+        let messages = self
+            .channels
             .iter()
             .map(|c| self.send_message_to(*c, ctx, &bm, &content))
             .collect::<stream::FuturesUnordered<_>>()
             .try_collect()
-            .await
+            .await?;
+        if let Some(metrics) = ctx.data.read().await.get::<Metrics>() {
+            let lag = (chrono::Utc::now() - self.score.date)
+                .to_std()
+                .unwrap_or_default();
+            metrics.observe(&ANNOUNCEMENT_LAG, &[], lag);
+        }
+        Ok(messages)
     }
 
-    async fn get_beatmap(&self, ctx: &Context) -> Result<(BeatmapWithMode, BeatmapContent)> {
+    async fn get_beatmap(&self, ctx: &Context) -> Result<(BeatmapWithMode, Arc<BeatmapContent>)> {
         let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
         let beatmap = env
             .beatmaps
@@ -310,41 +443,83 @@ impl<'a> CollectedScore<'a> {
                 return Err(e.into());
             }
         };
-        let m = channel
-            .send_message(
-                ctx.c.http(),
+        let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+        let theme = env.embed_theme(Some(guild)).await;
+        let card = score_card_attachment(&env, &theme, &self.score, bm, content, self.user).await;
+        let m = env
+            .prelude
+            .send_queue
+            .send(
+                ctx.c.clone(),
+                channel,
                 CreateMessage::new()
-                    .content(match self.kind {
-                        ScoreType::TopRecord(_) => {
-                            format!("New top record from {}!", self.discord_user.mention())
-                        }
-                        ScoreType::WorldRecord(rank) => {
-                            if rank <= 100 {
-                                format!(
-                                    "New leaderboard record from {}!",
-                                    self.discord_user.mention()
-                                )
-                            } else {
-                                format!("New leaderboard record from **{}**!", member.distinct())
-                            }
-                        }
-                    })
+                    .content(theme.trf(
+                        "announce-set",
+                        &[
+                            (
+                                "announcement",
+                                &match self.kind {
+                                    ScoreType::TopRecord(_) => theme.trf(
+                                        "announce-top-record",
+                                        &[("user", &self.discord_user.mention())],
+                                    ),
+                                    ScoreType::WorldRecord(rank) if rank <= 100 => theme.trf(
+                                        "announce-leaderboard-record",
+                                        &[("user", &self.discord_user.mention())],
+                                    ),
+                                    ScoreType::WorldRecord(_) => theme.trf(
+                                        "announce-leaderboard-record",
+                                        &[("user", &format!("**{}**", member.distinct()))],
+                                    ),
+                                },
+                            ),
+                            ("date", &self.score.date.relative_time()),
+                        ],
+                    ))
                     .embed({
-                        let mut b = score_embed(&self.score, bm, content, self.user);
+                        let mut b = score_embed(&self.score, bm, content, self.user, &theme);
                         match self.kind {
                             ScoreType::TopRecord(rank) => b.top_record(rank),
                             ScoreType::WorldRecord(rank) => b.world_record(rank),
                         }
+                        .score_card(card.is_some())
                         .build()
-                    }),
+                    })
+                    .add_files(card),
             )
             .await?;
 
-        let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
-
-        save_beatmap(&env, channel, bm).await.pls_ok();
+        publish_beatmap(&env, Some(guild), channel, bm).await;
+        self.record(&env, guild, &m).await.pls_ok();
         Ok(m)
     }
+
+    /// Save the announced score, to be queried later.
+    async fn record(&self, env: &OsuEnv, guild: GuildId, m: &Message) -> Result<()> {
+        let (kind, rank) = match self.kind {
+            ScoreType::TopRecord(rank) => (0, rank as i64),
+            ScoreType::WorldRecord(rank) => (1, rank as i64),
+        };
+        models::AnnouncedScore {
+            guild_id: guild.get() as i64,
+            channel_id: m.channel_id.get() as i64,
+            message_id: m.id.get() as i64,
+            discord_user_id: self.discord_user.get() as i64,
+            osu_user_id: self.user.id as i64,
+            beatmap_id: self.score.beatmap_id as i64,
+            mode: self.mode as u8,
+            mods: self.score.mods.bits() as i64,
+            pp: self.score.pp,
+            kind,
+            rank,
+            played_at: self.score.date,
+            announced_at: chrono::Utc::now(),
+            score: bincode::serialize(&self.score).unwrap(),
+        }
+        .store(&env.prelude.sql)
+        .await?;
+        Ok(())
+    }
 }
 
 enum ScoreType {
@@ -0,0 +1,113 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use youmubot_prelude::{settings::OsuConfig, *};
+
+/// The default size limit of the asset cache, in megabytes.
+const DEFAULT_SIZE_MB: u64 = 256;
+
+/// A size-bounded disk cache of beatmap covers, so that embeds can attach them
+/// instead of linking to the osu! CDN.
+///
+/// When the cache grows over its limit, the least recently used files are removed.
+#[derive(Debug, Clone)]
+pub struct AssetCache {
+    dir: PathBuf,
+    max_size: u64,
+    http: reqwest::Client,
+}
+
+impl AssetCache {
+    /// Create the cache at `osu.asset_cache`, bounded by `osu.asset_cache_size_mb`.
+    /// Returns `None` if the cache is not configured.
+    pub fn from_config(http: reqwest::Client, config: &OsuConfig) -> Result<Option<Self>> {
+        let Some(dir) = config.asset_cache.clone() else {
+            return Ok(None);
+        };
+        let max_size_mb = config.asset_cache_size_mb.unwrap_or(DEFAULT_SIZE_MB);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| error!("cannot create {}: {}", dir.display(), e))?;
+        Ok(Some(Self {
+            dir,
+            max_size: max_size_mb.saturating_mul(1024 * 1024),
+            http,
+        }))
+    }
+
+    /// Get the cover of the given beatmapset, downloading it if it is not cached.
+    pub async fn cover(&self, beatmapset_id: u64) -> Result<Vec<u8>> {
+        self.get(
+            &format!("cover-{}.jpg", beatmapset_id),
+            &format!(
+                "https://assets.ppy.sh/beatmaps/{}/covers/cover.jpg",
+                beatmapset_id
+            ),
+        )
+        .await
+    }
+
+    async fn get(&self, name: &str, url: &str) -> Result<Vec<u8>> {
+        let path = self.dir.join(name);
+        if let Ok(bytes) = tokio::fs::read(&path).await {
+            // Mark it as recently used.
+            tokio::task::spawn_blocking(move || {
+                std::fs::File::options()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|f| f.set_modified(SystemTime::now()))
+                    .pls_ok();
+            });
+            return Ok(bytes);
+        }
+        let bytes = self
+            .http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?
+            .to_vec();
+        // Write to a temporary file first, so that a partially written file is never read.
+        // Concurrent downloads of the same file each get their own.
+        let tmp = self
+            .dir
+            .join(format!("{}.{:x}.tmp", name, rand::random::<u64>()));
+        tokio::fs::write(&tmp, &bytes).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+
+        let dir = self.dir.clone();
+        let max_size = self.max_size;
+        spawn_future(async move {
+            tokio::task::spawn_blocking(move || evict(&dir, max_size))
+                .await
+                .map_err(Error::from)
+                .and_then(|v| v)
+                .pls_ok();
+        });
+        Ok(bytes)
+    }
+}
+
+/// Remove the least recently used files until the directory fits within `max_size` bytes.
+fn evict(dir: &Path, max_size: u64) -> Result<()> {
+    let mut files = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let meta = e.metadata().ok().filter(|m| m.is_file())?;
+            Some((meta.modified().ok()?, meta.len(), e.path()))
+        })
+        .collect::<Vec<_>>();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort_by_key(|(modified, _, _)| *modified);
+    for (_, len, path) in files {
+        if total <= max_size {
+            break;
+        }
+        std::fs::remove_file(&path)?;
+        total -= len;
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! Suggestions of osu! usernames for the `user` option of slash commands.
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use serenity::{all::CommandInteraction, model::id::ChannelId};
+
+use youmubot_prelude::{application::Autocomplete, *};
+
+use super::OsuEnv;
+
+/// The number of recently seen usernames kept for each channel.
+const MAX_RECENT_USERNAMES: usize = 25;
+
+/// The osu! usernames recently seen in each channel, most recent first.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RecentUsernames(Arc<DashMap<ChannelId, VecDeque<String>>>);
+
+impl RecentUsernames {
+    /// Record that the username was seen in the channel.
+    pub(crate) fn record(&self, channel_id: ChannelId, username: &str) {
+        let mut names = self.0.entry(channel_id).or_default();
+        names.retain(|n| n != username);
+        names.push_front(username.to_owned());
+        names.truncate(MAX_RECENT_USERNAMES);
+    }
+
+    /// The usernames recently seen in the channel, most recent first.
+    pub(crate) fn get(&self, channel_id: ChannelId) -> Vec<String> {
+        self.0
+            .get(&channel_id)
+            .map(|names| names.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Suggests the osu! usernames linked by members of the guild, then the ones recently seen in the channel.
+/// Usernames starting with the typed value come first.
+pub struct UsernameAutocomplete;
+
+#[async_trait]
+impl Autocomplete for UsernameAutocomplete {
+    async fn suggest(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        value: &str,
+    ) -> Result<Vec<String>> {
+        let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+        let mut names = Vec::new();
+        if let Some(guild_id) = interaction.guild_id {
+            let members = env.prelude.members.query_members(ctx, guild_id).await?;
+            let members = members.iter().map(|m| m.user.id).collect::<HashSet<_>>();
+            names.extend(
+                env.saved_users
+                    .all()
+                    .await?
+                    .into_iter()
+                    .filter(|u| members.contains(&u.user_id))
+                    .map(|u| u.username.into_owned()),
+            );
+        }
+        names.extend(env.recent_usernames.get(interaction.channel_id));
+
+        let value = value.to_lowercase();
+        let mut seen = HashSet::new();
+        let mut names = names
+            .into_iter()
+            .filter(|n| n.to_lowercase().contains(&value))
+            .filter(|n| seen.insert(n.to_lowercase()))
+            .collect::<Vec<_>>();
+        names.sort_by_key(|n| !n.to_lowercase().starts_with(&value));
+        Ok(names)
+    }
+}
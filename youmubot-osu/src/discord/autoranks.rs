@@ -0,0 +1,296 @@
+use std::str::FromStr;
+
+use chrono::Utc;
+use serenity::{
+    builder::{CreateAttachment, CreateMessage},
+    framework::standard::{macros::command, Args, CommandResult},
+    http::CacheHttp,
+    model::{
+        channel::Message,
+        id::{ChannelId, GuildId},
+    },
+    utils::MessageBuilder,
+};
+
+use youmubot_db_sql::models::osu as models;
+use youmubot_prelude::announcer::CacheAndHttp;
+use youmubot_prelude::permissions::MANAGE_GUILD_CHECK;
+use youmubot_prelude::*;
+
+use crate::models::DiscordTimestamp;
+
+use super::{
+    server_rank::{ranks, ranks_table, render_ranks, RankQuery},
+    OsuEnv,
+};
+
+/// When the scheduled posts are checked: every minute.
+pub(crate) const POSTS_SCHEDULE: &str = "* * * * *";
+/// How many posts a server can schedule.
+const MAX_POSTS_PER_GUILD: usize = 5;
+/// How many users are shown in a posted table.
+const POSTED_ROWS: usize = 10;
+/// The shortest time allowed between two posts.
+const MIN_INTERVAL: chrono::Duration = chrono::Duration::hours(1);
+
+#[command("autoranks")]
+#[description = "Post the server's ranks to a channel automatically, on a schedule. \
+                 The schedule is a cron expression in UTC (minute, hour, day, month, weekday), \
+                 or `@daily`/`@weekly`/`@monthly`, and cannot post more than once an hour. \
+                 The ranking is the same as in `ranks`. Without arguments, list the scheduled posts of this server."]
+#[usage = "[list] / add #channel \"schedule\" [mode (Std, Taiko, Catch, Mania, total, map-length) = Std] [--image] / cancel id"]
+#[example = "add #ranks \"0 18 * * 0\" total"]
+#[checks(manage_guild)]
+#[only_in(guilds)]
+#[max_args(5)]
+pub async fn autoranks(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let guild_id = msg.guild_id.unwrap();
+
+    match args.current().map(|v| v.to_lowercase()).as_deref() {
+        None | Some("list") => list_posts(ctx, msg, &env, guild_id).await,
+        Some("add") => {
+            args.advance();
+            add_post(ctx, msg, &env, guild_id, args).await
+        }
+        Some("cancel") => {
+            args.advance();
+            let id = args.single::<i64>()?;
+            if models::LeaderboardPost::delete(id, guild_id.get() as i64, &env.prelude.sql).await? {
+                msg.reply(&ctx, format!("Scheduled post `#{}` is cancelled.", id))
+                    .await?;
+            } else {
+                msg.reply(
+                    &ctx,
+                    format!("There is no scheduled post `#{}` in this server.", id),
+                )
+                .await?;
+            }
+            Ok(())
+        }
+        Some(v) => Err(error!("unknown action `{}`, expected `list`, `add` or `cancel`", v).into()),
+    }
+}
+
+/// Schedule a new post.
+async fn add_post(
+    ctx: &Context,
+    msg: &Message,
+    env: &OsuEnv,
+    guild_id: GuildId,
+    mut args: Args,
+) -> CommandResult {
+    let channel = args.single::<args::ChannelId>()?.0;
+    let schedule_text = args.single_quoted::<String>()?;
+    let schedule = Schedule::from_str(&schedule_text)?;
+    if !posts_rarely(&schedule) {
+        return Err(error!(
+            "schedule `{}` posts more than once an hour, please pick a less frequent one",
+            schedule
+        )
+        .into());
+    }
+    let mut ranking = "std".to_owned();
+    let mut as_image = false;
+    for arg in args.iter::<String>() {
+        let arg = arg?.to_lowercase();
+        if arg == "--image" {
+            as_image = true;
+        } else {
+            RankQuery::from_str(&arg)?;
+            ranking = arg;
+        }
+    }
+
+    if channel
+        .to_channel(&ctx)
+        .await?
+        .guild()
+        .filter(|c| c.guild_id == guild_id)
+        .is_none()
+    {
+        return Err(Error::msg("the channel must be in this server").into());
+    }
+    let guild = guild_id.get() as i64;
+    if models::LeaderboardPost::by_guild(guild, &env.prelude.sql)
+        .await?
+        .len()
+        >= MAX_POSTS_PER_GUILD
+    {
+        return Err(error!(
+            "this server already has {} scheduled posts, please cancel one first",
+            MAX_POSTS_PER_GUILD
+        )
+        .into());
+    }
+
+    let mut post = models::LeaderboardPost {
+        id: 0,
+        guild_id: guild,
+        channel_id: channel.get() as i64,
+        schedule: schedule.to_string(),
+        ranking,
+        as_image,
+        created_at: Utc::now(),
+        last_posted: None,
+    };
+    post.insert(&env.prelude.sql).await?;
+
+    let next = schedule
+        .next_after(post.created_at)
+        .map(|t| t.short_date_time())
+        .unwrap_or_else(|| "never".to_owned());
+    msg.reply(
+        &ctx,
+        MessageBuilder::new()
+            .push(format!("Scheduled post `#{}`: the ", post.id))
+            .push_bold(RankQuery::from_str(&post.ranking)?.name())
+            .push(" ranks will be posted to ")
+            .mention(&channel)
+            .push(" on ")
+            .push_mono(&post.schedule)
+            .push(format!(" (UTC), next on {}.", next))
+            .build(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Whether the schedule leaves at least [MIN_INTERVAL] between its next runs.
+fn posts_rarely(schedule: &Schedule) -> bool {
+    let Some(mut last) = schedule.next_after(Utc::now()) else {
+        return true;
+    };
+    // A day's worth of runs, if hourly.
+    for _ in 0..24 {
+        let Some(next) = schedule.next_after(last) else {
+            return true;
+        };
+        if next - last < MIN_INTERVAL {
+            return false;
+        }
+        last = next;
+    }
+    true
+}
+
+/// Show the scheduled posts of the server.
+async fn list_posts(
+    ctx: &Context,
+    msg: &Message,
+    env: &OsuEnv,
+    guild_id: GuildId,
+) -> CommandResult {
+    let posts = models::LeaderboardPost::by_guild(guild_id.get() as i64, &env.prelude.sql).await?;
+    if posts.is_empty() {
+        msg.reply(&ctx, "There are no scheduled posts in this server.")
+            .await?;
+        return Ok(());
+    }
+    let mut content = MessageBuilder::new();
+    content.push_line("Scheduled posts of this server:");
+    for post in &posts {
+        let next = Schedule::from_str(&post.schedule)
+            .ok()
+            .and_then(|s| s.next_after(post.last_posted.unwrap_or(post.created_at)))
+            .map(|t| t.relative_time())
+            .unwrap_or_else(|| "never".to_owned());
+        content
+            .push(format!("- `#{}`: ", post.id))
+            .push_mono(&post.ranking)
+            .push(if post.as_image {
+                " (image) to "
+            } else {
+                " to "
+            })
+            .mention(&ChannelId::new(post.channel_id as u64))
+            .push(" on ")
+            .push_mono(&post.schedule)
+            .push_line(format!(", next {}", next));
+    }
+    msg.reply(&ctx, content.build()).await?;
+    Ok(())
+}
+
+/// Make the scheduled posts that are due, as a scheduled job.
+pub(crate) async fn posts_job(env: OsuEnv) -> Result<()> {
+    // Wait for the Discord client to be built.
+    let Some(http) = env.prelude.scheduler.discord() else {
+        return Ok(());
+    };
+    let now = Utc::now();
+    for post in models::LeaderboardPost::all(&env.prelude.sql).await? {
+        let Some(schedule) = Schedule::from_str(&post.schedule).pls_ok() else {
+            continue;
+        };
+        let due = schedule
+            .next_after(post.last_posted.unwrap_or(post.created_at))
+            .is_some_and(|t| t <= now);
+        if !due {
+            continue;
+        }
+        // Mark the post first, so that a failing post is not retried every minute.
+        models::LeaderboardPost::set_last_posted(post.id, now, &env.prelude.sql).await?;
+        if let Err(e) = make_post(&env, http.clone(), &post).await {
+            tracing::warn!(
+                post = post.id,
+                guild = post.guild_id,
+                "osu: cannot post the scheduled ranks: {:#}",
+                e
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Post the ranks of the guild to the post's channel.
+async fn make_post(env: &OsuEnv, http: CacheAndHttp, post: &models::LeaderboardPost) -> Result<()> {
+    let guild = GuildId::new(post.guild_id as u64);
+    let channel = ChannelId::new(post.channel_id as u64);
+    let mode = RankQuery::from_str(&post.ranking)?;
+    let Some((users, last_update)) = ranks(env, http.clone(), guild, mode).await? else {
+        return Ok(());
+    };
+
+    let theme = env.embed_theme(Some(guild)).await;
+    let renderer = env
+        .renderer
+        .as_ref()
+        .filter(|_| post.as_image && theme.show_images);
+    let message = match renderer {
+        Some(renderer) => {
+            let guild_name = http
+                .cache()
+                .and_then(|c| guild.name(c))
+                .unwrap_or_else(|| "this server".to_owned());
+            let png = render_ranks(
+                env,
+                renderer.as_ref(),
+                mode,
+                &users,
+                last_update,
+                &guild_name,
+            )
+            .await?;
+            CreateMessage::new().add_file(CreateAttachment::bytes(png, "ranks.png"))
+        }
+        None => {
+            let top = &users[..users.len().min(POSTED_ROWS)];
+            CreateMessage::new().content(
+                MessageBuilder::new()
+                    .push("Server ranks: ")
+                    .push_bold_line(mode.name())
+                    .push_line(ranks_table(mode, top, 0))
+                    .push_line(format!(
+                        "Top **{}** of **{}**. Last updated: {}",
+                        top.len(),
+                        users.len(),
+                        last_update.relative_time(),
+                    ))
+                    .build(),
+            )
+        }
+    };
+    env.prelude.send_queue.send(http, channel, message).await?;
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use std::io::{Cursor, Read, Write};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    builder::{CreateAttachment, CreateMessage},
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandResult,
+    },
+    model::channel::Message,
+};
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use youmubot_db_sql::backup::{self as db_backup, TableDump};
+use youmubot_prelude::*;
+
+use super::OsuEnv;
+
+/// The name of the manifest inside the archive.
+const MANIFEST: &str = "manifest.bin";
+/// The largest archive we accept for restoring.
+const MAX_BACKUP_SIZE: u32 = 100 * 1024 * 1024; /* 100mb */
+/// The largest attachment Discord lets us send in a direct message.
+const MAX_UPLOAD_SIZE: usize = 10 * 1024 * 1024; /* 10mb */
+
+#[group]
+#[owners_only]
+#[description = "Backup and restore of the osu! data: saved users, last beatmaps, themes and history. Caches are excluded."]
+#[commands(backup, restore)]
+struct OsuBackup;
+
+/// Describes the content of a backup archive.
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    /// The database version the backup was taken from.
+    schema_version: i64,
+    created_at: DateTime<Utc>,
+    /// The tables in the archive, with the md5 of their content and their number of rows.
+    tables: Vec<(String, String, usize)>,
+}
+
+fn table_file(name: &str) -> String {
+    format!("{}.bin", name)
+}
+
+/// Pack the tables into a zip archive, with a manifest to check them against.
+fn write_archive(schema_version: i64, tables: &[TableDump]) -> Result<Vec<u8>> {
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let mut manifest = Manifest {
+        schema_version,
+        created_at: Utc::now(),
+        tables: Vec::with_capacity(tables.len()),
+    };
+    for table in tables {
+        let content = bincode::serialize(table)?;
+        manifest.tables.push((
+            table.name.clone(),
+            format!("{:x}", md5::compute(&content)),
+            table.rows.len(),
+        ));
+        zip.start_file(table_file(&table.name), options)?;
+        zip.write_all(&content)?;
+    }
+    zip.start_file(MANIFEST, options)?;
+    zip.write_all(&bincode::serialize(&manifest)?)?;
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Unpack the tables from a zip archive, checking them against the manifest.
+fn read_archive(archive: &[u8]) -> Result<(Manifest, Vec<TableDump>)> {
+    fn read_file(zip: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<Vec<u8>> {
+        let mut content = Vec::new();
+        zip.by_name(name)
+            .map_err(|e| error!("cannot read {}: {}", name, e))?
+            .take(MAX_BACKUP_SIZE as u64 + 1)
+            .read_to_end(&mut content)?;
+        if content.len() > MAX_BACKUP_SIZE as usize {
+            bail!("{} is too large once decompressed", name);
+        }
+        Ok(content)
+    }
+    let mut zip = ZipArchive::new(Cursor::new(archive))?;
+    let manifest: Manifest = bincode::deserialize(&read_file(&mut zip, MANIFEST)?)?;
+    let tables = manifest
+        .tables
+        .iter()
+        .map(|(name, md5, rows)| {
+            let content = read_file(&mut zip, &table_file(name))?;
+            if &format!("{:x}", md5::compute(&content)) != md5 {
+                bail!("checksum mismatch for table {}", name);
+            }
+            let table: TableDump = bincode::deserialize(&content)?;
+            if &table.name != name || table.rows.len() != *rows {
+                bail!("table {} does not match the manifest", name);
+            }
+            Ok(table)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((manifest, tables))
+}
+
+#[command]
+#[description = "Export the osu! data as a compressed archive, sent to you by DM."]
+#[max_args(0)]
+pub async fn backup(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let schema_version = db_backup::schema_version(&env.prelude.sql).await?;
+    let tables = db_backup::dump(&env.prelude.sql).await?;
+    let rows: usize = tables.iter().map(|t| t.rows.len()).sum();
+    let archive = write_archive(schema_version, &tables)?;
+    if archive.len() > MAX_UPLOAD_SIZE {
+        msg.reply(
+            ctx,
+            format!(
+                "The backup is {:.1}MB, over the {}MB that Discord lets us upload.",
+                archive.len() as f64 / 1024.0 / 1024.0,
+                MAX_UPLOAD_SIZE / 1024 / 1024
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+    let filename = format!("youmubot-osu-{}.zip", Utc::now().format("%Y%m%d-%H%M%S"));
+    // The archive holds user ids and guild settings, so it never goes to a public channel.
+    msg.author
+        .direct_message(
+            ctx,
+            CreateMessage::new()
+                .content(format!(
+                    "Backup of {} rows from {} tables.",
+                    rows,
+                    tables.len()
+                ))
+                .add_file(CreateAttachment::bytes(archive, filename)),
+        )
+        .await?;
+    if msg.guild_id.is_some() {
+        msg.reply(ctx, "The backup has been sent to your DMs.")
+            .await?;
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Replace the osu! data with the content of an archive made by `backup`. The archive should be attached to the message."]
+#[max_args(0)]
+pub async fn restore(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let attachment = match msg.attachments.first() {
+        Some(a) => a,
+        None => {
+            msg.reply(ctx, "Please attach the backup archive.").await?;
+            return Ok(());
+        }
+    };
+    if attachment.size > MAX_BACKUP_SIZE {
+        msg.reply(ctx, "The archive is too large.").await?;
+        return Ok(());
+    }
+    let archive = attachment.download().await?;
+    let (manifest, tables) = match read_archive(&archive) {
+        Ok(v) => v,
+        Err(e) => {
+            msg.reply(ctx, format!("Invalid backup: {}", e)).await?;
+            return Ok(());
+        }
+    };
+    let schema_version = db_backup::schema_version(&env.prelude.sql).await?;
+    if manifest.schema_version != schema_version {
+        msg.reply(
+            ctx,
+            format!(
+                "The backup was made with database version {}, but the current version is {}.",
+                manifest.schema_version, schema_version
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+    let rows: usize = tables.iter().map(|t| t.rows.len()).sum();
+    let prompt = format!(
+        "This replaces all osu! data with the {} rows of the backup made at {}, and cannot be undone. Proceed?",
+        rows,
+        manifest.created_at.to_rfc3339()
+    );
+    if !confirm(ctx, msg.channel_id, msg.author.id, prompt).await? {
+        msg.reply(ctx, "Nothing was restored.").await?;
+        return Ok(());
+    }
+    db_backup::restore(&env.prelude.sql, &tables).await?;
+    msg.reply(
+        ctx,
+        format!(
+            "Restored {} rows from {} tables, backed up at {}.",
+            rows,
+            tables.len(),
+            manifest.created_at.to_rfc3339()
+        ),
+    )
+    .await?;
+    Ok(())
+}
@@ -1,19 +1,157 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
+use dashmap::DashMap;
 use youmubot_db_sql::{models::osu as models, Pool};
-use youmubot_prelude::*;
+use youmubot_prelude::{settings::OsuConfig, *};
 
 use crate::{
     models::{ApprovalStatus, Beatmap, Mode},
+    ratelimit::Priority,
     Client,
 };
 
+use super::{cache_stats::HitCounter, refresh::RefreshQueue};
+
+/// The default number of beatmaps kept in memory.
+const DEFAULT_SIZE: usize = 2048;
+/// The default time beatmaps are kept in memory.
+const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+/// How long a cached beatmap is considered fresh.
+/// Older beatmaps are still returned, but refreshed in the background.
+const FRESH_FOR: chrono::Duration = chrono::Duration::days(7);
+
 /// BeatmapMetaCache intercepts beatmap-by-id requests and caches them for later recalling.
 /// Does not cache non-Ranked beatmaps.
+///
+/// Beatmaps are kept in a bounded in-memory cache, evicting the least recently used ones
+/// and the ones older than the TTL. The SQL database is used as the second tier.
+/// Cached beatmaps that are no longer fresh are refreshed in the background.
 #[derive(Clone)]
 pub struct BeatmapMetaCache {
     client: Arc<Client>,
+    /// The client used for background refreshes.
+    background: Arc<Client>,
     pool: Pool,
+    refresh: Arc<RefreshQueue<(u64, Mode)>>,
+    memory: Arc<MemoryCache>,
+    /// Beatmaps being fetched from the API, by id and requested mode.
+    inflight: Arc<Singleflight<(u64, Option<Mode>), Beatmap>>,
+    /// Beatmapsets being fetched from the API.
+    inflight_sets: Arc<Singleflight<u64, Vec<Beatmap>>>,
+    /// The ids of the beatmaps looked up by their MD5 checksum.
+    hashes: Arc<DashMap<String, u64>>,
+    /// Lookups served from memory.
+    memory_counter: Arc<HitCounter>,
+    /// Lookups missing memory, served from the database.
+    db_counter: Arc<HitCounter>,
+}
+
+/// A beatmap kept in memory.
+struct MemoryEntry {
+    beatmap: Beatmap,
+    /// When the beatmap was fetched from the API.
+    fetched_at: chrono::DateTime<chrono::Utc>,
+    inserted_at: Instant,
+    last_used: Instant,
+}
+
+/// The in-memory tier of the cache.
+struct MemoryCache {
+    entries: Mutex<HashMap<(u64, Mode), MemoryEntry>>,
+    size: usize,
+    ttl: Duration,
+}
+
+impl MemoryCache {
+    fn get(&self, id: u64, mode: Mode) -> Option<(Beatmap, chrono::DateTime<chrono::Utc>)> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(&(id, mode))?;
+        if entry.inserted_at.elapsed() > self.ttl {
+            entries.remove(&(id, mode));
+            return None;
+        }
+        entry.last_used = Instant::now();
+        Some((entry.beatmap.clone(), entry.fetched_at))
+    }
+
+    fn insert(&self, mode: Mode, beatmap: Beatmap, fetched_at: chrono::DateTime<chrono::Utc>) {
+        if self.size == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let key = (beatmap.beatmap_id, mode);
+        if entries.len() >= self.size && !entries.contains_key(&key) {
+            entries.retain(|_, e| e.inserted_at.elapsed() <= self.ttl);
+            if entries.len() >= self.size {
+                let lru = entries
+                    .iter()
+                    .min_by_key(|(_, e)| e.last_used)
+                    .map(|(k, _)| *k);
+                if let Some(lru) = lru {
+                    entries.remove(&lru);
+                }
+            }
+        }
+        let now = Instant::now();
+        entries.insert(
+            key,
+            MemoryEntry {
+                beatmap,
+                fetched_at,
+                inserted_at: now,
+                last_used: now,
+            },
+        );
+    }
+
+    fn evict_expired(&self) {
+        self.evict_older_than(self.ttl);
+    }
+
+    fn evict_older_than(&self, age: Duration) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|_, e| e.inserted_at.elapsed() <= age);
+    }
+
+    /// The number of entries, and the age of the oldest one.
+    fn stats(&self) -> (usize, Option<Duration>) {
+        let entries = self.entries.lock().unwrap();
+        let oldest = entries.values().map(|e| e.inserted_at.elapsed()).max();
+        (entries.len(), oldest)
+    }
+
+    /// Remove the beatmap in all modes.
+    fn remove(&self, id: u64) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|(beatmap_id, _), _| *beatmap_id != id);
+    }
+
+    fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// The entries of the beatmap, without marking them as used:
+    /// their mode, when they were fetched from the API, and how long they have been in memory.
+    fn peek(&self, id: u64) -> Vec<(Mode, chrono::DateTime<chrono::Utc>, Duration)> {
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((beatmap_id, _), _)| *beatmap_id == id)
+            .map(|((_, mode), e)| (*mode, e.fetched_at, e.inserted_at.elapsed()))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(mode, _, _)| *mode as u8);
+        entries
+    }
 }
 
 impl std::fmt::Debug for BeatmapMetaCache {
@@ -28,16 +166,125 @@ impl TypeMapKey for BeatmapMetaCache {
 
 impl BeatmapMetaCache {
     /// Create a new beatmap cache.
-    pub fn new(client: Arc<Client>, pool: Pool) -> Self {
-        BeatmapMetaCache { client, pool }
+    ///
+    /// The size and TTL of the in-memory cache are taken from the configuration.
+    ///
+    /// Must be called within the tokio runtime, as it starts the background refresh task.
+    pub fn new(client: Arc<Client>, pool: Pool, config: &OsuConfig) -> Self {
+        let size = config.beatmap_cache_size.unwrap_or(DEFAULT_SIZE);
+        let ttl = config
+            .beatmap_cache_ttl
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TTL);
+        let (refresh, worker) = RefreshQueue::new();
+        let cache = BeatmapMetaCache {
+            background: Arc::new(client.with_priority(Priority::Background)),
+            client,
+            pool,
+            refresh: Arc::new(refresh),
+            memory: Arc::new(MemoryCache {
+                entries: Mutex::new(HashMap::new()),
+                size,
+                ttl,
+            }),
+            inflight: Arc::new(Singleflight::new()),
+            inflight_sets: Arc::new(Singleflight::new()),
+            hashes: Arc::new(DashMap::new()),
+            memory_counter: Arc::new(HitCounter::default()),
+            db_counter: Arc::new(HitCounter::default()),
+        };
+        let c = cache.clone();
+        spawn_future(worker.run(move |(id, mode)| {
+            let c = c.clone();
+            async move {
+                c.fetch_beatmap(&c.background, id, Some(mode)).await?;
+                Ok(())
+            }
+        }));
+        cache
+    }
+
+    /// Periodically drop the expired beatmaps from memory. Never returns.
+    pub async fn run_eviction(self) {
+        let mut interval = tokio::time::interval(self.memory.ttl.max(Duration::from_secs(60)) / 2);
+        loop {
+            interval.tick().await;
+            self.memory.evict_expired();
+        }
     }
 
     /// Clean the cache.
     pub async fn clear(&self) -> Result<()> {
+        self.memory.clear();
+        self.hashes.clear();
         models::CachedBeatmap::clear_all(&self.pool).await?;
         Ok(())
     }
 
+    /// Drop the beatmaps cached longer than `age` ago. Returns the number of beatmaps deleted
+    /// from the database.
+    pub async fn evict_older_than(&self, age: Duration) -> Result<u64> {
+        self.memory.evict_older_than(age);
+        let before = chrono::Utc::now() - chrono::Duration::from_std(age)?;
+        let mut t = self.pool.begin().await?;
+        let deleted = models::CachedBeatmap::delete_older_than(before, &mut *t).await?;
+        t.commit().await?;
+        Ok(deleted)
+    }
+
+    /// Whether the cached copies of the beatmap differ from the given, freshly fetched one.
+    pub(crate) async fn is_outdated(&self, fresh: &Beatmap) -> Result<bool> {
+        let cached =
+            models::CachedBeatmap::by_beatmap_id(fresh.beatmap_id as i64, &self.pool).await?;
+        Ok(cached.iter().any(|c| {
+            bincode::deserialize::<Beatmap>(&c.beatmap[..])
+                .map(|b| b.last_update != fresh.last_update || b.approval != fresh.approval)
+                .unwrap_or(true)
+        }))
+    }
+
+    /// Drop the beatmap from the cache in all modes, so that it is fetched again on the next request.
+    pub async fn invalidate(&self, id: u64) -> Result<()> {
+        self.memory.remove(id);
+        let mut t = self.pool.begin().await?;
+        models::CachedBeatmap::delete(id as i64, &mut *t).await?;
+        t.commit().await?;
+        Ok(())
+    }
+
+    /// The counters of the memory and database tiers.
+    pub(crate) fn counters(&self) -> (&HitCounter, &HitCounter) {
+        (&self.memory_counter, &self.db_counter)
+    }
+
+    /// The number of beatmaps in memory, and the age of the oldest one.
+    pub(crate) fn memory_stats(&self) -> (usize, Option<Duration>) {
+        self.memory.stats()
+    }
+
+    /// The cached copies of the beatmap, without fetching it or counting a lookup:
+    /// the memory entries, as in [MemoryCache::peek], and the database entries, with their mode and when they were cached.
+    pub(crate) async fn inspect(
+        &self,
+        id: u64,
+    ) -> Result<(
+        Vec<(Mode, chrono::DateTime<chrono::Utc>, Duration)>,
+        Vec<(Mode, chrono::DateTime<chrono::Utc>)>,
+    )> {
+        let memory = self.memory.peek(id);
+        let db = models::CachedBeatmap::by_beatmap_id(id as i64, &self.pool)
+            .await?
+            .into_iter()
+            .map(|c| (Mode::from(c.mode), c.cached_at))
+            .collect();
+        Ok((memory, db))
+    }
+
+    /// The size of the database tier.
+    pub(crate) async fn db_stats(&self) -> Result<models::CacheTableStats> {
+        Ok(models::CachedBeatmap::stats(&self.pool).await?)
+    }
+
     #[allow(clippy::wrong_self_convention)]
     fn to_cached_beatmap(beatmap: &Beatmap, mode: Option<Mode>) -> models::CachedBeatmap {
         models::CachedBeatmap {
@@ -48,34 +295,71 @@ impl BeatmapMetaCache {
         }
     }
 
+    /// Fetch the beatmap from the API, joining the request for the same beatmap if one is in flight.
     async fn insert_if_possible(&self, id: u64, mode: Option<Mode>) -> Result<Beatmap> {
-        let beatmap = self
-            .client
+        let this = self.clone();
+        self.inflight
+            .run((id, mode), move || async move {
+                this.fetch_beatmap(&this.client, id, mode).await
+            })
+            .await
+    }
+
+    async fn fetch_beatmap(&self, client: &Client, id: u64, mode: Option<Mode>) -> Result<Beatmap> {
+        let beatmap = client
             .beatmaps(crate::BeatmapRequestKind::Beatmap(id), |f| {
                 if let Some(mode) = mode {
                     f.mode(mode, true);
                 }
                 f
             })
-            .await
-            .and_then(|v| {
-                v.into_iter()
-                    .next()
-                    .ok_or_else(|| Error::msg("beatmap not found"))
-            })?;
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(crate::ClientError::NotFound)?;
         if let ApprovalStatus::Ranked(_) = beatmap.approval {
             let mut c = Self::to_cached_beatmap(&beatmap, mode);
             c.store(&self.pool).await.pls_ok();
+            self.memory.insert(
+                mode.unwrap_or(beatmap.mode),
+                beatmap.clone(),
+                chrono::Utc::now(),
+            );
         };
         Ok(beatmap)
     }
 
+    /// Get the beatmap from the cache, queueing a refresh if it is no longer fresh.
     async fn get_beatmap_db(&self, id: u64, mode: Mode) -> Result<Option<Beatmap>> {
-        Ok(
-            models::CachedBeatmap::by_id(id as i64, mode as u8, &self.pool)
-                .await?
-                .map(|v| bincode::deserialize(&v.beatmap[..]).unwrap()),
-        )
+        let cached = match self.memory.get(id, mode) {
+            Some(v) => {
+                self.memory_counter.hit();
+                Some(v)
+            }
+            None => {
+                self.memory_counter.miss();
+                let cached = models::CachedBeatmap::by_id(id as i64, mode as u8, &self.pool)
+                    .await?
+                    .map(|v| {
+                        let bm: Beatmap = bincode::deserialize(&v.beatmap[..]).unwrap();
+                        (bm, v.cached_at)
+                    });
+                match &cached {
+                    Some((bm, cached_at)) => {
+                        self.db_counter.hit();
+                        self.memory.insert(mode, bm.clone(), *cached_at);
+                    }
+                    None => self.db_counter.miss(),
+                }
+                cached
+            }
+        };
+        Ok(cached.map(|(bm, fetched_at)| {
+            if chrono::Utc::now() - fetched_at > FRESH_FOR {
+                self.refresh.push((id, mode));
+            }
+            bm
+        }))
     }
 
     /// Get the given beatmap
@@ -86,6 +370,103 @@ impl BeatmapMetaCache {
         }
     }
 
+    /// Get many beatmaps at once, keyed by their ids.
+    /// Cached beatmaps are looked up first, and the rest are fetched in as few requests as possible.
+    /// Beatmaps that cannot be found are left out.
+    pub async fn get_beatmaps(&self, ids: &[u64], mode: Mode) -> Result<HashMap<u64, Beatmap>> {
+        self.get_beatmaps_with(Priority::Interactive, ids, mode)
+            .await
+    }
+
+    /// Load the given beatmaps into the cache with the background client.
+    pub async fn warm(&self, ids: &[u64], mode: Mode) -> Result<()> {
+        self.get_beatmaps_with(Priority::Background, ids, mode)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_beatmaps_with(
+        &self,
+        priority: Priority,
+        ids: &[u64],
+        mode: Mode,
+    ) -> Result<HashMap<u64, Beatmap>> {
+        let mut found = HashMap::with_capacity(ids.len());
+        let mut missing = Vec::new();
+        for &id in ids {
+            if found.contains_key(&id) || missing.contains(&id) {
+                continue;
+            }
+            match self.get_beatmap_db(id, mode).await? {
+                Some(bm) => {
+                    found.insert(id, bm);
+                }
+                None => missing.push(id),
+            }
+        }
+        if missing.is_empty() {
+            return Ok(found);
+        }
+
+        let mut converts = Vec::new();
+        let mut t = self.pool.begin().await?;
+        let client = match priority {
+            Priority::Interactive => &self.client,
+            Priority::Background => &self.background,
+        };
+        for beatmap in client.beatmaps_by_ids(&missing).await? {
+            if beatmap.mode != mode {
+                // Converted beatmaps need their own request.
+                converts.push(beatmap.beatmap_id);
+                continue;
+            }
+            if let ApprovalStatus::Ranked(_) = beatmap.approval {
+                let mut c = Self::to_cached_beatmap(&beatmap, Some(mode));
+                c.store(&mut *t).await.pls_ok();
+                self.memory
+                    .insert(mode, beatmap.clone(), chrono::Utc::now());
+            }
+            found.insert(beatmap.beatmap_id, beatmap);
+        }
+        t.commit().await?;
+        for id in converts {
+            let bm = match priority {
+                Priority::Interactive => self.insert_if_possible(id, Some(mode)).await,
+                Priority::Background => self.fetch_beatmap(client, id, Some(mode)).await,
+            };
+            if let Some(bm) = bm.pls_ok() {
+                found.insert(id, bm);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Get the beatmap with the given MD5 checksum (e.g. from a replay) in the given mode,
+    /// if it is submitted.
+    pub async fn get_beatmap_by_hash(&self, hash: &str, mode: Mode) -> Result<Option<Beatmap>> {
+        if let Some(id) = self.hashes.get(hash).map(|v| *v.value()) {
+            return self.get_beatmap(id, mode).await.map(Some);
+        }
+        let Some(beatmap) = self
+            .client
+            .beatmaps(
+                crate::BeatmapRequestKind::BeatmapHash(hash.to_owned()),
+                |f| f,
+            )
+            .await?
+            .into_iter()
+            .next()
+        else {
+            return Ok(None);
+        };
+        self.hashes.insert(hash.to_owned(), beatmap.beatmap_id);
+        if beatmap.mode == mode {
+            Ok(Some(beatmap))
+        } else {
+            self.get_beatmap(beatmap.beatmap_id, mode).await.map(Some)
+        }
+    }
+
     /// Get a beatmap without a mode...
     pub async fn get_beatmap_default(&self, id: u64) -> Result<Beatmap> {
         for mode in [Mode::Std, Mode::Taiko, Mode::Catch, Mode::Mania].into_iter() {
@@ -108,6 +489,13 @@ impl BeatmapMetaCache {
                 .map(|v| bincode::deserialize(&v.beatmap[..]).unwrap())
                 .collect());
         }
+        let this = self.clone();
+        self.inflight_sets
+            .run(id, move || async move { this.fetch_beatmapset(id).await })
+            .await
+    }
+
+    async fn fetch_beatmapset(&self, id: u64) -> Result<Vec<Beatmap>> {
         let mut beatmaps = self
             .client
             .beatmaps(crate::BeatmapRequestKind::Beatmapset(id), |f| f)
@@ -120,6 +508,7 @@ impl BeatmapMetaCache {
             // Save each beatmap.
             let mut t = self.pool.begin().await?;
             for b in &beatmaps {
+                self.memory.insert(b.mode, b.clone(), chrono::Utc::now());
                 let mut b = Self::to_cached_beatmap(b, None);
                 b.store(&mut *t).await?;
                 // Save the beatmapset mapping.
@@ -1,27 +1,33 @@
-use serenity::model::id::ChannelId;
+use serenity::model::id::{ChannelId, GuildId};
 
 use youmubot_prelude::*;
 
 use super::{BeatmapWithMode, OsuEnv};
 
-/// Save the beatmap into the server data storage.
-pub(crate) async fn save_beatmap(
+/// Publish the beatmap to the channel, making it the last beatmap `check`, `last` etc. work with.
+///
+/// Everything that shows a beatmap in a channel (commands, hooks and announcers) should call this
+/// once the beatmap is shown. Failures are only logged, as they should not fail the response itself.
+pub(crate) async fn publish_beatmap(
     env: &OsuEnv,
+    guild_id: Option<GuildId>,
     channel_id: ChannelId,
     bm: &BeatmapWithMode,
-) -> Result<()> {
-    env.last_beatmaps.save(channel_id, &bm.0, bm.1).await?;
-
-    Ok(())
+) {
+    env.last_beatmaps
+        .save(guild_id, channel_id, &bm.0, bm.1)
+        .await
+        .pls_ok();
 }
 
-/// Get the last beatmap requested from this channel.
+/// Get the `n`-th last beatmap requested from this channel, with 0 being the last one.
 pub(crate) async fn get_beatmap(
     env: &OsuEnv,
     channel_id: ChannelId,
+    n: usize,
 ) -> Result<Option<BeatmapWithMode>> {
     env.last_beatmaps
-        .by_channel(channel_id)
+        .by_channel_nth(channel_id, n)
         .await
         .map(|v| v.map(|(bm, mode)| BeatmapWithMode(bm, mode)))
 }
@@ -0,0 +1,250 @@
+use std::{
+    fmt::Write as _,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use serenity::{
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+    utils::MessageBuilder,
+};
+
+use youmubot_db_sql::models::osu::CacheTableStats;
+use youmubot_prelude::metrics::{Labels, Metric};
+use youmubot_prelude::*;
+
+use super::OsuEnv;
+
+/// Counts the hits and misses of a cache.
+#[derive(Debug, Default)]
+pub(crate) struct HitCounter {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl HitCounter {
+    pub fn hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns (hits, misses).
+    pub fn get(&self) -> (u64, u64) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+}
+
+impl std::fmt::Display for HitCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (hits, misses) = self.get();
+        let total = hits + misses;
+        if total == 0 {
+            return write!(f, "no lookups");
+        }
+        write!(
+            f,
+            "{} hits / {} misses ({:.1}%)",
+            hits,
+            misses,
+            hits as f64 / total as f64 * 100.0
+        )
+    }
+}
+
+/// Cache lookups, by cache and result.
+const CACHE_LOOKUPS: Metric = Metric::counter(
+    "youmubot_osu_cache_lookups_total",
+    "osu! cache lookups, by cache and result (hit or miss).",
+);
+/// osu! API requests waiting for the rate limiter.
+const API_QUEUED: Metric = Metric::gauge(
+    "youmubot_osu_api_queued_requests",
+    "osu! API requests waiting for the rate limiter.",
+);
+
+/// Expose the cache counters and the rate limiter queue in the metrics.
+pub(crate) fn register_metrics(env: &OsuEnv) {
+    let beatmaps = env.beatmaps.clone();
+    let oppai = env.oppai.clone();
+    env.prelude.metrics.collect(CACHE_LOOKUPS, move || {
+        let (memory, db) = beatmaps.counters();
+        let (content, attributes) = oppai.counters();
+        [
+            ("beatmaps_memory", memory),
+            ("beatmaps_db", db),
+            ("beatmap_files", content),
+            ("beatmap_attributes", attributes),
+        ]
+        .into_iter()
+        .flat_map(|(cache, counter)| {
+            let (hits, misses) = counter.get();
+            let labels = |result: &str| -> Labels {
+                vec![("cache", cache.to_owned()), ("result", result.to_owned())]
+            };
+            [
+                (labels("hit"), hits as f64),
+                (labels("miss"), misses as f64),
+            ]
+        })
+        .collect()
+    });
+    let client = env.client.clone();
+    env.prelude.metrics.collect(API_QUEUED, move || {
+        vec![(vec![], client.rate_limit_stats().queued as f64)]
+    });
+}
+
+fn write_table(s: &mut String, name: &str, stats: &CacheTableStats) {
+    writeln!(
+        s,
+        "  {:<12} {} entries, {}",
+        name,
+        stats.entries,
+        format_bytes(stats.bytes)
+    )
+    .unwrap();
+    if let Some(oldest) = stats.oldest {
+        writeln!(s, "  {:<12} oldest cached at {}", "", oldest.to_rfc3339()).unwrap();
+    }
+}
+
+fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[command("cachestats")]
+#[owners_only]
+#[description = "Show the statistics of the beatmap caches, optionally evicting old entries."]
+#[usage = "[--evict <age, e.g. 30d>]"]
+#[max_args(2)]
+pub async fn cache_stats(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+
+    let evict = match args.single::<String>().ok().as_deref() {
+        None => None,
+        Some("--evict") => Some(args.single::<Duration>()?),
+        Some(v) => return Err(Error::msg(format!("unknown option `{}`", v)).into()),
+    };
+
+    let mut s = String::new();
+    if let Some(age) = evict {
+        let beatmaps = env.beatmaps.evict_older_than(age.0).await?;
+        let contents = env.oppai.evict_older_than(age.0).await?;
+        writeln!(
+            s,
+            "Evicted {} beatmaps and {} beatmap files cached more than {} ago.\n",
+            beatmaps, contents, age
+        )
+        .unwrap();
+    }
+
+    // Beatmap metadata
+    let (memory_counter, db_counter) = env.beatmaps.counters();
+    let (memory_entries, memory_oldest) = env.beatmaps.memory_stats();
+    writeln!(s, "Beatmaps").unwrap();
+    writeln!(s, "  {:<12} {} entries", "memory", memory_entries).unwrap();
+    if let Some(oldest) = memory_oldest {
+        writeln!(
+            s,
+            "  {:<12} oldest fetched {} ago",
+            "",
+            Duration(std::time::Duration::from_secs(oldest.as_secs()))
+        )
+        .unwrap();
+    }
+    writeln!(s, "  {:<12} {}", "", memory_counter).unwrap();
+    write_table(&mut s, "database", &env.beatmaps.db_stats().await?);
+    writeln!(s, "  {:<12} {}", "", db_counter).unwrap();
+
+    // Beatmap files and difficulty
+    let (content_counter, attributes_counter) = env.oppai.counters();
+    let (contents, attributes) = env.oppai.db_stats().await?;
+    writeln!(s, "Beatmap files").unwrap();
+    write_table(&mut s, "files", &contents);
+    writeln!(s, "  {:<12} {}", "", content_counter).unwrap();
+    write_table(&mut s, "difficulty", &attributes);
+    writeln!(s, "  {:<12} {}", "", attributes_counter).unwrap();
+
+    // API
+    let rate_limit = env.client.rate_limit_stats();
+    writeln!(s, "API requests").unwrap();
+    writeln!(
+        s,
+        "  {:<12} {} requests, waited {:.1}s",
+        "interactive",
+        rate_limit.requests.0,
+        rate_limit.waited.0.as_secs_f64()
+    )
+    .unwrap();
+    writeln!(
+        s,
+        "  {:<12} {} requests, waited {:.1}s",
+        "background",
+        rate_limit.requests.1,
+        rate_limit.waited.1.as_secs_f64()
+    )
+    .unwrap();
+    writeln!(s, "  {:<12} {} waiting", "", rate_limit.queued).unwrap();
+    let osutrack = env.osutrack.rate_limit_stats();
+    writeln!(
+        s,
+        "  {:<12} {} requests, waited {:.1}s, {} waiting",
+        "osu!track",
+        osutrack.requests.0,
+        osutrack.waited.0.as_secs_f64(),
+        osutrack.queued
+    )
+    .unwrap();
+    let huismetbenen = env.huismetbenen.rate_limit_stats();
+    writeln!(
+        s,
+        "  {:<12} {} requests, waited {:.1}s, {} waiting",
+        "huismetbenen",
+        huismetbenen.requests.0 + huismetbenen.requests.1,
+        (huismetbenen.waited.0 + huismetbenen.waited.1).as_secs_f64(),
+        huismetbenen.queued
+    )
+    .unwrap();
+    let collector = env.collector.rate_limit_stats();
+    writeln!(
+        s,
+        "  {:<12} {} requests, waited {:.1}s, {} waiting",
+        "osu!collect",
+        collector.requests.0,
+        collector.waited.0.as_secs_f64(),
+        collector.queued
+    )
+    .unwrap();
+    if let Some(twitch) = &env.twitch {
+        let twitch = twitch.rate_limit_stats();
+        writeln!(
+            s,
+            "  {:<12} {} requests, waited {:.1}s, {} waiting",
+            "Twitch",
+            twitch.requests.0 + twitch.requests.1,
+            (twitch.waited.0 + twitch.waited.1).as_secs_f64(),
+            twitch.queued
+        )
+        .unwrap();
+    }
+
+    let db_size = youmubot_db_sql::database_size(&env.prelude.sql).await?;
+    writeln!(s, "Database size: {}", format_bytes(db_size)).unwrap();
+
+    msg.reply(ctx, MessageBuilder::new().push_codeblock(s, None).build())
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use std::sync::Arc;
+
+use serenity::{
+    builder::{
+        CreateAttachment, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, CreateMessage,
+        EditMessage,
+    },
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+    utils::MessageBuilder,
+};
+
+use youmubot_prelude::*;
+
+use crate::{
+    collection::{to_collection_db, to_osdb, Collection, CollectionEntry},
+    models::{Mode, Mods},
+    osucollector::{self, CollectionBeatmap},
+    request::UserID,
+};
+
+use super::{theme::EmbedTheme, user_on_server, ModeArg, OsuEnv, OPTIONS};
+
+/// The name of the attached file, which osu! reads from its folder.
+const COLLECTION_DB: &str = "collection.db";
+/// The name of the attached Collection Manager file.
+const COLLECTION_OSDB: &str = "collection.osdb";
+/// How many beatmaps are listed on each page.
+const ITEMS_PER_PAGE: usize = 10;
+/// The longest description shown.
+const MAX_DESCRIPTION_LEN: usize = 300;
+
+#[command("collection")]
+#[description = "Show a collection shared on osu!collector, with the star rating and FC pp of its beatmaps, \
+                 and attach it as a `collection.db` and an `.osdb` to import in osu! or Collection Manager. \
+                 `collection top` makes one from the top plays of an user instead."]
+#[usage = "[osu!collector link or id] / top [--mode (std, taiko, catch, mania) = std] [username or user_id = your saved user id]"]
+#[example = "https://osucollector.com/collections/44"]
+#[min_args(1)]
+#[max_args(4)]
+#[bucket("heavy")]
+pub async fn collection(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let theme = env.embed_theme_for(msg).await;
+    let arg = args.single::<String>()?;
+    if arg == "top" {
+        return top_collection(ctx, &env, msg, args, &theme).await;
+    }
+    let id = osucollector::collection_id(&arg)
+        .ok_or_else(|| Error::msg(theme.tr("collection-invalid-link")))?;
+    let Some(collection) = env.collector.collection(id).await? else {
+        msg.reply(&ctx, theme.tr("collection-not-found")).await?;
+        return Ok(());
+    };
+
+    let collections = [collection.to_collection()];
+    let db = to_collection_db(&collections);
+    let osdb = to_osdb(&collections, chrono::Utc::now());
+    msg.channel_id
+        .send_message(
+            &ctx,
+            CreateMessage::new()
+                .reference_message(msg)
+                .content(
+                    theme.trf(
+                        "collection-import",
+                        &[(
+                            "name",
+                            &MessageBuilder::new()
+                                .push_bold_safe(&collection.name)
+                                .build(),
+                        )],
+                    ),
+                )
+                .add_file(CreateAttachment::bytes(db, COLLECTION_DB))
+                .add_file(CreateAttachment::bytes(osdb, COLLECTION_OSDB)),
+        )
+        .await?;
+    if collection.beatmaps.is_empty() {
+        return Ok(());
+    }
+
+    let collection = Arc::new(collection);
+    let pages = collection.beatmaps.len().div_ceil(ITEMS_PER_PAGE);
+    paginate_reply_fn(
+        move |page: u8, ctx: &Context, m: &mut Message| {
+            let env = env.clone();
+            let theme = theme.clone();
+            let collection = collection.clone();
+            Box::pin(async move {
+                let start = page as usize * ITEMS_PER_PAGE;
+                let end = (start + ITEMS_PER_PAGE).min(collection.beatmaps.len());
+                if start >= end {
+                    return Ok(false);
+                }
+                let lines = collection.beatmaps[start..end]
+                    .iter()
+                    .enumerate()
+                    .map(|(i, b)| beatmap_line(&env, start + i + 1, b))
+                    .collect::<stream::FuturesOrdered<_>>()
+                    .collect::<Vec<_>>()
+                    .await;
+                let mut description = collection
+                    .description
+                    .chars()
+                    .take(MAX_DESCRIPTION_LEN)
+                    .collect::<String>();
+                if !description.is_empty() {
+                    description.push_str("\n\n");
+                }
+                description.push_str(&lines.join("\n"));
+                let embed = CreateEmbed::new()
+                    .title(MessageBuilder::new().push_safe(&collection.name).build())
+                    .url(collection.link())
+                    .color(theme.color)
+                    .author(CreateEmbedAuthor::new(
+                        theme.trf("collection-by", &[("user", &collection.uploader)]),
+                    ))
+                    .description(description)
+                    .footer(CreateEmbedFooter::new(theme.trf(
+                        "collection-footer",
+                        &[
+                            ("count", &collection.beatmaps.len()),
+                            ("favourites", &collection.favourites),
+                            ("page", &(page + 1)),
+                            ("pages", &pages),
+                        ],
+                    )));
+                m.edit(ctx, EditMessage::new().content("").embed(embed))
+                    .await?;
+                Ok(true)
+            })
+        },
+        ctx,
+        msg,
+        std::time::Duration::from_secs(60),
+    )
+    .await?;
+    Ok(())
+}
+
+/// The line of a beatmap, with its star rating and SS pp, or its id if it cannot be found.
+async fn beatmap_line(env: &OsuEnv, position: usize, b: &CollectionBeatmap) -> String {
+    let Some(beatmap) = env.beatmaps.get_beatmap_default(b.id).await.pls_ok() else {
+        return format!("**{}.** Beatmap #{}", position, b.id);
+    };
+    let pp = env
+        .oppai
+        .get_possible_pp(&beatmap, beatmap.mode, Mods::NOMOD)
+        .await
+        .pls_ok()
+        .map(|info| format!(" • {:.0}pp", info.pp[3]))
+        .unwrap_or_default();
+    format!(
+        "**{}.** [{} - {} [{}]]({}) ★{:.2}{}",
+        position,
+        MessageBuilder::new().push_safe(&beatmap.artist).build(),
+        MessageBuilder::new().push_safe(&beatmap.title).build(),
+        MessageBuilder::new()
+            .push_safe(&beatmap.difficulty_name)
+            .build(),
+        beatmap.link(),
+        beatmap.difficulty.stars,
+        pp
+    )
+}
+
+/// Make a collection of the top plays of an user.
+async fn top_collection(
+    ctx: &Context,
+    env: &OsuEnv,
+    msg: &Message,
+    args: Args,
+    theme: &EmbedTheme,
+) -> CommandResult {
+    let mut opts = Options::parse(args, OPTIONS)?;
+    let mode = opts.get::<ModeArg>("mode")?.map_or(Mode::Std, |m| m.0);
+    let user = opts.take::<UsernameArg>();
+    opts.finish()?;
+
+    let (client, user) = user_on_server(env, msg, None, user, mode).await?;
+    let user = user.ok_or_else(|| Error::msg(theme.tr("user-not-found")))?;
+    let plays = client
+        .user_best(UserID::ID(user.id), |f| f.mode(mode).limit(100))
+        .await?;
+    if plays.is_empty() {
+        msg.reply(&ctx, theme.tr("no-scores")).await?;
+        return Ok(());
+    }
+    let beatmaps = plays
+        .iter()
+        .map(|p| async move {
+            let beatmap = env.beatmaps.get_beatmap(p.beatmap_id, mode).await?;
+            Ok::<_, Error>(CollectionEntry {
+                beatmap_id: beatmap.beatmap_id,
+                beatmapset_id: beatmap.beatmapset_id,
+                hash: beatmap.file_hash,
+                difficulty: Some((mode, beatmap.difficulty.stars)),
+            })
+        })
+        .collect::<stream::FuturesOrdered<_>>()
+        .try_collect::<Vec<_>>()
+        .await?;
+    let name = format!("{} top plays ({})", user.username, mode);
+    let collections = [Collection {
+        name: name.clone(),
+        beatmaps,
+    }];
+    let db = to_collection_db(&collections);
+    let osdb = to_osdb(&collections, chrono::Utc::now());
+
+    msg.channel_id
+        .send_message(
+            &ctx,
+            CreateMessage::new()
+                .reference_message(msg)
+                .content(theme.trf(
+                    "collection-import",
+                    &[("name", &MessageBuilder::new().push_bold_safe(&name).build())],
+                ))
+                .add_file(CreateAttachment::bytes(db, COLLECTION_DB))
+                .add_file(CreateAttachment::bytes(osdb, COLLECTION_OSDB)),
+        )
+        .await?;
+    Ok(())
+}
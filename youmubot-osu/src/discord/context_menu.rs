@@ -0,0 +1,189 @@
+//! Context menu commands, on users and messages.
+use serenity::{
+    all::{ButtonStyle, CommandInteraction, CommandType},
+    builder::{
+        CreateActionRow, CreateButton, CreateCommand, CreateEmbed, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateMessage, EditInteractionResponse,
+    },
+    model::channel::Message,
+    utils::MessageBuilder,
+};
+
+use youmubot_prelude::{application::ApplicationCommand, interaction, *};
+
+use crate::{models::Mode, request::UserID};
+
+use super::{cache::publish_beatmap, hook::expand_beatmaps, user_profile, BeatmapWithMode, OsuEnv};
+
+/// "osu! profile" on a user: show their linked osu! profile.
+pub struct ProfileCommand;
+
+#[async_trait]
+impl ApplicationCommand for ProfileCommand {
+    fn name(&self) -> &'static str {
+        "osu! profile"
+    }
+
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name()).kind(CommandType::User)
+    }
+
+    async fn run(&self, ctx: &Context, interaction: &CommandInteraction) -> Result<()> {
+        let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+        let target = interaction
+            .data
+            .target_id
+            .ok_or_else(|| Error::msg("no user was selected"))?
+            .to_user_id();
+        interaction.defer(ctx).await?;
+
+        let response = match env.saved_users.by_user_id(target).await? {
+            None => EditInteractionResponse::new().content(
+                MessageBuilder::new()
+                    .mention(&target)
+                    .push(" has not linked an osu! account.")
+                    .build(),
+            ),
+            Some(u) => match user_profile(
+                &env,
+                UserID::ID(u.id),
+                Mode::Std,
+                interaction.guild_id,
+                interaction.channel_id,
+            )
+            .await?
+            {
+                Some(embed) => EditInteractionResponse::new().embed(embed),
+                None => EditInteractionResponse::new().content("🔍 user not found!"),
+            },
+        };
+        interaction.edit_response(ctx, response).await?;
+        Ok(())
+    }
+}
+
+/// "Expand beatmap" on a message: expand its beatmap links like the beatmap hook does.
+/// The result is only shown to the user at first, who can then post it to the channel.
+pub struct ExpandBeatmapCommand;
+
+#[async_trait]
+impl ApplicationCommand for ExpandBeatmapCommand {
+    fn name(&self) -> &'static str {
+        "Expand beatmap"
+    }
+
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name()).kind(CommandType::Message)
+    }
+
+    async fn run(&self, ctx: &Context, interaction: &CommandInteraction) -> Result<()> {
+        let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+        let target = interaction
+            .data
+            .target_id
+            .and_then(|t| interaction.data.resolved.messages.get(&t.to_message_id()))
+            .ok_or_else(|| Error::msg("no message was selected"))?;
+        interaction.defer_ephemeral(ctx).await?;
+
+        let expanded = expand_beatmaps(ctx, target).await?;
+        if expanded.is_empty() {
+            interaction
+                .edit_response(
+                    ctx,
+                    EditInteractionResponse::new().content("No beatmaps found in this message."),
+                )
+                .await?;
+            return Ok(());
+        }
+        let message = interaction
+            .edit_response(
+                ctx,
+                EditInteractionResponse::new()
+                    .embeds(expanded.iter().map(|(e, _)| e.clone()).collect())
+                    .components(vec![CreateActionRow::Buttons(vec![CreateButton::new(
+                        POST_PUBLICLY_ID,
+                    )
+                    .label("Post to the channel")
+                    .style(ButtonStyle::Primary)])]),
+            )
+            .await?;
+        interaction::run(
+            PostPublicly {
+                env,
+                command: interaction.clone(),
+                target: target.link(),
+                expanded,
+            },
+            ctx,
+            message,
+            interaction::Allowed::user(interaction.user.id),
+            POST_PUBLICLY_TIMEOUT,
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+const POST_PUBLICLY_ID: &str = "osu-expand-post";
+/// How long the "Post to the channel" button stays.
+const POST_PUBLICLY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+
+/// Posts the expanded beatmaps to the channel once asked to.
+struct PostPublicly {
+    env: OsuEnv,
+    command: CommandInteraction,
+    target: String,
+    expanded: Vec<(CreateEmbed, BeatmapWithMode)>,
+}
+
+#[async_trait]
+impl Interactive for PostPublicly {
+    async fn handle(
+        &mut self,
+        ctx: &Context,
+        incoming: &interaction::Incoming,
+        _message: &mut Message,
+    ) -> Result<bool> {
+        incoming
+            .respond(
+                ctx,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new().components(vec![]),
+                ),
+            )
+            .await?;
+        self.command
+            .channel_id
+            .send_message(
+                ctx,
+                CreateMessage::new()
+                    .content(
+                        MessageBuilder::new()
+                            .mention(&self.command.user)
+                            .push(" expanded the beatmaps of ")
+                            .push(&self.target)
+                            .build(),
+                    )
+                    .embeds(self.expanded.iter().map(|(e, _)| e.clone()).collect()),
+            )
+            .await?;
+        if let Some((_, bm)) = self.expanded.first() {
+            publish_beatmap(
+                &self.env,
+                self.command.guild_id,
+                self.command.channel_id,
+                bm,
+            )
+            .await;
+        }
+        Ok(false)
+    }
+
+    async fn stop(&mut self, ctx: &Context, _message: &mut Message) -> Result<()> {
+        // Ephemeral messages can only be edited through the interaction.
+        self.command
+            .edit_response(ctx, EditInteractionResponse::new().components(vec![]))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,437 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serenity::{
+    builder::{CreateMessage, EditMessage},
+    framework::standard::{macros::command, Args, CommandResult},
+    model::{channel::Message, id::GuildId},
+    utils::MessageBuilder,
+};
+
+use youmubot_db_sql::models::osu as models;
+use youmubot_prelude::table_format::{table_formatting, Align};
+use youmubot_prelude::*;
+
+use crate::{
+    models::{multiplayer::Room, DiscordTimestamp, Mods},
+    ratelimit::Priority,
+};
+
+use super::{
+    cache::publish_beatmap,
+    cover_attachment,
+    embeds::{beatmap_embed, grouped_number, with_cover, with_strain_graph},
+    strain_graph_attachment, BeatmapWithMode, OsuEnv,
+};
+
+/// When the daily challenge scores of the saved users are recorded: every hour, before the hour
+/// (even with the jobs' jitter) so that the last hour of each daily challenge is recorded too.
+pub(crate) const RECORD_SCHEDULE: &str = "45 * * * *";
+/// How many rows are shown per page.
+const ROWS_PER_PAGE: usize = 10;
+
+#[command("daily")]
+#[aliases("dc")]
+#[description = "Show today's daily challenge, with its pp. \
+                 `ranks` shows the scores of the server's members on it, and `streaks` their daily challenge streaks. \
+                 Scores are recorded every hour, for the saved users only."]
+#[usage = "[ranks / streaks]"]
+#[max_args(1)]
+#[bucket("heavy")]
+pub async fn daily(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let action = args.current().map(|v| v.to_lowercase());
+    if action.is_some() && msg.guild_id.is_none() {
+        return Err(Error::msg("the daily challenge ranks are only shown in servers").into());
+    }
+    match action.as_deref() {
+        None => show_daily(ctx, msg, &env).await,
+        Some("ranks") => show_ranks(ctx, msg, &env, msg.guild_id.unwrap()).await,
+        Some("streaks") => show_streaks(ctx, msg, &env, msg.guild_id.unwrap()).await,
+        Some(v) => Err(error!("unknown action `{}`, expected `ranks` or `streaks`", v).into()),
+    }
+}
+
+/// Today's daily challenge. Replies and returns `None` if there is none.
+async fn todays_room(ctx: &Context, msg: &Message, env: &OsuEnv) -> Result<Option<Room>> {
+    let room = env
+        .client
+        .daily_challenge()
+        .await?
+        .filter(|r| !r.playlist.is_empty());
+    if room.is_none() {
+        let theme = env.embed_theme_for(msg).await;
+        msg.reply(&ctx, theme.tr("daily-none")).await?;
+    }
+    Ok(room)
+}
+
+/// Show the beatmap of the daily challenge, with its pp.
+async fn show_daily(ctx: &Context, msg: &Message, env: &OsuEnv) -> CommandResult {
+    let Some(room) = todays_room(ctx, msg, env).await? else {
+        return Ok(());
+    };
+    let item = &room.playlist[0];
+    let (mode, mods) = (item.mode, item.required_mods);
+    let beatmap = env.beatmaps.get_beatmap(item.beatmap_id, mode).await?;
+    let content = env.oppai.get_beatmap(beatmap.beatmap_id).await?;
+    let info = content.get_possible_pp_with(mode, mods)?;
+
+    let theme = env.embed_theme_for(msg).await;
+    let text = theme.trf(
+        "daily-challenge",
+        &[
+            (
+                "room",
+                &format!(
+                    "[{}](<{}>)",
+                    MessageBuilder::new().push_safe(&room.name).build(),
+                    room.link()
+                ),
+            ),
+            ("participants", &grouped_number(room.participant_count)),
+            (
+                "ends",
+                &room
+                    .ends_at
+                    .map(|t| t.relative_time())
+                    .unwrap_or_else(|| "-".to_owned()),
+            ),
+        ],
+    );
+    let graph =
+        strain_graph_attachment(env, &theme, beatmap.beatmap_id, &content, mode, mods).await;
+    let cover = match graph {
+        Some(_) => None,
+        None => cover_attachment(env, &theme, beatmap.beatmapset_id).await,
+    };
+    msg.channel_id
+        .send_message(
+            &ctx,
+            CreateMessage::new()
+                .content(text)
+                .embed(with_cover(
+                    with_strain_graph(
+                        beatmap_embed(&beatmap, mode, mods, info, &theme),
+                        graph.is_some(),
+                    ),
+                    cover.is_some(),
+                ))
+                .add_files(graph)
+                .add_files(cover)
+                .reference_message(msg),
+        )
+        .await?;
+    publish_beatmap(
+        env,
+        msg.guild_id,
+        msg.channel_id,
+        &BeatmapWithMode(beatmap, mode),
+    )
+    .await;
+    Ok(())
+}
+
+/// The saved users among the guild's members, as their member name and osu! username, by osu! id.
+async fn guild_players(
+    ctx: &Context,
+    env: &OsuEnv,
+    guild: GuildId,
+) -> Result<HashMap<u64, (String, String)>> {
+    let saved = env
+        .saved_users
+        .all()
+        .await?
+        .into_iter()
+        .map(|u| (u.user_id, u))
+        .collect::<HashMap<_, _>>();
+    Ok(env
+        .prelude
+        .members
+        .query_members(ctx, guild)
+        .await?
+        .iter()
+        .filter_map(|m| {
+            let u = saved.get(&m.user.id)?;
+            Some((u.id, (m.user.name.clone(), u.username.clone().into_owned())))
+        })
+        .collect())
+}
+
+/// Show the recorded scores of the server's members on today's daily challenge.
+async fn show_ranks(ctx: &Context, msg: &Message, env: &OsuEnv, guild: GuildId) -> CommandResult {
+    let Some(room) = todays_room(ctx, msg, env).await? else {
+        return Ok(());
+    };
+    let players = guild_players(ctx, env, guild).await?;
+    let today = Utc::now().date_naive();
+    let mut rows = vec![];
+    for score in models::DailyChallengeScore::by_room(room.id as i64, &env.prelude.sql).await? {
+        let Some((member, username)) = players.get(&(score.user_id as u64)) else {
+            continue;
+        };
+        let days = models::DailyChallengeScore::days_of(score.user_id, &env.prelude.sql).await?;
+        let mods = score.mods.parse::<Mods>().unwrap_or_default();
+        rows.push([
+            (rows.len() + 1).to_string(),
+            grouped_number(score.total_score as u64),
+            format!("{:.2}%", score.accuracy),
+            format!("{}", mods),
+            streaks(&days, today).current.to_string(),
+            username.clone(),
+            member.clone(),
+        ]);
+    }
+    if rows.is_empty() {
+        let theme = env.embed_theme_for(msg).await;
+        msg.reply(&ctx, theme.tr("daily-no-scores")).await?;
+        return Ok(());
+    }
+    const HEADERS: [&str; 7] = ["#", "Score", "Acc", "Mods", "Streak", "Username", "Member"];
+    const ALIGNS: [Align; 7] = [
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Left,
+        Align::Right,
+        Align::Left,
+        Align::Left,
+    ];
+    paginate_table(
+        ctx,
+        msg,
+        MessageBuilder::new()
+            .push("Daily challenge ranks: ")
+            .push_bold_line_safe(&room.name)
+            .build(),
+        &HEADERS,
+        &ALIGNS,
+        rows,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Show the daily challenge streaks of the server's members.
+async fn show_streaks(ctx: &Context, msg: &Message, env: &OsuEnv, guild: GuildId) -> CommandResult {
+    let today = Utc::now().date_naive();
+    let mut players = vec![];
+    for (id, (member, username)) in guild_players(ctx, env, guild).await? {
+        let days = models::DailyChallengeScore::days_of(id as i64, &env.prelude.sql).await?;
+        if days.is_empty() {
+            continue;
+        }
+        players.push((streaks(&days, today), days.len(), username, member));
+    }
+    if players.is_empty() {
+        let theme = env.embed_theme_for(msg).await;
+        msg.reply(&ctx, theme.tr("daily-no-scores")).await?;
+        return Ok(());
+    }
+    players.sort_by(|a, b| (b.0.current, b.0.best, b.1).cmp(&(a.0.current, a.0.best, a.1)));
+    let rows = players
+        .into_iter()
+        .enumerate()
+        .map(|(i, (streaks, played, username, member))| {
+            [
+                (i + 1).to_string(),
+                streaks.current.to_string(),
+                streaks.best.to_string(),
+                played.to_string(),
+                username,
+                member,
+            ]
+        })
+        .collect::<Vec<_>>();
+    const HEADERS: [&str; 6] = ["#", "Streak", "Best", "Played", "Username", "Member"];
+    const ALIGNS: [Align; 6] = [
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Right,
+        Align::Left,
+        Align::Left,
+    ];
+    paginate_table(
+        ctx,
+        msg,
+        "Daily challenge streaks:\n".to_owned(),
+        &HEADERS,
+        &ALIGNS,
+        rows,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Reply with the table, one page at a time.
+async fn paginate_table<const N: usize>(
+    ctx: &Context,
+    msg: &Message,
+    title: String,
+    headers: &'static [&'static str; N],
+    aligns: &'static [Align; N],
+    rows: Vec<[String; N]>,
+) -> Result<()> {
+    let rows = Arc::new(rows);
+    paginate_reply_fn(
+        move |page: u8, ctx: &Context, m: &mut Message| {
+            let rows = rows.clone();
+            let title = title.clone();
+            Box::pin(async move {
+                let start = page as usize * ROWS_PER_PAGE;
+                let end = (start + ROWS_PER_PAGE).min(rows.len());
+                if start >= end {
+                    return Ok(false);
+                }
+                let content = MessageBuilder::new()
+                    .push(title)
+                    .push_line(table_formatting(headers, aligns, &rows[start..end]))
+                    .push_line(format!(
+                        "Page **{}**/**{}**. Recorded every hour, for the saved users only.",
+                        page + 1,
+                        (rows.len() + ROWS_PER_PAGE - 1) / ROWS_PER_PAGE,
+                    ))
+                    .build();
+                m.edit(ctx, EditMessage::new().content(content)).await?;
+                Ok(true)
+            })
+        },
+        ctx,
+        msg,
+        std::time::Duration::from_secs(60),
+    )
+    .await
+}
+
+/// The daily challenge streaks of an user.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Streaks {
+    /// The daily challenges played in a row up to today, or yesterday if today's is not played yet.
+    current: u32,
+    /// The most daily challenges ever played in a row.
+    best: u32,
+}
+
+/// Count the streaks from the start times of the daily challenges played, latest first.
+fn streaks(days: &[DateTime<Utc>], today: NaiveDate) -> Streaks {
+    let mut dates = days.iter().map(|d| d.date_naive()).collect::<Vec<_>>();
+    dates.dedup();
+    // The latest streak is broken if neither today's nor yesterday's daily challenge was played.
+    let alive = dates
+        .first()
+        .is_some_and(|d| today - *d <= Duration::days(1));
+    let mut streaks = Streaks::default();
+    let mut run = 0;
+    for (i, day) in dates.iter().enumerate() {
+        run = match i.checked_sub(1).map(|p| dates[p] - *day) {
+            Some(gap) if gap == Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        if alive && run as usize == i + 1 {
+            streaks.current = run;
+        }
+        streaks.best = streaks.best.max(run);
+    }
+    streaks
+}
+
+/// Record the saved users' scores on today's daily challenge, as a scheduled job.
+pub(crate) async fn record_job(env: OsuEnv) -> Result<()> {
+    let client = env.client.with_priority(Priority::Background);
+    let Some(room) = client.daily_challenge().await? else {
+        return Ok(());
+    };
+    let Some(item) = room.playlist.first() else {
+        return Ok(());
+    };
+    let mut recorded = 0;
+    for u in env.saved_users.all().await? {
+        let score = match client.user_playlist_score(room.id, item.id, u.id).await {
+            Ok(Some(score)) => score,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!(
+                    user = u.id,
+                    "osu: cannot fetch the daily challenge score: {:#}",
+                    e
+                );
+                continue;
+            }
+        };
+        models::DailyChallengeScore {
+            room_id: room.id as i64,
+            user_id: u.id as i64,
+            starts_at: room.starts_at,
+            beatmap_id: item.beatmap_id as i64,
+            total_score: score.total_score as i64,
+            accuracy: score.accuracy,
+            max_combo: score.max_combo as i64,
+            mods: score.mods.acronyms().collect(),
+            rank: score.rank.to_string(),
+            pp: score.pp,
+        }
+        .store(&env.prelude.sql)
+        .await?;
+        recorded += 1;
+    }
+    tracing::info!(
+        room = room.id,
+        recorded,
+        "osu: recorded the daily challenge scores"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn days(dates: &[(i32, u32, u32)]) -> Vec<DateTime<Utc>> {
+        dates
+            .iter()
+            .map(|&(y, m, d)| Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_streaks() {
+        let today = NaiveDate::from_ymd_opt(2024, 8, 10).unwrap();
+        let s = |d: &[(i32, u32, u32)]| streaks(&days(d), today);
+        assert_eq!(s(&[]), Streaks::default());
+        // Played today and the two days before, after a longer streak.
+        assert_eq!(
+            s(&[
+                (2024, 8, 10),
+                (2024, 8, 9),
+                (2024, 8, 8),
+                (2024, 8, 1),
+                (2024, 7, 31),
+                (2024, 7, 30),
+                (2024, 7, 29),
+            ]),
+            Streaks {
+                current: 3,
+                best: 4
+            }
+        );
+        // Today's is not played yet.
+        assert_eq!(
+            s(&[(2024, 8, 9), (2024, 8, 8)]),
+            Streaks {
+                current: 2,
+                best: 2
+            }
+        );
+        // Broken two days ago.
+        assert_eq!(
+            s(&[(2024, 8, 8), (2024, 8, 7)]),
+            Streaks {
+                current: 0,
+                best: 2
+            }
+        );
+    }
+}
@@ -1,13 +1,13 @@
 use std::borrow::Cow;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, DurationRound, Utc};
 use serde::{Deserialize, Serialize};
-use serenity::model::id::{ChannelId, UserId};
+use serenity::model::id::{ChannelId, GuildId, UserId};
 
-use youmubot_db_sql::{models::osu as models, models::osu_user as model, Pool};
-use youmubot_prelude::*;
+use youmubot_db_sql::{models::osu as models, models::osu_user as model, transaction, Pool};
+use youmubot_prelude::{settings::OsuConfig, *};
 
-use crate::models::{Beatmap, Mode, Score};
+use crate::models::{Beatmap, Mode, Score, User};
 
 /// Save the user IDs.
 #[derive(Debug, Clone)]
@@ -29,11 +29,11 @@ impl OsuSavedUsers {
 impl OsuSavedUsers {
     /// Get all users
     pub async fn all(&self) -> Result<Vec<OsuUser>> {
-        let mut conn = self.pool.acquire().await?;
-        model::OsuUser::all(&mut *conn)
-            .map(|v| v.map(OsuUser::from).map_err(Error::from))
-            .try_collect()
-            .await
+        Ok(model::OsuUser::all(&self.pool)
+            .await?
+            .into_iter()
+            .map(OsuUser::from)
+            .collect())
     }
 
     /// Get an user by their user_id.
@@ -47,21 +47,34 @@ impl OsuSavedUsers {
 
     /// Save the given user.
     pub async fn save(&self, u: OsuUser) -> Result<()> {
-        let mut conn = self.pool.acquire().await?;
-        Ok(model::OsuUser::from(u).store(&mut *conn).await?)
+        let u = model::OsuUser::from(u);
+        transaction(&self.pool, |t| {
+            let u = u.clone();
+            Box::pin(async move { u.store(t).await })
+        })
+        .await?;
+        Ok(())
     }
 
     /// Save the given user as a completely new user.
     pub async fn new_user(&self, u: OsuUser) -> Result<()> {
-        let mut t = self.pool.begin().await?;
-        model::OsuUser::delete(u.user_id.get() as i64, &mut *t).await?;
-        model::OsuUser::from(u).store(&mut *t).await?;
-        t.commit().await?;
+        let u = model::OsuUser::from(u);
+        transaction(&self.pool, |t| {
+            let u = u.clone();
+            Box::pin(async move {
+                model::OsuUser::delete(u.user_id, &mut *t).await?;
+                u.store(t).await
+            })
+        })
+        .await?;
         Ok(())
     }
 }
 
-/// Save each channel's last requested beatmap.
+/// How many beatmaps are remembered in each channel.
+pub const CHANNEL_HISTORY_SIZE: i64 = 20;
+
+/// Save the history of each channel's requested beatmaps.
 #[derive(Debug, Clone)]
 pub struct OsuLastBeatmap(Pool);
 
@@ -76,67 +89,313 @@ impl OsuLastBeatmap {
 }
 
 impl OsuLastBeatmap {
-    pub async fn by_channel(&self, id: impl Into<ChannelId>) -> Result<Option<(Beatmap, Mode)>> {
+    /// Get the `n`-th latest beatmap requested in the channel, with 0 being the latest one.
+    pub async fn by_channel_nth(
+        &self,
+        id: impl Into<ChannelId>,
+        n: usize,
+    ) -> Result<Option<(Beatmap, Mode)>> {
         let last_beatmap =
-            models::LastBeatmap::by_channel_id(id.into().get() as i64, &self.0).await?;
+            models::LastBeatmap::nth_by_channel_id(id.into().get() as i64, n as i64, &self.0)
+                .await?;
         Ok(match last_beatmap {
             Some(lb) => Some((bincode::deserialize(&lb.beatmap[..])?, lb.mode.into())),
             None => None,
         })
     }
 
+    /// Get the beatmaps requested in the channel, latest first, with when they were requested.
+    pub async fn history(
+        &self,
+        id: impl Into<ChannelId>,
+    ) -> Result<Vec<(Beatmap, Mode, DateTime<Utc>)>> {
+        models::LastBeatmap::by_channel_id(id.into().get() as i64, CHANNEL_HISTORY_SIZE, &self.0)
+            .await?
+            .into_iter()
+            .map(|lb| {
+                Ok((
+                    bincode::deserialize(&lb.beatmap[..])?,
+                    lb.mode.into(),
+                    lb.saved_at,
+                ))
+            })
+            .collect()
+    }
+
     pub async fn save(
         &self,
+        guild: Option<GuildId>,
         channel: impl Into<ChannelId>,
         beatmap: &Beatmap,
         mode: Mode,
     ) -> Result<()> {
-        let b = models::LastBeatmap {
-            channel_id: channel.into().get() as i64,
-            beatmap: bincode::serialize(beatmap)?,
-            mode: mode as u8,
-        };
-        b.store(&self.0).await?;
+        let guild_id = guild.map(|g| g.get() as i64);
+        let channel_id = channel.into().get() as i64;
+        let beatmap = bincode::serialize(beatmap)?;
+        let saved_at = Utc::now();
+        transaction(&self.0, |t| {
+            let b = models::LastBeatmap {
+                id: 0,
+                channel_id,
+                guild_id,
+                beatmap: beatmap.clone(),
+                mode: mode as u8,
+                saved_at,
+            };
+            Box::pin(async move {
+                b.store(&mut *t).await?;
+                models::LastBeatmap::trim(channel_id, CHANNEL_HISTORY_SIZE, &mut *t).await?;
+                Ok(())
+            })
+        })
+        .await?;
         Ok(())
     }
 }
 
-/// Save each channel's last requested beatmap.
+/// The default number of days the saved scores are kept.
+const DEFAULT_RETENTION_DAYS: i64 = 90;
+/// The default number of scores kept per user.
+const DEFAULT_PER_USER: i64 = 1000;
+/// When the saved scores are pruned: once a day.
+pub(crate) const PRUNE_SCHEDULE: &str = "30 4 * * *";
+
+/// Save each user's scores on each beatmap, as queried by `check`.
+///
+/// Only the latest query of each user on each beatmap is kept. Scores older than the retention
+/// period, and the oldest scores of users past the per-user limit, are pruned periodically.
 #[derive(Debug, Clone)]
-pub struct OsuUserBests(Pool);
+pub struct OsuUserBests {
+    pool: Pool,
+    retention: chrono::Duration,
+    per_user: i64,
+}
 
 impl TypeMapKey for OsuUserBests {
     type Value = OsuUserBests;
 }
 
 impl OsuUserBests {
-    pub fn new(pool: Pool) -> Self {
-        Self(pool)
+    /// Create a new database wrapper.
+    ///
+    /// The retention policy is taken from the configuration.
+    pub fn new(pool: Pool, config: &OsuConfig) -> Self {
+        Self {
+            pool,
+            retention: chrono::Duration::days(
+                config
+                    .user_bests_retention
+                    .unwrap_or(DEFAULT_RETENTION_DAYS),
+            ),
+            per_user: config.user_bests_per_user.unwrap_or(DEFAULT_PER_USER),
+        }
     }
 }
 
 impl OsuUserBests {
+    /// Save the user's scores, replacing the previously saved scores on the same beatmaps.
     pub async fn save(
         &self,
         user: impl Into<UserId>,
         mode: Mode,
         scores: impl IntoIterator<Item = Score>,
     ) -> Result<()> {
-        let user = user.into();
-        scores
+        let user_id = user.into().get() as i64;
+        let mode = mode as u8;
+        // (beatmap id, mods, serialized score)
+        let scores = scores
             .into_iter()
-            .map(|score| models::UserBestScore {
-                user_id: user.get() as i64,
-                beatmap_id: score.beatmap_id as i64,
-                mode: mode as u8,
-                mods: score.mods.bits() as i64,
-                cached_at: Utc::now(),
-                score: bincode::serialize(&score).unwrap(),
+            .map(|s| {
+                (
+                    s.beatmap_id as i64,
+                    s.mods.bits() as i64,
+                    bincode::serialize(&s).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut beatmaps = scores.iter().map(|s| s.0).collect::<Vec<_>>();
+        beatmaps.sort_unstable();
+        beatmaps.dedup();
+
+        transaction(&self.pool, |t| {
+            let beatmaps = beatmaps.clone();
+            let scores = scores.clone();
+            Box::pin(async move {
+                for beatmap_id in beatmaps {
+                    models::UserBestScore::clear_map_user(beatmap_id, mode, user_id, &mut *t)
+                        .await?;
+                }
+                for (beatmap_id, mods, score) in scores {
+                    models::UserBestScore {
+                        user_id,
+                        beatmap_id,
+                        mode,
+                        mods,
+                        cached_at: Utc::now(),
+                        score,
+                    }
+                    .store(&mut *t)
+                    .await?;
+                }
+                Ok(())
             })
-            .map(|mut us| async move { us.store(&self.0).await })
-            .collect::<stream::FuturesUnordered<_>>()
-            .try_collect::<()>()
-            .await?;
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Delete the scores past the retention policy. Returns the number of deleted scores.
+    pub async fn prune(&self) -> Result<u64> {
+        let before = Utc::now() - self.retention;
+        let per_user = self.per_user;
+        Ok(transaction(&self.pool, |t| {
+            Box::pin(async move {
+                let expired = models::UserBestScore::delete_older_than(before, &mut *t).await?;
+                let excess = models::UserBestScore::delete_excess(per_user, &mut *t).await?;
+                Ok(expired + excess)
+            })
+        })
+        .await?)
+    }
+
+    /// Prune the saved scores, as a scheduled job.
+    pub(crate) async fn prune_job(self) -> Result<()> {
+        let deleted = self.prune().await?;
+        if deleted > 0 {
+            tracing::info!("osu: pruned {} saved scores", deleted);
+        }
+        Ok(())
+    }
+}
+
+/// How long hourly snapshots are kept before being downsampled to daily ones.
+const HOURLY_SNAPSHOTS_FOR: chrono::Duration = chrono::Duration::days(2);
+/// How long daily snapshots are kept before being downsampled to weekly ones.
+const DAILY_SNAPSHOTS_FOR: chrono::Duration = chrono::Duration::days(90);
+/// When the snapshots are downsampled: every 6 hours.
+pub(crate) const DOWNSAMPLE_SCHEDULE: &str = "15 */6 * * *";
+
+/// A snapshot of an user's statistics.
+#[derive(Debug, Clone)]
+pub struct StatsSnapshot {
+    pub taken_at: DateTime<Utc>,
+    pub rank: Option<u64>,
+    pub pp: Option<f64>,
+    pub accuracy: Option<f64>,
+    pub play_count: Option<u64>,
+    pub map_length: Option<f64>,
+}
+
+impl From<models::UserStatsSnapshot> for StatsSnapshot {
+    fn from(s: models::UserStatsSnapshot) -> Self {
+        Self {
+            taken_at: s.taken_at,
+            rank: s.rank.map(|r| r as u64),
+            pp: s.pp,
+            accuracy: s.accuracy,
+            play_count: s.play_count.map(|p| p as u64),
+            map_length: s.map_length,
+        }
+    }
+}
+
+/// Save snapshots of each user's statistics over time.
+///
+/// At most one snapshot per hour is kept. Snapshots older than two days are downsampled
+/// to one per day, and the ones older than 90 days to one per week.
+#[derive(Debug, Clone)]
+pub struct OsuStatsSnapshots {
+    pool: Pool,
+}
+
+impl TypeMapKey for OsuStatsSnapshots {
+    type Value = OsuStatsSnapshots;
+}
+
+impl OsuStatsSnapshots {
+    /// Create a new database wrapper.
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+}
+
+impl OsuStatsSnapshots {
+    /// Record the user's current statistics, replacing the snapshot of the same hour.
+    pub async fn record(&self, user: &User, mode: Mode, map_length: Option<f64>) -> Result<()> {
+        let now = Utc::now();
+        models::UserStatsSnapshot {
+            user_id: user.id as i64,
+            mode: mode as u8,
+            taken_at: now
+                .duration_trunc(chrono::Duration::hours(1))
+                .unwrap_or(now),
+            rank: Some(user.rank as i64).filter(|r| *r > 0),
+            pp: user.pp,
+            accuracy: Some(user.accuracy),
+            play_count: Some(user.play_count as i64),
+            map_length,
+        }
+        .store(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Get all snapshots of the user, oldest first.
+    pub async fn history(&self, user_id: u64, mode: Mode) -> Result<Vec<StatsSnapshot>> {
+        Ok(
+            models::UserStatsSnapshot::by_user(user_id as i64, mode as u8, &self.pool)
+                .await?
+                .into_iter()
+                .map(StatsSnapshot::from)
+                .collect(),
+        )
+    }
+
+    /// Get the latest snapshot of the user taken before the given time.
+    pub async fn latest_before(
+        &self,
+        user_id: u64,
+        mode: Mode,
+        before: DateTime<Utc>,
+    ) -> Result<Option<StatsSnapshot>> {
+        Ok(
+            models::UserStatsSnapshot::latest_before(
+                user_id as i64,
+                mode as u8,
+                before,
+                &self.pool,
+            )
+            .await?
+            .map(StatsSnapshot::from),
+        )
+    }
+
+    /// Downsample the old snapshots. Returns the number of deleted snapshots.
+    pub async fn downsample(&self) -> Result<u64> {
+        let now = Utc::now();
+        Ok(transaction(&self.pool, |t| {
+            Box::pin(async move {
+                let hourly = models::UserStatsSnapshot::downsample(
+                    "%Y-%m-%d",
+                    now - HOURLY_SNAPSHOTS_FOR,
+                    &mut *t,
+                )
+                .await?;
+                let daily = models::UserStatsSnapshot::downsample(
+                    "%Y-%W",
+                    now - DAILY_SNAPSHOTS_FOR,
+                    &mut *t,
+                )
+                .await?;
+                Ok(hourly + daily)
+            })
+        })
+        .await?)
+    }
+
+    /// Downsample the snapshots, as a scheduled job.
+    pub(crate) async fn downsample_job(self) -> Result<()> {
+        self.downsample().await?;
         Ok(())
     }
 }
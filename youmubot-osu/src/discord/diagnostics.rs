@@ -0,0 +1,123 @@
+use std::{fmt::Write as _, sync::Arc};
+
+use serenity::{
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandResult,
+    },
+    model::channel::Message,
+};
+
+use youmubot_prelude::*;
+
+use super::{announcer::Announcer, OsuEnv, ANNOUNCER_MODULE};
+use crate::ratelimit::Priority;
+
+#[group]
+#[prefix = "diag"]
+#[owners_only]
+#[description = "Inspect the internal state of the osu! module."]
+#[commands(diag_user, diag_beatmap, diag_poll)]
+struct OsuDiagnostics;
+
+#[command("user")]
+#[description = "Show the saved row of an user (yourself by default)."]
+#[usage = "[ping user]"]
+#[max_args(1)]
+pub async fn diag_user(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let target = args
+        .single::<UserId>()
+        .map(|u| u.0)
+        .unwrap_or(msg.author.id);
+    let Some(u) = env.saved_users.by_user_id(target).await? else {
+        msg.reply(
+            &ctx,
+            format!("{} has no saved osu! user.", target.mention()),
+        )
+        .await?;
+        return Ok(());
+    };
+    let mut s = String::from("```\n");
+    writeln!(s, "{:<24} {}", "discord id", u.user_id).unwrap();
+    writeln!(s, "{:<24} {}", "osu! id", u.id).unwrap();
+    writeln!(s, "{:<24} {}", "username", u.username).unwrap();
+    writeln!(s, "{:<24} {}", "last update", u.last_update.to_rfc3339()).unwrap();
+    writeln!(s, "{:<24} {:?}", "pp (std/taiko/ctb/mania)", u.pp).unwrap();
+    writeln!(
+        s,
+        "{:<24} {:?}",
+        "std weighted map length", u.std_weighted_map_length
+    )
+    .unwrap();
+    writeln!(s, "{:<24} {}", "failures", u.failures).unwrap();
+    s.push_str("```");
+    msg.reply(&ctx, s).await?;
+    Ok(())
+}
+
+#[command("beatmap")]
+#[description = "Show the cached copies of a beatmap, without fetching it."]
+#[usage = "[beatmap id]"]
+#[num_args(1)]
+pub async fn diag_beatmap(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let id = args.single::<u64>()?;
+    let (memory, db) = env.beatmaps.inspect(id).await?;
+    if memory.is_empty() && db.is_empty() {
+        msg.reply(&ctx, format!("Beatmap `{}` is not cached.", id))
+            .await?;
+        return Ok(());
+    }
+    let mut s = format!("Beatmap `{}`\n```\n", id);
+    for (mode, fetched_at, age) in memory {
+        writeln!(
+            s,
+            "memory   {:<8} fetched at {}, in memory for {}",
+            mode.as_str_new_site(),
+            fetched_at.to_rfc3339(),
+            Duration(std::time::Duration::from_secs(age.as_secs()))
+        )
+        .unwrap();
+    }
+    for (mode, cached_at) in db {
+        writeln!(
+            s,
+            "database {:<8} cached at {}",
+            mode.as_str_new_site(),
+            cached_at.to_rfc3339()
+        )
+        .unwrap();
+    }
+    s.push_str("```");
+    msg.reply(&ctx, s).await?;
+    Ok(())
+}
+
+#[command("poll")]
+#[description = "Run the announcer on an user right away, announcing their new scores."]
+#[usage = "[ping user]"]
+#[num_args(1)]
+pub async fn diag_poll(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    if !env.prelude.modules.enabled(ANNOUNCER_MODULE.name) {
+        return Err(Error::msg("the osu! announcer is disabled").into());
+    }
+    let target = args.single::<UserId>()?.0;
+    let Some(u) = env.saved_users.by_user_id(target).await? else {
+        msg.reply(
+            &ctx,
+            format!("{} has no saved osu! user.", target.mention()),
+        )
+        .await?;
+        return Ok(());
+    };
+    let announcer = Announcer::new(Arc::new(env.client.with_priority(Priority::Interactive)));
+    let reply = if announcer.poll_user(ctx, u).await? {
+        "User polled, new scores are announced."
+    } else {
+        "User skipped: they are in no server with the osu! announcer."
+    };
+    msg.reply(&ctx, reply).await?;
+    Ok(())
+}
@@ -7,6 +7,7 @@ mod scores {
     use youmubot_prelude::*;
 
     use crate::models::{Mode, Score};
+    use crate::Client as OsuHttpClient;
 
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     /// The style for the scores list to be displayed.
@@ -25,25 +26,33 @@ mod scores {
         type Err = Error;
 
         fn from_str(s: &str) -> Result<Self, Self::Err> {
-            match s {
-                "--table" => Ok(Self::Table),
-                "--grid" => Ok(Self::Grid),
-                _ => Err(Error::msg("unknown value")),
-            }
+            args::keyword(
+                "style",
+                s,
+                &[
+                    (&["--table", "table"], Self::Table),
+                    (&["--grid", "grid"], Self::Grid),
+                ],
+            )
         }
     }
 
     impl ScoreListStyle {
+        /// Display the scores, set on the server of `client`.
         pub async fn display_scores<'a>(
             self,
             scores: Vec<Score>,
             mode: Mode,
+            lazer: bool,
+            client: std::sync::Arc<OsuHttpClient>,
             ctx: &'a Context,
             m: &'a Message,
         ) -> CommandResult {
             match self {
                 ScoreListStyle::Table => table::display_scores_table(scores, mode, ctx, m).await,
-                ScoreListStyle::Grid => grid::display_scores_grid(scores, mode, ctx, m).await,
+                ScoreListStyle::Grid => {
+                    grid::display_scores_grid(scores, mode, lazer, client, ctx, m).await
+                }
             }
         }
     }
@@ -54,22 +63,36 @@ mod scores {
 
         use youmubot_prelude::*;
 
-        use crate::discord::{cache::save_beatmap, BeatmapWithMode, OsuEnv};
+        use crate::discord::{
+            cache::publish_beatmap, score_global_rank, score_hit_stats, theme::EmbedTheme,
+            BeatmapWithMode, OsuEnv,
+        };
         use crate::models::{Mode, Score};
+        use crate::Client as OsuHttpClient;
 
         pub async fn display_scores_grid<'a>(
             scores: Vec<Score>,
             mode: Mode,
+            lazer: bool,
+            client: std::sync::Arc<OsuHttpClient>,
             ctx: &'a Context,
             m: &'a Message,
         ) -> CommandResult {
+            let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+            let theme = env.embed_theme_for(m).await;
             if scores.is_empty() {
-                m.reply(&ctx, "No plays found").await?;
+                m.reply(&ctx, theme.tr("no-plays")).await?;
                 return Ok(());
             }
 
             paginate_reply(
-                Paginate { scores, mode },
+                Paginate {
+                    scores,
+                    mode,
+                    lazer,
+                    theme,
+                    client,
+                },
                 ctx,
                 m,
                 std::time::Duration::from_secs(60),
@@ -81,6 +104,10 @@ mod scores {
         pub struct Paginate {
             scores: Vec<Score>,
             mode: Mode,
+            lazer: bool,
+            theme: EmbedTheme,
+            /// The client of the scores' server, to look up their players.
+            client: std::sync::Arc<OsuHttpClient>,
         }
 
         #[async_trait]
@@ -95,7 +122,10 @@ mod scores {
                 let beatmap = env.beatmaps.get_beatmap(score.beatmap_id, mode).await?;
                 let content = env.oppai.get_beatmap(beatmap.beatmap_id).await?;
                 let bm = BeatmapWithMode(beatmap, mode);
-                let user = env
+                let hit_stats = score_hit_stats(&env, score, mode, &content).await;
+                let global_rank = score_global_rank(&env, score, mode).await;
+                let theme = &self.theme;
+                let user = self
                     .client
                     .user(crate::request::UserID::ID(score.user_id), |f| f)
                     .await?
@@ -104,13 +134,19 @@ mod scores {
                 msg.edit(
                     ctx,
                     EditMessage::new().embed({
-                        crate::discord::embeds::score_embed(score, &bm, &content, &user)
-                            .footer(format!("Page {}/{}", page + 1, self.scores.len()))
+                        crate::discord::embeds::score_embed(score, &bm, &content, &user, theme)
+                            .hit_stats(hit_stats)
+                            .global_rank(global_rank)
+                            .lazer(self.lazer)
+                            .footer(theme.trf(
+                                "page-footer",
+                                &[("page", &(page + 1)), ("total", &self.scores.len())],
+                            ))
                             .build()
                     }),
                 )
                 .await?;
-                save_beatmap(&env, msg.channel_id, &bm).await?;
+                publish_beatmap(&env, msg.guild_id, msg.channel_id, &bm).await;
 
                 // End
                 hourglass.delete(ctx).await?;
@@ -188,19 +224,20 @@ mod scores {
                 let hourglass = msg.react(ctx, '⌛').await?;
                 let plays = &self.scores[start..end];
                 let mode = self.mode;
+                let ids = plays.iter().map(|p| p.beatmap_id).collect::<Vec<_>>();
+                let fetched = meta_cache.get_beatmaps(&ids, mode).await?;
                 let beatmaps = plays
                     .iter()
-                    .map(|play| async move {
-                        let beatmap = meta_cache.get_beatmap(play.beatmap_id, mode).await?;
-                        let info = {
-                            let b = oppai.get_beatmap(beatmap.beatmap_id).await?;
-                            b.get_info_with(mode, play.mods).ok()
-                        };
-                        Ok((beatmap, info)) as Result<(Beatmap, Option<BeatmapInfo>)>
+                    .map(|play| {
+                        let beatmap = fetched.get(&play.beatmap_id).cloned();
+                        async move {
+                            let beatmap = beatmap?;
+                            let info = oppai.get_info(&beatmap, mode, play.mods).await.ok();
+                            Some((beatmap, info))
+                        }
                     })
                     .collect::<stream::FuturesOrdered<_>>()
-                    .map(|v| v.ok())
-                    .collect::<Vec<_>>();
+                    .collect::<Vec<Option<(Beatmap, Option<BeatmapInfo>)>>>();
 
                 let pps = plays
                     .iter()
@@ -242,13 +279,12 @@ mod scores {
                         match p.rank {
                             crate::models::Rank::F => beatmaps[i]
                                 .as_ref()
-                                .and_then(|(_, i)| i.map(|i| i.objects))
-                                .map(|total| {
-                                    (p.count_300 + p.count_100 + p.count_50 + p.count_miss) as f64
-                                        / (total as f64)
-                                        * 100.0
+                                .map(|(b, info)| {
+                                    info.map(|info| info.objects as u64)
+                                        .unwrap_or_else(|| b.difficulty.total_objects())
                                 })
-                                .map(|p| format!("{:.0}% F", p).into())
+                                .and_then(|total| p.map_progress(mode, total))
+                                .map(|p| format!("failed at {:.0}%", p * 100.0).into())
                                 .unwrap_or_else(|| "F".into()),
                             crate::models::Rank::SS => "SS".into(),
                             crate::models::Rank::S => if p.perfect {
@@ -296,7 +332,7 @@ mod scores {
                             format!("{}", pp),
                             format!("{:.2}%", play.accuracy(self.mode)),
                             format!("{}", rank),
-                            play.mods.to_string(),
+                            play.mods_text(),
                             beatmap.clone(),
                         ]
                     })
@@ -334,32 +370,53 @@ mod beatmapset {
 
     use crate::discord::OsuEnv;
     use crate::{
-        discord::{cache::save_beatmap, oppai_cache::BeatmapInfoWithPP, BeatmapWithMode},
+        discord::{
+            cache::publish_beatmap, oppai_cache::BeatmapInfoWithPP, theme::EmbedTheme,
+            BeatmapWithMode,
+        },
         models::{Beatmap, Mode, Mods},
     };
 
     const SHOW_ALL_EMOTE: &str = "🗒️";
 
+    /// Display a beatmapset.
+    /// If `mode` is given, only difficulties playable in that mode are shown,
+    /// with native difficulties first and converts after.
     pub async fn display_beatmapset(
         ctx: &Context,
-        beatmapset: Vec<Beatmap>,
+        mut beatmapset: Vec<Beatmap>,
         mode: Option<Mode>,
         mods: Option<Mods>,
         reply_to: &Message,
         message: impl AsRef<str>,
     ) -> Result<bool> {
         let mods = mods.unwrap_or(Mods::NOMOD);
+        if let Some(mode) = mode {
+            beatmapset.retain(|b| b.playable_in(mode));
+            beatmapset.sort_by_key(|b| b.is_convert(mode));
+        }
 
         if beatmapset.is_empty() {
             return Ok(false);
         }
 
+        let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+        // Publish the first difficulty right away, the paginator publishes the others as they are shown.
+        let first = &beatmapset[0];
+        publish_beatmap(
+            &env,
+            reply_to.guild_id,
+            reply_to.channel_id,
+            &BeatmapWithMode(first.clone(), mode.unwrap_or(first.mode)),
+        )
+        .await;
         let p = Paginate {
             infos: vec![None; beatmapset.len()],
             maps: beatmapset,
             mode,
             mods,
             message: message.as_ref().to_owned(),
+            theme: env.embed_theme_for(reply_to).await,
         };
 
         let ctx = ctx.clone();
@@ -378,6 +435,7 @@ mod beatmapset {
         mode: Option<Mode>,
         mods: Mods,
         message: String,
+        theme: EmbedTheme,
     }
 
     impl Paginate {
@@ -385,9 +443,8 @@ mod beatmapset {
             let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
 
             env.oppai
-                .get_beatmap(b.beatmap_id)
+                .get_possible_pp(b, self.mode.unwrap_or(b.mode), self.mods)
                 .await
-                .and_then(move |v| v.get_possible_pp_with(self.mode.unwrap_or(b.mode), self.mods))
         }
     }
 
@@ -400,11 +457,28 @@ mod beatmapset {
         async fn render(&mut self, page: u8, ctx: &Context, msg: &mut Message) -> Result<bool> {
             let page = page as usize;
             if page == self.maps.len() {
+                // Converted difficulties need their star ratings recalculated.
+                let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+                let mut stars = Vec::with_capacity(self.maps.len());
+                for map in &self.maps {
+                    let mode = self.mode.unwrap_or(map.mode);
+                    stars.push(if map.is_convert(mode) {
+                        env.oppai
+                            .get_info(map, mode, Mods::NOMOD)
+                            .await
+                            .map(|v| v.stars)
+                            .pls_ok()
+                    } else {
+                        None
+                    });
+                }
                 msg.edit(
                     ctx,
                     EditMessage::new().embed(crate::discord::embeds::beatmapset_embed(
                         &self.maps[..],
                         self.mode,
+                        &stars[..],
+                        &self.theme,
                     )),
                 )
                 .await?;
@@ -430,6 +504,7 @@ mod beatmapset {
                            self.mode.unwrap_or(map.mode),
                            self.mods,
                            info,
+                           &self.theme,
                        )
                            .footer({
                                CreateEmbedFooter::new(format!(
@@ -443,13 +518,13 @@ mod beatmapset {
             )
                 .await?;
             let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
-            save_beatmap(
+            publish_beatmap(
                 &env,
+                msg.guild_id,
                 msg.channel_id,
                 &BeatmapWithMode(map.clone(), self.mode.unwrap_or(map.mode)),
             )
-            .await
-            .pls_ok();
+            .await;
 
             Ok(true)
         }
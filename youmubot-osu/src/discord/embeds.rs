@@ -1,7 +1,10 @@
-use super::BeatmapWithMode;
+use super::{BeatmapWithMode, COVER_FILENAME, SCORE_CARD_FILENAME, STRAIN_GRAPH_FILENAME};
 use crate::{
     discord::oppai_cache::{Accuracy, BeatmapContent, BeatmapInfo, BeatmapInfoWithPP},
-    models::{Beatmap, Difficulty, Mode, Mods, Rank, Score, User},
+    discord::theme::EmbedTheme,
+    models::{Beatmap, Difficulty, DiscordTimestamp, Mode, Mods, Rank, Score, User},
+    osutrack,
+    replay::HitStats,
 };
 use serenity::{
     builder::{CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter},
@@ -23,39 +26,76 @@ pub(crate) fn grouped_number(num: u64) -> String {
     b.build()
 }
 
-fn beatmap_description(b: &Beatmap) -> String {
+/// Draws a text progress bar of the given width, e.g. `▰▰▰▱▱`.
+fn progress_bar(ratio: f64, width: usize) -> String {
+    let filled = ((ratio.clamp(0.0, 1.0) * width as f64).round() as usize).min(width);
+    format!("{}{}", "▰".repeat(filled), "▱".repeat(width - filled))
+}
+
+/// Draws a text sparkline of the given rank history, where higher bars mean better ranks.
+/// The history is sampled down to at most `width` points.
+fn rank_sparkline(history: &[u64], width: usize) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let step = ((history.len() + width - 1) / width.max(1)).max(1);
+    let mut points = history.iter().copied().step_by(step).collect::<Vec<_>>();
+    // The latest rank is always shown, even if the sampling skipped it.
+    if !history.is_empty() && (history.len() - 1) % step != 0 {
+        points.extend(history.last().copied());
+    }
+    let (best, worst) = match (points.iter().min(), points.iter().max()) {
+        (Some(&b), Some(&w)) => (b, w),
+        _ => return String::new(),
+    };
+    points
+        .iter()
+        .map(|&rank| {
+            if best == worst {
+                BARS[BARS.len() - 1]
+            } else {
+                let ratio = (worst - rank) as f64 / (worst - best) as f64;
+                BARS[(ratio * (BARS.len() - 1) as f64).round() as usize]
+            }
+        })
+        .collect()
+}
+
+fn beatmap_description(b: &Beatmap, theme: &EmbedTheme) -> String {
+    let tags = b
+        .tags
+        .iter()
+        .map(|v| MessageBuilder::new().push_mono_safe(v).build())
+        .take(10)
+        .chain(std::iter::once("...".to_owned()))
+        .collect::<Vec<_>>()
+        .join(" ");
     MessageBuilder::new()
         .push_bold_line(b.approval.to_string())
-        .push({
-            let link = b.download_link(false);
-            format!(
-                "Download: [[Link]]({}) [[No Video]]({}?noVideo=1) [[Bloodcat]]({})",
-                link,
-                link,
-                b.download_link(true),
-            )
-        })
-        .push_line(format!(" [[Beatmapset]]({})", b.beatmapset_link()))
-        .push("Language: ")
-        .push_bold(b.language.to_string())
-        .push(" | Genre: ")
-        .push_bold_line(b.genre.to_string())
+        .push_line(theme.trf(
+            "beatmap-submitted",
+            &[
+                ("submitted", &b.submit_date.relative_time()),
+                ("updated", &b.last_update.relative_time()),
+            ],
+        ))
+        .push_line(theme.trf(
+            "beatmap-download",
+            &[
+                ("link", &b.download_link(false)),
+                ("mirror", &b.download_link(true)),
+                ("beatmapset", &b.beatmapset_link()),
+            ],
+        ))
+        .push_line(theme.trf(
+            "beatmap-language-genre",
+            &[("language", &b.language), ("genre", &b.genre)],
+        ))
         .push(
             b.source
                 .as_ref()
-                .map(|v| format!("Source: **{}**\n", v))
+                .map(|v| format!("{}\n", theme.trf("beatmap-source", &[("source", v)])))
                 .unwrap_or_else(|| "".to_owned()),
         )
-        .push("Tags: ")
-        .push_line(
-            b.tags
-                .iter()
-                .map(|v| MessageBuilder::new().push_mono_safe(v).build())
-                .take(10)
-                .chain(std::iter::once("...".to_owned()))
-                .collect::<Vec<_>>()
-                .join(" "),
-        )
+        .push_line(theme.trf("beatmap-tags", &[("tags", &tags)]))
         .build()
 }
 
@@ -63,10 +103,12 @@ pub fn beatmap_offline_embed(
     b: &'_ crate::discord::oppai_cache::BeatmapContent,
     m: Mode,
     mods: Mods,
+    theme: &EmbedTheme,
 ) -> Result<CreateEmbed> {
     let bm = b.content.clone();
     let metadata = b.metadata.clone();
-    let (info, pp) = b.get_possible_pp_with(m, mods)?;
+    let pp_info = b.get_possible_pp_with(m, mods)?;
+    let info = pp_info.info;
 
     let total_length = if !bm.hit_objects.is_empty() {
         Duration::from_millis(
@@ -93,7 +135,7 @@ pub fn beatmap_offline_embed(
         drain_length: total_length, // It's hard to calculate so maybe just skip...
         total_length,
     }
-    .apply_mods(mods, info.stars);
+    .apply_mods(m, mods, info.stars);
     Ok(
         CreateEmbed::new()
             .title(beatmap_title(
@@ -106,21 +148,114 @@ pub fn beatmap_offline_embed(
                 CreateEmbedAuthor::new(&metadata.creator)
                     .url(format!("https://osu.ppy.sh/users/{}", metadata.creator))
             })
-            .color(0xffb6c1)
+            .color(theme.color)
             .field(
-                "Calculated pp",
-                format!(
-                    "95%: **{:.2}**pp, 98%: **{:.2}**pp, 99%: **{:.2}**pp, 100%: **{:.2}**pp",
-                    pp[0], pp[1], pp[2], pp[3]
-                ),
+                pp_table_title(&pp_info, theme),
+                format!("{}{}", mods_line(theme, mods), pp_table(&pp_info)),
                 false,
             )
-            .field("Information", diff.format_info(m, mods, None), false), // .description(beatmap_description(b))
+            .field(
+                theme.tr("beatmap-information"),
+                diff.format_info(m, mods, None, None),
+                false,
+            ), // .description(beatmap_description(b))
+    )
+}
+
+/// An embed listing all the difficulties of an unsubmitted beatmapset (e.g. from an `.osz`),
+/// with their star ratings and FC pp. Returns `None` if there are no difficulties.
+pub fn beatmapset_offline_embed(
+    diffs: &[(BeatmapContent, BeatmapInfoWithPP)],
+    mods: Mods,
+    theme: &EmbedTheme,
+) -> Option<CreateEmbed> {
+    let (first, _) = diffs.first()?;
+    let metadata = &first.metadata;
+    let mut description = MessageBuilder::new();
+    description.push(mods_line(theme, mods));
+    for (b, info) in diffs {
+        let pp = info
+            .pp_curve()
+            .map(|(acc, pp)| format!("{}%: {:.0}pp", acc, pp))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        description
+            .push("**[")
+            .push_safe(&b.metadata.version)
+            .push("]** ")
+            .push_line(theme.trf(
+                "offline-difficulty",
+                &[
+                    ("mode", &info.mode),
+                    ("stars", &format!("{:.2}", info.info.stars)),
+                    ("combo", &info.info.max_combo),
+                ],
+            ))
+            .push_line(format!("> {}", pp));
+    }
+    Some(
+        CreateEmbed::new()
+            .title(
+                MessageBuilder::new()
+                    .push_bold_safe(&metadata.artist)
+                    .push(" - ")
+                    .push_bold_safe(&metadata.title)
+                    .push(if mods == Mods::NOMOD {
+                        "".to_owned()
+                    } else {
+                        format!(" {}", mods)
+                    })
+                    .build(),
+            )
+            .author(
+                CreateEmbedAuthor::new(&metadata.creator)
+                    .url(format!("https://osu.ppy.sh/users/{}", metadata.creator)),
+            )
+            .color(theme.color)
+            .description(description.build())
+            .footer(CreateEmbedFooter::new(
+                theme.trf("offline-footer", &[("count", &diffs.len())]),
+            )),
     )
 }
 
 // Some helper functions here
 
+/// A `**Mods**: ...` line rendered with the mod emojis, if they are configured.
+/// Embed titles cannot render custom emojis, so this goes into the body.
+fn mods_line(theme: &EmbedTheme, mods: Mods) -> String {
+    if theme.emojis.is_empty() || mods == Mods::NOMOD {
+        return "".to_owned();
+    }
+    format!(
+        "{}\n",
+        theme.trf("beatmap-mods", &[("mods", &theme.emojis.format(mods))])
+    )
+}
+
+/// The title of the pp table, e.g. `FC pp (osu!taiko +HDHR)`.
+fn pp_table_title(info: &BeatmapInfoWithPP, theme: &EmbedTheme) -> String {
+    let mode = if info.mods == Mods::NOMOD {
+        info.mode.to_string()
+    } else {
+        format!("{} {}", info.mode, info.mods)
+    };
+    theme.trf("beatmap-pp-table", &[("mode", &mode)])
+}
+
+/// Format the accuracy → pp curve as a small table.
+fn pp_table(info: &BeatmapInfoWithPP) -> String {
+    let (accs, pps): (Vec<_>, Vec<_>) = info
+        .pp_curve()
+        .map(|(acc, pp)| {
+            let (acc, pp) = (format!("{}%", acc), format!("{:.2}pp", pp));
+            let w = acc.len().max(pp.len());
+            (format!("{:>w$}", acc, w = w), format!("{:>w$}", pp, w = w))
+        })
+        .unzip();
+    format!("```\n{}\n{}\n```", accs.join(" | "), pps.join(" | "))
+}
+
 /// Create a properly formatted beatmap title, in the `Artist - Title [Difficulty] +mods` format.
 fn beatmap_title(
     artist: impl AsRef<str>,
@@ -144,9 +279,15 @@ fn beatmap_title(
         .build()
 }
 
-pub fn beatmap_embed(b: &'_ Beatmap, m: Mode, mods: Mods, info: BeatmapInfoWithPP) -> CreateEmbed {
-    let diff = b.difficulty.apply_mods(mods, info.0.stars);
-    CreateEmbed::new()
+pub fn beatmap_embed(
+    b: &'_ Beatmap,
+    m: Mode,
+    mods: Mods,
+    info: BeatmapInfoWithPP,
+    theme: &EmbedTheme,
+) -> CreateEmbed {
+    let diff = b.difficulty_with(m, mods, info.info.stars);
+    let embed = CreateEmbed::new()
         .title(beatmap_title(&b.artist, &b.title, &b.difficulty_name, mods))
         .author(
             CreateEmbedAuthor::new(&b.creator)
@@ -154,26 +295,49 @@ pub fn beatmap_embed(b: &'_ Beatmap, m: Mode, mods: Mods, info: BeatmapInfoWithP
                 .icon_url(format!("https://a.ppy.sh/{}", b.creator_id)),
         )
         .url(b.link())
-        .image(b.cover_url())
-        .color(0xffb6c1)
-        .fields({
-            let pp = info.1;
-            std::iter::once((
-                "Calculated pp",
-                format!(
-                    "95%: **{:.2}**pp, 98%: **{:.2}**pp, 99%: **{:.2}**pp, 100%: **{:.2}**pp",
-                    pp[0], pp[1], pp[2], pp[3]
-                ),
-                false,
-            ))
-        })
-        .field("Information", diff.format_info(m, mods, b), false)
-        .description(beatmap_description(b))
+        .color(theme.color)
+        .field(
+            pp_table_title(&info, theme),
+            format!("{}{}", mods_line(theme, mods), pp_table(&info)),
+            false,
+        )
+        .field(
+            theme.tr("beatmap-information"),
+            diff.format_info(m, mods, b, None),
+            false,
+        )
+        .description(beatmap_description(b, theme));
+    theme.image(embed, b.cover_url())
+}
+
+/// Show the attached strain graph as the embed's image, in place of the cover.
+pub(crate) fn with_strain_graph(embed: CreateEmbed, attached: bool) -> CreateEmbed {
+    if attached {
+        embed.image(format!("attachment://{}", STRAIN_GRAPH_FILENAME))
+    } else {
+        embed
+    }
+}
+
+/// Show the cover attached from the asset cache as the embed's image, in place of the CDN link.
+pub(crate) fn with_cover(embed: CreateEmbed, attached: bool) -> CreateEmbed {
+    if attached {
+        embed.image(format!("attachment://{}", COVER_FILENAME))
+    } else {
+        embed
+    }
 }
 
 const MAX_DIFFS: usize = 25 - 4;
 
-pub fn beatmapset_embed(bs: &'_ [Beatmap], m: Option<Mode>) -> CreateEmbed {
+/// Embed all difficulties of a beatmapset.
+/// `stars` optionally overrides the star rating of each difficulty, e.g. with its converted star rating.
+pub fn beatmapset_embed(
+    bs: &'_ [Beatmap],
+    m: Option<Mode>,
+    stars: &[Option<f64>],
+    theme: &EmbedTheme,
+) -> CreateEmbed {
     let too_many_diffs = bs.len() > MAX_DIFFS;
     let b: &Beatmap = &bs[0];
     let mut m = CreateEmbed::new()
@@ -193,28 +357,48 @@ pub fn beatmapset_embed(bs: &'_ [Beatmap], m: Option<Mode>) -> CreateEmbed {
             "https://osu.ppy.sh/beatmapsets/{}",
             b.beatmapset_id,
         ))
-        .image(format!(
-            "https://assets.ppy.sh/beatmaps/{}/covers/cover.jpg",
-            b.beatmapset_id
-        ))
-        .color(0xffb6c1)
-        .description(beatmap_description(b))
-        .fields(bs.iter().rev().take(MAX_DIFFS).rev().map(|b: &Beatmap| {
-            (
-                format!("[{}]", b.difficulty_name),
-                b.difficulty
-                    .format_info(m.unwrap_or(b.mode), Mods::NOMOD, b),
-                false,
-            )
-        }));
+        .color(theme.color)
+        .description(beatmap_description(b, theme))
+        .fields(bs.iter().enumerate().rev().take(MAX_DIFFS).rev().map(
+            |(i, b): (usize, &Beatmap)| {
+                let mode = m.unwrap_or(b.mode);
+                let stars = stars
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .unwrap_or(b.difficulty.stars);
+                (
+                    if b.is_convert(mode) {
+                        theme.trf(
+                            "beatmap-converted",
+                            &[("difficulty", &b.difficulty_name), ("mode", &mode)],
+                        )
+                    } else {
+                        format!("[{}]", b.difficulty_name)
+                    },
+                    b.difficulty_with(mode, Mods::NOMOD, stars).format_info(
+                        mode,
+                        Mods::NOMOD,
+                        b,
+                        None,
+                    ),
+                    false,
+                )
+            },
+        ));
     if too_many_diffs {
-        m = m.footer(CreateEmbedFooter::new(format!(
-            "This map has {} diffs, we are showing the last {}.",
-            bs.len(),
-            MAX_DIFFS
+        m = m.footer(CreateEmbedFooter::new(theme.trf(
+            "beatmapset-too-many-diffs",
+            &[("count", &bs.len()), ("shown", &MAX_DIFFS)],
         )));
     }
-    m
+    theme.image(
+        m,
+        format!(
+            "https://assets.ppy.sh/beatmaps/{}/covers/cover.jpg",
+            b.beatmapset_id
+        ),
+    )
 }
 
 pub(crate) struct ScoreEmbedBuilder<'a> {
@@ -224,10 +408,21 @@ pub(crate) struct ScoreEmbedBuilder<'a> {
     u: &'a User,
     top_record: Option<u8>,
     world_record: Option<u16>,
+    hit_stats: Option<HitStats>,
+    global_rank: Option<u32>,
+    theme: &'a EmbedTheme,
+    score_card: bool,
     footer: Option<String>,
+    lazer: bool,
 }
 
 impl<'a> ScoreEmbedBuilder<'a> {
+    /// If set, show the lazer (standardised) score even if the score was set on stable.
+    /// Scores set on lazer are always shown this way.
+    pub fn lazer(&mut self, lazer: bool) -> &mut Self {
+        self.lazer |= lazer;
+        self
+    }
     pub fn top_record(&mut self, rank: u8) -> &mut Self {
         self.top_record = Some(rank);
         self
@@ -236,6 +431,19 @@ impl<'a> ScoreEmbedBuilder<'a> {
         self.world_record = Some(rank);
         self
     }
+    pub fn hit_stats(&mut self, stats: Option<HitStats>) -> &mut Self {
+        self.hit_stats = stats;
+        self
+    }
+    pub fn global_rank(&mut self, rank: Option<u32>) -> &mut Self {
+        self.global_rank = rank;
+        self
+    }
+    /// Show the attached score card as the embed's image.
+    pub fn score_card(&mut self, attached: bool) -> &mut Self {
+        self.score_card = attached;
+        self
+    }
     pub fn footer(&mut self, footer: impl Into<String>) -> &mut Self {
         self.footer = Some(footer.into());
         self
@@ -247,6 +455,7 @@ pub(crate) fn score_embed<'a>(
     bm: &'a BeatmapWithMode,
     content: &'a BeatmapContent,
     u: &'a User,
+    theme: &'a EmbedTheme,
 ) -> ScoreEmbedBuilder<'a> {
     ScoreEmbedBuilder {
         s,
@@ -255,7 +464,12 @@ pub(crate) fn score_embed<'a>(
         u,
         top_record: None,
         world_record: None,
+        hit_stats: None,
+        global_rank: None,
+        theme,
+        score_card: false,
         footer: None,
+        lazer: s.mods.contains(Mods::LAZER),
     }
 }
 
@@ -263,6 +477,7 @@ impl<'a> ScoreEmbedBuilder<'a> {
     #[allow(clippy::many_single_char_names)]
     pub fn build(&mut self) -> CreateEmbed {
         let mode = self.bm.mode();
+        let theme = self.theme;
         let b = &self.bm.0;
         let s = self.s;
         let content = self.content;
@@ -277,19 +492,35 @@ impl<'a> ScoreEmbedBuilder<'a> {
             Rank::SS | Rank::SSH => "SS".to_string(),
             _ if s.perfect => format!("{:.2}% FC", accuracy),
             Rank::F => {
-                let display = info
-                    .map(|info| {
-                        ((s.count_300 + s.count_100 + s.count_50 + s.count_miss) as f64)
-                            / (info.objects as f64)
-                            * 100.0
+                let total_objects = info
+                    .map(|info| info.objects as u64)
+                    .unwrap_or_else(|| b.difficulty.total_objects());
+                let display = s
+                    .map_progress(mode, total_objects)
+                    .map(|p| {
+                        theme.trf(
+                            "score-failed-at",
+                            &[("progress", &format!("{:.0}", p * 100.0))],
+                        )
                     })
-                    .map(|p| format!("FAILED @ {:.2}%", p))
-                    .unwrap_or_else(|| "FAILED".to_owned());
-                format!("{:.2}% {} combo [{}]", accuracy, s.max_combo, display)
+                    .unwrap_or_else(|| theme.tr("score-failed").to_owned());
+                theme.trf(
+                    "score-line-failed",
+                    &[
+                        ("accuracy", &format!("{:.2}", accuracy)),
+                        ("combo", &s.max_combo),
+                        ("status", &display),
+                    ],
+                )
             }
-            v => format!(
-                "{:.2}% {}x {} miss {} rank",
-                accuracy, s.max_combo, s.count_miss, v
+            v => theme.trf(
+                "score-line",
+                &[
+                    ("accuracy", &format!("{:.2}", accuracy)),
+                    ("combo", &s.max_combo),
+                    ("misses", &s.count_miss),
+                    ("rank", &v),
+                ],
             ),
         };
         let pp = s.pp.map(|pp| (pp, format!("{:.2}pp", pp))).or_else(|| {
@@ -314,8 +545,12 @@ impl<'a> ScoreEmbedBuilder<'a> {
                 .ok()
                 .filter(|&v| pp.as_ref().map(|&(origin, _)| origin < v).unwrap_or(false))
                 .and_then(|value| {
-                    pp.as_ref()
-                        .map(|(_, original)| format!("{} ({:.2}pp if FC?)", original, value))
+                    pp.as_ref().map(|(_, original)| {
+                        theme.trf(
+                            "score-pp-if-fc",
+                            &[("pp", original), ("fc", &format!("{:.2}", value))],
+                        )
+                    })
                 })
                 .or_else(|| pp.map(|v| v.1))
         } else {
@@ -327,12 +562,19 @@ impl<'a> ScoreEmbedBuilder<'a> {
                     let after_pp = u.pp.unwrap();
                     let effective_pp = full_pp * (0.95f64).powi(top as i32 - 1);
                     let before_pp = after_pp - effective_pp;
-                    format!(
-                        "**pp gained**: **{:.2}**pp (+**{:.2}**pp | {:.2}pp \\➡️ {:.2}pp)",
-                        full_pp, effective_pp, before_pp, after_pp
+                    theme.trf(
+                        "score-pp-gained-top",
+                        &[
+                            ("pp", &format!("{:.2}", full_pp)),
+                            ("effective", &format!("{:.2}", effective_pp)),
+                            ("before", &format!("{:.2}", before_pp)),
+                            ("after", &format!("{:.2}", after_pp)),
+                        ],
                     )
                 })
-                .unwrap_or_else(|| format!("**pp gained**: **{:.2}**pp", full_pp))
+                .unwrap_or_else(|| {
+                    theme.trf("score-pp-gained", &[("pp", &format!("{:.2}", full_pp))])
+                })
         });
         let score_line = pp
             .map(|pp| format!("{} | {}", &score_line, pp))
@@ -344,17 +586,34 @@ impl<'a> ScoreEmbedBuilder<'a> {
             .unwrap_or_else(|| format!("**{}x**", s.max_combo));
         let top_record = self
             .top_record
-            .map(|v| format!(" | #{} top record!", v))
+            .map(|v| format!(" | {}", theme.trf("score-top-record", &[("rank", &v)])))
             .unwrap_or_else(|| "".to_owned());
         let world_record = self
             .world_record
-            .map(|v| format!(" | #{} on Global Rankings!", v))
+            .map(|v| format!(" | {}", theme.trf("score-world-record", &[("rank", &v)])))
             .unwrap_or_else(|| "".to_owned());
-        let diff = b.difficulty.apply_mods(s.mods, stars);
+        let links = self
+            .global_rank
+            .or(s.global_rank)
+            .map(|rank| {
+                let global = theme.trf("score-global-rank", &[("rank", &rank)]);
+                match s.link() {
+                    Some(link) => format!("[{}]({})", global, link),
+                    None => global,
+                }
+            })
+            .into_iter()
+            .chain(
+                s.replay_link()
+                    .map(|link| format!("[[{}]]({})", theme.tr("score-replay"), link)),
+            )
+            .map(|v| format!(" • {}", v))
+            .collect::<String>();
+        let diff = b.difficulty_with(mode, s.mods, stars);
         let creator = if b.difficulty_name.contains("'s") {
             "".to_owned()
         } else {
-            format!("by {} ", b.creator)
+            format!("{} ", theme.trf("score-by", &[("creator", &b.creator)]))
         };
         let mut m = CreateEmbed::new()
             .author(
@@ -362,7 +621,7 @@ impl<'a> ScoreEmbedBuilder<'a> {
                     .url(u.link())
                     .icon_url(u.avatar_url()),
             )
-            .color(0xffb6c1)
+            .color(theme.color)
             .title(
                 MessageBuilder::new()
                     .push_safe(&u.username)
@@ -373,7 +632,7 @@ impl<'a> ScoreEmbedBuilder<'a> {
                     .push(" [")
                     .push_safe(&b.difficulty_name)
                     .push("] ")
-                    .push(s.mods.to_string())
+                    .push(s.mods_text())
                     .push(" ")
                     .push(format!("({:.2}\\*)", stars))
                     .push(" ")
@@ -385,39 +644,73 @@ impl<'a> ScoreEmbedBuilder<'a> {
                     .build(),
             )
             .description(format!(
-                r#"**Played**: {}
-{}"#,
-                s.date.format("<t:%s:R>"),
+                "{}{}\n{}{}",
+                theme.trf("score-played", &[("date", &s.date.relative_time())]),
+                links,
+                mods_line(theme, s.mods),
                 pp_gained.as_ref().map(|v| &v[..]).unwrap_or(""),
             ))
-            .thumbnail(b.thumbnail_url())
             .field(
-                "Score stats",
+                theme.tr("score-stats"),
                 format!(
-                    "**{}** | {} | **{:.2}%**",
-                    grouped_number(s.score.unwrap_or(s.normalized_score as u64)),
+                    "**{}**{} | {} | **{:.2}%**",
+                    grouped_number(s.display_score(self.lazer)),
+                    if self.lazer {
+                        format!(" {}", theme.tr("score-lazer"))
+                    } else {
+                        "".to_owned()
+                    },
                     max_combo,
                     accuracy
                 ),
                 true,
             )
             .field(
-                "300s | 100s | 50s | misses",
+                theme.tr("score-hits"),
                 format!(
                     "**{}** ({}) | **{}** ({}) | **{}** | **{}**",
                     s.count_300, s.count_geki, s.count_100, s.count_katu, s.count_50, s.count_miss
                 ),
                 true,
             )
-            .field("Map stats", diff.format_info(mode, s.mods, b), false);
+            .fields(self.hit_stats.map(|stats| {
+                (
+                    theme.tr("score-hit-error"),
+                    theme.trf(
+                        "score-hit-error-value",
+                        &[
+                            ("ur", &format!("{:.2}", stats.unstable_rate)),
+                            ("average", &format!("{:+.2}", stats.average_error)),
+                            (
+                                "timing",
+                                &theme.tr(if stats.average_error < 0.0 {
+                                    "score-early"
+                                } else {
+                                    "score-late"
+                                }),
+                            ),
+                        ],
+                    ),
+                    true,
+                )
+            }))
+            .field(
+                theme.tr("score-map-stats"),
+                diff.format_info(mode, s.mods, b, Some((accuracy, s.max_combo))),
+                false,
+            );
         let mut footer = self.footer.take().unwrap_or_default();
         if mode != Mode::Std && s.mods != Mods::NOMOD {
-            footer += " Star difficulty does not reflect game mods.";
+            footer += " ";
+            footer += theme.tr("score-stars-without-mods");
         }
         if !footer.is_empty() {
             m = m.footer(CreateEmbedFooter::new(footer));
         }
-        m
+        if self.score_card {
+            m = m.image(format!("attachment://{}", SCORE_CARD_FILENAME));
+        }
+        theme.thumbnail(m, b.thumbnail_url())
     }
 }
 
@@ -425,22 +718,32 @@ pub(crate) fn user_embed(
     u: User,
     map_length: f64,
     best: Option<(Score, BeatmapWithMode, BeatmapInfo)>,
+    theme: &EmbedTheme,
 ) -> CreateEmbed {
-    CreateEmbed::new()
-        .title(MessageBuilder::new().push_safe(u.username).build())
+    let embed = CreateEmbed::new()
+        .title(MessageBuilder::new().push_safe(&u.username).build())
         .url(format!("https://osu.ppy.sh/users/{}", u.id))
-        .color(0xffb6c1)
-        .thumbnail(format!("https://a.ppy.sh/{}", u.id))
-        .description(format!("Member since **{}**", u.joined.format("<t:%s:R>")))
+        .color(theme.color)
+        .description(match u.badges.len() {
+            0 => theme.trf("user-member-since", &[("date", &u.joined.relative_time())]),
+            n => theme.trf(
+                "user-member-since-badges",
+                &[("date", &u.joined.relative_time()), ("count", &n)],
+            ),
+        })
         .field(
-            "Performance Points",
+            theme.tr("user-pp"),
             u.pp.map(|v| format!("{:.2}pp", v))
-                .unwrap_or_else(|| "Inactive".to_owned()),
+                .unwrap_or_else(|| theme.tr("user-inactive").to_owned()),
             false,
         )
-        .field("World Rank", format!("#{}", grouped_number(u.rank)), true)
         .field(
-            "Country Rank",
+            theme.tr("user-world-rank"),
+            format!("#{}", grouped_number(u.rank)),
+            true,
+        )
+        .field(
+            theme.tr("user-country-rank"),
             format!(
                 ":flag_{}: #{}",
                 u.country.to_lowercase(),
@@ -448,19 +751,45 @@ pub(crate) fn user_embed(
             ),
             true,
         )
-        .field("Accuracy", format!("{:.2}%", u.accuracy), true)
         .field(
-            "Play count / Play time",
-            format!(
-                "{} / {} hours ({})",
-                grouped_number(u.play_count),
-                u.played_time.as_secs() / 3600,
-                Duration(u.played_time)
+            theme.tr("user-accuracy"),
+            format!("{:.2}%", u.accuracy),
+            true,
+        )
+        .field(
+            theme.tr("user-play-count-time"),
+            theme.trf(
+                "user-play-count-time-value",
+                &[
+                    ("count", &grouped_number(u.play_count)),
+                    ("hours", &(u.played_time.as_secs() / 3600)),
+                    ("time", &format!("{:.3}", Duration(u.played_time))),
+                    ("hits", &grouped_number(u.total_hits)),
+                    ("combo", &grouped_number(u.max_combo)),
+                ],
             ),
             false,
         )
+        .fields(
+            Some(&u.rank_history)
+                .filter(|h| h.len() > 1)
+                .map(|history| {
+                    let peak = history.iter().min().copied().unwrap_or(u.rank);
+                    (
+                        theme.tr("user-rank-history"),
+                        format!(
+                            "`{}`\n#{} \\➡️ #{} {}",
+                            rank_sparkline(history, 30),
+                            grouped_number(history[0]),
+                            grouped_number(*history.last().unwrap()),
+                            theme.trf("user-rank-history-peak", &[("peak", &grouped_number(peak))]),
+                        ),
+                        false,
+                    )
+                }),
+        )
         .field(
-            "Ranks",
+            theme.tr("user-ranks"),
             format!(
                 "**{}** SSH | **{}** SS | **{}** SH | **{}** S | **{}** A",
                 grouped_number(u.count_ssh),
@@ -472,39 +801,50 @@ pub(crate) fn user_embed(
             false,
         )
         .field(
-            "Weighted Map Length",
+            theme.tr("user-map-length"),
             {
                 let secs = map_length.floor() as u64;
                 let minutes = secs / 60;
                 let seconds = map_length - (60 * minutes) as f64;
-                format!(
-                    "**{}** minutes **{:05.2}** seconds (**{:.2}**s)",
-                    minutes, seconds, map_length
+                theme.trf(
+                    "user-map-length-value",
+                    &[
+                        ("minutes", &minutes),
+                        ("seconds", &format!("{:05.2}", seconds)),
+                        ("total", &format!("{:.2}", map_length)),
+                    ],
                 )
             },
             false,
         )
         .field(
-            format!("Level {:.0}", u.level),
+            theme.trf("user-level", &[("level", &u.level.floor())]),
             format!(
-                "**{}** total score, **{}** ranked score",
-                grouped_number(u.total_score),
-                grouped_number(u.ranked_score)
+                "{} **{:.0}%**\n{}",
+                progress_bar(u.level_progress(), 20),
+                u.level_progress() * 100.0,
+                theme.trf(
+                    "user-scores",
+                    &[
+                        ("total", &grouped_number(u.total_score)),
+                        ("ranked", &grouped_number(u.ranked_score)),
+                    ],
+                ),
             ),
             false,
         )
         .fields(best.map(|(v, map, info)| {
             let BeatmapWithMode(map, mode) = map;
             (
-                "Best Record",
+                theme.tr("user-best-record"),
                 MessageBuilder::new()
                     .push_bold(format!(
                         "{:.2}pp",
                         v.pp.unwrap() /*Top record should have pp*/
                     ))
                     .push(" - ")
-                    .push_line(v.date.format("<t:%s:R>").to_string())
-                    .push("on ")
+                    .push_line(v.date.relative_time())
+                    .push(format!("{} ", theme.tr("user-best-record-on")))
                     .push_line(format!(
                         "[{} - {} [{}]]({})**{} **",
                         MessageBuilder::new().push_bold_safe(&map.artist).build(),
@@ -515,13 +855,147 @@ pub(crate) fn user_embed(
                     ))
                     .push(format!(
                         "> {}",
-                        map.difficulty
-                            .apply_mods(v.mods, info.stars)
-                            .format_info(mode, v.mods, &map)
+                        map.difficulty_with(mode, v.mods, info.stars)
+                            .format_info(mode, v.mods, &map, Some((v.accuracy(mode), v.max_combo)))
                             .replace('\n', "\n> ")
                     ))
                     .build(),
                 false,
             )
-        }))
+        }));
+    theme.thumbnail(embed, format!("https://a.ppy.sh/{}", u.id))
+}
+
+/// A short version of the user embed, for expanding profile links.
+pub(crate) fn user_compact_embed(u: &User, mode: Mode, theme: &EmbedTheme) -> CreateEmbed {
+    let embed = CreateEmbed::new()
+        .title(
+            MessageBuilder::new()
+                .push_safe(&u.username)
+                .push(format!(" ({})", mode.as_str_new_site()))
+                .build(),
+        )
+        .url(format!(
+            "https://osu.ppy.sh/users/{}/{}",
+            u.id,
+            mode.as_str_new_site()
+        ))
+        .color(theme.color)
+        .field(
+            theme.tr("user-pp"),
+            u.pp.map(|v| format!("{:.2}pp", v))
+                .unwrap_or_else(|| theme.tr("user-inactive").to_owned()),
+            true,
+        )
+        .field(
+            theme.tr("user-rank"),
+            format!(
+                "#{} (:flag_{}: #{})",
+                grouped_number(u.rank),
+                u.country.to_lowercase(),
+                grouped_number(u.country_rank)
+            ),
+            true,
+        )
+        .field(
+            theme.tr("user-accuracy"),
+            format!("{:.2}%", u.accuracy),
+            true,
+        )
+        .field(
+            theme.tr("user-play-count"),
+            grouped_number(u.play_count),
+            true,
+        )
+        .footer(CreateEmbedFooter::new(theme.trf(
+            "user-member-since-plain",
+            &[("date", &u.joined.format("%Y-%m-%d"))],
+        )));
+    theme.thumbnail(embed, format!("https://a.ppy.sh/{}", u.id))
+}
+
+/// The changes of an user since their last osu!track update.
+pub(crate) fn osutrack_update_embed(
+    u: &User,
+    mode: Mode,
+    update: &osutrack::Update,
+    theme: &EmbedTheme,
+) -> CreateEmbed {
+    let embed = CreateEmbed::new()
+        .title(
+            MessageBuilder::new()
+                .push_safe(&u.username)
+                .push(format!(" ({})", mode.as_str_new_site()))
+                .build(),
+        )
+        .url(format!("https://ameobea.me/osutrack/user/{}", u.username))
+        .color(theme.color)
+        .footer(CreateEmbedFooter::new(theme.tr("osutrack-footer")));
+    let embed = if update.first {
+        embed.description(theme.tr("osutrack-first"))
+    } else {
+        let rank_change = match update.rank {
+            0 => "±0".to_owned(),
+            r if r < 0 => format!("▲{}", grouped_number(r.unsigned_abs())),
+            r => format!("▼{}", grouped_number(r as u64)),
+        };
+        embed
+            .field(
+                theme.tr("user-pp"),
+                format!(
+                    "{} ({:+.2}pp)",
+                    u.pp.map(|v| format!("{:.2}pp", v))
+                        .unwrap_or_else(|| theme.tr("user-inactive").to_owned()),
+                    update.pp
+                ),
+                true,
+            )
+            .field(
+                theme.tr("user-rank"),
+                format!("#{} ({})", grouped_number(u.rank), rank_change),
+                true,
+            )
+            .field(
+                theme.tr("user-accuracy"),
+                format!("{:.2}% ({:+.3}%)", u.accuracy, update.accuracy),
+                true,
+            )
+            .field(
+                theme.tr("user-play-count"),
+                format!("{} ({:+})", grouped_number(u.play_count), update.play_count),
+                true,
+            )
+            .field(
+                theme.tr("osutrack-level"),
+                format!("{:+.2}", update.level),
+                true,
+            )
+            .fields(
+                Some(&update.new_highscores)
+                    .filter(|s| !s.is_empty())
+                    .map(|scores| {
+                        (
+                            theme.trf("osutrack-new-highscores", &[("count", &scores.len())]),
+                            scores
+                                .iter()
+                                .take(5)
+                                .map(|s| {
+                                    theme.trf(
+                                        "osutrack-new-highscore",
+                                        &[
+                                            ("position", &s.position),
+                                            ("pp", &format!("{:.2}", s.pp)),
+                                            ("grade", &s.grade),
+                                            ("beatmap", &s.beatmap_id),
+                                        ],
+                                    )
+                                })
+                                .collect::<Vec<_>>()
+                                .join("\n"),
+                            false,
+                        )
+                    }),
+            )
+    };
+    theme.thumbnail(embed, format!("https://a.ppy.sh/{}", u.id))
 }
@@ -0,0 +1,424 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use serenity::{
+    builder::{CreateAttachment, CreateMessage},
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandResult,
+    },
+    model::channel::Message,
+};
+
+use youmubot_prelude::*;
+
+use crate::{
+    models::{Mode, Score},
+    request::UserID,
+};
+
+use super::{
+    server_rank::{ranks, RankQuery},
+    sheets::{self, OSUEXPORTSHEET_GROUP},
+    user_on_server, ModeArg, OsuEnv, OPTIONS,
+};
+
+#[group]
+#[prefix = "export"]
+#[description = "Export osu! data as CSV files, as JSON with `--json`, or to the server's Google Sheet with `--sheet`, \
+                 for spreadsheets and tournament seeding."]
+#[commands(export_top, export_serverrank)]
+#[sub_groups(OsuExportSheet)]
+struct OsuExport;
+
+#[command("top")]
+#[description = "Export the top plays of an user."]
+#[usage = "[--mode (std, taiko, catch, mania) = std] / [username or user_id = your saved user id] / [--server (a private server, see `servers`) = osu!] / [--json or --sheet], in any order"]
+#[example = "--mode taiko natsukagami --json"]
+#[max_args(7)]
+#[bucket("heavy")]
+pub async fn export_top(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let mut opts = Options::parse(args, OPTIONS)?;
+    let format = Format::parse(&mut opts)?;
+    let mode = opts.get::<ModeArg>("mode")?.map_or(Mode::Std, |m| m.0);
+    let user = opts.take::<UsernameArg>();
+    let server = opts.value::<String>("server")?;
+    opts.finish()?;
+
+    let theme = env.embed_theme_for(msg).await;
+    let (osu_client, user) = user_on_server(&env, msg, server, user, mode).await?;
+    let user = user.ok_or_else(|| Error::msg(theme.tr("user-not-found")))?;
+    let plays = osu_client
+        .user_best(UserID::ID(user.id), |f| f.mode(mode).limit(100))
+        .await?;
+    if plays.is_empty() {
+        msg.reply(&ctx, theme.tr("no-scores")).await?;
+        return Ok(());
+    }
+
+    let rows = plays
+        .iter()
+        .enumerate()
+        .map(|(i, score)| {
+            let env = &env;
+            async move {
+                let beatmap = env.beatmaps.get_beatmap(score.beatmap_id, mode).await?;
+                Ok::<_, Error>(TopRow::new(i + 1, score, &beatmap, mode))
+            }
+        })
+        .collect::<stream::FuturesOrdered<_>>()
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let name = format!("top-{}-{}", user.username, mode.as_str_new_site());
+    let content = theme.trf("export-top", &[("user", &user.username), ("mode", &mode)]);
+    send(ctx, &env, msg, format, content, &rows, &name).await?;
+    Ok(())
+}
+
+#[command("serverrank")]
+#[description = "Export the server's ranks."]
+#[usage = "[mode (Std, Taiko, Catch, Mania, total, map-length) = Std] / [--json or --sheet]"]
+#[example = "total --json"]
+#[max_args(2)]
+#[only_in(guilds)]
+#[bucket("heavy-channel")]
+pub async fn export_serverrank(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let mut opts = Options::parse(args, &[])?;
+    let format = Format::parse(&mut opts)?;
+    let query = opts
+        .take::<RankQuery>()
+        .unwrap_or(RankQuery::Mode(Mode::Std));
+    opts.finish()?;
+    let guild = msg.guild_id.expect("Guild-only command");
+
+    let theme = env.embed_theme_for(msg).await;
+    let Some((users, _)) = ranks(&env, ctx, guild, query).await? else {
+        msg.reply(&ctx, theme.tr("export-no-users")).await?;
+        return Ok(());
+    };
+    let rows = users
+        .iter()
+        .enumerate()
+        .map(|(i, (value, (member, user)))| RankRow {
+            rank: i + 1,
+            member: member.clone(),
+            discord_id: user.user_id.get(),
+            osu_id: user.id,
+            username: user.username.clone().into_owned(),
+            value: *value,
+            pp_std: user.pp[Mode::Std as usize],
+            pp_taiko: user.pp[Mode::Taiko as usize],
+            pp_catch: user.pp[Mode::Catch as usize],
+            pp_mania: user.pp[Mode::Mania as usize],
+            std_weighted_map_length: user.std_weighted_map_length,
+            last_update: user.last_update,
+        })
+        .collect::<Vec<_>>();
+
+    let name = match query {
+        RankQuery::Total => "ranks-total".to_owned(),
+        RankQuery::MapLength => "ranks-map-length".to_owned(),
+        RankQuery::Mode(m) => format!("ranks-{}", m.as_str_new_site()),
+    };
+    let content = theme.trf("export-ranks", &[("mode", &query.name())]);
+    send(ctx, &env, msg, format, content, &rows, &name).await?;
+    Ok(())
+}
+
+/// Where an export goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Csv,
+    Json,
+    /// The server's Google Sheet, see `export sheet`.
+    Sheet,
+}
+
+impl Format {
+    /// Read the `--json` and `--sheet` flags.
+    fn parse(opts: &mut Options) -> Result<Self> {
+        match (opts.flag("json"), opts.flag("sheet")) {
+            (false, false) => Ok(Format::Csv),
+            (true, false) => Ok(Format::Json),
+            (false, true) => Ok(Format::Sheet),
+            (true, true) => bail!("`--json` and `--sheet` cannot be used together"),
+        }
+    }
+}
+
+/// Send the rows as a file named after `name`, or push them to the tab `name` of the server's sheet.
+async fn send<R: Row>(
+    ctx: &Context,
+    env: &OsuEnv,
+    msg: &Message,
+    format: Format,
+    content: String,
+    rows: &[R],
+    name: &str,
+) -> Result<()> {
+    let message = CreateMessage::new().reference_message(msg);
+    let message = match format {
+        Format::Csv => message.content(content).add_file(CreateAttachment::bytes(
+            to_csv(rows),
+            format!("{}.csv", name),
+        )),
+        Format::Json => message.content(content).add_file(CreateAttachment::bytes(
+            serde_json::to_vec_pretty(rows)?,
+            format!("{}.json", name),
+        )),
+        Format::Sheet => {
+            let theme = env.embed_theme_for(msg).await;
+            let guild = msg
+                .guild_id
+                .ok_or_else(|| Error::msg(theme.tr("sheet-guild-only")))?;
+            let link = sheets::push(env, guild, name, to_cells(rows)?).await?;
+            message.content(format!(
+                "{}\n{}",
+                content,
+                theme.trf("sheet-pushed", &[("link", &link)])
+            ))
+        }
+    };
+    msg.channel_id.send_message(ctx, message).await?;
+    Ok(())
+}
+
+/// A row of an export, with the same columns in CSV and JSON.
+trait Row: Serialize {
+    const HEADERS: &'static [&'static str];
+    fn fields(&self) -> Vec<String>;
+}
+
+/// The rows as the cells of a sheet, after the headers. Numbers are kept as numbers.
+fn to_cells<R: Row>(rows: &[R]) -> Result<Vec<Vec<Value>>> {
+    let mut cells = vec![R::HEADERS.iter().map(|&h| Value::from(h)).collect()];
+    for row in rows {
+        let row = serde_json::to_value(row)?;
+        cells.push(
+            R::HEADERS
+                .iter()
+                .map(|&h| match &row[h] {
+                    Value::Null => Value::from(""),
+                    v => v.clone(),
+                })
+                .collect(),
+        );
+    }
+    Ok(cells)
+}
+
+fn to_csv<R: Row>(rows: &[R]) -> String {
+    std::iter::once(R::HEADERS.iter().map(|h| h.to_string()).collect())
+        .chain(rows.iter().map(Row::fields))
+        .map(|fields: Vec<String>| {
+            let line = fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>();
+            line.join(",") + "\r\n"
+        })
+        .collect()
+}
+
+/// Quote the field if it has a separator, a quote or a line break in it.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+fn opt<T: ToString>(v: Option<T>) -> String {
+    v.map(|v| v.to_string()).unwrap_or_default()
+}
+
+#[derive(Serialize)]
+struct TopRow {
+    position: usize,
+    score_id: Option<u64>,
+    beatmap_id: u64,
+    artist: String,
+    title: String,
+    difficulty: String,
+    /// Without mods.
+    stars: f64,
+    mods: String,
+    pp: Option<f64>,
+    accuracy: f64,
+    grade: String,
+    max_combo: u64,
+    count_300: u64,
+    count_100: u64,
+    count_50: u64,
+    count_miss: u64,
+    score: u64,
+    date: DateTime<Utc>,
+}
+
+impl TopRow {
+    fn new(position: usize, score: &Score, beatmap: &crate::models::Beatmap, mode: Mode) -> Self {
+        Self {
+            position,
+            score_id: score.id,
+            beatmap_id: score.beatmap_id,
+            artist: beatmap.artist.clone(),
+            title: beatmap.title.clone(),
+            difficulty: beatmap.difficulty_name.clone(),
+            stars: beatmap.difficulty.stars,
+            mods: score.mods_text(),
+            pp: score.pp,
+            accuracy: score.accuracy(mode),
+            grade: score.rank.to_string(),
+            max_combo: score.max_combo,
+            count_300: score.count_300,
+            count_100: score.count_100,
+            count_50: score.count_50,
+            count_miss: score.count_miss,
+            score: score.display_score(false),
+            date: score.date,
+        }
+    }
+}
+
+impl Row for TopRow {
+    const HEADERS: &'static [&'static str] = &[
+        "position",
+        "score_id",
+        "beatmap_id",
+        "artist",
+        "title",
+        "difficulty",
+        "stars",
+        "mods",
+        "pp",
+        "accuracy",
+        "grade",
+        "max_combo",
+        "count_300",
+        "count_100",
+        "count_50",
+        "count_miss",
+        "score",
+        "date",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.position.to_string(),
+            opt(self.score_id),
+            self.beatmap_id.to_string(),
+            self.artist.clone(),
+            self.title.clone(),
+            self.difficulty.clone(),
+            format!("{:.2}", self.stars),
+            self.mods.clone(),
+            opt(self.pp.map(|pp| format!("{:.2}", pp))),
+            format!("{:.2}", self.accuracy),
+            self.grade.clone(),
+            self.max_combo.to_string(),
+            self.count_300.to_string(),
+            self.count_100.to_string(),
+            self.count_50.to_string(),
+            self.count_miss.to_string(),
+            self.score.to_string(),
+            self.date.to_rfc3339(),
+        ]
+    }
+}
+
+#[derive(Serialize)]
+struct RankRow {
+    rank: usize,
+    member: String,
+    /// As a string, as it does not fit in the numbers of JavaScript.
+    #[serde(serialize_with = "as_string")]
+    discord_id: u64,
+    osu_id: u64,
+    username: String,
+    /// The value ranked on: pp, or the weighted map length in seconds.
+    value: f64,
+    pp_std: Option<f64>,
+    pp_taiko: Option<f64>,
+    pp_catch: Option<f64>,
+    pp_mania: Option<f64>,
+    std_weighted_map_length: Option<f64>,
+    last_update: DateTime<Utc>,
+}
+
+fn as_string<S: serde::Serializer>(v: &u64, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(v)
+}
+
+impl Row for RankRow {
+    const HEADERS: &'static [&'static str] = &[
+        "rank",
+        "member",
+        "discord_id",
+        "osu_id",
+        "username",
+        "value",
+        "pp_std",
+        "pp_taiko",
+        "pp_catch",
+        "pp_mania",
+        "std_weighted_map_length",
+        "last_update",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        let pp = |v: Option<f64>| opt(v.map(|v| format!("{:.2}", v)));
+        vec![
+            self.rank.to_string(),
+            self.member.clone(),
+            self.discord_id.to_string(),
+            self.osu_id.to_string(),
+            self.username.clone(),
+            format!("{:.2}", self.value),
+            pp(self.pp_std),
+            pp(self.pp_taiko),
+            pp(self.pp_catch),
+            pp(self.pp_mania),
+            pp(self.std_weighted_map_length),
+            self.last_update.to_rfc3339(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("Camellia"), "Camellia");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn test_to_cells() {
+        let row = RankRow {
+            rank: 1,
+            member: "natsukagami".to_owned(),
+            discord_id: 111_222_333_444_555_666,
+            osu_id: 1234,
+            username: "natsukagami".to_owned(),
+            value: 1234.5,
+            pp_std: Some(1234.5),
+            pp_taiko: None,
+            pp_catch: None,
+            pp_mania: None,
+            std_weighted_map_length: None,
+            last_update: DateTime::UNIX_EPOCH,
+        };
+        let cells = to_cells(&[row]).unwrap();
+        assert_eq!(cells[0].len(), RankRow::HEADERS.len());
+        assert_eq!(cells[1][0], Value::from(1));
+        assert_eq!(cells[1][2], Value::from("111222333444555666"));
+        assert_eq!(cells[1][6], Value::from(1234.5));
+        assert_eq!(cells[1][7], Value::from(""));
+        assert_eq!(cells[1][11], Value::from("1970-01-01T00:00:00Z"));
+    }
+}
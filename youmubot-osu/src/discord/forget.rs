@@ -0,0 +1,181 @@
+use serenity::{
+    framework::standard::{macros::command, Args, CommandResult},
+    model::{channel::Message, id::ChannelId},
+    utils::MessageBuilder,
+};
+
+use youmubot_db_sql::{
+    models::{config::GuildConfigValue, hooks, osu as models},
+    transaction,
+};
+use youmubot_prelude::permissions::ADMINISTRATOR_CHECK;
+use youmubot_prelude::{announcer, hook::HOOK_SWITCHES, *};
+
+use super::{
+    announcer::ANNOUNCER_KEY,
+    snipes::SNIPE_ANNOUNCER_KEY,
+    theme::{EMBED_COLOR, EMBED_IMAGES},
+    twitch::TWITCH_ANNOUNCER_KEY,
+    OsuEnv,
+};
+
+/// What was removed from a guild's data.
+struct Forgotten {
+    beatmaps: u64,
+    announced_scores: u64,
+    leaderboard_posts: u64,
+    news_subscription: bool,
+    google_sheet: bool,
+    /// The configuration keys that were set.
+    config_keys: Vec<String>,
+    /// The channel hook switches that were not moved into the configuration yet.
+    legacy_hook_switches: u64,
+}
+
+impl Forgotten {
+    /// Whether the configuration key was set.
+    fn had_key(&self, key: &str) -> bool {
+        self.config_keys.iter().any(|k| k == key)
+    }
+}
+
+#[command("forgetserver")]
+#[description = "Remove all osu! data of this server: the announcer channels, the embed theme, \
+                 the scheduled leaderboard posts, the news subscription, the Google Sheet, the announced scores and the beatmap history of its channels, \
+                 as well as its configuration and the hook switches of the server and its channels. \
+                 The users' saved profiles and their history are kept, as they are not tied to the server."]
+#[usage = "[--confirm to skip the confirmation]"]
+#[checks(administrator)]
+#[only_in(guilds)]
+#[max_args(1)]
+pub async fn forget_server(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let guild_id = msg.guild_id.unwrap();
+
+    if args.current() != Some("--confirm")
+        && !confirm(
+            ctx,
+            msg.channel_id,
+            msg.author.id,
+            "This removes the osu! announcer channels, embed theme, scheduled leaderboard posts, news subscription, Google Sheet, announced scores \
+             and beatmap history of this server, as well as its configuration and hook switches, and cannot be undone. Proceed?",
+        )
+        .await?
+    {
+        msg.reply(&ctx, "Nothing was removed.").await?;
+        return Ok(());
+    }
+
+    // The announcer channels are part of the configuration, so they are read before it is removed.
+    let announcer = announcer::announcer_of(ctx, ANNOUNCER_KEY, guild_id).await?;
+    let snipe_announcer = announcer::announcer_of(ctx, SNIPE_ANNOUNCER_KEY, guild_id).await?;
+    let twitch_announcer = announcer::announcer_of(ctx, TWITCH_ANNOUNCER_KEY, guild_id).await?;
+
+    // Beatmaps saved before they were keyed by guild are only found by their channel,
+    // and so are the channel hook switches that were not moved into the configuration yet.
+    let channels = guild_id
+        .channels(&ctx)
+        .await?
+        .into_keys()
+        .map(|c| c.get() as i64)
+        .collect::<Vec<_>>();
+    let guild = guild_id.get() as i64;
+    let forgotten = transaction(&env.prelude.sql, |t| {
+        let channels = channels.clone();
+        Box::pin(async move {
+            let mut beatmaps = models::LastBeatmap::delete_by_guild(guild, &mut *t).await?;
+            let mut legacy_hook_switches = 0;
+            for channel in channels {
+                beatmaps += models::LastBeatmap::delete_by_channel(channel, &mut *t).await?;
+                legacy_hook_switches +=
+                    hooks::HookSetting::delete_by_channel(channel, &mut *t).await?;
+            }
+            let announced_scores = models::AnnouncedScore::delete_by_guild(guild, &mut *t).await?;
+            let leaderboard_posts =
+                models::LeaderboardPost::delete_by_guild(guild, &mut *t).await?;
+            let news_subscription = models::NewsSubscription::delete(guild, &mut *t).await?;
+            let google_sheet = models::GoogleSheet::delete(guild, &mut *t).await?;
+            let config_keys = GuildConfigValue::delete_by_guild(guild, &mut *t).await?;
+            Ok(Forgotten {
+                beatmaps,
+                announced_scores,
+                leaderboard_posts,
+                news_subscription,
+                google_sheet,
+                config_keys,
+                legacy_hook_switches,
+            })
+        })
+    })
+    .await?;
+    env.prelude
+        .config
+        .forgotten(guild_id, forgotten.config_keys.iter().cloned());
+    let removed_from = |channel: Option<ChannelId>| match channel {
+        Some(channel) => format!("removed from {}", channel.mention()),
+        None => "not registered".to_owned(),
+    };
+
+    msg.reply(
+        &ctx,
+        MessageBuilder::new()
+            .push_line("All osu! data of this server has been removed:")
+            .push_line(format!("- announcer: {}", removed_from(announcer)))
+            .push_line(format!(
+                "- snipe announcer: {}",
+                removed_from(snipe_announcer)
+            ))
+            .push_line(format!(
+                "- Twitch announcer: {}",
+                removed_from(twitch_announcer)
+            ))
+            .push_line(format!(
+                "- embed theme: {}",
+                if forgotten.had_key(EMBED_COLOR.name) || forgotten.had_key(EMBED_IMAGES.name) {
+                    "reset"
+                } else {
+                    "was the default"
+                }
+            ))
+            .push_line(format!(
+                "- scheduled leaderboard posts: {}",
+                forgotten.leaderboard_posts
+            ))
+            .push_line(format!(
+                "- news subscription: {}",
+                if forgotten.news_subscription {
+                    "removed"
+                } else {
+                    "none"
+                }
+            ))
+            .push_line(format!(
+                "- Google Sheet: {}",
+                if forgotten.google_sheet {
+                    "removed"
+                } else {
+                    "none"
+                }
+            ))
+            .push_line(format!(
+                "- announced scores: {}",
+                forgotten.announced_scores
+            ))
+            .push_line(format!("- saved beatmaps: {}", forgotten.beatmaps))
+            .push_line(format!(
+                "- configuration keys: {}",
+                forgotten.config_keys.len()
+            ))
+            .push_line(format!(
+                "- hook switches: {}",
+                if forgotten.had_key(HOOK_SWITCHES.name) || forgotten.legacy_hook_switches > 0 {
+                    "removed"
+                } else {
+                    "none"
+                }
+            ))
+            .build(),
+    )
+    .await?;
+    Ok(())
+}
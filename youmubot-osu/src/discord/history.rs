@@ -0,0 +1,462 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serenity::{
+    builder::{CreateAttachment, CreateMessage},
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+    utils::MessageBuilder,
+};
+
+use youmubot_prelude::*;
+
+use crate::{
+    models::{Mode, User},
+    render::History,
+};
+
+use super::{embeds::osutrack_update_embed, to_user_id_query, ModeArg, OsuEnv, OPTIONS};
+
+/// The file name of attached history graphs.
+const HISTORY_GRAPH_FILENAME: &str = "history.png";
+
+/// The statistic to plot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stat {
+    Rank,
+    Pp,
+    Accuracy,
+}
+
+impl FromStr for Stat {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        args::keyword(
+            "statistic",
+            s,
+            &[
+                (&["rank"], Stat::Rank),
+                (&["pp"], Stat::Pp),
+                (&["accuracy", "acc"], Stat::Accuracy),
+            ],
+        )
+    }
+}
+
+impl Stat {
+    fn name(self) -> &'static str {
+        match self {
+            Stat::Rank => "rank",
+            Stat::Pp => "pp",
+            Stat::Accuracy => "accuracy",
+        }
+    }
+
+    fn of(self, s: &Snapshot) -> Option<f64> {
+        match self {
+            Stat::Rank => s.rank.map(|r| r as f64),
+            Stat::Pp => s.pp,
+            Stat::Accuracy => s.accuracy,
+        }
+    }
+
+    fn format(self) -> fn(f64) -> String {
+        match self {
+            Stat::Rank => |v| format!("#{:.0}", v),
+            Stat::Pp => |v| format!("{:.0}pp", v),
+            Stat::Accuracy => |v| format!("{:.2}%", v),
+        }
+    }
+
+    /// Format the change of the statistic, e.g. `▲12pp`.
+    fn format_change(self, from: f64, to: f64) -> String {
+        let diff = to - from;
+        let better = if self == Stat::Rank {
+            diff < 0.0
+        } else {
+            diff > 0.0
+        };
+        let arrow = if diff == 0.0 {
+            "±"
+        } else if better {
+            "▲"
+        } else {
+            "▼"
+        };
+        let amount = match self {
+            Stat::Rank => format!("{:.0}", diff.abs()),
+            _ => self.format()(diff.abs()),
+        };
+        format!("{}{}", arrow, amount)
+    }
+}
+
+/// The period summed up by `recap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Period {
+    Week,
+    Month,
+    Year,
+}
+
+impl FromStr for Period {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        args::keyword(
+            "period",
+            s,
+            &[
+                (&["week"], Period::Week),
+                (&["month"], Period::Month),
+                (&["year"], Period::Year),
+            ],
+        )
+    }
+}
+
+impl Period {
+    fn name(self) -> &'static str {
+        match self {
+            Period::Week => "week",
+            Period::Month => "month",
+            Period::Year => "year",
+        }
+    }
+
+    fn duration(self) -> Duration {
+        match self {
+            Period::Week => Duration::days(7),
+            Period::Month => Duration::days(30),
+            Period::Year => Duration::days(365),
+        }
+    }
+}
+
+/// An user's statistics on a given day.
+#[derive(Debug, Clone, Copy, Default)]
+struct Snapshot {
+    rank: Option<u64>,
+    pp: Option<f64>,
+    accuracy: Option<f64>,
+}
+
+impl Snapshot {
+    /// Fill in the missing statistics from another snapshot of the same day.
+    fn or(self, other: Snapshot) -> Snapshot {
+        Snapshot {
+            rank: self.rank.or(other.rank),
+            pp: self.pp.or(other.pp),
+            accuracy: self.accuracy.or(other.accuracy),
+        }
+    }
+}
+
+/// Truncate the time to the day it falls in.
+fn day_of(t: DateTime<Utc>) -> DateTime<Utc> {
+    t.duration_trunc(Duration::days(1)).unwrap_or(t)
+}
+
+/// The statistic at `since` (or the earliest after it, if it is not known then) and the latest one.
+fn change_since(
+    history: &BTreeMap<DateTime<Utc>, Snapshot>,
+    stat: Stat,
+    since: DateTime<Utc>,
+) -> Option<(f64, f64)> {
+    let from = history
+        .range(..=since)
+        .rev()
+        .chain(history.range(since..))
+        .find_map(|(_, s)| stat.of(s))?;
+    let to = history.values().rev().find_map(|s| stat.of(s))?;
+    Some((from, to))
+}
+
+impl OsuEnv {
+    /// Collect the user's history, one snapshot per day, from our own snapshots,
+    /// osu!track and the rank history of the profile.
+    async fn stats_history(
+        &self,
+        user: &User,
+        mode: Mode,
+    ) -> Result<BTreeMap<DateTime<Utc>, Snapshot>> {
+        let mut history = BTreeMap::<DateTime<Utc>, Snapshot>::new();
+        let mut add = |t: DateTime<Utc>, s: Snapshot| {
+            let e = history.entry(day_of(t)).or_default();
+            *e = e.or(s);
+        };
+        // Our own snapshots take priority, the latest of each day first.
+        for s in self
+            .stats_snapshots
+            .history(user.id, mode)
+            .await?
+            .into_iter()
+            .rev()
+        {
+            add(
+                s.taken_at,
+                Snapshot {
+                    rank: s.rank,
+                    pp: s.pp,
+                    accuracy: s.accuracy,
+                },
+            );
+        }
+        // osu!track backfills the history from before we saw the user.
+        for e in self
+            .osutrack
+            .stats_history(user.id, mode)
+            .await
+            .pls_ok()
+            .unwrap_or_default()
+        {
+            add(
+                e.timestamp,
+                Snapshot {
+                    rank: e.rank,
+                    pp: e.pp,
+                    accuracy: e.accuracy,
+                },
+            );
+        }
+        // The rank history ends today.
+        let today = Utc::now();
+        let days = user.rank_history.len() as i64;
+        for (i, rank) in user.rank_history.iter().enumerate() {
+            add(
+                today - Duration::days(days - 1 - i as i64),
+                Snapshot {
+                    rank: Some(*rank).filter(|r| *r > 0),
+                    ..Default::default()
+                },
+            );
+        }
+        Ok(history)
+    }
+}
+
+#[command("graph")]
+#[aliases("history")]
+#[description = "Plot an user's rank, pp or accuracy over time."]
+#[usage = "[rank / pp / acc = rank] / [--mode (std, taiko, catch, mania) = std] / [username or user id = your saved user id], in any order"]
+#[example = "pp --mode taiko natsukagami"]
+#[delimiters("/", " ")]
+#[max_args(4)]
+#[bucket("heavy")]
+pub async fn graph(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let mut opts = Options::parse(args, OPTIONS)?;
+    let stat = opts.take::<Stat>().unwrap_or(Stat::Rank);
+    let mode = opts.get::<ModeArg>("mode")?.map_or(Mode::Std, |m| m.0);
+    let user = opts.take::<UsernameArg>();
+    opts.finish()?;
+    let user = to_user_id_query(user, &env, msg).await?;
+    let user = env
+        .users
+        .get(user, Some(mode))
+        .await?
+        .ok_or_else(|| Error::msg("User not found"))?;
+    env.stats_snapshots.record(&user, mode, None).await.pls_ok();
+
+    let points = env
+        .stats_history(&user, mode)
+        .await?
+        .into_iter()
+        .filter_map(|(t, s)| stat.of(&s).map(|v| (t, v)))
+        .collect::<Vec<_>>();
+    let (Some(first), Some(last)) = (points.first().copied(), points.last().copied()) else {
+        msg.reply(
+            &ctx,
+            format!("No {} history for this user yet!", stat.name()),
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let format = stat.format();
+    let summary = MessageBuilder::new()
+        .push_bold_safe(&user.username)
+        .push(format!("'s {} ({}): ", stat.name(), mode))
+        .push_mono(format(first.1))
+        .push(format!(" on {} → ", first.0.format("%Y-%m-%d")))
+        .push_mono(format(last.1))
+        .push(format!(" on {}", last.0.format("%Y-%m-%d")))
+        .build();
+
+    let png = match &env.renderer {
+        Some(renderer) => renderer
+            .history_graph(History {
+                title: format!("{}'s {} ({})", user.username, stat.name(), mode),
+                points,
+                lower_is_better: stat == Stat::Rank,
+                format,
+            })
+            .await
+            .pls_ok(),
+        None => None,
+    };
+    msg.channel_id
+        .send_message(
+            &ctx,
+            CreateMessage::new()
+                .reference_message(msg)
+                .content(summary)
+                .add_files(png.map(|png| CreateAttachment::bytes(png, HISTORY_GRAPH_FILENAME))),
+        )
+        .await?;
+    Ok(())
+}
+
+#[command("recap")]
+#[description = "Sum up how an user's rank, pp and accuracy changed over the last week, month or year, \
+                 including the history osu!track has from before we saw them."]
+#[usage = "[week / month / year = month] / [--mode (std, taiko, catch, mania) = std] / [username or user id = your saved user id], in any order"]
+#[example = "year --mode taiko natsukagami"]
+#[delimiters("/", " ")]
+#[max_args(4)]
+#[bucket("heavy")]
+pub async fn recap(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let mut opts = Options::parse(args, OPTIONS)?;
+    let period = opts.take::<Period>().unwrap_or(Period::Month);
+    let mode = opts.get::<ModeArg>("mode")?.map_or(Mode::Std, |m| m.0);
+    let user = opts.take::<UsernameArg>();
+    opts.finish()?;
+    let user = to_user_id_query(user, &env, msg).await?;
+    let user = env
+        .users
+        .get(user, Some(mode))
+        .await?
+        .ok_or_else(|| Error::msg("User not found"))?;
+    env.stats_snapshots.record(&user, mode, None).await.pls_ok();
+
+    let history = env.stats_history(&user, mode).await?;
+    let since = day_of(Utc::now() - period.duration());
+    let mut content = MessageBuilder::new();
+    content.push_bold_safe(&user.username).push_line(format!(
+        "'s last {} ({}):",
+        period.name(),
+        mode
+    ));
+    let mut known = false;
+    for stat in [Stat::Rank, Stat::Pp, Stat::Accuracy] {
+        let Some((from, to)) = change_since(&history, stat, since) else {
+            continue;
+        };
+        known = true;
+        let format = stat.format();
+        content
+            .push(format!("- {}: ", stat.name()))
+            .push_mono(format(from))
+            .push(" → ")
+            .push_mono(format(to))
+            .push_line(format!(" ({})", stat.format_change(from, to)));
+    }
+    if !known {
+        msg.reply(&ctx, "No history for this user yet!").await?;
+        return Ok(());
+    }
+    msg.reply(&ctx, content.build()).await?;
+    Ok(())
+}
+
+#[command("update")]
+#[description = "Update an user on osu!track, and show their changes since the last update: \
+                 pp, rank, accuracy and the new top plays. \
+                 osu!track also backfills the history shown by `graph`."]
+#[usage = "[--mode (std, taiko, catch, mania) = std] / [username or user id = your saved user id], in any order"]
+#[example = "--mode mania natsukagami"]
+#[delimiters("/", " ")]
+#[max_args(3)]
+#[bucket("heavy")]
+pub async fn update(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let mut opts = Options::parse(args, OPTIONS)?;
+    let mode = opts.get::<ModeArg>("mode")?.map_or(Mode::Std, |m| m.0);
+    let user = opts.take::<UsernameArg>();
+    opts.finish()?;
+    let user = to_user_id_query(user, &env, msg).await?;
+    let user = env
+        .users
+        .get(user, Some(mode))
+        .await?
+        .ok_or_else(|| Error::msg("User not found"))?;
+    env.stats_snapshots.record(&user, mode, None).await.pls_ok();
+
+    let theme = env.embed_theme_for(msg).await;
+    let Some(update) = env.osutrack.update(user.id, mode).await? else {
+        msg.reply(&ctx, theme.tr("osutrack-not-found")).await?;
+        return Ok(());
+    };
+    msg.channel_id
+        .send_message(
+            &ctx,
+            CreateMessage::new()
+                .reference_message(msg)
+                .embed(osutrack_update_embed(&user, mode, &update, &theme)),
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn day(d: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, d, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_change_since() {
+        let history = BTreeMap::from([
+            (
+                day(1),
+                Snapshot {
+                    rank: Some(1000),
+                    pp: Some(5000.0),
+                    accuracy: None,
+                },
+            ),
+            (
+                day(10),
+                Snapshot {
+                    rank: Some(800),
+                    pp: None,
+                    accuracy: Some(98.5),
+                },
+            ),
+            (
+                day(20),
+                Snapshot {
+                    rank: Some(700),
+                    pp: Some(5200.0),
+                    accuracy: None,
+                },
+            ),
+        ]);
+        // From the latest value before the start of the period.
+        assert_eq!(
+            change_since(&history, Stat::Rank, day(5)),
+            Some((1000.0, 700.0))
+        );
+        assert_eq!(
+            change_since(&history, Stat::Pp, day(15)),
+            Some((5000.0, 5200.0))
+        );
+        // From the earliest value in the period, if there is none before.
+        assert_eq!(
+            change_since(&history, Stat::Accuracy, day(5)),
+            Some((98.5, 98.5))
+        );
+        assert_eq!(change_since(&BTreeMap::new(), Stat::Rank, day(5)), None);
+    }
+
+    #[test]
+    fn test_format_change() {
+        assert_eq!(Stat::Rank.format_change(1000.0, 700.0), "▲300");
+        assert_eq!(Stat::Pp.format_change(5200.0, 5000.0), "▼200pp");
+        assert_eq!(Stat::Accuracy.format_change(98.5, 98.5), "±0.00%");
+    }
+}
@@ -2,17 +2,34 @@ use std::str::FromStr;
 
 use lazy_static::lazy_static;
 use regex::Regex;
-use serenity::{builder::CreateMessage, model::channel::Message, utils::MessageBuilder};
+use serenity::{
+    builder::{CreateEmbed, CreateMessage},
+    http::CacheHttp,
+    model::channel::Message,
+    utils::MessageBuilder,
+};
 
 use youmubot_prelude::*;
 
 use crate::discord::OsuEnv;
 use crate::{
-    discord::oppai_cache::BeatmapInfoWithPP,
+    discord::oppai_cache::{compute_offline, Accuracy, BeatmapInfoWithPP},
     models::{Beatmap, Mode, Mods},
+    replay::{HitStats, Replay},
+    request::UserID,
 };
 
-use super::embeds::beatmap_embed;
+use super::embeds::{
+    beatmap_embed, beatmapset_embed, score_embed, user_compact_embed, with_cover, with_strain_graph,
+};
+use super::hook_settings::{
+    BEATMAP_HOOK, COMPACT_HOOK, MATCH_HOOK, NP_HOOK, PROFILE_HOOK, REPLAY_HOOK, SCORE_HOOK,
+};
+use super::match_summary;
+use super::{
+    cover_attachment, score_card_attachment, score_global_rank, score_hit_stats,
+    strain_graph_attachment,
+};
 
 lazy_static! {
     pub(crate) static ref OLD_LINK_REGEX: Regex = Regex::new(
@@ -22,10 +39,39 @@ lazy_static! {
         r"(?:https?://)?osu\.ppy\.sh/beatmapsets/(?P<set_id>\d+)/?(?:\#(?P<mode>osu|taiko|fruits|mania)(?:/(?P<beatmap_id>\d+)|/?))?(?:\+(?P<mods>[A-Z]+))?"
     ).unwrap();
     pub(crate) static ref SHORT_LINK_REGEX: Regex = Regex::new(
-        r"(?:^|\s|\W)(?P<main>/b/(?P<id>\d+)(?:/(?P<mode>osu|taiko|fruits|mania))?(?:\+(?P<mods>[A-Z]+))?)"
+        r"(?:^|\s|\W)(?P<main>/b/(?P<id>\d+)(?:/(?P<mode>osu|taiko|fruits|mania))?(?:\s*\+(?P<mods>[A-Z]+))?(?:\s+(?P<acc>\d{1,3}(?:\.\d+)?)%)?(?:\s+(?P<combo>\d+)x)?)"
+    ).unwrap();
+    pub(crate) static ref PROFILE_LINK_REGEX: Regex = Regex::new(
+        r"(?:https?://)?osu\.ppy\.sh/(?:users|u)/(?P<user>[^\s/?#]+)(?:/(?P<mode>osu|taiko|fruits|mania))?"
+    ).unwrap();
+    /// The "now playing" format of the osu! client, e.g. `Artist - Title [Diff] +HDDT`.
+    pub(crate) static ref NP_PASTE_REGEX: Regex = Regex::new(
+        r"^(?P<artist>[^\[\]]+?) - (?P<title>[^\[\]]+) \[(?P<diff>[^\[\]]+)\](?:\s*\+(?P<mods>[A-Za-z]+))?$"
+    ).unwrap();
+    pub(crate) static ref MATCH_LINK_REGEX: Regex = Regex::new(
+        r"(?:https?://)?osu\.ppy\.sh/(?:community/matches|mp)/(?P<id>\d+)"
+    ).unwrap();
+    pub(crate) static ref ROOM_LINK_REGEX: Regex = Regex::new(
+        r"(?:https?://)?osu\.ppy\.sh/multiplayer/rooms/(?P<id>\d+)"
+    ).unwrap();
+    pub(crate) static ref SCORE_LINK_REGEX: Regex = Regex::new(
+        r"(?:https?://)?osu\.ppy\.sh/scores/(?:(?P<mode>osu|taiko|fruits|mania)/)?(?P<id>\d+)"
     ).unwrap();
 }
 
+/// The maximum number of profile links expanded per message.
+const MAX_PROFILE_LINKS: usize = 3;
+/// The maximum number of score links expanded per message.
+const MAX_SCORE_LINKS: usize = 3;
+/// The maximum number of match and room links summarized per message.
+const MAX_MATCH_LINKS: usize = 2;
+/// The maximum number of beatmaps expanded by the "Expand beatmap" command.
+const MAX_EXPANDED_BEATMAPS: usize = 5;
+/// The accuracy of the pp shown in compact responses.
+const COMPACT_ACCURACY: f64 = 97.0;
+/// The maximum number of difficulties shown in a compact beatmapset response.
+const MAX_COMPACT_DIFFS: usize = 5;
+
 pub fn dot_osu_hook<'a>(
     ctx: &'a Context,
     msg: &'a Message,
@@ -35,85 +81,157 @@ pub fn dot_osu_hook<'a>(
             return Ok(());
         }
 
-        // Take all the .osu attachments
-        let mut osu_embeds = msg
-            .attachments
-            .iter()
-            .filter(
-                |a| a.filename.ends_with(".osu") && a.size < 1024 * 1024, /* 1mb */
-            )
-            .map(|attachment| {
-                let url = attachment.url.clone();
+        let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+        if env.hook_enabled(msg, BEATMAP_HOOK).await {
+            // Take all the .osu attachments
+            let mut osu_embeds = msg
+                .attachments
+                .iter()
+                .filter(
+                    |a| a.filename.ends_with(".osu") && a.size < 1024 * 1024, /* 1mb */
+                )
+                .map(|attachment| {
+                    let url = attachment.url.clone();
 
-                async move {
-                    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+                    async move {
+                        let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
 
-                    let (beatmap, _) = env.oppai.download_beatmap_from_url(&url).await.ok()?;
-                    crate::discord::embeds::beatmap_offline_embed(
-                        &beatmap,
-                        Mode::from(beatmap.content.mode as u8), /*For now*/
-                        msg.content.trim().parse().unwrap_or(Mods::NOMOD),
-                    )
-                    .pls_ok()
-                }
-            })
-            .collect::<stream::FuturesUnordered<_>>()
-            .filter_map(future::ready)
-            .collect::<Vec<_>>()
-            .await;
-
-        let osz_embeds = msg
-            .attachments
-            .iter()
-            .filter(
-                |a| a.filename.ends_with(".osz") && a.size < 20 * 1024 * 1024, /* 20mb */
-            )
-            .map(|attachment| {
-                let url = attachment.url.clone();
-                async move {
-                    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
-
-                    let beatmaps = env.oppai.download_osz_from_url(&url).await.pls_ok()?;
-                    Some(
-                        beatmaps
-                            .into_iter()
-                            .filter_map(|beatmap| {
-                                crate::discord::embeds::beatmap_offline_embed(
-                                    &beatmap,
-                                    Mode::from(beatmap.content.mode as u8), /*For now*/
-                                    msg.content.trim().parse().unwrap_or(Mods::NOMOD),
-                                )
-                                .pls_ok()
-                            })
-                            .collect::<Vec<_>>(),
-                    )
-                }
-            })
-            .collect::<stream::FuturesUnordered<_>>()
-            .filter_map(future::ready)
-            .filter(|v| future::ready(!v.is_empty()))
-            .collect::<Vec<_>>()
-            .await
-            .concat();
-        osu_embeds.extend(osz_embeds);
-
-        if !osu_embeds.is_empty() {
-            msg.channel_id
-                .send_message(
-                    ctx,
-                    CreateMessage::new()
-                        .reference_message(msg)
-                        .content(format!("{} attached beatmaps found", osu_embeds.len()))
-                        .add_embeds(osu_embeds),
+                        let (beatmap, _) = env.oppai.download_beatmap_from_url(&url).await.ok()?;
+                        let theme = env.embed_theme_for(msg).await;
+                        crate::discord::embeds::beatmap_offline_embed(
+                            &beatmap,
+                            Mode::from(beatmap.content.mode as u8), /*For now*/
+                            msg.content.trim().parse().unwrap_or(Mods::NOMOD),
+                            &theme,
+                        )
+                        .pls_ok()
+                    }
+                })
+                .collect::<stream::FuturesUnordered<_>>()
+                .filter_map(future::ready)
+                .collect::<Vec<_>>()
+                .await;
+
+            let osz_embeds = msg
+                .attachments
+                .iter()
+                .filter(
+                    |a| a.filename.ends_with(".osz") && a.size < 20 * 1024 * 1024, /* 20mb */
                 )
-                .await
-                .ok();
+                .map(|attachment| {
+                    let url = attachment.url.clone();
+                    async move {
+                        let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+
+                        let beatmaps = env.oppai.download_osz_from_url(&url).await.pls_ok()?;
+                        let mods = msg.content.trim().parse().unwrap_or(Mods::NOMOD);
+                        let diffs = compute_offline(beatmaps, mods).await.pls_ok()?;
+                        let theme = env.embed_theme_for(msg).await;
+                        crate::discord::embeds::beatmapset_offline_embed(&diffs, mods, &theme)
+                    }
+                })
+                .collect::<stream::FuturesUnordered<_>>()
+                .filter_map(future::ready)
+                .collect::<Vec<_>>()
+                .await;
+            osu_embeds.extend(osz_embeds);
+
+            if !osu_embeds.is_empty() {
+                msg.channel_id
+                    .send_message(
+                        ctx,
+                        CreateMessage::new()
+                            .reference_message(msg)
+                            .content(format!("{} attached beatmaps found", osu_embeds.len()))
+                            .add_embeds(osu_embeds),
+                    )
+                    .await
+                    .ok();
+            }
         }
 
-        Ok(())
+        handle_replay_attachments(ctx, msg).await
     })
 }
 
+/// Analyze the attached `.osr` replays, and post them as scores with their hit statistics.
+async fn handle_replay_attachments(ctx: &Context, msg: &Message) -> Result<()> {
+    let attachments = msg
+        .attachments
+        .iter()
+        .filter(
+            |a| a.filename.ends_with(".osr") && a.size < 5 * 1024 * 1024, /* 5mb */
+        )
+        .collect::<Vec<_>>();
+    if attachments.is_empty() {
+        return Ok(());
+    }
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    if !env.hook_enabled(msg, REPLAY_HOOK).await {
+        return Ok(());
+    }
+    let theme = env.embed_theme_for(msg).await;
+    for attachment in attachments {
+        let replay = Replay::parse(&attachment.download().await?[..])
+            .map_err(|e| InputError(e.context(format!("cannot read `{}`", attachment.filename))))?;
+        if !env.should_respond(msg, REPLAY_HOOK, &replay.replay_hash) {
+            continue;
+        }
+        let mode = replay.mode;
+        let Some(beatmap) = env
+            .beatmaps
+            .get_beatmap_by_hash(&replay.beatmap_hash, mode)
+            .await?
+        else {
+            msg.reply(
+                ctx,
+                format!(
+                    "The beatmap of `{}` is not submitted, cannot analyze it.",
+                    attachment.filename
+                ),
+            )
+            .await?;
+            continue;
+        };
+        let Some(user) = env
+            .users
+            .get(UserID::Username(replay.player.clone()), Some(mode))
+            .await?
+        else {
+            msg.reply(
+                ctx,
+                format!("Player `{}` was not found on osu!", replay.player),
+            )
+            .await?;
+            continue;
+        };
+        let content = env.oppai.get_beatmap(beatmap.beatmap_id).await?;
+        let score = replay.to_score(beatmap.beatmap_id, user.id);
+        let hit_stats = HitStats::from_replay(&replay, &content.content);
+        let bm = super::BeatmapWithMode(beatmap, mode);
+        msg.channel_id
+            .send_message(
+                ctx,
+                CreateMessage::new()
+                    .reference_message(msg)
+                    .content(
+                        MessageBuilder::new()
+                            .push("Replay analysis for ")
+                            .push_mono_safe(&attachment.filename)
+                            .build(),
+                    )
+                    .embed(
+                        score_embed(&score, &bm, &content, &user, &theme)
+                            .hit_stats(hit_stats)
+                            .build(),
+                    ),
+            )
+            .await?;
+        crate::discord::cache::publish_beatmap(&env, msg.guild_id, msg.channel_id, &bm).await;
+    }
+    Ok(())
+}
+
 pub fn hook<'a>(
     ctx: &'a Context,
     msg: &'a Message,
@@ -122,41 +240,301 @@ pub fn hook<'a>(
         if msg.author.bot {
             return Ok(());
         }
-        let (old_links, new_links, short_links) = (
-            handle_old_links(ctx, &msg.content),
-            handle_new_links(ctx, &msg.content),
-            handle_short_links(ctx, msg, &msg.content),
-        );
-        stream::select(old_links, stream::select(new_links, short_links))
-            .then(|l| async move {
-                match l.embed {
-                    EmbedType::Beatmap(b, info, mods) => {
-                        handle_beatmap(ctx, &b, info, l.link, l.mode, mods, msg)
-                            .await
-                            .pls_ok();
-                        let mode = l.mode.unwrap_or(b.mode);
-                        let bm = super::BeatmapWithMode(*b, mode);
-
-                        let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+        let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+        for set in handle_beatmap_links(ctx, &env, msg).await {
+            handle_beatmapset(ctx, set.beatmaps, set.link, set.mode, msg)
+                .await
+                .pls_ok();
+        }
 
-                        crate::discord::cache::save_beatmap(&env, msg.channel_id, &bm)
-                            .await
-                            .pls_ok();
-                    }
-                    EmbedType::Beatmapset(b) => {
-                        handle_beatmapset(ctx, b, l.link, l.mode, msg)
-                            .await
-                            .pls_ok();
-                    }
-                }
-            })
-            .collect::<()>()
-            .await;
+        handle_profile_links(ctx, msg).await.pls_ok();
+        handle_score_links(ctx, msg).await.pls_ok();
+        handle_np_paste(ctx, msg).await.pls_ok();
+        handle_match_links(ctx, msg).await.pls_ok();
 
         Ok(())
     })
 }
 
+/// A beatmapset link to be shown in full.
+struct LinkedBeatmapset<'a> {
+    beatmaps: Vec<Beatmap>,
+    link: &'a str,
+    mode: Option<Mode>,
+}
+
+/// Reply to the beatmap links in the message, if the beatmap hook is enabled.
+/// Beatmapsets shown in full are paginated, which needs a [Context], so they are returned to be shown instead.
+async fn handle_beatmap_links<'a>(
+    http: impl CacheHttp,
+    env: &'a OsuEnv,
+    msg: &'a Message,
+) -> Vec<LinkedBeatmapset<'a>> {
+    if !env.hook_enabled(msg, BEATMAP_HOOK).await {
+        return vec![];
+    }
+    let compact = env.hook_enabled(msg, COMPACT_HOOK).await;
+    let http = &http;
+    linked_beatmaps(env, msg, &msg.content, true)
+        .filter(|l| future::ready(env.should_respond(msg, BEATMAP_HOOK, l.link)))
+        .filter_map(|l| async move {
+            match l.embed {
+                EmbedType::Beatmap(b, info, _) if compact => {
+                    handle_beatmap_compact(http, env, &b, info, msg)
+                        .await
+                        .pls_ok();
+                }
+                EmbedType::Beatmap(b, info, mods) => {
+                    handle_beatmap(http, env, &b, info, l.link, l.mode, mods, msg)
+                        .await
+                        .pls_ok();
+                }
+                EmbedType::Beatmapset(b) if compact => {
+                    handle_beatmapset_compact(http, env, b, l.mode, msg)
+                        .await
+                        .pls_ok();
+                }
+                EmbedType::Beatmapset(beatmaps) => {
+                    return Some(LinkedBeatmapset {
+                        beatmaps,
+                        link: l.link,
+                        mode: l.mode,
+                    })
+                }
+            }
+            None
+        })
+        .collect()
+        .await
+}
+
+/// Summarize the multiplayer match and lazer room links in the message.
+async fn handle_match_links(ctx: &Context, msg: &Message) -> Result<()> {
+    let matches = MATCH_LINK_REGEX
+        .captures_iter(&msg.content)
+        .filter_map(|c| c["id"].parse::<u64>().ok())
+        .map(|id| (false, id));
+    let rooms = ROOM_LINK_REGEX
+        .captures_iter(&msg.content)
+        .filter_map(|c| c["id"].parse::<u64>().ok())
+        .map(|id| (true, id));
+    let links = matches
+        .chain(rooms)
+        .take(MAX_MATCH_LINKS)
+        .collect::<Vec<_>>();
+    if links.is_empty() {
+        return Ok(());
+    }
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    if !env.hook_enabled(msg, MATCH_HOOK).await {
+        return Ok(());
+    }
+    for (is_room, id) in links {
+        let key = format!("{}{}", if is_room { "room" } else { "match" }, id);
+        if !env.should_respond(msg, MATCH_HOOK, key) {
+            continue;
+        }
+        if is_room {
+            match_summary::post_room(ctx, msg, id).await?;
+        } else {
+            match_summary::post_match(ctx, msg, id).await?;
+        }
+    }
+    Ok(())
+}
+
+/// A "now playing" paste, as parsed from a message.
+struct NowPlaying<'a> {
+    artist: &'a str,
+    title: &'a str,
+    difficulty: &'a str,
+    mods: Mods,
+}
+
+impl<'a> NowPlaying<'a> {
+    /// Parse the message as a "now playing" paste. The whole message must be the paste.
+    fn parse(content: &'a str) -> Option<Self> {
+        let content = content.trim();
+        if content.contains("osu.ppy.sh") {
+            return None;
+        }
+        let capture = NP_PASTE_REGEX.captures(content)?;
+        let mods = match capture.name("mods") {
+            Some(m) => Mods::from_str(m.as_str()).ok()?,
+            None => Mods::NOMOD,
+        };
+        Some(Self {
+            artist: capture.name("artist")?.as_str().trim(),
+            title: capture.name("title")?.as_str().trim(),
+            difficulty: capture.name("diff")?.as_str().trim(),
+            mods,
+        })
+    }
+
+    /// How well the beatmap matches the paste, `None` if it does not match at all.
+    fn similarity(&self, beatmap: &Beatmap) -> Option<u32> {
+        fn normalize(s: &str) -> String {
+            s.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect()
+        }
+        fn score(expected: &str, actual: &str, exact: u32) -> u32 {
+            let (expected, actual) = (normalize(expected), normalize(actual));
+            if expected == actual {
+                exact
+            } else if !expected.is_empty()
+                && (actual.contains(&expected) || expected.contains(&actual))
+            {
+                1
+            } else {
+                0
+            }
+        }
+        let title = score(self.title, &beatmap.title, 2);
+        if title == 0 {
+            return None;
+        }
+        Some(
+            title
+                + score(self.artist, &beatmap.artist, 2)
+                + score(self.difficulty, &beatmap.difficulty_name, 4),
+        )
+    }
+}
+
+/// Look up "now playing" pastes from the osu! client with the beatmap search,
+/// and post the closest beatmap with the pasted mods.
+async fn handle_np_paste(ctx: &Context, msg: &Message) -> Result<()> {
+    let Some(np) = NowPlaying::parse(&msg.content) else {
+        return Ok(());
+    };
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    if !env.hook_enabled(msg, NP_HOOK).await
+        || !env.should_respond(msg, NP_HOOK, msg.content.trim())
+    {
+        return Ok(());
+    }
+    let query = format!("{} {}", np.artist, np.title);
+    let candidates = env.client.search_beatmaps(&query, None).await?;
+    let Some(beatmap) = candidates
+        .into_iter()
+        .filter_map(|b| np.similarity(&b).map(|s| (s, b)))
+        .max_by_key(|(s, _)| *s)
+        .map(|(_, b)| b)
+    else {
+        return Ok(());
+    };
+    let mode = beatmap.mode;
+    let info = env.oppai.get_possible_pp(&beatmap, mode, np.mods).await?;
+    handle_beatmap(
+        ctx,
+        &env,
+        &beatmap,
+        info,
+        msg.content.trim(),
+        None,
+        np.mods,
+        msg,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Expand the osu! score links in the message into score embeds.
+/// Links without a mode are lazer score links, which are unique across modes.
+async fn handle_score_links(ctx: &Context, msg: &Message) -> Result<()> {
+    let captures = SCORE_LINK_REGEX
+        .captures_iter(&msg.content)
+        .take(MAX_SCORE_LINKS)
+        .collect::<Vec<_>>();
+    if captures.is_empty() {
+        return Ok(());
+    }
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    if !env.hook_enabled(msg, SCORE_HOOK).await {
+        return Ok(());
+    }
+    let theme = env.embed_theme_for(msg).await;
+    for capture in captures {
+        let score_id: u64 = capture["id"].parse()?;
+        if !env.should_respond(msg, SCORE_HOOK, score_id) {
+            continue;
+        }
+        let mode = capture
+            .name("mode")
+            .and_then(|v| Mode::parse_from_new_site(v.as_str()));
+        let Some((score, mode)) = env.client.score(score_id, mode).await? else {
+            continue;
+        };
+        let beatmap = env.beatmaps.get_beatmap(score.beatmap_id, mode).await?;
+        let content = env.oppai.get_beatmap(beatmap.beatmap_id).await?;
+        let bm = super::BeatmapWithMode(beatmap, mode);
+        let Some(user) = env.users.get(UserID::ID(score.user_id), Some(mode)).await? else {
+            continue;
+        };
+        let hit_stats = score_hit_stats(&env, &score, mode, &content).await;
+        let global_rank = score_global_rank(&env, &score, mode).await;
+        let card = score_card_attachment(&env, &theme, &score, &bm, &content, &user).await;
+        msg.channel_id
+            .send_message(
+                ctx,
+                CreateMessage::new()
+                    .reference_message(msg)
+                    .embed(
+                        score_embed(&score, &bm, &content, &user, &theme)
+                            .lazer(capture.name("mode").is_none())
+                            .hit_stats(hit_stats)
+                            .global_rank(global_rank)
+                            .score_card(card.is_some())
+                            .build(),
+                    )
+                    .add_files(card),
+            )
+            .await?;
+        crate::discord::cache::publish_beatmap(&env, msg.guild_id, msg.channel_id, &bm).await;
+    }
+    Ok(())
+}
+
+/// Expand the osu! profile links in the message into compact user embeds.
+async fn handle_profile_links(ctx: &Context, msg: &Message) -> Result<()> {
+    let captures = PROFILE_LINK_REGEX
+        .captures_iter(&msg.content)
+        .take(MAX_PROFILE_LINKS)
+        .collect::<Vec<_>>();
+    if captures.is_empty() {
+        return Ok(());
+    }
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    if !env.hook_enabled(msg, PROFILE_HOOK).await {
+        return Ok(());
+    }
+    let theme = env.embed_theme_for(msg).await;
+    for capture in captures {
+        if !env.should_respond(msg, PROFILE_HOOK, &capture[0]) {
+            continue;
+        }
+        let user = UserID::from_string(&capture["user"]);
+        let mode = capture
+            .name("mode")
+            .and_then(|v| Mode::parse_from_new_site(v.as_str()));
+        let Some(u) = env.users.get(user, mode).await? else {
+            continue;
+        };
+        env.recent_usernames.record(msg.channel_id, &u.username);
+        msg.channel_id
+            .send_message(
+                ctx,
+                CreateMessage::new()
+                    .reference_message(msg)
+                    .embed(user_compact_embed(&u, mode.unwrap_or(Mode::Std), &theme)),
+            )
+            .await?;
+    }
+    Ok(())
+}
+
 enum EmbedType {
     Beatmap(Box<Beatmap>, BeatmapInfoWithPP, Mods),
     Beatmapset(Vec<Beatmap>),
@@ -168,15 +546,76 @@ struct ToPrint<'a> {
     mode: Option<Mode>,
 }
 
+/// The beatmaps linked in `content`, by old site, new site and short links.
+/// If `gated`, short links are only expanded in the server's announcer channel.
+fn linked_beatmaps<'a>(
+    env: &'a OsuEnv,
+    msg: &'a Message,
+    content: &'a str,
+    gated: bool,
+) -> impl stream::Stream<Item = ToPrint<'a>> + 'a {
+    stream::select(
+        handle_old_links(env, content),
+        stream::select(
+            handle_new_links(env, content),
+            handle_short_links(env, msg, content, gated),
+        ),
+    )
+}
+
+/// Expand the beatmaps linked in the message, in its embeds and in the message it replies to,
+/// like the beatmap hook does, but into embeds to be posted anywhere.
+/// Each embed comes with its beatmap, the first difficulty for beatmapsets.
+pub(crate) async fn expand_beatmaps(
+    ctx: &Context,
+    msg: &Message,
+) -> Result<Vec<(CreateEmbed, super::BeatmapWithMode)>> {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let theme = env.embed_theme_for(msg).await;
+    let mut texts = vec![msg.content.clone()];
+    for e in &msg.embeds {
+        texts.extend(e.url.clone());
+        texts.extend(e.description.clone());
+        texts.extend(e.fields.iter().map(|f| f.value.clone()));
+    }
+    texts.extend(msg.referenced_message.as_ref().map(|m| m.content.clone()));
+
+    let mut seen = std::collections::HashSet::new();
+    let found = stream::iter(texts.iter())
+        .flat_map(|text| linked_beatmaps(&env, msg, text, false))
+        .filter(|l| future::ready(seen.insert(l.link.trim().to_owned())))
+        .take(MAX_EXPANDED_BEATMAPS)
+        .collect::<Vec<_>>()
+        .await;
+    Ok(found
+        .into_iter()
+        .map(|l| match l.embed {
+            EmbedType::Beatmap(b, info, mods) => {
+                let mode = l.mode.unwrap_or(b.mode);
+                (
+                    beatmap_embed(&b, mode, mods, info, &theme),
+                    super::BeatmapWithMode(*b, mode),
+                )
+            }
+            EmbedType::Beatmapset(maps) => {
+                let first = maps[0].clone();
+                let mode = l.mode.unwrap_or(first.mode);
+                (
+                    beatmapset_embed(&maps, l.mode, &[], &theme),
+                    super::BeatmapWithMode(first, mode),
+                )
+            }
+        })
+        .collect())
+}
+
 fn handle_old_links<'a>(
-    ctx: &'a Context,
+    env: &'a OsuEnv,
     content: &'a str,
 ) -> impl stream::Stream<Item = ToPrint<'a>> + 'a {
     OLD_LINK_REGEX
         .captures_iter(content)
         .map(move |capture| async move {
-            let data = ctx.data.read().await;
-            let env = data.get::<OsuEnv>().unwrap();
             let req_type = capture.name("link_type").unwrap().as_str();
             let mode = capture
                 .name("mode")
@@ -220,10 +659,7 @@ fn handle_old_links<'a>(
                         .unwrap_or(Mods::NOMOD);
                     let info = {
                         let mode = mode.unwrap_or(b.mode);
-                        env.oppai
-                            .get_beatmap(b.beatmap_id)
-                            .await
-                            .and_then(|b| b.get_possible_pp_with(mode, mods))?
+                        env.oppai.get_possible_pp(&b, mode, mods).await?
                     };
                     Some(ToPrint {
                         embed: EmbedType::Beatmap(b, info, mods),
@@ -243,20 +679,19 @@ fn handle_old_links<'a>(
         .collect::<stream::FuturesUnordered<_>>()
         .filter_map(|v| {
             future::ready(v.unwrap_or_else(|e| {
-                eprintln!("{}", e);
+                tracing::warn!("cannot handle a beatmap link: {:?}", e);
                 None
             }))
         })
 }
 
 fn handle_new_links<'a>(
-    ctx: &'a Context,
+    env: &'a OsuEnv,
     content: &'a str,
 ) -> impl stream::Stream<Item = ToPrint<'a>> + 'a {
     NEW_LINK_REGEX
         .captures_iter(content)
         .map(|capture| async move {
-            let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
             let mode = capture
                 .name("mode")
                 .and_then(|v| Mode::parse_from_new_site(v.as_str()));
@@ -289,10 +724,7 @@ fn handle_new_links<'a>(
                         .unwrap_or(Mods::NOMOD);
                     let info = {
                         let mode = mode.unwrap_or(beatmap.mode);
-                        env.oppai
-                            .get_beatmap(beatmap.beatmap_id)
-                            .await
-                            .and_then(|b| b.get_possible_pp_with(mode, mods))?
+                        env.oppai.get_possible_pp(&beatmap, mode, mods).await?
                     };
                     Some(ToPrint {
                         embed: EmbedType::Beatmap(beatmap, info, mods),
@@ -313,7 +745,7 @@ fn handle_new_links<'a>(
             future::ready(match v {
                 Ok(v) => v,
                 Err(e) => {
-                    eprintln!("{}", e);
+                    tracing::warn!("cannot handle a beatmap link: {:?}", e);
                     None
                 }
             })
@@ -321,23 +753,27 @@ fn handle_new_links<'a>(
 }
 
 fn handle_short_links<'a>(
-    ctx: &'a Context,
+    env: &'a OsuEnv,
     msg: &'a Message,
     content: &'a str,
+    gated: bool,
 ) -> impl stream::Stream<Item = ToPrint<'a>> + 'a {
     SHORT_LINK_REGEX
         .captures_iter(content)
-        .map(|capture| async move {
-            if let Some(guild_id) = msg.guild_id {
-                if announcer::announcer_of(ctx, crate::discord::announcer::ANNOUNCER_KEY, guild_id)
+        .map(move |capture| async move {
+            if let Some(guild_id) = msg.guild_id.filter(|_| gated) {
+                let announcers = env
+                    .prelude
+                    .config
+                    .get(guild_id, announcer::ANNOUNCERS)
                     .await?
-                    != Some(msg.channel_id)
+                    .unwrap_or_default();
+                if announcers.get(crate::discord::announcer::ANNOUNCER_KEY) != Some(msg.channel_id)
                 {
                     // Disable if we are not in the server's announcer channel
                     return Err(Error::msg("not in server announcer channel"));
                 }
             }
-            let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
             let mode = capture
                 .name("mode")
                 .and_then(|v| Mode::parse_from_new_site(v.as_str()));
@@ -352,10 +788,7 @@ fn handle_short_links<'a>(
                 .unwrap_or(Mods::NOMOD);
             let info = {
                 let mode = mode.unwrap_or(beatmap.mode);
-                env.oppai
-                    .get_beatmap(beatmap.beatmap_id)
-                    .await
-                    .and_then(|b| b.get_possible_pp_with(mode, mods))?
+                env.oppai.get_possible_pp(&beatmap, mode, mods).await?
             };
             let r: Result<_> = Ok(ToPrint {
                 embed: EmbedType::Beatmap(Box::new(beatmap), info, mods),
@@ -369,15 +802,17 @@ fn handle_short_links<'a>(
             future::ready(match v {
                 Ok(v) => Some(v),
                 Err(e) => {
-                    eprintln!("{}", e);
+                    tracing::warn!("cannot handle a beatmap link: {:?}", e);
                     None
                 }
             })
         })
 }
 
-async fn handle_beatmap<'a, 'b>(
-    ctx: &Context,
+#[allow(clippy::too_many_arguments)]
+async fn handle_beatmap(
+    http: impl CacheHttp,
+    env: &OsuEnv,
     beatmap: &Beatmap,
     info: BeatmapInfoWithPP,
     link: &'_ str,
@@ -385,10 +820,22 @@ async fn handle_beatmap<'a, 'b>(
     mods: Mods,
     reply_to: &Message,
 ) -> Result<()> {
+    let theme = env.embed_theme_for(reply_to).await;
+    let mode = mode.unwrap_or(beatmap.mode);
+    let graph = match env.oppai.get_beatmap(beatmap.beatmap_id).await.pls_ok() {
+        Some(content) => {
+            strain_graph_attachment(env, &theme, beatmap.beatmap_id, &content, mode, mods).await
+        }
+        None => None,
+    };
+    let cover = match graph {
+        Some(_) => None,
+        None => cover_attachment(env, &theme, beatmap.beatmapset_id).await,
+    };
     reply_to
         .channel_id
         .send_message(
-            ctx,
+            http,
             CreateMessage::new()
                 .content(
                     MessageBuilder::new()
@@ -396,18 +843,115 @@ async fn handle_beatmap<'a, 'b>(
                         .push_mono_safe(link)
                         .build(),
                 )
-                .embed(beatmap_embed(
-                    beatmap,
-                    mode.unwrap_or(beatmap.mode),
-                    mods,
-                    info,
+                .embed(with_cover(
+                    with_strain_graph(
+                        beatmap_embed(beatmap, mode, mods, info, &theme),
+                        graph.is_some(),
+                    ),
+                    cover.is_some(),
+                ))
+                .add_files(graph)
+                .add_files(cover)
+                .reference_message(reply_to),
+        )
+        .await?;
+    let bm = super::BeatmapWithMode(beatmap.clone(), mode);
+    crate::discord::cache::publish_beatmap(env, reply_to.guild_id, reply_to.channel_id, &bm).await;
+    Ok(())
+}
+
+/// Reply to a beatmap link with a single line of star rating and pp, for busy channels.
+async fn handle_beatmap_compact(
+    http: impl CacheHttp,
+    env: &OsuEnv,
+    beatmap: &Beatmap,
+    info: BeatmapInfoWithPP,
+    reply_to: &Message,
+) -> Result<()> {
+    let content = env.oppai.get_beatmap(beatmap.beatmap_id).await?;
+    let pp = content.get_pp_from(
+        info.mode,
+        None,
+        Accuracy::ByValue(COMPACT_ACCURACY, 0),
+        info.mods,
+    )?;
+    reply_to
+        .channel_id
+        .send_message(
+            http,
+            CreateMessage::new()
+                .content(format!(
+                    "{} (`{}`)",
+                    compact_line(info.info.stars, pp),
+                    beatmap.short_link(Some(info.mode), Some(info.mods))
                 ))
                 .reference_message(reply_to),
         )
         .await?;
+    let bm = super::BeatmapWithMode(beatmap.clone(), info.mode);
+    crate::discord::cache::publish_beatmap(env, reply_to.guild_id, reply_to.channel_id, &bm).await;
+    Ok(())
+}
+
+/// Reply to a beatmapset link with a star rating and pp line for each of its hardest difficulties.
+async fn handle_beatmapset_compact(
+    http: impl CacheHttp,
+    env: &OsuEnv,
+    mut beatmaps: Vec<Beatmap>,
+    mode: Option<Mode>,
+    reply_to: &Message,
+) -> Result<()> {
+    if let Some(mode) = mode {
+        beatmaps.retain(|b| b.playable_in(mode));
+    }
+    beatmaps.sort_by(|a, b| b.difficulty.stars.total_cmp(&a.difficulty.stars));
+    let Some(first) = beatmaps.first() else {
+        return Ok(());
+    };
+
+    let mut content = MessageBuilder::new();
+    for b in beatmaps.iter().take(MAX_COMPACT_DIFFS) {
+        let mode = mode.unwrap_or(b.mode);
+        let Some(map) = env.oppai.get_beatmap(b.beatmap_id).await.pls_ok() else {
+            continue;
+        };
+        let stars = map.get_info_with(mode, Mods::NOMOD)?.stars;
+        let pp = map.get_pp_from(
+            mode,
+            None,
+            Accuracy::ByValue(COMPACT_ACCURACY, 0),
+            Mods::NOMOD,
+        )?;
+        content
+            .push_safe(format!("[{}] ", b.difficulty_name))
+            .push(compact_line(stars, pp))
+            .push_line(format!(" (`{}`)", b.short_link(Some(mode), None)));
+    }
+    if beatmaps.len() > MAX_COMPACT_DIFFS {
+        content.push_italic(format!(
+            "...and {} easier difficulties",
+            beatmaps.len() - MAX_COMPACT_DIFFS
+        ));
+    }
+    reply_to
+        .channel_id
+        .send_message(
+            http,
+            CreateMessage::new()
+                .content(content.build())
+                .reference_message(reply_to),
+        )
+        .await?;
+    let bm = super::BeatmapWithMode(first.clone(), mode.unwrap_or(first.mode));
+    crate::discord::cache::publish_beatmap(env, reply_to.guild_id, reply_to.channel_id, &bm).await;
     Ok(())
 }
 
+/// The `★6.32 | 287pp @97%` line of compact responses.
+fn compact_line(stars: f64, pp: f64) -> String {
+    format!("★{:.2} | {:.0}pp @{}%", stars, pp, COMPACT_ACCURACY)
+}
+
 async fn handle_beatmapset<'a, 'b>(
     ctx: &Context,
     beatmaps: Vec<Beatmap>,
@@ -427,3 +971,86 @@ async fn handle_beatmapset<'a, 'b>(
     .pls_ok();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::id::GuildId;
+
+    use super::*;
+    use crate::discord::tests::{TestEnv, AUTHOR, CHANNEL};
+
+    const GUILD: GuildId = GuildId::new(5);
+
+    #[tokio::test]
+    async fn test_beatmap_link() {
+        let t = TestEnv::new().await;
+        t.add_beatmap(100).await;
+        let msg = t
+            .discord
+            .message(CHANNEL, AUTHOR, "https://osu.ppy.sh/beatmapsets/10#osu/100")
+            .unwrap();
+
+        let sets = handle_beatmap_links(t.discord.cache_and_http(), &t.env, &msg).await;
+        assert!(sets.is_empty());
+        let sent = t.discord.sent();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].content.starts_with("Beatmap information for"));
+        assert_eq!(sent[0].embeds.len(), 1);
+
+        // The same link is not shown again right away.
+        handle_beatmap_links(t.discord.cache_and_http(), &t.env, &msg).await;
+        assert_eq!(t.discord.sent().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_beatmapset_link() {
+        let t = TestEnv::new().await;
+        t.add_beatmap(100).await;
+        t.add_beatmap(101).await;
+        let msg = t
+            .discord
+            .message(CHANNEL, AUTHOR, "https://osu.ppy.sh/beatmapsets/10")
+            .unwrap();
+
+        let sets = handle_beatmap_links(t.discord.cache_and_http(), &t.env, &msg).await;
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].beatmaps.len(), 2);
+        assert_eq!(sets[0].link, "https://osu.ppy.sh/beatmapsets/10");
+        // Full beatmapsets are paginated by the caller.
+        assert!(t.discord.sent().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_beatmap_link_switches() {
+        let t = TestEnv::new().await;
+        t.add_beatmap(100).await;
+        let hooks = &t.env.prelude.hooks;
+
+        hooks
+            .set(GUILD, None, COMPACT_HOOK, Some(true))
+            .await
+            .unwrap();
+        let mut msg = t
+            .discord
+            .message(CHANNEL, AUTHOR, "https://osu.ppy.sh/beatmapsets/10#osu/100")
+            .unwrap();
+        msg.guild_id = Some(GUILD);
+        handle_beatmap_links(t.discord.cache_and_http(), &t.env, &msg).await;
+        let sent = t.discord.sent();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].content.contains('★'));
+        assert!(sent[0].embeds.is_empty());
+
+        hooks
+            .set(GUILD, Some(CHANNEL), BEATMAP_HOOK, Some(false))
+            .await
+            .unwrap();
+        let mut msg = t
+            .discord
+            .message(CHANNEL, AUTHOR, "https://osu.ppy.sh/b/100")
+            .unwrap();
+        msg.guild_id = Some(GUILD);
+        handle_beatmap_links(t.discord.cache_and_http(), &t.env, &msg).await;
+        assert_eq!(t.discord.sent().len(), 1);
+    }
+}
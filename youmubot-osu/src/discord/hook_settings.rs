@@ -0,0 +1,156 @@
+use serenity::{
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+    utils::MessageBuilder,
+};
+
+use youmubot_prelude::permissions::MANAGE_CHANNELS_CHECK;
+use youmubot_prelude::*;
+
+use super::OsuEnv;
+
+/// The beatmap link and `.osu`/`.osz` attachment hook.
+pub const BEATMAP_HOOK: &str = "beatmap";
+/// The profile link hook.
+pub const PROFILE_HOOK: &str = "profile";
+/// The score link hook.
+pub const SCORE_HOOK: &str = "score";
+/// The "now playing" paste hook.
+pub const NP_HOOK: &str = "np";
+/// The `.osr` replay attachment hook.
+pub const REPLAY_HOOK: &str = "replay";
+/// The multiplayer match and room link hook.
+pub const MATCH_HOOK: &str = "match";
+/// Reply to beatmap links with a single line of star rating and pp instead of a full embed.
+pub const COMPACT_HOOK: &str = "compact";
+
+/// Hooks that are disabled unless switched on.
+const OPT_IN_HOOKS: &[&str] = &[COMPACT_HOOK];
+
+/// All hooks that can be switched on and off, with their descriptions.
+pub const HOOKS: &[(&str, &str)] = &[
+    (
+        BEATMAP_HOOK,
+        "expand beatmap links and attached .osu/.osz files",
+    ),
+    (PROFILE_HOOK, "expand osu! profile links"),
+    (SCORE_HOOK, "expand osu! score links"),
+    (NP_HOOK, "look up pasted \"now playing\" beatmaps"),
+    (REPLAY_HOOK, "analyze attached .osr replays"),
+    (MATCH_HOOK, "summarize multiplayer match and room links"),
+    (
+        COMPACT_HOOK,
+        "reply to beatmap links with a short star rating and pp line instead of an embed (off by default)",
+    ),
+];
+
+impl OsuEnv {
+    /// Whether the given hook should respond to the message.
+    pub(crate) async fn hook_enabled(&self, msg: &Message, hook: &str) -> bool {
+        self.prelude
+            .hooks
+            .enabled_or(
+                msg.guild_id,
+                msg.channel_id,
+                hook,
+                !OPT_IN_HOOKS.contains(&hook),
+            )
+            .await
+    }
+
+    /// Whether the hook should respond to `key` (e.g. a link) in the message's channel,
+    /// i.e. it did not just respond to the same key, and the channel is not flooded with responses.
+    pub(crate) fn should_respond(
+        &self,
+        msg: &Message,
+        hook: &str,
+        key: impl std::fmt::Display,
+    ) -> bool {
+        self.prelude
+            .hook_cooldown
+            .try_respond(msg.channel_id, &format!("{}:{}", hook, key))
+    }
+}
+
+#[command("hooks")]
+#[description = "Switch the osu! hooks on or off in the whole server, or in a single channel. \
+                 `reset` removes the switch, going back to the server's switch (for channels) or the default. \
+                 Without arguments, show the hooks and whether they are enabled in this channel."]
+#[usage = "[enable/disable/reset] [hook] [#channel]"]
+#[example = "disable replay #no-bots"]
+#[checks(manage_channels)]
+#[only_in(guilds)]
+#[max_args(3)]
+pub async fn hooks(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let guild_id = msg.guild_id.unwrap();
+
+    let action = match args.current().map(|v| v.to_lowercase()).as_deref() {
+        Some("enable") => Some(Some(true)),
+        Some("disable") => Some(Some(false)),
+        Some("reset") => Some(None),
+        _ => None,
+    };
+    let Some(enabled) = action else {
+        return list_hooks(ctx, msg, &env).await;
+    };
+    args.advance();
+
+    let hook = args.single::<String>()?.to_lowercase();
+    if !HOOKS.iter().any(|(name, _)| *name == hook) {
+        return Err(error!(
+            "unknown hook `{}`, available hooks are: {}",
+            hook,
+            HOOKS
+                .iter()
+                .map(|(name, _)| format!("`{}`", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+        .into());
+    }
+    let channel = if args.is_empty() {
+        None
+    } else {
+        Some(args.single::<args::ChannelId>()?.0)
+    };
+    env.prelude
+        .hooks
+        .set(guild_id, channel, &hook, enabled)
+        .await?;
+
+    let mut reply = MessageBuilder::new();
+    reply.push("Hook ").push_mono(&hook).push(match enabled {
+        Some(true) => " is now enabled in ",
+        Some(false) => " is now disabled in ",
+        None => " is reset in ",
+    });
+    match channel {
+        Some(c) => reply.mention(&c),
+        None => reply.push("the whole server"),
+    };
+    msg.reply(&ctx, reply.build()).await?;
+    Ok(())
+}
+
+/// Show the hooks, and whether they respond in the channel.
+async fn list_hooks(ctx: &Context, msg: &Message, env: &OsuEnv) -> CommandResult {
+    let mut content = MessageBuilder::new();
+    content
+        .push("Hooks in ")
+        .mention(&msg.channel_id)
+        .push_line(":");
+    for (name, description) in HOOKS {
+        content
+            .push("- ")
+            .push_mono(*name)
+            .push(format!(" ({}): ", description))
+            .push_bold_line(if env.hook_enabled(msg, name).await {
+                "enabled"
+            } else {
+                "disabled"
+            });
+    }
+    msg.reply(&ctx, content.build()).await?;
+    Ok(())
+}
@@ -0,0 +1,22 @@
+//! The translations of the osu! messages, kept in `locales/<language>.ftl`.
+use std::fmt::Display;
+
+use lazy_static::lazy_static;
+use youmubot_prelude::{i18n::Bundle, Locale};
+
+lazy_static! {
+    static ref BUNDLE: Bundle = Bundle::new(&[
+        (Locale::En, include_str!("../../locales/en.ftl")),
+        (Locale::Vi, include_str!("../../locales/vi.ftl")),
+    ]);
+}
+
+/// The message with the key, in the given language.
+pub(crate) fn tr(locale: Locale, key: &'static str) -> &'static str {
+    BUNDLE.get(locale, key)
+}
+
+/// The message with the key in the given language, with its placeholders replaced by the arguments.
+pub(crate) fn trf(locale: Locale, key: &str, args: &[(&str, &dyn Display)]) -> String {
+    BUNDLE.format(locale, key, args)
+}
@@ -0,0 +1,85 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use dashmap::DashMap;
+
+use youmubot_prelude::*;
+
+use crate::models::{Mode, Score};
+
+use super::BeatmapMetaCache;
+
+/// How many users to keep in memory.
+const CACHE_SIZE: usize = 1024;
+
+/// A cache of the weighted map length of each user's top plays.
+///
+/// The value is reused for as long as the user's top plays are the same beatmaps with the same mods,
+/// in the same order. Any change to the top plays invalidates it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MapLengthCache {
+    entries: Arc<DashMap<(u64, Mode), (u64 /* fingerprint */, f64)>>,
+}
+
+impl MapLengthCache {
+    /// Get the weighted map length of the user's top plays, computing it if the top plays changed.
+    pub async fn get(
+        &self,
+        user_id: u64,
+        scores: &[Score],
+        beatmaps: &BeatmapMetaCache,
+        mode: Mode,
+    ) -> Result<f64> {
+        let key = (user_id, mode);
+        let fingerprint = Self::fingerprint(scores);
+        if let Some(entry) = self.entries.get(&key) {
+            let (cached_fingerprint, length) = *entry.value();
+            if cached_fingerprint == fingerprint {
+                return Ok(length);
+            }
+        }
+        let length = calculate_weighted_map_length(scores, beatmaps, mode).await?;
+        if self.entries.len() >= CACHE_SIZE {
+            self.entries.clear();
+        }
+        self.entries.insert(key, (fingerprint, length));
+        Ok(length)
+    }
+
+    /// The fingerprint of a list of top plays: the beatmaps and mods, in order.
+    fn fingerprint(scores: &[Score]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for s in scores {
+            s.beatmap_id.hash(&mut hasher);
+            s.mods.bits().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+async fn calculate_weighted_map_length(
+    scores: &[Score],
+    cache: &BeatmapMetaCache,
+    mode: Mode,
+) -> Result<f64> {
+    const SCALING_FACTOR: f64 = 0.975;
+    let ids = scores.iter().map(|s| s.beatmap_id).collect::<Vec<_>>();
+    let beatmaps = cache.get_beatmaps(&ids, mode).await?;
+    scores
+        .iter()
+        .enumerate()
+        .map(|(i, s)| -> Result<f64> {
+            let beatmap = beatmaps
+                .get(&s.beatmap_id)
+                .ok_or_else(|| error!("beatmap {} not found", s.beatmap_id))?;
+            Ok(beatmap
+                .difficulty_with(mode, s.mods, 0.0 /* dont care */)
+                .drain_length
+                .as_secs_f64()
+                * (SCALING_FACTOR.powi(i as i32)))
+        })
+        .sum()
+}
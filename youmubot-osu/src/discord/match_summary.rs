@@ -0,0 +1,715 @@
+//! Summaries of multiplayer matches and lazer rooms, for the match link hook and the `match` command.
+use std::collections::HashMap;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serenity::{
+    builder::{CreateEmbed, CreateEmbedFooter, CreateMessage, EditMessage},
+    framework::standard::{macros::command, Args, CommandResult},
+    model::{
+        channel::Message,
+        id::{ChannelId, MessageId},
+    },
+    utils::MessageBuilder,
+};
+
+use youmubot_prelude::announcer::CacheAndHttp;
+use youmubot_prelude::*;
+
+use crate::{
+    models::{
+        multiplayer::{Match, MatchGame, Room, Team, TeamType},
+        Beatmap,
+    },
+    ratelimit::Priority,
+};
+
+use super::{
+    cache::publish_beatmap, hook::MATCH_LINK_REGEX, theme::EmbedTheme, BeatmapWithMode, OsuEnv,
+};
+
+/// The number of most recent games shown in a match summary.
+const MAX_GAMES_SHOWN: usize = 10;
+/// The number of top scores fetched for each playlist item of a room.
+const ROOM_SCORES_PER_ITEM: u32 = 1;
+/// How often a running match's summary is refreshed.
+const LIVE_INTERVAL: Duration = Duration::from_secs(60);
+/// How long a running match's summary keeps being refreshed.
+const LIVE_FOR: Duration = Duration::from_secs(3 * 60 * 60);
+/// The number of games on each page of the `match` command.
+const GAMES_PER_PAGE: usize = 8;
+/// The number of players listed in the overview of the `match` command.
+const MAX_PLAYERS_SHOWN: usize = 16;
+
+#[command("match")]
+#[aliases("mp")]
+#[description = "Summarize a multiplayer match: the team totals, the average of each player and the MVP, \
+                 followed by the winner of each game."]
+#[usage = "[link to the match, or its id]"]
+#[example = "https://osu.ppy.sh/community/matches/111555364"]
+#[num_args(1)]
+#[bucket("heavy")]
+pub async fn mp(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let theme = env.embed_theme_for(msg).await;
+    let arg = args.rest().trim();
+    let match_id = match MATCH_LINK_REGEX.captures(arg) {
+        Some(c) => c["id"].parse::<u64>().ok(),
+        None => arg.parse::<u64>().ok(),
+    }
+    .ok_or_else(|| Error::msg(theme.tr("match-invalid-link")))?;
+    let Some(m) = env.client.osu_match(match_id).await? else {
+        msg.reply(&ctx, theme.tr("match-not-found")).await?;
+        return Ok(());
+    };
+
+    let m = Arc::new(m);
+    let stats = Arc::new(player_stats(&m));
+    let pages = 1 + m.games.len().div_ceil(GAMES_PER_PAGE);
+    paginate_reply_fn(
+        move |page: u8, ctx: &Context, message: &mut Message| {
+            let env = env.clone();
+            let theme = theme.clone();
+            let m = m.clone();
+            let stats = stats.clone();
+            Box::pin(async move {
+                let page = page as usize;
+                let embed = if page == 0 {
+                    overview_embed(&m, &stats, &theme)
+                } else {
+                    let start = (page - 1) * GAMES_PER_PAGE;
+                    let end = (start + GAMES_PER_PAGE).min(m.games.len());
+                    if start >= end {
+                        return Ok(false);
+                    }
+                    let beatmaps = game_beatmaps(&env, &m.games[start..end]).await;
+                    games_embed(&m, start, &m.games[start..end], &beatmaps, &theme)
+                };
+                message
+                    .edit(
+                        ctx,
+                        EditMessage::new().embed(embed.footer(CreateEmbedFooter::new(format!(
+                            "{} games, {} • Page {}/{}",
+                            m.games.len(),
+                            if m.end_time.is_some() {
+                                "finished"
+                            } else {
+                                "in progress"
+                            },
+                            page + 1,
+                            pages
+                        )))),
+                    )
+                    .await?;
+                Ok(true)
+            })
+        },
+        ctx,
+        msg,
+        Duration::from_secs(60),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Reply with the summary of the match, and keep it updated while the match is running.
+pub(crate) async fn post_match(ctx: &Context, reply_to: &Message, match_id: u64) -> Result<()> {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let Some(m) = env.client.osu_match(match_id).await? else {
+        return Ok(());
+    };
+    let theme = env.embed_theme_for(reply_to).await;
+    let beatmaps = game_beatmaps(&env, shown_games(&m)).await;
+    let message = reply_to
+        .channel_id
+        .send_message(
+            ctx,
+            CreateMessage::new()
+                .reference_message(reply_to)
+                .embed(match_embed(&m, &beatmaps, &theme)),
+        )
+        .await?;
+    if let Some((game, b)) = m
+        .games
+        .iter()
+        .rev()
+        .find_map(|g| beatmaps.get(&g.beatmap_id).map(|b| (g, b)))
+    {
+        let bm = BeatmapWithMode(b.clone(), game.mode);
+        publish_beatmap(&env, reply_to.guild_id, reply_to.channel_id, &bm).await;
+    }
+    if m.end_time.is_some() {
+        return Ok(());
+    }
+
+    env.live_matches.watch(
+        &env,
+        CacheAndHttp::from_context(ctx),
+        &m,
+        LivePost {
+            channel_id: message.channel_id,
+            message_id: message.id,
+            theme,
+            posted: Instant::now(),
+        },
+    );
+    Ok(())
+}
+
+/// The summaries of running matches that are kept up to date, by match id.
+///
+/// All of them are refreshed by a single background task, so that a match is only polled once
+/// no matter how many times it is posted.
+#[derive(Clone, Default)]
+pub(crate) struct LiveMatches {
+    matches: Arc<DashMap<u64, LiveMatch>>,
+    started: Arc<AtomicBool>,
+}
+
+struct LiveMatch {
+    progress: (usize, usize),
+    posts: Vec<LivePost>,
+}
+
+/// A posted summary of a running match.
+struct LivePost {
+    channel_id: ChannelId,
+    message_id: MessageId,
+    theme: EmbedTheme,
+    posted: Instant,
+}
+
+impl LiveMatches {
+    /// Keep the posted summary of the match updated, starting the background task if needed.
+    fn watch(&self, env: &OsuEnv, http: CacheAndHttp, m: &Match, post: LivePost) {
+        self.matches
+            .entry(m.id)
+            .or_insert_with(|| LiveMatch {
+                progress: progress(m),
+                posts: vec![],
+            })
+            .posts
+            .push(post);
+        if self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let live = self.clone();
+        let env = env.clone();
+        let supervisor = env.prelude.supervisor.clone();
+        supervisor.spawn("osu-live-matches", move |shutdown| {
+            live.clone().run(env.clone(), http.clone(), shutdown)
+        });
+    }
+
+    /// Refresh the summaries every [LIVE_INTERVAL], until shutdown.
+    async fn run(self, env: OsuEnv, http: CacheAndHttp, shutdown: Shutdown) {
+        let client = env.client.with_priority(Priority::Background);
+        while shutdown.sleep(LIVE_INTERVAL).await {
+            let match_ids = self.matches.iter().map(|m| *m.key()).collect::<Vec<_>>();
+            for match_id in match_ids {
+                let m = client.osu_match(match_id).await.pls_ok().flatten();
+                self.refresh(&env, &http, match_id, m).await;
+            }
+        }
+    }
+
+    /// Edit the summaries of the match if it changed, and stop following it once it is finished,
+    /// gone, or has been followed for [LIVE_FOR].
+    async fn refresh(&self, env: &OsuEnv, http: &CacheAndHttp, match_id: u64, m: Option<Match>) {
+        let Some(m) = m else {
+            self.matches.remove(&match_id);
+            return;
+        };
+        let finished = m.end_time.is_some();
+        let to_edit = {
+            let Some(mut live) = self.matches.get_mut(&match_id) else {
+                return;
+            };
+            let changed = finished || live.progress != progress(&m);
+            live.progress = progress(&m);
+            let to_edit = if changed {
+                live.posts
+                    .iter()
+                    .map(|p| (p.channel_id, p.message_id, p.theme.clone()))
+                    .collect()
+            } else {
+                vec![]
+            };
+            live.posts.retain(|p| p.posted.elapsed() < LIVE_FOR);
+            to_edit
+        };
+        self.matches
+            .remove_if(&match_id, |_, live| finished || live.posts.is_empty());
+        if to_edit.is_empty() {
+            return;
+        }
+        let beatmaps = game_beatmaps(env, shown_games(&m)).await;
+        for (channel_id, message_id, theme) in to_edit {
+            channel_id
+                .edit_message(
+                    http,
+                    message_id,
+                    EditMessage::new().embed(match_embed(&m, &beatmaps, &theme)),
+                )
+                .await
+                .pls_ok();
+        }
+    }
+}
+
+/// The number of games started and finished in the match, to tell whether the summary changed.
+fn progress(m: &Match) -> (usize, usize) {
+    (
+        m.games.len(),
+        m.games.iter().filter(|g| g.end_time.is_some()).count(),
+    )
+}
+
+/// Reply with the summary of the lazer room.
+pub(crate) async fn post_room(ctx: &Context, reply_to: &Message, room_id: u64) -> Result<()> {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let Some(room) = env.client.room(room_id).await? else {
+        return Ok(());
+    };
+    let theme = env.embed_theme_for(reply_to).await;
+    let mut items = Vec::with_capacity(room.playlist.len());
+    for item in room.playlist.iter().take(MAX_GAMES_SHOWN) {
+        let beatmap = env
+            .beatmaps
+            .get_beatmap(item.beatmap_id, item.mode)
+            .await
+            .pls_ok();
+        let top = env
+            .client
+            .playlist_scores(room.id, item.id, ROOM_SCORES_PER_ITEM)
+            .await
+            .pls_ok()
+            .and_then(|s| s.into_iter().next());
+        items.push((beatmap, top));
+    }
+
+    let mut description = MessageBuilder::new();
+    if let Some(host) = &room.host {
+        description.push("Hosted by ").push_bold_line_safe(host);
+    }
+    description.push_line(format!(
+        "**{}** participants, {}",
+        room.participant_count,
+        match room.ends_at {
+            Some(t) if t > chrono::Utc::now() => format!("ends <t:{}:R>", t.timestamp()),
+            Some(t) => format!("ended <t:{}:R>", t.timestamp()),
+            None => "open until closed".to_owned(),
+        }
+    ));
+    description.push_line("");
+    for ((beatmap, top), item) in items.iter().zip(&room.playlist) {
+        description.push(map_line(beatmap.as_ref(), item.beatmap_id));
+        if item.required_mods != crate::models::Mods::NOMOD {
+            description.push(format!(" {}", item.required_mods));
+        }
+        description.push_line("");
+        match top {
+            Some(s) => description.push_line(format!(
+                "> 🥇 **{}**: {} ({:.2}%, {}x)",
+                MessageBuilder::new().push_safe(&s.username).build(),
+                super::embeds::grouped_number(s.total_score),
+                s.accuracy,
+                s.max_combo
+            )),
+            None => description.push_line("> No scores yet"),
+        };
+    }
+
+    reply_to
+        .channel_id
+        .send_message(
+            ctx,
+            CreateMessage::new().reference_message(reply_to).embed(
+                CreateEmbed::new()
+                    .title(MessageBuilder::new().push_safe(&room.name).build())
+                    .url(room.link())
+                    .color(theme.color)
+                    .description(description.build())
+                    .footer(CreateEmbedFooter::new(format!(
+                        "{} playlist items",
+                        room.playlist.len()
+                    ))),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Fetch the beatmaps of the games. Missing beatmaps are left out.
+async fn game_beatmaps(env: &OsuEnv, games: &[MatchGame]) -> HashMap<u64, Beatmap> {
+    let mut beatmaps = HashMap::new();
+    for game in games {
+        if beatmaps.contains_key(&game.beatmap_id) {
+            continue;
+        }
+        if let Some(b) = env
+            .beatmaps
+            .get_beatmap(game.beatmap_id, game.mode)
+            .await
+            .pls_ok()
+        {
+            beatmaps.insert(game.beatmap_id, b);
+        }
+    }
+    beatmaps
+}
+
+/// The most recent games, that are shown in the summary.
+fn shown_games(m: &Match) -> &[MatchGame] {
+    &m.games[m.games.len().saturating_sub(MAX_GAMES_SHOWN)..]
+}
+
+/// The `[Title [Difficulty]](link)` line of a beatmap, or its id if it is unknown.
+fn map_line(beatmap: Option<&Beatmap>, beatmap_id: u64) -> String {
+    match beatmap {
+        Some(b) => format!(
+            "[{} [{}]]({})",
+            MessageBuilder::new().push_safe(&b.title).build(),
+            MessageBuilder::new().push_safe(&b.difficulty_name).build(),
+            b.link()
+        ),
+        None => format!("Beatmap #{}", beatmap_id),
+    }
+}
+
+/// The winner of the game: a team in team games, otherwise the top player.
+enum Winner {
+    Team(Team, u64, u64),
+    Player(u64, u64),
+}
+
+fn game_winner(game: &MatchGame) -> Option<Winner> {
+    match game.team_type {
+        TeamType::TeamVS | TeamType::TagTeamVS => {
+            let mut teams = game.team_scores();
+            teams.sort_by(|a, b| b.1.cmp(&a.1));
+            match &teams[..] {
+                [(team, score), (_, other), ..] => Some(Winner::Team(*team, *score, *other)),
+                [(team, score)] => Some(Winner::Team(*team, *score, 0)),
+                [] => None,
+            }
+        }
+        TeamType::HeadToHead | TeamType::TagCoop => game
+            .ranked_scores()
+            .first()
+            .map(|s| Winner::Player(s.user_id, s.score)),
+    }
+}
+
+/// How a player did over the whole match.
+struct PlayerStats {
+    user_id: u64,
+    games: usize,
+    total_score: u64,
+    total_accuracy: f64,
+    /// The games won by the player, or by their team.
+    wins: u32,
+    /// The score divided by the lobby average, for each game with more than one player.
+    ratios: Vec<f64>,
+}
+
+impl PlayerStats {
+    fn average_score(&self) -> u64 {
+        self.total_score / self.games.max(1) as u64
+    }
+
+    fn average_accuracy(&self) -> f64 {
+        self.total_accuracy / self.games.max(1) as f64
+    }
+
+    /// The average of [PlayerStats::ratios], 0 if the player never played against anyone.
+    fn performance(&self) -> f64 {
+        if self.ratios.is_empty() {
+            return 0.0;
+        }
+        self.ratios.iter().sum::<f64>() / self.ratios.len() as f64
+    }
+}
+
+/// The stats of each player of the match, from the best performance to the worst.
+fn player_stats(m: &Match) -> Vec<PlayerStats> {
+    let mut stats: HashMap<u64, PlayerStats> = HashMap::new();
+    for game in &m.games {
+        let winner = game_winner(game);
+        let average =
+            game.scores.iter().map(|s| s.score as f64).sum::<f64>() / game.scores.len() as f64;
+        for s in &game.scores {
+            let p = stats.entry(s.user_id).or_insert_with(|| PlayerStats {
+                user_id: s.user_id,
+                games: 0,
+                total_score: 0,
+                total_accuracy: 0.0,
+                wins: 0,
+                ratios: vec![],
+            });
+            p.games += 1;
+            p.total_score += s.score;
+            p.total_accuracy += s.accuracy;
+            match &winner {
+                Some(Winner::Team(team, _, _)) if *team == s.team => p.wins += 1,
+                Some(Winner::Player(user, _)) if *user == s.user_id => p.wins += 1,
+                _ => (),
+            }
+            if game.scores.len() > 1 && average > 0.0 {
+                p.ratios.push(s.score as f64 / average);
+            }
+        }
+    }
+    let mut stats = stats.into_values().collect::<Vec<_>>();
+    stats.sort_by(|a, b| b.performance().total_cmp(&a.performance()));
+    stats
+}
+
+/// The player that did best compared to the lobby average, among those who played
+/// at least half of the games. Returns the player and their average performance ratio.
+fn mvp(m: &Match, stats: &[PlayerStats]) -> Option<(u64, f64)> {
+    let games = m.games.iter().filter(|g| g.scores.len() > 1).count();
+    stats
+        .iter()
+        .filter(|p| !p.ratios.is_empty() && p.ratios.len() * 2 >= games)
+        .map(|p| (p.user_id, p.performance()))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// The wins of each team, if the match is played in teams.
+fn team_wins(m: &Match) -> HashMap<Team, u32> {
+    let mut wins = HashMap::new();
+    for game in &m.games {
+        if let Some(Winner::Team(team, _, _)) = game_winner(game) {
+            *wins.entry(team).or_default() += 1;
+        }
+    }
+    wins
+}
+
+/// The `> Red wins, 1,000 to 900` line of a game.
+fn result_line(m: &Match, game: &MatchGame) -> String {
+    let result = match game_winner(game) {
+        _ if game.end_time.is_none() => "playing...".to_owned(),
+        Some(Winner::Team(team, score, other)) => format!(
+            "{} wins, {} to {}",
+            match team {
+                Team::Red => "🔴 Red",
+                Team::Blue => "🔵 Blue",
+                Team::None => "Nobody",
+            },
+            super::embeds::grouped_number(score),
+            super::embeds::grouped_number(other)
+        ),
+        Some(Winner::Player(user, score)) => format!(
+            "**{}** wins with {}",
+            MessageBuilder::new().push_safe(m.username(user)).build(),
+            super::embeds::grouped_number(score)
+        ),
+        None => "no scores".to_owned(),
+    };
+    format!("> {}", result)
+}
+
+/// The first page of the `match` command: the totals of the teams and the players.
+fn overview_embed(m: &Match, stats: &[PlayerStats], theme: &EmbedTheme) -> CreateEmbed {
+    let mut description = MessageBuilder::new();
+    description.push_line(match m.end_time {
+        Some(end) => format!(
+            "Played <t:{}:f>, for {} minutes",
+            m.start_time.timestamp(),
+            (end - m.start_time).num_minutes()
+        ),
+        None => format!("Started <t:{}:R>", m.start_time.timestamp()),
+    });
+    let wins = team_wins(m);
+    if !wins.is_empty() {
+        let mut totals: HashMap<Team, u64> = HashMap::new();
+        for game in &m.games {
+            for (team, score) in game.team_scores() {
+                *totals.entry(team).or_default() += score;
+            }
+        }
+        description.push_line(format!(
+            "🔴 **{}** - **{}** 🔵",
+            wins.get(&Team::Red).copied().unwrap_or(0),
+            wins.get(&Team::Blue).copied().unwrap_or(0)
+        ));
+        description.push_line(format!(
+            "Total score: 🔴 {} | 🔵 {}",
+            super::embeds::grouped_number(totals.get(&Team::Red).copied().unwrap_or(0)),
+            super::embeds::grouped_number(totals.get(&Team::Blue).copied().unwrap_or(0))
+        ));
+    }
+    if let Some((user, ratio)) = mvp(m, stats) {
+        description.push_line(format!(
+            "⭐ MVP: **{}** ({:.2}x the lobby average)",
+            MessageBuilder::new().push_safe(m.username(user)).build(),
+            ratio
+        ));
+    }
+    description.push_line("");
+    for (i, p) in stats.iter().take(MAX_PLAYERS_SHOWN).enumerate() {
+        description.push_line(format!(
+            "**{}.** **{}**: {} game{}, {} win{} • avg {} • {:.2}% • {:.2}x",
+            i + 1,
+            MessageBuilder::new()
+                .push_safe(m.username(p.user_id))
+                .build(),
+            p.games,
+            if p.games == 1 { "" } else { "s" },
+            p.wins,
+            if p.wins == 1 { "" } else { "s" },
+            super::embeds::grouped_number(p.average_score()),
+            p.average_accuracy(),
+            p.performance()
+        ));
+    }
+    if stats.len() > MAX_PLAYERS_SHOWN {
+        description.push_line(format!(
+            "*...and {} more players*",
+            stats.len() - MAX_PLAYERS_SHOWN
+        ));
+    }
+
+    CreateEmbed::new()
+        .title(MessageBuilder::new().push_safe(&m.name).build())
+        .url(m.link())
+        .color(theme.color)
+        .description(description.build())
+}
+
+/// A page of games of the `match` command, with the top 3 players of each.
+fn games_embed(
+    m: &Match,
+    start: usize,
+    games: &[MatchGame],
+    beatmaps: &HashMap<u64, Beatmap>,
+    theme: &EmbedTheme,
+) -> CreateEmbed {
+    let mut description = MessageBuilder::new();
+    for (i, game) in games.iter().enumerate() {
+        description.push(format!(
+            "**#{}** {}",
+            start + i + 1,
+            map_line(beatmaps.get(&game.beatmap_id), game.beatmap_id)
+        ));
+        if game.mods != crate::models::Mods::NOMOD {
+            description.push(format!(" {}", game.mods));
+        }
+        description.push_line("");
+        description.push_line(result_line(m, game));
+        let top = game
+            .ranked_scores()
+            .into_iter()
+            .take(3)
+            .map(|s| {
+                format!(
+                    "{}**{}** {} ({:.2}%)",
+                    match s.team {
+                        Team::Red => "🔴 ",
+                        Team::Blue => "🔵 ",
+                        Team::None => "",
+                    },
+                    MessageBuilder::new()
+                        .push_safe(m.username(s.user_id))
+                        .build(),
+                    super::embeds::grouped_number(s.score),
+                    s.accuracy
+                )
+            })
+            .collect::<Vec<_>>();
+        if !top.is_empty() {
+            description.push_line(format!("> {}", top.join(" | ")));
+        }
+    }
+
+    CreateEmbed::new()
+        .title(MessageBuilder::new().push_safe(&m.name).build())
+        .url(m.link())
+        .color(theme.color)
+        .description(description.build())
+}
+
+/// The summary embed of a match.
+pub(crate) fn match_embed(
+    m: &Match,
+    beatmaps: &HashMap<u64, Beatmap>,
+    theme: &EmbedTheme,
+) -> CreateEmbed {
+    let team_wins = team_wins(m);
+    let mut player_wins: HashMap<u64, u32> = HashMap::new();
+    for game in &m.games {
+        if let Some(Winner::Player(user, _)) = game_winner(game) {
+            *player_wins.entry(user).or_default() += 1;
+        }
+    }
+
+    let mut description = MessageBuilder::new();
+    description.push_line(format!("Started <t:{}:R>", m.start_time.timestamp()));
+    if !team_wins.is_empty() {
+        description.push_line(format!(
+            "🔴 **{}** - **{}** 🔵",
+            team_wins.get(&Team::Red).copied().unwrap_or(0),
+            team_wins.get(&Team::Blue).copied().unwrap_or(0)
+        ));
+    }
+    if let Some((user, ratio)) = mvp(m, &player_stats(m)) {
+        description.push_line(format!(
+            "⭐ MVP: **{}** ({:.2}x the lobby average)",
+            MessageBuilder::new().push_safe(m.username(user)).build(),
+            ratio
+        ));
+    }
+    description.push_line("");
+    let skipped = m.games.len() - shown_games(m).len();
+    if skipped > 0 {
+        description.push_line(format!("*...and {} earlier games*", skipped));
+    }
+    for (i, game) in shown_games(m).iter().enumerate() {
+        description.push(format!(
+            "**#{}** {}",
+            skipped + i + 1,
+            map_line(beatmaps.get(&game.beatmap_id), game.beatmap_id)
+        ));
+        if game.mods != crate::models::Mods::NOMOD {
+            description.push(format!(" {}", game.mods));
+        }
+        description.push_line("");
+        description.push_line(result_line(m, game));
+    }
+
+    if team_wins.is_empty() && !player_wins.is_empty() {
+        let mut standings = player_wins.into_iter().collect::<Vec<_>>();
+        standings.sort_by(|a, b| b.1.cmp(&a.1));
+        description.push_line("").push_line(
+            standings
+                .into_iter()
+                .take(5)
+                .map(|(user, wins)| {
+                    format!(
+                        "**{}**: {} win{}",
+                        MessageBuilder::new().push_safe(m.username(user)).build(),
+                        wins,
+                        if wins == 1 { "" } else { "s" }
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" | "),
+        );
+    }
+
+    CreateEmbed::new()
+        .title(MessageBuilder::new().push_safe(&m.name).build())
+        .url(m.link())
+        .color(theme.color)
+        .description(description.build())
+        .footer(CreateEmbedFooter::new(match m.end_time {
+            Some(_) => format!("{} games, finished", m.games.len()),
+            None => format!("{} games, in progress (updating live)", m.games.len()),
+        }))
+}
@@ -1,43 +1,115 @@
-use std::{str::FromStr, sync::Arc};
+use std::{collections::BTreeMap, str::FromStr, sync::Arc};
 
 use rand::seq::IteratorRandom;
 use serenity::{
-    builder::{CreateMessage, EditMessage},
-    collector,
+    all::ButtonStyle,
+    builder::{
+        CreateActionRow, CreateAttachment, CreateButton, CreateEmbed, CreateMessage, EditMessage,
+    },
     framework::standard::{
         macros::{command, group},
         Args, CommandResult,
     },
-    model::channel::Message,
+    http::CacheHttp,
+    model::{
+        channel::Message,
+        id::{ChannelId, GuildId},
+    },
     utils::MessageBuilder,
 };
 
-use db::{OsuLastBeatmap, OsuSavedUsers, OsuUser, OsuUserBests};
-use embeds::{beatmap_embed, score_embed, user_embed};
+pub use autocomplete::UsernameAutocomplete;
+use autoranks::AUTORANKS_COMMAND;
+pub use backup::OSUBACKUP_GROUP;
+use cache_stats::CACHE_STATS_COMMAND;
+use collection::COLLECTION_COMMAND;
+pub use context_menu::{ExpandBeatmapCommand, ProfileCommand};
+use daily_challenge::DAILY_COMMAND;
+use db::{OsuLastBeatmap, OsuSavedUsers, OsuStatsSnapshots, OsuUser, OsuUserBests};
+use diagnostics::OSUDIAGNOSTICS_GROUP;
+use embeds::{beatmap_embed, score_embed, user_embed, with_cover, with_strain_graph};
+use export::OSUEXPORT_GROUP;
+use forget::FORGET_SERVER_COMMAND;
+use history::{GRAPH_COMMAND, RECAP_COMMAND, UPDATE_COMMAND};
 use hook::SHORT_LINK_REGEX;
 pub use hook::{dot_osu_hook, hook};
+use hook_settings::HOOKS_COMMAND;
+use match_summary::MP_COMMAND;
+use news::OSUNEWS_GROUP;
+use plots::{CARD_COMMAND, HEATMAP_COMMAND, SCATTER_COMMAND};
+use replay_render::RENDER_COMMAND;
 use server_rank::{SERVER_RANK_COMMAND, SHOW_LEADERBOARD_COMMAND};
+use servers::SERVERS_COMMAND;
+use snipes::{SNIPELIST_COMMAND, SNIPES_COMMAND};
+use theme::THEME_COMMAND;
+use twitch::TWITCH_COMMAND;
+use wiki::WIKI_COMMAND;
 use youmubot_prelude::announcer::AnnouncerHandler;
-use youmubot_prelude::{stream::FuturesUnordered, *};
+use youmubot_prelude::interaction;
+use youmubot_prelude::*;
 
 use crate::{
+    discord::asset_cache::AssetCache,
     discord::beatmap_cache::BeatmapMetaCache,
     discord::display::ScoreListStyle,
-    discord::oppai_cache::{BeatmapCache, BeatmapInfo},
+    discord::map_length::MapLengthCache,
+    discord::mod_emoji::ModEmojis,
+    discord::oppai_cache::{Accuracy, BeatmapCache, BeatmapContent, BeatmapInfo},
+    discord::theme::EmbedTheme,
+    discord::user_cache::UserCache,
+    huismetbenen,
     models::{Beatmap, Mode, Mods, Score, User},
+    ordr, osucollector, osutrack,
+    ratelimit::Priority,
+    render::{self, Renderer, ScoreCard},
+    replay::HitStats,
     request::{BeatmapRequestKind, UserID},
-    Client as OsuHttpClient,
+    resilience::ResiliencePolicy,
+    twitch as twitch_api, Client as OsuHttpClient,
 };
 
 mod announcer;
+pub(crate) mod asset_cache;
+mod autocomplete;
+mod autoranks;
+mod backup;
 pub(crate) mod beatmap_cache;
 mod cache;
+mod cache_stats;
+mod collection;
+mod context_menu;
+mod daily_challenge;
 mod db;
+mod diagnostics;
 pub(crate) mod display;
 pub(crate) mod embeds;
+mod export;
+mod forget;
+mod history;
 mod hook;
+pub(crate) mod hook_settings;
+pub(crate) mod i18n;
+mod map_length;
+mod match_summary;
+pub(crate) mod mod_emoji;
+mod news;
 pub(crate) mod oppai_cache;
+mod plots;
+mod refresh;
+mod replay_render;
 mod server_rank;
+mod servers;
+mod sheets;
+mod snipes;
+#[cfg(test)]
+mod tests;
+pub(crate) mod theme;
+mod twitch;
+pub(crate) mod user_cache;
+mod validation;
+mod warmup;
+mod web;
+mod wiki;
 
 /// The osu! client.
 pub(crate) struct OsuClient;
@@ -54,10 +126,29 @@ pub struct OsuEnv {
     pub(crate) saved_users: OsuSavedUsers,
     pub(crate) last_beatmaps: OsuLastBeatmap,
     pub(crate) user_bests: OsuUserBests,
+    pub(crate) stats_snapshots: OsuStatsSnapshots,
     // clients
     pub(crate) client: Arc<OsuHttpClient>,
     pub(crate) oppai: BeatmapCache,
     pub(crate) beatmaps: BeatmapMetaCache,
+    pub(crate) users: UserCache,
+    pub(crate) map_lengths: MapLengthCache,
+    pub(crate) ordr: Arc<ordr::Client>,
+    pub(crate) osutrack: Arc<osutrack::Client>,
+    pub(crate) huismetbenen: Arc<huismetbenen::Client>,
+    pub(crate) collector: Arc<osucollector::Client>,
+    /// Only set up with the Twitch API credentials.
+    pub(crate) twitch: Option<Arc<twitch_api::Client>>,
+    /// The private servers, by name.
+    pub(crate) servers: Arc<BTreeMap<String, Arc<OsuHttpClient>>>,
+    // rendering
+    pub(crate) assets: Option<AssetCache>,
+    pub(crate) mod_emojis: ModEmojis,
+    pub(crate) renderer: Option<Arc<dyn Renderer>>,
+    // autocompletion
+    pub(crate) recent_usernames: autocomplete::RecentUsernames,
+    // running matches
+    pub(crate) live_matches: match_summary::LiveMatches,
 }
 
 impl std::fmt::Debug for OsuEnv {
@@ -70,6 +161,61 @@ impl TypeMapKey for OsuEnv {
     type Value = OsuEnv;
 }
 
+/// The default number of osu! API requests per minute.
+const DEFAULT_API_RATE_LIMIT: u32 = 60;
+/// The most the scheduled jobs are delayed by, so that they do not all start at once.
+const JOB_JITTER: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+/// Announcing the new top plays and leaderboard records of saved users.
+pub const ANNOUNCER_MODULE: Module = Module::new(
+    "osu_announcer",
+    "announce the new top plays and leaderboard records",
+);
+/// Announcing the country #1s taken by or from saved users, from huismetbenen.
+pub const SNIPE_ANNOUNCER_MODULE: Module = Module::new(
+    "osu_snipe_announcer",
+    "announce the country #1s taken by or from saved users",
+);
+/// Announcing the Twitch streams of the users who linked their channel.
+/// Only available with the Twitch API credentials.
+pub const TWITCH_ANNOUNCER_MODULE: Module = Module::new(
+    "osu_twitch_announcer",
+    "announce the Twitch streams of users who linked their channel",
+);
+/// The hooks on osu! links and `.osu` files. Registered by whoever adds the hooks.
+pub const HOOKS_MODULE: Module = Module::new(
+    "osu_hooks",
+    "show the beatmaps, scores and users of osu! links and .osu files",
+);
+/// Rendering score cards, plots and leaderboards as images.
+pub const RENDER_MODULE: Module = Module::new(
+    "osu_render",
+    "render score cards, plots and leaderboards as images",
+);
+/// Warming up the caches on start up.
+pub const CACHE_WARMUP_MODULE: Module =
+    Module::new("osu_cache_warmup", "warm up the osu! caches on start up");
+/// Keeping the cached beatmaps up to date.
+pub const CACHE_VALIDATION_MODULE: Module = Module::new(
+    "osu_cache_validation",
+    "keep the cached beatmaps up to date in the background",
+);
+/// Recording the daily challenge scores of saved users, for the `daily` ranks and streaks.
+pub const DAILY_CHALLENGE_MODULE: Module = Module::new(
+    "osu_daily_challenge",
+    "record the daily challenge scores of saved users every hour",
+);
+/// Posting the osu! news and lazer changelogs to the channels subscribed with `news subscribe`.
+pub const NEWS_MODULE: Module = Module::new(
+    "osu_news",
+    "post the osu! news and lazer changelogs to subscribed channels",
+);
+/// Posting the server ranks on the schedules set with `autoranks`.
+pub const LEADERBOARD_POSTS_MODULE: Module = Module::new(
+    "osu_leaderboard_posts",
+    "post the server ranks to channels on a schedule",
+);
+
 /// Sets up the osu! command handling section.
 ///
 /// This automatically enables:
@@ -79,44 +225,182 @@ impl TypeMapKey for OsuEnv {
 ///
 ///  This does NOT automatically enable:
 ///  - Commands on the "osu" prefix
-///  - Hooks. Hooks are completely opt-in.
-///  
+///  - Hooks. Hooks are completely opt-in, see [HOOKS_MODULE].
+///
+///  Modules switched off in the configuration are skipped.
+///
+///  Everything that may fail is done before any background task is started,
+///  so a failed setup can be retried.
 pub async fn setup(
     data: &mut TypeMap,
     prelude: youmubot_prelude::Env,
     announcers: &mut AnnouncerHandler,
-) -> Result<OsuEnv> {
-    // Databases
-    let saved_users = OsuSavedUsers::new(prelude.sql.clone());
-    let last_beatmaps = OsuLastBeatmap::new(prelude.sql.clone());
-    let user_bests = OsuUserBests::new(prelude.sql.clone());
+) -> Result<OsuEnv, SetupError> {
+    let settings = prelude.settings.clone();
+    let modules = prelude.modules.clone();
+    let supervisor = prelude.supervisor.clone();
+    let scheduler = prelude.scheduler.clone();
+
+    // Settings
+    let schedule = |s: &str| Schedule::from_str(s).map_err(SetupError::Invalid);
+    let prune_schedule = schedule(db::PRUNE_SCHEDULE)?;
+    let downsample_schedule = schedule(db::DOWNSAMPLE_SCHEDULE)?;
+    let validation_schedule = schedule(validation::VALIDATION_SCHEDULE)?;
+    let posts_schedule = schedule(autoranks::POSTS_SCHEDULE)?;
+    let daily_challenge_schedule = schedule(daily_challenge::RECORD_SCHEDULE)?;
+    let news_schedule = schedule(news::NEWS_SCHEDULE)?;
+    let user_bests = OsuUserBests::new(prelude.sql.clone(), &settings.osu);
+    let policy = ResiliencePolicy::from_config(&settings.osu);
+    let mod_emojis = ModEmojis::from_config(&settings.osu).map_err(|e| {
+        SetupError::Invalid(error!("invalid osu.mod_emojis or OSU_MOD_EMOJIS: {}", e))
+    })?;
+    let client_id = settings.osu.client_id.ok_or(SetupError::Missing(
+        "osu.client_id or OSU_API_CLIENT_ID as osu! api v2 client ID",
+    ))?;
+    let client_secret = settings
+        .osu
+        .client_secret
+        .clone()
+        .ok_or(SetupError::Missing(
+            "osu.client_secret or OSU_API_CLIENT_SECRET as osu! api v2 client secret",
+        ))?;
+    let web = match (settings.web.listen, settings.web.token.clone()) {
+        (Some(addr), Some(token)) if !token.is_empty() => Some((addr, token)),
+        (Some(_), _) => {
+            return Err(SetupError::Missing(
+                "web.token or WEB_TOKEN to protect the endpoints at web.listen",
+            ))
+        }
+        (None, _) => None,
+    };
+
+    // Files
+    let oppai_cache = BeatmapCache::new(prelude.http.clone(), prelude.sql.clone(), &settings.osu)
+        .map_err(|e| SetupError::Storage {
+        what: "the beatmap files (osu.beatmap_files)".to_owned(),
+        source: e,
+    })?;
+    let assets = AssetCache::from_config(prelude.http.clone(), &settings.osu).map_err(|e| {
+        SetupError::Storage {
+            what: "the asset cache (osu.asset_cache)".to_owned(),
+            source: e,
+        }
+    })?;
+    let renderer = if modules.register(RENDER_MODULE) {
+        render::from_config(prelude.http.clone(), &settings.osu).map_err(|e| {
+            SetupError::Storage {
+                what: "the renderer assets (osu.render_assets)".to_owned(),
+                source: e,
+            }
+        })?
+    } else {
+        None
+    };
 
     // API client
     let osu_client = Arc::new(
         OsuHttpClient::new(
-            std::env::var("OSU_API_CLIENT_ID")
-                .expect("Please set OSU_API_CLIENT_ID as osu! api v2 client ID.")
-                .parse()
-                .expect("client_id should be u64"),
-            std::env::var("OSU_API_CLIENT_SECRET")
-                .expect("Please set OSU_API_CLIENT_SECRET as osu! api v2 client secret."),
+            client_id,
+            client_secret,
+            settings.osu.rate_limit.unwrap_or(DEFAULT_API_RATE_LIMIT),
+            policy,
         )
         .await
-        .expect("osu! should be initialized"),
+        .map_err(|e| SetupError::Unavailable {
+            service: "the osu! API",
+            transient: e.is_transient(),
+            source: e.into(),
+        })?
+        .with_metrics(prelude.metrics.clone()),
     );
-    let oppai_cache = BeatmapCache::new(prelude.http.clone(), prelude.sql.clone());
-    let beatmap_cache = BeatmapMetaCache::new(osu_client.clone(), prelude.sql.clone());
+    let beatmap_cache =
+        BeatmapMetaCache::new(osu_client.clone(), prelude.sql.clone(), &settings.osu);
+
+    // Databases
+    let saved_users = OsuSavedUsers::new(prelude.sql.clone());
+    let last_beatmaps = OsuLastBeatmap::new(prelude.sql.clone());
+    let stats_snapshots = OsuStatsSnapshots::new(prelude.sql.clone());
+    prelude.config.register(theme::EMBED_COLOR);
+    prelude.config.register(theme::EMBED_IMAGES);
+    scheduler.register("osu-prune", prune_schedule, JOB_JITTER, {
+        let user_bests = user_bests.clone();
+        move || user_bests.clone().prune_job()
+    });
+    scheduler.register("osu-downsampling", downsample_schedule, JOB_JITTER, {
+        let stats_snapshots = stats_snapshots.clone();
+        move || stats_snapshots.clone().downsample_job()
+    });
+
+    // Background tasks of the API client and caches
+    prelude.errors.register(crate::error::describe);
+    prelude.health.register("osu_api", {
+        let osu_client = osu_client.clone();
+        move || {
+            let osu_client = osu_client.clone();
+            async move {
+                if osu_client.token_valid().await {
+                    Ok(())
+                } else {
+                    Err(Error::msg("the osu! API token expired"))
+                }
+            }
+        }
+    });
+    supervisor.spawn("osu-token-refresh", {
+        let osu_client = osu_client.clone();
+        move |_| osu_client.clone().run_token_refresh()
+    });
+    supervisor.spawn("osu-beatmap-eviction", {
+        let beatmap_cache = beatmap_cache.clone();
+        move |_| beatmap_cache.clone().run_eviction()
+    });
+    let user_cache = UserCache::new(osu_client.clone());
+    let servers =
+        servers::clients(&osu_client, &settings.osu.servers).map_err(SetupError::Invalid)?;
+    let ordr = Arc::new(ordr::Client::new(
+        prelude.http.clone(),
+        settings.osu.ordr_api_key.clone(),
+    ));
+    let osutrack = Arc::new(osutrack::Client::new(prelude.http.clone()));
+    let huismetbenen = Arc::new(huismetbenen::Client::new(prelude.http.clone()));
+    let collector = Arc::new(osucollector::Client::new(prelude.http.clone()));
+    let twitch = match (settings.twitch.client_id, settings.twitch.client_secret) {
+        (Some(id), Some(secret)) => Some(Arc::new(twitch_api::Client::new(
+            prelude.http.clone(),
+            id,
+            secret,
+        ))),
+        _ => None,
+    };
 
     // Announcer
-    announcers.add(
-        announcer::ANNOUNCER_KEY,
-        announcer::Announcer::new(osu_client.clone()),
-    );
+    if modules.register(ANNOUNCER_MODULE) {
+        announcers.add(
+            announcer::ANNOUNCER_KEY,
+            announcer::Announcer::new(Arc::new(osu_client.with_priority(Priority::Background))),
+        );
+    }
+    if modules.register(SNIPE_ANNOUNCER_MODULE) {
+        announcers.add(
+            snipes::SNIPE_ANNOUNCER_KEY,
+            snipes::SnipeAnnouncer::new(huismetbenen.clone()),
+        );
+    }
+    if let Some(twitch) = &twitch {
+        if modules.register(TWITCH_ANNOUNCER_MODULE) {
+            announcers.add(
+                twitch::TWITCH_ANNOUNCER_KEY,
+                twitch::TwitchAnnouncer::new(twitch.clone()),
+            );
+            prelude.config.register(twitch::TWITCH_ALL_GAMES);
+        }
+    }
 
     // Legacy data
     data.insert::<OsuLastBeatmap>(last_beatmaps.clone());
     data.insert::<OsuSavedUsers>(saved_users.clone());
     data.insert::<OsuUserBests>(user_bests.clone());
+    data.insert::<OsuStatsSnapshots>(stats_snapshots.clone());
     data.insert::<OsuClient>(osu_client.clone());
     data.insert::<BeatmapCache>(oppai_cache.clone());
     data.insert::<BeatmapMetaCache>(beatmap_cache.clone());
@@ -126,12 +410,82 @@ pub async fn setup(
         saved_users,
         last_beatmaps,
         user_bests,
+        stats_snapshots,
         client: osu_client,
         oppai: oppai_cache,
         beatmaps: beatmap_cache,
+        users: user_cache,
+        map_lengths: MapLengthCache::default(),
+        ordr,
+        osutrack,
+        huismetbenen,
+        collector,
+        twitch,
+        servers: Arc::new(servers),
+        assets,
+        mod_emojis,
+        renderer,
+        recent_usernames: Default::default(),
+        live_matches: Default::default(),
     };
 
     data.insert::<OsuEnv>(env.clone());
+    cache_stats::register_metrics(&env);
+
+    // Serve the server standings, if configured
+    if let Some((addr, token)) = web {
+        let env = env.clone();
+        supervisor.spawn("osu-web", move |shutdown| {
+            let env = env.clone();
+            let token = token.clone();
+            async move {
+                web::serve(addr, token, env, shutdown).await.pls_ok();
+            }
+        });
+    }
+
+    // Warm up the caches
+    if modules.register(CACHE_WARMUP_MODULE) {
+        spawn_future(warmup::warm_up(env.clone()));
+    }
+    // Keep the cached beatmaps up to date
+    if modules.register(CACHE_VALIDATION_MODULE) {
+        scheduler.register("osu-validation", validation_schedule, JOB_JITTER, {
+            let env = env.clone();
+            move || validation::validation_job(env.clone())
+        });
+    }
+    // Record the daily challenge scores
+    if modules.register(DAILY_CHALLENGE_MODULE) {
+        scheduler.register(
+            "osu-daily-challenge",
+            daily_challenge_schedule,
+            JOB_JITTER,
+            {
+                let env = env.clone();
+                move || daily_challenge::record_job(env.clone())
+            },
+        );
+    }
+    // Post the osu! news and lazer changelogs
+    if modules.register(NEWS_MODULE) {
+        scheduler.register("osu-news", news_schedule, JOB_JITTER, {
+            let env = env.clone();
+            move || news::news_job(env.clone())
+        });
+    }
+    // Post the scheduled server ranks, on time
+    if modules.register(LEADERBOARD_POSTS_MODULE) {
+        scheduler.register(
+            "osu-leaderboard-posts",
+            posts_schedule,
+            std::time::Duration::ZERO,
+            {
+                let env = env.clone();
+                move || autoranks::posts_job(env.clone())
+            },
+        );
+    }
 
     Ok(env)
 }
@@ -148,12 +502,35 @@ pub async fn setup(
     forcesave,
     recent,
     last,
+    channel_maps,
     check,
     top,
+    graph,
+    recap,
+    update,
+    daily,
+    snipes,
+    snipelist,
+    twitch,
+    wiki,
+    collection,
+    mp,
+    servers,
+    scatter,
+    heatmap,
+    card,
+    render,
     server_rank,
     show_leaderboard,
-    clean_cache
+    autoranks,
+    theme,
+    hooks,
+    clean_cache,
+    cache_stats,
+    prune_bests,
+    forget_server
 )]
+#[sub_groups(OsuDiagnostics, OsuNews, OsuExport)]
 #[default_command(std)]
 struct Osu;
 
@@ -218,12 +595,13 @@ impl AsRef<Beatmap> for BeatmapWithMode {
 pub async fn save(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
     let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
     let osu_client = &env.client;
+    let locale = env.locale_for(msg).await;
 
     let user = args.single::<String>()?;
     let u = match osu_client.user(UserID::from_string(user), |f| f).await? {
         Some(u) => u,
         None => {
-            msg.reply(&ctx, "user not found...").await?;
+            msg.reply(&ctx, i18n::tr(locale, "user-not-found")).await?;
             return Ok(());
         }
     };
@@ -241,33 +619,56 @@ pub async fn save(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult
     let (score, mode) = match find_score(osu_client, &u).await? {
         Some(v) => v,
         None => {
-            msg.reply(
-                &ctx,
-                "No plays found in this account! Play something first...!",
-            )
-            .await?;
+            msg.reply(&ctx, i18n::tr(locale, "save-no-plays")).await?;
             return Ok(());
         }
     };
 
-    async fn check(client: &OsuHttpClient, u: &User, map_id: u64) -> Result<bool> {
-        Ok(client
-            .user_recent(UserID::ID(u.id), |f| f.mode(Mode::Std).limit(1))
-            .await?
-            .into_iter()
-            .take(1)
-            .any(|s| s.beatmap_id == map_id))
+    /// Checks the user's most recent play whenever they press the button.
+    struct SaveCheck<'a> {
+        client: &'a OsuHttpClient,
+        user: &'a User,
+        map_id: u64,
+        locale: Locale,
+        completed: bool,
+    }
+
+    #[async_trait]
+    impl<'a> Interactive for SaveCheck<'a> {
+        async fn handle(
+            &mut self,
+            ctx: &Context,
+            incoming: &interaction::Incoming,
+            _message: &mut Message,
+        ) -> Result<bool> {
+            let played = self
+                .client
+                .user_recent(UserID::ID(self.user.id), |f| f.mode(Mode::Std).limit(1))
+                .await?
+                .into_iter()
+                .take(1)
+                .any(|s| s.beatmap_id == self.map_id);
+            if played {
+                self.completed = true;
+                incoming.acknowledge(ctx).await?;
+                return Ok(false);
+            }
+            incoming
+                .ephemeral(ctx, i18n::tr(self.locale, "save-not-played"))
+                .await?;
+            Ok(true)
+        }
     }
 
     let reply = msg.reply(
         &ctx,
-        format!(
-            "To set your osu username, please make your most recent play \
-            be the following map: `/b/{}` in **{}** mode! \
-        It does **not** have to be a pass, and **NF** can be used! \
-        React to this message with 👌 within 5 minutes when you're done!",
-            score.beatmap_id,
-            mode.as_str_new_site()
+        i18n::trf(
+            locale,
+            "save-instructions",
+            &[
+                ("beatmap", &score.beatmap_id),
+                ("mode", &mode.as_str_new_site()),
+            ],
         ),
     );
     let beatmap = osu_client
@@ -280,37 +681,37 @@ pub async fn save(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult
         .unwrap();
     let info = env
         .oppai
-        .get_beatmap(beatmap.beatmap_id)
-        .await?
-        .get_possible_pp_with(mode, Mods::NOMOD)?;
+        .get_possible_pp(&beatmap, mode, Mods::NOMOD)
+        .await?;
+    let theme = env.embed_theme_for(msg).await;
     let mut reply = reply.await?;
     reply
         .edit(
             &ctx,
-            EditMessage::new().embed(beatmap_embed(&beatmap, mode, Mods::NOMOD, info)),
+            EditMessage::new()
+                .embed(beatmap_embed(&beatmap, mode, Mods::NOMOD, info, &theme))
+                .components(vec![CreateActionRow::Buttons(vec![CreateButton::new(
+                    "osu-save-done",
+                )
+                .label(i18n::tr(locale, "save-done"))
+                .style(ButtonStyle::Success)])]),
         )
         .await?;
-    let reaction = reply.react(&ctx, '👌').await?;
-    let completed = loop {
-        let emoji = reaction.emoji.clone();
-        let user_reaction = collector::ReactionCollector::new(ctx)
-            .message_id(reply.id)
-            .author_id(msg.author.id)
-            .filter(move |r| r.emoji == emoji)
-            .timeout(std::time::Duration::from_secs(300))
-            .next()
-            .await;
-        if let Some(ur) = user_reaction {
-            if check(osu_client, &u, score.beatmap_id).await? {
-                break true;
-            }
-            ur.delete(&ctx).await?;
-        } else {
-            break false;
-        }
-    };
-    if !completed {
-        reaction.delete(&ctx).await?;
+    let check = interaction::run(
+        SaveCheck {
+            client: osu_client,
+            user: &u,
+            map_id: score.beatmap_id,
+            locale,
+            completed: false,
+        },
+        ctx,
+        reply,
+        interaction::Allowed::user(msg.author.id),
+        std::time::Duration::from_secs(300),
+    )
+    .await?;
+    if !check.completed {
         return Ok(());
     }
 
@@ -318,10 +719,11 @@ pub async fn save(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult
     add_user(msg.author.id, u, &env).await?;
     msg.reply(
         &ctx,
-        MessageBuilder::new()
-            .push("user has been set to ")
-            .push_mono_safe(username)
-            .build(),
+        i18n::trf(
+            locale,
+            "save-success",
+            &[("username", &username.replace('`', ""))],
+        ),
     )
     .await?;
     Ok(())
@@ -380,15 +782,22 @@ async fn add_user(target: serenity::model::id::UserId, user: User, env: &OsuEnv)
 struct ModeArg(Mode);
 
 impl FromStr for ModeArg {
-    type Err = String;
+    type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(ModeArg(match &s.to_lowercase()[..] {
-            "osu" | "std" => Mode::Std,
-            "taiko" | "osu!taiko" => Mode::Taiko,
-            "ctb" | "fruits" | "catch" | "osu!ctb" | "osu!catch" => Mode::Catch,
-            "osu!mania" | "mania" => Mode::Mania,
-            _ => return Err(format!("Unknown mode {}", s)),
-        }))
+        args::keyword(
+            "mode",
+            s,
+            &[
+                (&["std", "osu"], Mode::Std),
+                (&["taiko", "osu!taiko"], Mode::Taiko),
+                (
+                    &["catch", "ctb", "fruits", "osu!ctb", "osu!catch"],
+                    Mode::Catch,
+                ),
+                (&["mania", "osu!mania"], Mode::Mania),
+            ],
+        )
+        .map(ModeArg)
     }
 }
 
@@ -403,94 +812,189 @@ async fn to_user_id_query(
         None => msg.author.id,
     };
 
-    env.saved_users
-        .by_user_id(id)
-        .await?
-        .map(|u| UserID::ID(u.id))
-        .ok_or_else(|| Error::msg("No saved account found"))
+    match env.saved_users.by_user_id(id).await? {
+        Some(u) => Ok(UserID::ID(u.id)),
+        None => Err(Error::msg(i18n::tr(
+            env.locale_for(msg).await,
+            "no-saved-account",
+        ))),
+    }
+}
+
+/// The client of the server picked with `--server`, or of osu! if none is, and the user looked up on it.
+async fn user_on_server(
+    env: &OsuEnv,
+    msg: &Message,
+    server: Option<String>,
+    user: Option<UsernameArg>,
+    mode: Mode,
+) -> Result<(Arc<OsuHttpClient>, Option<User>)> {
+    match server {
+        Some(server) => {
+            let client = env.server(&server)?;
+            let query = servers::server_user_query(user, env, msg).await?;
+            let user = client.user(query, |f| f.mode(mode)).await?;
+            Ok((client, user))
+        }
+        None => {
+            let query = to_user_id_query(user, env, msg).await?;
+            let user = env.users.get(query, Some(mode)).await?;
+            Ok((env.client.clone(), user))
+        }
+    }
 }
 
 enum Nth {
     All,
     Nth(u8),
+    /// The plays between two positions, e.g. `#5..#20`.
+    Range(args::Range<Position>),
 }
 
 impl FromStr for Nth {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s == "--all" || s == "-a" || s == "##" {
-            Ok(Nth::All)
-        } else if !s.starts_with('#') {
-            Err(Error::msg("Not an order"))
+            return Ok(Nth::All);
+        }
+        let nth = if s.contains("..") || s.contains('-') {
+            Nth::Range(s.parse()?)
         } else {
-            let v = s.split_at("#".len()).1.parse()?;
-            Ok(Nth::Nth(v))
+            Nth::Nth(s.parse::<Position>()?.0.try_into()?)
+        };
+        Ok(nth)
+    }
+}
+
+impl Nth {
+    /// Keep the plays of the range, if any.
+    fn select<T>(&self, plays: Vec<T>) -> Vec<T> {
+        match self {
+            Nth::Range(r) => plays
+                .into_iter()
+                .skip(r.start.index())
+                .take(r.end.0 - r.start.index())
+                .collect(),
+            _ => plays,
         }
     }
 }
 
+/// The options taking a value, shared by the osu! commands, e.g. `--mode taiko --style grid --mods HDDT`.
+/// They can also be given as plain arguments in any order, e.g. `taiko --grid +HDDT`.
+const OPTIONS: &[&str] = &["mode", "style", "mods", "server"];
+
 #[command]
 #[aliases("rs", "rc", "r")]
 #[description = "Gets an user's recent play"]
-#[usage = "#[the nth recent play = --all] / [style (table or grid) = --table] / [mode (std, taiko, mania, catch) = std] / [username / user id = your saved id]"]
-#[example = "#1 / taiko / natsukagami"]
+#[usage = "#[the nth recent play, or a range like #5..#20 = --all] / [--style (table or grid) = table] / [--mode (std, taiko, mania, catch) = std] / [username / user id = your saved id] / [--server (a private server, see `servers`) = osu!] / [--lazer to show lazer scores], in any order"]
+#[example = "#1 --mode taiko natsukagami"]
 #[delimiters("/", " ")]
-#[max_args(4)]
-pub async fn recent(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+#[max_args(10)]
+pub async fn recent(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let args = RecentArgs::parse(args)?;
+    let (style, mode, lazer) = (args.style, args.mode, args.lazer);
+    if let Some((plays, osu_client)) = reply_recent(ctx, &env, msg, args).await? {
+        style
+            .display_scores(plays, mode, lazer, osu_client, ctx, msg)
+            .await?;
+    }
+    Ok(())
+}
 
-    let nth = args.single::<Nth>().unwrap_or(Nth::All);
-    let style = args.single::<ScoreListStyle>().unwrap_or_default();
-    let mode = args.single::<ModeArg>().unwrap_or(ModeArg(Mode::Std)).0;
-    let user = to_user_id_query(
-        args.quoted().trimmed().single::<UsernameArg>().ok(),
-        &env,
-        msg,
-    )
-    .await?;
+/// The arguments of `recent`.
+struct RecentArgs {
+    lazer: bool,
+    nth: Nth,
+    style: ScoreListStyle,
+    mode: Mode,
+    user: Option<UsernameArg>,
+    server: Option<String>,
+}
 
-    let osu_client = &env.client;
+impl RecentArgs {
+    fn parse(args: Args) -> Result<Self> {
+        let mut opts = Options::parse(args, OPTIONS)?;
+        let lazer = opts.flag("lazer");
+        let nth = opts.take::<Nth>().unwrap_or(Nth::All);
+        let style = opts.get::<ScoreListStyle>("style")?.unwrap_or_default();
+        let mode = opts.get::<ModeArg>("mode")?.map_or(Mode::Std, |m| m.0);
+        let user = opts.take::<UsernameArg>();
+        let server = opts.value::<String>("server")?;
+        opts.finish()?;
+        Ok(Self {
+            lazer,
+            nth,
+            style,
+            mode,
+            user,
+            server,
+        })
+    }
+}
 
-    let user = osu_client
-        .user(user, |f| f.mode(mode))
-        .await?
-        .ok_or_else(|| Error::msg("User not found"))?;
-    match nth {
+/// Reply to the message with the requested recent play.
+/// Lists of plays are paginated, which needs a [Context], so they are returned to be displayed instead.
+async fn reply_recent(
+    http: impl CacheHttp,
+    env: &OsuEnv,
+    msg: &Message,
+    args: RecentArgs,
+) -> Result<Option<(Vec<Score>, Arc<OsuHttpClient>)>> {
+    let (lazer, mode) = (args.lazer, args.mode);
+    let theme = env.embed_theme_for(msg).await;
+    let (osu_client, user) = user_on_server(env, msg, args.server, args.user, mode).await?;
+    let user = user.ok_or_else(|| Error::msg(theme.tr("user-not-found")))?;
+    match args.nth {
         Nth::Nth(nth) => {
             let recent_play = osu_client
                 .user_recent(UserID::ID(user.id), |f| f.mode(mode).limit(nth))
                 .await?
                 .into_iter()
                 .last()
-                .ok_or_else(|| Error::msg("No such play"))?;
+                .ok_or_else(|| Error::msg(theme.tr("no-such-play")))?;
             let beatmap = env
                 .beatmaps
                 .get_beatmap(recent_play.beatmap_id, mode)
                 .await?;
             let content = env.oppai.get_beatmap(beatmap.beatmap_id).await?;
             let beatmap_mode = BeatmapWithMode(beatmap, mode);
+            let hit_stats = score_hit_stats(env, &recent_play, mode, &content).await;
+            let global_rank = score_global_rank(env, &recent_play, mode).await;
+            let card =
+                score_card_attachment(env, &theme, &recent_play, &beatmap_mode, &content, &user)
+                    .await;
 
             msg.channel_id
                 .send_message(
-                    &ctx,
+                    http,
                     CreateMessage::new()
-                        .content("Here is the play that you requested".to_string())
-                        .embed(score_embed(&recent_play, &beatmap_mode, &content, &user).build())
+                        .content(theme.tr("requested-play"))
+                        .embed(
+                            score_embed(&recent_play, &beatmap_mode, &content, &user, &theme)
+                                .lazer(lazer)
+                                .hit_stats(hit_stats)
+                                .global_rank(global_rank)
+                                .score_card(card.is_some())
+                                .build(),
+                        )
+                        .add_files(card)
                         .reference_message(msg),
                 )
                 .await?;
 
             // Save the beatmap...
-            cache::save_beatmap(&env, msg.channel_id, &beatmap_mode).await?;
+            cache::publish_beatmap(env, msg.guild_id, msg.channel_id, &beatmap_mode).await;
+            Ok(None)
         }
-        Nth::All => {
+        Nth::All | Nth::Range(_) => {
             let plays = osu_client
                 .user_recent(UserID::ID(user.id), |f| f.mode(mode).limit(50))
                 .await?;
-            style.display_scores(plays, mode, ctx, msg).await?;
+            Ok(Some((args.nth.select(plays), osu_client)))
         }
     }
-    Ok(())
 }
 
 /// Get beatmapset.
@@ -507,11 +1011,34 @@ impl FromStr for OptBeatmapSet {
     }
 }
 
+/// Points to an earlier beatmap in the channel's history, as `#N`, with `#1` being the last one.
+#[derive(Debug, Clone, Copy)]
+struct BeatmapIndex(usize);
+
+impl FromStr for BeatmapIndex {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let n = s.parse::<Position>()?;
+        if n.0 as i64 > db::CHANNEL_HISTORY_SIZE {
+            bail!(
+                "beatmap index should be between #1 and #{}",
+                db::CHANNEL_HISTORY_SIZE
+            );
+        }
+        Ok(Self(n.index()))
+    }
+}
+
 /// Load the mentioned beatmap from the given message.
+///
+/// If the message does not reply to a beatmap, the `index`-th last beatmap of the channel is used,
+/// with 0 being the last one.
 pub(crate) async fn load_beatmap(
     env: &OsuEnv,
     msg: &Message,
-) -> Option<(BeatmapWithMode, Option<Mods>)> {
+    index: usize,
+) -> Option<(BeatmapWithMode, LinkAnnotations)> {
     if let Some(replied) = &msg.referenced_message {
         // Try to look for a mention of the replied message.
         let beatmap_id = SHORT_LINK_REGEX.captures(&replied.content).or_else(|| {
@@ -531,9 +1058,7 @@ pub(crate) async fn load_beatmap(
             let mode = caps
                 .name("mode")
                 .and_then(|m| Mode::parse_from_new_site(m.as_str()));
-            let mods = caps
-                .name("mods")
-                .and_then(|m| m.as_str().parse::<Mods>().ok());
+            let annotations = LinkAnnotations::from_captures(&caps);
             let osu_client = &env.client;
             let bms = osu_client
                 .beatmaps(BeatmapRequestKind::Beatmap(id), |f| f.maybe_mode(mode))
@@ -544,37 +1069,65 @@ pub(crate) async fn load_beatmap(
                 let bm_mode = beatmap.mode;
                 let bm = BeatmapWithMode(beatmap, mode.unwrap_or(bm_mode));
                 // Store the beatmap in history
-                cache::save_beatmap(&env, msg.channel_id, &bm)
-                    .await
-                    .pls_ok();
+                cache::publish_beatmap(&env, msg.guild_id, msg.channel_id, &bm).await;
 
-                return Some((bm, mods));
+                return Some((bm, annotations));
             }
         }
     }
 
-    let b = cache::get_beatmap(&env, msg.channel_id)
+    let b = cache::get_beatmap(&env, msg.channel_id, index)
         .await
         .ok()
         .flatten();
-    b.map(|b| (b, None))
+    b.map(|b| (b, LinkAnnotations::default()))
+}
+
+/// The mods, accuracy and combo annotated on a beatmap short link, e.g. `/b/123456 +HDDT 98.5% 1200x`.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct LinkAnnotations {
+    pub mods: Option<Mods>,
+    pub accuracy: Option<f64>,
+    pub combo: Option<u64>,
+}
+
+impl LinkAnnotations {
+    fn from_captures(caps: &regex::Captures) -> Self {
+        Self {
+            mods: caps.name("mods").and_then(|m| m.as_str().parse().ok()),
+            accuracy: caps
+                .name("acc")
+                .and_then(|m| m.as_str().parse::<f64>().ok())
+                .filter(|acc| *acc <= 100.0),
+            combo: caps.name("combo").and_then(|m| m.as_str().parse().ok()),
+        }
+    }
 }
 
 #[command]
 #[aliases("map")]
 #[description = "Show information from the last queried beatmap."]
-#[usage = "[--set/-s/--beatmapset] / [mods = no mod]"]
+#[usage = "[#N = #1, the N-th last beatmap] / [--set/-s/--beatmapset] / [--mods = the replied link's mods, or no mod] / [accuracy, e.g. 98.5% = the replied link's accuracy], in any order"]
 #[delimiters(" ")]
-#[max_args(2)]
-pub async fn last(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+#[max_args(5)]
+pub async fn last(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
 
-    let b = load_beatmap(&env, msg).await;
-    let beatmapset = args.find::<OptBeatmapSet>().is_ok();
+    let mut opts = Options::parse(args, OPTIONS)?;
+    let index = opts.take::<BeatmapIndex>().map(|v| v.0).unwrap_or(0);
+    let beatmapset = opts.take::<OptBeatmapSet>().is_some();
+    let accuracy = opts.take::<Percentage>();
+    let mods = opts.get::<Mods>("mods")?;
+    opts.finish()?;
+    let b = load_beatmap(&env, msg, index).await;
+    let theme = env.embed_theme_for(msg).await;
 
     match b {
-        Some((BeatmapWithMode(b, m), mods_def)) => {
-            let mods = args.find::<Mods>().ok().or(mods_def).unwrap_or(Mods::NOMOD);
+        Some((BeatmapWithMode(b, m), mut annotations)) => {
+            if let Some(Percentage(acc)) = accuracy {
+                annotations.accuracy = Some(acc);
+            }
+            let mods = mods.or(annotations.mods).unwrap_or(Mods::NOMOD);
             if beatmapset {
                 let beatmapset = env.beatmaps.get_beatmapset(b.beatmapset_id).await?;
                 display::display_beatmapset(
@@ -583,60 +1136,139 @@ pub async fn last(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult
                     None,
                     Some(mods),
                     msg,
-                    "Here is the beatmapset you requested!",
+                    theme.tr("requested-beatmapset"),
                 )
                 .await?;
                 return Ok(());
             }
-            let info = env
-                .oppai
-                .get_beatmap(b.beatmap_id)
-                .await?
-                .get_possible_pp_with(m, mods)?;
+            let content = env.oppai.get_beatmap(b.beatmap_id).await?;
+            let info = content.get_possible_pp_with(m, mods)?;
+            // Annotated links also show the pp of the annotated play, keeping the annotations for replies.
+            let text = if annotations.accuracy.is_some() || annotations.combo.is_some() {
+                let accuracy = annotations.accuracy.unwrap_or(100.0);
+                let combo = annotations.combo.or(b.difficulty.max_combo).unwrap_or(0);
+                let pp = content.get_pp_from(
+                    m,
+                    annotations.combo.map(|c| c as usize),
+                    Accuracy::ByValue(accuracy, 0),
+                    mods,
+                )?;
+                theme.trf(
+                    "requested-beatmap-pp",
+                    &[
+                        (
+                            "play",
+                            &b.annotated_link(Some(m), Some(mods), accuracy, combo),
+                        ),
+                        ("pp", &format!("{:.2}", pp)),
+                    ],
+                )
+            } else {
+                theme.tr("requested-beatmap").to_owned()
+            };
+            let graph =
+                strain_graph_attachment(&env, &theme, b.beatmap_id, &content, m, mods).await;
+            let cover = match graph {
+                Some(_) => None,
+                None => cover_attachment(&env, &theme, b.beatmapset_id).await,
+            };
             msg.channel_id
                 .send_message(
                     &ctx,
                     CreateMessage::new()
-                        .content("Here is the beatmap you requested!")
-                        .embed(beatmap_embed(&b, m, mods, info))
+                        .content(text)
+                        .embed(with_cover(
+                            with_strain_graph(
+                                beatmap_embed(&b, m, mods, info, &theme),
+                                graph.is_some(),
+                            ),
+                            cover.is_some(),
+                        ))
+                        .add_files(graph)
+                        .add_files(cover)
                         .reference_message(msg),
                 )
                 .await?;
         }
         None => {
-            msg.reply(&ctx, "No beatmap was queried on this channel.")
-                .await?;
+            msg.reply(&ctx, theme.tr("no-beatmap-queried")).await?;
         }
     }
 
     Ok(())
 }
 
+#[command("maps")]
+#[aliases("lasts")]
+#[description = "List the beatmaps last queried on this channel, to be picked with `last #N` or `check #N`."]
+#[max_args(0)]
+pub async fn channel_maps(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let history = env.last_beatmaps.history(msg.channel_id).await?;
+    let locale = env.locale_for(msg).await;
+    if history.is_empty() {
+        msg.reply(&ctx, i18n::tr(locale, "no-beatmap-queried"))
+            .await?;
+        return Ok(());
+    }
+    let mut content = MessageBuilder::new();
+    for (i, (b, m, saved_at)) in history.iter().enumerate() {
+        content
+            .push_bold(format!("#{}", i + 1))
+            .push(" ")
+            .push_safe(format!(
+                "{} - {} [{}]",
+                b.artist, b.title, b.difficulty_name
+            ))
+            .push(format!(" ({}) ", m))
+            .push_mono(b.short_link(Some(*m), None))
+            .push_line(format!(" <t:{}:R>", saved_at.timestamp()));
+    }
+    msg.channel_id
+        .send_message(
+            &ctx,
+            CreateMessage::new()
+                .embed(
+                    CreateEmbed::new()
+                        .title(i18n::tr(locale, "beatmaps-queried"))
+                        .description(content.build()),
+                )
+                .reference_message(msg),
+        )
+        .await?;
+    Ok(())
+}
+
 #[command]
 #[aliases("c", "chk")]
-#[usage = "[style (table or grid) = --table] / [username or tag = yourself] / [mods to filter]"]
+#[usage = "[#N = #1, the N-th last beatmap] / [--style (table or grid) = grid] / [username or tag = yourself] / [--mods to filter = the replied link's mods], in any order"]
 #[description = "Check your own or someone else's best record on the last beatmap. Also stores the result if possible."]
-#[max_args(3)]
-pub async fn check(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+#[max_args(6)]
+pub async fn check(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
-    let bm = load_beatmap(&env, msg).await;
+    let mut opts = Options::parse(args, OPTIONS)?;
+    let index = opts.take::<BeatmapIndex>().map(|v| v.0).unwrap_or(0);
+    let style = opts
+        .get::<ScoreListStyle>("style")?
+        .unwrap_or(ScoreListStyle::Grid);
+    let mods = opts.get::<Mods>("mods")?;
+    let username_arg = opts.take::<UsernameArg>();
+    opts.finish()?;
+    let bm = load_beatmap(&env, msg, index).await;
+    let locale = env.locale_for(msg).await;
 
-    let bm = match bm {
-        Some((bm, _)) => bm,
+    let (bm, annotations) = match bm {
+        Some(v) => v,
         None => {
-            msg.reply(&ctx, "No beatmap queried on this channel.")
+            msg.reply(&ctx, i18n::tr(locale, "no-beatmap-queried"))
                 .await?;
             return Ok(());
         }
     };
 
-    let mods = args.find::<Mods>().ok().unwrap_or_default();
+    let mods = mods.or(annotations.mods).unwrap_or_default();
     let b = &bm.0;
     let m = bm.1;
-    let style = args
-        .single::<ScoreListStyle>()
-        .unwrap_or(ScoreListStyle::Grid);
-    let username_arg = args.single::<UsernameArg>().ok();
     let user_id = match username_arg.as_ref() {
         Some(UsernameArg::Tagged(v)) => Some(*v),
         None => Some(msg.author.id),
@@ -644,12 +1276,12 @@ pub async fn check(ctx: &Context, msg: &Message, mut args: Args) -> CommandResul
     };
     let user = to_user_id_query(username_arg, &env, msg).await?;
 
-    let osu_client = env.client;
-
-    let user = osu_client
-        .user(user, |f| f)
+    let user = env
+        .users
+        .get(user, None)
         .await?
-        .ok_or_else(|| Error::msg("User not found"))?;
+        .ok_or_else(|| Error::msg(i18n::tr(locale, "user-not-found")))?;
+    let osu_client = &env.client;
     let mut scores = osu_client
         .scores(b.beatmap_id, |f| f.user(UserID::ID(user.id)).mode(m))
         .await?
@@ -663,7 +1295,7 @@ pub async fn check(ctx: &Context, msg: &Message, mut args: Args) -> CommandResul
     });
 
     if scores.is_empty() {
-        msg.reply(&ctx, "No scores found").await?;
+        msg.reply(&ctx, i18n::tr(locale, "no-scores")).await?;
         return Ok(());
     }
 
@@ -675,7 +1307,9 @@ pub async fn check(ctx: &Context, msg: &Message, mut args: Args) -> CommandResul
             .pls_ok();
     }
 
-    style.display_scores(scores, m, ctx, msg).await?;
+    style
+        .display_scores(scores, m, false, env.client.clone(), ctx, msg)
+        .await?;
 
     Ok(())
 }
@@ -683,24 +1317,23 @@ pub async fn check(ctx: &Context, msg: &Message, mut args: Args) -> CommandResul
 #[command]
 #[aliases("t")]
 #[description = "Get the n-th top record of an user."]
-#[usage = "#[n-th = --all] / [style (table or grid) = --table] / [mode (std, taiko, catch, mania)] = std / [username or user_id = your saved user id]"]
-#[example = "#2 / taiko / natsukagami"]
-#[max_args(4)]
-pub async fn top(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+#[usage = "#[n-th, or a range like #5..#20 = --all] / [--style (table or grid) = table] / [--mode (std, taiko, catch, mania) = std] / [username or user_id = your saved user id] / [--server (a private server, see `servers`) = osu!] / [--lazer to show lazer scores], in any order"]
+#[example = "#2 --mode taiko --style grid natsukagami"]
+#[max_args(10)]
+pub async fn top(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
-    let nth = args.single::<Nth>().unwrap_or(Nth::All);
-    let style = args.single::<ScoreListStyle>().unwrap_or_default();
-    let mode = args
-        .single::<ModeArg>()
-        .map(|ModeArg(t)| t)
-        .unwrap_or(Mode::Std);
-
-    let user = to_user_id_query(args.single::<UsernameArg>().ok(), &env, msg).await?;
-    let osu_client = &env.client;
-    let user = osu_client
-        .user(user, |f| f.mode(mode))
-        .await?
-        .ok_or_else(|| Error::msg("User not found"))?;
+    let mut opts = Options::parse(args, OPTIONS)?;
+    let lazer = opts.flag("lazer");
+    let nth = opts.take::<Nth>().unwrap_or(Nth::All);
+    let style = opts.get::<ScoreListStyle>("style")?.unwrap_or_default();
+    let mode = opts.get::<ModeArg>("mode")?.map_or(Mode::Std, |m| m.0);
+    let user = opts.take::<UsernameArg>();
+    let server = opts.value::<String>("server")?;
+    opts.finish()?;
+
+    let theme = env.embed_theme_for(msg).await;
+    let (osu_client, user) = user_on_server(&env, msg, server, user, mode).await?;
+    let user = user.ok_or_else(|| Error::msg(theme.tr("user-not-found")))?;
 
     match nth {
         Nth::Nth(nth) => {
@@ -713,34 +1346,38 @@ pub async fn top(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult
             let top_play = top_play
                 .into_iter()
                 .last()
-                .ok_or_else(|| Error::msg("No such play"))?;
+                .ok_or_else(|| Error::msg(theme.tr("no-such-play")))?;
             let beatmap = env.beatmaps.get_beatmap(top_play.beatmap_id, mode).await?;
             let content = env.oppai.get_beatmap(beatmap.beatmap_id).await?;
             let beatmap = BeatmapWithMode(beatmap, mode);
+            let hit_stats = score_hit_stats(&env, &top_play, mode, &content).await;
+            let global_rank = score_global_rank(&env, &top_play, mode).await;
 
             msg.channel_id
                 .send_message(&ctx, {
                     CreateMessage::new()
-                        .content(format!(
-                            "{}: here is the play that you requested",
-                            msg.author
-                        ))
+                        .content(theme.trf("requested-play-of", &[("user", &msg.author)]))
                         .embed(
-                            score_embed(&top_play, &beatmap, &content, &user)
+                            score_embed(&top_play, &beatmap, &content, &user, &theme)
+                                .lazer(lazer)
                                 .top_record(rank)
+                                .hit_stats(hit_stats)
+                                .global_rank(global_rank)
                                 .build(),
                         )
                 })
                 .await?;
 
             // Save the beatmap...
-            cache::save_beatmap(&env, msg.channel_id, &beatmap).await?;
+            cache::publish_beatmap(&env, msg.guild_id, msg.channel_id, &beatmap).await;
         }
-        Nth::All => {
+        Nth::All | Nth::Range(_) => {
             let plays = osu_client
                 .user_best(UserID::ID(user.id), |f| f.mode(mode).limit(100))
                 .await?;
-            style.display_scores(plays, mode, ctx, msg).await?;
+            style
+                .display_scores(nth.select(plays), mode, lazer, osu_client, ctx, msg)
+                .await?;
         }
     }
     Ok(())
@@ -753,15 +1390,37 @@ pub async fn top(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult
 #[max_args(1)]
 pub async fn clean_cache(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let oppai = args.remains() == Some("--oppai");
+    let prompt = if oppai {
+        "This clears the beatmap cache and the oppai cache. Proceed?"
+    } else {
+        "This clears the beatmap cache. Proceed?"
+    };
+    if !confirm(ctx, msg.channel_id, msg.author.id, prompt).await? {
+        msg.reply(&ctx, "Nothing was cleared.").await?;
+        return Ok(());
+    }
     env.beatmaps.clear().await?;
 
-    if args.remains() == Some("--oppai") {
+    if oppai {
         env.oppai.clear().await?;
     }
     msg.reply_ping(ctx, "Beatmap cache cleared!").await?;
     Ok(())
 }
 
+#[command("prunebests")]
+#[owners_only]
+#[description = "Prune the saved scores past the retention policy now."]
+#[max_args(0)]
+pub async fn prune_bests(ctx: &Context, msg: &Message, _: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let deleted = env.user_bests.prune().await?;
+    msg.reply_ping(ctx, format!("Pruned {} saved scores!", deleted))
+        .await?;
+    Ok(())
+}
+
 async fn get_user(
     ctx: &Context,
     env: &OsuEnv,
@@ -770,66 +1429,155 @@ async fn get_user(
     mode: Mode,
 ) -> CommandResult {
     let user = to_user_id_query(args.single::<UsernameArg>().ok(), &env, msg).await?;
-    let osu_client = &env.client;
-    let meta_cache = &env.beatmaps;
-    let user = osu_client.user(user, |f| f.mode(mode)).await?;
-
-    match user {
-        Some(u) => {
-            let bests = osu_client
-                .user_best(UserID::ID(u.id), |f| f.limit(100).mode(mode))
-                .await?;
-            let map_length = calculate_weighted_map_length(&bests, meta_cache, mode).await?;
-            let best = match bests.into_iter().next() {
-                Some(m) => {
-                    let beatmap = meta_cache.get_beatmap(m.beatmap_id, mode).await?;
-                    let info = env
-                        .oppai
-                        .get_beatmap(m.beatmap_id)
-                        .await?
-                        .get_info_with(mode, m.mods)?;
-                    Some((m, BeatmapWithMode(beatmap, mode), info))
-                }
-                None => None,
-            };
+    let locale = env.locale_for(msg).await;
+    match user_profile(env, user, mode, msg.guild_id, msg.channel_id).await? {
+        Some(embed) => {
             msg.channel_id
                 .send_message(
                     &ctx,
                     CreateMessage::new()
-                        .content(format!(
-                            "{}: here is the user that you requested",
-                            msg.author
+                        .content(i18n::trf(
+                            locale,
+                            "requested-user-of",
+                            &[("user", &msg.author)],
                         ))
-                        .embed(user_embed(u, map_length, best)),
+                        .embed(embed),
                 )
                 .await?;
         }
         None => {
-            msg.reply(&ctx, "🔍 user not found!").await?;
+            msg.reply(&ctx, i18n::tr(locale, "user-not-found")).await?;
         }
     };
     Ok(())
 }
 
-pub(in crate::discord) async fn calculate_weighted_map_length(
-    from_scores: impl IntoIterator<Item = &Score>,
-    cache: &BeatmapMetaCache,
+/// The profile embed of the user in `mode`, shown in the given channel, or `None` if the user is not found.
+pub(crate) async fn user_profile(
+    env: &OsuEnv,
+    user: UserID,
     mode: Mode,
-) -> Result<f64> {
-    from_scores
-        .into_iter()
-        .enumerate()
-        .map(|(i, s)| async move {
-            let beatmap = cache.get_beatmap(s.beatmap_id, mode).await?;
-            const SCALING_FACTOR: f64 = 0.975;
-            Ok(beatmap
-                .difficulty
-                .apply_mods(s.mods, 0.0 /* dont care */)
-                .drain_length
-                .as_secs_f64()
-                * (SCALING_FACTOR.powi(i as i32)))
-        })
-        .collect::<FuturesUnordered<_>>()
-        .try_fold(0.0, |a, b| future::ready(Ok(a + b)))
+    guild_id: Option<GuildId>,
+    channel_id: ChannelId,
+) -> Result<Option<CreateEmbed>> {
+    let osu_client = &env.client;
+    let meta_cache = &env.beatmaps;
+    let Some(u) = env.users.get(user, Some(mode)).await? else {
+        return Ok(None);
+    };
+    env.recent_usernames.record(channel_id, &u.username);
+    let bests = osu_client
+        .user_best(UserID::ID(u.id), |f| f.limit(100).mode(mode))
+        .await?;
+    let map_length = env.map_lengths.get(u.id, &bests, meta_cache, mode).await?;
+    env.stats_snapshots
+        .record(&u, mode, Some(map_length))
+        .await
+        .pls_ok();
+    let best = match bests.into_iter().next() {
+        Some(m) => {
+            let beatmap = meta_cache.get_beatmap(m.beatmap_id, mode).await?;
+            let info = env.oppai.get_info(&beatmap, mode, m.mods).await?;
+            Some((m, BeatmapWithMode(beatmap, mode), info))
+        }
+        None => None,
+    };
+    let theme = env.embed_theme(guild_id).await;
+    Ok(Some(user_embed(u, map_length, best, &theme)))
+}
+
+/// The file name of attached score cards.
+pub(crate) const SCORE_CARD_FILENAME: &str = "score-card.png";
+
+/// Render the score card of the given score, if rendering is enabled and the theme allows images.
+pub(crate) async fn score_card_attachment(
+    env: &OsuEnv,
+    theme: &EmbedTheme,
+    score: &Score,
+    bm: &BeatmapWithMode,
+    content: &BeatmapContent,
+    user: &User,
+) -> Option<CreateAttachment> {
+    let renderer = env.renderer.as_ref().filter(|_| theme.show_images)?;
+    let mode = bm.mode();
+    let info = content.get_info_with(mode, score.mods).ok();
+    let card = ScoreCard::new(
+        score,
+        &bm.0,
+        mode,
+        user,
+        info.map(|i| i.stars).unwrap_or(bm.0.difficulty.stars),
+        info.map(|i| i.max_combo as u64),
+    );
+    let png = renderer.score_card(card).await.pls_ok()?;
+    Some(CreateAttachment::bytes(
+        png.as_ref().clone(),
+        SCORE_CARD_FILENAME,
+    ))
+}
+
+/// The file name of attached strain graphs.
+pub(crate) const STRAIN_GRAPH_FILENAME: &str = "strains.png";
+
+/// Render the strain graph of the given beatmap, if rendering is enabled and the theme allows images.
+pub(crate) async fn strain_graph_attachment(
+    env: &OsuEnv,
+    theme: &EmbedTheme,
+    beatmap_id: u64,
+    content: &BeatmapContent,
+    mode: Mode,
+    mods: Mods,
+) -> Option<CreateAttachment> {
+    let renderer = env.renderer.as_ref().filter(|_| theme.show_images)?;
+    let key = format!("strains-{}-{}-{}", beatmap_id, mode as u8, mods.bits());
+    let png = renderer
+        .strain_graph(key, Box::new(content.get_strains(mode, mods)))
         .await
+        .pls_ok()?;
+    Some(CreateAttachment::bytes(
+        png.as_ref().clone(),
+        STRAIN_GRAPH_FILENAME,
+    ))
+}
+
+/// The file name of attached beatmap covers.
+pub(crate) const COVER_FILENAME: &str = "cover.jpg";
+
+/// Attach the cover of the given beatmapset from the asset cache, if it is enabled and the theme allows images.
+pub(crate) async fn cover_attachment(
+    env: &OsuEnv,
+    theme: &EmbedTheme,
+    beatmapset_id: u64,
+) -> Option<CreateAttachment> {
+    let assets = env.assets.as_ref().filter(|_| theme.show_images)?;
+    let cover = assets.cover(beatmapset_id).await.pls_ok()?;
+    Some(CreateAttachment::bytes(cover, COVER_FILENAME))
+}
+
+/// Find the position of the given score on the beatmap's global leaderboard, if it is there.
+pub(crate) async fn score_global_rank(env: &OsuEnv, score: &Score, mode: Mode) -> Option<u32> {
+    if score.global_rank.is_some() {
+        return score.global_rank;
+    }
+    let id = score.id?;
+    let (best_id, pos) = env
+        .client
+        .user_beatmap_position(score.beatmap_id, score.user_id, mode)
+        .await
+        .pls_ok()??;
+    Some(pos).filter(|_| best_id == id)
+}
+
+/// Download the replay of the given score, if available, and compute its hit statistics.
+pub(crate) async fn score_hit_stats(
+    env: &OsuEnv,
+    score: &Score,
+    mode: Mode,
+    content: &BeatmapContent,
+) -> Option<HitStats> {
+    if !score.replay_available {
+        return None;
+    }
+    let replay = env.client.replay(score.id?, mode).await.pls_ok()??;
+    HitStats::from_replay(&replay, &content.content)
 }
@@ -0,0 +1,78 @@
+use std::{collections::HashMap, sync::Arc};
+
+use youmubot_prelude::{settings::OsuConfig, *};
+
+use crate::models::{mods::LAZER_TEXT, Mods};
+
+/// A mapping from mod acronyms to (custom) emojis, configured per bot instance.
+///
+/// Emojis do not render inside code blocks, so tables should keep using the plain text form.
+#[derive(Debug, Clone, Default)]
+pub struct ModEmojis(Arc<HashMap<String, String>>);
+
+impl ModEmojis {
+    /// Load the emoji set at `osu.mod_emojis`.
+    /// Returns an empty set (text only) if it is not set.
+    pub fn from_config(config: &OsuConfig) -> Result<Self> {
+        match &config.mod_emojis {
+            Some(v) => Self::parse(v),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Parse an emoji set, in the `HD=<:hd:1234>,HR=<:hr:5678>` format.
+    /// The lazer flag can be given an emoji with the `v2` key.
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut map = HashMap::new();
+        for entry in s.split(',').map(|v| v.trim()).filter(|v| !v.is_empty()) {
+            let (name, emoji) = entry
+                .split_once('=')
+                .ok_or_else(|| error!("invalid mod emoji entry `{}`, expected MOD=emoji", entry))?;
+            let name = name.trim().to_uppercase();
+            if name != LAZER_TEXT.to_uppercase() && name.parse::<Mods>().is_err() {
+                bail!("`{}` is not a valid mod", name);
+            }
+            map.insert(name, emoji.trim().to_owned());
+        }
+        Ok(Self(Arc::new(map)))
+    }
+
+    /// Whether no emojis are configured.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Format the mods with emojis, falling back to the text form of
+    /// each mod that does not have one.
+    pub fn format(&self, mods: Mods) -> String {
+        if self.is_empty() {
+            return mods.to_string();
+        }
+        let lookup = |name: &str| {
+            self.0
+                .get(&name.to_uppercase())
+                .cloned()
+                .unwrap_or_else(|| name.to_owned())
+        };
+        let mut s = mods.acronyms().map(lookup).collect::<String>();
+        if mods.contains(Mods::LAZER) {
+            s.push_str(&lookup(LAZER_TEXT));
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let emojis = ModEmojis::parse("HD=<:hd:1>, nc = <:nc:2>").unwrap();
+        assert_eq!(emojis.format(Mods::HD | Mods::HR), "<:hd:1>HR");
+        assert_eq!(emojis.format(Mods::NC | Mods::DT), "<:nc:2>");
+        assert_eq!(ModEmojis::default().format(Mods::HD), "+HD");
+        assert!(ModEmojis::parse("XX=<:x:3>").is_err());
+        assert!(ModEmojis::parse("HD").is_err());
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use serenity::{
+    builder::{CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, CreateMessage},
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandResult,
+    },
+    model::{
+        channel::Message,
+        id::{ChannelId, GuildId},
+    },
+    utils::MessageBuilder,
+};
+
+use youmubot_db_sql::models::osu as models;
+use youmubot_prelude::announcer::CacheAndHttp;
+use youmubot_prelude::permissions::MANAGE_GUILD_CHECK;
+use youmubot_prelude::*;
+
+use crate::{
+    models::{
+        news::{ChangelogBuild, NewsPost},
+        DiscordTimestamp,
+    },
+    ratelimit::Priority,
+};
+
+use super::{theme::EmbedTheme, OsuEnv};
+
+/// When the news and changelogs are checked: every 15 minutes.
+pub(crate) const NEWS_SCHEDULE: &str = "*/15 * * * *";
+/// The update stream of lazer releases.
+const LAZER_STREAM: &str = "lazer";
+/// How many news articles are fetched each time.
+const NEWS_FETCHED: u32 = 5;
+/// How many news articles or releases are posted at once, at most.
+const MAX_POSTED: usize = 3;
+/// The longest changelog shown in an embed.
+const MAX_CHANGELOG_LEN: usize = 3000;
+
+#[group]
+#[prefix = "news"]
+#[description = "The osu! news and the lazer changelogs."]
+#[commands(news_latest, news_status, news_subscribe, news_unsubscribe)]
+#[default_command(news_latest)]
+struct OsuNews;
+
+#[command("latest")]
+#[description = "Show the latest osu! news article and lazer release."]
+#[max_args(0)]
+#[bucket("heavy")]
+pub async fn news_latest(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let theme = env.embed_theme_for(msg).await;
+    let post = env.client.news(1).await?.into_iter().next();
+    let build = env.client.changelog(LAZER_STREAM).await?.into_iter().next();
+    let embeds = post
+        .map(|p| news_embed(&p, &theme))
+        .into_iter()
+        .chain(build.map(|b| changelog_embed(&b, &theme)))
+        .collect::<Vec<_>>();
+    if embeds.is_empty() {
+        msg.reply(&ctx, theme.tr("news-none")).await?;
+        return Ok(());
+    }
+    msg.channel_id
+        .send_message(
+            &ctx,
+            CreateMessage::new().reference_message(msg).embeds(embeds),
+        )
+        .await?;
+    Ok(())
+}
+
+#[command("status")]
+#[description = "Show where the osu! news and lazer changelogs are posted in this server."]
+#[only_in(guilds)]
+#[max_args(0)]
+pub async fn news_status(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let theme = env.embed_theme_for(msg).await;
+    let guild = msg.guild_id.unwrap().get() as i64;
+    let reply = match models::NewsSubscription::by_guild(guild, &env.prelude.sql).await? {
+        Some(sub) => theme.trf(
+            "news-subscription",
+            &[
+                ("what", &theme.tr(subscription_key(sub.news, sub.changelog))),
+                (
+                    "channel",
+                    &ChannelId::new(sub.channel_id as u64).mention().to_string(),
+                ),
+            ],
+        ),
+        None => theme.tr("news-not-subscribed").to_owned(),
+    };
+    msg.reply(&ctx, reply).await?;
+    Ok(())
+}
+
+#[command("subscribe")]
+#[description = "Post the new osu! news articles, the new lazer releases, or both, to a channel. \
+                 Replaces the server's previous subscription."]
+#[usage = "#channel [news / changelog / all = all]"]
+#[example = "#osu-news changelog"]
+#[checks(manage_guild)]
+#[only_in(guilds)]
+#[min_args(1)]
+#[max_args(2)]
+pub async fn news_subscribe(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let guild_id = msg.guild_id.unwrap();
+    let channel = args.single::<args::ChannelId>()?.0;
+    let (news, changelog) = match args
+        .single::<String>()
+        .ok()
+        .map(|v| v.to_lowercase())
+        .as_deref()
+    {
+        None | Some("all") => (true, true),
+        Some("news") => (true, false),
+        Some("changelog") => (false, true),
+        Some(v) => {
+            return Err(error!(
+                "unknown subscription `{}`, expected `news`, `changelog` or `all`",
+                v
+            )
+            .into())
+        }
+    };
+    if channel
+        .to_channel(&ctx)
+        .await?
+        .guild()
+        .filter(|c| c.guild_id == guild_id)
+        .is_none()
+    {
+        return Err(Error::msg("the channel must be in this server").into());
+    }
+
+    // Only what is published from now on is posted.
+    let now = Utc::now();
+    models::NewsSubscription {
+        guild_id: guild_id.get() as i64,
+        channel_id: channel.get() as i64,
+        news,
+        changelog,
+        last_news_at: now,
+        last_build_at: now,
+    }
+    .store(&env.prelude.sql)
+    .await?;
+
+    let theme = env.embed_theme_for(msg).await;
+    msg.reply(
+        &ctx,
+        theme.trf(
+            "news-subscribed",
+            &[
+                ("what", &theme.tr(subscription_key(news, changelog))),
+                ("channel", &channel.mention().to_string()),
+            ],
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+#[command("unsubscribe")]
+#[description = "Stop posting the osu! news and lazer changelogs in this server."]
+#[checks(manage_guild)]
+#[only_in(guilds)]
+#[max_args(0)]
+pub async fn news_unsubscribe(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let theme = env.embed_theme_for(msg).await;
+    let guild = msg.guild_id.unwrap().get() as i64;
+    let reply = if models::NewsSubscription::delete(guild, &env.prelude.sql).await? {
+        "news-unsubscribed"
+    } else {
+        "news-not-subscribed"
+    };
+    msg.reply(&ctx, theme.tr(reply)).await?;
+    Ok(())
+}
+
+/// The translation key of what a subscription posts.
+fn subscription_key(news: bool, changelog: bool) -> &'static str {
+    match (news, changelog) {
+        (true, false) => "news-kind-news",
+        (false, true) => "news-kind-changelog",
+        _ => "news-kind-all",
+    }
+}
+
+/// The embed of a news article.
+fn news_embed(post: &NewsPost, theme: &EmbedTheme) -> CreateEmbed {
+    let mut embed = CreateEmbed::new()
+        .title(MessageBuilder::new().push_safe(&post.title).build())
+        .url(post.link())
+        .color(theme.color)
+        .author(CreateEmbedAuthor::new(&post.author))
+        .footer(CreateEmbedFooter::new(theme.tr("news-footer")))
+        .field(
+            theme.tr("news-published"),
+            post.published_at.relative_time(),
+            true,
+        );
+    if let Some(preview) = &post.preview {
+        embed = embed.description(MessageBuilder::new().push_safe(preview).build());
+    }
+    match &post.first_image {
+        Some(image) => theme.image(embed, image),
+        None => embed,
+    }
+}
+
+/// The embed of a release's changelog, grouped by category.
+fn changelog_embed(build: &ChangelogBuild, theme: &EmbedTheme) -> CreateEmbed {
+    let mut categories = BTreeMap::<&str, Vec<String>>::new();
+    for entry in &build.entries {
+        let title = MessageBuilder::new().push_safe(&entry.title).build();
+        let line = match (&entry.url, entry.major) {
+            (Some(url), true) => format!("- **[{}](<{}>)**", title, url),
+            (Some(url), false) => format!("- [{}](<{}>)", title, url),
+            (None, true) => format!("- **{}**", title),
+            (None, false) => format!("- {}", title),
+        };
+        categories.entry(&entry.category).or_default().push(line);
+    }
+    let mut description = String::new();
+    let mut shown = 0;
+    'categories: for (category, lines) in &categories {
+        let header = format!("**{}**\n", category);
+        if description.len() + header.len() > MAX_CHANGELOG_LEN {
+            break;
+        }
+        description.push_str(&header);
+        for line in lines {
+            if description.len() + line.len() + 1 > MAX_CHANGELOG_LEN {
+                break 'categories;
+            }
+            description.push_str(line);
+            description.push('\n');
+            shown += 1;
+        }
+    }
+    if shown < build.entries.len() {
+        description.push_str(&theme.trf(
+            "news-changelog-more",
+            &[("count", &(build.entries.len() - shown))],
+        ));
+    }
+    CreateEmbed::new()
+        .title(format!("{} {}", build.stream_name, build.version))
+        .url(build.link())
+        .color(theme.color)
+        .description(description)
+        .footer(CreateEmbedFooter::new(theme.tr("news-changelog-footer")))
+        .field(
+            theme.tr("news-released"),
+            build.created_at.relative_time(),
+            true,
+        )
+}
+
+/// Post the new news articles and lazer releases to the subscribed channels, as a scheduled job.
+pub(crate) async fn news_job(env: OsuEnv) -> Result<()> {
+    // Wait for the Discord client to be built.
+    let Some(http) = env.prelude.scheduler.discord() else {
+        return Ok(());
+    };
+    let subscriptions = models::NewsSubscription::all(&env.prelude.sql).await?;
+    if subscriptions.is_empty() {
+        return Ok(());
+    }
+    let client = env.client.with_priority(Priority::Background);
+    let mut posts = if subscriptions.iter().any(|s| s.news) {
+        client.news(NEWS_FETCHED).await?
+    } else {
+        vec![]
+    };
+    let mut builds = if subscriptions.iter().any(|s| s.changelog) {
+        client.changelog(LAZER_STREAM).await?
+    } else {
+        vec![]
+    };
+    // Oldest first.
+    posts.sort_by_key(|p| p.published_at);
+    builds.sort_by_key(|b| b.created_at);
+
+    for sub in subscriptions {
+        let new_posts = posts
+            .iter()
+            .filter(|p| sub.news && p.published_at > sub.last_news_at)
+            .collect::<Vec<_>>();
+        let new_builds = builds
+            .iter()
+            .filter(|b| sub.changelog && b.created_at > sub.last_build_at)
+            .collect::<Vec<_>>();
+        if new_posts.is_empty() && new_builds.is_empty() {
+            continue;
+        }
+        // Mark them first, so that a failing channel is not retried every time.
+        models::NewsSubscription::set_seen(
+            sub.guild_id,
+            new_posts
+                .last()
+                .map_or(sub.last_news_at, |p| p.published_at),
+            new_builds
+                .last()
+                .map_or(sub.last_build_at, |b| b.created_at),
+            &env.prelude.sql,
+        )
+        .await?;
+        // Only the latest ones are posted after a long downtime.
+        let new_posts = &new_posts[new_posts.len().saturating_sub(MAX_POSTED)..];
+        let new_builds = &new_builds[new_builds.len().saturating_sub(MAX_POSTED)..];
+        if let Err(e) = post_news(&env, http.clone(), &sub, new_posts, new_builds).await {
+            tracing::warn!(guild = sub.guild_id, "osu: cannot post the news: {:#}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Post the news articles and releases to the subscription's channel.
+async fn post_news(
+    env: &OsuEnv,
+    http: CacheAndHttp,
+    sub: &models::NewsSubscription,
+    posts: &[&NewsPost],
+    builds: &[&ChangelogBuild],
+) -> Result<()> {
+    let channel = ChannelId::new(sub.channel_id as u64);
+    let theme = env
+        .embed_theme(Some(GuildId::new(sub.guild_id as u64)))
+        .await;
+    for post in posts {
+        let message = CreateMessage::new()
+            .content(theme.tr("announce-news"))
+            .embed(news_embed(post, &theme));
+        env.prelude
+            .send_queue
+            .send(http.clone(), channel, message)
+            .await?;
+    }
+    for build in builds {
+        let message = CreateMessage::new()
+            .content(theme.trf(
+                "announce-changelog",
+                &[(
+                    "version",
+                    &format!("{} {}", build.stream_name, build.version),
+                )],
+            ))
+            .embed(changelog_embed(build, &theme));
+        env.prelude
+            .send_queue
+            .send(http.clone(), channel, message)
+            .await?;
+    }
+    Ok(())
+}
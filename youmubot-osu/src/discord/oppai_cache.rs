@@ -1,27 +1,35 @@
 use std::io::Read;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
 
 use osuparse::MetadataSection;
-use rosu_pp::catch::CatchDifficultyAttributes;
-use rosu_pp::mania::ManiaDifficultyAttributes;
-use rosu_pp::osu::OsuDifficultyAttributes;
-use rosu_pp::taiko::TaikoDifficultyAttributes;
+use rosu_pp::catch::{CatchDifficultyAttributes, CatchStars};
+use rosu_pp::mania::{ManiaDifficultyAttributes, ManiaStars};
+use rosu_pp::osu::{OsuDifficultyAttributes, OsuStars};
+use rosu_pp::taiko::{TaikoDifficultyAttributes, TaikoStars};
 use rosu_pp::{AttributeProvider, Beatmap, CatchPP, DifficultyAttributes, ManiaPP, OsuPP, TaikoPP};
 
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
 use youmubot_db_sql::{models::osu as models, Pool};
-use youmubot_prelude::*;
+use youmubot_prelude::{settings::OsuConfig, *};
 
-use crate::{models::Mode, mods::Mods};
+use crate::{models::Mode, mods::Mods, render::Strains};
+
+use super::cache_stats::HitCounter;
 
 /// the information collected from a download/Oppai request.
 #[derive(Debug)]
 pub struct BeatmapContent {
     pub metadata: MetadataSection,
     pub content: Arc<Beatmap>,
+    /// The md5 of the .osu file, to compare with the beatmap's checksum.
+    pub file_md5: String,
 }
 
 /// the output of "one" oppai run.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct BeatmapInfo {
     pub objects: usize,
     pub max_combo: usize,
@@ -67,8 +75,26 @@ impl Accuracy {
     }
 }
 
-/// Beatmap Info with attached 95/98/99/100% FC pp.
-pub type BeatmapInfoWithPP = (BeatmapInfo, [f64; 4]);
+/// The accuracies (in %) that [BeatmapInfoWithPP] calculates FC pp for.
+pub const PP_ACCURACIES: [f64; 4] = [95.0, 98.0, 99.0, 100.0];
+
+/// Beatmap Info with attached 95/98/99/100% FC pp, for the given mode and mods.
+#[derive(Clone, Copy, Debug)]
+pub struct BeatmapInfoWithPP {
+    pub info: BeatmapInfo,
+    /// The mode the pp was calculated for. Might differ from the beatmap's mode for converts.
+    pub mode: Mode,
+    pub mods: Mods,
+    /// FC pp for each accuracy in [PP_ACCURACIES].
+    pub pp: [f64; 4],
+}
+
+impl BeatmapInfoWithPP {
+    /// Pairs of (accuracy, pp).
+    pub fn pp_curve(&self) -> impl Iterator<Item = (f64, f64)> {
+        PP_ACCURACIES.into_iter().zip(self.pp)
+    }
+}
 
 trait PPCalc<'a>: Sized {
     type Attrs: rosu_pp::AttributeProvider + Clone;
@@ -111,19 +137,14 @@ trait PPCalc<'a>: Sized {
         BeatmapInfo::extract(beatmap, attrs)
     }
 
-    fn map_info_with_pp(beatmap: &'a Beatmap, mods: Mods) -> BeatmapInfoWithPP {
+    fn map_info_with_pp(beatmap: &'a Beatmap, mods: Mods) -> (BeatmapInfo, [f64; 4]) {
         let attrs = Self::map_attributes(beatmap, mods);
         let nw = || {
             Self::new(beatmap)
                 .mods(mods.bits() as u32)
                 .attributes(attrs.clone())
         };
-        let pps = [
-            nw().accuracy_from(Accuracy::ByValue(95.0, 0)).get_pp(),
-            nw().accuracy_from(Accuracy::ByValue(98.0, 0)).get_pp(),
-            nw().accuracy_from(Accuracy::ByValue(99.0, 0)).get_pp(),
-            nw().accuracy_from(Accuracy::ByValue(100.0, 0)).get_pp(),
-        ];
+        let pps = PP_ACCURACIES.map(|acc| nw().accuracy_from(Accuracy::ByValue(acc, 0)).get_pp());
         let info = BeatmapInfo::extract(beatmap, attrs.attributes());
         (info, pps)
     }
@@ -299,37 +320,219 @@ impl BeatmapContent {
         })
     }
 
+    /// Get the strains of each skill over time, given mods.
+    /// Beatmaps are converted to `mode` if needed.
+    ///
+    /// Computing the strains is slow, so it is left to the caller, e.g. to run on a blocking thread.
+    pub fn get_strains(&self, mode: Mode, mods: Mods) -> impl FnOnce() -> Strains + Send + 'static {
+        let bm = self.content.clone();
+        let mods = mods.bits() as u32;
+        move || strains(&bm, mode, mods)
+    }
+
+    /// Get info and the pp curve given mods.
+    /// Beatmaps are converted to `mode` if needed.
     pub fn get_possible_pp_with(&self, mode: Mode, mods: Mods) -> Result<BeatmapInfoWithPP> {
         let bm = self.content.as_ref();
-        Ok(match mode {
+        let (info, pp) = match mode {
             Mode::Std => OsuPP::map_info_with_pp(bm, mods),
             Mode::Taiko => TaikoPP::map_info_with_pp(bm, mods),
             Mode::Catch => CatchPP::map_info_with_pp(bm, mods),
             Mode::Mania => ManiaPP::map_info_with_pp(bm, mods),
+        };
+        Ok(BeatmapInfoWithPP {
+            info,
+            mode,
+            mods,
+            pp,
         })
     }
 }
 
+/// The strains of each skill over time of the beatmap, converted to `mode`.
+fn strains(bm: &Beatmap, mode: Mode, mods: u32) -> Strains {
+    match mode {
+        Mode::Std => {
+            let s = OsuStars::new(bm).mods(mods).strains();
+            Strains {
+                section_len: s.section_len,
+                skills: vec![("Aim", s.aim), ("Speed", s.speed)],
+            }
+        }
+        Mode::Taiko => {
+            let s = TaikoStars::new(bm).mods(mods).strains();
+            Strains {
+                section_len: s.section_len,
+                skills: vec![
+                    ("Color", s.color),
+                    ("Rhythm", s.rhythm),
+                    ("Stamina", s.stamina),
+                ],
+            }
+        }
+        Mode::Catch => {
+            let s = CatchStars::new(bm).mods(mods).strains();
+            Strains {
+                section_len: s.section_len,
+                skills: vec![("Movement", s.movement)],
+            }
+        }
+        Mode::Mania => {
+            let s = ManiaStars::new(bm).mods(mods).strains();
+            Strains {
+                section_len: s.section_len,
+                skills: vec![("Strain", s.strains)],
+            }
+        }
+    }
+}
+
+/// Compute the difficulty and FC pp of unsubmitted beatmaps (e.g. the ones in an `.osz`)
+/// in their own modes, off the async runtime.
+/// The beatmaps are returned sorted by mode, then by star rating.
+pub async fn compute_offline(
+    beatmaps: Vec<BeatmapContent>,
+    mods: Mods,
+) -> Result<Vec<(BeatmapContent, BeatmapInfoWithPP)>> {
+    let mut computed = tokio::task::spawn_blocking(move || {
+        beatmaps
+            .into_iter()
+            .filter_map(|b| {
+                let mode = Mode::from(b.content.mode as u8);
+                let info = b.get_possible_pp_with(mode, mods).pls_ok()?;
+                Some((b, info))
+            })
+            .collect::<Vec<_>>()
+    })
+    .await?;
+    computed.sort_by(|(_, a), (_, b)| {
+        (a.mode as u8)
+            .cmp(&(b.mode as u8))
+            .then(a.info.stars.total_cmp(&b.info.stars))
+    });
+    Ok(computed)
+}
+
+/// The default directory of the cached .osu files.
+const DEFAULT_FILES_DIR: &str = "cache/beatmaps";
+
 /// A central cache for the beatmaps.
+///
+/// The .osu files are stored on disk, named by their beatmap id and checksum,
+/// and indexed in the database. Their difficulty attributes are stored in the database.
 #[derive(Debug, Clone)]
 pub struct BeatmapCache {
     client: ratelimit::Ratelimit<reqwest::Client>,
     pool: Pool,
+    /// Where the .osu files are stored.
+    dir: PathBuf,
+    /// Beatmaps being loaded and parsed.
+    inflight: Arc<Singleflight<u64, Arc<BeatmapContent>>>,
+    /// When each beatmap was last used, so that the recently used ones can be checked for updates.
+    active: Arc<DashMap<u64, Instant>>,
+    /// Beatmap files found on disk.
+    content_counter: Arc<HitCounter>,
+    /// Difficulty attributes found in the database.
+    attributes_counter: Arc<HitCounter>,
 }
 
 impl BeatmapCache {
-    /// Create a new cache.
-    pub fn new(client: reqwest::Client, pool: Pool) -> Self {
+    /// Create a new cache, storing the .osu files in the directory at `osu.beatmap_files`.
+    pub fn new(client: reqwest::Client, pool: Pool, config: &OsuConfig) -> Result<Self> {
+        let dir = config
+            .beatmap_files
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_FILES_DIR));
+        Self::with_dir(client, pool, dir)
+    }
+
+    /// Create a new cache, storing the .osu files in `dir`.
+    pub fn with_dir(client: reqwest::Client, pool: Pool, dir: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| error!("cannot create {}: {}", dir.display(), e))?;
         let client = ratelimit::Ratelimit::new(client, 5, std::time::Duration::from_secs(1));
-        BeatmapCache { client, pool }
+        Ok(BeatmapCache {
+            client,
+            pool,
+            dir,
+            inflight: Arc::new(Singleflight::new()),
+            active: Arc::new(DashMap::new()),
+            content_counter: Arc::new(HitCounter::default()),
+            attributes_counter: Arc::new(HitCounter::default()),
+        })
     }
 
     /// Clean the cache.
     pub async fn clear(&self) -> Result<()> {
-        models::CachedBeatmapContent::clear_all(&self.pool).await?;
+        let files = models::CachedBeatmapFile::clear_all(&self.pool).await?;
+        self.remove_files(&files).await;
+        models::CachedDifficulty::clear_all(&self.pool).await?;
         Ok(())
     }
 
+    /// Drop the beatmaps and attributes cached longer than `age` ago.
+    /// Returns the number of deleted entries.
+    pub async fn evict_older_than(&self, age: std::time::Duration) -> Result<u64> {
+        let before = chrono::Utc::now() - chrono::Duration::from_std(age)?;
+        let files = models::CachedBeatmapFile::delete_older_than(before, &self.pool).await?;
+        self.remove_files(&files).await;
+        let attributes = models::CachedDifficulty::delete_older_than(before, &self.pool).await?;
+        Ok(files.len() as u64 + attributes)
+    }
+
+    /// The counters of the beatmap files and difficulty attributes.
+    pub(crate) fn counters(&self) -> (&HitCounter, &HitCounter) {
+        (&self.content_counter, &self.attributes_counter)
+    }
+
+    /// The size of the beatmap files and difficulty attributes tables.
+    pub(crate) async fn db_stats(
+        &self,
+    ) -> Result<(models::CacheTableStats, models::CacheTableStats)> {
+        Ok((
+            models::CachedBeatmapFile::stats(&self.pool).await?,
+            models::CachedDifficulty::stats(&self.pool).await?,
+        ))
+    }
+
+    /// The beatmaps used within `window`. The ones used before are forgotten.
+    pub(crate) fn recently_used(&self, window: std::time::Duration) -> Vec<u64> {
+        self.active.retain(|_, used| used.elapsed() <= window);
+        self.active.iter().map(|e| *e.key()).collect()
+    }
+
+    /// Drop the beatmap's file and difficulty attributes if they were not computed from
+    /// the .osu file with the given checksum, e.g. when the beatmap was updated.
+    /// Returns whether anything was dropped.
+    pub async fn invalidate(&self, id: u64, file_md5: &str) -> Result<bool> {
+        let mut invalidated = false;
+        if let Some(f) = models::CachedBeatmapFile::by_id(id as i64, &self.pool).await? {
+            if f.file_md5 != file_md5 {
+                models::CachedBeatmapFile::delete(id as i64, &self.pool).await?;
+                self.remove_files(&[f]).await;
+                invalidated = true;
+            }
+        }
+        let attributes =
+            models::CachedDifficulty::delete_outdated(id as i64, file_md5, &self.pool).await?;
+        Ok(invalidated || attributes > 0)
+    }
+
+    /// The path of the .osu file of the given beatmap and checksum.
+    fn file_path(&self, id: u64, file_md5: &str) -> PathBuf {
+        self.dir.join(format!("{}-{}.osu", id, file_md5))
+    }
+
+    /// Remove the files of the given deleted index entries.
+    async fn remove_files(&self, files: &[models::CachedBeatmapFile]) {
+        for f in files {
+            // The file might have been removed already.
+            tokio::fs::remove_file(self.file_path(f.beatmap_id as u64, &f.file_md5))
+                .await
+                .ok();
+        }
+    }
+
     fn parse_beatmap(content: impl AsRef<str>) -> Result<BeatmapContent> {
         let content = content.as_ref();
         let metadata = osuparse::parse_beatmap(content)
@@ -338,6 +541,7 @@ impl BeatmapCache {
         Ok(BeatmapContent {
             metadata,
             content: Arc::new(Beatmap::parse(content.as_bytes())?),
+            file_md5: format!("{:x}", md5::compute(content)),
         })
     }
 
@@ -399,30 +603,167 @@ impl BeatmapCache {
         let (bm, content) = self
             .download_beatmap_from_url(&format!("https://osu.ppy.sh/osu/{}", id))
             .await?;
+        self.store_file(id, &bm, &content).await?;
+        Ok(bm)
+    }
+
+    /// Store the beatmap's .osu file, as if it was downloaded.
+    #[cfg(test)]
+    pub(crate) async fn store_beatmap(&self, id: u64, content: &str) -> Result<()> {
+        let bm = Self::parse_beatmap(content)?;
+        self.store_file(id, &bm, content).await
+    }
 
-        let mut bc = models::CachedBeatmapContent {
+    /// Store the parsed .osu file, replacing the cached one.
+    async fn store_file(&self, id: u64, bm: &BeatmapContent, content: &str) -> Result<()> {
+        let previous = models::CachedBeatmapFile::by_id(id as i64, &self.pool).await?;
+        // Write to a temporary file first, so that a partially written file is never read.
+        let path = self.file_path(id, &bm.file_md5);
+        let tmp = path.with_extension("osu.tmp");
+        tokio::fs::write(&tmp, content).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        let mut f = models::CachedBeatmapFile {
             beatmap_id: id as i64,
+            file_md5: bm.file_md5.clone(),
+            size: content.len() as i64,
             cached_at: chrono::Utc::now(),
-            content: content.into_bytes(),
         };
-        bc.store(&self.pool).await?;
-        Ok(bm)
+        f.store(&self.pool).await?;
+        if let Some(previous) = previous.filter(|p| p.file_md5 != bm.file_md5) {
+            self.remove_files(&[previous]).await;
+        }
+        Ok(())
     }
 
-    async fn get_beatmap_db(&self, id: u64) -> Result<Option<BeatmapContent>> {
-        models::CachedBeatmapContent::by_id(id as i64, &self.pool)
-            .await?
-            .map(|v| Self::parse_beatmap(String::from_utf8(v.content)?))
-            .transpose()
+    /// Read the beatmap's .osu file from disk, if it is cached and intact.
+    async fn get_beatmap_file(&self, id: u64) -> Result<Option<BeatmapContent>> {
+        let Some(f) = models::CachedBeatmapFile::by_id(id as i64, &self.pool).await? else {
+            return Ok(None);
+        };
+        let path = self.file_path(id, &f.file_md5);
+        let content = match tokio::fs::read(&path).await {
+            Ok(v) => v,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if format!("{:x}", md5::compute(&content)) != f.file_md5 {
+            tracing::warn!("beatmap file {} is corrupted, removing", path.display());
+            tokio::fs::remove_file(&path).await.pls_ok();
+            return Ok(None);
+        }
+        Self::parse_beatmap(String::from_utf8(content)?).map(Some)
     }
 
     /// Get a beatmap from the cache.
-    pub async fn get_beatmap(&self, id: u64) -> Result<BeatmapContent> {
-        match self.get_beatmap_db(id).await? {
-            Some(v) => Ok(v),
-            None => self.download_beatmap(id).await,
+    /// Concurrent requests for the same beatmap are only loaded and parsed once.
+    pub async fn get_beatmap(&self, id: u64) -> Result<Arc<BeatmapContent>> {
+        self.active.insert(id, Instant::now());
+        let this = self.clone();
+        self.inflight
+            .run(id, move || async move {
+                let content = match this.get_beatmap_file(id).await? {
+                    Some(v) => {
+                        this.content_counter.hit();
+                        v
+                    }
+                    None => {
+                        this.content_counter.miss();
+                        this.download_beatmap(id).await?
+                    }
+                };
+                Ok(Arc::new(content))
+            })
+            .await
+    }
+
+    /// Get the beatmap's content, making sure it matches the beatmap's checksum.
+    /// Beatmaps updated since they were cached are downloaded again.
+    async fn get_beatmap_checked(
+        &self,
+        beatmap: &crate::models::Beatmap,
+    ) -> Result<Arc<BeatmapContent>> {
+        let content = self.get_beatmap(beatmap.beatmap_id).await?;
+        if beatmap.file_hash == "none" || content.file_md5 == beatmap.file_hash {
+            return Ok(content);
         }
+        self.invalidate(beatmap.beatmap_id, &beatmap.file_hash)
+            .await?;
+        self.get_beatmap(beatmap.beatmap_id).await
+    }
+
+    /// Get the path of the beatmap's .osu file, for tools that read it directly.
+    /// The file is downloaded if it is not cached, or if the beatmap was updated since.
+    pub async fn get_beatmap_path(&self, beatmap: &crate::models::Beatmap) -> Result<PathBuf> {
+        let content = self.get_beatmap_checked(beatmap).await?;
+        Ok(self.file_path(beatmap.beatmap_id, &content.file_md5))
     }
+
+    /// Get info and the pp curve of the beatmap given mods.
+    /// Beatmaps are converted to `mode` if needed.
+    ///
+    /// The results are stored, and only recomputed when the beatmap is updated.
+    pub async fn get_possible_pp(
+        &self,
+        beatmap: &crate::models::Beatmap,
+        mode: Mode,
+        mods: Mods,
+    ) -> Result<BeatmapInfoWithPP> {
+        let id = beatmap.beatmap_id as i64;
+        let stored =
+            models::CachedDifficulty::by_key(id, mode as u8, mods.bits() as i64, &self.pool)
+                .await
+                .pls_ok()
+                .flatten()
+                .filter(|v| v.file_md5 == beatmap.file_hash)
+                .and_then(|v| bincode::deserialize::<StoredAttributes>(&v.attributes[..]).pls_ok());
+        if let Some(StoredAttributes { info, pp }) = stored {
+            self.attributes_counter.hit();
+            return Ok(BeatmapInfoWithPP {
+                info,
+                mode,
+                mods,
+                pp,
+            });
+        }
+
+        self.attributes_counter.miss();
+        let content = self.get_beatmap_checked(beatmap).await?;
+        let info = content.get_possible_pp_with(mode, mods)?;
+        // Drop the attributes of older versions of the beatmap.
+        models::CachedDifficulty::delete_outdated(id, &content.file_md5, &self.pool)
+            .await
+            .pls_ok();
+        let mut stored = models::CachedDifficulty {
+            beatmap_id: id,
+            mode: mode as u8,
+            mods: mods.bits() as i64,
+            file_md5: content.file_md5.clone(),
+            cached_at: chrono::Utc::now(),
+            attributes: bincode::serialize(&StoredAttributes {
+                info: info.info,
+                pp: info.pp,
+            })?,
+        };
+        stored.store(&self.pool).await.pls_ok();
+        Ok(info)
+    }
+
+    /// Get info of the beatmap given mods, see [BeatmapCache::get_possible_pp].
+    pub async fn get_info(
+        &self,
+        beatmap: &crate::models::Beatmap,
+        mode: Mode,
+        mods: Mods,
+    ) -> Result<BeatmapInfo> {
+        Ok(self.get_possible_pp(beatmap, mode, mods).await?.info)
+    }
+}
+
+/// The difficulty attributes stored in the database.
+#[derive(Serialize, Deserialize)]
+struct StoredAttributes {
+    info: BeatmapInfo,
+    pp: [f64; 4],
 }
 
 impl TypeMapKey for BeatmapCache {
@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+
+use chrono::{Datelike, Timelike};
+use serenity::{
+    builder::{CreateAttachment, CreateMessage},
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use youmubot_db_sql::models::osu as models;
+use youmubot_prelude::*;
+
+use crate::{
+    models::{Mode, Mods, Score},
+    render::{Heatmap, ProfileCard, ProfileTopPlay, Scatter, ScatterPoint},
+    request::UserID,
+};
+
+use super::{to_user_id_query, ModeArg, OsuEnv, OPTIONS};
+
+/// The group of a score on plots, by its mods.
+fn mods_group(mods: Mods) -> String {
+    let s = mods.acronyms().collect::<String>();
+    if s.is_empty() {
+        "NM".to_owned()
+    } else {
+        format!("+{}", s)
+    }
+}
+
+/// Compute the star rating of each score with its mods, skipping the ones we can't calculate.
+async fn scores_with_stars(env: &OsuEnv, scores: &[Score], mode: Mode) -> Vec<(f64, &Score)> {
+    scores
+        .iter()
+        .map(|s| async move {
+            let stars = env
+                .oppai
+                .get_beatmap(s.beatmap_id)
+                .await
+                .and_then(|b| b.get_info_with(mode, s.mods))
+                .pls_ok()?
+                .stars;
+            Some((stars, s))
+        })
+        .collect::<stream::FuturesOrdered<_>>()
+        .filter_map(future::ready)
+        .collect()
+        .await
+}
+
+#[command("scatter")]
+#[description = "Plot the star rating against the pp of an user's top 100 plays, colored by mods."]
+#[usage = "[--mode (std, taiko, catch, mania) = std] / [username or user id = your saved user id], in any order"]
+#[example = "--mode taiko natsukagami"]
+#[delimiters("/", " ")]
+#[max_args(3)]
+#[bucket("heavy")]
+pub async fn scatter(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let Some(renderer) = env.renderer.clone() else {
+        msg.reply(&ctx, "Image rendering is not enabled on this bot.")
+            .await?;
+        return Ok(());
+    };
+    let mut opts = Options::parse(args, OPTIONS)?;
+    let mode = opts.get::<ModeArg>("mode")?.map_or(Mode::Std, |m| m.0);
+    let user = opts.take::<UsernameArg>();
+    opts.finish()?;
+    let user = to_user_id_query(user, &env, msg).await?;
+    let user = env
+        .users
+        .get(user, Some(mode))
+        .await?
+        .ok_or_else(|| Error::msg("User not found"))?;
+    let scores = env
+        .client
+        .user_best(UserID::ID(user.id), |f| f.mode(mode).limit(100))
+        .await?;
+    if scores.is_empty() {
+        msg.reply(&ctx, "This user has no top plays!").await?;
+        return Ok(());
+    }
+
+    let points = scores_with_stars(&env, &scores, mode)
+        .await
+        .into_iter()
+        .filter_map(|(stars, s)| {
+            Some(ScatterPoint {
+                x: stars,
+                y: s.pp?,
+                group: mods_group(s.mods),
+            })
+        })
+        .collect::<Vec<_>>();
+    let png = renderer
+        .scatter(Scatter {
+            title: format!("{}'s top plays ({})", user.username, mode),
+            x_label: "Star rating".to_owned(),
+            y_label: "pp".to_owned(),
+            points,
+        })
+        .await?;
+    msg.channel_id
+        .send_message(
+            &ctx,
+            CreateMessage::new()
+                .reference_message(msg)
+                .add_file(CreateAttachment::bytes(png, "scatter.png")),
+        )
+        .await?;
+    Ok(())
+}
+
+#[command("heatmap")]
+#[aliases("activity")]
+#[description = "Show when an user plays the most, as a heatmap of the week (in UTC). \
+                 Built from the plays recorded while tracking the user, and their top plays."]
+#[usage = "[username or user id = your saved user id]"]
+#[example = "natsukagami"]
+#[max_args(1)]
+#[bucket("heavy")]
+pub async fn heatmap(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let Some(renderer) = env.renderer.clone() else {
+        msg.reply(&ctx, "Image rendering is not enabled on this bot.")
+            .await?;
+        return Ok(());
+    };
+    let user = to_user_id_query(args.single::<UsernameArg>().ok(), &env, msg).await?;
+    let user = env
+        .users
+        .get(user, None)
+        .await?
+        .ok_or_else(|| Error::msg("User not found"))?;
+
+    // Recorded plays, plus the top plays of each mode, without duplicates.
+    let mut times = models::UserPlay::by_user(user.id as i64, &env.prelude.sql)
+        .await?
+        .into_iter()
+        .map(|p| p.played_at)
+        .collect::<HashSet<_>>();
+    let tops = [Mode::Std, Mode::Taiko, Mode::Catch, Mode::Mania]
+        .into_iter()
+        .map(|mode| {
+            env.client
+                .user_best(UserID::ID(user.id), move |f| f.mode(mode).limit(100))
+        })
+        .collect::<stream::FuturesUnordered<_>>()
+        .try_collect::<Vec<_>>()
+        .await?;
+    times.extend(tops.into_iter().flatten().map(|s| s.date));
+    if times.is_empty() {
+        msg.reply(&ctx, "No plays recorded for this user yet!")
+            .await?;
+        return Ok(());
+    }
+
+    let mut counts = [[0u32; 24]; 7];
+    for t in &times {
+        counts[t.weekday().num_days_from_monday() as usize][t.hour() as usize] += 1;
+    }
+    let png = renderer
+        .heatmap(Heatmap {
+            title: format!("{}'s activity ({} plays, UTC)", user.username, times.len()),
+            counts,
+        })
+        .await?;
+    msg.channel_id
+        .send_message(
+            &ctx,
+            CreateMessage::new()
+                .reference_message(msg)
+                .add_file(CreateAttachment::bytes(png, "heatmap.png")),
+        )
+        .await?;
+    Ok(())
+}
+
+#[command("card")]
+#[aliases("profile")]
+#[description = "Get a shareable profile card of an user, with their ranks, top play and rank history."]
+#[usage = "[--mode (std, taiko, catch, mania) = std] / [username or user id = your saved user id], in any order"]
+#[example = "--mode taiko natsukagami"]
+#[delimiters("/", " ")]
+#[max_args(3)]
+#[bucket("heavy")]
+pub async fn card(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let Some(renderer) = env.renderer.clone() else {
+        msg.reply(&ctx, "Image rendering is not enabled on this bot.")
+            .await?;
+        return Ok(());
+    };
+    let mut opts = Options::parse(args, OPTIONS)?;
+    let mode = opts.get::<ModeArg>("mode")?.map_or(Mode::Std, |m| m.0);
+    let user = opts.take::<UsernameArg>();
+    opts.finish()?;
+    let user = to_user_id_query(user, &env, msg).await?;
+    let user = env
+        .users
+        .get(user, Some(mode))
+        .await?
+        .ok_or_else(|| Error::msg("User not found"))?;
+    let bests = env
+        .client
+        .user_best(UserID::ID(user.id), |f| f.mode(mode).limit(100))
+        .await?;
+    let map_length = env
+        .map_lengths
+        .get(user.id, &bests, &env.beatmaps, mode)
+        .await
+        .pls_ok();
+    env.stats_snapshots
+        .record(&user, mode, map_length)
+        .await
+        .pls_ok();
+    let top_play = match bests.first() {
+        Some(s) => {
+            let b = env.beatmaps.get_beatmap(s.beatmap_id, mode).await?;
+            Some(ProfileTopPlay {
+                beatmap: format!("{} - {} [{}]", b.artist, b.title, b.difficulty_name),
+                mods: s.mods.to_string(),
+                pp: s.pp,
+            })
+        }
+        None => None,
+    };
+
+    let png = renderer
+        .profile_card(ProfileCard::new(&user, mode, map_length, top_play))
+        .await?;
+    msg.channel_id
+        .send_message(
+            &ctx,
+            CreateMessage::new()
+                .reference_message(msg)
+                .content(user.link())
+                .add_file(CreateAttachment::bytes(png, "profile.png")),
+        )
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use std::{
+    collections::HashSet,
+    future::Future,
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::mpsc;
+
+use youmubot_prelude::*;
+
+/// How many refreshes can be waiting at once. Further ones are dropped.
+const QUEUE_SIZE: usize = 256;
+
+/// A queue of background refreshes of cached data.
+/// Each key is only queued once until it is refreshed.
+#[derive(Debug)]
+pub(crate) struct RefreshQueue<K> {
+    send: mpsc::Sender<K>,
+    pending: Arc<Mutex<HashSet<K>>>,
+}
+
+/// The receiving end of a [RefreshQueue], running the refreshes one by one.
+pub(crate) struct RefreshWorker<K> {
+    recv: mpsc::Receiver<K>,
+    pending: Arc<Mutex<HashSet<K>>>,
+}
+
+impl<K: Hash + Eq + Clone + Send + 'static> RefreshQueue<K> {
+    /// Create a queue and its worker.
+    pub fn new() -> (Self, RefreshWorker<K>) {
+        let (send, recv) = mpsc::channel(QUEUE_SIZE);
+        let pending = Arc::new(Mutex::new(HashSet::new()));
+        (
+            Self {
+                send,
+                pending: pending.clone(),
+            },
+            RefreshWorker { recv, pending },
+        )
+    }
+
+    /// Queue a refresh of `key`, unless one is already queued.
+    pub fn push(&self, key: K) {
+        let mut pending = self.pending.lock().unwrap();
+        if !pending.insert(key.clone()) {
+            return;
+        }
+        if self.send.try_send(key.clone()).is_err() {
+            // The queue is full, we'll try again on the next lookup.
+            pending.remove(&key);
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone + Send + 'static> RefreshWorker<K> {
+    /// Run `refresh` on every queued key. Returns when the queue is dropped.
+    pub async fn run<F, Fut>(mut self, refresh: F)
+    where
+        F: Fn(K) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        while let Some(key) = self.recv.recv().await {
+            refresh(key.clone()).await.pls_ok();
+            self.pending.lock().unwrap().remove(&key);
+        }
+    }
+}
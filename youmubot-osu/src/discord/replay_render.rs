@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serenity::{
+    builder::EditMessage,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use youmubot_prelude::*;
+
+use crate::{
+    models::Mode,
+    ordr::{self, RenderStatus},
+};
+
+use super::OsuEnv;
+
+lazy_static! {
+    pub(crate) static ref SCORE_LINK_REGEX: Regex = Regex::new(
+        r"(?:https?://)?osu\.ppy\.sh/scores/(?:(?P<mode>osu|taiko|fruits|mania)/)?(?P<id>\d+)"
+    )
+    .unwrap();
+}
+
+/// How often to check on a render.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// How long to wait for a render before giving up.
+const POLL_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// Replays larger than this are not accepted.
+const MAX_REPLAY_SIZE: u32 = 2 * 1024 * 1024;
+
+#[command("render")]
+#[description = "Render a replay into a video with o!rdr. \
+                 Give a score link, or attach an `.osr` file to the message."]
+#[usage = "[score link, or attach a replay]"]
+#[example = "https://osu.ppy.sh/scores/osu/4222871012"]
+#[max_args(1)]
+#[bucket("heavy-guild")]
+pub async fn render(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let replay = if let Some(a) = msg
+        .attachments
+        .iter()
+        .find(|a| a.filename.ends_with(".osr"))
+    {
+        if a.size > MAX_REPLAY_SIZE {
+            bail!("the replay is too large");
+        }
+        a.download().await?
+    } else {
+        let caps = args
+            .remains()
+            .and_then(|s| SCORE_LINK_REGEX.captures(s))
+            .ok_or_else(|| Error::msg("give me a score link or attach an `.osr` file!"))?;
+        let mode = caps
+            .name("mode")
+            .and_then(|m| Mode::parse_from_new_site(m.as_str()))
+            .unwrap_or(Mode::Std);
+        let score_id: u64 = caps["id"].parse()?;
+        env.client
+            .replay_raw(score_id, mode)
+            .await?
+            .ok_or_else(|| Error::msg("this score has no replay available"))?
+    };
+
+    let render_id = env.ordr.submit(replay, msg.author.name.clone()).await?;
+    let reply = msg
+        .reply(
+            &ctx,
+            format!(
+                "🎬 Replay submitted to o!rdr (render `{}`), in queue...",
+                render_id
+            ),
+        )
+        .await?;
+
+    let ctx = ctx.clone();
+    let ordr = env.ordr.clone();
+    spawn_future(async move {
+        track_render(&ctx, &ordr, render_id, reply).await.pls_ok();
+    });
+    Ok(())
+}
+
+/// Poll the render until it finishes, keeping the reply updated with its progress.
+async fn track_render(
+    ctx: &Context,
+    ordr: &ordr::Client,
+    render_id: u64,
+    mut reply: Message,
+) -> Result<()> {
+    let started = std::time::Instant::now();
+    let mut last_progress = String::new();
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let content = match ordr.status(render_id).await {
+            Ok(RenderStatus::InProgress(progress)) => {
+                if started.elapsed() > POLL_TIMEOUT {
+                    format!(
+                        "⌛ Render `{}` is taking too long, gave up waiting. Last status: {}",
+                        render_id, progress
+                    )
+                } else {
+                    if progress != last_progress {
+                        reply
+                            .edit(
+                                ctx,
+                                EditMessage::new().content(format!(
+                                    "🎬 Rendering replay with o!rdr (render `{}`): {}",
+                                    render_id, progress
+                                )),
+                            )
+                            .await?;
+                        last_progress = progress;
+                    }
+                    continue;
+                }
+            }
+            Ok(RenderStatus::Done(url)) => format!("✅ Render `{}` is ready: {}", render_id, url),
+            Ok(RenderStatus::Failed(reason)) => {
+                format!("❌ Render `{}` failed: {}", render_id, reason)
+            }
+            Err(e) => {
+                tracing::warn!("o!rdr: cannot check render {}: {}", render_id, e);
+                if started.elapsed() > POLL_TIMEOUT {
+                    format!("❌ Lost track of render `{}`.", render_id)
+                } else {
+                    continue;
+                }
+            }
+        };
+        reply.edit(ctx, EditMessage::new().content(content)).await?;
+        return Ok(());
+    }
+}
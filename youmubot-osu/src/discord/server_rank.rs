@@ -1,9 +1,11 @@
 use std::{collections::HashMap, str::FromStr, sync::Arc};
 
+use chrono::{DateTime, Utc};
 use serenity::{
-    builder::EditMessage,
+    builder::{CreateAttachment, CreateMessage, EditMessage},
     framework::standard::{macros::command, Args, CommandResult},
-    model::channel::Message,
+    http::CacheHttp,
+    model::{channel::Message, id::GuildId},
     utils::MessageBuilder,
 };
 
@@ -16,21 +18,22 @@ use youmubot_prelude::{
 
 use crate::{
     discord::{display::ScoreListStyle, oppai_cache::Accuracy},
-    models::{Mode, Mods},
+    models::{DiscordTimestamp, Mode, Mods},
+    render::{Leaderboard, LeaderboardEntry, Renderer},
     request::UserID,
 };
 
-use super::{ModeArg, OsuEnv};
+use super::{db::OsuUser, ModeArg, OsuEnv, OPTIONS};
 
 #[derive(Debug, Clone, Copy)]
-enum RankQuery {
+pub(super) enum RankQuery {
     Total,
     MapLength,
     Mode(Mode),
 }
 
 impl FromStr for RankQuery {
-    type Err = <ModeArg as FromStr>::Err;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
@@ -41,18 +44,92 @@ impl FromStr for RankQuery {
     }
 }
 
+impl RankQuery {
+    pub(super) fn name(&self) -> String {
+        match self {
+            RankQuery::Total => "total pp".to_owned(),
+            RankQuery::MapLength => "weighted map length".to_owned(),
+            RankQuery::Mode(m) => m.to_string(),
+        }
+    }
+}
+
+/// How many users to show on a leaderboard image.
+const IMAGE_ROWS: usize = 20;
+
 #[command("ranks")]
-#[description = "See the server's ranks"]
-#[usage = "[mode (Std, Taiko, Catch, Mania) = Std]"]
-#[max_args(1)]
+#[description = "See the server's ranks. Add `--image` to get the top 20 as an image instead."]
+#[usage = "[mode (Std, Taiko, Catch, Mania) = Std] / [--image]"]
+#[max_args(2)]
 #[only_in(guilds)]
+#[bucket("heavy-channel")]
 pub async fn server_rank(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
     let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
-    let mode = args
-        .single::<RankQuery>()
-        .unwrap_or(RankQuery::Mode(Mode::Std));
+    let mut mode = RankQuery::Mode(Mode::Std);
+    let mut as_image = false;
+    for arg in args.iter::<String>().filter_map(|v| v.ok()) {
+        if arg == "--image" {
+            as_image = true;
+        } else if let Ok(q) = arg.parse() {
+            mode = q;
+        }
+    }
     let guild = m.guild_id.expect("Guild-only command");
 
+    let Some((users, last_update)) = ranks(&env, ctx, guild, mode).await? else {
+        m.reply(&ctx, "No saved users in the current server...")
+            .await?;
+        return Ok(());
+    };
+
+    if as_image {
+        return send_leaderboard_image(ctx, &env, m, mode, &users, last_update).await;
+    }
+
+    let users = Arc::new(users);
+    paginate_reply_fn(
+        move |page: u8, ctx: &Context, m: &mut Message| {
+            const ITEMS_PER_PAGE: usize = 10;
+            let users = users.clone();
+            Box::pin(async move {
+                let start = (page as usize) * ITEMS_PER_PAGE;
+                let end = (start + ITEMS_PER_PAGE).min(users.len());
+                if start >= end {
+                    return Ok(false);
+                }
+                let content = MessageBuilder::new()
+                    .push_line(ranks_table(mode, &users[start..end], start))
+                    .push_line(format!(
+                        "Page **{}**/**{}**. Last updated: {}",
+                        page + 1,
+                        (users.len() + ITEMS_PER_PAGE - 1) / ITEMS_PER_PAGE,
+                        last_update.relative_time(),
+                    ))
+                    .build();
+                m.edit(ctx, EditMessage::new().content(content)).await?;
+                Ok(true)
+            })
+        },
+        ctx,
+        m,
+        std::time::Duration::from_secs(60),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// The saved users of a server with their member names, best first.
+pub(super) type Ranks = Vec<(f64, (String, OsuUser))>;
+
+/// Rank the saved users of the guild.
+/// Also returns the oldest update time of their data, or `None` if no members are saved users.
+pub(super) async fn ranks(
+    env: &OsuEnv,
+    cache_http: impl CacheHttp,
+    guild: GuildId,
+    mode: RankQuery,
+) -> Result<Option<(Ranks, DateTime<Utc>)>> {
     let osu_users = env
         .saved_users
         .all()
@@ -64,7 +141,7 @@ pub async fn server_rank(ctx: &Context, m: &Message, mut args: Args) -> CommandR
     let users = env
         .prelude
         .members
-        .query_members(&ctx, guild)
+        .query_members(cache_http, guild)
         .await?
         .iter()
         .filter_map(|m| osu_users.get(&m.user.id).map(|ou| (m, ou)))
@@ -80,7 +157,9 @@ pub async fn server_rank(ctx: &Context, m: &Message, mut args: Args) -> CommandR
             Some((pp, member.user.name.clone(), osu_user))
         })
         .collect::<Vec<_>>();
-    let last_update = users.iter().map(|(_, _, a)| a.last_update).min();
+    let Some(last_update) = users.iter().map(|(_, _, a)| a.last_update).min() else {
+        return Ok(None);
+    };
     let mut users = users
         .into_iter()
         .map(|(a, b, u)| (a, (b, u.clone())))
@@ -94,96 +173,167 @@ pub async fn server_rank(ctx: &Context, m: &Message, mut args: Args) -> CommandR
     } else {
         users.sort_by(|(a, _), (b, _)| (*b).partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
     }
+    Ok(Some((users, last_update)))
+}
 
-    if users.is_empty() {
-        m.reply(&ctx, "No saved users in the current server...")
-            .await?;
-        return Ok(());
-    }
+/// Format a slice of the ranks as a table, `start` being the index of its first row.
+pub(super) fn ranks_table(
+    mode: RankQuery,
+    users: &[(f64, (String, OsuUser))],
+    start: usize,
+) -> String {
+    if matches!(mode, RankQuery::Mode(Mode::Std) | RankQuery::MapLength) {
+        const HEADERS: [&str; 5] = ["#", "pp", "Map length", "Username", "Member"];
+        const ALIGNS: [Align; 5] = [Right, Right, Right, Left, Left];
+
+        let table = users
+            .iter()
+            .enumerate()
+            .map(|(i, (pp, (mem, ou)))| {
+                [
+                    format!("{}", 1 + i + start),
+                    format!("{:.2}", pp),
+                    format_map_length(ou.std_weighted_map_length),
+                    ou.username.clone().into_owned(),
+                    mem.clone(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        table_formatting(&HEADERS, &ALIGNS, table)
+    } else {
+        const HEADERS: [&str; 4] = ["#", "pp", "Username", "Member"];
+        const ALIGNS: [Align; 4] = [Right, Right, Left, Left];
 
-    let users = Arc::new(users);
-    let last_update = last_update.unwrap();
-    paginate_reply_fn(
-        move |page: u8, ctx: &Context, m: &mut Message| {
-            use Align::*;
-            const ITEMS_PER_PAGE: usize = 10;
-            let users = users.clone();
-            Box::pin(async move {
-                let start = (page as usize) * ITEMS_PER_PAGE;
-                let end = (start + ITEMS_PER_PAGE).min(users.len());
-                if start >= end {
-                    return Ok(false);
-                }
-                let total_len = users.len();
-                let users = &users[start..end];
-                let table = if matches!(mode, RankQuery::Mode(Mode::Std) | RankQuery::MapLength) {
-                    const HEADERS: [&'static str; 5] =
-                        ["#", "pp", "Map length", "Username", "Member"];
-                    const ALIGNS: [Align; 5] = [Right, Right, Right, Left, Left];
-
-                    let table = users
-                        .iter()
-                        .enumerate()
-                        .map(|(i, (pp, (mem, ou)))| {
-                            let map_length = match ou.std_weighted_map_length {
-                                Some(len) => {
-                                    let trunc_secs = len.floor() as u64;
-                                    let minutes = trunc_secs / 60;
-                                    let seconds = len - (60 * minutes) as f64;
-                                    format!("{}m{:05.2}s", minutes, seconds)
-                                }
-                                None => "unknown".to_owned(),
-                            };
-                            [
-                                format!("{}", 1 + i + start),
-                                format!("{:.2}", pp),
-                                map_length,
-                                ou.username.clone().into_owned(),
-                                mem.clone(),
-                            ]
-                        })
-                        .collect::<Vec<_>>();
-                    table_formatting(&HEADERS, &ALIGNS, table)
-                } else {
-                    const HEADERS: [&'static str; 4] = ["#", "pp", "Username", "Member"];
-                    const ALIGNS: [Align; 4] = [Right, Right, Left, Left];
-
-                    let table = users
-                        .iter()
-                        .enumerate()
-                        .map(|(i, (pp, (mem, ou)))| {
-                            [
-                                format!("{}", 1 + i + start),
-                                format!("{:.2}", pp),
-                                ou.username.clone().into_owned(),
-                                mem.clone(),
-                            ]
-                        })
-                        .collect::<Vec<_>>();
-                    table_formatting(&HEADERS, &ALIGNS, table)
-                };
-                let content = MessageBuilder::new()
-                    .push_line(table)
-                    .push_line(format!(
-                        "Page **{}**/**{}**. Last updated: {}",
-                        page + 1,
-                        (total_len + ITEMS_PER_PAGE - 1) / ITEMS_PER_PAGE,
-                        last_update.format("<t:%s:R>"),
-                    ))
-                    .build();
-                m.edit(ctx, EditMessage::new().content(content)).await?;
-                Ok(true)
+        let table = users
+            .iter()
+            .enumerate()
+            .map(|(i, (pp, (mem, ou)))| {
+                [
+                    format!("{}", 1 + i + start),
+                    format!("{:.2}", pp),
+                    ou.username.clone().into_owned(),
+                    mem.clone(),
+                ]
             })
-        },
-        ctx,
-        m,
-        std::time::Duration::from_secs(60),
+            .collect::<Vec<_>>();
+        table_formatting(&HEADERS, &ALIGNS, table)
+    }
+}
+
+fn format_map_length(len: Option<f64>) -> String {
+    match len {
+        Some(len) => {
+            let trunc_secs = len.floor() as u64;
+            let minutes = trunc_secs / 60;
+            let seconds = len - (60 * minutes) as f64;
+            format!("{}m{:05.2}s", minutes, seconds)
+        }
+        None => "unknown".to_owned(),
+    }
+}
+
+/// Render the top of the server ranks as an image.
+async fn send_leaderboard_image(
+    ctx: &Context,
+    env: &OsuEnv,
+    m: &Message,
+    mode: RankQuery,
+    users: &[(f64, (String, OsuUser))],
+    last_update: DateTime<Utc>,
+) -> CommandResult {
+    let theme = env.embed_theme_for(m).await;
+    let Some(renderer) = env.renderer.as_ref().filter(|_| theme.show_images) else {
+        m.reply(
+            &ctx,
+            "Image rendering is not enabled here, try without `--image`.",
+        )
+        .await?;
+        return Ok(());
+    };
+    let guild_name = m
+        .guild_id
+        .and_then(|g| g.name(ctx))
+        .unwrap_or_else(|| "this server".to_owned());
+    let png = render_ranks(
+        env,
+        renderer.as_ref(),
+        mode,
+        users,
+        last_update,
+        &guild_name,
     )
     .await?;
-
+    m.channel_id
+        .send_message(
+            &ctx,
+            CreateMessage::new()
+                .reference_message(m)
+                .add_file(CreateAttachment::bytes(png, "ranks.png")),
+        )
+        .await?;
     Ok(())
 }
 
+/// Render the top [IMAGE_ROWS] of the ranks as a PNG image.
+pub(super) async fn render_ranks(
+    env: &OsuEnv,
+    renderer: &dyn Renderer,
+    mode: RankQuery,
+    users: &[(f64, (String, OsuUser))],
+    last_update: DateTime<Utc>,
+    guild_name: &str,
+) -> Result<Vec<u8>> {
+    let osu_mode = match mode {
+        RankQuery::Mode(m) => m,
+        _ => Mode::Std,
+    };
+    let entries = users
+        .iter()
+        .take(IMAGE_ROWS)
+        .enumerate()
+        .map(|(i, (pp, (member, ou)))| async move {
+            let country = env
+                .users
+                .get(UserID::ID(ou.id), Some(osu_mode))
+                .await
+                .pls_ok()
+                .flatten()
+                .map(|u| u.country);
+            let pp = format!("{:.2}pp", pp);
+            let map_length = format_map_length(ou.std_weighted_map_length);
+            let (value, extra) = match mode {
+                RankQuery::MapLength => (map_length, Some(pp)),
+                RankQuery::Mode(Mode::Std) => (pp, Some(map_length)),
+                _ => (pp, None),
+            };
+            LeaderboardEntry {
+                rank: i + 1,
+                username: ou.username.clone().into_owned(),
+                member: member.clone(),
+                value,
+                extra,
+                avatar_url: format!("https://a.ppy.sh/{}", ou.id),
+                flag_url: country.map(|c| format!("https://osu.ppy.sh/images/flags/{}.png", c)),
+            }
+        })
+        .collect::<stream::FuturesOrdered<_>>()
+        .collect::<Vec<_>>()
+        .await;
+    renderer
+        .leaderboard(Leaderboard {
+            title: format!("osu! ranks of {}", guild_name),
+            subtitle: format!(
+                "{} • top {} of {} • last updated {}",
+                mode.name(),
+                entries.len(),
+                users.len(),
+                last_update.format("%Y-%m-%d %H:%M UTC")
+            ),
+            entries,
+        })
+        .await
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum OrderBy {
     PP,
@@ -200,29 +350,33 @@ impl FromStr for OrderBy {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "--score" => Ok(OrderBy::Score),
-            "--pp" => Ok(OrderBy::PP),
-            _ => Err(Error::msg("unknown value")),
-        }
+        args::keyword(
+            "order",
+            s,
+            &[(&["--pp"], OrderBy::PP), (&["--score"], OrderBy::Score)],
+        )
     }
 }
 
 #[command("leaderboard")]
 #[aliases("lb", "bmranks", "br", "cc", "updatelb")]
-#[usage = "[--score to sort by score, default to sort by pp] / [--table to show a table, --grid to show score by score] / [mods to filter]"]
+#[usage = "[--score to sort by score, default to sort by pp] / [--style (table, or grid to show score by score) = table] / [--mods to filter], in any order"]
 #[description = "See the server's ranks on the last seen beatmap"]
-#[max_args(2)]
+#[max_args(5)]
 #[only_in(guilds)]
-pub async fn show_leaderboard(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
-    let order = args.single::<OrderBy>().unwrap_or_default();
-    let style = args.single::<ScoreListStyle>().unwrap_or_default();
+#[bucket("heavy-channel")]
+pub async fn show_leaderboard(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let mut opts = Options::parse(args, OPTIONS)?;
+    let order = opts.take::<OrderBy>().unwrap_or_default();
+    let style = opts.get::<ScoreListStyle>("style")?.unwrap_or_default();
+    let mods = opts.get::<Mods>("mods")?;
+    opts.finish()?;
 
     let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
 
-    let (bm, _) = match super::load_beatmap(&env, msg).await {
-        Some((bm, mods_def)) => {
-            let mods = args.find::<Mods>().ok().or(mods_def).unwrap_or(Mods::NOMOD);
+    let (bm, _) = match super::load_beatmap(&env, msg, 0).await {
+        Some((bm, annotations)) => {
+            let mods = mods.or(annotations.mods).unwrap_or(Mods::NOMOD);
             (bm, mods)
         }
         None => {
@@ -329,6 +483,8 @@ pub async fn show_leaderboard(ctx: &Context, msg: &Message, mut args: Args) -> C
             .display_scores(
                 scores.into_iter().map(|(_, _, a)| a).collect(),
                 mode,
+                false,
+                env.client.clone(),
                 ctx,
                 msg,
             )
@@ -373,7 +529,7 @@ pub async fn show_leaderboard(ctx: &Context, msg: &Message, mut args: Args) -> C
                                     })
                                 }
                             },
-                            score.mods.to_string(),
+                            score.mods_text(),
                             score.rank.to_string(),
                             format!("{:.2}%", score.accuracy(bm.1)),
                             format!("{}x", score.max_combo),
@@ -0,0 +1,109 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use serenity::{
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+};
+
+use youmubot_prelude::settings::OsuServerConfig;
+use youmubot_prelude::*;
+
+use crate::{request::UserID, server::ServerProfile, Client as OsuHttpClient};
+
+use super::{i18n, OsuEnv};
+
+/// How many requests are sent to each private server per minute, at most.
+const SERVER_RATE_LIMIT: u32 = 60;
+
+/// The clients of the built-in and the configured private servers, by name.
+/// A configured server replaces the built-in one of the same name.
+pub(crate) fn clients(
+    osu: &OsuHttpClient,
+    configured: &BTreeMap<String, OsuServerConfig>,
+) -> Result<BTreeMap<String, Arc<OsuHttpClient>>> {
+    let mut profiles = ServerProfile::builtin()
+        .into_iter()
+        .map(|p| (p.name.clone(), p))
+        .collect::<BTreeMap<_, _>>();
+    for (name, server) in configured {
+        let name = name.to_lowercase();
+        for (field, value) in [
+            ("api_url", &server.api_url),
+            ("website", &server.website),
+            ("avatars", &server.avatars),
+        ] {
+            if value.is_empty() {
+                bail!("osu.servers.{}.{} is not set", name, field);
+            }
+        }
+        let profile = ServerProfile {
+            name: name.clone(),
+            api_url: server.api_url.trim_end_matches('/').to_owned(),
+            website: server.website.trim_end_matches('/').to_owned(),
+            avatars: server.avatars.trim_end_matches('/').to_owned(),
+            api_key: server.api_key.clone(),
+        };
+        profiles.insert(name, profile);
+    }
+    Ok(profiles
+        .into_iter()
+        .map(|(name, profile)| {
+            let client = osu.with_server(Arc::new(profile), SERVER_RATE_LIMIT);
+            (name, Arc::new(client))
+        })
+        .collect())
+}
+
+impl OsuEnv {
+    /// The client of the private server with the given name.
+    pub(crate) fn server(&self, name: &str) -> Result<Arc<OsuHttpClient>> {
+        self.servers
+            .get(&name.to_lowercase())
+            .cloned()
+            .ok_or_else(|| {
+                error!(
+                    "unknown server `{}`, available: {}",
+                    name,
+                    self.servers.keys().cloned().collect::<Vec<_>>().join(", ")
+                )
+            })
+    }
+}
+
+/// The user to look up on a private server: the given username, or else the username saved on osu!,
+/// as the ids of osu! are not the ones of the server.
+pub(crate) async fn server_user_query(
+    user: Option<UsernameArg>,
+    env: &OsuEnv,
+    msg: &Message,
+) -> Result<UserID> {
+    let id = match user {
+        Some(UsernameArg::Raw(s)) => return Ok(UserID::Username(s)),
+        Some(UsernameArg::Tagged(r)) => r,
+        None => msg.author.id,
+    };
+    match env.saved_users.by_user_id(id).await? {
+        Some(u) => Ok(UserID::Username(u.username.into_owned())),
+        None => Err(Error::msg(i18n::tr(
+            env.locale_for(msg).await,
+            "no-saved-account",
+        ))),
+    }
+}
+
+#[command("servers")]
+#[description = "List the private servers that can be picked with `--server` in `recent` and `top`."]
+#[max_args(0)]
+pub async fn servers(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let theme = env.embed_theme_for(msg).await;
+    let names = env
+        .servers
+        .keys()
+        .map(|n| format!("`{}`", n))
+        .collect::<Vec<_>>()
+        .join(", ");
+    msg.reply(&ctx, theme.trf("servers-list", &[("servers", &names)]))
+        .await?;
+    Ok(())
+}
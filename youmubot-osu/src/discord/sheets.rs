@@ -0,0 +1,162 @@
+use serde_json::Value;
+use serenity::{
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandResult,
+    },
+    model::{channel::Message, id::GuildId},
+};
+
+use youmubot_db_sql::models::osu as models;
+use youmubot_prelude::permissions::MANAGE_GUILD_CHECK;
+use youmubot_prelude::*;
+
+use crate::sheets::{self, ServiceAccount};
+
+use super::OsuEnv;
+
+/// The largest service account key read. They are about 2KB.
+const MAX_KEY_SIZE: u32 = 16 * 1024;
+
+#[group]
+#[prefix = "sheet"]
+#[description = "The Google Sheet that `export` pushes to with `--sheet`, written by a Google Cloud service account."]
+#[commands(sheet_status, sheet_set, sheet_remove)]
+#[default_command(sheet_status)]
+struct OsuExportSheet;
+
+#[command("status")]
+#[description = "Show the Google Sheet of this server, and the account writing to it."]
+#[only_in(guilds)]
+#[max_args(0)]
+pub async fn sheet_status(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let theme = env.embed_theme_for(msg).await;
+    let guild = msg.guild_id.unwrap().get() as i64;
+    let reply = match models::GoogleSheet::by_guild(guild, &env.prelude.sql).await? {
+        Some(sheet) => {
+            let account = ServiceAccount::parse(&sheet.credentials)?;
+            theme.trf(
+                "sheet-status",
+                &[
+                    ("link", &spreadsheet_link(&sheet.spreadsheet_id)),
+                    ("account", &account.client_email),
+                ],
+            )
+        }
+        None => theme.tr("sheet-not-set").to_owned(),
+    };
+    msg.reply(&ctx, reply).await?;
+    Ok(())
+}
+
+#[command("set")]
+#[description = "Push the exports of this server to a Google Sheet. \
+                 Attach the JSON key of a Google Cloud service account, and share the sheet with the account as an editor. \
+                 The message with the key is deleted once it is read."]
+#[usage = "[link to the spreadsheet, or its id] + the key as an attachment"]
+#[checks(manage_guild)]
+#[only_in(guilds)]
+#[num_args(1)]
+#[bucket("heavy")]
+pub async fn sheet_set(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let theme = env.embed_theme_for(msg).await;
+    let guild = msg.guild_id.unwrap();
+    let Some(spreadsheet_id) = sheets::spreadsheet_id(args.rest().trim()) else {
+        return Err(Error::msg(theme.tr("sheet-invalid-link")).into());
+    };
+    let Some(attachment) = msg.attachments.first().filter(|a| a.size <= MAX_KEY_SIZE) else {
+        msg.reply(&ctx, theme.tr("sheet-no-key")).await?;
+        return Ok(());
+    };
+    let credentials = String::from_utf8(attachment.download().await?)
+        .map_err(|_| Error::msg(theme.tr("sheet-no-key")))?;
+    // The key is a secret, so it should not stay in the channel.
+    let key_deleted = match msg.delete(&ctx).await {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!(
+                guild = guild.get(),
+                "osu: cannot delete a service account key: {}",
+                e
+            );
+            msg.reply(&ctx, theme.tr("sheet-key-not-deleted")).await?;
+            false
+        }
+    };
+
+    let account = ServiceAccount::parse(&credentials)?;
+    let client = sheets::Client::sign_in(env.prelude.http.clone(), &account).await?;
+    client.check_access(spreadsheet_id).await.map_err(|e| {
+        error!(
+            "{}\n{}",
+            theme.trf("sheet-no-access", &[("account", &account.client_email)]),
+            e
+        )
+    })?;
+    models::GoogleSheet {
+        guild_id: guild.get() as i64,
+        spreadsheet_id: spreadsheet_id.to_owned(),
+        credentials,
+    }
+    .store(&env.prelude.sql)
+    .await?;
+
+    msg.channel_id
+        .say(
+            &ctx,
+            theme.trf(
+                if key_deleted {
+                    "sheet-set"
+                } else {
+                    "sheet-set-key-kept"
+                },
+                &[
+                    ("user", &msg.author.mention()),
+                    ("link", &spreadsheet_link(spreadsheet_id)),
+                ],
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+#[command("remove")]
+#[description = "Stop pushing the exports of this server to its Google Sheet, and forget the service account key."]
+#[checks(manage_guild)]
+#[only_in(guilds)]
+#[max_args(0)]
+pub async fn sheet_remove(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let theme = env.embed_theme_for(msg).await;
+    let guild = msg.guild_id.unwrap().get() as i64;
+    let reply = if models::GoogleSheet::delete(guild, &env.prelude.sql).await? {
+        "sheet-removed"
+    } else {
+        "sheet-not-set"
+    };
+    msg.reply(&ctx, theme.tr(reply)).await?;
+    Ok(())
+}
+
+fn spreadsheet_link(id: &str) -> String {
+    format!("<https://docs.google.com/spreadsheets/d/{}>", id)
+}
+
+/// Replace the tab of the guild's sheet with the rows. Returns the link to the tab.
+pub(super) async fn push(
+    env: &OsuEnv,
+    guild: GuildId,
+    tab: &str,
+    rows: Vec<Vec<Value>>,
+) -> Result<String> {
+    let theme = env.embed_theme(Some(guild)).await;
+    let Some(sheet) = models::GoogleSheet::by_guild(guild.get() as i64, &env.prelude.sql).await?
+    else {
+        bail!("{}", theme.tr("sheet-not-set"));
+    };
+    let account = ServiceAccount::parse(&sheet.credentials)?;
+    let client = sheets::Client::sign_in(env.prelude.http.clone(), &account).await?;
+    client.write_tab(&sheet.spreadsheet_id, tab, rows).await
+}
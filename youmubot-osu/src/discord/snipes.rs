@@ -0,0 +1,400 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+
+use chrono::{DateTime, Utc};
+use serenity::{
+    builder::{CreateEmbed, CreateMessage, EditMessage},
+    framework::standard::{macros::command, Args, CommandResult},
+    model::{
+        channel::Message,
+        id::{ChannelId, UserId},
+    },
+    utils::MessageBuilder,
+};
+
+use announcer::MemberToChannels;
+use youmubot_prelude::announcer::CacheAndHttp;
+use youmubot_prelude::table_format::{table_formatting, Align};
+use youmubot_prelude::*;
+
+use crate::{
+    huismetbenen::{self, Snipe},
+    models::{DiscordTimestamp, Mode, User},
+    request::UserID,
+};
+
+use super::{embeds::grouped_number, to_user_id_query, ModeArg, OsuEnv, OPTIONS};
+
+/// The snipe announcer's unique announcer key.
+pub const SNIPE_ANNOUNCER_KEY: &str = "osu-snipes";
+
+/// How many country #1s are shown per page.
+const FIRSTS_PER_PAGE: usize = 10;
+/// The longest beatmap title shown in the list of country #1s.
+const MAX_TITLE_LEN: usize = 40;
+
+/// Find the user and check that huismetbenen tracks their country.
+/// Replies and returns `None` if it does not.
+async fn tracked_user(
+    ctx: &Context,
+    msg: &Message,
+    env: &OsuEnv,
+    args: Args,
+) -> Result<Option<(User, Mode)>> {
+    let mut opts = Options::parse(args, OPTIONS)?;
+    let mode = opts.get::<ModeArg>("mode")?.map_or(Mode::Std, |m| m.0);
+    let user = opts.take::<UsernameArg>();
+    opts.finish()?;
+    let user = to_user_id_query(user, env, msg).await?;
+    let user = env
+        .users
+        .get(user, Some(mode))
+        .await?
+        .ok_or_else(|| Error::msg("User not found"))?;
+    if !env.huismetbenen.is_tracked(&user.country).await? {
+        let theme = env.embed_theme_for(msg).await;
+        msg.reply(
+            &ctx,
+            theme.trf("snipes-untracked-country", &[("country", &user.country)]),
+        )
+        .await?;
+        return Ok(None);
+    }
+    Ok(Some((user, mode)))
+}
+
+/// The huismetbenen page of the player.
+fn player_url(user: &User, mode: Mode) -> String {
+    format!(
+        "https://snipe.huismetbenen.nl/player/{}/{}/{}",
+        user.country.to_lowercase(),
+        mode.as_str_new_site(),
+        user.id
+    )
+}
+
+#[command("snipes")]
+#[description = "Show an user's country #1s statistics, from huismetbenen. Only some countries are tracked."]
+#[usage = "[--mode (std, taiko, catch, mania) = std] / [username or user id = your saved user id], in any order"]
+#[example = "--mode taiko natsukagami"]
+#[delimiters("/", " ")]
+#[max_args(3)]
+#[bucket("heavy")]
+pub async fn snipes(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let Some((user, mode)) = tracked_user(ctx, msg, &env, args).await? else {
+        return Ok(());
+    };
+    let theme = env.embed_theme_for(msg).await;
+    let player = env
+        .huismetbenen
+        .player(&user.country, user.id, mode)
+        .await?
+        .filter(|p| p.count > 0);
+    let Some(player) = player else {
+        msg.reply(
+            &ctx,
+            theme.trf(
+                "snipes-none",
+                &[("user", &user.username), ("country", &user.country)],
+            ),
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let average = |v: Option<f64>, f: fn(f64) -> String| v.map(f).unwrap_or_else(|| "-".to_owned());
+    let embed = CreateEmbed::new()
+        .title(
+            MessageBuilder::new()
+                .push_safe(&user.username)
+                .push(format!(" ({})", mode.as_str_new_site()))
+                .build(),
+        )
+        .url(player_url(&user, mode))
+        .color(theme.color)
+        .field(
+            theme.trf("snipes-count", &[("country", &user.country)]),
+            match player.rank {
+                Some(rank) => format!("**{}** (#{})", grouped_number(player.count), rank),
+                None => format!("**{}**", grouped_number(player.count)),
+            },
+            false,
+        )
+        .field(
+            theme.tr("snipes-average-pp"),
+            average(player.average_pp, |v| format!("{:.2}pp", v)),
+            true,
+        )
+        .field(
+            theme.tr("user-accuracy"),
+            average(player.average_accuracy, |v| format!("{:.2}%", v)),
+            true,
+        )
+        .field(
+            theme.tr("snipes-average-stars"),
+            average(player.average_stars, |v| format!("{:.2}⭐", v)),
+            true,
+        );
+    let embed = theme.thumbnail(embed, format!("https://a.ppy.sh/{}", user.id));
+    msg.channel_id
+        .send_message(
+            &ctx,
+            CreateMessage::new().reference_message(msg).embed(embed),
+        )
+        .await?;
+    Ok(())
+}
+
+#[command("snipelist")]
+#[description = "List an user's country #1s, best pp first, from huismetbenen. Only some countries are tracked."]
+#[usage = "[--mode (std, taiko, catch, mania) = std] / [username or user id = your saved user id], in any order"]
+#[example = "natsukagami"]
+#[delimiters("/", " ")]
+#[max_args(3)]
+#[bucket("heavy")]
+pub async fn snipelist(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let Some((user, mode)) = tracked_user(ctx, msg, &env, args).await? else {
+        return Ok(());
+    };
+    let total = env
+        .huismetbenen
+        .player(&user.country, user.id, mode)
+        .await?
+        .map_or(0, |p| p.count as usize);
+    if total == 0 {
+        let theme = env.embed_theme_for(msg).await;
+        msg.reply(
+            &ctx,
+            theme.trf(
+                "snipes-none",
+                &[("user", &user.username), ("country", &user.country)],
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    // The #1s fetched so far, and whether there are more to fetch.
+    let firsts = Arc::new(tokio::sync::Mutex::new((Vec::new(), 0u32, false)));
+    let client = env.huismetbenen.clone();
+    paginate_reply_fn(
+        move |page: u8, ctx: &Context, m: &mut Message| {
+            let firsts = firsts.clone();
+            let client = client.clone();
+            let user = user.clone();
+            Box::pin(async move {
+                let start = page as usize * FIRSTS_PER_PAGE;
+                let end = start + FIRSTS_PER_PAGE;
+                let mut firsts = firsts.lock().await;
+                let (list, fetched_pages, done) = &mut *firsts;
+                while list.len() < end && !*done {
+                    *fetched_pages += 1;
+                    let more = client
+                        .country_firsts(&user.country, user.id, mode, *fetched_pages)
+                        .await?;
+                    *done = more.is_empty();
+                    list.extend(more);
+                }
+                if start >= list.len() {
+                    return Ok(false);
+                }
+                const HEADERS: [&str; 6] = ["#", "pp", "Acc", "Mods", "Map", "Set"];
+                const ALIGNS: [Align; 6] = [
+                    Align::Right,
+                    Align::Right,
+                    Align::Right,
+                    Align::Left,
+                    Align::Left,
+                    Align::Left,
+                ];
+                let rows = list[start..end.min(list.len())]
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| {
+                        let mut title = f.title.clone();
+                        if title.chars().count() > MAX_TITLE_LEN {
+                            title = title.chars().take(MAX_TITLE_LEN - 1).collect::<String>() + "…";
+                        }
+                        [
+                            format!("{}", start + i + 1),
+                            f.pp.map(|v| format!("{:.2}", v)).unwrap_or_default(),
+                            f.accuracy.map(|v| format!("{:.2}%", v)).unwrap_or_default(),
+                            f.mods
+                                .clone()
+                                .filter(|m| !m.is_empty())
+                                .unwrap_or_else(|| "NM".to_owned()),
+                            title,
+                            f.date.format("%Y-%m-%d").to_string(),
+                        ]
+                    })
+                    .collect::<Vec<_>>();
+                let content = MessageBuilder::new()
+                    .push_bold_safe(&user.username)
+                    .push_line(format!("'s country #1s ({}, {})", user.country, mode))
+                    .push_line(table_formatting(&HEADERS, &ALIGNS, rows))
+                    .push_line(format!(
+                        "Page **{}**/**{}**. {}",
+                        page + 1,
+                        (total + FIRSTS_PER_PAGE - 1) / FIRSTS_PER_PAGE,
+                        player_url(&user, mode)
+                    ))
+                    .build();
+                m.edit(ctx, EditMessage::new().content(content)).await?;
+                Ok(true)
+            })
+        },
+        ctx,
+        msg,
+        std::time::Duration::from_secs(60),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Announces the osu!std snipes involving saved users, in the countries tracked by huismetbenen.
+pub struct SnipeAnnouncer {
+    client: Arc<huismetbenen::Client>,
+    /// The countries of the saved users, by osu! id.
+    countries: HashMap<u64, String>,
+    /// When each country was last checked for snipes.
+    checked: HashMap<String, DateTime<Utc>>,
+}
+
+impl SnipeAnnouncer {
+    pub fn new(client: Arc<huismetbenen::Client>) -> Self {
+        Self {
+            client,
+            countries: HashMap::new(),
+            checked: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl youmubot_prelude::Announcer for SnipeAnnouncer {
+    async fn updates(
+        &mut self,
+        c: CacheAndHttp,
+        d: AppData,
+        channels: MemberToChannels,
+    ) -> Result<()> {
+        let env = d.read().await.get::<OsuEnv>().unwrap().clone();
+        // The saved users in servers with the announcer, and their channels.
+        let mut watched = HashMap::<u64, (UserId, Vec<ChannelId>)>::new();
+        for u in env.saved_users.all().await? {
+            let user_channels = channels.channels_of(c.clone(), u.user_id).await;
+            if !user_channels.is_empty() {
+                watched.insert(u.id, (u.user_id, user_channels));
+            }
+        }
+
+        let tracked = self.client.countries().await?;
+        let mut countries = BTreeSet::new();
+        for &id in watched.keys() {
+            let country = match self.countries.get(&id) {
+                Some(country) => country.clone(),
+                None => {
+                    let Some(user) = env
+                        .users
+                        .get(UserID::ID(id), Some(Mode::Std))
+                        .await
+                        .pls_ok()
+                        .flatten()
+                    else {
+                        continue;
+                    };
+                    self.countries.insert(id, user.country.clone());
+                    user.country
+                }
+            };
+            if tracked.contains(&country.to_uppercase()) {
+                countries.insert(country);
+            }
+        }
+
+        let now = Utc::now();
+        for country in countries {
+            // Countries seen for the first time are only checked from now on.
+            let Some(since) = self.checked.insert(country.clone(), now) else {
+                continue;
+            };
+            let snipes = match self.client.snipes_since(&country, Mode::Std, since).await {
+                Ok(v) => v,
+                Err(e) => {
+                    // Check the same period again on the next sweep.
+                    self.checked.insert(country.clone(), since);
+                    tracing::warn!(country = %country, "osu: cannot fetch the snipes: {:#}", e);
+                    continue;
+                }
+            };
+            for snipe in snipes {
+                let sniper = watched.get(&snipe.sniper_id);
+                let victim = snipe.sniped_id.and_then(|id| watched.get(&id));
+                let targets = sniper
+                    .iter()
+                    .chain(victim.iter())
+                    .flat_map(|(_, channels)| channels.iter().copied())
+                    .collect::<BTreeSet<_>>();
+                for channel in targets {
+                    announce_snipe(&env, c.clone(), channel, &snipe, sniper, victim)
+                        .await
+                        .pls_ok();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Announce the snipe in the channel.
+async fn announce_snipe(
+    env: &OsuEnv,
+    c: CacheAndHttp,
+    channel: ChannelId,
+    snipe: &Snipe,
+    sniper: Option<&(UserId, Vec<ChannelId>)>,
+    victim: Option<&(UserId, Vec<ChannelId>)>,
+) -> Result<()> {
+    let guild = channel.to_channel(&c).await?.guild().map(|g| g.guild_id);
+    let theme = env.embed_theme(guild).await;
+    // Saved users are mentioned, the others are named.
+    let name = |user: Option<&(UserId, Vec<ChannelId>)>, username: &str| match user {
+        Some((user_id, _)) => user_id.mention().to_string(),
+        None => MessageBuilder::new().push_bold_safe(username).build(),
+    };
+    let map = format!(
+        "[{}](https://osu.ppy.sh/b/{})",
+        MessageBuilder::new().push_safe(&snipe.title).build(),
+        snipe.beatmap_id
+    );
+    let sniper = name(sniper, &snipe.sniper);
+    let content = match &snipe.sniped {
+        Some(sniped) => theme.trf(
+            "announce-snipe",
+            &[
+                ("sniper", &sniper),
+                ("victim", &name(victim, sniped)),
+                ("map", &map),
+            ],
+        ),
+        None => theme.trf("announce-snipe-new", &[("sniper", &sniper), ("map", &map)]),
+    };
+    let content = match snipe.pp {
+        Some(pp) => format!(
+            "{} (**{:.2}pp**, {})",
+            content,
+            pp,
+            snipe.date.relative_time()
+        ),
+        None => format!("{} ({})", content, snipe.date.relative_time()),
+    };
+    env.prelude
+        .send_queue
+        .send(c, channel, CreateMessage::new().content(content))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,323 @@
+//! Integration tests of the commands and hooks, on a mock osu! API and a mock Discord.
+use std::{path::PathBuf, time::Duration};
+
+use chrono::{DateTime, TimeZone, Utc};
+use serenity::{
+    framework::standard::{Args, Delimiter},
+    model::id::{ChannelId, UserId},
+};
+use youmubot_prelude::testing::{self, MockDiscord};
+
+use super::*;
+use crate::{
+    mock::MockApi,
+    models::{ApprovalStatus, Difficulty, Genre, Language, Rank},
+};
+
+pub(crate) const CHANNEL: ChannelId = ChannelId::new(10);
+pub(crate) const AUTHOR: UserId = UserId::new(20);
+
+/// A beatmap with three circles.
+pub(crate) const OSU_FILE: &str = "osu file format v14
+
+[General]
+AudioFilename: audio.mp3
+AudioLeadIn: 0
+PreviewTime: -1
+Countdown: 0
+SampleSet: Normal
+StackLeniency: 0.7
+Mode: 0
+
+[Editor]
+DistanceSpacing: 1
+BeatDivisor: 4
+GridSize: 32
+
+[Metadata]
+Title:Test
+Artist:Youmu
+Creator:youmubot
+Version:Normal
+BeatmapID:100
+BeatmapSetID:10
+
+[Difficulty]
+HPDrainRate:5
+CircleSize:4
+OverallDifficulty:8
+ApproachRate:9
+SliderMultiplier:1.4
+SliderTickRate:1
+
+[Events]
+
+[TimingPoints]
+0,500,4,1,0,100,1,0
+
+[HitObjects]
+256,192,1000,1,0,0:0:0:0:
+128,192,1500,1,0,0:0:0:0:
+384,192,2000,1,0,0:0:0:0:
+";
+
+/// The mocks, and an [OsuEnv] talking to them.
+pub(crate) struct TestEnv {
+    pub(crate) env: OsuEnv,
+    pub(crate) api: Arc<MockApi>,
+    pub(crate) discord: MockDiscord,
+    dir: PathBuf,
+}
+
+impl TestEnv {
+    pub(crate) async fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("youmubot-test-{}", rand::random::<u64>()));
+        let prelude = testing::env(&dir).await.unwrap();
+        let api = Arc::new(MockApi::default());
+        let client = Arc::new(api.client());
+        let sql = prelude.sql.clone();
+        let http = prelude.http.clone();
+        let settings = prelude.settings.clone();
+        let env = OsuEnv {
+            saved_users: OsuSavedUsers::new(sql.clone()),
+            last_beatmaps: OsuLastBeatmap::new(sql.clone()),
+            user_bests: OsuUserBests::new(sql.clone(), &settings.osu),
+            stats_snapshots: OsuStatsSnapshots::new(sql.clone()),
+            oppai: BeatmapCache::with_dir(http.clone(), sql.clone(), dir.join("beatmaps")).unwrap(),
+            beatmaps: BeatmapMetaCache::new(client.clone(), sql, &settings.osu),
+            users: UserCache::new(client.clone()),
+            client,
+            map_lengths: MapLengthCache::default(),
+            ordr: Arc::new(ordr::Client::new(http.clone(), None)),
+            osutrack: Arc::new(osutrack::Client::new(http.clone())),
+            huismetbenen: Arc::new(huismetbenen::Client::new(http.clone())),
+            collector: Arc::new(osucollector::Client::new(http)),
+            twitch: None,
+            servers: Arc::new(BTreeMap::new()),
+            assets: None,
+            mod_emojis: ModEmojis::default(),
+            renderer: None,
+            recent_usernames: Default::default(),
+            live_matches: Default::default(),
+            prelude,
+        };
+        let discord = MockDiscord::start().await.unwrap();
+        Self {
+            env,
+            api,
+            discord,
+            dir,
+        }
+    }
+
+    /// Add [OSU_FILE] to the API as the beatmap `id` of the beatmapset 10.
+    pub(crate) async fn add_beatmap(&self, id: u64) {
+        self.env.oppai.store_beatmap(id, OSU_FILE).await.unwrap();
+        self.api.add_beatmap(beatmap(id));
+    }
+}
+
+impl Drop for TestEnv {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+fn date() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+}
+
+fn beatmap(id: u64) -> Beatmap {
+    Beatmap {
+        approval: ApprovalStatus::Ranked(date()),
+        submit_date: date(),
+        last_update: date(),
+        download_available: true,
+        audio_available: true,
+        artist: "Youmu".to_owned(),
+        title: "Test".to_owned(),
+        beatmapset_id: 10,
+        creator: "youmubot".to_owned(),
+        creator_id: 1,
+        source: None,
+        genre: Genre::Any,
+        language: Language::Any,
+        tags: vec![],
+        beatmap_id: id,
+        difficulty_name: "Normal".to_owned(),
+        difficulty: Difficulty {
+            stars: 3.0,
+            aim: None,
+            speed: None,
+            cs: 4.0,
+            od: 8.0,
+            ar: 9.0,
+            hp: 5.0,
+            count_normal: 3,
+            count_slider: 0,
+            count_spinner: 0,
+            max_combo: Some(3),
+            bpm: 120.0,
+            drain_length: Duration::from_secs(1),
+            total_length: Duration::from_secs(2),
+        },
+        file_hash: format!("{:x}", md5::compute(OSU_FILE)),
+        mode: Mode::Std,
+        favourite_count: 0,
+        rating: 0.0,
+        play_count: 0,
+        pass_count: 0,
+    }
+}
+
+fn user(id: u64, username: &str) -> User {
+    User {
+        id,
+        username: username.to_owned(),
+        joined: date(),
+        country: "VN".to_owned(),
+        count_300: 3,
+        count_100: 0,
+        count_50: 0,
+        play_count: 1,
+        played_time: Duration::from_secs(2),
+        ranked_score: 1000,
+        total_score: 1000,
+        count_ss: 1,
+        count_ssh: 0,
+        count_s: 0,
+        count_sh: 0,
+        count_a: 0,
+        events: vec![],
+        rank: 1,
+        country_rank: 1,
+        level: 1.0,
+        pp: Some(100.0),
+        accuracy: 100.0,
+        rank_history: vec![],
+        total_hits: 3,
+        max_combo: 3,
+        replays_watched: 0,
+        badges: vec![],
+        server: None,
+    }
+}
+
+fn score(user_id: u64, beatmap_id: u64) -> Score {
+    Score {
+        id: Some(1),
+        user_id,
+        date: date(),
+        replay_available: false,
+        beatmap_id,
+        score: None,
+        normalized_score: 1_000_000,
+        pp: Some(100.0),
+        rank: Rank::SS,
+        mods: Mods::NOMOD,
+        count_300: 3,
+        count_100: 0,
+        count_50: 0,
+        count_miss: 0,
+        count_katu: 0,
+        count_geki: 0,
+        max_combo: 3,
+        perfect: true,
+        lazer_build_id: None,
+        mod_settings: Default::default(),
+        global_rank: Some(1),
+    }
+}
+
+fn recent_args(args: &str) -> RecentArgs {
+    RecentArgs::parse(Args::new(args, &[Delimiter::Single(' ')])).unwrap()
+}
+
+/// Set up "tester" with a recent play on the beatmap 100.
+async fn with_recent_play() -> TestEnv {
+    let t = TestEnv::new().await;
+    t.add_beatmap(100).await;
+    t.api.add_user(user(2, "tester"));
+    t.api.add_score(Mode::Std, score(2, 100));
+    t
+}
+
+#[tokio::test]
+async fn test_recent_nth() {
+    let t = with_recent_play().await;
+    let msg = t
+        .discord
+        .message(CHANNEL, AUTHOR, "y!recent #1 tester")
+        .unwrap();
+
+    let plays = reply_recent(
+        t.discord.cache_and_http(),
+        &t.env,
+        &msg,
+        recent_args("#1 tester"),
+    )
+    .await
+    .unwrap();
+    assert!(plays.is_none());
+    let sent = t.discord.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].channel_id, CHANNEL);
+    assert_eq!(
+        sent[0].content,
+        i18n::tr(Locale::default(), "requested-play")
+    );
+    assert_eq!(sent[0].embeds.len(), 1);
+    // The beatmap is now the last one of the channel.
+    let (last, mode) = t
+        .env
+        .last_beatmaps
+        .by_channel_nth(CHANNEL, 0)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((last.beatmap_id, mode), (100, Mode::Std));
+}
+
+#[tokio::test]
+async fn test_recent_list() {
+    let t = with_recent_play().await;
+    let msg = t
+        .discord
+        .message(CHANNEL, AUTHOR, "y!recent tester")
+        .unwrap();
+
+    let (plays, _) = reply_recent(
+        t.discord.cache_and_http(),
+        &t.env,
+        &msg,
+        recent_args("tester"),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        plays.iter().map(|s| s.beatmap_id).collect::<Vec<_>>(),
+        [100]
+    );
+    // Lists are displayed by the caller.
+    assert!(t.discord.sent().is_empty());
+}
+
+#[tokio::test]
+async fn test_recent_unknown_user() {
+    let t = with_recent_play().await;
+    let msg = t
+        .discord
+        .message(CHANNEL, AUTHOR, "y!recent #1 nobody")
+        .unwrap();
+
+    let result = reply_recent(
+        t.discord.cache_and_http(),
+        &t.env,
+        &msg,
+        recent_args("#1 nobody"),
+    )
+    .await;
+    assert!(result.is_err());
+    assert!(t.discord.sent().is_empty());
+}
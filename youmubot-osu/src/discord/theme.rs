@@ -0,0 +1,196 @@
+use std::{fmt::Display, str::FromStr};
+
+use serenity::{
+    builder::CreateEmbed,
+    framework::standard::{macros::command, Args, CommandResult},
+    model::{channel::Message, id::GuildId},
+    utils::MessageBuilder,
+};
+
+use youmubot_prelude::permissions::MANAGE_GUILD_CHECK;
+use youmubot_prelude::*;
+
+use super::{i18n, mod_emoji::ModEmojis, OsuEnv};
+
+/// The default accent color of the embeds.
+pub const DEFAULT_COLOR: u32 = 0xffb6c1;
+
+/// The accent color of the osu! embeds in the guild.
+pub const EMBED_COLOR: ConfigKey<EmbedColor> = ConfigKey::new(
+    "embed_color",
+    "the accent color of the osu! embeds, as `#rrggbb`",
+);
+
+/// Whether the osu! embeds show images in the guild.
+pub const EMBED_IMAGES: ConfigKey<bool> = ConfigKey::new(
+    "embed_images",
+    "whether the osu! embeds show beatmap covers, thumbnails and avatars",
+);
+
+/// An accent color, written as `#rrggbb`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbedColor(pub u32);
+
+impl FromStr for EmbedColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_color(s)
+            .map(EmbedColor)
+            .ok_or_else(|| format!("`{}` is not a valid hex color", s))
+    }
+}
+
+impl Display for EmbedColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:06x}", self.0)
+    }
+}
+
+/// How the osu! embeds should look, resolved for the guild they are sent to.
+#[derive(Debug, Clone)]
+pub struct EmbedTheme {
+    /// The accent color of the embeds.
+    pub color: u32,
+    /// Whether to show beatmap covers, thumbnails and avatars.
+    pub show_images: bool,
+    /// The mod emojis of this bot instance.
+    pub emojis: ModEmojis,
+    /// The language of the embeds.
+    pub locale: Locale,
+}
+
+impl EmbedTheme {
+    /// The default theme, with the given mod emojis.
+    pub fn new(emojis: ModEmojis) -> Self {
+        Self {
+            color: DEFAULT_COLOR,
+            show_images: true,
+            emojis,
+            locale: Locale::default(),
+        }
+    }
+
+    /// The message with the key, in the theme's language.
+    pub(crate) fn tr(&self, key: &'static str) -> &'static str {
+        i18n::tr(self.locale, key)
+    }
+
+    /// The message with the key in the theme's language, with its placeholders replaced by the arguments.
+    pub(crate) fn trf(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        i18n::trf(self.locale, key, args)
+    }
+
+    /// Set the embed's image, if images are enabled.
+    pub(crate) fn image(&self, embed: CreateEmbed, url: impl Into<String>) -> CreateEmbed {
+        if self.show_images {
+            embed.image(url)
+        } else {
+            embed
+        }
+    }
+
+    /// Set the embed's thumbnail, if images are enabled.
+    pub(crate) fn thumbnail(&self, embed: CreateEmbed, url: impl Into<String>) -> CreateEmbed {
+        if self.show_images {
+            embed.thumbnail(url)
+        } else {
+            embed
+        }
+    }
+}
+
+impl OsuEnv {
+    /// Get the embed theme for the given guild, or the default one outside of guilds.
+    pub(crate) async fn embed_theme(&self, guild_id: Option<GuildId>) -> EmbedTheme {
+        let mut theme = EmbedTheme::new(self.mod_emojis.clone());
+        theme.locale = self.prelude.locales.for_guild(guild_id).await;
+        let Some(guild_id) = guild_id else {
+            return theme;
+        };
+        let config = &self.prelude.config;
+        if let Some(color) = config.get(guild_id, EMBED_COLOR).await.pls_ok().flatten() {
+            theme.color = color.0;
+        }
+        if let Some(show_images) = config.get(guild_id, EMBED_IMAGES).await.pls_ok().flatten() {
+            theme.show_images = show_images;
+        }
+        theme
+    }
+
+    /// Get the embed theme for replying to the message, in the language of its author.
+    pub(crate) async fn embed_theme_for(&self, msg: &Message) -> EmbedTheme {
+        let mut theme = self.embed_theme(msg.guild_id).await;
+        theme.locale = self
+            .prelude
+            .locales
+            .for_user(msg.author.id, msg.guild_id)
+            .await;
+        theme
+    }
+
+    /// The language of the replies to the message's author.
+    pub(crate) async fn locale_for(&self, msg: &Message) -> Locale {
+        self.prelude
+            .locales
+            .for_user(msg.author.id, msg.guild_id)
+            .await
+    }
+}
+
+#[command("theme")]
+#[description = "Set the look of the osu! embeds in this server. \
+                 Give a hex color (e.g. `#ffb6c1`) to change the accent color, \
+                 `images`/`noimages` to toggle covers and thumbnails, or `reset` to go back to the defaults."]
+#[usage = "[#color] / [images/noimages] / [reset]"]
+#[example = "#66ccff noimages"]
+#[checks(manage_guild)]
+#[only_in(guilds)]
+#[max_args(2)]
+pub async fn theme(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let guild_id = msg.guild_id.unwrap();
+    let config = &env.prelude.config;
+
+    if args.current() == Some("reset") {
+        config.reset(guild_id, EMBED_COLOR).await?;
+        config.reset(guild_id, EMBED_IMAGES).await?;
+        msg.reply(&ctx, "Embed theme has been reset to the default.")
+            .await?;
+        return Ok(());
+    }
+
+    let current = env.embed_theme(Some(guild_id)).await;
+    let mut color = EmbedColor(current.color);
+    let mut show_images = current.show_images;
+    while let Ok(arg) = args.single::<String>() {
+        match &arg.to_lowercase()[..] {
+            "images" => show_images = true,
+            "noimages" => show_images = false,
+            c => color = c.parse::<EmbedColor>().map_err(Error::msg)?,
+        }
+    }
+    config.set(guild_id, EMBED_COLOR, &color).await?;
+    config.set(guild_id, EMBED_IMAGES, &show_images).await?;
+
+    msg.reply(
+        &ctx,
+        MessageBuilder::new()
+            .push("Embed theme updated: accent color ")
+            .push_mono(color.to_string())
+            .push(", images ")
+            .push_bold(if show_images { "on" } else { "off" })
+            .build(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Parse a `#rrggbb` or `rrggbb` hex color.
+fn parse_color(s: &str) -> Option<u32> {
+    let s = s.strip_prefix('#').unwrap_or(s);
+    if s.len() != 6 || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(s, 16).ok()
+}
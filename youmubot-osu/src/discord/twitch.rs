@@ -0,0 +1,244 @@
+use std::sync::Arc;
+
+use serenity::{
+    builder::{CreateEmbed, CreateEmbedFooter, CreateMessage},
+    framework::standard::{macros::command, Args, CommandResult},
+    model::{
+        channel::Message,
+        id::{ChannelId, UserId},
+    },
+    utils::MessageBuilder,
+};
+
+use announcer::MemberToChannels;
+use youmubot_db_sql::models::osu as models;
+use youmubot_prelude::announcer::CacheAndHttp;
+use youmubot_prelude::*;
+
+use crate::{
+    models::DiscordTimestamp,
+    twitch::{self, Stream},
+};
+
+use super::{embeds::grouped_number, OsuEnv};
+
+/// The Twitch announcer's unique announcer key.
+pub const TWITCH_ANNOUNCER_KEY: &str = "osu-twitch";
+
+/// Whether the streams of any game are announced in the guild, not only osu! ones.
+pub const TWITCH_ALL_GAMES: ConfigKey<bool> = ConfigKey::new(
+    "osu_twitch_all_games",
+    "whether the Twitch streams of linked users are announced whatever they play, not only osu!",
+);
+
+/// The size of the stream thumbnails.
+const THUMBNAIL_SIZE: (u32, u32) = (1280, 720);
+
+#[command("twitch")]
+#[description = "Link your Twitch channel, so that your osu! streams are announced in the servers with the Twitch announcer. \
+                 Without arguments, show the channel linked by you or the mentioned user."]
+#[usage = "set [twitch username] / unset / [@user = you]"]
+#[example = "set natsukagami"]
+#[max_args(2)]
+pub async fn twitch(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let theme = env.embed_theme_for(msg).await;
+    let Some(client) = env.twitch.clone() else {
+        msg.reply(&ctx, theme.tr("twitch-not-configured")).await?;
+        return Ok(());
+    };
+    let author = msg.author.id.get() as i64;
+
+    match args.current().map(|v| v.to_lowercase()).as_deref() {
+        Some("set") => {
+            args.advance();
+            let login = args.single::<String>()?.to_lowercase();
+            let Some(user) = client.user(&login).await? else {
+                msg.reply(
+                    &ctx,
+                    theme.trf("twitch-channel-not-found", &[("login", &login)]),
+                )
+                .await?;
+                return Ok(());
+            };
+            let channel = models::TwitchChannel {
+                user_id: author,
+                twitch_login: user.login.clone(),
+                live_stream_id: None,
+            };
+            channel.store(&env.prelude.sql).await?;
+            msg.reply(
+                &ctx,
+                theme.trf(
+                    "twitch-linked",
+                    &[("channel", &channel_link(&user.display_name, &user.login))],
+                ),
+            )
+            .await?;
+        }
+        Some("unset") => {
+            let reply = if models::TwitchChannel::delete(author, &env.prelude.sql).await? {
+                "twitch-unlinked"
+            } else {
+                "twitch-none"
+            };
+            msg.reply(&ctx, theme.tr(reply)).await?;
+        }
+        _ => {
+            let user = match args.single::<args::UserId>() {
+                Ok(u) => u.0,
+                Err(_) if args.is_empty() => msg.author.id,
+                Err(_) => {
+                    return Err(error!(
+                        "unknown action `{}`, expected `set`, `unset` or an user mention",
+                        args.rest()
+                    )
+                    .into())
+                }
+            };
+            let linked =
+                models::TwitchChannel::by_user_id(user.get() as i64, &env.prelude.sql).await?;
+            let reply = match linked {
+                Some(c) => theme.trf(
+                    "twitch-show",
+                    &[
+                        ("user", &user.mention().to_string()),
+                        ("channel", &channel_link(&c.twitch_login, &c.twitch_login)),
+                    ],
+                ),
+                None => theme.trf("twitch-show-none", &[("user", &user.mention().to_string())]),
+            };
+            msg.reply(&ctx, reply).await?;
+        }
+    }
+    Ok(())
+}
+
+/// A markdown link to the channel, without embedding it.
+fn channel_link(name: &str, login: &str) -> String {
+    format!(
+        "[{}](<https://twitch.tv/{}>)",
+        MessageBuilder::new().push_safe(name).build(),
+        login
+    )
+}
+
+/// Announces the streams of the users who linked their Twitch channel.
+pub struct TwitchAnnouncer {
+    client: Arc<twitch::Client>,
+}
+
+impl TwitchAnnouncer {
+    pub fn new(client: Arc<twitch::Client>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl youmubot_prelude::Announcer for TwitchAnnouncer {
+    async fn updates(
+        &mut self,
+        c: CacheAndHttp,
+        d: AppData,
+        channels: MemberToChannels,
+    ) -> Result<()> {
+        let env = d.read().await.get::<OsuEnv>().unwrap().clone();
+        let linked = models::TwitchChannel::all(&env.prelude.sql).await?;
+        if linked.is_empty() {
+            return Ok(());
+        }
+        let logins = linked
+            .iter()
+            .map(|l| l.twitch_login.clone())
+            .collect::<Vec<_>>();
+        let live = self.client.live_streams(&logins).await?;
+
+        for l in linked {
+            let stream = live.get(&l.twitch_login);
+            // Each stream is announced once, when it is first seen.
+            if stream.map(|s| &s.id) == l.live_stream_id.as_ref() {
+                continue;
+            }
+            models::TwitchChannel::set_live(
+                l.user_id,
+                stream.map(|s| s.id.as_str()),
+                &env.prelude.sql,
+            )
+            .await?;
+            let Some(stream) = stream else {
+                continue;
+            };
+            let user = UserId::new(l.user_id as u64);
+            for channel in channels.channels_of(c.clone(), user).await {
+                announce_stream(&env, c.clone(), channel, user, stream)
+                    .await
+                    .pls_ok();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Announce the stream in the channel, if the channel's guild announces its game.
+async fn announce_stream(
+    env: &OsuEnv,
+    c: CacheAndHttp,
+    channel: ChannelId,
+    user: UserId,
+    stream: &Stream,
+) -> Result<()> {
+    let guild = channel.to_channel(&c).await?.guild().map(|g| g.guild_id);
+    if !stream.is_osu() {
+        let all_games = match guild {
+            Some(guild) => env
+                .prelude
+                .config
+                .get(guild, TWITCH_ALL_GAMES)
+                .await?
+                .unwrap_or(false),
+            None => false,
+        };
+        if !all_games {
+            return Ok(());
+        }
+    }
+    let theme = env.embed_theme(guild).await;
+    let game = if stream.game_name.is_empty() {
+        theme.tr("twitch-no-game").to_owned()
+    } else {
+        stream.game_name.clone()
+    };
+    let embed = CreateEmbed::new()
+        .title(MessageBuilder::new().push_safe(&stream.title).build())
+        .url(stream.url())
+        .color(theme.color)
+        .field(theme.tr("twitch-game"), &game, true)
+        .field(
+            theme.tr("twitch-viewers"),
+            grouped_number(stream.viewer_count),
+            true,
+        )
+        .field(
+            theme.tr("twitch-started"),
+            stream.started_at.relative_time(),
+            true,
+        )
+        .footer(CreateEmbedFooter::new(&stream.user_name));
+    let embed = theme.image(embed, stream.thumbnail(THUMBNAIL_SIZE.0, THUMBNAIL_SIZE.1));
+    let content = theme.trf(
+        "announce-twitch-live",
+        &[
+            ("user", &user.mention().to_string()),
+            ("game", &MessageBuilder::new().push_bold_safe(&game).build()),
+        ],
+    );
+    env.prelude
+        .send_queue
+        .send(
+            c,
+            channel,
+            CreateMessage::new().content(content).embed(embed),
+        )
+        .await?;
+    Ok(())
+}
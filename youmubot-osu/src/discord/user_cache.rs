@@ -0,0 +1,122 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+
+use youmubot_prelude::*;
+
+use crate::{
+    models::{Mode, User},
+    ratelimit::Priority,
+    request::UserID,
+    Client,
+};
+
+use super::refresh::RefreshQueue;
+
+/// How long a cached user is returned without refreshing.
+const FRESH_FOR: Duration = Duration::from_secs(2 * 60);
+/// How long a cached user is returned at all. Between [FRESH_FOR] and this,
+/// the user is returned and refreshed in the background.
+const STALE_FOR: Duration = Duration::from_secs(30 * 60);
+/// How many users to keep in memory.
+const CACHE_SIZE: usize = 1024;
+
+type Key = (UserID, Option<Mode>);
+
+/// A short-lived cache of user profiles, so that commands don't wait on the API
+/// when the profile was fetched recently.
+#[derive(Clone)]
+pub struct UserCache {
+    client: Arc<Client>,
+    /// The client used for background refreshes.
+    background: Arc<Client>,
+    users: Arc<DashMap<Key, (User, Instant)>>,
+    refresh: Arc<RefreshQueue<Key>>,
+}
+
+impl std::fmt::Debug for UserCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<UserCache>")
+    }
+}
+
+impl UserCache {
+    /// Create a new user cache.
+    /// Must be called within the tokio runtime, as it starts the background refresh task.
+    pub fn new(client: Arc<Client>) -> Self {
+        let (refresh, worker) = RefreshQueue::new();
+        let cache = Self {
+            background: Arc::new(client.with_priority(Priority::Background)),
+            client,
+            users: Arc::new(DashMap::new()),
+            refresh: Arc::new(refresh),
+        };
+        let c = cache.clone();
+        spawn_future(worker.run(move |key| {
+            let c = c.clone();
+            async move {
+                c.fetch(&c.background, key).await?;
+                Ok(())
+            }
+        }));
+        cache
+    }
+
+    /// Get an user, in the given mode or their default mode.
+    /// Recently fetched users are returned from the cache.
+    pub async fn get(&self, user: UserID, mode: Option<Mode>) -> Result<Option<User>> {
+        let key = (Self::normalize(user), mode);
+        if let Some(entry) = self.users.get(&key) {
+            let (user, fetched_at) = entry.value();
+            let age = fetched_at.elapsed();
+            if age <= STALE_FOR {
+                if age > FRESH_FOR {
+                    self.refresh.push(key.clone());
+                }
+                return Ok(Some(user.clone()));
+            }
+        }
+        self.fetch(&self.client, key).await
+    }
+
+    /// Fetch an user into the cache with the background client.
+    pub async fn warm(&self, user: UserID, mode: Option<Mode>) -> Result<Option<User>> {
+        self.fetch(&self.background, (Self::normalize(user), mode))
+            .await
+    }
+
+    async fn fetch(&self, client: &Client, key: Key) -> Result<Option<User>> {
+        let (id, mode) = key.clone();
+        let user = client
+            .user(id, |f| match mode {
+                Some(mode) => f.mode(mode),
+                None => f,
+            })
+            .await?;
+        if let Some(user) = &user {
+            if self.users.len() >= CACHE_SIZE {
+                self.users
+                    .retain(|_, (_, fetched_at)| fetched_at.elapsed() <= STALE_FOR);
+                if self.users.len() >= CACHE_SIZE {
+                    self.users.clear();
+                }
+            }
+            let now = Instant::now();
+            self.users
+                .insert((UserID::ID(user.id), mode), (user.clone(), now));
+            self.users.insert(key, (user.clone(), now));
+        }
+        Ok(user)
+    }
+
+    /// Usernames are case-insensitive.
+    fn normalize(user: UserID) -> UserID {
+        match user {
+            UserID::Username(name) => UserID::Username(name.to_lowercase()),
+            id => id,
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use youmubot_prelude::*;
+
+use crate::ratelimit::Priority;
+
+use super::OsuEnv;
+
+/// When the recently used beatmaps are checked for updates: every hour.
+pub(crate) const VALIDATION_SCHEDULE: &str = "@hourly";
+/// How recently a beatmap should have been used to be checked.
+const ACTIVE_WINDOW: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// Check the recently used beatmaps for updates, dropping the cached metadata,
+/// .osu files and difficulty attributes of the updated ones, as a scheduled job.
+///
+/// Qualified and loved beatmaps can be updated after they were cached,
+/// which would otherwise leave their metadata and pp outdated.
+pub(crate) async fn validation_job(env: OsuEnv) -> Result<()> {
+    let invalidated = validate(&env).await?;
+    if invalidated > 0 {
+        tracing::info!("osu: invalidated {} updated beatmaps", invalidated);
+    }
+    Ok(())
+}
+
+/// Check the recently used beatmaps against the API, returning how many were invalidated.
+async fn validate(env: &OsuEnv) -> Result<usize> {
+    let ids = env.oppai.recently_used(ACTIVE_WINDOW);
+    if ids.is_empty() {
+        return Ok(0);
+    }
+    let client = env.client.with_priority(Priority::Background);
+    let mut invalidated = 0;
+    for fresh in client.beatmaps_by_ids(&ids).await? {
+        let mut updated = false;
+        if fresh.file_hash != "none" {
+            updated |= env
+                .oppai
+                .invalidate(fresh.beatmap_id, &fresh.file_hash)
+                .await?;
+        }
+        if env.beatmaps.is_outdated(&fresh).await? {
+            env.beatmaps.invalidate(fresh.beatmap_id).await?;
+            updated = true;
+        }
+        if updated {
+            invalidated += 1;
+        }
+    }
+    Ok(invalidated)
+}
@@ -0,0 +1,57 @@
+use youmubot_prelude::*;
+
+use crate::{models::Mode, ratelimit::Priority, request::UserID};
+
+use super::OsuEnv;
+
+/// How many users are warmed up at once.
+const CONCURRENCY: usize = 4;
+/// How many top plays of each user to load the beatmaps of.
+const TOP_PLAYS: u8 = 50;
+
+/// Load the saved users' profiles and the beatmaps of their top plays into the caches,
+/// so that the first announcer sweep and the first commands don't wait on the API.
+///
+/// Everything is fetched with the background client, so commands are served first.
+pub(crate) async fn warm_up(env: OsuEnv) {
+    let users = match env.saved_users.all().await {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::warn!("osu! warm-up: cannot load saved users: {}", e);
+            return;
+        }
+    };
+    let client = env.client.with_priority(Priority::Background);
+    let targets = users.into_iter().flat_map(|u| {
+        // Only warm up the modes the user has played.
+        let modes = [Mode::Std, Mode::Taiko, Mode::Catch, Mode::Mania]
+            .into_iter()
+            .filter(|m| u.pp[*m as usize].is_some())
+            .collect::<Vec<_>>();
+        let modes = if modes.is_empty() {
+            vec![Mode::Std]
+        } else {
+            modes
+        };
+        let id = u.id;
+        modes.into_iter().map(move |m| (id, m))
+    });
+    let count = stream::iter(targets)
+        .map(|(id, mode)| {
+            let env = &env;
+            let client = &client;
+            async move {
+                env.users.warm(UserID::ID(id), Some(mode)).await?;
+                let bests = client
+                    .user_best(UserID::ID(id), |f| f.mode(mode).limit(TOP_PLAYS))
+                    .await?;
+                let ids = bests.iter().map(|s| s.beatmap_id).collect::<Vec<_>>();
+                env.beatmaps.warm(&ids, mode).await
+            }
+        })
+        .buffer_unordered(CONCURRENCY)
+        .filter_map(|r: Result<()>| future::ready(r.pls_ok()))
+        .count()
+        .await;
+    tracing::info!("osu! warm-up: warmed up {} user profiles", count);
+}
@@ -0,0 +1,341 @@
+//! A tiny HTTP server with the osu! standings of each server as JSON, enabled with `web.listen`,
+//! so that communities can show them on their websites:
+//! - `GET /guilds/{id}/ranks?mode=std`: the server ranks, as in `ranks`.
+//!   `mode` is one of `std`, `taiko`, `catch`, `mania`, `total` or `map-length`.
+//! - `GET /guilds/{id}/users`: the saved users in the server.
+//! - `GET /guilds/{id}/scores?limit=20`: the latest scores announced in the server, up to 100.
+//!
+//! Every request must carry `web.token`, as `Authorization: Bearer <token>`.
+//! The endpoints are meant to be called by the community's website backend, not by browsers,
+//! so cross-origin requests are not allowed.
+use std::{collections::HashMap, fmt::Display, net::SocketAddr, time::Duration};
+
+use serde_json::{json, Value};
+use serenity::{http::CacheHttp, model::id::GuildId};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use youmubot_db_sql::models::osu as models;
+use youmubot_prelude::*;
+
+use crate::models::{Mode, Score};
+
+use super::{
+    db::OsuUser,
+    server_rank::{ranks, RankQuery},
+    OsuEnv,
+};
+
+/// How many scores are returned by default.
+const DEFAULT_SCORES: i64 = 20;
+/// How many scores can be asked for at most.
+const MAX_SCORES: i64 = 100;
+/// How long a client has to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// The largest request line and headers accepted.
+const MAX_HEAD: usize = 8 * 1024;
+
+/// Serve the endpoints at `http://<addr>/`, until shutdown.
+pub(crate) async fn serve(
+    addr: SocketAddr,
+    token: String,
+    env: OsuEnv,
+    shutdown: Shutdown,
+) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!("serving the server standings at http://{}/", addr);
+    while let Some(accepted) = shutdown.cancellable(listener.accept()).await {
+        let (mut stream, _) = match accepted {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!("web: cannot accept connection: {}", e);
+                continue;
+            }
+        };
+        let env = env.clone();
+        let token = token.clone();
+        tokio::spawn(async move {
+            let result = match read_head(&mut stream).await {
+                Ok(request) => handle(&env, &token, &request).await,
+                Err(f) => Err(f),
+            };
+            let (status, body) = match result {
+                Ok(body) => ("200 OK", body),
+                Err(f) => (f.status, json!({ "error": f.message })),
+            };
+            stream
+                .write_all(response(status, &body.to_string()).as_bytes())
+                .await
+                .ok();
+            stream.shutdown().await.ok();
+        });
+    }
+    Ok(())
+}
+
+/// Read the request line and the headers, which are all that matter.
+async fn read_head(stream: &mut TcpStream) -> Result<String, Failure> {
+    let mut head = Vec::new();
+    let read = async {
+        let mut buf = [0u8; 1024];
+        loop {
+            let n = stream.read(&mut buf).await.unwrap_or(0);
+            if n == 0 {
+                return Err(Failure::bad_request("malformed request"));
+            }
+            head.extend_from_slice(&buf[..n]);
+            if let Some(end) = head.windows(4).position(|w| w == b"\r\n\r\n") {
+                head.truncate(end);
+                return Ok(());
+            }
+            if head.len() > MAX_HEAD {
+                return Err(Failure::new(
+                    "431 Request Header Fields Too Large",
+                    "request headers are too large",
+                ));
+            }
+        }
+    };
+    tokio::time::timeout(READ_TIMEOUT, read)
+        .await
+        .map_err(|_| Failure::new("408 Request Timeout", "request timed out"))??;
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+fn response(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+/// A request that cannot be answered.
+struct Failure {
+    status: &'static str,
+    message: String,
+}
+
+impl Failure {
+    fn new(status: &'static str, message: impl Display) -> Self {
+        Self {
+            status,
+            message: message.to_string(),
+        }
+    }
+
+    fn bad_request(message: impl Display) -> Self {
+        Self::new("400 Bad Request", message)
+    }
+
+    fn not_found() -> Self {
+        Self::new("404 Not Found", "not found")
+    }
+}
+
+impl From<Error> for Failure {
+    fn from(e: Error) -> Self {
+        tracing::warn!("web: {:?}", e);
+        Self::new("500 Internal Server Error", "internal error")
+    }
+}
+
+/// Route the request.
+async fn handle(env: &OsuEnv, token: &str, request: &str) -> Result<Value, Failure> {
+    let mut lines = request.lines();
+    let (method, target) = match lines
+        .next()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>()[..]
+    {
+        [method, target, ..] => (method, target),
+        _ => return Err(Failure::bad_request("malformed request")),
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter_map(|p| p.split_once('='))
+        .collect::<HashMap<_, _>>();
+
+    let bearer = lines
+        .take_while(|l| !l.is_empty())
+        .filter_map(|l| l.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("authorization"))
+        .and_then(|(_, value)| value.trim().strip_prefix("Bearer "));
+    if !bearer.is_some_and(|given| same_token(given.trim(), token)) {
+        return Err(Failure::new("401 Unauthorized", "missing or invalid token"));
+    }
+    if method != "GET" {
+        return Err(Failure::new(
+            "405 Method Not Allowed",
+            "only GET is supported",
+        ));
+    }
+
+    let (guild, endpoint) = match path.trim_matches('/').split('/').collect::<Vec<_>>()[..] {
+        ["guilds", guild, endpoint] => (guild, endpoint),
+        _ => return Err(Failure::not_found()),
+    };
+    let guild = guild
+        .parse::<u64>()
+        .ok()
+        .filter(|&g| g != 0)
+        .map(GuildId::new)
+        .ok_or_else(Failure::not_found)?;
+    let Some(discord) = env.prelude.scheduler.discord() else {
+        return Err(Failure::new(
+            "503 Service Unavailable",
+            "not connected to Discord yet",
+        ));
+    };
+    // Only the servers the bot is in have standings.
+    if discord
+        .cache()
+        .is_some_and(|cache| cache.guild(guild).is_none())
+    {
+        return Err(Failure::not_found());
+    }
+
+    match endpoint {
+        "ranks" => {
+            let mode = match query.get("mode") {
+                Some(mode) => mode
+                    .parse::<RankQuery>()
+                    .map_err(|_| Failure::bad_request(format!("unknown mode `{}`", mode)))?,
+                None => RankQuery::Mode(Mode::Std),
+            };
+            let (users, last_update) = match ranks(env, discord, guild, mode).await? {
+                Some((users, last_update)) => (users, Some(last_update)),
+                None => (vec![], None),
+            };
+            let users = users
+                .iter()
+                .enumerate()
+                .map(|(i, (pp, (member, user)))| {
+                    let mut v = user_json(member, user);
+                    v["rank"] = json!(i + 1);
+                    v["value"] = json!(pp);
+                    v
+                })
+                .collect::<Vec<_>>();
+            Ok(json!({
+                "mode": mode.name(),
+                "last_update": last_update,
+                "users": users,
+            }))
+        }
+        "users" => {
+            let saved = saved_users(env).await?;
+            let users = env
+                .prelude
+                .members
+                .query_members(discord, guild)
+                .await?
+                .iter()
+                .filter_map(|m| {
+                    saved
+                        .get(&m.user.id.get())
+                        .map(|u| user_json(&m.user.name, u))
+                })
+                .collect::<Vec<_>>();
+            Ok(json!({ "users": users }))
+        }
+        "scores" => {
+            let limit = match query.get("limit") {
+                Some(limit) => limit
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|l| (1..=MAX_SCORES).contains(l))
+                    .ok_or_else(|| {
+                        Failure::bad_request(format!("limit must be from 1 to {}", MAX_SCORES))
+                    })?,
+                None => DEFAULT_SCORES,
+            };
+            let saved = saved_users(env).await?;
+            let scores = models::AnnouncedScore::recent_by_guild(
+                guild.get() as i64,
+                limit,
+                &env.prelude.sql,
+            )
+            .await
+            .map_err(Error::from)?
+            .iter()
+            .map(|s| score_json(s, &saved))
+            .collect::<Vec<_>>();
+            Ok(json!({ "scores": scores }))
+        }
+        _ => Err(Failure::not_found()),
+    }
+}
+
+/// Compare the tokens without leaking where they differ.
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// The saved users, by Discord user id.
+async fn saved_users(env: &OsuEnv) -> Result<HashMap<u64, OsuUser>> {
+    Ok(env
+        .saved_users
+        .all()
+        .await?
+        .into_iter()
+        .map(|u| (u.user_id.get(), u))
+        .collect())
+}
+
+fn user_json(member: &str, user: &OsuUser) -> Value {
+    let pp = |mode: Mode| user.pp[mode as usize];
+    json!({
+        "discord_id": user.user_id.get().to_string(),
+        "member": member,
+        "osu_id": user.id,
+        "username": user.username,
+        "link": format!("https://osu.ppy.sh/users/{}", user.id),
+        "pp": {
+            "std": pp(Mode::Std),
+            "taiko": pp(Mode::Taiko),
+            "catch": pp(Mode::Catch),
+            "mania": pp(Mode::Mania),
+        },
+        "std_weighted_map_length": user.std_weighted_map_length,
+        "last_update": user.last_update,
+    })
+}
+
+fn score_json(s: &models::AnnouncedScore, saved: &HashMap<u64, OsuUser>) -> Value {
+    let mode = Mode::from(s.mode);
+    let mut v = json!({
+        "discord_id": s.discord_user_id.to_string(),
+        "osu_id": s.osu_user_id,
+        "username": saved.get(&(s.discord_user_id as u64)).map(|u| &u.username),
+        "beatmap_id": s.beatmap_id,
+        "beatmap_link": format!("https://osu.ppy.sh/b/{}", s.beatmap_id),
+        "mode": mode.as_str_new_site(),
+        "pp": s.pp,
+        // The position on the user's top plays, or on the beatmap's leaderboard.
+        "kind": if s.kind == 0 { "top" } else { "leaderboard" },
+        "position": s.rank,
+        "played_at": s.played_at,
+        "announced_at": s.announced_at,
+    });
+    if let Some(score) = bincode::deserialize::<Score>(&s.score[..]).pls_ok() {
+        v["mods"] = json!(score.mods_text());
+        v["grade"] = json!(score.rank.to_string());
+        v["accuracy"] = json!(score.accuracy(mode));
+        v["max_combo"] = json!(score.max_combo);
+        v["score_link"] = json!(score
+            .id
+            .map(|id| format!("https://osu.ppy.sh/scores/{}", id)));
+    }
+    v
+}
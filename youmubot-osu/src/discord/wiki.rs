@@ -0,0 +1,195 @@
+use std::time::Duration;
+
+use serenity::{
+    all::ComponentInteractionDataKind,
+    builder::{
+        CreateActionRow, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter,
+        CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
+        CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+    },
+    framework::standard::{macros::command, Args, CommandResult},
+    model::channel::Message,
+    utils::MessageBuilder,
+};
+
+use youmubot_prelude::interaction::{self, Incoming, Interactive};
+use youmubot_prelude::*;
+
+use crate::{models::wiki::WikiPage, Client as OsuHttpClient};
+
+use super::{theme::EmbedTheme, OsuEnv};
+
+const PICKER_ID: &str = "osu-wiki-pick";
+/// The language of the untranslated articles.
+const DEFAULT_LOCALE: &str = "en";
+/// How many search results can be picked from. Discord allows 25 options.
+const MAX_RESULTS: usize = 10;
+/// The longest summary shown in an embed.
+const MAX_SUMMARY_LEN: usize = 1000;
+/// Discord's limit on the length of a select menu option's label and description.
+const MAX_OPTION_LEN: usize = 100;
+/// How long the other results can be picked.
+const PICKER_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[command("wiki")]
+#[description = "Search the osu! wiki, and show the best matching article in the server's language. \
+                 The other results can be picked from the menu below it."]
+#[usage = "[search query]"]
+#[example = "hidden"]
+#[min_args(1)]
+#[bucket("heavy")]
+pub async fn wiki(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let env = ctx.data.read().await.get::<OsuEnv>().unwrap().clone();
+    let theme = env.embed_theme_for(msg).await;
+    let query = args.rest().trim();
+
+    let mut results = env.client.wiki_search(query).await?;
+    // The same article can be found in several languages.
+    let mut seen = std::collections::HashSet::new();
+    results.retain(|p| seen.insert(p.path.clone()));
+    results.truncate(MAX_RESULTS);
+    let Some(first) = results.first() else {
+        msg.reply(
+            &ctx,
+            theme.trf(
+                "wiki-not-found",
+                &[(
+                    "query",
+                    &MessageBuilder::new().push_mono_safe(query).build(),
+                )],
+            ),
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let page = localized_page(&env.client, first, &theme).await?;
+    let mut message = CreateMessage::new()
+        .reference_message(msg)
+        .embed(wiki_embed(&page, &theme));
+    if results.len() > 1 {
+        message = message.components(vec![picker(&results, 0)]);
+    }
+    let reply = msg.channel_id.send_message(&ctx, message).await?;
+    if results.len() > 1 {
+        interaction::run(
+            WikiPicker {
+                client: &env.client,
+                theme: &theme,
+                results: &results,
+            },
+            ctx,
+            reply,
+            interaction::Allowed::user(msg.author.id),
+            PICKER_TIMEOUT,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// The article in the theme's language, or in English if it is not translated.
+async fn localized_page(
+    client: &OsuHttpClient,
+    result: &WikiPage,
+    theme: &EmbedTheme,
+) -> Result<WikiPage> {
+    let locale = theme.locale.code();
+    let locale = if result.available_locales.iter().any(|l| l == locale) {
+        locale
+    } else {
+        DEFAULT_LOCALE
+    };
+    if result.locale == locale && !result.markdown.is_empty() {
+        return Ok(result.clone());
+    }
+    Ok(client
+        .wiki_page(locale, &result.path)
+        .await?
+        .unwrap_or_else(|| result.clone()))
+}
+
+/// The embed of a wiki article.
+fn wiki_embed(page: &WikiPage, theme: &EmbedTheme) -> CreateEmbed {
+    let mut footer = theme.tr("wiki-footer").to_owned();
+    if page.locale != theme.locale.code() {
+        footer = format!("{} • {}", footer, theme.tr("wiki-not-translated"));
+    }
+    let mut embed = CreateEmbed::new()
+        .title(MessageBuilder::new().push_safe(&page.title).build())
+        .url(page.link())
+        .color(theme.color)
+        .description(page.summary(MAX_SUMMARY_LEN))
+        .footer(CreateEmbedFooter::new(footer));
+    if let Some(subtitle) = &page.subtitle {
+        embed = embed.author(CreateEmbedAuthor::new(subtitle));
+    }
+    embed
+}
+
+/// The menu to pick one of the search results, with the `selected`-th one chosen.
+fn picker(results: &[WikiPage], selected: usize) -> CreateActionRow {
+    let options = results
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let option = CreateSelectMenuOption::new(cut(&p.title), i.to_string())
+                .default_selection(i == selected);
+            match &p.subtitle {
+                Some(subtitle) => option.description(cut(subtitle)),
+                None => option,
+            }
+        })
+        .collect();
+    CreateActionRow::SelectMenu(CreateSelectMenu::new(
+        PICKER_ID,
+        CreateSelectMenuKind::String { options },
+    ))
+}
+
+fn cut(s: &str) -> String {
+    s.chars().take(MAX_OPTION_LEN).collect()
+}
+
+/// Shows the search result picked from the menu.
+struct WikiPicker<'a> {
+    client: &'a OsuHttpClient,
+    theme: &'a EmbedTheme,
+    results: &'a [WikiPage],
+}
+
+#[async_trait]
+impl<'a> Interactive for WikiPicker<'a> {
+    async fn handle(
+        &mut self,
+        ctx: &Context,
+        incoming: &Incoming,
+        _message: &mut Message,
+    ) -> Result<bool> {
+        let picked = match incoming {
+            Incoming::Component(c) => match &c.data.kind {
+                ComponentInteractionDataKind::StringSelect { values } => {
+                    values.first().and_then(|v| v.parse::<usize>().ok())
+                }
+                _ => None,
+            },
+            Incoming::Modal(_) => None,
+        };
+        let Some(picked) = picked.filter(|&i| i < self.results.len()) else {
+            incoming.acknowledge(ctx).await?;
+            return Ok(true);
+        };
+        let page = localized_page(self.client, &self.results[picked], self.theme).await?;
+        incoming
+            .respond(
+                ctx,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .embed(wiki_embed(&page, self.theme))
+                        .components(vec![picker(self.results, picked)]),
+                ),
+            )
+            .await?;
+        Ok(true)
+    }
+}
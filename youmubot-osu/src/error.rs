@@ -0,0 +1,120 @@
+use rosu_v2::error::OsuError;
+
+/// Errors returned by the osu! API client.
+#[derive(thiserror::Error, Debug)]
+pub enum ClientError {
+    /// The requested beatmap, score or user does not exist.
+    #[error("not found")]
+    NotFound,
+    /// The API refused the request because too many were sent.
+    #[error("rate limited by the osu! API")]
+    RateLimited {
+        /// How long to wait before retrying, if the API said so.
+        retry_after: Option<std::time::Duration>,
+    },
+    /// An user looked up by id cannot be found, which usually means they were restricted.
+    #[error("the user is restricted or no longer exists")]
+    Restricted,
+    /// The API did not respond in time.
+    #[error("the osu! API did not respond in time")]
+    Timeout,
+    /// The API failed to handle the request.
+    #[error("the osu! API failed with status {status}")]
+    Server { status: u16 },
+    /// Requests are paused, as the API kept failing.
+    #[error("the osu! API is unavailable")]
+    Unavailable,
+    /// The client's backend cannot send this request, e.g. a mock in tests.
+    #[error("the request is not supported by the client's backend")]
+    Unsupported,
+    /// The API rejected the client's token, and it could not be renewed.
+    #[error("the osu! API token expired")]
+    AuthExpired,
+    /// The API response could not be parsed.
+    #[error("cannot parse the osu! API response")]
+    Deserialize {
+        /// The unparsed response.
+        body: String,
+    },
+    /// Any other error from the API or the connection to it.
+    #[error("osu! API error: {0}")]
+    Other(#[source] OsuError),
+    /// An error from a request sent without rosu-v2.
+    #[error("osu! API request failed: {0}")]
+    Request(#[from] reqwest::Error),
+}
+
+impl ClientError {
+    /// Whether the error is likely to go away by sending the request again.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ClientError::Timeout | ClientError::Server { .. } => true,
+            ClientError::Other(OsuError::Request { .. }) => true,
+            ClientError::Request(e) => e.is_timeout() || e.is_connect(),
+            _ => false,
+        }
+    }
+
+    /// A short name of the kind of error, e.g. for metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ClientError::NotFound => "not_found",
+            ClientError::RateLimited { .. } => "rate_limited",
+            ClientError::Restricted => "restricted",
+            ClientError::Timeout => "timeout",
+            ClientError::Server { .. } => "server_error",
+            ClientError::Unavailable => "unavailable",
+            ClientError::Unsupported => "unsupported",
+            ClientError::AuthExpired => "auth_expired",
+            ClientError::Deserialize { .. } => "deserialize",
+            ClientError::Other(_) | ClientError::Request(_) => "other",
+        }
+    }
+
+    /// A message explaining the error to the user of a command.
+    pub fn user_message(&self) -> &'static str {
+        match self {
+            ClientError::NotFound => "🔍 I couldn't find that on osu!.",
+            ClientError::RateLimited { .. } => {
+                "⏳ osu! is receiving too many requests from me, please try again in a minute."
+            }
+            ClientError::Restricted => "🚫 This user is restricted or no longer exists on osu!.",
+            ClientError::Timeout | ClientError::Server { .. } | ClientError::Unavailable => {
+                "🔌 The osu! API is unavailable right now, please try again later."
+            }
+            ClientError::AuthExpired
+            | ClientError::Unsupported
+            | ClientError::Deserialize { .. }
+            | ClientError::Other(_)
+            | ClientError::Request(_) => {
+                "😵 I couldn't talk to osu! right now, please try again later."
+            }
+        }
+    }
+}
+
+/// Explain osu! API errors to the user, for the [youmubot_prelude::ErrorReporter].
+pub(crate) fn describe(e: &(dyn std::error::Error + 'static)) -> Option<String> {
+    e.downcast_ref::<ClientError>()
+        .map(|e| e.user_message().to_owned())
+}
+
+impl From<OsuError> for ClientError {
+    fn from(e: OsuError) -> Self {
+        match e {
+            OsuError::NotFound => ClientError::NotFound,
+            OsuError::Parsing { body, .. } => ClientError::Deserialize { body },
+            OsuError::Response { status, .. } if status.as_u16() == 429 => {
+                ClientError::RateLimited { retry_after: None }
+            }
+            OsuError::Response { status, .. } if status.as_u16() == 401 => ClientError::AuthExpired,
+            OsuError::Response { status, .. } if status.is_server_error() => ClientError::Server {
+                status: status.as_u16(),
+            },
+            OsuError::ServiceUnavailable { .. } => ClientError::Server { status: 503 },
+            OsuError::RequestTimeout => ClientError::Timeout,
+            OsuError::UpdateToken { .. } => ClientError::AuthExpired,
+            e => ClientError::Other(e),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! A client for [huismetbenen](https://snipe.huismetbenen.nl), which tracks the country #1 scores
+//! of the players of some countries, and who takes them from whom ("snipes").
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use youmubot_prelude::*;
+
+use crate::{
+    models::Mode,
+    ratelimit::{Priority, RateLimitStats, RateLimiter},
+};
+
+const API_URL: &str = "https://api.huismetbenen.nl";
+
+/// How many requests are sent to huismetbenen per minute, at most.
+const REQUESTS_PER_MINUTE: u32 = 30;
+/// How long the list of tracked countries is kept before being fetched again.
+const COUNTRIES_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// Fetches the country #1s and snipes of the tracked countries.
+#[derive(Debug)]
+pub struct Client {
+    http: reqwest::Client,
+    limiter: RateLimiter,
+    countries: Mutex<Option<(Instant, Arc<HashSet<String>>)>>,
+}
+
+/// A player's country #1s statistics.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Player {
+    #[serde(alias = "name")]
+    pub username: String,
+    /// The number of country #1s.
+    #[serde(default)]
+    pub count: u64,
+    /// The player's rank in the country, by number of #1s.
+    #[serde(default)]
+    pub rank: Option<u64>,
+    #[serde(alias = "avg_pp", default)]
+    pub average_pp: Option<f64>,
+    #[serde(alias = "avg_acc", default)]
+    pub average_accuracy: Option<f64>,
+    #[serde(alias = "avg_stars", default)]
+    pub average_stars: Option<f64>,
+}
+
+/// A country #1 score.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CountryFirst {
+    #[serde(alias = "map_id")]
+    pub beatmap_id: u64,
+    /// The beatmap's `artist - title [version]`.
+    #[serde(alias = "map", default)]
+    pub title: String,
+    #[serde(default)]
+    pub pp: Option<f64>,
+    #[serde(default)]
+    pub accuracy: Option<f64>,
+    #[serde(default)]
+    pub mods: Option<String>,
+    #[serde(alias = "date_set")]
+    pub date: DateTime<Utc>,
+}
+
+/// A country #1 taken by a player, from another player if it was set before.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Snipe {
+    #[serde(alias = "map_id")]
+    pub beatmap_id: u64,
+    /// The beatmap's `artist - title [version]`.
+    #[serde(alias = "map", default)]
+    pub title: String,
+    #[serde(alias = "sniper_name")]
+    pub sniper: String,
+    pub sniper_id: u64,
+    #[serde(alias = "sniped_name", default)]
+    pub sniped: Option<String>,
+    #[serde(default)]
+    pub sniped_id: Option<u64>,
+    #[serde(default)]
+    pub pp: Option<f64>,
+    #[serde(alias = "date_set")]
+    pub date: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct Country {
+    #[serde(alias = "code")]
+    country_code: String,
+}
+
+impl Client {
+    pub fn new(http: reqwest::Client) -> Self {
+        Self {
+            http,
+            limiter: RateLimiter::new(REQUESTS_PER_MINUTE),
+            countries: Mutex::new(None),
+        }
+    }
+
+    /// The statistics of the rate limiter.
+    pub fn rate_limit_stats(&self) -> RateLimitStats {
+        self.limiter.stats()
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(
+        &self,
+        priority: Priority,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<Option<T>> {
+        self.limiter.acquire(priority).await;
+        let resp = self
+            .http
+            .get(format!("{}/{}", API_URL, path))
+            .query(query)
+            .send()
+            .await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(resp.error_for_status()?.json().await?))
+    }
+
+    /// The codes of the countries tracked by huismetbenen, e.g. `BE`.
+    pub async fn countries(&self) -> Result<Arc<HashSet<String>>> {
+        if let Some((fetched, countries)) = &*self.countries.lock().unwrap() {
+            if fetched.elapsed() < COUNTRIES_TTL {
+                return Ok(countries.clone());
+            }
+        }
+        let countries: Vec<Country> = self
+            .get(Priority::Interactive, "country/all", &[])
+            .await?
+            .unwrap_or_default();
+        let countries = Arc::new(
+            countries
+                .into_iter()
+                .map(|c| c.country_code.to_uppercase())
+                .collect::<HashSet<_>>(),
+        );
+        *self.countries.lock().unwrap() = Some((Instant::now(), countries.clone()));
+        Ok(countries)
+    }
+
+    /// Whether the country is tracked by huismetbenen.
+    pub async fn is_tracked(&self, country: &str) -> Result<bool> {
+        Ok(self.countries().await?.contains(&country.to_uppercase()))
+    }
+
+    /// The country #1s statistics of the player, or `None` if they have none.
+    pub async fn player(&self, country: &str, user_id: u64, mode: Mode) -> Result<Option<Player>> {
+        self.get(
+            Priority::Interactive,
+            &format!("player/{}/{}", country.to_lowercase(), user_id),
+            &[("mode", mode.as_str_new_site().to_owned())],
+        )
+        .await
+    }
+
+    /// The player's country #1s, best pp first, one page at a time (starting from 1).
+    pub async fn country_firsts(
+        &self,
+        country: &str,
+        user_id: u64,
+        mode: Mode,
+        page: u32,
+    ) -> Result<Vec<CountryFirst>> {
+        Ok(self
+            .get(
+                Priority::Interactive,
+                &format!("player/{}/{}/topranks", country.to_lowercase(), user_id),
+                &[
+                    ("page", page.to_string()),
+                    ("mode", mode.as_str_new_site().to_owned()),
+                    ("sort", "pp".to_owned()),
+                    ("order", "desc".to_owned()),
+                ],
+            )
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// The snipes in the country since the given time, oldest first.
+    pub async fn snipes_since(
+        &self,
+        country: &str,
+        mode: Mode,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Snipe>> {
+        let mut snipes: Vec<Snipe> = self
+            .get(
+                Priority::Background,
+                &format!("changes/new/{}", country.to_lowercase()),
+                &[
+                    ("since", since.to_rfc3339()),
+                    ("mode", mode.as_str_new_site().to_owned()),
+                ],
+            )
+            .await?
+            .unwrap_or_default();
+        snipes.retain(|s| s.date > since);
+        snipes.sort_by_key(|s| s.date);
+        Ok(snipes)
+    }
+}
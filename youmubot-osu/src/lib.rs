@@ -1,19 +1,104 @@
 use std::convert::TryInto;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Instant;
 
+pub use backend::OsuApi;
+use backend::RosuApi;
+pub use error::ClientError;
 use models::*;
+use ratelimit::{Priority, RateLimitStats, RateLimiter};
 use request::builders::*;
 use request::*;
+use resilience::{CircuitBreaker, ResiliencePolicy};
+use youmubot_prelude::metrics::Metric;
 use youmubot_prelude::*;
 
+mod api;
+pub mod backend;
+pub mod collection;
 pub mod discord;
+mod error;
+pub mod huismetbenen;
+#[cfg(any(test, feature = "testing"))]
+pub mod mock;
 pub mod models;
+pub mod ordr;
+pub mod osucollector;
+pub mod osutrack;
+pub mod ratelimit;
+pub mod render;
+pub mod replay;
 pub mod request;
+pub mod resilience;
+pub mod server;
+pub mod sheets;
+pub mod twitch;
+
+/// How often the API token is renewed. Tokens are valid for a day.
+const TOKEN_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(12 * 60 * 60);
+/// How long API tokens are valid for.
+const TOKEN_LIFETIME: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+/// A token rejected this soon after being renewed is not renewed again.
+const MIN_REAUTH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// The number of players in a page of the rankings.
+pub const RANKINGS_PAGE_SIZE: u32 = 50;
+/// The API only shows the top 10000 players of the rankings.
+const RANKINGS_MAX_RANK: u32 = 10_000;
+
+/// osu! API requests, by priority and outcome.
+const API_REQUESTS: Metric = Metric::counter(
+    "youmubot_osu_api_requests_total",
+    "osu! API requests sent, by priority and outcome.",
+);
+/// How long osu! API requests take, including waiting for the rate limiter and retries.
+const API_REQUEST_DURATION: Metric = Metric::histogram(
+    "youmubot_osu_api_request_duration_seconds",
+    "How long osu! API requests take, including rate limiting and retries, by priority.",
+);
+/// osu! API requests refused for sending too many.
+const API_RATE_LIMITED: Metric = Metric::counter(
+    "youmubot_osu_api_rate_limited_total",
+    "osu! API requests refused by the API for sending too many.",
+);
 
 /// Client is the client that will perform calls to the osu! api server.
 #[derive(Clone)]
 pub struct Client {
-    rosu: Arc<rosu_v2::Osu>,
+    connection: Option<Arc<Connection>>,
+    backend: Arc<dyn OsuApi>,
+    last_auth: Arc<tokio::sync::Mutex<Instant>>,
+    limiter: Arc<RateLimiter>,
+    priority: Priority,
+    policy: ResiliencePolicy,
+    breaker: Arc<CircuitBreaker>,
+    metrics: Metrics,
+}
+
+/// The connection to the osu! API, missing from clients built on another backend.
+struct Connection {
+    rosu: Arc<RosuApi>,
+    credentials: Arc<Credentials>,
+    api: api::RawApi,
+}
+
+/// The credentials used to get API tokens.
+struct Credentials {
+    client_id: u64,
+    client_secret: String,
+    timeout: std::time::Duration,
+}
+
+impl Credentials {
+    async fn authenticate(&self) -> Result<rosu_v2::Osu, rosu_v2::error::OsuError> {
+        rosu_v2::OsuBuilder::new()
+            .client_id(self.client_id)
+            .client_secret(self.client_secret.clone())
+            .timeout(self.timeout)
+            .build()
+            .await
+    }
 }
 
 pub fn vec_try_into<U, T: std::convert::TryFrom<U>>(v: Vec<U>) -> Result<Vec<T>, T::Error> {
@@ -27,53 +112,377 @@ pub fn vec_try_into<U, T: std::convert::TryFrom<U>>(v: Vec<U>) -> Result<Vec<T>,
 }
 
 impl Client {
-    /// Create a new client from the given API key.
-    pub async fn new(client_id: u64, client_secret: impl Into<String>) -> Result<Client> {
-        let rosu = rosu_v2::OsuBuilder::new()
-            .client_id(client_id)
-            .client_secret(client_secret)
-            .build()
-            .await?;
-        Ok(Client {
-            rosu: Arc::new(rosu),
-        })
+    /// Create a new client from the given API key, sending at most `requests_per_minute` requests.
+    /// Failing requests are handled according to `policy`.
+    ///
+    /// Fails if the client cannot authenticate, see [ClientError::is_transient] for whether to try again.
+    pub async fn new(
+        client_id: u64,
+        client_secret: impl Into<String>,
+        requests_per_minute: u32,
+        policy: ResiliencePolicy,
+    ) -> Result<Client, ClientError> {
+        let credentials = Credentials {
+            client_id,
+            client_secret: client_secret.into(),
+            timeout: policy.timeout,
+        };
+        let rosu = Arc::new(RosuApi::new(credentials.authenticate().await?));
+        let credentials = Arc::new(credentials);
+        let connection = Connection {
+            rosu: rosu.clone(),
+            api: api::RawApi::new(credentials.clone(), policy.timeout),
+            credentials,
+        };
+        let mut client = Client::with_backend(rosu, requests_per_minute, policy);
+        client.connection = Some(Arc::new(connection));
+        Ok(client)
+    }
+
+    /// Create a client sending its requests to `backend`, e.g. a [mock](crate::mock) in tests.
+    /// Requests outside of [OsuApi] fail with [ClientError::Unsupported].
+    pub fn with_backend(
+        backend: Arc<dyn OsuApi>,
+        requests_per_minute: u32,
+        policy: ResiliencePolicy,
+    ) -> Client {
+        Client {
+            connection: None,
+            backend,
+            last_auth: Arc::new(tokio::sync::Mutex::new(Instant::now())),
+            limiter: Arc::new(RateLimiter::new(requests_per_minute)),
+            priority: Priority::Interactive,
+            policy,
+            breaker: Arc::new(CircuitBreaker::default()),
+            metrics: Metrics::default(),
+        }
+    }
+
+    /// A client of the private server, with its own rate limit.
+    /// Beatmaps are still requested from this client's backend, and the requests outside of
+    /// [OsuApi] fail with [ClientError::Unsupported].
+    pub fn with_server(
+        &self,
+        profile: Arc<server::ServerProfile>,
+        requests_per_minute: u32,
+    ) -> Client {
+        let api = server::PrivateServerApi::new(profile, self.backend.clone(), self.policy.timeout);
+        Client::with_backend(Arc::new(api), requests_per_minute, self.policy)
+            .with_metrics(self.metrics.clone())
+    }
+
+    /// Record the requests into the given metrics.
+    pub fn with_metrics(self, metrics: Metrics) -> Client {
+        Client { metrics, ..self }
+    }
+
+    /// Get a client sharing the same rate limit, whose requests have the given priority.
+    pub fn with_priority(&self, priority: Priority) -> Client {
+        Client {
+            priority,
+            ..self.clone()
+        }
+    }
+
+    /// Get the statistics of the rate limiter.
+    pub fn rate_limit_stats(&self) -> RateLimitStats {
+        self.limiter.stats()
+    }
+
+    fn connection(&self) -> Result<&Connection, ClientError> {
+        self.connection.as_deref().ok_or(ClientError::Unsupported)
     }
 
+    /// Wait for the rate limiter, then return the backend.
+    async fn backend(&self) -> &dyn OsuApi {
+        self.limiter.acquire(self.priority).await;
+        &*self.backend
+    }
+
+    /// Wait for the rate limiter, then return the API client.
+    pub(crate) async fn rosu(&self) -> Result<Arc<rosu_v2::Osu>, ClientError> {
+        let connection = self.connection()?;
+        self.limiter.acquire(self.priority).await;
+        Ok(connection.rosu.osu())
+    }
+
+    /// Wait for the rate limiter, then return the client for the endpoints rosu-v2 lacks.
+    async fn api(&self) -> Result<&api::RawApi, ClientError> {
+        let connection = self.connection()?;
+        self.limiter.acquire(self.priority).await;
+        Ok(&connection.api)
+    }
+
+    /// Get a new API token, replacing the current one.
+    /// Unless `force` is set, a token renewed less than a minute ago is kept, so that
+    /// requests rejected at the same time only renew it once.
+    pub async fn reauthenticate(&self, force: bool) -> Result<(), ClientError> {
+        let Some(connection) = &self.connection else {
+            return Ok(());
+        };
+        let mut last_auth = self.last_auth.lock().await;
+        if !force && last_auth.elapsed() < MIN_REAUTH_INTERVAL {
+            return Ok(());
+        }
+        connection
+            .rosu
+            .replace(connection.credentials.authenticate().await?);
+        connection.api.reset_token().await;
+        *last_auth = Instant::now();
+        Ok(())
+    }
+
+    /// Whether the API token is still valid, i.e. it was renewed less than a day ago.
+    pub async fn token_valid(&self) -> bool {
+        self.last_auth.lock().await.elapsed() < TOKEN_LIFETIME
+    }
+
+    /// Renew the API token periodically, before it expires.
+    pub async fn run_token_refresh(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(TOKEN_REFRESH_INTERVAL);
+        interval.tick().await; // The first tick completes immediately.
+        loop {
+            interval.tick().await;
+            match self.reauthenticate(true).await {
+                Ok(()) => tracing::info!("osu: API token renewed"),
+                Err(e) => tracing::error!("osu: cannot renew API token: {}", e),
+            }
+        }
+    }
+
+    /// Send the request, recording its outcome and duration.
+    async fn send<T, F>(&self, request: impl Fn() -> F) -> Result<T, ClientError>
+    where
+        F: Future<Output = Result<T, ClientError>>,
+    {
+        let started = Instant::now();
+        let result = self.send_with_retries(request).await;
+        let priority = self.priority.name();
+        let outcome = match &result {
+            Ok(_) => "ok",
+            Err(e) => e.kind(),
+        };
+        self.metrics.inc(
+            &API_REQUESTS,
+            &[("priority", priority), ("outcome", outcome)],
+        );
+        self.metrics.observe(
+            &API_REQUEST_DURATION,
+            &[("priority", priority)],
+            started.elapsed(),
+        );
+        result
+    }
+
+    /// Send the request, unless the circuit breaker is open.
+    ///
+    /// Requests failing with a timeout or a server error are retried with backoff.
+    /// If the token was rejected, a new one is requested and the request is sent once more.
+    async fn send_with_retries<T, F>(&self, request: impl Fn() -> F) -> Result<T, ClientError>
+    where
+        F: Future<Output = Result<T, ClientError>>,
+    {
+        if !self.breaker.allows() {
+            return Err(ClientError::Unavailable);
+        }
+        let mut reauthenticated = false;
+        let mut retries = 0;
+        loop {
+            let result = request().await;
+            if let Err(ClientError::RateLimited { .. }) = &result {
+                self.metrics
+                    .inc(&API_RATE_LIMITED, &[("priority", self.priority.name())]);
+            }
+            match result {
+                Err(ClientError::AuthExpired) if !reauthenticated => {
+                    tracing::warn!("osu: API token rejected, renewing");
+                    reauthenticated = true;
+                    self.reauthenticate(false).await?;
+                }
+                Err(e) if e.is_transient() => {
+                    if retries >= self.policy.max_retries {
+                        self.breaker.record_failure(&self.policy);
+                        return Err(e);
+                    }
+                    let wait = self.policy.backoff_for(retries);
+                    tracing::warn!(
+                        retry = retries + 1,
+                        "osu: request failed ({}), retrying in {:?}",
+                        e,
+                        wait
+                    );
+                    retries += 1;
+                    tokio::time::sleep(wait).await;
+                }
+                r => {
+                    self.breaker.record_success();
+                    return r;
+                }
+            }
+        }
+    }
+
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "beatmaps",
+            priority = self.priority.name(),
+            kind = ?kind
+        )
+    )]
     pub async fn beatmaps(
         &self,
         kind: BeatmapRequestKind,
         f: impl FnOnce(&mut BeatmapRequestBuilder) -> &mut BeatmapRequestBuilder,
-    ) -> Result<Vec<Beatmap>> {
+    ) -> Result<Vec<Beatmap>, ClientError> {
         let mut r = BeatmapRequestBuilder::new(kind);
         f(&mut r);
-        r.build(self).await
+        self.send(|| async { self.backend().await.beatmaps(r.clone()).await })
+            .await
+    }
+
+    /// Fetch many beatmaps by their ids, in as few requests as possible.
+    /// Beatmaps that cannot be found are left out.
+    ///
+    /// The beatmaps are returned in their own mode, i.e. converts are not computed.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "beatmaps_by_ids",
+            priority = self.priority.name(),
+            count = ids.len()
+        )
+    )]
+    pub async fn beatmaps_by_ids(&self, ids: &[u64]) -> Result<Vec<Beatmap>, ClientError> {
+        /// The maximum number of beatmaps the API returns in one request.
+        const BATCH_SIZE: usize = 50;
+        let mut beatmaps = Vec::with_capacity(ids.len());
+        for batch in ids.chunks(BATCH_SIZE) {
+            let fetched = self
+                .send(move || async move {
+                    let rosu = self.rosu().await?;
+                    let bms = rosu.beatmaps(batch.iter().map(|&id| id as u32)).await?;
+                    Ok(bms)
+                })
+                .await;
+            let mut single = Vec::new();
+            match fetched {
+                Ok(bms) => {
+                    for mut bm in bms {
+                        match bm.mapset.take() {
+                            Some(set) => beatmaps.push(Beatmap::from_rosu(bm, &set)),
+                            None => single.push(bm.map_id as u64),
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "osu: bulk beatmap request failed, fetching one by one: {}",
+                        e
+                    );
+                    single.extend_from_slice(batch);
+                }
+            }
+            for id in single {
+                beatmaps.extend(
+                    self.beatmaps(BeatmapRequestKind::Beatmap(id), |f| f)
+                        .await?,
+                );
+            }
+        }
+        Ok(beatmaps)
     }
 
+    /// Search for beatmaps with the given query, in any ranked status.
+    /// Returns the difficulties of the first page of matching beatmapsets.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "search_beatmaps",
+            priority = self.priority.name(),
+            query = %query,
+            mode = ?mode
+        )
+    )]
+    pub async fn search_beatmaps(
+        &self,
+        query: &str,
+        mode: Option<Mode>,
+    ) -> Result<Vec<Beatmap>, ClientError> {
+        let result = self
+            .send(move || async move {
+                let rosu = self.rosu().await?;
+                let mut req = rosu.beatmapset_search().query(query).any_status();
+                if let Some(mode) = mode {
+                    req = req.mode(mode.into());
+                }
+                Ok(req.await?)
+            })
+            .await?;
+        Ok(result
+            .mapsets
+            .into_iter()
+            .flat_map(|mut set| {
+                let maps = set.maps.take().unwrap_or_default();
+                maps.into_iter()
+                    .map(|bm| Beatmap::from_rosu(bm, &set))
+                    .collect::<Vec<_>>()
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "user",
+            priority = self.priority.name(),
+            user = ?user
+        )
+    )]
     pub async fn user(
         &self,
         user: UserID,
         f: impl FnOnce(&mut UserRequestBuilder) -> &mut UserRequestBuilder,
-    ) -> Result<Option<User>, Error> {
+    ) -> Result<Option<User>, ClientError> {
         let mut r = UserRequestBuilder::new(user);
         f(&mut r);
-        r.build(self).await
+        self.send(|| async {
+            // The user and their recent events are two requests.
+            self.limiter.acquire(self.priority).await;
+            self.backend().await.user(r.clone()).await
+        })
+        .await
     }
 
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "scores",
+            priority = self.priority.name(),
+            beatmap_id = beatmap_id
+        )
+    )]
     pub async fn scores(
         &self,
         beatmap_id: u64,
         f: impl FnOnce(&mut ScoreRequestBuilder) -> &mut ScoreRequestBuilder,
-    ) -> Result<Vec<Score>, Error> {
+    ) -> Result<Vec<Score>, ClientError> {
         let mut r = ScoreRequestBuilder::new(beatmap_id);
         f(&mut r);
-        r.build(self).await
+        self.send(|| async { self.backend().await.scores(r.clone()).await })
+            .await
     }
 
     pub async fn user_best(
         &self,
         user: UserID,
         f: impl FnOnce(&mut UserScoreRequestBuilder) -> &mut UserScoreRequestBuilder,
-    ) -> Result<Vec<Score>, Error> {
+    ) -> Result<Vec<Score>, ClientError> {
         self.user_scores(UserScoreType::Best, user, f).await
     }
 
@@ -81,18 +490,558 @@ impl Client {
         &self,
         user: UserID,
         f: impl FnOnce(&mut UserScoreRequestBuilder) -> &mut UserScoreRequestBuilder,
-    ) -> Result<Vec<Score>, Error> {
+    ) -> Result<Vec<Score>, ClientError> {
         self.user_scores(UserScoreType::Recent, user, f).await
     }
 
+    /// Get the position of the user's best score on the beatmap's global leaderboard,
+    /// along with the id of that score.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "user_beatmap_position",
+            priority = self.priority.name(),
+            beatmap_id = beatmap_id,
+            user_id = user_id,
+            mode = ?mode
+        )
+    )]
+    pub async fn user_beatmap_position(
+        &self,
+        beatmap_id: u64,
+        user_id: u64,
+        mode: Mode,
+    ) -> Result<Option<(u64, u32)>, ClientError> {
+        let score = self
+            .send(move || async move {
+                let rosu = self.rosu().await?;
+                let score = rosu
+                    .beatmap_user_score(beatmap_id as u32, user_id as u32)
+                    .mode(mode.into())
+                    .await;
+                Ok(handle_not_found(score)?)
+            })
+            .await?;
+        Ok(score.map(|s| (s.score.id, s.pos as u32)))
+    }
+
+    /// Get a score by its id, along with the mode it was played in.
+    /// Legacy (stable) score ids are only unique per mode, so the mode must be given for those.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "score",
+            priority = self.priority.name(),
+            score_id = score_id,
+            mode = ?mode
+        )
+    )]
+    pub async fn score(
+        &self,
+        score_id: u64,
+        mode: Option<Mode>,
+    ) -> Result<Option<(Score, Mode)>, ClientError> {
+        let score = self
+            .send(move || async move {
+                let rosu = self.rosu().await?;
+                let req = rosu.score(score_id);
+                let score = match mode {
+                    Some(mode) => req.mode(mode.into()).await,
+                    None => req.await,
+                };
+                Ok(handle_not_found(score)?)
+            })
+            .await?;
+        Ok(score.map(|s| {
+            let mode = s.mode.into();
+            (s.into(), mode)
+        }))
+    }
+
+    /// Download and parse the replay of the given score, if it is available.
+    pub async fn replay(&self, score_id: u64, mode: Mode) -> Result<Option<replay::Replay>> {
+        let raw = match self.replay_raw(score_id, mode).await? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        replay::Replay::parse(&raw[..]).map(Some)
+    }
+
+    /// Download the replay of the given score as an `.osr` file, if it is available.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "replay_raw",
+            priority = self.priority.name(),
+            score_id = score_id,
+            mode = ?mode
+        )
+    )]
+    pub async fn replay_raw(
+        &self,
+        score_id: u64,
+        mode: Mode,
+    ) -> Result<Option<Vec<u8>>, ClientError> {
+        self.send(move || async move {
+            let rosu = self.rosu().await?;
+            let replay = rosu.replay_raw(mode.into(), score_id).await;
+            Ok(handle_not_found(replay)?)
+        })
+        .await
+    }
+
+    /// Get a multiplayer match, with all of its games.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "osu_match",
+            priority = self.priority.name(),
+            match_id = match_id
+        )
+    )]
+    pub async fn osu_match(
+        &self,
+        match_id: u64,
+    ) -> Result<Option<multiplayer::Match>, ClientError> {
+        let first = match self
+            .send(move || async move {
+                let rosu = self.rosu().await?;
+                Ok(handle_not_found(rosu.osu_match(match_id as u32).await)?)
+            })
+            .await?
+        {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        // The API only returns the first events of long matches, fetch the rest page by page.
+        let latest_event_id = first.latest_event_id;
+        let mut last_event_id = first.events.last().map(|e| e.event_id());
+        let mut rest = vec![];
+        while let Some(after) = last_event_id.filter(|&id| id < latest_event_id) {
+            let page = self
+                .send(move || async move {
+                    let rosu = self.rosu().await?;
+                    Ok(rosu.osu_match(match_id as u32).after(after).await?)
+                })
+                .await?;
+            last_event_id = page.events.last().map(|e| e.event_id());
+            if last_event_id.is_none() {
+                break;
+            }
+            rest.push(page);
+        }
+        Ok(Some(multiplayer::Match::from_rosu(first, rest)))
+    }
+
+    /// Get a page of the pp leaderboard, in the given country (by its country code) if any.
+    /// Pages start from 1, and have `RANKINGS_PAGE_SIZE` players each.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "pp_rankings",
+            priority = self.priority.name(),
+            mode = ?mode,
+            country = ?country,
+            page = page
+        )
+    )]
+    pub async fn pp_rankings(
+        &self,
+        mode: Mode,
+        country: Option<&str>,
+        page: u32,
+    ) -> Result<Vec<RankingEntry>, ClientError> {
+        let rankings = self
+            .send(move || async move {
+                let rosu = self.rosu().await?;
+                let mut r = rosu.performance_rankings(mode.into()).page(page);
+                if let Some(country) = country {
+                    r = r.country(country);
+                }
+                Ok(r.await?)
+            })
+            .await?;
+        let first_rank = (page.max(1) - 1) * RANKINGS_PAGE_SIZE;
+        Ok(rankings
+            .ranking
+            .into_iter()
+            .enumerate()
+            .map(|(i, u)| RankingEntry::from_rosu(first_rank + i as u32 + 1, u))
+            .collect())
+    }
+
+    /// Get the top `count` players of the pp leaderboard, going through as many pages as needed.
+    pub async fn pp_rankings_top(
+        &self,
+        mode: Mode,
+        country: Option<&str>,
+        count: u32,
+    ) -> Result<Vec<RankingEntry>, ClientError> {
+        let count = count.min(RANKINGS_MAX_RANK) as usize;
+        let mut res = Vec::with_capacity(count);
+        let mut page = 1;
+        while res.len() < count {
+            let entries = self.pp_rankings(mode, country, page).await?;
+            let last_page = entries.len() < RANKINGS_PAGE_SIZE as usize;
+            res.extend(entries);
+            if last_page {
+                break;
+            }
+            page += 1;
+        }
+        res.truncate(count);
+        Ok(res)
+    }
+
+    /// Get the player at the given rank of the pp leaderboard, e.g. to find what pp rank N has.
+    /// Returns `None` past the last player, or past the ranks the API can show.
+    pub async fn pp_ranking_at(
+        &self,
+        mode: Mode,
+        country: Option<&str>,
+        rank: u32,
+    ) -> Result<Option<RankingEntry>, ClientError> {
+        if rank == 0 || rank > RANKINGS_MAX_RANK {
+            return Ok(None);
+        }
+        let page = (rank - 1) / RANKINGS_PAGE_SIZE + 1;
+        let index = ((rank - 1) % RANKINGS_PAGE_SIZE) as usize;
+        Ok(self
+            .pp_rankings(mode, country, page)
+            .await?
+            .into_iter()
+            .nth(index))
+    }
+
+    /// Get the beatmapset events of the given kinds (all kinds if empty) newer than
+    /// the event `since_id`, oldest first.
+    ///
+    /// Without `since_id`, only the latest page of events is returned.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "beatmapset_events",
+            priority = self.priority.name(),
+            since_id = since_id
+        )
+    )]
+    pub async fn beatmapset_events(
+        &self,
+        kinds: &[beatmapset_events::BeatmapsetEventKind],
+        since_id: Option<u64>,
+    ) -> Result<Vec<beatmapset_events::BeatmapsetEvent>, ClientError> {
+        /// Stop going back in time after this many pages, if `since_id` is too old.
+        const MAX_PAGES: u32 = 10;
+        let mut events = vec![];
+        for page in 1..=MAX_PAGES {
+            let mut query = kinds
+                .iter()
+                .map(|k| ("types[]", k.api_name().to_owned()))
+                .collect::<Vec<_>>();
+            query.push(("sort", "id_desc".to_owned()));
+            query.push(("page", page.to_string()));
+            let query = &query;
+            let fetched = self
+                .send(move || async move {
+                    self.api()
+                        .await?
+                        .get::<api::raw::RawBeatmapsetEvents>("beatmapsets/events", query)
+                        .await
+                })
+                .await?
+                .events;
+            let reached_end = fetched.is_empty()
+                || since_id.is_none()
+                || fetched.iter().any(|e| Some(e.id) <= since_id);
+            events.extend(
+                fetched
+                    .into_iter()
+                    .filter(|e| since_id.map_or(true, |since| e.id > since))
+                    .filter_map(|e| e.into_event()),
+            );
+            if reached_end {
+                break;
+            }
+        }
+        events.reverse();
+        Ok(events)
+    }
+
+    /// Get the latest news articles, newest first.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(request = "news", priority = self.priority.name())
+    )]
+    pub async fn news(&self, limit: u32) -> Result<Vec<news::NewsPost>, ClientError> {
+        let query = &[("limit", limit.to_string())];
+        let news = self
+            .send(move || async move {
+                self.api()
+                    .await?
+                    .get::<api::raw::RawNews>("news", query)
+                    .await
+            })
+            .await?;
+        Ok(news
+            .news_posts
+            .into_iter()
+            .map(news::NewsPost::from)
+            .collect())
+    }
+
+    /// Get the latest releases of the update stream (e.g. `lazer`), newest first.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(request = "changelog", priority = self.priority.name(), stream = stream)
+    )]
+    pub async fn changelog(&self, stream: &str) -> Result<Vec<news::ChangelogBuild>, ClientError> {
+        let query = &[("stream", stream.to_owned())];
+        let changelog = self
+            .send(move || async move {
+                self.api()
+                    .await?
+                    .get::<api::raw::RawChangelog>("changelog", query)
+                    .await
+            })
+            .await?;
+        Ok(changelog
+            .builds
+            .into_iter()
+            .map(news::ChangelogBuild::from)
+            .collect())
+    }
+
+    /// Search the osu! wiki, best matches first.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(request = "wiki_search", priority = self.priority.name(), query = query)
+    )]
+    pub async fn wiki_search(&self, query: &str) -> Result<Vec<wiki::WikiPage>, ClientError> {
+        let query = &[
+            ("mode", "wiki_page".to_owned()),
+            ("query", query.to_owned()),
+        ];
+        let results = self
+            .send(move || async move {
+                self.api()
+                    .await?
+                    .get::<api::raw::RawWikiSearch>("search", query)
+                    .await
+            })
+            .await?;
+        Ok(results
+            .wiki_page
+            .map(|r| r.data.into_iter().map(wiki::WikiPage::from).collect())
+            .unwrap_or_default())
+    }
+
+    /// Get the wiki article at `path`, in the given language (e.g. `en`).
+    /// Articles that are not translated come in English.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(request = "wiki_page", priority = self.priority.name(), locale = locale, path = path)
+    )]
+    pub async fn wiki_page(
+        &self,
+        locale: &str,
+        path: &str,
+    ) -> Result<Option<wiki::WikiPage>, ClientError> {
+        let page = self
+            .send(move || async move {
+                let path = format!("wiki/{}/{}", locale, path);
+                self.api()
+                    .await?
+                    .get::<api::raw::RawWikiPage>(&path, &[])
+                    .await
+            })
+            .await;
+        match page {
+            Ok(page) => Ok(Some(page.into())),
+            Err(ClientError::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get a lazer multiplayer room or playlist, with its playlist.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "room",
+            priority = self.priority.name(),
+            room_id = room_id
+        )
+    )]
+    pub async fn room(&self, room_id: u64) -> Result<Option<multiplayer::Room>, ClientError> {
+        let room = self
+            .send(move || async move {
+                let path = format!("rooms/{}", room_id);
+                self.api().await?.get::<api::raw::RawRoom>(&path, &[]).await
+            })
+            .await;
+        match room {
+            Ok(room) => Ok(Some(room.into())),
+            Err(ClientError::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get the rooms that are currently open, of the given category if any.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "active_rooms",
+            priority = self.priority.name(),
+            category = ?category
+        )
+    )]
+    pub async fn active_rooms(
+        &self,
+        category: Option<multiplayer::RoomCategory>,
+    ) -> Result<Vec<multiplayer::Room>, ClientError> {
+        let mut query = vec![("mode", "active".to_owned())];
+        if let Some(category) = category {
+            // Reuse the serde names of the categories, e.g. `daily_challenge`.
+            let name = serde_json::to_value(category)
+                .ok()
+                .and_then(|v| v.as_str().map(|v| v.to_owned()))
+                .unwrap_or_default();
+            query.push(("category", name));
+        }
+        let query = &query;
+        let rooms = self
+            .send(move || async move {
+                self.api()
+                    .await?
+                    .get::<Vec<api::raw::RawRoom>>("rooms", &query)
+                    .await
+            })
+            .await?;
+        Ok(rooms
+            .into_iter()
+            .map(multiplayer::Room::from)
+            .filter(|r| category.map_or(true, |c| r.category == c))
+            .collect())
+    }
+
+    /// Get the best scores on a playlist item of a room, at most `limit` of them.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "playlist_scores",
+            priority = self.priority.name(),
+            room_id = room_id,
+            playlist_id = playlist_id
+        )
+    )]
+    pub async fn playlist_scores(
+        &self,
+        room_id: u64,
+        playlist_id: u64,
+        limit: u32,
+    ) -> Result<Vec<multiplayer::PlaylistScore>, ClientError> {
+        let scores = self
+            .send(move || async move {
+                let path = format!("rooms/{}/playlist/{}/scores", room_id, playlist_id);
+                let query = [("limit", limit.to_string())];
+                self.api()
+                    .await?
+                    .get::<api::raw::RawPlaylistScores>(&path, &query)
+                    .await
+            })
+            .await?;
+        Ok(scores
+            .scores
+            .into_iter()
+            .map(multiplayer::PlaylistScore::from)
+            .collect())
+    }
+
+    /// Get today's daily challenge, with its playlist, if there is one running.
+    pub async fn daily_challenge(&self) -> Result<Option<multiplayer::Room>, ClientError> {
+        let Some(room) = self
+            .active_rooms(Some(multiplayer::RoomCategory::DailyChallenge))
+            .await?
+            .into_iter()
+            .next()
+        else {
+            return Ok(None);
+        };
+        // The listed rooms do not come with their playlist.
+        if !room.playlist.is_empty() {
+            return Ok(Some(room));
+        }
+        self.room(room.id).await
+    }
+
+    /// Get the user's best score on a playlist item of a room, if they played it.
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "user_playlist_score",
+            priority = self.priority.name(),
+            room_id = room_id,
+            playlist_id = playlist_id,
+            user_id = user_id
+        )
+    )]
+    pub async fn user_playlist_score(
+        &self,
+        room_id: u64,
+        playlist_id: u64,
+        user_id: u64,
+    ) -> Result<Option<multiplayer::PlaylistScore>, ClientError> {
+        let score = self
+            .send(move || async move {
+                let path = format!(
+                    "rooms/{}/playlist/{}/scores/users/{}",
+                    room_id, playlist_id, user_id
+                );
+                self.api()
+                    .await?
+                    .get::<api::raw::RawPlaylistScore>(&path, &[])
+                    .await
+            })
+            .await;
+        match score {
+            Ok(score) => Ok(Some(score.into())),
+            Err(ClientError::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[tracing::instrument(
+        name = "osu_api",
+        skip_all,
+        fields(
+            request = "user_scores",
+            priority = self.priority.name(),
+            kind = ?u,
+            user = ?user
+        )
+    )]
     async fn user_scores(
         &self,
         u: UserScoreType,
         user: UserID,
         f: impl FnOnce(&mut UserScoreRequestBuilder) -> &mut UserScoreRequestBuilder,
-    ) -> Result<Vec<Score>, Error> {
+    ) -> Result<Vec<Score>, ClientError> {
         let mut r = UserScoreRequestBuilder::new(u, user);
         f(&mut r);
-        r.build(self).await
+        self.send(|| async { self.backend().await.user_scores(r.clone()).await })
+            .await
     }
 }
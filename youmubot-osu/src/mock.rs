@@ -0,0 +1,233 @@
+//! A mock of the osu! API, to test code built on the [Client] without network access.
+//!
+//! Beatmaps, users and scores added to the [MockApi] are served by the clients built with [MockApi::client].
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use youmubot_prelude::async_trait;
+
+use crate::{
+    backend::OsuApi,
+    models::{Beatmap, Mode, Score, User},
+    request::{builders::*, BeatmapRequestKind, UserID},
+    resilience::ResiliencePolicy,
+    Client, ClientError,
+};
+
+/// An in-memory osu! API.
+#[derive(Debug, Default)]
+pub struct MockApi {
+    beatmaps: Mutex<Vec<Beatmap>>,
+    users: Mutex<Vec<User>>,
+    scores: Mutex<Vec<(Mode, Score)>>,
+    failures: Mutex<VecDeque<ClientError>>,
+    requests: Mutex<Vec<&'static str>>,
+}
+
+impl MockApi {
+    /// A client sending its requests to the mock, without rate limits.
+    pub fn client(self: &Arc<Self>) -> Client {
+        Client::with_backend(self.clone(), u32::MAX, ResiliencePolicy::default())
+    }
+
+    pub fn add_beatmap(&self, beatmap: Beatmap) {
+        self.beatmaps.lock().unwrap().push(beatmap);
+    }
+
+    pub fn add_user(&self, user: User) {
+        self.users.lock().unwrap().push(user);
+    }
+
+    /// Add a score, set in the given mode.
+    pub fn add_score(&self, mode: Mode, score: Score) {
+        self.scores.lock().unwrap().push((mode, score));
+    }
+
+    /// Fail the next request with the given error, e.g. to test retries.
+    /// Queued errors are returned in order, one per request.
+    pub fn fail_next(&self, error: ClientError) {
+        self.failures.lock().unwrap().push_back(error);
+    }
+
+    /// The names of the requests received so far, e.g. `user_scores`.
+    pub fn requests(&self) -> Vec<&'static str> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Record the request, failing it if a failure is queued.
+    fn receive(&self, name: &'static str) -> Result<(), ClientError> {
+        self.requests.lock().unwrap().push(name);
+        match self.failures.lock().unwrap().pop_front() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn user_id(&self, user: &UserID) -> Option<u64> {
+        match user {
+            UserID::ID(id) => Some(*id),
+            UserID::Username(name) => self
+                .users
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|u| u.username.eq_ignore_ascii_case(name))
+                .map(|u| u.id),
+        }
+    }
+}
+
+#[async_trait]
+impl OsuApi for MockApi {
+    async fn beatmaps(&self, request: BeatmapRequestBuilder) -> Result<Vec<Beatmap>, ClientError> {
+        self.receive("beatmaps")?;
+        let beatmaps = self.beatmaps.lock().unwrap();
+        Ok(beatmaps
+            .iter()
+            .filter(|b| match &request.kind {
+                BeatmapRequestKind::Beatmap(id) => b.beatmap_id == *id,
+                BeatmapRequestKind::Beatmapset(id) => b.beatmapset_id == *id,
+                BeatmapRequestKind::BeatmapHash(hash) => b.file_hash == *hash,
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn user(&self, request: UserRequestBuilder) -> Result<Option<User>, ClientError> {
+        self.receive("user")?;
+        let Some(id) = self.user_id(&request.user) else {
+            return Ok(None);
+        };
+        let users = self.users.lock().unwrap();
+        Ok(users.iter().find(|u| u.id == id).cloned())
+    }
+
+    async fn scores(&self, request: ScoreRequestBuilder) -> Result<Vec<Score>, ClientError> {
+        self.receive("scores")?;
+        let user = match &request.user {
+            Some(u) => Some(self.user_id(u).ok_or(ClientError::NotFound)?),
+            None => None,
+        };
+        let mut scores = self
+            .scores
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(mode, s)| {
+                s.beatmap_id == request.beatmap_id
+                    && request.mode.map_or(true, |m| m == *mode)
+                    && user.map_or(true, |u| u == s.user_id)
+                    && request.mods.map_or(true, |m| s.mods.contains(m))
+            })
+            .map(|(_, s)| s.clone())
+            .collect::<Vec<_>>();
+        scores.sort_by_key(|s| std::cmp::Reverse(s.display_score(true)));
+        scores.truncate(request.limit.unwrap_or(50) as usize);
+        Ok(scores)
+    }
+
+    async fn user_scores(
+        &self,
+        request: UserScoreRequestBuilder,
+    ) -> Result<Vec<Score>, ClientError> {
+        self.receive("user_scores")?;
+        let user = self.user_id(&request.user).ok_or(ClientError::NotFound)?;
+        let mode = request.mode.unwrap_or(Mode::Std);
+        let mut scores = self
+            .scores
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(m, s)| *m == mode && s.user_id == user)
+            .map(|(_, s)| s.clone())
+            .collect::<Vec<_>>();
+        match request.score_type {
+            UserScoreType::Recent => scores.sort_by_key(|s| std::cmp::Reverse(s.date)),
+            UserScoreType::Best => {
+                scores.retain(|s| s.pp.is_some());
+                scores.sort_by(|a, b| b.pp.partial_cmp(&a.pp).unwrap());
+            }
+        }
+        scores.truncate(request.limit.unwrap_or(100) as usize);
+        Ok(scores)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::models::{Mods, Rank};
+
+    fn score(user_id: u64, beatmap_id: u64, pp: f64, day: u32) -> Score {
+        Score {
+            id: Some(beatmap_id * 100 + user_id),
+            user_id,
+            date: Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap(),
+            replay_available: false,
+            beatmap_id,
+            score: None,
+            normalized_score: (pp * 1000.0) as u32,
+            pp: Some(pp),
+            rank: Rank::S,
+            mods: Mods::NOMOD,
+            count_300: 100,
+            count_100: 0,
+            count_50: 0,
+            count_miss: 0,
+            count_katu: 0,
+            count_geki: 0,
+            max_combo: 100,
+            perfect: true,
+            lazer_build_id: None,
+            mod_settings: Default::default(),
+            global_rank: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_scores() {
+        let api = Arc::new(MockApi::default());
+        api.add_score(Mode::Std, score(1, 10, 100.0, 3));
+        api.add_score(Mode::Std, score(1, 11, 300.0, 1));
+        api.add_score(Mode::Std, score(1, 12, 200.0, 2));
+        api.add_score(Mode::Taiko, score(1, 13, 400.0, 4));
+        let client = api.client();
+
+        let best = client
+            .user_best(UserID::ID(1), |f| f.limit(2))
+            .await
+            .unwrap();
+        assert_eq!(
+            best.iter().map(|s| s.beatmap_id).collect::<Vec<_>>(),
+            [11, 12]
+        );
+        let recent = client
+            .user_recent(UserID::ID(1), |f| f.mode(Mode::Std))
+            .await
+            .unwrap();
+        assert_eq!(
+            recent.iter().map(|s| s.beatmap_id).collect::<Vec<_>>(),
+            [10, 12, 11]
+        );
+        assert_eq!(api.requests(), ["user_scores", "user_scores"]);
+    }
+
+    #[tokio::test]
+    async fn test_retries() {
+        let api = Arc::new(MockApi::default());
+        api.add_score(Mode::Std, score(1, 10, 100.0, 1));
+        api.fail_next(ClientError::Server { status: 502 });
+        let client = api.client();
+
+        let best = client.user_best(UserID::ID(1), |f| f).await.unwrap();
+        assert_eq!(best.len(), 1);
+        assert_eq!(api.requests().len(), 2);
+        assert!(matches!(client.user(UserID::ID(1), |f| f).await, Ok(None)));
+        assert!(matches!(
+            client.replay_raw(1, Mode::Std).await,
+            Err(ClientError::Unsupported)
+        ));
+    }
+}
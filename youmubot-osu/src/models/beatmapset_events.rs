@@ -0,0 +1,61 @@
+//! Models of the events in the ranking process of beatmapsets.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A change in the ranking process of a beatmapset, e.g. a nomination.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BeatmapsetEvent {
+    pub id: u64,
+    pub kind: BeatmapsetEventKind,
+    pub created_at: DateTime<Utc>,
+    /// The user that caused the event, e.g. the nominator. `None` for automatic events.
+    pub user_id: Option<u64>,
+    pub beatmapset: BeatmapsetInfo,
+}
+
+/// The kinds of beatmapset events.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BeatmapsetEventKind {
+    Nominate,
+    Qualify,
+    Disqualify,
+    NominationReset,
+    Rank,
+    Love,
+    RemoveFromLoved,
+    #[serde(other)]
+    Other,
+}
+
+impl BeatmapsetEventKind {
+    /// The name of the kind in the API.
+    pub fn api_name(&self) -> &'static str {
+        match self {
+            BeatmapsetEventKind::Nominate => "nominate",
+            BeatmapsetEventKind::Qualify => "qualify",
+            BeatmapsetEventKind::Disqualify => "disqualify",
+            BeatmapsetEventKind::NominationReset => "nomination_reset",
+            BeatmapsetEventKind::Rank => "rank",
+            BeatmapsetEventKind::Love => "love",
+            BeatmapsetEventKind::RemoveFromLoved => "remove_from_loved",
+            BeatmapsetEventKind::Other => "other",
+        }
+    }
+}
+
+/// The beatmapset an event is about.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BeatmapsetInfo {
+    pub id: u64,
+    pub artist: String,
+    pub title: String,
+    pub creator: String,
+}
+
+impl BeatmapsetInfo {
+    /// Link to the beatmapset's page.
+    pub fn link(&self) -> String {
+        format!("https://osu.ppy.sh/beatmapsets/{}", self.id)
+    }
+}
@@ -4,12 +4,39 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::time::Duration;
 
+pub mod beatmapset_events;
 pub mod mods;
+pub mod multiplayer;
+pub mod news;
 pub(crate) mod rosu;
+pub mod wiki;
 
-pub use mods::Mods;
+pub use mods::{ModSettings, Mods};
 use serenity::utils::MessageBuilder;
 
+/// Renders dates as Discord timestamp markers (`<t:...>`),
+/// which every reader sees in their own locale and timezone.
+pub trait DiscordTimestamp {
+    /// The number of seconds since the Unix epoch.
+    fn unix_timestamp(&self) -> i64;
+
+    /// A relative time marker, e.g. "3 days ago".
+    fn relative_time(&self) -> String {
+        format!("<t:{}:R>", self.unix_timestamp())
+    }
+
+    /// A short date-time marker, e.g. "20 April 2021 16:20".
+    fn short_date_time(&self) -> String {
+        format!("<t:{}:f>", self.unix_timestamp())
+    }
+}
+
+impl DiscordTimestamp for DateTime<Utc> {
+    fn unix_timestamp(&self) -> i64 {
+        self.timestamp()
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum ApprovalStatus {
     Loved,
@@ -24,7 +51,7 @@ pub enum ApprovalStatus {
 impl fmt::Display for ApprovalStatus {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if let ApprovalStatus::Ranked(ref d) = self {
-            write!(f, "Ranked on {}", d.format("<t:%s>"))
+            write!(f, "Ranked {}", d.relative_time())
         } else {
             write!(f, "{:?}", self)
         }
@@ -78,22 +105,68 @@ impl Difficulty {
             (1200.0 - approach_time) / 150.0 + 5.0
         };
     }
-    fn apply_od_by_time_ratio(&mut self, rat: f64) {
-        // Convert OD to hit timing
-        let hit_timing = 79.0 - self.od * 6.0 + 0.5;
+    fn apply_od_by_time_ratio(&mut self, mode: Mode, rat: f64) {
+        // Convert OD to the 300 (Great) hit window, which differs by mode.
+        let (base, per_od) = match mode {
+            Mode::Taiko => (50.0, 3.0),
+            _ => (80.0, 6.0),
+        };
+        let hit_timing = base - self.od * per_od + 0.5;
         // Update it...
         let hit_timing = hit_timing * rat + 0.5 / rat;
         // then convert back
-        self.od = (79.0 - (hit_timing - 0.5)) / 6.0;
+        self.od = (base - (hit_timing - 0.5)) / per_od;
     }
     fn apply_length_by_ratio(&mut self, mul: u32, div: u32) {
         self.bpm = self.bpm / (mul as f64) * (div as f64); // Inverse since bpm increases while time decreases
         self.drain_length = self.drain_length * mul / div;
         self.total_length = self.total_length * mul / div;
     }
-    /// Apply mods to the given difficulty.
+
+    /// The total number of hit objects in the beatmap.
+    pub fn total_objects(&self) -> u64 {
+        self.count_normal + self.count_slider + self.count_spinner
+    }
+
+    /// Convert the difficulty of a beatmap from its original mode into the given mode.
+    ///
+    /// Only osu!std beatmaps can be converted, the rest are returned as-is.
+    pub fn convert_to(&self, original: Mode, mode: Mode) -> Difficulty {
+        let mut diff = self.clone();
+        if original != Mode::Std || mode != Mode::Mania {
+            return diff;
+        }
+        // Compute the number of keys of the converted map, as osu!stable does.
+        let objects = self.total_objects();
+        let (cs, od) = (self.cs.round(), self.od.round());
+        let slider_or_spinner_ratio = if objects == 0 {
+            0.0
+        } else {
+            (self.count_slider + self.count_spinner) as f64 / objects as f64
+        };
+        diff.cs = if slider_or_spinner_ratio < 0.2 {
+            7.0
+        } else if slider_or_spinner_ratio < 0.3 || cs >= 5.0 {
+            if od > 5.0 {
+                7.0
+            } else {
+                6.0
+            }
+        } else if slider_or_spinner_ratio > 0.6 {
+            if od > 4.0 {
+                5.0
+            } else {
+                4.0
+            }
+        } else {
+            (od + 1.0).clamp(4.0, 7.0)
+        };
+        diff
+    }
+
+    /// Apply mods to the given difficulty, played in the given mode.
     /// Note that `stars`, `aim` and `speed` cannot be calculated from this alone.
-    pub fn apply_mods(&self, mods: Mods, updated_stars: f64) -> Difficulty {
+    pub fn apply_mods(&self, mode: Mode, mods: Mods, updated_stars: f64) -> Difficulty {
         let mut diff = Difficulty {
             stars: updated_stars,
             ..self.clone()
@@ -107,28 +180,68 @@ impl Difficulty {
             let old_cs = diff.cs;
             diff.apply_everything_by_ratio(1.4);
             // CS is changed by 1.3 tho
-            diff.cs = old_cs * 1.3;
+            diff.cs = (old_cs * 1.3).min(10.0);
         }
-        if mods.contains(Mods::HT) {
-            diff.apply_ar_by_time_ratio(4.0 / 3.0);
-            diff.apply_od_by_time_ratio(4.0 / 3.0);
-            diff.apply_length_by_ratio(4, 3);
+        if mode == Mode::Mania {
+            // CS is the key count, which no mods change.
+            diff.cs = self.cs;
         }
-        if mods.contains(Mods::DT) {
-            diff.apply_ar_by_time_ratio(2.0 / 3.0);
-            diff.apply_od_by_time_ratio(2.0 / 3.0);
-            diff.apply_length_by_ratio(2, 3);
+        let speed = if mods.contains(Mods::DT) {
+            Some((2, 3))
+        } else if mods.contains(Mods::HT) {
+            Some((4, 3))
+        } else {
+            None
+        };
+        if let Some((mul, div)) = speed {
+            let rat = mul as f64 / div as f64;
+            if matches!(mode, Mode::Std | Mode::Catch) {
+                diff.apply_ar_by_time_ratio(rat);
+            }
+            // osu!mania hit windows are not affected by speed changes.
+            if mode != Mode::Mania {
+                diff.apply_od_by_time_ratio(mode, rat);
+            }
+            diff.apply_length_by_ratio(mul, div);
         }
 
         diff
     }
 
+    /// Format the mode-relevant difficulty settings, e.g. `CS**4.0**, AR**9.0**, OD**8.0**, HP**5.0**`.
+    fn format_settings(&self, mode: Mode) -> String {
+        let settings: &[(&str, f64)] = match mode {
+            Mode::Std => &[
+                ("CS", self.cs),
+                ("AR", self.ar),
+                ("OD", self.od),
+                ("HP", self.hp),
+            ],
+            Mode::Taiko => &[("OD", self.od), ("HP", self.hp)],
+            Mode::Catch => &[("CS", self.cs), ("AR", self.ar), ("HP", self.hp)],
+            Mode::Mania => &[("OD", self.od), ("HP", self.hp)],
+        };
+        let mut b = MessageBuilder::new();
+        if mode == Mode::Mania {
+            b.push_bold(format!("{}K", self.cs as u64)).push(", ");
+        }
+        for (i, (name, value)) in settings.iter().enumerate() {
+            if i > 0 {
+                b.push(", ");
+            }
+            b.push(*name).push_bold(format!("{:.1}", value));
+        }
+        b.build()
+    }
+
     /// Format the difficulty info into a short summary.
+    /// If the info is of a play, its accuracy and combo are annotated on the beatmap's short link.
     pub fn format_info<'a>(
         &self,
         mode: Mode,
         mods: Mods,
         original_beatmap: impl Into<Option<&'a Beatmap>> + 'a,
+        play: Option<(f64, u64)>,
     ) -> String {
         let original_beatmap = original_beatmap.into();
         let is_not_ranked = !matches!(
@@ -146,7 +259,15 @@ impl Difficulty {
                             original_beatmap.link(),
                             original_beatmap.download_link(false),
                             original_beatmap.download_link(true),
-                            original_beatmap.short_link(Some(mode), Some(mods))
+                            match play {
+                                Some((accuracy, combo)) => original_beatmap.annotated_link(
+                                    Some(mode),
+                                    Some(mods),
+                                    accuracy,
+                                    combo
+                                ),
+                                None => original_beatmap.short_link(Some(mode), Some(mods)),
+                            }
                         )
                     })
                     .unwrap_or("**Uploaded**".to_owned()),
@@ -173,14 +294,7 @@ impl Difficulty {
                 mode,
                 original_beatmap.map(|v| v.mode).unwrap_or(mode),
             ))
-            .push("CS")
-            .push_bold(format!("{:.1}", self.cs))
-            .push(", AR")
-            .push_bold(format!("{:.1}", self.ar))
-            .push(", OD")
-            .push_bold(format!("{:.1}", self.od))
-            .push(", HP")
-            .push_bold(format!("{:.1}", self.hp))
+            .push(self.format_settings(mode))
             .push(format!(", BPM**{}**", bpm))
             .push(", ⌛ ")
             .push({
@@ -415,6 +529,40 @@ impl Beatmap {
         )
     }
 
+    /// The short link of the beatmap, annotated with the accuracy and combo of a play,
+    /// e.g. `/b/123456+HDDT 98.50% 1200x`.
+    pub fn annotated_link(
+        &self,
+        override_mode: Option<Mode>,
+        mods: Option<Mods>,
+        accuracy: f64,
+        combo: u64,
+    ) -> String {
+        format!(
+            "{} {:.2}% {}x",
+            self.short_link(override_mode, mods),
+            accuracy,
+            combo
+        )
+    }
+
+    /// Whether the beatmap can be played in the given mode, either natively or as a convert.
+    pub fn playable_in(&self, mode: Mode) -> bool {
+        self.mode == mode || self.mode == Mode::Std
+    }
+
+    /// Whether playing the beatmap in the given mode makes it a convert.
+    pub fn is_convert(&self, mode: Mode) -> bool {
+        self.mode != mode && self.playable_in(mode)
+    }
+
+    /// Get the difficulty of the beatmap when played in `mode` (possibly converted) with `mods`.
+    pub fn difficulty_with(&self, mode: Mode, mods: Mods, updated_stars: f64) -> Difficulty {
+        self.difficulty
+            .convert_to(self.mode, mode)
+            .apply_mods(mode, mods, updated_stars)
+    }
+
     /// Link to the cover image of the beatmap.
     pub fn cover_url(&self) -> String {
         format!(
@@ -498,15 +646,57 @@ pub struct User {
     pub level: f64,
     pub pp: Option<f64>,
     pub accuracy: f64,
+    /// Global rank over the last 90 days, oldest first.
+    pub rank_history: Vec<u64>,
+    // Statistics only found in the v2 API
+    pub total_hits: u64,
+    pub max_combo: u64,
+    pub replays_watched: u64,
+    /// The profile badges, most recent first.
+    pub badges: Vec<UserBadge>,
+    /// The private server the user plays on, `None` for osu!.
+    pub server: Option<std::sync::Arc<crate::server::ServerProfile>>,
+}
+
+/// A badge shown on an user's profile, e.g. for tournament wins.
+#[derive(Clone, Debug)]
+pub struct UserBadge {
+    pub awarded_at: DateTime<Utc>,
+    pub description: String,
+    pub image_url: String,
+}
+
+/// A player on the pp leaderboard.
+#[derive(Clone, Debug)]
+pub struct RankingEntry {
+    /// The player's position on the leaderboard it was taken from, global or country.
+    pub rank: u32,
+    pub user_id: u64,
+    pub username: String,
+    pub country: String,
+    pub pp: f64,
+    pub accuracy: f64,
+    pub play_count: u64,
 }
 
 impl User {
     pub fn link(&self) -> String {
-        format!("https://osu.ppy.sh/users/{}", self.id)
+        match &self.server {
+            Some(server) => server.user_link(self.id),
+            None => format!("https://osu.ppy.sh/users/{}", self.id),
+        }
     }
 
     pub fn avatar_url(&self) -> String {
-        format!("https://a.ppy.sh/{}", self.id)
+        match &self.server {
+            Some(server) => server.avatar_url(self.id),
+            None => format!("https://a.ppy.sh/{}", self.id),
+        }
+    }
+
+    /// The progress towards the next level, between 0 and 1.
+    pub fn level_progress(&self) -> f64 {
+        self.level.fract()
     }
 }
 
@@ -571,9 +761,59 @@ pub struct Score {
     pub perfect: bool,
 
     pub lazer_build_id: Option<u32>,
+    /// The settings of the lazer mods used, e.g. the custom rate of DT.
+    pub mod_settings: ModSettings,
+    /// The score's position on the beatmap's global leaderboard, if known.
+    pub global_rank: Option<u32>,
 }
 
 impl Score {
+    /// The total score to display: the standardised lazer score if `lazer` is set,
+    /// otherwise the stable score, if the play was set on stable.
+    pub fn display_score(&self, lazer: bool) -> u64 {
+        match self.score {
+            Some(score) if !lazer => score,
+            _ => self.normalized_score as u64,
+        }
+    }
+
+    /// The mods of the score, with their custom settings.
+    pub fn mods_text(&self) -> String {
+        self.mods.to_string_with(&self.mod_settings)
+    }
+
+    /// Link to the score's page, if it was submitted.
+    pub fn link(&self) -> Option<String> {
+        self.id.map(|id| format!("https://osu.ppy.sh/scores/{}", id))
+    }
+
+    /// Link to download the score's replay, if one is available.
+    pub fn replay_link(&self) -> Option<String> {
+        self.id
+            .filter(|_| self.replay_available)
+            .map(|id| format!("https://osu.ppy.sh/scores/{}/download", id))
+    }
+
+    /// The number of judged hit objects in the play.
+    /// In osu!catch, tiny droplets are not counted.
+    pub fn judged_objects(&self, mode: Mode) -> u64 {
+        let base = self.count_300 + self.count_100 + self.count_miss;
+        match mode {
+            Mode::Std => base + self.count_50,
+            Mode::Taiko | Mode::Catch => base,
+            Mode::Mania => base + self.count_geki + self.count_katu + self.count_50,
+        }
+    }
+
+    /// How far into the map the player got, as a ratio between 0 and 1.
+    /// Mostly useful for failed plays.
+    pub fn map_progress(&self, mode: Mode, total_objects: u64) -> Option<f64> {
+        if total_objects == 0 {
+            return None;
+        }
+        Some((self.judged_objects(mode) as f64 / total_objects as f64).min(1.0))
+    }
+
     /// Given the play's mode, calculate the score's accuracy.
     pub fn accuracy(&self, mode: Mode) -> f64 {
         100.0
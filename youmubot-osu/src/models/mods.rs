@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-const LAZER_TEXT: &str = "v2";
+pub(crate) const LAZER_TEXT: &str = "v2";
 
 bitflags::bitflags! {
     /// The mods available to osu!
@@ -37,6 +37,12 @@ bitflags::bitflags! {
         const KEY3 = 1 << 27;
         const KEY2 = 1 << 28;
         const SCOREV2 = 1 << 29;
+        const MR = 1 << 30;
+
+        // Mods only available on lazer
+        const DA = 1 << 32;
+        const TC = 1 << 33;
+        const BL = 1 << 34;
 
         // Made up flags
         const LAZER = 1 << 59;
@@ -70,6 +76,10 @@ const MODS_WITH_NAMES: &[(Mods, &str)] = &[
     (Mods::SO, "SO"),
     (Mods::AP, "AP"),
     (Mods::PF, "PF"),
+    (Mods::MR, "MR"),
+    (Mods::DA, "DA"),
+    (Mods::TC, "TC"),
+    (Mods::BL, "BL"),
     (Mods::KEY1, "1K"),
     (Mods::KEY2, "2K"),
     (Mods::KEY3, "3K"),
@@ -83,6 +93,24 @@ const MODS_WITH_NAMES: &[(Mods, &str)] = &[
 ];
 
 impl Mods {
+    /// The acronyms of the mods, in display order.
+    /// Implied mods (DT under NC, SD under PF) and the lazer flag are not included.
+    pub fn acronyms(self) -> impl Iterator<Item = &'static str> {
+        self.named().map(|(_, name)| name)
+    }
+
+    fn named(self) -> impl Iterator<Item = (Mods, &'static str)> {
+        MODS_WITH_NAMES
+            .iter()
+            .filter(move |(m, _)| self.contains(*m))
+            .filter(move |(m, _)| match *m {
+                Mods::DT => !self.contains(Mods::NC),
+                Mods::SD => !self.contains(Mods::PF),
+                _ => true,
+            })
+            .copied()
+    }
+
     // Return the string length of the string representation of the mods.
     pub fn str_len(&self) -> usize {
         let s = format!("{}", self);
@@ -97,6 +125,79 @@ impl Mods {
     }
 }
 
+/// The settings of the lazer mods that can be customized, when they are not the default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModSettings {
+    /// The speed rate of DT, NC or HT.
+    pub speed_change: Option<f64>,
+    /// The circle size set by DA.
+    pub circle_size: Option<f64>,
+    /// The approach rate set by DA.
+    pub approach_rate: Option<f64>,
+    /// The overall difficulty set by DA.
+    pub overall_difficulty: Option<f64>,
+    /// The HP drain rate set by DA.
+    pub drain_rate: Option<f64>,
+}
+
+impl ModSettings {
+    /// The settings of the given mod, e.g. `1.25x` for DT, or `AR10 OD9` for DA.
+    fn describe(&self, m: Mods) -> Option<String> {
+        match m {
+            Mods::DT | Mods::NC | Mods::HT => self.speed_change.map(|v| format!("{}x", v)),
+            Mods::DA => {
+                let s = [
+                    ("CS", self.circle_size),
+                    ("AR", self.approach_rate),
+                    ("OD", self.overall_difficulty),
+                    ("HP", self.drain_rate),
+                ]
+                .into_iter()
+                .filter_map(|(name, v)| v.map(|v| format!("{}{}", name, v)))
+                .collect::<Vec<_>>()
+                .join(" ");
+                Some(s).filter(|s| !s.is_empty())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Mods {
+    /// Format the mods along with their custom settings, e.g. `+HDDT(1.25x)`.
+    pub fn to_string_with(self, settings: &ModSettings) -> String {
+        let mut s = String::new();
+        if !(self & (Mods::all() ^ Mods::LAZER)).is_empty() {
+            s.push('+');
+            for (m, name) in self.named() {
+                s.push_str(name);
+                if let Some(v) = settings.describe(m) {
+                    s.push_str(&format!("({})", v));
+                }
+            }
+        }
+        if self.contains(Mods::LAZER) {
+            s.push_str(LAZER_TEXT);
+        }
+        s
+    }
+}
+
+impl Mods {
+    /// Build the mods of a lazer score from their acronyms.
+    /// Scores without the classic mod (`CL`) are lazer scores.
+    pub(crate) fn from_acronyms<'a>(acronyms: impl IntoIterator<Item = &'a str>) -> Mods {
+        let mut res = Mods::LAZER;
+        for acronym in acronyms {
+            match acronym {
+                "CL" => res -= Mods::LAZER,
+                v => res |= v.parse::<Mods>().unwrap_or(Mods::UNKNOWN),
+            }
+        }
+        res
+    }
+}
+
 impl std::str::FromStr for Mods {
     type Err = String;
     fn from_str(mut s: &str) -> Result<Self, Self::Err> {
@@ -124,6 +225,10 @@ impl std::str::FromStr for Mods {
                 "SO" => res |= Mods::SO,
                 "AP" => res |= Mods::AP,
                 "PF" => res |= Mods::PF,
+                "MR" => res |= Mods::MR,
+                "DA" => res |= Mods::DA,
+                "TC" => res |= Mods::TC,
+                "BL" => res |= Mods::BL,
                 "1K" => res |= Mods::KEY1,
                 "2K" => res |= Mods::KEY2,
                 "3K" => res |= Mods::KEY3,
@@ -147,23 +252,6 @@ impl std::str::FromStr for Mods {
 
 impl fmt::Display for Mods {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if !(*self & (Mods::all() ^ Mods::LAZER)).is_empty() {
-            write!(f, "+")?;
-            for p in MODS_WITH_NAMES.iter() {
-                if !self.contains(p.0) {
-                    continue;
-                }
-                match p.0 {
-                    Mods::DT if self.contains(Mods::NC) => continue,
-                    Mods::SD if self.contains(Mods::PF) => continue,
-                    _ => (),
-                };
-                write!(f, "{}", p.1)?;
-            }
-        }
-        if self.contains(Mods::LAZER) {
-            write!(f, "{}", LAZER_TEXT)?;
-        }
-        Ok(())
+        write!(f, "{}", self.to_string_with(&ModSettings::default()))
     }
 }
@@ -0,0 +1,197 @@
+//! Models of multiplayer matches.
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{Mode, Mods, Rank};
+
+/// A multiplayer match.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Match {
+    pub id: u64,
+    pub name: String,
+    pub start_time: DateTime<Utc>,
+    /// `None` if the match is still running.
+    pub end_time: Option<DateTime<Utc>>,
+    /// The games played in the match, in order.
+    pub games: Vec<MatchGame>,
+    /// The usernames of the players in the match, by their user id.
+    pub usernames: HashMap<u64, String>,
+}
+
+impl Match {
+    /// Link to the match's page.
+    pub fn link(&self) -> String {
+        format!("https://osu.ppy.sh/community/matches/{}", self.id)
+    }
+
+    /// The player's username, or their id if it is unknown.
+    pub fn username(&self, user_id: u64) -> String {
+        self.usernames
+            .get(&user_id)
+            .cloned()
+            .unwrap_or_else(|| user_id.to_string())
+    }
+}
+
+/// A game (one beatmap played) in a multiplayer match.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MatchGame {
+    pub id: u64,
+    pub start_time: DateTime<Utc>,
+    /// `None` if the game was aborted or is still being played.
+    pub end_time: Option<DateTime<Utc>>,
+    pub beatmap_id: u64,
+    pub mode: Mode,
+    pub scoring: ScoringType,
+    pub team_type: TeamType,
+    /// The mods enforced on all players.
+    pub mods: Mods,
+    pub scores: Vec<MatchScore>,
+}
+
+impl MatchGame {
+    /// The total score of each team. Empty if the game is not played in teams.
+    pub fn team_scores(&self) -> Vec<(Team, u64)> {
+        let mut totals: Vec<(Team, u64)> = vec![];
+        for s in self.scores.iter().filter(|s| s.team != Team::None) {
+            match totals.iter_mut().find(|(t, _)| *t == s.team) {
+                Some((_, total)) => *total += s.score,
+                None => totals.push((s.team, s.score)),
+            }
+        }
+        totals
+    }
+
+    /// The scores, from the highest to the lowest.
+    pub fn ranked_scores(&self) -> Vec<&MatchScore> {
+        let mut scores = self.scores.iter().collect::<Vec<_>>();
+        scores.sort_by(|a, b| b.score.cmp(&a.score));
+        scores
+    }
+}
+
+/// A player's score in a multiplayer game.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MatchScore {
+    pub user_id: u64,
+    pub team: Team,
+    pub slot: u8,
+    pub score: u64,
+    /// The accuracy, between 0 and 100.
+    pub accuracy: f64,
+    pub max_combo: u64,
+    /// The player's own mods, including the ones enforced on all players.
+    pub mods: Mods,
+    pub passed: bool,
+    pub perfect: bool,
+
+    pub count_300: u64,
+    pub count_100: u64,
+    pub count_50: u64,
+    pub count_miss: u64,
+    pub count_katu: u64,
+    pub count_geki: u64,
+}
+
+/// The team of a player in a multiplayer game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Team {
+    None,
+    Blue,
+    Red,
+}
+
+/// How the winner of a multiplayer game is decided.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScoringType {
+    Score,
+    Accuracy,
+    Combo,
+    ScoreV2,
+}
+
+/// How the players of a multiplayer game are grouped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TeamType {
+    HeadToHead,
+    TagCoop,
+    TeamVS,
+    TagTeamVS,
+}
+
+/// A lazer multiplayer room or playlist, e.g. the daily challenge.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Room {
+    pub id: u64,
+    pub name: String,
+    pub category: RoomCategory,
+    /// The username of the room's host, if any.
+    pub host: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    /// `None` if the room is open until its host closes it.
+    pub ends_at: Option<DateTime<Utc>>,
+    pub participant_count: u64,
+    pub playlist: Vec<PlaylistItem>,
+}
+
+impl Room {
+    /// Link to the room's page.
+    pub fn link(&self) -> String {
+        format!("https://osu.ppy.sh/multiplayer/rooms/{}", self.id)
+    }
+
+    /// The playlist items that can still be played.
+    pub fn current_items(&self) -> impl Iterator<Item = &PlaylistItem> {
+        self.playlist.iter().filter(|i| !i.expired)
+    }
+}
+
+/// The kind of a lazer multiplayer room.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoomCategory {
+    Normal,
+    Spotlight,
+    FeaturedArtist,
+    DailyChallenge,
+    #[serde(other)]
+    Other,
+}
+
+/// A beatmap in the playlist of a room.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlaylistItem {
+    pub id: u64,
+    pub beatmap_id: u64,
+    pub mode: Mode,
+    /// The mods every player has to use.
+    pub required_mods: Mods,
+    /// Whether the item can no longer be played.
+    pub expired: bool,
+}
+
+/// A player's best score on a playlist item.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlaylistScore {
+    pub id: u64,
+    pub user_id: u64,
+    pub username: String,
+    /// The score's position on the playlist item's leaderboard.
+    pub position: Option<u32>,
+    pub total_score: u64,
+    /// The accuracy, between 0 and 100.
+    pub accuracy: f64,
+    pub max_combo: u64,
+    pub mods: Mods,
+    pub rank: Rank,
+    pub passed: bool,
+    pub pp: Option<f64>,
+    pub ended_at: DateTime<Utc>,
+
+    pub count_300: u64,
+    pub count_100: u64,
+    pub count_50: u64,
+    pub count_miss: u64,
+}
@@ -0,0 +1,60 @@
+//! Models of the osu! news and the changelogs of the game.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A news article on the osu! website.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NewsPost {
+    pub id: u64,
+    pub title: String,
+    /// The article's identifier in its link.
+    pub slug: String,
+    pub author: String,
+    pub published_at: DateTime<Utc>,
+    /// The first paragraph of the article, in plain text.
+    pub preview: Option<String>,
+    /// The link to the article's first image, if any.
+    pub first_image: Option<String>,
+}
+
+impl NewsPost {
+    /// Link to the article.
+    pub fn link(&self) -> String {
+        format!("https://osu.ppy.sh/home/news/{}", self.slug)
+    }
+}
+
+/// A release of the game, on one of its update streams (e.g. `lazer`).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChangelogBuild {
+    pub id: u64,
+    pub version: String,
+    /// The update stream's name in links, e.g. `lazer`.
+    pub stream: String,
+    /// The update stream's name, e.g. `Lazer`.
+    pub stream_name: String,
+    pub created_at: DateTime<Utc>,
+    pub entries: Vec<ChangelogEntry>,
+}
+
+impl ChangelogBuild {
+    /// Link to the release's changelog.
+    pub fn link(&self) -> String {
+        format!(
+            "https://osu.ppy.sh/home/changelog/{}/{}",
+            self.stream, self.version
+        )
+    }
+}
+
+/// A change in a release.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChangelogEntry {
+    /// What the change is about, e.g. `Gameplay`.
+    pub category: String,
+    pub title: String,
+    /// Whether the change is highlighted as an important one.
+    pub major: bool,
+    /// Link to the change, e.g. its pull request.
+    pub url: Option<String>,
+}
@@ -1,6 +1,8 @@
+use std::collections::HashMap;
+
 use rosu_v2::model::{
     self as rosu,
-    mods::{GameModIntermode, GameModsIntermode},
+    mods::{GameMod, GameModIntermode, GameMods, GameModsIntermode},
 };
 
 use super::*;
@@ -83,9 +85,9 @@ impl User {
             username: user.username.into_string(),
             joined: time_to_utc(user.join_date),
             country: user.country_code.to_string(),
-            count_300: 0, // why do we even want this
-            count_100: 0, // why do we even want this
-            count_50: 0,  // why do we even want this
+            count_300: stats.count_300 as u64,
+            count_100: stats.count_100 as u64,
+            count_50: stats.count_50 as u64,
             play_count: stats.playcount as u64,
             played_time: Duration::from_secs(stats.playtime as u64),
             ranked_score: stats.ranked_score,
@@ -101,6 +103,47 @@ impl User {
             level: stats.level.current as f64 + stats.level.progress as f64 / 100.0,
             pp: Some(stats.pp as f64),
             accuracy: stats.accuracy as f64,
+            rank_history: user
+                .rank_history
+                .unwrap_or_default()
+                .into_iter()
+                .map(|v| v as u64)
+                .collect(),
+            total_hits: stats.total_hits,
+            max_combo: stats.max_combo as u64,
+            replays_watched: stats.replays_watched as u64,
+            badges: user
+                .badges
+                .unwrap_or_default()
+                .into_iter()
+                .map(UserBadge::from)
+                .collect(),
+            server: None,
+        }
+    }
+}
+
+impl RankingEntry {
+    pub(crate) fn from_rosu(rank: u32, user: rosu::user::User) -> Self {
+        let stats = user.statistics;
+        Self {
+            rank,
+            user_id: user.user_id as u64,
+            username: user.username.into_string(),
+            country: user.country_code.to_string(),
+            pp: stats.as_ref().map(|s| s.pp as f64).unwrap_or(0.0),
+            accuracy: stats.as_ref().map(|s| s.accuracy as f64).unwrap_or(0.0),
+            play_count: stats.as_ref().map(|s| s.playcount as u64).unwrap_or(0),
+        }
+    }
+}
+
+impl From<rosu::user::Badge> for UserBadge {
+    fn from(b: rosu::user::Badge) -> Self {
+        Self {
+            awarded_at: time_to_utc(b.awarded_at),
+            description: b.description,
+            image_url: b.image_url,
         }
     }
 }
@@ -139,10 +182,56 @@ impl From<rosu::score::Score> for Score {
             max_combo: s.max_combo as u64,
             perfect: s.is_perfect_combo,
             lazer_build_id: s.build_id,
+            mod_settings: ModSettings::from_rosu(&s.mods),
+            global_rank: s.rank_global,
         }
     }
 }
 
+impl ModSettings {
+    pub(crate) fn from_rosu(mods: &GameMods) -> Self {
+        let mut res = Self::default();
+        for m in mods.iter() {
+            match m {
+                GameMod::DoubleTimeOsu(m) => res.speed_change = m.speed_change.map(f64::from),
+                GameMod::DoubleTimeTaiko(m) => res.speed_change = m.speed_change.map(f64::from),
+                GameMod::DoubleTimeCatch(m) => res.speed_change = m.speed_change.map(f64::from),
+                GameMod::DoubleTimeMania(m) => res.speed_change = m.speed_change.map(f64::from),
+                GameMod::NightcoreOsu(m) => res.speed_change = m.speed_change.map(f64::from),
+                GameMod::NightcoreTaiko(m) => res.speed_change = m.speed_change.map(f64::from),
+                GameMod::NightcoreCatch(m) => res.speed_change = m.speed_change.map(f64::from),
+                GameMod::NightcoreMania(m) => res.speed_change = m.speed_change.map(f64::from),
+                GameMod::HalfTimeOsu(m) => res.speed_change = m.speed_change.map(f64::from),
+                GameMod::HalfTimeTaiko(m) => res.speed_change = m.speed_change.map(f64::from),
+                GameMod::HalfTimeCatch(m) => res.speed_change = m.speed_change.map(f64::from),
+                GameMod::HalfTimeMania(m) => res.speed_change = m.speed_change.map(f64::from),
+                GameMod::DifficultyAdjustOsu(m) => {
+                    res.circle_size = m.circle_size.map(f64::from);
+                    res.approach_rate = m.approach_rate.map(f64::from);
+                    res.overall_difficulty = m.overall_difficulty.map(f64::from);
+                    res.drain_rate = m.drain_rate.map(f64::from);
+                }
+                GameMod::DifficultyAdjustCatch(m) => {
+                    res.circle_size = m.circle_size.map(f64::from);
+                    res.approach_rate = m.approach_rate.map(f64::from);
+                    res.overall_difficulty = m.overall_difficulty.map(f64::from);
+                    res.drain_rate = m.drain_rate.map(f64::from);
+                }
+                GameMod::DifficultyAdjustTaiko(m) => {
+                    res.overall_difficulty = m.overall_difficulty.map(f64::from);
+                    res.drain_rate = m.drain_rate.map(f64::from);
+                }
+                GameMod::DifficultyAdjustMania(m) => {
+                    res.overall_difficulty = m.overall_difficulty.map(f64::from);
+                    res.drain_rate = m.drain_rate.map(f64::from);
+                }
+                _ => (),
+            }
+        }
+        res
+    }
+}
+
 impl Difficulty {
     pub(crate) fn from_rosu(bm: &rosu::beatmap::BeatmapExtended) -> Self {
         Self {
@@ -155,7 +244,7 @@ impl Difficulty {
             hp: bm.hp as f64,
             count_normal: bm.count_circles as u64,
             count_slider: bm.count_sliders as u64,
-            count_spinner: bm.count_sliders as u64,
+            count_spinner: bm.count_spinners as u64,
             max_combo: bm.max_combo.map(|v| v as u64),
             bpm: bm.bpm as f64,
             drain_length: Duration::from_secs(bm.seconds_drain as u64),
@@ -264,6 +353,10 @@ impl From<Mods> for rosu::mods::GameModsIntermode {
             (Mods::SO, GameModIntermode::SpunOut),
             (Mods::AP, GameModIntermode::Autopilot),
             (Mods::PF, GameModIntermode::Perfect),
+            (Mods::MR, GameModIntermode::Mirror),
+            (Mods::DA, GameModIntermode::DifficultyAdjust),
+            (Mods::TC, GameModIntermode::Traceable),
+            (Mods::BL, GameModIntermode::Blinds),
             (Mods::KEY1, GameModIntermode::OneKey),
             (Mods::KEY2, GameModIntermode::TwoKeys),
             (Mods::KEY3, GameModIntermode::ThreeKeys),
@@ -311,6 +404,10 @@ impl From<rosu::mods::GameModsIntermode> for Mods {
                 GameModIntermode::SpunOut => Mods::SO,
                 GameModIntermode::Autopilot => Mods::AP,
                 GameModIntermode::Perfect => Mods::PF,
+                GameModIntermode::Mirror => Mods::MR,
+                GameModIntermode::DifficultyAdjust => Mods::DA,
+                GameModIntermode::Traceable => Mods::TC,
+                GameModIntermode::Blinds => Mods::BL,
                 GameModIntermode::OneKey => Mods::KEY1,
                 GameModIntermode::TwoKeys => Mods::KEY2,
                 GameModIntermode::ThreeKeys => Mods::KEY3,
@@ -328,3 +425,118 @@ impl From<rosu::mods::GameModsIntermode> for Mods {
         // Mods::from_bits_truncate(value.bits() as u64)
     }
 }
+
+impl multiplayer::Match {
+    /// Build the match from its pages of events, in order.
+    pub(crate) fn from_rosu(
+        m: rosu::matches::OsuMatch,
+        rest: Vec<rosu::matches::OsuMatch>,
+    ) -> Self {
+        let mut usernames = HashMap::new();
+        let mut games = vec![];
+        let info = m.match_info.clone();
+        for page in std::iter::once(m).chain(rest) {
+            usernames.extend(
+                page.users
+                    .into_values()
+                    .map(|u| (u.user_id as u64, u.username.into_string())),
+            );
+            games.extend(page.events.into_iter().filter_map(|e| match e {
+                rosu::matches::MatchEvent::Game { game, .. } => {
+                    Some(multiplayer::MatchGame::from(*game))
+                }
+                _ => None,
+            }));
+        }
+        Self {
+            id: info.match_id as u64,
+            name: info.name,
+            start_time: time_to_utc(info.start_time),
+            end_time: info.end_time.map(time_to_utc),
+            games,
+            usernames,
+        }
+    }
+}
+
+impl From<rosu::matches::MatchGame> for multiplayer::MatchGame {
+    fn from(g: rosu::matches::MatchGame) -> Self {
+        let mode = g.mode;
+        Self {
+            id: g.game_id,
+            start_time: time_to_utc(g.start_time),
+            end_time: g.end_time.map(time_to_utc),
+            beatmap_id: g.map_id as u64,
+            mode: mode.into(),
+            scoring: g.scoring_type.into(),
+            team_type: g.team_type.into(),
+            mods: g
+                .mods
+                .iter()
+                .map(|v| v.intermode())
+                .collect::<GameModsIntermode>()
+                .into(),
+            scores: g
+                .scores
+                .into_iter()
+                .map(|s| {
+                    let legacy_stats = s.statistics.as_legacy(mode);
+                    multiplayer::MatchScore {
+                        user_id: s.user_id as u64,
+                        team: s.info.team.into(),
+                        slot: s.info.slot,
+                        score: s.score as u64,
+                        accuracy: s.accuracy as f64,
+                        max_combo: s.max_combo as u64,
+                        mods: s
+                            .mods
+                            .iter()
+                            .map(|v| v.intermode())
+                            .collect::<GameModsIntermode>()
+                            .into(),
+                        passed: s.passed,
+                        perfect: s.perfect,
+                        count_300: legacy_stats.count_300 as u64,
+                        count_100: legacy_stats.count_100 as u64,
+                        count_50: legacy_stats.count_50 as u64,
+                        count_miss: legacy_stats.count_miss as u64,
+                        count_katu: legacy_stats.count_katu as u64,
+                        count_geki: legacy_stats.count_geki as u64,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<rosu::matches::Team> for multiplayer::Team {
+    fn from(value: rosu::matches::Team) -> Self {
+        match value {
+            rosu::matches::Team::None => multiplayer::Team::None,
+            rosu::matches::Team::Blue => multiplayer::Team::Blue,
+            rosu::matches::Team::Red => multiplayer::Team::Red,
+        }
+    }
+}
+
+impl From<rosu::matches::ScoringType> for multiplayer::ScoringType {
+    fn from(value: rosu::matches::ScoringType) -> Self {
+        match value {
+            rosu::matches::ScoringType::Score => multiplayer::ScoringType::Score,
+            rosu::matches::ScoringType::Accuracy => multiplayer::ScoringType::Accuracy,
+            rosu::matches::ScoringType::Combo => multiplayer::ScoringType::Combo,
+            rosu::matches::ScoringType::ScoreV2 => multiplayer::ScoringType::ScoreV2,
+        }
+    }
+}
+
+impl From<rosu::matches::TeamType> for multiplayer::TeamType {
+    fn from(value: rosu::matches::TeamType) -> Self {
+        match value {
+            rosu::matches::TeamType::HeadToHead => multiplayer::TeamType::HeadToHead,
+            rosu::matches::TeamType::TagCoop => multiplayer::TeamType::TagCoop,
+            rosu::matches::TeamType::TeamVS => multiplayer::TeamType::TeamVS,
+            rosu::matches::TeamType::TagTeamVS => multiplayer::TeamType::TagTeamVS,
+        }
+    }
+}
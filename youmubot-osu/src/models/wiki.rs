@@ -0,0 +1,124 @@
+//! Models of the osu! wiki.
+use serde::{Deserialize, Serialize};
+
+/// An article of the osu! wiki, in one language.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WikiPage {
+    pub title: String,
+    /// The category of the article, e.g. `Game modifier`.
+    pub subtitle: Option<String>,
+    /// The article's path on the wiki, e.g. `Gameplay/Game_modifier/Hidden`.
+    pub path: String,
+    /// The language of the article, e.g. `en`.
+    pub locale: String,
+    /// The languages the article is translated to.
+    pub available_locales: Vec<String>,
+    /// The article, in markdown.
+    pub markdown: String,
+}
+
+impl WikiPage {
+    /// Link to the article.
+    pub fn link(&self) -> String {
+        format!("https://osu.ppy.sh/wiki/{}/{}", self.locale, self.path)
+    }
+
+    /// The first paragraph of the article, with its links and images removed,
+    /// cut to at most `max_len` characters.
+    pub fn summary(&self, max_len: usize) -> String {
+        let paragraph = self
+            .markdown
+            // The front matter is the first "paragraph" of some articles.
+            .split("\n\n")
+            .map(str::trim)
+            .find(|p| {
+                !p.is_empty()
+                    && !p.starts_with('#')
+                    && !p.starts_with("---")
+                    && !p.starts_with("<!--")
+                    && !p.starts_with(":::")
+                    && !p.starts_with("![")
+            })
+            .unwrap_or_default();
+        let text = strip_links(&paragraph.replace('\n', " "));
+        if text.chars().count() <= max_len {
+            return text;
+        }
+        let mut cut = text.chars().take(max_len - 1).collect::<String>();
+        // Don't cut in the middle of a word.
+        if let Some(space) = cut.rfind(' ') {
+            cut.truncate(space);
+        }
+        cut.push('…');
+        cut
+    }
+}
+
+/// Replace the markdown links by their text, and remove the images and footnote references.
+fn strip_links(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        let image = rest[..start].ends_with('!');
+        out.push_str(&rest[..start - image as usize]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find(']') else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let label = &after[..end];
+        let tail = &after[end + 1..];
+        rest = match tail
+            .strip_prefix('(')
+            .and_then(|t| t.find(')').map(|i| &t[i + 1..]))
+        {
+            Some(tail) => {
+                if !image {
+                    out.push_str(label);
+                }
+                tail
+            }
+            // Footnote references, e.g. `[^note]`, are removed.
+            None if label.starts_with('^') => tail,
+            None => {
+                out.push('[');
+                out.push_str(label);
+                out.push(']');
+                tail
+            }
+        };
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(markdown: &str) -> WikiPage {
+        WikiPage {
+            title: "Hidden".to_owned(),
+            subtitle: None,
+            path: "Gameplay/Game_modifier/Hidden".to_owned(),
+            locale: "en".to_owned(),
+            available_locales: vec!["en".to_owned()],
+            markdown: markdown.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let p = page(
+            "---\ntags:\n  - HD\n---\n\n# Hidden\n\n![](img/hidden.png)\n\n\
+             **Hidden** (*HD*) is a [game modifier](/wiki/Game_modifier)[^note] that\nhides the \
+             [hit objects](/wiki/Hit_object).\n\n## Gameplay\n\nMore text.",
+        );
+        assert_eq!(
+            p.summary(200),
+            "**Hidden** (*HD*) is a game modifier that hides the hit objects."
+        );
+        assert_eq!(p.summary(30), "**Hidden** (*HD*) is a game…");
+        assert_eq!(page("").summary(10), "");
+    }
+}
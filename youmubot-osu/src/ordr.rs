@@ -0,0 +1,118 @@
+//! A client for [o!rdr](https://ordr.issou.best), a replay rendering service.
+use reqwest::multipart;
+use serde::Deserialize;
+
+use youmubot_prelude::*;
+
+const RENDERS_URL: &str = "https://apis.issou.best/ordr/renders";
+
+/// The resolution of rendered videos.
+const RESOLUTION: &str = "1280x720";
+/// The skin used for rendered videos.
+const SKIN: &str = "default";
+
+/// Submits replays to o!rdr and checks on their progress.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    /// The verification key.
+    /// Without one, o!rdr only accepts a handful of renders (for development).
+    api_key: Option<String>,
+}
+
+/// The state of a submitted render.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderStatus {
+    /// Still queued or rendering, with o!rdr's progress message.
+    InProgress(String),
+    /// Done, with the link to the video.
+    Done(String),
+    /// The render failed, with o!rdr's reason.
+    Failed(String),
+}
+
+#[derive(Deserialize)]
+struct SubmitResponse {
+    message: String,
+    #[serde(rename = "renderID")]
+    render_id: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct RendersResponse {
+    renders: Vec<Render>,
+}
+
+#[derive(Deserialize)]
+struct Render {
+    progress: String,
+    #[serde(rename = "videoUrl")]
+    video_url: Option<String>,
+    #[serde(rename = "errorCode", default)]
+    error_code: i64,
+    #[serde(default)]
+    description: String,
+}
+
+impl Client {
+    /// Create a client, with the verification key at `osu.ordr_api_key` if set.
+    pub fn new(http: reqwest::Client, api_key: Option<String>) -> Self {
+        Self { http, api_key }
+    }
+
+    /// Submit an `.osr` replay for rendering, returning the render id.
+    /// `username` is shown on o!rdr as the requester.
+    pub async fn submit(&self, replay: Vec<u8>, username: impl Into<String>) -> Result<u64> {
+        let mut form = multipart::Form::new()
+            .part(
+                "replayFile",
+                multipart::Part::bytes(replay).file_name("replay.osr"),
+            )
+            .text("username", username.into())
+            .text("resolution", RESOLUTION)
+            .text("skin", SKIN);
+        if let Some(key) = &self.api_key {
+            form = form.text("verificationKey", key.clone());
+        }
+        let resp: SubmitResponse = self
+            .http
+            .post(RENDERS_URL)
+            .multipart(form)
+            .send()
+            .await?
+            .json()
+            .await?;
+        resp.render_id
+            .ok_or_else(|| error!("o!rdr rejected the replay: {}", resp.message))
+    }
+
+    /// Check on the progress of a render.
+    pub async fn status(&self, render_id: u64) -> Result<RenderStatus> {
+        let resp: RendersResponse = self
+            .http
+            .get(RENDERS_URL)
+            .query(&[("renderID", render_id)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let render = resp
+            .renders
+            .into_iter()
+            .next()
+            .ok_or_else(|| error!("render {} not found", render_id))?;
+        Ok(if render.error_code != 0 {
+            RenderStatus::Failed(if render.description.is_empty() {
+                render.progress
+            } else {
+                render.description
+            })
+        } else {
+            match render.video_url.filter(|u| !u.is_empty()) {
+                Some(url) if render.progress.starts_with("Done") => RenderStatus::Done(url),
+                _ => RenderStatus::InProgress(render.progress),
+            }
+        })
+    }
+}
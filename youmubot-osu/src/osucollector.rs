@@ -0,0 +1,160 @@
+//! A client for [osu!collector](https://osucollector.com), where players share their beatmap collections.
+use serde::Deserialize;
+
+use youmubot_prelude::*;
+
+use crate::ratelimit::{Priority, RateLimitStats, RateLimiter};
+
+const COLLECTIONS_URL: &str = "https://osucollector.com/api/collections";
+
+/// How many requests are sent to osu!collector per minute, at most.
+const REQUESTS_PER_MINUTE: u32 = 30;
+
+/// Fetches the collections shared on osu!collector.
+#[derive(Debug)]
+pub struct Client {
+    http: reqwest::Client,
+    limiter: RateLimiter,
+}
+
+/// A shared collection.
+#[derive(Debug, Clone)]
+pub struct Collection {
+    pub id: u64,
+    pub name: String,
+    pub description: String,
+    pub uploader: String,
+    pub favourites: u64,
+    pub beatmaps: Vec<CollectionBeatmap>,
+}
+
+impl Collection {
+    /// The collection's page.
+    pub fn link(&self) -> String {
+        format!("https://osucollector.com/collections/{}", self.id)
+    }
+
+    /// The collection as a [crate::collection::Collection], to be written to a `collection.db` or an `.osdb`.
+    pub fn to_collection(&self) -> crate::collection::Collection {
+        crate::collection::Collection {
+            name: self.name.clone(),
+            beatmaps: self
+                .beatmaps
+                .iter()
+                .map(|b| crate::collection::CollectionEntry {
+                    beatmap_id: b.id,
+                    beatmapset_id: b.beatmapset_id,
+                    hash: b.checksum.clone(),
+                    difficulty: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A beatmap of a collection.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CollectionBeatmap {
+    pub id: u64,
+    /// Filled in from the beatmapset the beatmap is listed in.
+    #[serde(skip)]
+    pub beatmapset_id: u64,
+    /// The MD5 hash of the `.osu` file.
+    pub checksum: String,
+}
+
+/// The id of the collection in its link, or the id itself.
+pub fn collection_id(link: &str) -> Option<u64> {
+    let id = match link.split_once("osucollector.com/collections/") {
+        Some((_, rest)) => rest.split(['/', '?', '#']).next().unwrap_or_default(),
+        None => link,
+    };
+    id.parse().ok()
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawCollection {
+    id: u64,
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    uploader: RawUploader,
+    #[serde(default)]
+    favourites: u64,
+    #[serde(default)]
+    beatmapsets: Vec<RawBeatmapset>,
+}
+
+#[derive(Deserialize)]
+struct RawUploader {
+    #[serde(default)]
+    username: String,
+}
+
+#[derive(Deserialize)]
+struct RawBeatmapset {
+    id: u64,
+    #[serde(default)]
+    beatmaps: Vec<CollectionBeatmap>,
+}
+
+impl Client {
+    pub fn new(http: reqwest::Client) -> Self {
+        Self {
+            http,
+            limiter: RateLimiter::new(REQUESTS_PER_MINUTE),
+        }
+    }
+
+    /// The statistics of the rate limiter.
+    pub fn rate_limit_stats(&self) -> RateLimitStats {
+        self.limiter.stats()
+    }
+
+    /// Fetch the collection, if it exists.
+    pub async fn collection(&self, id: u64) -> Result<Option<Collection>> {
+        self.limiter.acquire(Priority::Interactive).await;
+        let response = self
+            .http
+            .get(format!("{}/{}", COLLECTIONS_URL, id))
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let c: RawCollection = response.error_for_status()?.json().await?;
+        Ok(Some(Collection {
+            id: c.id,
+            name: c.name,
+            description: c.description.unwrap_or_default(),
+            uploader: c.uploader.username,
+            favourites: c.favourites,
+            beatmaps: c
+                .beatmapsets
+                .into_iter()
+                .flat_map(|s| {
+                    s.beatmaps.into_iter().map(move |b| CollectionBeatmap {
+                        beatmapset_id: s.id,
+                        ..b
+                    })
+                })
+                .collect(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collection_id() {
+        assert_eq!(
+            collection_id("https://osucollector.com/collections/44/Best-of-2023"),
+            Some(44)
+        );
+        assert_eq!(collection_id("44"), Some(44));
+        assert_eq!(collection_id("https://osu.ppy.sh/b/44"), None);
+    }
+}
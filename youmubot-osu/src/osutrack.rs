@@ -0,0 +1,203 @@
+//! A client for [osu!track](https://ameobea.me/osutrack), which keeps the stats history of the users it tracks.
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use youmubot_prelude::*;
+
+use crate::{
+    models::Mode,
+    ratelimit::{Priority, RateLimitStats, RateLimiter},
+};
+
+const HISTORY_URL: &str = "https://osutrack-api.ameo.dev/stats_history";
+const UPDATE_URL: &str = "https://osutrack-api.ameo.dev/update";
+
+/// How many requests are sent to osu!track per minute, at most.
+/// It is a free service, so we stay well under what it accepts.
+const REQUESTS_PER_MINUTE: u32 = 30;
+
+/// Fetches the stats history of users from osu!track, and asks it to update them.
+#[derive(Debug)]
+pub struct Client {
+    http: reqwest::Client,
+    limiter: RateLimiter,
+}
+
+/// An user's statistics at a point in time, as recorded by osu!track.
+#[derive(Debug, Clone, Copy)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    pub rank: Option<u64>,
+    pub pp: Option<f64>,
+    pub accuracy: Option<f64>,
+}
+
+/// The changes of an user's statistics since their last osu!track update.
+#[derive(Debug, Clone)]
+pub struct Update {
+    /// Whether this is the first time osu!track sees the user, so there are no changes yet.
+    pub first: bool,
+    pub play_count: i64,
+    /// The change of the world rank: negative when the user climbed.
+    pub rank: i64,
+    pub pp: f64,
+    /// The change of the accuracy, in percents.
+    pub accuracy: f64,
+    pub level: f64,
+    /// The new top plays set since the last update.
+    pub new_highscores: Vec<NewHighscore>,
+}
+
+/// A top play set since the last osu!track update.
+#[derive(Debug, Clone)]
+pub struct NewHighscore {
+    pub beatmap_id: u64,
+    pub pp: f64,
+    /// The grade, e.g. `S` or `XH`.
+    pub grade: String,
+    /// The position in the user's top plays, starting from 1.
+    pub position: usize,
+}
+
+/// A number that osu!track might send as a string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Num {
+    Num(f64),
+    Str(String),
+}
+
+impl Num {
+    fn get(&self) -> Option<f64> {
+        match self {
+            Num::Num(v) => Some(*v),
+            Num::Str(s) => s.parse().ok(),
+        }
+    }
+}
+
+fn num(v: Option<Num>) -> Option<f64> {
+    v.and_then(|v| v.get())
+}
+
+#[derive(Deserialize)]
+struct RawHistoryEntry {
+    pp_rank: Option<Num>,
+    pp_raw: Option<Num>,
+    accuracy: Option<Num>,
+    timestamp: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct RawUpdate {
+    #[serde(default = "default_exists")]
+    exists: bool,
+    #[serde(default)]
+    first: bool,
+    playcount: Option<Num>,
+    pp_rank: Option<Num>,
+    pp_raw: Option<Num>,
+    accuracy: Option<Num>,
+    level: Option<Num>,
+    #[serde(default)]
+    newhs: Vec<RawHighscore>,
+}
+
+fn default_exists() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct RawHighscore {
+    beatmap_id: Option<Num>,
+    pp: Option<Num>,
+    #[serde(default)]
+    rank: String,
+    /// The 0-based position in the top plays.
+    ranking: Option<Num>,
+}
+
+impl Client {
+    pub fn new(http: reqwest::Client) -> Self {
+        Self {
+            http,
+            limiter: RateLimiter::new(REQUESTS_PER_MINUTE),
+        }
+    }
+
+    /// The statistics of the rate limiter.
+    pub fn rate_limit_stats(&self) -> RateLimitStats {
+        self.limiter.stats()
+    }
+
+    /// Fetch the user's stats history, oldest first. Users not tracked by osu!track have no history.
+    pub async fn stats_history(&self, user_id: u64, mode: Mode) -> Result<Vec<HistoryEntry>> {
+        self.limiter.acquire(Priority::Interactive).await;
+        let entries: Vec<RawHistoryEntry> = self
+            .http
+            .get(HISTORY_URL)
+            .query(&[
+                ("user", user_id.to_string()),
+                ("mode", (mode as u8).to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let mut entries = entries
+            .into_iter()
+            .map(|e| HistoryEntry {
+                timestamp: e.timestamp,
+                rank: num(e.pp_rank).map(|v| v as u64).filter(|r| *r > 0),
+                pp: num(e.pp_raw),
+                accuracy: num(e.accuracy),
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|e| e.timestamp);
+        Ok(entries)
+    }
+
+    /// Ask osu!track to update the user, returning the changes since their last update.
+    /// Returns `None` if osu!track cannot find the user.
+    pub async fn update(&self, user_id: u64, mode: Mode) -> Result<Option<Update>> {
+        self.limiter.acquire(Priority::Interactive).await;
+        let u: RawUpdate = self
+            .http
+            .post(UPDATE_URL)
+            .query(&[
+                ("user", user_id.to_string()),
+                ("mode", (mode as u8).to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if !u.exists {
+            return Ok(None);
+        }
+        let mut new_highscores = u
+            .newhs
+            .into_iter()
+            .filter_map(|s| {
+                Some(NewHighscore {
+                    beatmap_id: num(s.beatmap_id)? as u64,
+                    pp: num(s.pp).unwrap_or(0.0),
+                    grade: s.rank,
+                    position: num(s.ranking)? as usize + 1,
+                })
+            })
+            .collect::<Vec<_>>();
+        new_highscores.sort_by_key(|s| s.position);
+        Ok(Some(Update {
+            first: u.first,
+            play_count: num(u.playcount).unwrap_or(0.0) as i64,
+            rank: num(u.pp_rank).unwrap_or(0.0) as i64,
+            pp: num(u.pp_raw).unwrap_or(0.0),
+            accuracy: num(u.accuracy).unwrap_or(0.0),
+            level: num(u.level).unwrap_or(0.0),
+            new_highscores,
+        }))
+    }
+}
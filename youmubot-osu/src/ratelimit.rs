@@ -0,0 +1,156 @@
+//! A token bucket rate limiter for the osu! API, shared by every user of the [Client](crate::Client).
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// The share of the budget that background requests cannot use,
+/// so that interactive commands are served even when the announcer is busy.
+const INTERACTIVE_RESERVE: f64 = 0.2;
+
+/// The priority of an API request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Priority {
+    /// Requests made for an user waiting on the reply, e.g. commands.
+    Interactive,
+    /// Requests made by background tasks, e.g. the announcer.
+    Background,
+}
+
+impl Priority {
+    /// The name of the priority, e.g. for metrics.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Priority::Interactive => "interactive",
+            Priority::Background => "background",
+        }
+    }
+}
+
+/// Statistics of the rate limiter.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RateLimitStats {
+    /// The number of requests sent, by priority (interactive, background).
+    pub requests: (u64, u64),
+    /// The total time spent waiting, by priority (interactive, background).
+    pub waited: (Duration, Duration),
+    /// The number of requests currently waiting.
+    pub queued: u64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+    /// The number of interactive requests waiting. Background requests wait until there are none.
+    waiting_interactive: u64,
+}
+
+/// A token bucket with two priority classes.
+#[derive(Debug)]
+pub struct RateLimiter {
+    bucket: Mutex<Bucket>,
+    capacity: f64,
+    /// Tokens added per second.
+    rate: f64,
+
+    requests: [AtomicU64; 2],
+    waited_ms: [AtomicU64; 2],
+    queued: AtomicU64,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter allowing `per_minute` requests per minute, in bursts of at most
+    /// `per_minute` requests.
+    pub fn new(per_minute: u32) -> Self {
+        let capacity = per_minute.max(1) as f64;
+        Self {
+            bucket: Mutex::new(Bucket {
+                tokens: capacity,
+                last_refill: Instant::now(),
+                waiting_interactive: 0,
+            }),
+            capacity,
+            rate: capacity / 60.0,
+            requests: Default::default(),
+            waited_ms: Default::default(),
+            queued: AtomicU64::new(0),
+        }
+    }
+
+    /// Wait until a request with the given priority can be sent.
+    pub async fn acquire(&self, priority: Priority) {
+        let start = Instant::now();
+        let _queued = QueueGuard::new(self, priority);
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                self.refill(&mut bucket);
+                let needed = match priority {
+                    Priority::Interactive => 1.0,
+                    Priority::Background if bucket.waiting_interactive > 0 => f64::INFINITY,
+                    Priority::Background => 1.0 + self.capacity * INTERACTIVE_RESERVE,
+                };
+                if bucket.tokens >= needed {
+                    bucket.tokens -= 1.0;
+                    break;
+                }
+                if needed.is_finite() {
+                    Duration::from_secs_f64((needed - bucket.tokens) / self.rate)
+                } else {
+                    Duration::from_secs_f64(1.0 / self.rate)
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+        let i = priority as usize;
+        self.requests[i].fetch_add(1, Ordering::Relaxed);
+        self.waited_ms[i].fetch_add(start.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Get the statistics of the rate limiter.
+    pub fn stats(&self) -> RateLimitStats {
+        let requests = |i: usize| self.requests[i].load(Ordering::Relaxed);
+        let waited = |i: usize| Duration::from_millis(self.waited_ms[i].load(Ordering::Relaxed));
+        RateLimitStats {
+            requests: (requests(0), requests(1)),
+            waited: (waited(0), waited(1)),
+            queued: self.queued.load(Ordering::Relaxed),
+        }
+    }
+
+    fn refill(&self, bucket: &mut Bucket) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.capacity);
+        bucket.last_refill = now;
+    }
+}
+
+/// Counts a waiting request for as long as it lives, even if the request is cancelled.
+struct QueueGuard<'a> {
+    limiter: &'a RateLimiter,
+    priority: Priority,
+}
+
+impl<'a> QueueGuard<'a> {
+    fn new(limiter: &'a RateLimiter, priority: Priority) -> Self {
+        limiter.queued.fetch_add(1, Ordering::Relaxed);
+        if priority == Priority::Interactive {
+            limiter.bucket.lock().unwrap().waiting_interactive += 1;
+        }
+        Self { limiter, priority }
+    }
+}
+
+impl<'a> Drop for QueueGuard<'a> {
+    fn drop(&mut self) {
+        self.limiter.queued.fetch_sub(1, Ordering::Relaxed);
+        if self.priority == Priority::Interactive {
+            self.limiter.bucket.lock().unwrap().waiting_interactive -= 1;
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::path::{Path, PathBuf};
+
+use rusttype::Font;
+
+use youmubot_prelude::*;
+
+/// The fonts and other static assets used for rendering.
+pub struct Assets {
+    /// The font used for most text.
+    pub regular: Font<'static>,
+    /// The font used for titles and numbers. Falls back to the regular font.
+    pub bold: Font<'static>,
+}
+
+impl std::fmt::Debug for Assets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<render::Assets>")
+    }
+}
+
+impl Assets {
+    /// Load the assets from a directory, which should contain `regular.ttf`
+    /// and optionally `bold.ttf`.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let regular = load_font(dir.join("regular.ttf"))?;
+        let bold_path = dir.join("bold.ttf");
+        let bold = if bold_path.exists() {
+            load_font(bold_path)?
+        } else {
+            regular.clone()
+        };
+        Ok(Self { regular, bold })
+    }
+}
+
+fn load_font(path: PathBuf) -> Result<Font<'static>> {
+    let bytes =
+        std::fs::read(&path).map_err(|e| error!("cannot read font {}: {}", path.display(), e))?;
+    Font::try_from_vec(bytes).ok_or_else(|| error!("{} is not a valid font", path.display()))
+}
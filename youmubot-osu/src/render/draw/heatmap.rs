@@ -0,0 +1,91 @@
+use image::{Rgba, RgbaImage};
+use imageproc::{
+    drawing::{draw_filled_rect_mut, draw_text_mut},
+    rect::Rect,
+};
+use rusttype::Scale;
+
+use crate::render::Heatmap;
+
+use super::assets::Assets;
+
+/// The size of a heatmap.
+pub const WIDTH: u32 = 900;
+pub const HEIGHT: u32 = 330;
+
+const MARGIN_LEFT: i32 = 60;
+const MARGIN_TOP: i32 = 60;
+const CELL: u32 = 30;
+const GAP: u32 = 3;
+
+const BACKGROUND: Rgba<u8> = Rgba([40, 40, 48, 255]);
+const TEXT: Rgba<u8> = Rgba([200, 200, 200, 255]);
+const EMPTY: [f32; 3] = [56.0, 56.0, 66.0];
+const FULL: [f32; 3] = [255.0, 102.0, 170.0];
+
+const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+impl Heatmap {
+    /// Draw the heatmap as a grid, one row per day and one column per hour.
+    pub(crate) fn draw(&self, assets: &Assets) -> RgbaImage {
+        let mut img = RgbaImage::from_pixel(WIDTH, HEIGHT, BACKGROUND);
+        draw_text_mut(
+            &mut img,
+            TEXT,
+            MARGIN_LEFT,
+            14,
+            Scale::uniform(22.0),
+            &assets.bold,
+            &self.title,
+        );
+        let max = self.counts.iter().flatten().copied().max().unwrap_or(0);
+        let label_scale = Scale::uniform(16.0);
+        let step = (CELL + GAP) as i32;
+
+        for (day, row) in self.counts.iter().enumerate() {
+            let y = MARGIN_TOP + step * day as i32;
+            draw_text_mut(
+                &mut img,
+                TEXT,
+                12,
+                y + 7,
+                label_scale,
+                &assets.regular,
+                DAYS[day],
+            );
+            for (hour, count) in row.iter().enumerate() {
+                let x = MARGIN_LEFT + step * hour as i32;
+                draw_filled_rect_mut(
+                    &mut img,
+                    Rect::at(x, y).of_size(CELL, CELL),
+                    cell_color(*count, max),
+                );
+            }
+        }
+        let bottom = MARGIN_TOP + step * 7 + 4;
+        for hour in (0..24).step_by(3) {
+            draw_text_mut(
+                &mut img,
+                TEXT,
+                MARGIN_LEFT + step * hour + 4,
+                bottom,
+                label_scale,
+                &assets.regular,
+                &format!("{:02}h", hour),
+            );
+        }
+        img
+    }
+}
+
+/// Interpolate between the empty and full colors. Uses a square root scale
+/// so that a few busy hours don't wash out everything else.
+fn cell_color(count: u32, max: u32) -> Rgba<u8> {
+    let t = if max == 0 {
+        0.0
+    } else {
+        (count as f32 / max as f32).sqrt()
+    };
+    let c = |i: usize| (EMPTY[i] + (FULL[i] - EMPTY[i]) * t) as u8;
+    Rgba([c(0), c(1), c(2), 255])
+}
@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use image::{Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_circle_mut, draw_line_segment_mut, draw_text_mut};
+use rusttype::Scale;
+
+use crate::render::History;
+
+use super::assets::Assets;
+
+/// The size of a history graph.
+pub const WIDTH: u32 = 900;
+pub const HEIGHT: u32 = 300;
+
+const MARGIN_LEFT: i32 = 90;
+const MARGIN_RIGHT: i32 = 30;
+const MARGIN_TOP: i32 = 50;
+const MARGIN_BOTTOM: i32 = 35;
+
+const BACKGROUND: Rgba<u8> = Rgba([40, 40, 48, 255]);
+const AXIS: Rgba<u8> = Rgba([120, 120, 130, 255]);
+const GRID: Rgba<u8> = Rgba([60, 60, 70, 255]);
+const TEXT: Rgba<u8> = Rgba([200, 200, 200, 255]);
+const LINE: Rgba<u8> = Rgba([255, 182, 193, 255]);
+
+/// How many horizontal grid lines (and value labels) to draw.
+const GRID_LINES: usize = 4;
+
+impl History {
+    /// Draw the history as a line graph over time.
+    pub(crate) fn draw(&self, assets: &Assets) -> RgbaImage {
+        let mut img = RgbaImage::from_pixel(WIDTH, HEIGHT, BACKGROUND);
+        draw_text_mut(
+            &mut img,
+            TEXT,
+            MARGIN_LEFT,
+            14,
+            Scale::uniform(22.0),
+            &assets.bold,
+            &self.title,
+        );
+        let (Some(first), Some(last)) = (self.points.first(), self.points.last()) else {
+            return img;
+        };
+
+        let (min, max) = self
+            .points
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(lo, hi), (_, v)| {
+                (lo.min(*v), hi.max(*v))
+            });
+        // Pad the range a little so flat lines don't sit on the edges.
+        let pad = ((max - min) * 0.1).max(max.abs() * 0.01).max(1e-6);
+        let (min, max) = (min - pad, max + pad);
+        let span_secs = (last.0 - first.0).num_seconds().max(1) as f32;
+
+        let (left, right) = (MARGIN_LEFT, WIDTH as i32 - MARGIN_RIGHT);
+        let (top, bottom) = (MARGIN_TOP, HEIGHT as i32 - MARGIN_BOTTOM);
+        let x_of = |t: DateTime<Utc>| {
+            left as f32 + (right - left) as f32 * (t - first.0).num_seconds() as f32 / span_secs
+        };
+        let y_of = |v: f64| {
+            let ratio = ((v - min) / (max - min)) as f32;
+            let ratio = if self.lower_is_better {
+                1.0 - ratio
+            } else {
+                ratio
+            };
+            bottom as f32 - (bottom - top) as f32 * ratio
+        };
+
+        // Grid lines with value labels.
+        let label_scale = Scale::uniform(16.0);
+        for i in 0..=GRID_LINES {
+            let v = min + (max - min) * i as f64 / GRID_LINES as f64;
+            let y = y_of(v);
+            draw_line_segment_mut(&mut img, (left as f32, y), (right as f32, y), GRID);
+            draw_text_mut(
+                &mut img,
+                TEXT,
+                8,
+                y as i32 - 8,
+                label_scale,
+                &assets.regular,
+                &(self.format)(v),
+            );
+        }
+
+        // Time axis, labelled at both ends and in the middle.
+        draw_line_segment_mut(
+            &mut img,
+            (left as f32, bottom as f32),
+            (right as f32, bottom as f32),
+            AXIS,
+        );
+        let middle = first.0 + (last.0 - first.0) / 2;
+        for t in [first.0, middle, last.0] {
+            let x = x_of(t);
+            draw_line_segment_mut(&mut img, (x, bottom as f32), (x, bottom as f32 + 4.0), AXIS);
+            draw_text_mut(
+                &mut img,
+                TEXT,
+                (x as i32 - 40).clamp(0, WIDTH as i32 - 90),
+                bottom + 8,
+                label_scale,
+                &assets.regular,
+                &t.format("%Y-%m-%d").to_string(),
+            );
+        }
+
+        // The line itself.
+        for w in self.points.windows(2) {
+            draw_line_segment_mut(
+                &mut img,
+                (x_of(w[0].0), y_of(w[0].1)),
+                (x_of(w[1].0), y_of(w[1].1)),
+                LINE,
+            );
+        }
+        if self.points.len() == 1 {
+            draw_filled_circle_mut(
+                &mut img,
+                (x_of(first.0) as i32, y_of(first.1) as i32),
+                3,
+                LINE,
+            );
+        }
+        let (lx, ly) = (x_of(last.0), y_of(last.1));
+        draw_filled_circle_mut(&mut img, (lx as i32, ly as i32), 4, LINE);
+        img
+    }
+}
@@ -0,0 +1,181 @@
+use image::{imageops, Rgba, RgbaImage};
+use imageproc::{
+    drawing::{draw_filled_rect_mut, draw_text_mut, text_size},
+    rect::Rect,
+};
+use rusttype::Scale;
+
+use crate::render::Leaderboard;
+
+use super::{assets::Assets, score_card::ellipsize};
+
+/// The width of a leaderboard image. The height depends on the number of rows.
+pub const WIDTH: u32 = 800;
+
+const HEADER_HEIGHT: u32 = 80;
+const ROW_HEIGHT: u32 = 52;
+const FOOTER_HEIGHT: u32 = 16;
+const AVATAR_SIZE: u32 = 40;
+const FLAG_WIDTH: u32 = 30;
+const FLAG_HEIGHT: u32 = 20;
+
+const BACKGROUND: Rgba<u8> = Rgba([40, 40, 48, 255]);
+const ROW_ALT: Rgba<u8> = Rgba([48, 48, 58, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const GREY: Rgba<u8> = Rgba([160, 160, 170, 255]);
+const PODIUM: [Rgba<u8>; 3] = [
+    Rgba([255, 205, 60, 255]),
+    Rgba([200, 200, 215, 255]),
+    Rgba([215, 140, 80, 255]),
+];
+
+/// The images of a leaderboard row, fetched beforehand.
+#[derive(Debug, Default)]
+pub(crate) struct EntryImages<'a> {
+    pub avatar: Option<&'a RgbaImage>,
+    pub flag: Option<&'a RgbaImage>,
+}
+
+impl Leaderboard {
+    fn height(&self) -> u32 {
+        HEADER_HEIGHT + ROW_HEIGHT * self.entries.len() as u32 + FOOTER_HEIGHT
+    }
+
+    /// Draw the leaderboard. `images` should be in the same order as the entries.
+    pub(crate) fn draw(&self, assets: &Assets, images: &[EntryImages]) -> RgbaImage {
+        let mut img = RgbaImage::from_pixel(WIDTH, self.height(), BACKGROUND);
+        draw_text_mut(
+            &mut img,
+            WHITE,
+            24,
+            14,
+            Scale::uniform(30.0),
+            &assets.bold,
+            &ellipsize(
+                &assets.bold,
+                Scale::uniform(30.0),
+                &self.title,
+                WIDTH as i32 - 48,
+            ),
+        );
+        draw_text_mut(
+            &mut img,
+            GREY,
+            24,
+            50,
+            Scale::uniform(18.0),
+            &assets.regular,
+            &self.subtitle,
+        );
+
+        let name_scale = Scale::uniform(22.0);
+        let member_scale = Scale::uniform(16.0);
+        let value_scale = Scale::uniform(22.0);
+        let rank_scale = Scale::uniform(24.0);
+        for (i, entry) in self.entries.iter().enumerate() {
+            let top = (HEADER_HEIGHT + ROW_HEIGHT * i as u32) as i32;
+            if i % 2 == 0 {
+                draw_filled_rect_mut(
+                    &mut img,
+                    Rect::at(0, top).of_size(WIDTH, ROW_HEIGHT),
+                    ROW_ALT,
+                );
+            }
+            let rank_color = PODIUM
+                .get(entry.rank.wrapping_sub(1))
+                .copied()
+                .unwrap_or(GREY);
+            let rank = format!("#{}", entry.rank);
+            let (rw, _) = text_size(rank_scale, &assets.bold, &rank);
+            draw_text_mut(
+                &mut img,
+                rank_color,
+                70 - rw,
+                top + 13,
+                rank_scale,
+                &assets.bold,
+                &rank,
+            );
+
+            let images = images.get(i);
+            let avatar_x = 84;
+            if let Some(avatar) = images.and_then(|v| v.avatar) {
+                let avatar = imageops::resize(
+                    avatar,
+                    AVATAR_SIZE,
+                    AVATAR_SIZE,
+                    imageops::FilterType::Triangle,
+                );
+                imageops::overlay(
+                    &mut img,
+                    &avatar,
+                    avatar_x,
+                    (top + (ROW_HEIGHT - AVATAR_SIZE) as i32 / 2) as i64,
+                );
+            }
+            let flag_x = avatar_x + AVATAR_SIZE as i64 + 12;
+            if let Some(flag) = images.and_then(|v| v.flag) {
+                let flag = imageops::resize(
+                    flag,
+                    FLAG_WIDTH,
+                    FLAG_HEIGHT,
+                    imageops::FilterType::Triangle,
+                );
+                imageops::overlay(
+                    &mut img,
+                    &flag,
+                    flag_x,
+                    (top + (ROW_HEIGHT - FLAG_HEIGHT) as i32 / 2) as i64,
+                );
+            }
+
+            // Values on the right.
+            let value_right = WIDTH as i32 - 24;
+            let (vw, _) = text_size(value_scale, &assets.bold, &entry.value);
+            draw_text_mut(
+                &mut img,
+                WHITE,
+                value_right - vw,
+                top + 6,
+                value_scale,
+                &assets.bold,
+                &entry.value,
+            );
+            if let Some(extra) = &entry.extra {
+                let (ew, _) = text_size(member_scale, &assets.regular, extra);
+                draw_text_mut(
+                    &mut img,
+                    GREY,
+                    value_right - ew,
+                    top + 30,
+                    member_scale,
+                    &assets.regular,
+                    extra,
+                );
+            }
+
+            // Names in between.
+            let name_x = (flag_x + FLAG_WIDTH as i64 + 14) as i32;
+            let name_width = value_right - 180 - name_x;
+            draw_text_mut(
+                &mut img,
+                WHITE,
+                name_x,
+                top + 6,
+                name_scale,
+                &assets.bold,
+                &ellipsize(&assets.bold, name_scale, &entry.username, name_width),
+            );
+            draw_text_mut(
+                &mut img,
+                GREY,
+                name_x,
+                top + 30,
+                member_scale,
+                &assets.regular,
+                &ellipsize(&assets.regular, member_scale, &entry.member, name_width),
+            );
+        }
+        img
+    }
+}
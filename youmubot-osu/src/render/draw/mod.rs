@@ -0,0 +1,240 @@
+//! The renderer implementation drawing with the `image` crate.
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use image::{ImageOutputFormat, RgbaImage};
+
+use youmubot_prelude::{settings::OsuConfig, *};
+
+use super::{Heatmap, History, Leaderboard, ProfileCard, Renderer, Scatter, ScoreCard, Strains};
+
+mod assets;
+mod heatmap;
+mod history_graph;
+mod leaderboard;
+mod profile_card;
+mod scatter;
+mod score_card;
+mod strain_graph;
+
+use assets::Assets;
+
+/// How many generated images (and backgrounds) to keep in memory.
+const CACHE_SIZE: usize = 64;
+
+/// Renders images with the `image` crate, caching backgrounds and generated images.
+#[derive(Debug)]
+pub struct ImageRenderer {
+    assets: Arc<Assets>,
+    http: reqwest::Client,
+    backgrounds: BoundedCache<u64, Arc<RgbaImage>>,
+    /// Avatars and flags, keyed by their url.
+    images: BoundedCache<String, Arc<RgbaImage>>,
+    /// Generated images, keyed by what they show.
+    cards: BoundedCache<String, Arc<Vec<u8>>>,
+}
+
+impl ImageRenderer {
+    /// Create a renderer with the assets at `osu.render_assets`.
+    /// Returns `None` if rendering is not configured.
+    pub fn from_config(http: reqwest::Client, config: &OsuConfig) -> Result<Option<Self>> {
+        let Some(dir) = config.render_assets.clone() else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            assets: Arc::new(Assets::load(dir)?),
+            http,
+            backgrounds: BoundedCache::default(),
+            images: BoundedCache::default(),
+            cards: BoundedCache::default(),
+        }))
+    }
+
+    /// Fetch an image, e.g. an avatar or a flag.
+    async fn image(&self, url: &str) -> Result<Arc<RgbaImage>> {
+        if let Some(img) = self.images.get(url) {
+            return Ok(img);
+        }
+        let bytes = self
+            .http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        let img = tokio::task::spawn_blocking(move || -> Result<RgbaImage> {
+            Ok(image::load_from_memory(&bytes)?.into_rgba8())
+        })
+        .await??;
+        let img = Arc::new(img);
+        self.images.insert(url.to_owned(), img.clone());
+        Ok(img)
+    }
+
+    /// Fetch the background of the given beatmapset, resized to fit a score card.
+    async fn background(&self, beatmapset_id: u64) -> Result<Arc<RgbaImage>> {
+        if let Some(bg) = self.backgrounds.get(&beatmapset_id) {
+            return Ok(bg);
+        }
+        let bytes = self
+            .http
+            .get(format!(
+                "https://assets.ppy.sh/beatmaps/{}/covers/cover@2x.jpg",
+                beatmapset_id
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        let bg = tokio::task::spawn_blocking(move || -> Result<RgbaImage> {
+            Ok(score_card::fit_background(&image::load_from_memory(
+                &bytes,
+            )?))
+        })
+        .await??;
+        let bg = Arc::new(bg);
+        self.backgrounds.insert(beatmapset_id, bg.clone());
+        Ok(bg)
+    }
+}
+
+#[async_trait]
+impl Renderer for ImageRenderer {
+    async fn score_card(&self, card: ScoreCard) -> Result<Arc<Vec<u8>>> {
+        let key = card.cache_key();
+        if let Some(png) = self.cards.get(&key) {
+            return Ok(png);
+        }
+        let background = self.background(card.beatmapset_id).await.pls_ok();
+        let assets = self.assets.clone();
+        let png = tokio::task::spawn_blocking(move || {
+            encode_png(&card.draw(&assets, background.as_deref()))
+        })
+        .await??;
+        let png = Arc::new(png);
+        self.cards.insert(key, png.clone());
+        Ok(png)
+    }
+
+    async fn strain_graph(
+        &self,
+        key: String,
+        strains: Box<dyn FnOnce() -> Strains + Send>,
+    ) -> Result<Arc<Vec<u8>>> {
+        if let Some(png) = self.cards.get(&key) {
+            return Ok(png);
+        }
+        let assets = self.assets.clone();
+        let png =
+            tokio::task::spawn_blocking(move || encode_png(&strains().draw(&assets))).await??;
+        let png = Arc::new(png);
+        self.cards.insert(key, png.clone());
+        Ok(png)
+    }
+
+    async fn history_graph(&self, history: History) -> Result<Vec<u8>> {
+        // Not cached, as the history keeps changing.
+        let assets = self.assets.clone();
+        tokio::task::spawn_blocking(move || encode_png(&history.draw(&assets))).await?
+    }
+
+    async fn heatmap(&self, heatmap: Heatmap) -> Result<Vec<u8>> {
+        let assets = self.assets.clone();
+        tokio::task::spawn_blocking(move || encode_png(&heatmap.draw(&assets))).await?
+    }
+
+    async fn profile_card(&self, card: ProfileCard) -> Result<Vec<u8>> {
+        let avatar = self.image(&card.avatar_url()).await.pls_ok();
+        let assets = self.assets.clone();
+        tokio::task::spawn_blocking(move || encode_png(&card.draw(&assets, avatar.as_deref())))
+            .await?
+    }
+
+    async fn scatter(&self, scatter: Scatter) -> Result<Vec<u8>> {
+        let assets = self.assets.clone();
+        tokio::task::spawn_blocking(move || encode_png(&scatter.draw(&assets))).await?
+    }
+
+    async fn leaderboard(&self, leaderboard: Leaderboard) -> Result<Vec<u8>> {
+        // Fetch the avatars and flags first, then draw them all at once.
+        let images = leaderboard
+            .entries
+            .iter()
+            .map(|e| async move {
+                let avatar = self.image(&e.avatar_url).await.pls_ok();
+                let flag = match &e.flag_url {
+                    Some(url) => self.image(url).await.pls_ok(),
+                    None => None,
+                };
+                (avatar, flag)
+            })
+            .collect::<stream::FuturesOrdered<_>>()
+            .collect::<Vec<_>>()
+            .await;
+        let assets = self.assets.clone();
+        tokio::task::spawn_blocking(move || {
+            let images = images
+                .iter()
+                .map(|(avatar, flag)| leaderboard::EntryImages {
+                    avatar: avatar.as_deref(),
+                    flag: flag.as_deref(),
+                })
+                .collect::<Vec<_>>();
+            encode_png(&leaderboard.draw(&assets, &images))
+        })
+        .await?
+    }
+}
+
+fn encode_png(img: &RgbaImage) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    img.write_to(&mut Cursor::new(&mut buf), ImageOutputFormat::Png)?;
+    Ok(buf)
+}
+
+/// A cache of at most [CACHE_SIZE] entries, evicting the least recently used one when full.
+#[derive(Debug)]
+struct BoundedCache<K, V> {
+    entries: Mutex<HashMap<K, (V, Instant)>>,
+}
+
+impl<K, V> Default for BoundedCache<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: std::hash::Hash + Eq + Clone, V: Clone> BoundedCache<K, V> {
+    fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: std::hash::Hash + Eq + ?Sized,
+    {
+        let mut entries = self.entries.lock().unwrap();
+        let (value, last_used) = entries.get_mut(key)?;
+        *last_used = Instant::now();
+        Some(value.clone())
+    }
+
+    fn insert(&self, key: K, value: V) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= CACHE_SIZE && !entries.contains_key(&key) {
+            let lru = entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(k, _)| k.clone());
+            if let Some(lru) = lru {
+                entries.remove(&lru);
+            }
+        }
+        entries.insert(key, (value, Instant::now()));
+    }
+}
@@ -0,0 +1,238 @@
+use image::{imageops, Rgba, RgbaImage};
+use imageproc::{
+    drawing::{draw_filled_rect_mut, draw_line_segment_mut, draw_text_mut},
+    rect::Rect,
+};
+use rusttype::Scale;
+
+use crate::render::ProfileCard;
+
+use super::{assets::Assets, score_card::ellipsize};
+
+/// The size of a profile card.
+pub const WIDTH: u32 = 900;
+pub const HEIGHT: u32 = 300;
+
+const AVATAR_SIZE: u32 = 160;
+const STATS_LEFT: i32 = 200;
+const STATS_COLUMN: i32 = 145;
+const GRAPH_LEFT: i32 = 650;
+const GRAPH_RIGHT: i32 = 880;
+const GRAPH_TOP: i32 = 50;
+const GRAPH_BOTTOM: i32 = 190;
+
+const BACKGROUND: Rgba<u8> = Rgba([40, 40, 48, 255]);
+const PANEL: Rgba<u8> = Rgba([52, 52, 62, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const GREY: Rgba<u8> = Rgba([160, 160, 170, 255]);
+const ACCENT: Rgba<u8> = Rgba([255, 182, 193, 255]);
+
+impl ProfileCard {
+    /// The url of the user's avatar.
+    pub(crate) fn avatar_url(&self) -> String {
+        format!("https://a.ppy.sh/{}", self.user_id)
+    }
+
+    /// Draw the card, with the user's avatar if available.
+    pub(crate) fn draw(&self, assets: &Assets, avatar: Option<&RgbaImage>) -> RgbaImage {
+        let mut img = RgbaImage::from_pixel(WIDTH, HEIGHT, BACKGROUND);
+
+        // Avatar
+        draw_filled_rect_mut(
+            &mut img,
+            Rect::at(20, 20).of_size(AVATAR_SIZE, AVATAR_SIZE),
+            PANEL,
+        );
+        if let Some(avatar) = avatar {
+            let avatar = imageops::resize(
+                avatar,
+                AVATAR_SIZE,
+                AVATAR_SIZE,
+                imageops::FilterType::Triangle,
+            );
+            imageops::overlay(&mut img, &avatar, 20, 20);
+        }
+
+        // Name and mode
+        let name_scale = Scale::uniform(34.0);
+        draw_text_mut(
+            &mut img,
+            WHITE,
+            STATS_LEFT,
+            18,
+            name_scale,
+            &assets.bold,
+            &ellipsize(
+                &assets.bold,
+                name_scale,
+                &self.username,
+                GRAPH_LEFT - STATS_LEFT - 20,
+            ),
+        );
+        draw_text_mut(
+            &mut img,
+            GREY,
+            STATS_LEFT,
+            58,
+            Scale::uniform(18.0),
+            &assets.regular,
+            &format!("{} • {}", self.mode, self.country),
+        );
+
+        // Stats grid
+        let stats = [
+            ("Global rank", rank_text(self.rank)),
+            ("Country rank", rank_text(self.country_rank)),
+            (
+                "pp",
+                self.pp
+                    .map(|pp| format!("{:.0}", pp))
+                    .unwrap_or_else(|| "-".to_owned()),
+            ),
+            ("Accuracy", format!("{:.2}%", self.accuracy)),
+            ("Play count", self.play_count.to_string()),
+            (
+                "Map length",
+                self.map_length
+                    .map(|len| {
+                        let secs = len as u64;
+                        format!("{}:{:02}", secs / 60, secs % 60)
+                    })
+                    .unwrap_or_else(|| "-".to_owned()),
+            ),
+        ];
+        for (i, (label, value)) in stats.iter().enumerate() {
+            let x = STATS_LEFT + STATS_COLUMN * (i % 3) as i32;
+            let y = 95 + 55 * (i / 3) as i32;
+            draw_text_mut(
+                &mut img,
+                GREY,
+                x,
+                y,
+                Scale::uniform(16.0),
+                &assets.regular,
+                label,
+            );
+            draw_text_mut(
+                &mut img,
+                WHITE,
+                x,
+                y + 18,
+                Scale::uniform(26.0),
+                &assets.bold,
+                value,
+            );
+        }
+
+        // Level bar
+        let bar_width = (GRAPH_LEFT - STATS_LEFT - 30) as u32;
+        draw_filled_rect_mut(
+            &mut img,
+            Rect::at(STATS_LEFT, 212).of_size(bar_width, 6),
+            PANEL,
+        );
+        let progress = (bar_width as f64 * self.level.fract()) as u32;
+        if progress > 0 {
+            draw_filled_rect_mut(
+                &mut img,
+                Rect::at(STATS_LEFT, 212).of_size(progress, 6),
+                ACCENT,
+            );
+        }
+        draw_text_mut(
+            &mut img,
+            GREY,
+            20,
+            202,
+            Scale::uniform(18.0),
+            &assets.regular,
+            &format!("Level {:.0}", self.level.floor()),
+        );
+
+        self.draw_rank_graph(&mut img, assets);
+
+        // Top play strip
+        draw_filled_rect_mut(&mut img, Rect::at(20, 235).of_size(WIDTH - 40, 48), PANEL);
+        let top_play = match &self.top_play {
+            Some(p) => format!(
+                "Top play: {} {} • {}",
+                p.beatmap,
+                p.mods,
+                p.pp.map(|pp| format!("{:.2}pp", pp))
+                    .unwrap_or_else(|| "-".to_owned())
+            ),
+            None => "No top plays yet".to_owned(),
+        };
+        let play_scale = Scale::uniform(20.0);
+        draw_text_mut(
+            &mut img,
+            WHITE,
+            34,
+            248,
+            play_scale,
+            &assets.regular,
+            &ellipsize(&assets.regular, play_scale, &top_play, WIDTH as i32 - 68),
+        );
+        img
+    }
+
+    /// Draw the rank history as a small line graph, better ranks up.
+    fn draw_rank_graph(&self, img: &mut RgbaImage, assets: &Assets) {
+        draw_text_mut(
+            img,
+            GREY,
+            GRAPH_LEFT,
+            20,
+            Scale::uniform(16.0),
+            &assets.regular,
+            &format!("Rank, last {} days", self.rank_history.len()),
+        );
+        draw_filled_rect_mut(
+            img,
+            Rect::at(GRAPH_LEFT, GRAPH_TOP).of_size(
+                (GRAPH_RIGHT - GRAPH_LEFT) as u32,
+                (GRAPH_BOTTOM - GRAPH_TOP) as u32,
+            ),
+            PANEL,
+        );
+        let ranks = self
+            .rank_history
+            .iter()
+            .copied()
+            .filter(|r| *r > 0)
+            .collect::<Vec<_>>();
+        if ranks.len() < 2 {
+            return;
+        }
+        let best = *ranks.iter().min().unwrap() as f32;
+        let worst = *ranks.iter().max().unwrap() as f32;
+        let span = (worst - best).max(1.0);
+        let pad = 8.0;
+        let x_of = |i: usize| {
+            GRAPH_LEFT as f32
+                + pad
+                + (GRAPH_RIGHT - GRAPH_LEFT) as f32 * 0.92 * i as f32 / (ranks.len() - 1) as f32
+        };
+        let y_of = |r: u64| {
+            GRAPH_TOP as f32
+                + pad
+                + (GRAPH_BOTTOM - GRAPH_TOP) as f32 * 0.85 * (r as f32 - best) / span
+        };
+        for (i, w) in ranks.windows(2).enumerate() {
+            draw_line_segment_mut(
+                img,
+                (x_of(i), y_of(w[0])),
+                (x_of(i + 1), y_of(w[1])),
+                ACCENT,
+            );
+        }
+    }
+}
+
+fn rank_text(rank: u64) -> String {
+    if rank == 0 {
+        "-".to_owned()
+    } else {
+        format!("#{}", rank)
+    }
+}
@@ -0,0 +1,190 @@
+use image::{Rgba, RgbaImage};
+use imageproc::{
+    drawing::{draw_filled_circle_mut, draw_filled_rect_mut, draw_line_segment_mut, draw_text_mut},
+    rect::Rect,
+};
+use rusttype::Scale;
+
+use crate::render::{Scatter, ScatterPoint};
+
+use super::assets::Assets;
+
+/// The size of a scatter plot.
+pub const WIDTH: u32 = 900;
+pub const HEIGHT: u32 = 500;
+
+const MARGIN_LEFT: i32 = 70;
+const MARGIN_RIGHT: i32 = 170;
+const MARGIN_TOP: i32 = 64;
+const MARGIN_BOTTOM: i32 = 50;
+
+const BACKGROUND: Rgba<u8> = Rgba([40, 40, 48, 255]);
+const AXIS: Rgba<u8> = Rgba([120, 120, 130, 255]);
+const GRID: Rgba<u8> = Rgba([60, 60, 70, 255]);
+const TEXT: Rgba<u8> = Rgba([200, 200, 200, 255]);
+const COLORS: [Rgba<u8>; 8] = [
+    Rgba([255, 182, 193, 255]),
+    Rgba([102, 204, 255, 255]),
+    Rgba([255, 205, 60, 255]),
+    Rgba([140, 230, 140, 255]),
+    Rgba([200, 150, 255, 255]),
+    Rgba([255, 140, 90, 255]),
+    Rgba([90, 220, 210, 255]),
+    Rgba([240, 240, 240, 255]),
+];
+
+/// How many grid lines (and labels) to draw on each axis.
+const GRID_LINES: usize = 5;
+
+impl Scatter {
+    /// The groups, most common first. Groups past the palette are drawn as "Other".
+    fn groups(&self) -> Vec<String> {
+        let mut counts = Vec::<(String, usize)>::new();
+        for p in &self.points {
+            match counts.iter_mut().find(|(g, _)| g == &p.group) {
+                Some((_, c)) => *c += 1,
+                None => counts.push((p.group.clone(), 1)),
+            }
+        }
+        counts.sort_by(|a, b| b.1.cmp(&a.1));
+        counts.into_iter().map(|(g, _)| g).collect()
+    }
+
+    /// Draw the scatter plot.
+    pub(crate) fn draw(&self, assets: &Assets) -> RgbaImage {
+        let mut img = RgbaImage::from_pixel(WIDTH, HEIGHT, BACKGROUND);
+        draw_text_mut(
+            &mut img,
+            TEXT,
+            MARGIN_LEFT,
+            14,
+            Scale::uniform(22.0),
+            &assets.bold,
+            &self.title,
+        );
+        if self.points.is_empty() {
+            return img;
+        }
+
+        let range = |f: fn(&ScatterPoint) -> f64| {
+            let (lo, hi) = self
+                .points
+                .iter()
+                .map(f)
+                .fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)));
+            let pad = ((hi - lo) * 0.05).max(0.1);
+            (lo - pad, hi + pad)
+        };
+        let (x_min, x_max) = range(|p| p.x);
+        let (y_min, y_max) = range(|p| p.y);
+
+        let (left, right) = (MARGIN_LEFT, WIDTH as i32 - MARGIN_RIGHT);
+        let (top, bottom) = (MARGIN_TOP, HEIGHT as i32 - MARGIN_BOTTOM);
+        let x_of =
+            |v: f64| left as f32 + (right - left) as f32 * ((v - x_min) / (x_max - x_min)) as f32;
+        let y_of =
+            |v: f64| bottom as f32 - (bottom - top) as f32 * ((v - y_min) / (y_max - y_min)) as f32;
+
+        // Grid lines and labels.
+        let label_scale = Scale::uniform(16.0);
+        for i in 0..=GRID_LINES {
+            let t = i as f64 / GRID_LINES as f64;
+            let (xv, yv) = (x_min + (x_max - x_min) * t, y_min + (y_max - y_min) * t);
+            let (x, y) = (x_of(xv), y_of(yv));
+            draw_line_segment_mut(&mut img, (x, top as f32), (x, bottom as f32), GRID);
+            draw_line_segment_mut(&mut img, (left as f32, y), (right as f32, y), GRID);
+            draw_text_mut(
+                &mut img,
+                TEXT,
+                x as i32 - 12,
+                bottom + 6,
+                label_scale,
+                &assets.regular,
+                &format!("{:.2}", xv),
+            );
+            draw_text_mut(
+                &mut img,
+                TEXT,
+                8,
+                y as i32 - 8,
+                label_scale,
+                &assets.regular,
+                &format!("{:.0}", yv),
+            );
+        }
+        draw_line_segment_mut(
+            &mut img,
+            (left as f32, bottom as f32),
+            (right as f32, bottom as f32),
+            AXIS,
+        );
+        draw_line_segment_mut(
+            &mut img,
+            (left as f32, top as f32),
+            (left as f32, bottom as f32),
+            AXIS,
+        );
+        draw_text_mut(
+            &mut img,
+            TEXT,
+            (left + right) / 2 - 30,
+            bottom + 26,
+            label_scale,
+            &assets.bold,
+            &self.x_label,
+        );
+        draw_text_mut(
+            &mut img,
+            TEXT,
+            8,
+            top - 22,
+            label_scale,
+            &assets.bold,
+            &self.y_label,
+        );
+
+        // Points, with the less common groups drawn first so the common ones don't hide them.
+        let groups = self.groups();
+        let color_of = |group: &str| {
+            let idx = groups.iter().position(|g| g == group).unwrap_or(usize::MAX);
+            COLORS.get(idx).copied().unwrap_or(COLORS[COLORS.len() - 1])
+        };
+        let mut points = self.points.iter().collect::<Vec<_>>();
+        points.sort_by_key(|p| std::cmp::Reverse(groups.iter().position(|g| g == &p.group)));
+        for p in points {
+            draw_filled_circle_mut(
+                &mut img,
+                (x_of(p.x) as i32, y_of(p.y) as i32),
+                4,
+                color_of(&p.group),
+            );
+        }
+
+        // Legend.
+        let legend_x = right + 20;
+        let last = COLORS.len() - 1;
+        for (i, group) in groups.iter().take(COLORS.len()).enumerate() {
+            let y = top + 24 * i as i32;
+            let name = if i == last && groups.len() > COLORS.len() {
+                "Other"
+            } else {
+                group.as_str()
+            };
+            draw_filled_rect_mut(
+                &mut img,
+                Rect::at(legend_x, y + 2).of_size(14, 14),
+                COLORS[i],
+            );
+            draw_text_mut(
+                &mut img,
+                TEXT,
+                legend_x + 20,
+                y,
+                Scale::uniform(18.0),
+                &assets.regular,
+                name,
+            );
+        }
+        img
+    }
+}
@@ -0,0 +1,200 @@
+use image::{imageops, Rgba, RgbaImage};
+use imageproc::{
+    drawing::{draw_filled_rect_mut, draw_text_mut, text_size},
+    rect::Rect,
+};
+use rusttype::{Font, Scale};
+
+use crate::{
+    models::{Mods, Rank},
+    render::ScoreCard,
+};
+
+use super::assets::Assets;
+
+/// The size of a score card.
+pub const WIDTH: u32 = 900;
+pub const HEIGHT: u32 = 250;
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const GREY: Rgba<u8> = Rgba([200, 200, 200, 255]);
+
+impl ScoreCard {
+    /// A key identifying the card, for caching.
+    pub(crate) fn cache_key(&self) -> String {
+        match self.score_id {
+            Some(id) => format!("score-{}", id),
+            None => format!("play-{}-{}", self.player, self.date.timestamp()),
+        }
+    }
+
+    /// Draw the card on top of the (already resized) background.
+    pub(crate) fn draw(&self, assets: &Assets, background: Option<&RgbaImage>) -> RgbaImage {
+        let mut img = match background {
+            Some(bg) => {
+                let mut bg = bg.clone();
+                darken(&mut bg, 0.35);
+                bg
+            }
+            None => RgbaImage::from_pixel(WIDTH, HEIGHT, Rgba([40, 40, 48, 255])),
+        };
+
+        // Grade
+        let grade = grade_text(self.rank);
+        let grade_scale = Scale::uniform(150.0);
+        let (gw, _) = text_size(grade_scale, &assets.bold, grade);
+        draw_text_mut(
+            &mut img,
+            grade_color(self.rank),
+            (200 - gw) / 2,
+            40,
+            grade_scale,
+            &assets.bold,
+            grade,
+        );
+        draw_filled_rect_mut(&mut img, Rect::at(200, 30).of_size(2, HEIGHT - 60), GREY);
+
+        // Beatmap
+        let left = 230;
+        draw_text_mut(
+            &mut img,
+            WHITE,
+            left,
+            25,
+            Scale::uniform(34.0),
+            &assets.bold,
+            &ellipsize(
+                &assets.bold,
+                Scale::uniform(34.0),
+                &format!("{} - {}", self.artist, self.title),
+                WIDTH as i32 - left - 30,
+            ),
+        );
+        draw_text_mut(
+            &mut img,
+            GREY,
+            left,
+            65,
+            Scale::uniform(24.0),
+            &assets.regular,
+            &ellipsize(
+                &assets.regular,
+                Scale::uniform(24.0),
+                &format!(
+                    "[{}] by {} • {:.2}★",
+                    self.difficulty_name, self.creator, self.stars
+                ),
+                WIDTH as i32 - left - 30,
+            ),
+        );
+
+        // pp and stats
+        let pp = self
+            .pp
+            .map(|pp| format!("{:.2}pp", pp))
+            .unwrap_or_else(|| "-pp".to_owned());
+        draw_text_mut(
+            &mut img,
+            WHITE,
+            left,
+            105,
+            Scale::uniform(64.0),
+            &assets.bold,
+            &pp,
+        );
+        let combo = match self.map_max_combo {
+            Some(max) => format!("{}x/{}x", self.max_combo, max),
+            None => format!("{}x", self.max_combo),
+        };
+        draw_text_mut(
+            &mut img,
+            WHITE,
+            left,
+            185,
+            Scale::uniform(30.0),
+            &assets.regular,
+            &format!(
+                "{:.2}%  •  {}  •  {} miss",
+                self.accuracy, combo, self.misses
+            ),
+        );
+
+        // Mods and player, right aligned
+        if self.mods != Mods::NOMOD {
+            draw_right_aligned(
+                &mut img,
+                &assets.bold,
+                Scale::uniform(40.0),
+                &self.mods.to_string(),
+                120,
+            );
+        }
+        draw_right_aligned(
+            &mut img,
+            &assets.regular,
+            Scale::uniform(22.0),
+            &format!("{} • {}", self.player, self.date.format("%Y-%m-%d")),
+            HEIGHT as i32 - 35,
+        );
+        img
+    }
+}
+
+/// Resize the background to fill the card.
+pub(crate) fn fit_background(bg: &image::DynamicImage) -> RgbaImage {
+    bg.resize_to_fill(WIDTH, HEIGHT, imageops::FilterType::Triangle)
+        .to_rgba8()
+}
+
+fn darken(img: &mut RgbaImage, ratio: f32) {
+    for p in img.pixels_mut() {
+        for c in p.0.iter_mut().take(3) {
+            *c = (*c as f32 * ratio) as u8;
+        }
+    }
+}
+
+fn draw_right_aligned(img: &mut RgbaImage, font: &Font, scale: Scale, text: &str, y: i32) {
+    let (w, _) = text_size(scale, font, text);
+    draw_text_mut(img, WHITE, WIDTH as i32 - 30 - w, y, scale, font, text);
+}
+
+/// Cut the text short with an ellipsis so that it fits within `max_width`.
+pub(super) fn ellipsize(font: &Font, scale: Scale, text: &str, max_width: i32) -> String {
+    if text_size(scale, font, text).0 <= max_width {
+        return text.to_owned();
+    }
+    let mut chars = text.chars().collect::<Vec<_>>();
+    while !chars.is_empty() {
+        chars.pop();
+        let s = format!("{}…", chars.iter().collect::<String>().trim_end());
+        if text_size(scale, font, &s).0 <= max_width {
+            return s;
+        }
+    }
+    "…".to_owned()
+}
+
+fn grade_text(rank: Rank) -> &'static str {
+    match rank {
+        Rank::SS | Rank::SSH => "SS",
+        Rank::S | Rank::SH => "S",
+        Rank::A => "A",
+        Rank::B => "B",
+        Rank::C => "C",
+        Rank::D => "D",
+        Rank::F => "F",
+    }
+}
+
+fn grade_color(rank: Rank) -> Rgba<u8> {
+    match rank {
+        Rank::SSH | Rank::SH => Rgba([220, 220, 235, 255]),
+        Rank::SS | Rank::S => Rgba([255, 205, 60, 255]),
+        Rank::A => Rgba([110, 220, 90, 255]),
+        Rank::B => Rgba([80, 160, 255, 255]),
+        Rank::C => Rgba([200, 110, 230, 255]),
+        Rank::D => Rgba([255, 90, 90, 255]),
+        Rank::F => Rgba([150, 150, 150, 255]),
+    }
+}
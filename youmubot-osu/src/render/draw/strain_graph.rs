@@ -0,0 +1,111 @@
+use image::{Rgba, RgbaImage};
+use imageproc::{
+    drawing::{draw_filled_rect_mut, draw_line_segment_mut, draw_text_mut},
+    rect::Rect,
+};
+use rusttype::Scale;
+
+use crate::render::Strains;
+
+use super::assets::Assets;
+
+/// The size of a strain graph.
+pub const WIDTH: u32 = 900;
+pub const HEIGHT: u32 = 250;
+
+const MARGIN_LEFT: i32 = 20;
+const MARGIN_RIGHT: i32 = 20;
+const MARGIN_TOP: i32 = 40;
+const MARGIN_BOTTOM: i32 = 30;
+
+const BACKGROUND: Rgba<u8> = Rgba([40, 40, 48, 255]);
+const AXIS: Rgba<u8> = Rgba([120, 120, 130, 255]);
+const TEXT: Rgba<u8> = Rgba([200, 200, 200, 255]);
+const COLORS: [Rgba<u8>; 3] = [
+    Rgba([255, 182, 193, 255]),
+    Rgba([102, 204, 255, 255]),
+    Rgba([255, 205, 60, 255]),
+];
+
+impl Strains {
+    fn sections(&self) -> usize {
+        self.skills.iter().map(|(_, v)| v.len()).max().unwrap_or(0)
+    }
+
+    /// Draw the strains as a line graph over time.
+    pub(crate) fn draw(&self, assets: &Assets) -> RgbaImage {
+        let mut img = RgbaImage::from_pixel(WIDTH, HEIGHT, BACKGROUND);
+        let sections = self.sections();
+        let max = self
+            .skills
+            .iter()
+            .flat_map(|(_, v)| v.iter().copied())
+            .fold(0.0f64, f64::max);
+
+        let (left, right) = (MARGIN_LEFT, WIDTH as i32 - MARGIN_RIGHT);
+        let (top, bottom) = (MARGIN_TOP, HEIGHT as i32 - MARGIN_BOTTOM);
+        let x_of = |i: usize| {
+            left as f32 + (right - left) as f32 * i as f32 / (sections.max(2) - 1) as f32
+        };
+        let y_of = |v: f64| {
+            let ratio = if max > 0.0 { v / max } else { 0.0 };
+            bottom as f32 - (bottom - top) as f32 * ratio as f32
+        };
+
+        // Axis and time labels, one every minute (or every 15 seconds on short maps).
+        draw_line_segment_mut(
+            &mut img,
+            (left as f32, bottom as f32),
+            (right as f32, bottom as f32),
+            AXIS,
+        );
+        let total_ms = self.section_len * sections as f64;
+        let step_ms = if total_ms > 120_000.0 {
+            60_000.0
+        } else {
+            15_000.0
+        };
+        let label_scale = Scale::uniform(16.0);
+        let mut t = 0.0;
+        while t <= total_ms && self.section_len > 0.0 {
+            let x = x_of((t / self.section_len) as usize);
+            draw_line_segment_mut(&mut img, (x, bottom as f32), (x, bottom as f32 + 4.0), AXIS);
+            let secs = (t / 1000.0) as u64;
+            draw_text_mut(
+                &mut img,
+                TEXT,
+                x as i32 - 12,
+                bottom + 6,
+                label_scale,
+                &assets.regular,
+                &format!("{}:{:02}", secs / 60, secs % 60),
+            );
+            t += step_ms;
+        }
+
+        // The lines, and a legend.
+        let mut legend_x = left;
+        for ((name, values), color) in self.skills.iter().zip(COLORS.iter().cycle()) {
+            for (i, w) in values.windows(2).enumerate() {
+                draw_line_segment_mut(
+                    &mut img,
+                    (x_of(i), y_of(w[0])),
+                    (x_of(i + 1), y_of(w[1])),
+                    *color,
+                );
+            }
+            draw_filled_rect_mut(&mut img, Rect::at(legend_x, 14).of_size(14, 14), *color);
+            draw_text_mut(
+                &mut img,
+                TEXT,
+                legend_x + 20,
+                12,
+                Scale::uniform(18.0),
+                &assets.regular,
+                name,
+            );
+            legend_x += 30 + 12 * name.len() as i32;
+        }
+        img
+    }
+}
@@ -0,0 +1,6 @@
+/// Play counts by day of the week (Monday first) and hour of the day.
+#[derive(Debug, Clone)]
+pub struct Heatmap {
+    pub title: String,
+    pub counts: [[u32; 24]; 7],
+}
@@ -0,0 +1,14 @@
+use chrono::{DateTime, Utc};
+
+/// A statistic of an user over time.
+#[derive(Debug, Clone)]
+pub struct History {
+    /// The title of the graph, e.g. "natsukagami's rank (osu!)".
+    pub title: String,
+    /// The data points, oldest first.
+    pub points: Vec<(DateTime<Utc>, f64)>,
+    /// Whether lower values are better (e.g. rank), in which case the y-axis is flipped.
+    pub lower_is_better: bool,
+    /// How to format the values on the y-axis.
+    pub format: fn(f64) -> String,
+}
@@ -0,0 +1,24 @@
+/// A row of a leaderboard.
+#[derive(Debug, Clone)]
+pub struct LeaderboardEntry {
+    pub rank: usize,
+    /// The osu! username.
+    pub username: String,
+    /// The name of the member in the server.
+    pub member: String,
+    /// The main value, e.g. "12345.67pp".
+    pub value: String,
+    /// A secondary value shown next to the main one, e.g. the map length.
+    pub extra: Option<String>,
+    pub avatar_url: String,
+    /// The flag image of the user's country, if known.
+    pub flag_url: Option<String>,
+}
+
+/// A server leaderboard, rendered as a table.
+#[derive(Debug, Clone)]
+pub struct Leaderboard {
+    pub title: String,
+    pub subtitle: String,
+    pub entries: Vec<LeaderboardEntry>,
+}
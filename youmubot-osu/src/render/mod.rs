@@ -0,0 +1,77 @@
+//! Image rendering, e.g. score cards, leaderboards and various graphs.
+//!
+//! Commands describe what to draw with the types in this module, and hand them to a [`Renderer`].
+//! The image-based renderer is behind the `image-render` feature, and needs fonts loaded from the
+//! directory given by `osu.render_assets` in the configuration. Without either, there is no
+//! renderer and commands only send embeds.
+use std::sync::Arc;
+
+use youmubot_prelude::{settings::OsuConfig, *};
+
+pub use heatmap::Heatmap;
+pub use history_graph::History;
+pub use leaderboard::{Leaderboard, LeaderboardEntry};
+pub use profile_card::{ProfileCard, ProfileTopPlay};
+pub use scatter::{Scatter, ScatterPoint};
+pub use score_card::ScoreCard;
+pub use strain_graph::Strains;
+
+#[cfg(feature = "image-render")]
+mod draw;
+mod heatmap;
+mod history_graph;
+mod leaderboard;
+mod profile_card;
+mod scatter;
+mod score_card;
+mod strain_graph;
+
+/// Renders images as PNGs.
+#[async_trait]
+pub trait Renderer: std::fmt::Debug + Send + Sync {
+    /// Render a score card.
+    async fn score_card(&self, card: ScoreCard) -> Result<Arc<Vec<u8>>>;
+
+    /// Render a strain graph. `key` should uniquely identify the beatmap, mode and mods.
+    /// `strains` is only called if the graph is not cached, on a blocking thread.
+    async fn strain_graph(
+        &self,
+        key: String,
+        strains: Box<dyn FnOnce() -> Strains + Send>,
+    ) -> Result<Arc<Vec<u8>>>;
+
+    /// Render an user's history graph.
+    async fn history_graph(&self, history: History) -> Result<Vec<u8>>;
+
+    /// Render an activity heatmap.
+    async fn heatmap(&self, heatmap: Heatmap) -> Result<Vec<u8>>;
+
+    /// Render an user's profile card.
+    async fn profile_card(&self, card: ProfileCard) -> Result<Vec<u8>>;
+
+    /// Render a scatter plot.
+    async fn scatter(&self, scatter: Scatter) -> Result<Vec<u8>>;
+
+    /// Render a leaderboard, with the avatars and flags of the users.
+    async fn leaderboard(&self, leaderboard: Leaderboard) -> Result<Vec<u8>>;
+}
+
+/// Create the renderer with the assets at `osu.render_assets`.
+/// Returns `None` if rendering is not configured, or not compiled in.
+pub fn from_config(http: reqwest::Client, config: &OsuConfig) -> Result<Option<Arc<dyn Renderer>>> {
+    #[cfg(feature = "image-render")]
+    {
+        Ok(draw::ImageRenderer::from_config(http, config)?
+            .map(|r| Arc::new(r) as Arc<dyn Renderer>))
+    }
+    #[cfg(not(feature = "image-render"))]
+    {
+        let _ = http;
+        if config.render_assets.is_some() {
+            tracing::warn!(
+                "osu: render assets are set, but image rendering was not compiled in. Only embeds will be sent."
+            );
+        }
+        Ok(None)
+    }
+}
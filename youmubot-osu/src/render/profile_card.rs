@@ -0,0 +1,55 @@
+use crate::models::{Mode, User};
+
+/// The best play shown on a profile card.
+#[derive(Debug, Clone)]
+pub struct ProfileTopPlay {
+    /// The beatmap, in the `Artist - Title [Difficulty]` format.
+    pub beatmap: String,
+    pub mods: String,
+    pub pp: Option<f64>,
+}
+
+/// An user's profile summary, drawn as a shareable card.
+#[derive(Debug, Clone)]
+pub struct ProfileCard {
+    pub user_id: u64,
+    pub username: String,
+    pub country: String,
+    pub mode: Mode,
+    pub rank: u64,
+    pub country_rank: u64,
+    pub pp: Option<f64>,
+    pub accuracy: f64,
+    pub play_count: u64,
+    pub level: f64,
+    /// The weighted map length of the top plays, in seconds.
+    pub map_length: Option<f64>,
+    pub top_play: Option<ProfileTopPlay>,
+    /// Global rank over the last days, oldest first.
+    pub rank_history: Vec<u64>,
+}
+
+impl ProfileCard {
+    pub fn new(
+        user: &User,
+        mode: Mode,
+        map_length: Option<f64>,
+        top_play: Option<ProfileTopPlay>,
+    ) -> Self {
+        Self {
+            user_id: user.id,
+            username: user.username.clone(),
+            country: user.country.clone(),
+            mode,
+            rank: user.rank,
+            country_rank: user.country_rank,
+            pp: user.pp,
+            accuracy: user.accuracy,
+            play_count: user.play_count,
+            level: user.level,
+            map_length,
+            top_play,
+            rank_history: user.rank_history.clone(),
+        }
+    }
+}
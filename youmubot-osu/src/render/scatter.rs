@@ -0,0 +1,17 @@
+/// A point on a scatter plot.
+#[derive(Debug, Clone)]
+pub struct ScatterPoint {
+    pub x: f64,
+    pub y: f64,
+    /// The group of the point, which decides its color (e.g. the mods).
+    pub group: String,
+}
+
+/// A scatter plot, with points colored by their group.
+#[derive(Debug, Clone)]
+pub struct Scatter {
+    pub title: String,
+    pub x_label: String,
+    pub y_label: String,
+    pub points: Vec<ScatterPoint>,
+}
@@ -0,0 +1,56 @@
+use crate::models::{Beatmap, Mode, Mods, Rank, Score, User};
+
+/// Everything that goes on a score card.
+#[derive(Debug, Clone)]
+pub struct ScoreCard {
+    pub beatmapset_id: u64,
+    pub artist: String,
+    pub title: String,
+    pub difficulty_name: String,
+    pub creator: String,
+    pub stars: f64,
+
+    pub player: String,
+    pub rank: Rank,
+    pub pp: Option<f64>,
+    pub accuracy: f64,
+    pub max_combo: u64,
+    pub map_max_combo: Option<u64>,
+    pub misses: u64,
+    pub mods: Mods,
+    pub date: chrono::DateTime<chrono::Utc>,
+
+    /// The score id, if the score was submitted.
+    pub score_id: Option<u64>,
+}
+
+impl ScoreCard {
+    /// Collect the score card data from a score.
+    pub fn new(
+        score: &Score,
+        beatmap: &Beatmap,
+        mode: Mode,
+        user: &User,
+        stars: f64,
+        map_max_combo: Option<u64>,
+    ) -> Self {
+        Self {
+            beatmapset_id: beatmap.beatmapset_id,
+            artist: beatmap.artist.clone(),
+            title: beatmap.title.clone(),
+            difficulty_name: beatmap.difficulty_name.clone(),
+            creator: beatmap.creator.clone(),
+            stars,
+            player: user.username.clone(),
+            rank: score.rank,
+            pp: score.pp,
+            accuracy: score.accuracy(mode),
+            max_combo: score.max_combo,
+            map_max_combo,
+            misses: score.count_miss,
+            mods: score.mods,
+            date: score.date,
+            score_id: score.id,
+        }
+    }
+}
@@ -0,0 +1,8 @@
+/// The strains of each skill over time, as computed by the difficulty calculator.
+#[derive(Debug, Clone)]
+pub struct Strains {
+    /// The length of each strain section, in milliseconds.
+    pub section_len: f64,
+    /// The name and strain values of each skill.
+    pub skills: Vec<(&'static str, Vec<f64>)>,
+}
@@ -0,0 +1,428 @@
+//! Parsing of `.osr` replay files, and hit timing analysis from replay data.
+//!
+//! See https://osu.ppy.sh/wiki/en/Client/File_formats/osr_%28file_format%29 for the format.
+use std::io::{Read, Write};
+
+use youmubot_prelude::*;
+
+use crate::models::{ModSettings, Mode, Mods, Rank, Score};
+
+/// The number of Windows ticks (100ns) between 0001-01-01 and the Unix epoch.
+const UNIX_EPOCH_TICKS: i64 = 621_355_968_000_000_000;
+/// The largest decompressed replay data accepted, well above that of the longest maps.
+const MAX_REPLAY_DATA: usize = 64 * 1024 * 1024;
+
+/// A parsed `.osr` replay.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub mode: Mode,
+    pub version: i32,
+    pub beatmap_hash: String,
+    pub player: String,
+    pub replay_hash: String,
+
+    pub count_300: u16,
+    pub count_100: u16,
+    pub count_50: u16,
+    pub count_geki: u16,
+    pub count_katu: u16,
+    pub count_miss: u16,
+    pub score: i32,
+    pub max_combo: u16,
+    pub perfect: bool,
+    pub mods: Mods,
+
+    /// Windows ticks since 0001-01-01.
+    pub timestamp: i64,
+    pub frames: Vec<ReplayFrame>,
+    pub online_score_id: i64,
+}
+
+/// A single replay frame, with the time being absolute (in milliseconds since the beginning of the map).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayFrame {
+    pub time: f64,
+    pub x: f32,
+    pub y: f32,
+    pub keys: u32,
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.0.len() < n {
+            bail!("unexpected end of replay file");
+        }
+        let (l, r) = self.0.split_at(n);
+        self.0 = r;
+        Ok(l)
+    }
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into()?))
+    }
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_le_bytes(self.bytes(8)?.try_into()?))
+    }
+    fn uleb128(&mut self) -> Result<usize> {
+        let mut res = 0u64;
+        let mut shift = 0;
+        loop {
+            let b = self.u8()?;
+            res |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(res.try_into()?);
+            }
+            shift += 7;
+            if shift > 63 {
+                bail!("invalid uleb128 length");
+            }
+        }
+    }
+    fn string(&mut self) -> Result<String> {
+        match self.u8()? {
+            0x00 => Ok(String::new()),
+            0x0b => {
+                let len = self.uleb128()?;
+                Ok(String::from_utf8(self.bytes(len)?.to_vec())?)
+            }
+            v => bail!("invalid string marker {:#x}", v),
+        }
+    }
+}
+
+impl Replay {
+    /// Parse a replay from the content of an `.osr` file.
+    pub fn parse(content: &[u8]) -> Result<Replay> {
+        let mut r = Reader(content);
+        let mode = match r.u8()? {
+            v @ 0..=3 => Mode::from(v),
+            v => bail!("invalid mode {}", v),
+        };
+        let version = r.i32()?;
+        let beatmap_hash = r.string()?;
+        let player = r.string()?;
+        let replay_hash = r.string()?;
+        let count_300 = r.u16()?;
+        let count_100 = r.u16()?;
+        let count_50 = r.u16()?;
+        let count_geki = r.u16()?;
+        let count_katu = r.u16()?;
+        let count_miss = r.u16()?;
+        let score = r.i32()?;
+        let max_combo = r.u16()?;
+        let perfect = r.u8()? != 0;
+        let mods = Mods::from_bits_truncate(r.i32()? as u32 as u64);
+        let _life_bar = r.string()?;
+        let timestamp = r.i64()?;
+        let data_len = r.i32()?;
+        let data = r.bytes(data_len.max(0) as usize)?;
+        let online_score_id = r.i64().unwrap_or(0);
+
+        let mut decompressed = LimitedWriter(Vec::new());
+        lzma_rs::lzma_decompress(&mut std::io::BufReader::new(data), &mut decompressed)
+            .map_err(|e| error!("cannot decompress replay data: {:?}", e))?;
+        let mut frames_str = String::new();
+        (&decompressed.0[..]).read_to_string(&mut frames_str)?;
+
+        Ok(Replay {
+            mode,
+            version,
+            beatmap_hash,
+            player,
+            replay_hash,
+            count_300,
+            count_100,
+            count_50,
+            count_geki,
+            count_katu,
+            count_miss,
+            score,
+            max_combo,
+            perfect,
+            mods,
+            timestamp,
+            frames: parse_frames(&frames_str)?,
+            online_score_id,
+        })
+    }
+
+    /// When the replay was set.
+    pub fn date(&self) -> chrono::DateTime<chrono::Utc> {
+        let Some(ticks) = self.timestamp.checked_sub(UNIX_EPOCH_TICKS) else {
+            return Default::default();
+        };
+        chrono::DateTime::from_timestamp(ticks / 10_000_000, ((ticks % 10_000_000) * 100) as u32)
+            .unwrap_or_default()
+    }
+
+    /// Turn the replay into a score on the given beatmap, by the given user.
+    /// The grade is computed from the hit counts, and the pp is left out.
+    pub fn to_score(&self, beatmap_id: u64, user_id: u64) -> Score {
+        let mut score = Score {
+            id: Some(self.online_score_id as u64).filter(|&id| id > 0),
+            user_id,
+            date: self.date(),
+            replay_available: true,
+            beatmap_id,
+            score: Some(self.score as u64),
+            normalized_score: self.score.max(0) as u32,
+            pp: None,
+            rank: Rank::F,
+            mods: self.mods,
+            count_300: self.count_300 as u64,
+            count_100: self.count_100 as u64,
+            count_50: self.count_50 as u64,
+            count_miss: self.count_miss as u64,
+            count_katu: self.count_katu as u64,
+            count_geki: self.count_geki as u64,
+            max_combo: self.max_combo as u64,
+            perfect: self.perfect,
+            lazer_build_id: None,
+            mod_settings: ModSettings::default(),
+            global_rank: None,
+        };
+        score.rank = self.rank(score.accuracy(self.mode));
+        score
+    }
+
+    /// The grade of the replay, following the stable rules.
+    fn rank(&self, accuracy: f64) -> Rank {
+        let silver = self.mods.intersects(Mods::HD | Mods::FL);
+        let (ss, s) = if silver {
+            (Rank::SSH, Rank::SH)
+        } else {
+            (Rank::SS, Rank::S)
+        };
+        match self.mode {
+            Mode::Std | Mode::Taiko => {
+                let total =
+                    (self.count_300 + self.count_100 + self.count_50 + self.count_miss) as f64;
+                let ratio_300 = self.count_300 as f64 / total;
+                let ratio_50 = self.count_50 as f64 / total;
+                let no_miss = self.count_miss == 0;
+                if ratio_300 >= 1.0 {
+                    ss
+                } else if ratio_300 > 0.9 && ratio_50 <= 0.01 && no_miss {
+                    s
+                } else if (ratio_300 > 0.8 && no_miss) || ratio_300 > 0.9 {
+                    Rank::A
+                } else if (ratio_300 > 0.7 && no_miss) || ratio_300 > 0.8 {
+                    Rank::B
+                } else if ratio_300 > 0.6 {
+                    Rank::C
+                } else {
+                    Rank::D
+                }
+            }
+            Mode::Catch | Mode::Mania => match accuracy {
+                a if a >= 100.0 => ss,
+                a if a > 95.0 => s,
+                a if a > 90.0 => Rank::A,
+                a if a > 80.0 => Rank::B,
+                a if a > 70.0 => Rank::C,
+                _ => Rank::D,
+            },
+        }
+    }
+}
+
+/// A buffer that refuses to grow past [MAX_REPLAY_DATA].
+struct LimitedWriter(Vec<u8>);
+
+impl Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.0.len() + buf.len() > MAX_REPLAY_DATA {
+            return Err(std::io::Error::other("replay data is too large"));
+        }
+        self.0.write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Parse the (decompressed) frame list, in the `w|x|y|z,` format.
+fn parse_frames(s: &str) -> Result<Vec<ReplayFrame>> {
+    let mut time = 0.0;
+    let mut frames = Vec::new();
+    for frame in s.split(',').filter(|v| !v.is_empty()) {
+        let mut parts = frame.split('|');
+        let (w, x, y, z) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(w), Some(x), Some(y), Some(z)) => (w, x, y, z),
+            _ => bail!("invalid replay frame `{}`", frame),
+        };
+        let w: f64 = w.parse()?;
+        // The RNG seed frame
+        if w == -12345.0 {
+            continue;
+        }
+        time += w;
+        frames.push(ReplayFrame {
+            time,
+            x: x.parse()?,
+            y: y.parse()?,
+            keys: z.parse::<f64>()? as u32,
+        });
+    }
+    Ok(frames)
+}
+
+/// Hit timing statistics computed from a replay.
+#[derive(Debug, Clone, Copy)]
+pub struct HitStats {
+    /// Unstable rate, in real time (speed changing mods are accounted for).
+    pub unstable_rate: f64,
+    /// Average hit error in milliseconds. Negative means early.
+    pub average_error: f64,
+}
+
+impl HitStats {
+    /// Compute the hit statistics from a list of hit errors (in milliseconds, map time).
+    fn from_errors(errors: &[f64], clock_rate: f64) -> Option<HitStats> {
+        if errors.is_empty() {
+            return None;
+        }
+        let n = errors.len() as f64;
+        let mean = errors.iter().sum::<f64>() / n;
+        let variance = errors.iter().map(|e| (e - mean) * (e - mean)).sum::<f64>() / n;
+        Some(HitStats {
+            unstable_rate: variance.sqrt() * 10.0 / clock_rate,
+            average_error: mean / clock_rate,
+        })
+    }
+
+    /// Compute the hit statistics of an osu!standard replay.
+    ///
+    /// This is an approximation: each new key press is matched against the earliest
+    /// un-hit object, if the cursor is on top of it and the press is within the 50 hit window.
+    pub fn from_replay(replay: &Replay, beatmap: &rosu_pp::Beatmap) -> Option<HitStats> {
+        if replay.mode != Mode::Std {
+            return None;
+        }
+        let mods = replay.mods;
+        let (mut od, mut cs) = (beatmap.od as f64, beatmap.cs as f64);
+        if mods.contains(Mods::EZ) {
+            od *= 0.5;
+            cs *= 0.5;
+        }
+        if mods.contains(Mods::HR) {
+            od = (od * 1.4).min(10.0);
+            cs = (cs * 1.3).min(10.0);
+        }
+        let window_50 = 200.0 - 10.0 * od;
+        let radius = (54.4 - 4.48 * cs) as f32;
+        let clock_rate = if mods.contains(Mods::DT) {
+            1.5
+        } else if mods.contains(Mods::HT) {
+            0.75
+        } else {
+            1.0
+        };
+
+        let objects = beatmap
+            .hit_objects
+            .iter()
+            .filter(|h| !h.is_spinner())
+            .collect::<Vec<_>>();
+        let mut errors = Vec::with_capacity(objects.len());
+        let mut next = 0;
+        let mut prev_keys = 0;
+        for frame in &replay.frames {
+            // K1/K2 are always set alongside M1/M2, so looking at M1/M2 covers both.
+            let pressed = frame.keys & !prev_keys & 0b11;
+            prev_keys = frame.keys;
+            if pressed == 0 {
+                continue;
+            }
+            // Skip over all missed objects.
+            while next < objects.len() && objects[next].start_time + window_50 < frame.time {
+                next += 1;
+            }
+            let Some(obj) = objects.get(next) else {
+                break;
+            };
+            let error = frame.time - obj.start_time;
+            if error.abs() > window_50 {
+                continue;
+            }
+            let obj_y = if mods.contains(Mods::HR) {
+                384.0 - obj.pos.y
+            } else {
+                obj.pos.y
+            };
+            let (dx, dy) = (frame.x - obj.pos.x, frame.y - obj_y);
+            if dx * dx + dy * dy > radius * radius {
+                continue;
+            }
+            errors.push(error);
+            next += 1;
+        }
+        Self::from_errors(&errors, clock_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frames() {
+        let frames = parse_frames("0|256|-500|0,-1|256|-500|0,16|100.5|200|1,-12345|0|0|1337,")
+            .unwrap();
+        assert_eq!(
+            frames,
+            vec![
+                ReplayFrame {
+                    time: 0.0,
+                    x: 256.0,
+                    y: -500.0,
+                    keys: 0
+                },
+                ReplayFrame {
+                    time: -1.0,
+                    x: 256.0,
+                    y: -500.0,
+                    keys: 0
+                },
+                ReplayFrame {
+                    time: 15.0,
+                    x: 100.5,
+                    y: 200.0,
+                    keys: 1
+                },
+            ]
+        );
+        assert!(parse_frames("0|1|2").is_err());
+    }
+
+    #[test]
+    fn test_hit_stats() {
+        let stats = HitStats::from_errors(&[-10.0, 10.0, -10.0, 10.0], 1.5).unwrap();
+        assert!((stats.unstable_rate - 100.0 / 1.5).abs() < 1e-9);
+        assert!(stats.average_error.abs() < 1e-9);
+        assert!(HitStats::from_errors(&[], 1.0).is_none());
+    }
+
+    #[test]
+    fn test_read_string() {
+        let mut r = Reader(&[0x0b, 0x03, b'a', b'b', b'c', 0x00]);
+        assert_eq!(r.string().unwrap(), "abc");
+        assert_eq!(r.string().unwrap(), "");
+        assert!(r.string().is_err());
+    }
+
+    #[test]
+    fn test_read_uleb128() {
+        let mut r = Reader(&[0xc8, 0x01]);
+        assert_eq!(r.uleb128().unwrap(), 200);
+        let mut r = Reader(&[0xff; 16]);
+        assert!(r.uleb128().is_err());
+    }
+}
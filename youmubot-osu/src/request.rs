@@ -1,10 +1,10 @@
 use crate::models::{Mode, Mods};
-use crate::Client;
+use crate::ClientError;
 use chrono::{DateTime, Utc};
 use rosu_v2::error::OsuError;
 use youmubot_prelude::*;
 
-trait ToQuery {
+pub(crate) trait ToQuery {
     fn to_query(&self) -> Vec<(&'static str, String)>;
 }
 
@@ -50,6 +50,7 @@ impl ToQuery for (&'static str, DateTime<Utc>) {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum UserID {
     Username(String),
     ID(u64),
@@ -83,6 +84,7 @@ impl ToQuery for UserID {
         }
     }
 }
+#[derive(Clone, Debug)]
 pub enum BeatmapRequestKind {
     Beatmap(u64),
     Beatmapset(u64),
@@ -100,7 +102,7 @@ impl ToQuery for BeatmapRequestKind {
     }
 }
 
-fn handle_not_found<T>(v: Result<T, OsuError>) -> Result<Option<T>, OsuError> {
+pub(crate) fn handle_not_found<T>(v: Result<T, OsuError>) -> Result<Option<T>, OsuError> {
     match v {
         Ok(v) => Ok(Some(v)),
         Err(OsuError::NotFound) => Ok(None),
@@ -115,9 +117,10 @@ pub mod builders {
 
     use super::*;
     /// A builder for a Beatmap request.
+    #[derive(Clone)]
     pub struct BeatmapRequestBuilder {
-        kind: BeatmapRequestKind,
-        mode: Option<(Mode, /* Converted */ bool)>,
+        pub(crate) kind: BeatmapRequestKind,
+        pub(crate) mode: Option<(Mode, /* Converted */ bool)>,
     }
     impl BeatmapRequestBuilder {
         pub(crate) fn new(kind: BeatmapRequestKind) -> Self {
@@ -137,10 +140,13 @@ pub mod builders {
             self
         }
 
-        pub(crate) async fn build(self, client: &Client) -> Result<Vec<models::Beatmap>> {
+        pub(crate) async fn build(
+            self,
+            osu: &rosu_v2::Osu,
+        ) -> Result<Vec<models::Beatmap>, ClientError> {
             Ok(match self.kind {
                 BeatmapRequestKind::Beatmap(id) => {
-                    match handle_not_found(client.rosu.beatmap().map_id(id as u32).await)? {
+                    match handle_not_found(osu.beatmap().map_id(id as u32).await)? {
                         Some(mut bm) => {
                             let set = bm.mapset.take().unwrap();
                             vec![models::Beatmap::from_rosu(bm, &set)]
@@ -149,7 +155,7 @@ pub mod builders {
                     }
                 }
                 BeatmapRequestKind::Beatmapset(id) => {
-                    let mut set = match handle_not_found(client.rosu.beatmapset(id as u32).await)? {
+                    let mut set = match handle_not_found(osu.beatmapset(id as u32).await)? {
                         Some(v) => v,
                         None => return Ok(vec![]),
                     };
@@ -159,8 +165,7 @@ pub mod builders {
                         .collect()
                 }
                 BeatmapRequestKind::BeatmapHash(hash) => {
-                    let mut bm = match handle_not_found(client.rosu.beatmap().checksum(hash).await)?
-                    {
+                    let mut bm = match handle_not_found(osu.beatmap().checksum(hash).await)? {
                         Some(v) => v,
                         None => return Ok(vec![]),
                     };
@@ -171,10 +176,11 @@ pub mod builders {
         }
     }
 
+    #[derive(Clone)]
     pub struct UserRequestBuilder {
-        user: UserID,
-        mode: Option<Mode>,
-        event_days: Option<u8>,
+        pub(crate) user: UserID,
+        pub(crate) mode: Option<Mode>,
+        pub(crate) event_days: Option<u8>,
     }
 
     impl UserRequestBuilder {
@@ -196,8 +202,11 @@ pub mod builders {
             self
         }
 
-        pub(crate) async fn build(self, client: &Client) -> Result<Option<models::User>> {
-            let mut r = client.rosu.user(self.user);
+        pub(crate) async fn build(
+            self,
+            osu: &rosu_v2::Osu,
+        ) -> Result<Option<models::User>, ClientError> {
+            let mut r = osu.user(self.user);
             if let Some(mode) = self.mode {
                 r = r.mode(mode.into());
             }
@@ -207,21 +216,21 @@ pub mod builders {
             };
             let now = time::OffsetDateTime::now_utc()
                 - time::Duration::DAY * self.event_days.unwrap_or(31);
-            let mut events =
-                handle_not_found(client.rosu.recent_events(user.user_id).limit(50).await)?
-                    .unwrap_or(vec![]);
+            let mut events = handle_not_found(osu.recent_events(user.user_id).limit(50).await)?
+                .unwrap_or(vec![]);
             events.retain(|e| (now <= e.created_at));
             let stats = user.statistics.take().unwrap();
             Ok(Some(models::User::from_rosu(user, stats, events)))
         }
     }
 
+    #[derive(Clone)]
     pub struct ScoreRequestBuilder {
-        beatmap_id: u64,
-        user: Option<UserID>,
-        mode: Option<Mode>,
-        mods: Option<Mods>,
-        limit: Option<u8>,
+        pub(crate) beatmap_id: u64,
+        pub(crate) user: Option<UserID>,
+        pub(crate) mode: Option<Mode>,
+        pub(crate) mods: Option<Mods>,
+        pub(crate) limit: Option<u8>,
     }
 
     impl ScoreRequestBuilder {
@@ -255,12 +264,13 @@ pub mod builders {
             self
         }
 
-        pub(crate) async fn build(self, client: &Client) -> Result<Vec<models::Score>> {
+        pub(crate) async fn build(
+            self,
+            osu: &rosu_v2::Osu,
+        ) -> Result<Vec<models::Score>, ClientError> {
             let scores = handle_not_found(match self.user {
                 Some(user) => {
-                    let mut r = client
-                        .rosu
-                        .beatmap_user_scores(self.beatmap_id as u32, user);
+                    let mut r = osu.beatmap_user_scores(self.beatmap_id as u32, user);
                     if let Some(mode) = self.mode {
                         r = r.mode(mode.into());
                     }
@@ -274,7 +284,7 @@ pub mod builders {
                     }
                 }
                 None => {
-                    let mut r = client.rosu.beatmap_scores(self.beatmap_id as u32).global();
+                    let mut r = osu.beatmap_scores(self.beatmap_id as u32).global();
                     if let Some(mode) = self.mode {
                         r = r.mode(mode.into());
                     }
@@ -287,21 +297,23 @@ pub mod builders {
                     r.await
                 }
             })?
-            .ok_or_else(|| error!("beatmap or user not found"))?;
+            .ok_or(ClientError::NotFound)?;
             Ok(scores.into_iter().map(|v| v.into()).collect())
         }
     }
 
+    #[derive(Clone, Debug)]
     pub(crate) enum UserScoreType {
         Recent,
         Best,
     }
 
+    #[derive(Clone)]
     pub struct UserScoreRequestBuilder {
-        score_type: UserScoreType,
-        user: UserID,
-        mode: Option<Mode>,
-        limit: Option<u8>,
+        pub(crate) score_type: UserScoreType,
+        pub(crate) user: UserID,
+        pub(crate) mode: Option<Mode>,
+        pub(crate) limit: Option<u8>,
     }
 
     impl UserScoreRequestBuilder {
@@ -324,9 +336,17 @@ pub mod builders {
             self
         }
 
-        pub(crate) async fn build(self, client: &Client) -> Result<Vec<models::Score>> {
+        pub(crate) async fn build(
+            self,
+            osu: &rosu_v2::Osu,
+        ) -> Result<Vec<models::Score>, ClientError> {
+            // An user looked up by id exists, unless they were restricted.
+            let not_found = match self.user {
+                UserID::ID(_) => ClientError::Restricted,
+                UserID::Username(_) => ClientError::NotFound,
+            };
             let scores = handle_not_found({
-                let mut r = client.rosu.user_scores(self.user);
+                let mut r = osu.user_scores(self.user);
                 r = match self.score_type {
                     UserScoreType::Recent => r.recent().include_fails(true),
                     UserScoreType::Best => r.best(),
@@ -339,7 +359,7 @@ pub mod builders {
                 }
                 r.await
             })?
-            .ok_or_else(|| error!("user not found"))?;
+            .ok_or(not_found)?;
             Ok(scores.into_iter().map(|v| v.into()).collect())
         }
     }
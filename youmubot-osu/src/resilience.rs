@@ -0,0 +1,103 @@
+//! Timeouts, retries and a circuit breaker for the requests to the osu! API.
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use youmubot_prelude::settings::OsuConfig;
+
+/// How requests to the osu! API deal with failures.
+#[derive(Clone, Copy, Debug)]
+pub struct ResiliencePolicy {
+    /// How long to wait for a response.
+    pub timeout: Duration,
+    /// How many times a request failing with a timeout or a server error is retried.
+    pub max_retries: u32,
+    /// The wait before the first retry, doubled on each retry after it.
+    pub backoff: Duration,
+    /// After this many requests failing in a row, no request is sent for `cooldown`.
+    pub failure_threshold: u32,
+    pub cooldown: Duration,
+}
+
+impl Default for ResiliencePolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(15),
+            max_retries: 2,
+            backoff: Duration::from_millis(500),
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(60),
+        }
+    }
+}
+
+impl ResiliencePolicy {
+    /// The default policy, with the timeout and the number of retries from the configuration.
+    pub fn from_config(config: &OsuConfig) -> Self {
+        let mut policy = Self::default();
+        if let Some(timeout) = config.timeout {
+            policy.timeout = Duration::from_secs(timeout);
+        }
+        if let Some(retries) = config.retries {
+            policy.max_retries = retries;
+        }
+        policy
+    }
+
+    /// The wait before the given retry, counting from 0.
+    pub(crate) fn backoff_for(&self, retry: u32) -> Duration {
+        self.backoff * 2u32.saturating_pow(retry)
+    }
+}
+
+/// Stops sending requests for a while when the API keeps failing,
+/// instead of making every command wait for its timeouts.
+#[derive(Debug, Default)]
+pub(crate) struct CircuitBreaker {
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    /// The number of requests that failed in a row.
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Whether requests can be sent right now.
+    ///
+    /// Once the cooldown is over, requests are sent again, but the breaker opens
+    /// on the first failure until a request succeeds.
+    pub(crate) fn allows(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state
+            .open_until
+            .map_or(true, |until| Instant::now() >= until)
+    }
+
+    pub(crate) fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.failures = 0;
+        state.open_until = None;
+    }
+
+    pub(crate) fn record_failure(&self, policy: &ResiliencePolicy) {
+        let mut state = self.state.lock().unwrap();
+        state.failures += 1;
+        if state.failures >= policy.failure_threshold {
+            if state
+                .open_until
+                .map_or(true, |until| Instant::now() >= until)
+            {
+                tracing::warn!(
+                    "osu: API failed {} times in a row, pausing requests for {:?}",
+                    state.failures,
+                    policy.cooldown
+                );
+            }
+            state.open_until = Some(Instant::now() + policy.cooldown);
+        }
+    }
+}
@@ -0,0 +1,369 @@
+//! Private osu! servers, e.g. Ripple or Akatsuki, serving their own users and scores
+//! in the shape of the osu! API v1.
+//!
+//! Private servers host the beatmaps of osu!, so beatmap requests are still sent to the osu! API.
+//! Clients of a private server are built with [Client::with_server](crate::Client::with_server).
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{de::DeserializeOwned, de::Error as _, Deserialize, Deserializer};
+use serde_json::Value;
+use youmubot_prelude::async_trait;
+
+use crate::{
+    backend::OsuApi,
+    models::{Beatmap, Mods, Rank, Score, User},
+    request::{builders::*, ToQuery},
+    ClientError,
+};
+
+/// A private server, and how to reach its API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerProfile {
+    /// The name the server is picked by, e.g. `akatsuki`.
+    pub name: String,
+    /// The base of the API, where `get_user` and the other v1 endpoints are found.
+    pub api_url: String,
+    /// The website, with the user profiles at `/u/{id}`.
+    pub website: String,
+    /// The avatar host, with the avatars at `/{id}`.
+    pub avatars: String,
+    /// The API key, sent as the `k` parameter, for the servers that require one.
+    pub api_key: Option<String>,
+}
+
+impl ServerProfile {
+    /// The servers that are available without configuration.
+    pub fn builtin() -> Vec<ServerProfile> {
+        [("ripple", "ripple.moe"), ("akatsuki", "akatsuki.gg")]
+            .into_iter()
+            .map(|(name, domain)| ServerProfile {
+                name: name.to_owned(),
+                api_url: format!("https://{}/api", domain),
+                website: format!("https://{}", domain),
+                avatars: format!("https://a.{}", domain),
+                api_key: None,
+            })
+            .collect()
+    }
+
+    /// Link to the user's profile on the server.
+    pub fn user_link(&self, user_id: u64) -> String {
+        format!("{}/u/{}", self.website, user_id)
+    }
+
+    /// Link to the user's avatar on the server.
+    pub fn avatar_url(&self, user_id: u64) -> String {
+        format!("{}/{}", self.avatars, user_id)
+    }
+}
+
+/// A private server's API, with the beatmaps taken from the osu! API.
+pub(crate) struct PrivateServerApi {
+    profile: Arc<ServerProfile>,
+    http: reqwest::Client,
+    osu: Arc<dyn OsuApi>,
+}
+
+impl PrivateServerApi {
+    pub(crate) fn new(
+        profile: Arc<ServerProfile>,
+        osu: Arc<dyn OsuApi>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            profile,
+            http: reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .expect("a reqwest client with a timeout should build"),
+            osu,
+        }
+    }
+
+    /// Send a GET request to the given endpoint, e.g. `get_user`.
+    async fn get<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        mut query: Vec<(&'static str, String)>,
+    ) -> Result<T, ClientError> {
+        if let Some(key) = &self.profile.api_key {
+            query.push(("k", key.clone()));
+        }
+        let resp = self
+            .http
+            .get(format!("{}/{}", self.profile.api_url, endpoint))
+            .query(&query)
+            .send()
+            .await?;
+        match resp.status().as_u16() {
+            404 => return Err(ClientError::NotFound),
+            429 => return Err(ClientError::RateLimited { retry_after: None }),
+            status if status >= 500 => return Err(ClientError::Server { status }),
+            _ => (),
+        }
+        let body = resp.error_for_status()?.text().await?;
+        serde_json::from_str(&body).map_err(|_| ClientError::Deserialize { body })
+    }
+}
+
+#[async_trait]
+impl OsuApi for PrivateServerApi {
+    async fn beatmaps(&self, request: BeatmapRequestBuilder) -> Result<Vec<Beatmap>, ClientError> {
+        self.osu.beatmaps(request).await
+    }
+
+    async fn user(&self, request: UserRequestBuilder) -> Result<Option<User>, ClientError> {
+        let mut query = request.user.to_query();
+        query.extend(request.mode.to_query());
+        let users: Vec<RawUser> = self.get("get_user", query).await?;
+        Ok(users
+            .into_iter()
+            .next()
+            .map(|u| u.into_user(self.profile.clone())))
+    }
+
+    async fn scores(&self, request: ScoreRequestBuilder) -> Result<Vec<Score>, ClientError> {
+        let mut query = vec![("b", request.beatmap_id.to_string())];
+        query.extend(request.user.to_query());
+        query.extend(request.mode.to_query());
+        query.extend(request.mods.to_query());
+        if let Some(limit) = request.limit {
+            query.push(("limit", limit.to_string()));
+        }
+        let scores: Vec<RawScore> = self.get("get_scores", query).await?;
+        Ok(scores
+            .into_iter()
+            .map(|s| s.into_score(request.beatmap_id))
+            .collect())
+    }
+
+    async fn user_scores(
+        &self,
+        request: UserScoreRequestBuilder,
+    ) -> Result<Vec<Score>, ClientError> {
+        let endpoint = match request.score_type {
+            UserScoreType::Recent => "get_user_recent",
+            UserScoreType::Best => "get_user_best",
+        };
+        let mut query = request.user.to_query();
+        query.extend(request.mode.to_query());
+        if let Some(limit) = request.limit {
+            query.push(("limit", limit.to_string()));
+        }
+        let scores: Vec<RawScore> = self.get(endpoint, query).await?;
+        Ok(scores.into_iter().map(|s| s.into_score(0)).collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct RawUser {
+    #[serde(deserialize_with = "number")]
+    user_id: u64,
+    username: String,
+    #[serde(default, deserialize_with = "date")]
+    join_date: DateTime<Utc>,
+    #[serde(default)]
+    country: String,
+    #[serde(default, deserialize_with = "number")]
+    count300: u64,
+    #[serde(default, deserialize_with = "number")]
+    count100: u64,
+    #[serde(default, deserialize_with = "number")]
+    count50: u64,
+    #[serde(default, deserialize_with = "number")]
+    playcount: u64,
+    #[serde(default, deserialize_with = "number")]
+    total_seconds_played: u64,
+    #[serde(default, deserialize_with = "number")]
+    ranked_score: u64,
+    #[serde(default, deserialize_with = "number")]
+    total_score: u64,
+    #[serde(default, deserialize_with = "number")]
+    count_rank_ss: u64,
+    #[serde(default, deserialize_with = "number")]
+    count_rank_ssh: u64,
+    #[serde(default, deserialize_with = "number")]
+    count_rank_s: u64,
+    #[serde(default, deserialize_with = "number")]
+    count_rank_sh: u64,
+    #[serde(default, deserialize_with = "number")]
+    count_rank_a: u64,
+    #[serde(default, deserialize_with = "number")]
+    pp_rank: u64,
+    #[serde(default, deserialize_with = "number")]
+    pp_country_rank: u64,
+    #[serde(default, deserialize_with = "number")]
+    level: f64,
+    #[serde(default, deserialize_with = "number")]
+    pp_raw: f64,
+    #[serde(default, deserialize_with = "number")]
+    accuracy: f64,
+}
+
+impl RawUser {
+    fn into_user(self, server: Arc<ServerProfile>) -> User {
+        User {
+            id: self.user_id,
+            username: self.username,
+            joined: self.join_date,
+            country: self.country,
+            count_300: self.count300,
+            count_100: self.count100,
+            count_50: self.count50,
+            play_count: self.playcount,
+            played_time: Duration::from_secs(self.total_seconds_played),
+            ranked_score: self.ranked_score,
+            total_score: self.total_score,
+            count_ss: self.count_rank_ss,
+            count_ssh: self.count_rank_ssh,
+            count_s: self.count_rank_s,
+            count_sh: self.count_rank_sh,
+            count_a: self.count_rank_a,
+            // The events of the API v1 are not parsed.
+            events: vec![],
+            rank: self.pp_rank,
+            country_rank: self.pp_country_rank,
+            level: self.level,
+            pp: Some(self.pp_raw),
+            accuracy: self.accuracy,
+            rank_history: vec![],
+            total_hits: self.count300 + self.count100 + self.count50,
+            max_combo: 0,
+            replays_watched: 0,
+            badges: vec![],
+            server: Some(server),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawScore {
+    /// Missing from the scores of a beatmap, which are requested by the beatmap's id.
+    #[serde(default, deserialize_with = "number")]
+    beatmap_id: u64,
+    #[serde(deserialize_with = "number")]
+    user_id: u64,
+    #[serde(deserialize_with = "date")]
+    date: DateTime<Utc>,
+    #[serde(deserialize_with = "number")]
+    score: u64,
+    /// Missing from the failed plays.
+    #[serde(default, deserialize_with = "number")]
+    pp: f64,
+    rank: String,
+    #[serde(default, deserialize_with = "number")]
+    enabled_mods: u64,
+    #[serde(default, deserialize_with = "number")]
+    count300: u64,
+    #[serde(default, deserialize_with = "number")]
+    count100: u64,
+    #[serde(default, deserialize_with = "number")]
+    count50: u64,
+    #[serde(default, deserialize_with = "number")]
+    countmiss: u64,
+    #[serde(default, deserialize_with = "number")]
+    countkatu: u64,
+    #[serde(default, deserialize_with = "number")]
+    countgeki: u64,
+    #[serde(default, deserialize_with = "number")]
+    maxcombo: u64,
+    #[serde(default, deserialize_with = "flag")]
+    perfect: bool,
+}
+
+impl RawScore {
+    fn into_score(self, beatmap_id: u64) -> Score {
+        Score {
+            // The ids, replays and leaderboard positions of the server are not the ones of osu!,
+            // so they are left out to not be looked up there.
+            id: None,
+            user_id: self.user_id,
+            date: self.date,
+            replay_available: false,
+            beatmap_id: if self.beatmap_id == 0 {
+                beatmap_id
+            } else {
+                self.beatmap_id
+            },
+            score: Some(self.score),
+            // Private servers have no standardised score.
+            normalized_score: 0,
+            pp: Some(self.pp).filter(|pp| *pp > 0.0),
+            rank: self.rank.parse().unwrap_or(Rank::F),
+            mods: Mods::from_bits_truncate(self.enabled_mods),
+            count_300: self.count300,
+            count_100: self.count100,
+            count_50: self.count50,
+            count_miss: self.countmiss,
+            count_katu: self.countkatu,
+            count_geki: self.countgeki,
+            max_combo: self.maxcombo,
+            perfect: self.perfect,
+            lazer_build_id: None,
+            mod_settings: Default::default(),
+            global_rank: None,
+        }
+    }
+}
+
+/// Numbers of the API v1 are strings on osu!, but some servers send them as numbers, or `null`.
+fn number<'de, D, T>(d: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr + Default,
+    T::Err: Display,
+{
+    match Value::deserialize(d)? {
+        Value::Null => Ok(T::default()),
+        Value::String(s) if s.is_empty() => Ok(T::default()),
+        Value::String(s) => s.parse().map_err(D::Error::custom),
+        Value::Number(n) => n.to_string().parse().map_err(D::Error::custom),
+        v => Err(D::Error::custom(format!("expected a number, got {}", v))),
+    }
+}
+
+/// Flags are `"1"` or `"0"` on osu!, and booleans or numbers on some servers.
+fn flag<'de, D: Deserializer<'de>>(d: D) -> Result<bool, D::Error> {
+    Ok(match Value::deserialize(d)? {
+        Value::Bool(b) => b,
+        Value::String(s) => s == "1" || s == "true",
+        Value::Number(n) => n.as_u64().is_some_and(|n| n != 0),
+        _ => false,
+    })
+}
+
+/// Dates are `2024-01-31 12:34:56` in UTC on osu!, and RFC 3339 on some servers.
+fn date<'de, D: Deserializer<'de>>(d: D) -> Result<DateTime<Utc>, D::Error> {
+    let s = String::deserialize(d)?;
+    DateTime::parse_from_rfc3339(&s)
+        .map(|d| d.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S").map(|d| d.and_utc()))
+        .map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_score() {
+        let score: RawScore = serde_json::from_str(
+            r#"{"beatmap_id":"129891","score_id":"1234","score":"13547467","maxcombo":"1320",
+                "count50":"0","count100":"7","count300":"917","countmiss":0,"countkatu":"6",
+                "countgeki":"180","perfect":1,"enabled_mods":"72","user_id":"1001",
+                "date":"2024-01-31 12:34:56","rank":"SH","pp":null,"replay_available":"0"}"#,
+        )
+        .unwrap();
+        let score = score.into_score(1);
+        assert_eq!(score.beatmap_id, 129891);
+        assert_eq!(score.mods, Mods::HD | Mods::DT);
+        assert_eq!(score.rank, Rank::SH);
+        assert_eq!(score.pp, None);
+        assert!(score.perfect);
+        assert_eq!(score.date.to_rfc3339(), "2024-01-31T12:34:56+00:00");
+    }
+}
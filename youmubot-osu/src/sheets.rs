@@ -0,0 +1,263 @@
+//! A client for the [Google Sheets](https://developers.google.com/sheets/api) API,
+//! signed in as a [service account](https://developers.google.com/identity/protocols/oauth2/service-account).
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rsa::{
+    pkcs1v15::SigningKey,
+    pkcs8::DecodePrivateKey,
+    signature::{SignatureEncoding, Signer},
+    RsaPrivateKey,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+use youmubot_prelude::*;
+
+const SHEETS_URL: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+const SCOPE: &str = "https://www.googleapis.com/auth/spreadsheets";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+/// How long the signed token requests are valid for. Google allows an hour at most.
+const ASSERTION_LIFETIME_SECS: i64 = 3600;
+
+/// The key of a service account, as downloaded from Google Cloud.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServiceAccount {
+    #[serde(rename = "type")]
+    kind: String,
+    /// The account's address, which the sheets must be shared with.
+    pub client_email: String,
+    private_key: String,
+    #[serde(default = "default_token_uri")]
+    token_uri: String,
+}
+
+fn default_token_uri() -> String {
+    DEFAULT_TOKEN_URI.to_owned()
+}
+
+impl ServiceAccount {
+    /// Read the key, making sure that it can sign.
+    pub fn parse(json: &str) -> Result<Self> {
+        let account: ServiceAccount =
+            serde_json::from_str(json).map_err(|e| error!("not a service account key: {}", e))?;
+        if account.kind != "service_account" {
+            bail!("not a service account key, but a `{}` key", account.kind);
+        }
+        account.signing_key()?;
+        Ok(account)
+    }
+
+    fn signing_key(&self) -> Result<SigningKey<Sha256>> {
+        let key = RsaPrivateKey::from_pkcs8_pem(&self.private_key)
+            .map_err(|e| error!("invalid private key: {}", e))?;
+        Ok(SigningKey::new(key))
+    }
+
+    /// The JWT asking for an access token to the sheets, signed with the account's key.
+    fn assertion(&self, now: i64) -> Result<String> {
+        let claims = json!({
+            "iss": self.client_email,
+            "scope": SCOPE,
+            "aud": self.token_uri,
+            "iat": now,
+            "exp": now + ASSERTION_LIFETIME_SECS,
+        });
+        Ok(sign_jwt(&self.signing_key()?, &claims))
+    }
+}
+
+fn sign_jwt(key: &SigningKey<Sha256>, claims: &Value) -> String {
+    let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"RS256","typ":"JWT"}"#);
+    let claims = URL_SAFE_NO_PAD.encode(claims.to_string());
+    let message = format!("{}.{}", header, claims);
+    let signature = URL_SAFE_NO_PAD.encode(key.sign(message.as_bytes()).to_bytes());
+    format!("{}.{}", message, signature)
+}
+
+/// The id of the spreadsheet in its link, or the id itself.
+pub fn spreadsheet_id(link: &str) -> Option<&str> {
+    let id = match link.split_once("/spreadsheets/d/") {
+        Some((_, rest)) => rest.split(['/', '?', '#']).next().unwrap_or_default(),
+        None => link,
+    };
+    Some(id).filter(|id| {
+        !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    })
+}
+
+#[derive(Deserialize)]
+struct Token {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct Spreadsheet {
+    #[serde(default)]
+    sheets: Vec<Sheet>,
+}
+
+#[derive(Deserialize)]
+struct Sheet {
+    properties: SheetProperties,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SheetProperties {
+    /// Left out when it is 0, as for the first tab.
+    #[serde(default)]
+    sheet_id: u64,
+    title: String,
+}
+
+/// Writes to the spreadsheets shared with a service account.
+/// Each client signs in once, so it is meant for a few requests only.
+pub struct Client {
+    http: reqwest::Client,
+    token: String,
+}
+
+impl Client {
+    /// Sign in as the service account.
+    pub async fn sign_in(http: reqwest::Client, account: &ServiceAccount) -> Result<Self> {
+        let assertion = account.assertion(chrono::Utc::now().timestamp())?;
+        let token: Token = http
+            .post(&account.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()
+            .map_err(|e| error!("cannot sign in as {}: {}", account.client_email, e))?
+            .json()
+            .await?;
+        Ok(Self {
+            http,
+            token: token.access_token,
+        })
+    }
+
+    /// Make sure that the spreadsheet exists, and is shared with the account.
+    pub async fn check_access(&self, spreadsheet_id: &str) -> Result<()> {
+        self.find_tab(spreadsheet_id, "").await.map(|_| ())
+    }
+
+    /// Replace the content of the tab with the rows, creating the tab if it does not exist.
+    /// Returns the link to the tab.
+    pub async fn write_tab(
+        &self,
+        spreadsheet_id: &str,
+        tab: &str,
+        rows: Vec<Vec<Value>>,
+    ) -> Result<String> {
+        let sheet_id = match self.find_tab(spreadsheet_id, tab).await? {
+            Some(id) => id,
+            None => self.add_tab(spreadsheet_id, tab).await?,
+        };
+        // Quoted, so that any title is a valid range.
+        let range = format!("'{}'", tab.replace('\'', "''"));
+        self.request(
+            reqwest::Method::POST,
+            &[spreadsheet_id, "values", &format!("{}:clear", range)],
+            &[],
+            json!({}),
+        )
+        .await?;
+        self.request(
+            reqwest::Method::PUT,
+            &[spreadsheet_id, "values", &format!("{}!A1", range)],
+            &[("valueInputOption", "RAW")],
+            json!({ "majorDimension": "ROWS", "values": rows }),
+        )
+        .await?;
+        Ok(format!(
+            "https://docs.google.com/spreadsheets/d/{}/edit#gid={}",
+            spreadsheet_id, sheet_id
+        ))
+    }
+
+    async fn find_tab(&self, spreadsheet_id: &str, tab: &str) -> Result<Option<u64>> {
+        let spreadsheet: Spreadsheet = serde_json::from_value(
+            self.request(
+                reqwest::Method::GET,
+                &[spreadsheet_id],
+                &[("fields", "sheets.properties(sheetId,title)")],
+                Value::Null,
+            )
+            .await?,
+        )?;
+        Ok(spreadsheet
+            .sheets
+            .into_iter()
+            .find(|s| s.properties.title == tab)
+            .map(|s| s.properties.sheet_id))
+    }
+
+    async fn add_tab(&self, spreadsheet_id: &str, tab: &str) -> Result<u64> {
+        let reply = self
+            .request(
+                reqwest::Method::POST,
+                &[&format!("{}:batchUpdate", spreadsheet_id)],
+                &[],
+                json!({ "requests": [{ "addSheet": { "properties": { "title": tab } } }] }),
+            )
+            .await?;
+        reply["replies"][0]["addSheet"]["properties"]["sheetId"]
+            .as_u64()
+            .ok_or_else(|| error!("the new tab has no id: {}", reply))
+    }
+
+    /// Send a request to the path under [SHEETS_URL], with each segment escaped.
+    async fn request(
+        &self,
+        method: reqwest::Method,
+        path: &[&str],
+        query: &[(&str, &str)],
+        body: Value,
+    ) -> Result<Value> {
+        let mut url = reqwest::Url::parse(SHEETS_URL)?;
+        url.path_segments_mut()
+            .map_err(|_| error!("invalid sheets url"))?
+            .extend(path);
+        let mut request = self
+            .http
+            .request(method, url)
+            .bearer_auth(&self.token)
+            .query(query);
+        if !body.is_null() {
+            request = request.json(&body);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let reply: Value = response.json().await.unwrap_or_default();
+        if !status.is_success() {
+            bail!(
+                "Google Sheets replied {}: {}",
+                status,
+                reply["error"]["message"].as_str().unwrap_or("no details")
+            );
+        }
+        Ok(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spreadsheet_id() {
+        assert_eq!(
+            spreadsheet_id("https://docs.google.com/spreadsheets/d/1AbC-d_E/edit#gid=0"),
+            Some("1AbC-d_E")
+        );
+        assert_eq!(spreadsheet_id("1AbC-d_E"), Some("1AbC-d_E"));
+        assert_eq!(spreadsheet_id("https://example.com/"), None);
+        assert_eq!(spreadsheet_id(""), None);
+    }
+}
@@ -0,0 +1,198 @@
+//! A client for the [Twitch](https://dev.twitch.tv/docs/api) Helix API, to find out who is live.
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use youmubot_prelude::*;
+
+use crate::ratelimit::{Priority, RateLimitStats, RateLimiter};
+
+const TOKEN_URL: &str = "https://id.twitch.tv/oauth2/token";
+const USERS_URL: &str = "https://api.twitch.tv/helix/users";
+const STREAMS_URL: &str = "https://api.twitch.tv/helix/streams";
+
+/// How many requests are sent to Twitch per minute, at most. Twitch allows 800.
+const REQUESTS_PER_MINUTE: u32 = 120;
+/// How many channels can be asked about in one request.
+const MAX_LOGINS_PER_REQUEST: usize = 100;
+/// Tokens are renewed a bit before they expire.
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// The name of osu! on Twitch.
+pub const OSU_GAME_NAME: &str = "osu!";
+
+/// Asks Twitch about channels and their streams, with an app access token.
+#[derive(Debug)]
+pub struct Client {
+    http: reqwest::Client,
+    client_id: String,
+    client_secret: String,
+    limiter: RateLimiter,
+    token: Mutex<Option<(String, Instant)>>,
+}
+
+/// A Twitch channel.
+#[derive(Debug, Clone, Deserialize)]
+pub struct User {
+    pub id: String,
+    /// The login, in lowercase, e.g. `natsukagami`.
+    pub login: String,
+    pub display_name: String,
+    #[serde(default)]
+    pub profile_image_url: String,
+}
+
+/// A live stream.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Stream {
+    pub id: String,
+    pub user_login: String,
+    pub user_name: String,
+    #[serde(default)]
+    pub game_name: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub viewer_count: u64,
+    pub started_at: DateTime<Utc>,
+    /// The thumbnail, with `{width}` and `{height}` placeholders.
+    #[serde(default)]
+    pub thumbnail_url: String,
+}
+
+impl Stream {
+    /// Whether the stream is of osu!.
+    pub fn is_osu(&self) -> bool {
+        self.game_name.eq_ignore_ascii_case(OSU_GAME_NAME)
+    }
+
+    /// The stream's page.
+    pub fn url(&self) -> String {
+        format!("https://twitch.tv/{}", self.user_login)
+    }
+
+    /// The thumbnail in the given size.
+    /// Twitch caches thumbnails for a while, so the stream's start time is added to get a fresh one.
+    pub fn thumbnail(&self, width: u32, height: u32) -> String {
+        format!(
+            "{}?t={}",
+            self.thumbnail_url
+                .replace("{width}", &width.to_string())
+                .replace("{height}", &height.to_string()),
+            self.started_at.timestamp()
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct Token {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct Page<T> {
+    data: Vec<T>,
+}
+
+impl Client {
+    pub fn new(http: reqwest::Client, client_id: String, client_secret: String) -> Self {
+        Self {
+            http,
+            client_id,
+            client_secret,
+            limiter: RateLimiter::new(REQUESTS_PER_MINUTE),
+            token: Mutex::new(None),
+        }
+    }
+
+    /// The statistics of the rate limiter.
+    pub fn rate_limit_stats(&self) -> RateLimitStats {
+        self.limiter.stats()
+    }
+
+    /// The app access token, fetching a new one if it expired.
+    async fn token(&self) -> Result<String> {
+        let mut token = self.token.lock().await;
+        if let Some((t, expires)) = &*token {
+            if Instant::now() + TOKEN_EXPIRY_MARGIN < *expires {
+                return Ok(t.clone());
+            }
+        }
+        self.limiter.acquire(Priority::Background).await;
+        let t: Token = self
+            .http
+            .post(TOKEN_URL)
+            .query(&[
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+                ("grant_type", "client_credentials"),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        *token = Some((
+            t.access_token.clone(),
+            Instant::now() + Duration::from_secs(t.expires_in),
+        ));
+        Ok(t.access_token)
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(
+        &self,
+        priority: Priority,
+        url: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<T>> {
+        let token = self.token().await?;
+        self.limiter.acquire(priority).await;
+        let resp = self
+            .http
+            .get(url)
+            .query(query)
+            .header("Client-Id", &self.client_id)
+            .bearer_auth(token)
+            .send()
+            .await?;
+        if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+            // The token was revoked, get a new one next time.
+            *self.token.lock().await = None;
+        }
+        let page: Page<T> = resp.error_for_status()?.json().await?;
+        Ok(page.data)
+    }
+
+    /// Find the channel with the given login.
+    pub async fn user(&self, login: &str) -> Result<Option<User>> {
+        Ok(self
+            .get::<User>(Priority::Interactive, USERS_URL, &[("login", login)])
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// The live streams of the given channels, by login.
+    pub async fn live_streams(&self, logins: &[String]) -> Result<HashMap<String, Stream>> {
+        let mut live = HashMap::new();
+        for chunk in logins.chunks(MAX_LOGINS_PER_REQUEST) {
+            let query = chunk
+                .iter()
+                .map(|l| ("user_login", l.as_str()))
+                .collect::<Vec<_>>();
+            for stream in self
+                .get::<Stream>(Priority::Background, STREAMS_URL, &query)
+                .await?
+            {
+                live.insert(stream.user_login.to_lowercase(), stream);
+            }
+        }
+        Ok(live)
+    }
+}
@@ -1,4 +1,11 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    num::NonZeroU64,
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+};
 
 use async_trait::async_trait;
 use futures_util::{
@@ -20,12 +27,15 @@ use serenity::{
     utils::MessageBuilder,
 };
 
+use tracing::Instrument;
 use youmubot_db::DB;
 
-use crate::{AppData, MemberCache, Result};
+use crate::metrics::ANNOUNCER_SWEEP;
+use crate::permissions::MANAGE_CHANNELS_CHECK;
+use crate::{AppData, ConfigKey, GuildConfig, MemberCache, Metrics, Result, Shutdown};
 
 #[derive(Debug, Clone)]
-pub struct CacheAndHttp(Arc<Cache>, Arc<Http>);
+pub struct CacheAndHttp(pub(crate) Arc<Cache>, pub(crate) Arc<Http>);
 
 impl CacheAndHttp {
     pub fn from_client(client: &Client) -> Self {
@@ -46,8 +56,101 @@ impl CacheHttp for CacheAndHttp {
     }
 }
 
-/// A list of assigned channels for an announcer.
-pub(crate) type AnnouncerChannels = DB<HashMap<String, HashMap<GuildId, ChannelId>>>;
+/// The channel of each announcer in the guild.
+pub const ANNOUNCERS: ConfigKey<AnnouncerChannels> = ConfigKey::new(
+    "announcers",
+    "the channel of each announcer, as `key:channel_id` separated by commas; \
+     prefer the `announcer` commands",
+);
+
+/// The channels of the announcers in a guild, by announcer key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnnouncerChannels(BTreeMap<String, ChannelId>);
+
+impl AnnouncerChannels {
+    /// The channel of the announcer, if it is registered.
+    pub fn get(&self, key: &str) -> Option<ChannelId> {
+        self.0.get(key).copied()
+    }
+
+    /// Register the announcer on the channel, or remove it with `None`.
+    /// Returns the channel it was registered on before.
+    pub fn set(&mut self, key: &str, channel: Option<ChannelId>) -> Option<ChannelId> {
+        match channel {
+            Some(channel) => self.0.insert(key.to_owned(), channel),
+            None => self.0.remove(key),
+        }
+    }
+}
+
+impl FromStr for AnnouncerChannels {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                let (key, channel) = v
+                    .split_once(':')
+                    .ok_or_else(|| format!("`{}` is not of the form `key:channel_id`", v))?;
+                let channel = channel
+                    .parse::<NonZeroU64>()
+                    .map_err(|e| format!("invalid channel id `{}`: {}", channel, e))?;
+                Ok((key.to_owned(), ChannelId::from(channel)))
+            })
+            .collect::<Result<_, _>>()
+            .map(AnnouncerChannels)
+    }
+}
+
+impl Display for AnnouncerChannels {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (key, channel)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}:{}", key, channel)?;
+        }
+        Ok(())
+    }
+}
+
+/// The announcer channels of all guilds, as stored before they were part of the guild configuration.
+type LegacyAnnouncerChannels = DB<HashMap<String, HashMap<GuildId, ChannelId>>>;
+
+/// Move the announcer channels from the old announcer DB at `path`, if there is one,
+/// into the guild configuration. The old DB is then renamed, so that it is only moved once.
+pub(crate) async fn import_legacy_channels(config: &GuildConfig, path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let legacy = LegacyAnnouncerChannels::load_from_path(path)?
+        .borrow_data()?
+        .clone();
+    let mut guilds: HashMap<GuildId, AnnouncerChannels> = HashMap::new();
+    for (key, channels) in legacy {
+        for (guild, channel) in channels {
+            guilds.entry(guild).or_default().set(&key, Some(channel));
+        }
+    }
+    for (guild, channels) in guilds {
+        let mut current = config.get(guild, ANNOUNCERS).await?.unwrap_or_default();
+        for (key, channel) in channels.0 {
+            // Announcers registered since then win.
+            current.0.entry(key).or_insert(channel);
+        }
+        config.set(guild, ANNOUNCERS, &current).await?;
+    }
+    std::fs::rename(path, path.with_extension("yaml.imported"))?;
+    Ok(())
+}
+
+/// The announcer channels of the guild.
+async fn channels_in(data: &AppData, guild: GuildId) -> Result<AnnouncerChannels> {
+    let config = data.read().await.get::<GuildConfig>().unwrap().clone();
+    Ok(config.get(guild, ANNOUNCERS).await?.unwrap_or_default())
+}
 
 /// The Announcer trait.
 ///
@@ -74,6 +177,14 @@ pub trait Announcer: Send {
 pub struct MemberToChannels(Vec<(GuildId, ChannelId)>, AppData);
 
 impl MemberToChannels {
+    /// The channels of the announcer with the given key, e.g. to run it outside of a sweep.
+    pub async fn of(data: &AppData, key: &'static str) -> Result<Self> {
+        Ok(Self(
+            AnnouncerRunner::get_guilds(data, key).await?,
+            data.clone(),
+        ))
+    }
+
     /// Gets the channel list of an user related to that channel.
     pub async fn channels_of(
         &self,
@@ -160,12 +271,13 @@ impl TypeMapKey for AnnouncerRunner {
 impl AnnouncerRunner {
     /// Collect the list of guilds and their respective channels, by the key of the announcer.
     async fn get_guilds(data: &AppData, key: &'static str) -> Result<Vec<(GuildId, ChannelId)>> {
-        let data = AnnouncerChannels::open(&*data.read().await)
-            .borrow()?
-            .get(key)
-            .map(|m| m.iter().map(|(a, b)| (*a, *b)).collect())
-            .unwrap_or_else(Vec::new);
-        Ok(data)
+        let config = data.read().await.get::<GuildConfig>().unwrap().clone();
+        Ok(config
+            .all(ANNOUNCERS)
+            .await?
+            .into_iter()
+            .filter_map(|(guild, channels)| channels.get(key).map(|c| (guild, c)))
+            .collect())
     }
 
     /// Run the announcing sequence on a certain announcer.
@@ -183,29 +295,47 @@ impl AnnouncerRunner {
             .await
     }
 
-    /// Start the AnnouncerHandler, looping forever.
+    /// Start the AnnouncerHandler, looping until shutdown.
     ///
     /// It will run all the announcers every *cooldown* seconds.
-    pub async fn scan(self, cooldown: std::time::Duration) {
+    /// On shutdown, running sweeps are finished (as in-flight work), and no new sweeps are started.
+    pub async fn scan(&self, cooldown: std::time::Duration, shutdown: Shutdown) {
         // First we store all the keys inside the database.
         let keys = self.announcers.keys().cloned().collect::<Vec<_>>();
         self.data.write().await.insert::<Self>(keys.clone());
+        let metrics = self
+            .data
+            .read()
+            .await
+            .get::<Metrics>()
+            .cloned()
+            .unwrap_or_default();
         join_all(self.announcers.iter().map(|(key, announcer)| {
             let data = self.data.clone();
             let cache = self.cache_http.clone();
+            let shutdown = shutdown.clone();
+            let metrics = metrics.clone();
             async move {
-                loop {
-                    eprintln!(" - scanning key `{}`", key);
-                    match Self::announce(data.clone(), cache.clone(), key, announcer).await {
+                while !shutdown.is_shutting_down() {
+                    let span = tracing::info_span!("announcer", key = *key);
+                    let work = shutdown.work();
+                    let started = std::time::Instant::now();
+                    match Self::announce(data.clone(), cache.clone(), key, announcer)
+                        .instrument(span.clone())
+                        .await
+                    {
                         Err(e) => {
-                            eprintln!(" - key `{}`: {:?}", *key, e)
-                        }
-                        Ok(()) => {
-                            eprintln!(" - key `{}`: complete", *key)
+                            tracing::warn!(parent: &span, "sweep failed: {:?}", e)
                         }
+                        Ok(()) => tracing::info!(parent: &span, "sweep complete"),
                     };
-                    tokio::time::sleep(cooldown).await;
+                    metrics.observe(&ANNOUNCER_SWEEP, &[("announcer", *key)], started.elapsed());
+                    drop(work);
+                    if !shutdown.sleep(cooldown).await {
+                        break;
+                    }
                 }
+                tracing::info!(key = *key, "announcer stopped");
             }
         }))
         .await;
@@ -218,10 +348,29 @@ pub async fn announcer_of(
     key: &'static str,
     guild: GuildId,
 ) -> Result<Option<ChannelId>> {
-    Ok(AnnouncerChannels::open(&*ctx.data.read().await)
-        .borrow()?
-        .get(key)
-        .and_then(|channels| channels.get(&guild).cloned()))
+    Ok(channels_in(&ctx.data, guild).await?.get(key))
+}
+
+/// Gets all announcers registered in the given guild, and their channels, sorted by key.
+pub async fn announcers_in(data: &AppData, guild: GuildId) -> Result<Vec<(String, ChannelId)>> {
+    Ok(channels_in(data, guild).await?.0.into_iter().collect())
+}
+
+/// Registers the announcer of the given guild on the channel, or removes it with `None`,
+/// returning the channel it was registered on before.
+async fn set_announcer_of(
+    data: &AppData,
+    key: &str,
+    guild: GuildId,
+    channel: Option<ChannelId>,
+) -> Result<Option<ChannelId>> {
+    let config = data.read().await.get::<GuildConfig>().unwrap().clone();
+    let mut channels = config.get(guild, ANNOUNCERS).await?.unwrap_or_default();
+    let previous = channels.set(key, channel);
+    if previous != channel {
+        config.set(guild, ANNOUNCERS, &channels).await?;
+    }
+    Ok(previous)
 }
 
 #[command("list")]
@@ -230,19 +379,17 @@ pub async fn announcer_of(
 #[only_in(guilds)]
 pub async fn list_announcers(ctx: &Context, m: &Message, _: Args) -> CommandResult {
     let guild_id = m.guild_id.unwrap();
-    let data = &*ctx.data.read().await;
-    let announcers = AnnouncerChannels::open(data);
-    let channels = data.get::<AnnouncerRunner>().unwrap();
-    let channels = channels
+    let announcers = channels_in(&ctx.data, guild_id).await?;
+    let keys = ctx
+        .data
+        .read()
+        .await
+        .get::<AnnouncerRunner>()
+        .unwrap()
+        .clone();
+    let channels = keys
         .iter()
-        .filter_map(|&key| {
-            announcers.borrow().ok().and_then(|announcers| {
-                announcers
-                    .get(key)
-                    .and_then(|channels| channels.get(&guild_id))
-                    .map(|&ch| (key, ch))
-            })
-        })
+        .filter_map(|&key| announcers.get(key).map(|ch| (key, ch)))
         .map(|(key, ch)| format!(" - `{}`: activated on channel {}", key, ch.mention()))
         .collect::<Vec<_>>();
 
@@ -261,13 +408,18 @@ pub async fn list_announcers(ctx: &Context, m: &Message, _: Args) -> CommandResu
 #[command("register")]
 #[description = "Register the current channel with an announcer"]
 #[usage = "[announcer key]"]
-#[required_permissions(MANAGE_CHANNELS)]
+#[checks(manage_channels)]
 #[only_in(guilds)]
 #[num_args(1)]
 pub async fn register_announcer(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
-    let data = ctx.data.read().await;
     let key = args.single::<String>()?;
-    let keys = data.get::<AnnouncerRunner>().unwrap();
+    let keys = ctx
+        .data
+        .read()
+        .await
+        .get::<AnnouncerRunner>()
+        .unwrap()
+        .clone();
     if !keys.contains(&&key[..]) {
         m.reply(
             &ctx,
@@ -285,11 +437,7 @@ pub async fn register_announcer(ctx: &Context, m: &Message, mut args: Args) -> C
         .to_partial_guild(&ctx)
         .await?;
     let channel = m.channel_id.to_channel(&ctx).await?;
-    AnnouncerChannels::open(&data)
-        .borrow_mut()?
-        .entry(key.clone())
-        .or_default()
-        .insert(guild.id, m.channel_id);
+    set_announcer_of(&ctx.data, &key, guild.id, Some(m.channel_id)).await?;
     m.reply(
         &ctx,
         MessageBuilder::new()
@@ -308,13 +456,18 @@ pub async fn register_announcer(ctx: &Context, m: &Message, mut args: Args) -> C
 #[command("remove")]
 #[description = "Remove an announcer from the server"]
 #[usage = "[announcer key]"]
-#[required_permissions(MANAGE_CHANNELS)]
+#[checks(manage_channels)]
 #[only_in(guilds)]
 #[num_args(1)]
 pub async fn remove_announcer(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
-    let data = ctx.data.read().await;
     let key = args.single::<String>()?;
-    let keys = data.get::<AnnouncerRunner>().unwrap();
+    let keys = ctx
+        .data
+        .read()
+        .await
+        .get::<AnnouncerRunner>()
+        .unwrap()
+        .clone();
     if !keys.contains(&key.as_str()) {
         m.reply(
             &ctx,
@@ -331,12 +484,7 @@ pub async fn remove_announcer(ctx: &Context, m: &Message, mut args: Args) -> Com
         .expect("Guild-only command")
         .to_partial_guild(&ctx)
         .await?;
-    AnnouncerChannels::open(&data)
-        .borrow_mut()?
-        .entry(key.clone())
-        .and_modify(|m| {
-            m.remove(&guild.id);
-        });
+    set_announcer_of(&ctx.data, &key, guild.id, None).await?;
     m.reply(
         &ctx,
         MessageBuilder::new()
@@ -353,7 +501,7 @@ pub async fn remove_announcer(ctx: &Context, m: &Message, mut args: Args) -> Com
 #[group("announcer")]
 #[prefix("announcer")]
 #[only_in(guilds)]
-#[required_permissions(MANAGE_CHANNELS)]
+#[checks(manage_channels)]
 #[description = "Manage the announcers in the server."]
 #[commands(remove_announcer, register_announcer, list_announcers)]
 pub struct AnnouncerCommands;
@@ -0,0 +1,175 @@
+//! Application command interactions: slash and context menu commands, and option autocompletion.
+use std::{collections::HashMap, sync::Arc};
+
+use serenity::{
+    all::{Command, CommandInteraction, Interaction},
+    builder::{
+        CreateAutocompleteResponse, CreateCommand, CreateInteractionResponse,
+        CreateInteractionResponseFollowup, CreateInteractionResponseMessage,
+    },
+};
+use tracing::Instrument;
+
+use crate::errors::ErrorSource;
+use crate::metrics::{COMMANDS, COMMAND_DURATION};
+use crate::{Context, ErrorReporter, Metrics, OkPrint, Result};
+
+/// The maximum number of suggestions Discord accepts.
+const MAX_SUGGESTIONS: usize = 25;
+
+/// An application command: a slash command, or a context menu command on users or messages.
+#[async_trait::async_trait]
+pub trait ApplicationCommand: Send + Sync {
+    /// The name of the command, as shown in Discord.
+    fn name(&self) -> &'static str;
+
+    /// The definition of the command, registered with Discord.
+    fn create(&self) -> CreateCommand;
+
+    /// Run the command. The command should respond to the interaction.
+    async fn run(&self, ctx: &Context, interaction: &CommandInteraction) -> Result<()>;
+}
+
+/// The application commands, by name.
+#[derive(Clone, Default)]
+pub struct ApplicationCommands(HashMap<&'static str, Arc<dyn ApplicationCommand>>);
+
+impl ApplicationCommands {
+    /// Add the command.
+    pub fn register(&mut self, command: impl ApplicationCommand + 'static) {
+        self.0.insert(command.name(), Arc::new(command));
+    }
+
+    /// Register the commands with Discord, replacing all previously registered ones.
+    pub async fn register_all(&self, ctx: &Context) -> Result<()> {
+        Command::set_global_commands(ctx, self.0.values().map(|c| c.create()).collect()).await?;
+        Ok(())
+    }
+
+    /// Run the command of the interaction, if it is one. Returns whether it was one.
+    /// Errors are reported with the [ErrorReporter], and the reply is only shown to the user.
+    pub async fn dispatch(&self, ctx: &Context, interaction: &Interaction) -> bool {
+        let Interaction::Command(c) = interaction else {
+            return false;
+        };
+        let Some(command) = self.0.get(c.data.name.as_str()) else {
+            return true;
+        };
+        let metrics = ctx.data.read().await.get::<Metrics>().cloned();
+        let span = tracing::info_span!(
+            "command",
+            name = command.name(),
+            kind = "slash",
+            user_id = c.user.id.get(),
+            channel_id = c.channel_id.get(),
+        );
+        let started = std::time::Instant::now();
+        let result = command.run(ctx, c).instrument(span.clone()).await;
+        if let Some(metrics) = metrics {
+            let outcome = if result.is_ok() { "ok" } else { "error" };
+            metrics.inc(
+                &COMMANDS,
+                &[
+                    ("command", command.name()),
+                    ("kind", "slash"),
+                    ("outcome", outcome),
+                ],
+            );
+            metrics.observe(
+                &COMMAND_DURATION,
+                &[("command", command.name())],
+                started.elapsed(),
+            );
+        }
+        if let Err(e) = result {
+            let reporter = ctx
+                .data
+                .read()
+                .await
+                .get::<ErrorReporter>()
+                .unwrap()
+                .clone();
+            let content = reporter
+                .report(
+                    ctx,
+                    ErrorSource {
+                        command: &c.data.name,
+                        user: &c.user.name,
+                        channel_id: c.channel_id,
+                    },
+                    e.as_ref(),
+                )
+                .instrument(span)
+                .await;
+            let response = CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(&content)
+                    .ephemeral(true),
+            );
+            // The command might have deferred or responded already, then the error is a follow-up.
+            if c.create_response(ctx, response).await.is_err() {
+                c.create_followup(
+                    ctx,
+                    CreateInteractionResponseFollowup::new()
+                        .content(content)
+                        .ephemeral(true),
+                )
+                .await
+                .pls_ok();
+            }
+        }
+        true
+    }
+}
+
+/// Suggests values for an option of the slash commands, as it is being typed.
+#[async_trait::async_trait]
+pub trait Autocomplete: Send + Sync {
+    /// Suggestions for the partially typed `value`, most relevant first.
+    async fn suggest(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        value: &str,
+    ) -> Result<Vec<String>>;
+}
+
+/// The autocompletion handlers, by the name of the option they complete.
+/// Every slash command option with that name is completed by the same handler.
+#[derive(Clone, Default)]
+pub struct Autocompletes(HashMap<&'static str, Arc<dyn Autocomplete>>);
+
+impl Autocompletes {
+    /// Complete the options named `option` with `handler`.
+    pub fn register(&mut self, option: &'static str, handler: impl Autocomplete + 'static) {
+        self.0.insert(option, Arc::new(handler));
+    }
+
+    /// Respond to the interaction if it is an autocompletion. Returns whether it was one.
+    pub async fn dispatch(&self, ctx: &Context, interaction: &Interaction) -> bool {
+        let Interaction::Autocomplete(i) = interaction else {
+            return false;
+        };
+        let Some(focused) = i.data.autocomplete() else {
+            return true;
+        };
+        let suggestions = match self.0.get(focused.name) {
+            Some(h) => h
+                .suggest(ctx, i, focused.value)
+                .await
+                .pls_ok()
+                .unwrap_or_default(),
+            None => vec![],
+        };
+        let response = suggestions
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .fold(CreateAutocompleteResponse::new(), |r, s| {
+                r.add_string_choice(s.clone(), s)
+            });
+        i.create_response(ctx, CreateInteractionResponse::Autocomplete(response))
+            .await
+            .pls_ok();
+        true
+    }
+}
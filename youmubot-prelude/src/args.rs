@@ -1,5 +1,6 @@
 pub use duration::Duration;
 pub use ids::*;
+pub use numbers::{keyword, Percentage, Position, Range, StarRange};
 pub use username_arg::UsernameArg;
 
 mod duration {
@@ -60,7 +61,12 @@ mod duration {
     impl std::str::FromStr for Duration {
         type Err = Error;
         fn from_str(s: &str) -> Result<Self, Self::Err> {
-            parse_duration_string(s).map(Duration)
+            parse_duration_string(s).map(Duration).map_err(|_| {
+                Error::msg(format!(
+                    "`{}` is not a valid duration, try e.g. `7d` or `1h30m`",
+                    s
+                ))
+            })
         }
     }
 
@@ -277,6 +283,163 @@ mod username_arg {
     }
 }
 
+mod numbers {
+    use crate::{Error, Result};
+    use std::str::FromStr;
+
+    /// A percentage between 0 and 100, written with its sign, e.g. `97.5%`.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Percentage(pub f64);
+
+    impl FromStr for Percentage {
+        type Err = Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let v = s
+                .strip_suffix('%')
+                .and_then(|v| v.parse::<f64>().ok())
+                .ok_or_else(|| {
+                    Error::msg(format!("`{}` is not a percentage, try e.g. `97.5%`", s))
+                })?;
+            if !(0.0..=100.0).contains(&v) {
+                return Err(Error::msg(format!("`{}` should be between 0% and 100%", s)));
+            }
+            Ok(Percentage(v))
+        }
+    }
+
+    /// A position in a list, written `#N`, with `#1` being the first one.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct Position(pub usize);
+
+    impl FromStr for Position {
+        type Err = Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.strip_prefix('#').and_then(|v| v.parse::<usize>().ok()) {
+                Some(0) => Err(Error::msg("positions start from `#1`")),
+                Some(v) => Ok(Position(v)),
+                None => Err(Error::msg(format!(
+                    "`{}` is not a position, try e.g. `#5`",
+                    s
+                ))),
+            }
+        }
+    }
+
+    impl Position {
+        /// The index of the position, starting from 0.
+        pub fn index(self) -> usize {
+            self.0 - 1
+        }
+    }
+
+    /// An inclusive range of values, written `a..b` or `a-b`, e.g. `#5..#20`.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Range<T> {
+        pub start: T,
+        pub end: T,
+    }
+
+    impl<T> FromStr for Range<T>
+    where
+        T: FromStr + PartialOrd,
+        T::Err: std::fmt::Display,
+    {
+        type Err = Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let (start, end) = s
+                .split_once("..")
+                .or_else(|| s.split_once('-'))
+                .ok_or_else(|| Error::msg(format!("`{}` is not a range, try e.g. `#5..#20`", s)))?;
+            let parse = |v: &str| v.parse::<T>().map_err(|e| Error::msg(e.to_string()));
+            let (start, end) = (parse(start)?, parse(end)?);
+            if start > end {
+                return Err(Error::msg(format!(
+                    "the range `{}` should start before it ends",
+                    s
+                )));
+            }
+            Ok(Range { start, end })
+        }
+    }
+
+    impl<T: PartialOrd> Range<T> {
+        /// Whether the value is inside the range.
+        pub fn contains(&self, v: &T) -> bool {
+            &self.start <= v && v <= &self.end
+        }
+    }
+
+    /// A range of star ratings, e.g. `5.5-6.0*`.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct StarRange(pub Range<f64>);
+
+    impl FromStr for StarRange {
+        type Err = Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let range = s.strip_suffix('*').ok_or_else(|| {
+                Error::msg(format!("`{}` is not a star range, try e.g. `5.5-6.0*`", s))
+            })?;
+            range.parse().map(StarRange)
+        }
+    }
+
+    /// Parse `s` as one of the keywords, ignoring case.
+    /// Each value comes with its aliases, the first one is listed in the error message.
+    ///
+    /// `what` names the argument in the error message, e.g. `mode`.
+    pub fn keyword<T: Copy>(what: &str, s: &str, keywords: &[(&[&str], T)]) -> Result<T> {
+        let lower = s.to_lowercase();
+        keywords
+            .iter()
+            .find(|(aliases, _)| aliases.contains(&lower.as_str()))
+            .map(|(_, v)| *v)
+            .ok_or_else(|| {
+                let expected = keywords
+                    .iter()
+                    .filter_map(|(aliases, _)| aliases.first())
+                    .map(|v| format!("`{}`", v))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                Error::msg(format!(
+                    "unknown {} `{}`, expected one of {}",
+                    what, s, expected
+                ))
+            })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_ranges() {
+            let r = "#5..#20".parse::<Range<Position>>().unwrap();
+            assert_eq!((r.start, r.end), (Position(5), Position(20)));
+            let StarRange(r) = "5.5-6.0*".parse().unwrap();
+            assert_eq!((r.start, r.end), (5.5, 6.0));
+            assert!(r.contains(&5.75));
+            assert!("#20..#5".parse::<Range<Position>>().is_err());
+            assert!("5.5-6.0".parse::<StarRange>().is_err());
+        }
+
+        #[test]
+        fn test_parse_numbers() {
+            assert_eq!("97.5%".parse::<Percentage>().unwrap(), Percentage(97.5));
+            assert!("101%".parse::<Percentage>().is_err());
+            assert!("97.5".parse::<Percentage>().is_err());
+            assert!("#0".parse::<Position>().is_err());
+            assert_eq!(
+                keyword("mode", "TAIKO", &[(&["std"], 0), (&["taiko"], 1)]).unwrap(),
+                1
+            );
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ParseError {
     #[error("invalid id format")]
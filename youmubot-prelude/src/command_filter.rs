@@ -0,0 +1,189 @@
+//! Per-guild command prefixes, and commands or command groups disabled in some channels.
+//! Both are stored in the [GuildConfig], and checked by the framework before dispatching commands.
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Display,
+    str::FromStr,
+    sync::Arc,
+};
+
+use serenity::{
+    framework::standard::{macros::hook, CommandGroup},
+    model::{channel::Message, id::ChannelId},
+};
+
+use crate::{ConfigKey, Context, GuildConfig, OkPrint, TypeMapKey};
+
+/// The guild's own command prefix, used besides the default ones.
+pub const PREFIX: ConfigKey<String> = ConfigKey::new(
+    "prefix",
+    "the server's own command prefix, used besides the default ones",
+);
+
+/// The commands and groups disabled in each channel of the guild.
+pub const DISABLED_COMMANDS: ConfigKey<DisabledCommands> = ConfigKey::new(
+    "disabled_commands",
+    "commands and groups disabled per channel, as `channel_id:name` separated by commas; \
+     prefer the `disable`/`enable` commands",
+);
+
+/// Commands that can never be disabled, so that nothing gets locked out.
+pub const ALWAYS_ENABLED: &[&str] = &["enable", "disable", "config", "help"];
+
+/// A set of commands or groups, disabled in a channel.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DisabledCommands(BTreeSet<(ChannelId, String)>);
+
+impl DisabledCommands {
+    /// Whether the command or group is disabled in the channel.
+    pub fn contains(&self, channel_id: ChannelId, name: &str) -> bool {
+        self.0.contains(&(channel_id, name.to_lowercase()))
+    }
+
+    /// Disable the command or group in the channel. Returns whether it was enabled before.
+    pub fn insert(&mut self, channel_id: ChannelId, name: &str) -> bool {
+        self.0.insert((channel_id, name.to_lowercase()))
+    }
+
+    /// Enable the command or group in the channel again. Returns whether it was disabled before.
+    pub fn remove(&mut self, channel_id: ChannelId, name: &str) -> bool {
+        self.0.remove(&(channel_id, name.to_lowercase()))
+    }
+
+    /// The commands and groups disabled in the channel.
+    pub fn in_channel(&self, channel_id: ChannelId) -> impl Iterator<Item = &str> {
+        self.0
+            .iter()
+            .filter(move |(c, _)| *c == channel_id)
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+impl FromStr for DisabledCommands {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                let (channel, name) = v
+                    .split_once(':')
+                    .ok_or_else(|| format!("`{}` is not of the form `channel_id:name`", v))?;
+                let channel = channel
+                    .parse::<u64>()
+                    .map_err(|e| format!("invalid channel id `{}`: {}", channel, e))?;
+                Ok((ChannelId::new(channel), name.to_lowercase()))
+            })
+            .collect::<Result<_, _>>()
+            .map(DisabledCommands)
+    }
+}
+
+impl Display for DisabledCommands {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (channel, name)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}:{}", channel, name)?;
+        }
+        Ok(())
+    }
+}
+
+/// The groups of each command, to check commands disabled by their group.
+#[derive(Debug, Clone, Default)]
+pub struct CommandFilter {
+    groups: Arc<HashMap<&'static str, Vec<&'static str>>>,
+}
+
+impl TypeMapKey for CommandFilter {
+    type Value = CommandFilter;
+}
+
+impl CommandFilter {
+    /// Index the commands of the given groups, and their sub-groups.
+    pub fn new(groups: &[&'static CommandGroup]) -> Self {
+        fn index(
+            group: &'static CommandGroup,
+            parents: &[&'static str],
+            map: &mut HashMap<&'static str, Vec<&'static str>>,
+        ) {
+            let mut parents = parents.to_vec();
+            parents.push(group.name);
+            for command in group.options.commands {
+                for name in command.options.names {
+                    map.entry(name).or_default().extend(parents.iter().copied());
+                }
+            }
+            for sub in group.options.sub_groups {
+                index(sub, &parents, map);
+            }
+        }
+        let mut map = HashMap::new();
+        for group in groups {
+            index(group, &[], &mut map);
+        }
+        Self {
+            groups: Arc::new(map),
+        }
+    }
+
+    /// The groups the command is in, outermost first.
+    pub fn groups_of(&self, command: &str) -> &[&'static str] {
+        self.groups.get(command).map(|v| &v[..]).unwrap_or(&[])
+    }
+
+    /// Whether the command or the group with that name exists.
+    pub fn exists(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.groups.contains_key(name.as_str())
+            || self
+                .groups
+                .values()
+                .any(|g| g.iter().any(|g| g.to_lowercase() == name))
+    }
+
+    /// Whether the command can be run in the message's channel.
+    pub async fn allowed(&self, config: &GuildConfig, msg: &Message, command: &str) -> bool {
+        let Some(guild_id) = msg.guild_id else {
+            return true;
+        };
+        if ALWAYS_ENABLED.contains(&command) {
+            return true;
+        }
+        let Some(disabled) = config
+            .get(guild_id, DISABLED_COMMANDS)
+            .await
+            .pls_ok()
+            .flatten()
+        else {
+            return true;
+        };
+        !std::iter::once(&command)
+            .chain(self.groups_of(command))
+            .any(|name| disabled.contains(msg.channel_id, name))
+    }
+}
+
+/// The framework's dynamic prefix: the guild's own prefix, if one is set.
+#[hook]
+pub async fn guild_prefix(ctx: &Context, msg: &Message) -> Option<String> {
+    let guild_id = msg.guild_id?;
+    let config = ctx.data.read().await.get::<GuildConfig>()?.clone();
+    config.get(guild_id, PREFIX).await.pls_ok().flatten()
+}
+
+/// Whether the command is enabled in the message's channel.
+/// To be checked by the framework's `before` hook, which should not dispatch the command otherwise.
+pub async fn check(ctx: &Context, msg: &Message, command_name: &str) -> bool {
+    let (filter, config) = {
+        let data = ctx.data.read().await;
+        match (data.get::<CommandFilter>(), data.get::<GuildConfig>()) {
+            (Some(f), Some(c)) => (f.clone(), c.clone()),
+            _ => return true,
+        }
+    };
+    filter.allowed(&config, msg, command_name).await
+}
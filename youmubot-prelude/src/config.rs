@@ -0,0 +1,245 @@
+//! Per-guild configuration: typed keys whose values are stored as text in the SQL database,
+//! cached in memory, with notifications when they change.
+//!
+//! Values are cached for [CACHE_TTL], so that changes made by another instance sharing the
+//! database are seen within that time. Notifications are only sent for changes made by this one.
+use std::{
+    fmt::Display,
+    marker::PhantomData,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use serenity::model::id::GuildId;
+use tokio::sync::broadcast;
+use youmubot_db_sql::{models::config::GuildConfigValue, Pool};
+
+use crate::{Result, TypeMapKey};
+
+/// The number of unreceived change notifications kept for each subscriber.
+const CHANGES_CAPACITY: usize = 64;
+/// How long a value is cached before it is read from the database again.
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// A configuration key, with the type of its values.
+#[derive(Debug)]
+pub struct ConfigKey<T> {
+    pub name: &'static str,
+    pub description: &'static str,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for ConfigKey<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ConfigKey<T> {}
+
+impl<T> ConfigKey<T> {
+    pub const fn new(name: &'static str, description: &'static str) -> Self {
+        Self {
+            name,
+            description,
+            _value: PhantomData,
+        }
+    }
+}
+
+/// A change of a configuration value.
+#[derive(Debug, Clone)]
+pub struct ConfigChange {
+    pub guild_id: GuildId,
+    pub key: String,
+    /// The new value, or `None` if it was reset.
+    pub value: Option<String>,
+}
+
+/// A registered key, to be read and written by name with the `config` command.
+struct KeyInfo {
+    description: &'static str,
+    validate: fn(&str) -> Result<()>,
+}
+
+/// The per-guild configuration service.
+#[derive(Clone)]
+pub struct GuildConfig {
+    pool: Pool,
+    /// The values, with when they were read.
+    cache: Arc<DashMap<(GuildId, String), (Instant, Option<String>)>>,
+    keys: Arc<DashMap<&'static str, KeyInfo>>,
+    changes: broadcast::Sender<ConfigChange>,
+}
+
+impl std::fmt::Debug for GuildConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<GuildConfig>")
+    }
+}
+
+impl TypeMapKey for GuildConfig {
+    type Value = GuildConfig;
+}
+
+impl GuildConfig {
+    pub fn new(pool: Pool) -> Self {
+        Self {
+            pool,
+            cache: Arc::new(DashMap::new()),
+            keys: Arc::new(DashMap::new()),
+            changes: broadcast::channel(CHANGES_CAPACITY).0,
+        }
+    }
+
+    /// Make the key readable and writable with the `config` command.
+    pub fn register<T>(&self, key: ConfigKey<T>)
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.keys.insert(
+            key.name,
+            KeyInfo {
+                description: key.description,
+                validate: |v| {
+                    v.parse::<T>()
+                        .map(|_| ())
+                        .map_err(|e| crate::Error::msg(e.to_string()))
+                },
+            },
+        );
+    }
+
+    /// The registered keys and their descriptions, by name.
+    pub fn keys(&self) -> Vec<(&'static str, &'static str)> {
+        let mut keys = self
+            .keys
+            .iter()
+            .map(|k| (*k.key(), k.description))
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
+
+    /// Subscribe to the changes of the configuration.
+    pub fn subscribe(&self) -> broadcast::Receiver<ConfigChange> {
+        self.changes.subscribe()
+    }
+
+    /// The value of the key in the guild, if it was set.
+    /// Values that cannot be parsed anymore are treated as unset.
+    pub async fn get<T>(&self, guild_id: GuildId, key: ConfigKey<T>) -> Result<Option<T>>
+    where
+        T: FromStr,
+    {
+        Ok(self
+            .get_raw(guild_id, key.name)
+            .await?
+            .and_then(|v| v.parse().ok()))
+    }
+
+    /// The value of the key in every guild that set it.
+    /// Values that cannot be parsed anymore are left out.
+    pub async fn all<T>(&self, key: ConfigKey<T>) -> Result<Vec<(GuildId, T)>>
+    where
+        T: FromStr,
+    {
+        Ok(GuildConfigValue::by_key(key.name, &self.pool)
+            .await?
+            .into_iter()
+            .filter_map(|v| {
+                let value = v.value.parse().ok()?;
+                Some((GuildId::new(v.guild_id as u64), value))
+            })
+            .collect())
+    }
+
+    /// Set the value of the key in the guild.
+    pub async fn set<T>(&self, guild_id: GuildId, key: ConfigKey<T>, value: &T) -> Result<()>
+    where
+        T: Display,
+    {
+        self.set_raw(guild_id, key.name, Some(value.to_string()))
+            .await
+    }
+
+    /// Reset the key in the guild, going back to its default.
+    pub async fn reset<T>(&self, guild_id: GuildId, key: ConfigKey<T>) -> Result<()> {
+        self.set_raw(guild_id, key.name, None).await
+    }
+
+    /// The text value of the key in the guild, if it was set.
+    pub async fn get_raw(&self, guild_id: GuildId, key: &str) -> Result<Option<String>> {
+        let cache_key = (guild_id, key.to_owned());
+        if let Some(v) = self.cache.get(&cache_key) {
+            let (read_at, value) = v.value();
+            if read_at.elapsed() < CACHE_TTL {
+                return Ok(value.clone());
+            }
+        }
+        let v = GuildConfigValue::get(guild_id.get() as i64, key, &self.pool).await?;
+        self.cache.insert(cache_key, (Instant::now(), v.clone()));
+        Ok(v)
+    }
+
+    /// Set the text value of a registered key in the guild, or reset it with `None`.
+    /// Values are checked against the key's type.
+    pub async fn set_by_name(
+        &self,
+        guild_id: GuildId,
+        key: &str,
+        value: Option<String>,
+    ) -> Result<()> {
+        let validate = match self.keys.get(key) {
+            Some(info) => info.validate,
+            None => crate::bail!("unknown configuration key `{}`", key),
+        };
+        if let Some(v) = &value {
+            validate(v).map_err(|e| crate::error!("invalid value for `{}`: {}", key, e))?;
+        }
+        self.set_raw(guild_id, key, value).await
+    }
+
+    /// Update the cache and notify the subscribers after the given keys of the guild were
+    /// deleted from the database directly, e.g. in a transaction with other data.
+    pub fn forgotten(&self, guild_id: GuildId, keys: impl IntoIterator<Item = String>) {
+        for key in keys {
+            self.cache
+                .insert((guild_id, key.clone()), (Instant::now(), None));
+            // Nobody listening is fine.
+            let _ = self.changes.send(ConfigChange {
+                guild_id,
+                key,
+                value: None,
+            });
+        }
+    }
+
+    async fn set_raw(&self, guild_id: GuildId, key: &str, value: Option<String>) -> Result<()> {
+        let guild = guild_id.get() as i64;
+        match &value {
+            Some(v) => {
+                GuildConfigValue {
+                    guild_id: guild,
+                    key: key.to_owned(),
+                    value: v.clone(),
+                }
+                .store(&self.pool)
+                .await?
+            }
+            None => GuildConfigValue::delete(guild, key, &self.pool).await?,
+        }
+        self.cache
+            .insert((guild_id, key.to_owned()), (Instant::now(), value.clone()));
+        // Nobody listening is fine.
+        let _ = self.changes.send(ConfigChange {
+            guild_id,
+            key: key.to_owned(),
+            value,
+        });
+        Ok(())
+    }
+}
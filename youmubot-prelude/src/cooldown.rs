@@ -0,0 +1,120 @@
+//! Cooldown buckets for expensive commands, applied by the framework.
+//!
+//! Commands opt in with `#[bucket(...)]` and one of the bucket names below.
+//! The limits can be changed in the `[cooldowns]` configuration section (e.g. `heavy = "2/60"`),
+//! giving the number of uses allowed per number of seconds.
+use serenity::framework::standard::{BucketBuilder, RateLimitInfo, StandardFramework};
+
+use crate::{bail, error, settings::CooldownsConfig, Result};
+
+/// Expensive commands, limited per user.
+pub const HEAVY: &str = "heavy";
+/// Expensive commands, limited per channel.
+pub const HEAVY_CHANNEL: &str = "heavy-channel";
+/// Expensive commands, limited per guild (or per DM channel).
+pub const HEAVY_GUILD: &str = "heavy-guild";
+
+/// Who a cooldown bucket is counted for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CooldownScope {
+    User,
+    Channel,
+    Guild,
+}
+
+/// A cooldown bucket: at most `limit` uses per `time_span` seconds in each scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cooldown {
+    pub name: &'static str,
+    pub scope: CooldownScope,
+    pub limit: u32,
+    pub time_span: u64,
+}
+
+/// The default cooldown buckets.
+const DEFAULT_COOLDOWNS: &[Cooldown] = &[
+    Cooldown {
+        name: HEAVY,
+        scope: CooldownScope::User,
+        limit: 2,
+        time_span: 60,
+    },
+    Cooldown {
+        name: HEAVY_CHANNEL,
+        scope: CooldownScope::Channel,
+        limit: 3,
+        time_span: 60,
+    },
+    Cooldown {
+        name: HEAVY_GUILD,
+        scope: CooldownScope::Guild,
+        limit: 2,
+        time_span: 300,
+    },
+];
+
+impl Cooldown {
+    /// Read the limits from the configuration, as `limit/seconds`, if they are set.
+    fn with_config(mut self, config: &CooldownsConfig) -> Result<Self> {
+        let Some(v) = config.get(self.name) else {
+            return Ok(self);
+        };
+        let (limit, time_span) = v.split_once('/').ok_or_else(|| {
+            error!(
+                "invalid cooldown {}: expected `limit/seconds`, got `{}`",
+                self.name, v
+            )
+        })?;
+        self.limit = limit
+            .trim()
+            .parse()
+            .map_err(|e| error!("invalid cooldown {}: {}", self.name, e))?;
+        self.time_span = time_span
+            .trim()
+            .parse()
+            .map_err(|e| error!("invalid cooldown {}: {}", self.name, e))?;
+        Ok(self)
+    }
+
+    fn bucket(&self) -> BucketBuilder {
+        let builder = match self.scope {
+            CooldownScope::User => BucketBuilder::new_user(),
+            CooldownScope::Channel => BucketBuilder::new_channel(),
+            CooldownScope::Guild => BucketBuilder::new_guild(),
+        };
+        builder.limit(self.limit).time_span(self.time_span)
+    }
+}
+
+/// The cooldown buckets, with their limits read from the configuration.
+pub fn from_config(config: &CooldownsConfig) -> Result<Vec<Cooldown>> {
+    if let Some(name) = config.names().find(|name| {
+        !DEFAULT_COOLDOWNS
+            .iter()
+            .any(|c| c.name.replace('-', "_") == *name)
+    }) {
+        bail!("unknown cooldown {}", name);
+    }
+    DEFAULT_COOLDOWNS
+        .iter()
+        .map(|c| c.with_config(config))
+        .collect()
+}
+
+/// Add the cooldown buckets to the framework.
+pub async fn apply(mut fw: StandardFramework, cooldowns: &[Cooldown]) -> StandardFramework {
+    for c in cooldowns {
+        fw = fw.bucket(c.name, c.bucket()).await;
+    }
+    fw
+}
+
+/// The reply to a command on cooldown. Only the first attempt is replied to, to not add to the spam.
+pub fn reply(info: &RateLimitInfo) -> Option<String> {
+    info.is_first_try.then(|| {
+        format!(
+            "⏳ Slow down! Try this again in **{}s**.",
+            info.rate_limit.as_secs().max(1)
+        )
+    })
+}
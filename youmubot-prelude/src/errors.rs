@@ -0,0 +1,141 @@
+//! Reporting command errors: a friendly reply to the user, and the full error to the owners.
+//!
+//! Every reported error gets an ID, shown to the user and logged along with the error,
+//! so that a user's report can be matched with the log.
+//! Modules teach the reporter how to explain their own error types with [ErrorReporter::register].
+use std::{
+    error::Error as StdError,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use serenity::{
+    builder::CreateMessage, http::CacheHttp, model::id::ChannelId, utils::MessageBuilder,
+};
+
+use crate::{OkPrint, TypeMapKey};
+
+/// The longest error chain sent to the log channel, to fit in a Discord message.
+const MAX_LOGGED_LEN: usize = 1500;
+
+/// Explains an error to the user, if it is of a known type.
+pub type Describe = fn(&(dyn StdError + 'static)) -> Option<String>;
+
+/// Where an error happened, for the logs.
+#[derive(Debug, Clone)]
+pub struct ErrorSource<'a> {
+    /// The command that failed.
+    pub command: &'a str,
+    /// Who ran the command, e.g. their name.
+    pub user: &'a str,
+    /// The channel the command was run in.
+    pub channel_id: ChannelId,
+}
+
+/// Turns command errors into replies, and reports them to the log channel.
+#[derive(Debug, Clone)]
+pub struct ErrorReporter {
+    describers: Arc<RwLock<Vec<Describe>>>,
+    log_channel: Option<ChannelId>,
+    counter: Arc<AtomicU32>,
+}
+
+impl TypeMapKey for ErrorReporter {
+    type Value = ErrorReporter;
+}
+
+impl ErrorReporter {
+    pub fn new(log_channel: Option<ChannelId>) -> Self {
+        Self {
+            describers: Arc::new(RwLock::new(vec![])),
+            log_channel,
+            counter: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    /// Add a way to explain errors to the user.
+    pub fn register(&self, describe: Describe) {
+        self.describers.write().unwrap().push(describe);
+    }
+
+    /// Log the error, and return the reply to send to the user.
+    pub async fn report(
+        &self,
+        http: impl CacheHttp,
+        source: ErrorSource<'_>,
+        error: &(dyn StdError + Send + Sync + 'static),
+    ) -> String {
+        let id = self.new_id();
+        let chain = chain_of(error);
+        tracing::error!(
+            error_id = %id,
+            command = source.command,
+            user = source.user,
+            channel_id = source.channel_id.get(),
+            "command failed: {}",
+            chain
+        );
+        if let Some(channel) = self.log_channel {
+            let mut logged = chain;
+            if logged.len() > MAX_LOGGED_LEN {
+                let mut end = MAX_LOGGED_LEN;
+                while !logged.is_char_boundary(end) {
+                    end -= 1;
+                }
+                logged.truncate(end);
+                logged.push('…');
+            }
+            let content = MessageBuilder::new()
+                .push("⚠️ Error ")
+                .push_mono_safe(&id)
+                .push(" in ")
+                .push_mono_safe(source.command)
+                .push(" by ")
+                .push_safe(source.user)
+                .push(" in ")
+                .mention(&source.channel_id)
+                .push_line("")
+                .push_codeblock_safe(logged, None)
+                .build();
+            channel
+                .send_message(&http, CreateMessage::new().content(content))
+                .await
+                .pls_ok();
+        }
+        format!("{}\n*Error ID: `{}`*", self.describe(error), id)
+    }
+
+    /// The friendliest explanation of the error: the first known error in its chain,
+    /// or else the error's own message.
+    fn describe(&self, error: &(dyn StdError + 'static)) -> String {
+        let describers = self.describers.read().unwrap();
+        let mut current = Some(error);
+        while let Some(e) = current {
+            if let Some(message) = describers.iter().find_map(|d| d(e)) {
+                return message;
+            }
+            current = e.source();
+        }
+        format!("😵 Something went wrong: {}", error)
+    }
+
+    /// A short ID for a new error: the time in seconds and a counter, in hexadecimal.
+    fn new_id(&self) -> String {
+        let n = self.counter.fetch_add(1, Ordering::Relaxed) & 0xff;
+        format!("{:x}{:02x}", chrono::Utc::now().timestamp(), n)
+    }
+}
+
+/// The error and all of its causes, one per line.
+fn chain_of(error: &(dyn StdError + 'static)) -> String {
+    let mut s = error.to_string();
+    let mut current = error.source();
+    while let Some(e) = current {
+        s.push_str("\n  caused by: ");
+        s.push_str(&e.to_string());
+        current = e.source();
+    }
+    s
+}
@@ -0,0 +1,62 @@
+//! Health checks, served at `/healthz` next to the metrics, for container orchestration.
+//!
+//! Parts of the bot register a check with [Health::register], e.g. the database or the Discord gateway.
+//! The bot is healthy when all checks pass.
+use std::{
+    future::Future,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use futures_util::future::{join_all, BoxFuture, FutureExt};
+
+use crate::{Result, TypeMapKey};
+
+/// How long a check may take before it is considered failed.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Check = Arc<dyn Fn() -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// The health checks of the bot.
+#[derive(Clone, Default)]
+pub struct Health {
+    checks: Arc<RwLock<Vec<(&'static str, Check)>>>,
+}
+
+impl std::fmt::Debug for Health {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<Health>")
+    }
+}
+
+impl TypeMapKey for Health {
+    type Value = Health;
+}
+
+impl Health {
+    /// Add a check, failing with an error explaining what is wrong.
+    pub fn register<F, Fut>(&self, name: &'static str, check: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.checks
+            .write()
+            .unwrap()
+            .push((name, Arc::new(move || check().boxed())));
+    }
+
+    /// Run all checks, returning each check's name and error, if it failed.
+    pub async fn check(&self) -> Vec<(&'static str, Option<String>)> {
+        let checks = self.checks.read().unwrap().clone();
+        join_all(checks.into_iter().map(|(name, check)| async move {
+            let error = match tokio::time::timeout(CHECK_TIMEOUT, check()).await {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => Some(e.to_string()),
+                Err(_) => Some(format!("timed out after {:?}", CHECK_TIMEOUT)),
+            };
+            (name, error)
+        }))
+        .await
+    }
+}
@@ -1,10 +1,97 @@
-use crate::{async_trait, future, Context, Result};
-use serenity::model::channel::Message;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Display;
+use std::num::NonZeroU64;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serenity::{
+    http::{CacheHttp, HttpError},
+    model::{
+        channel::{Channel, Message},
+        id::{ChannelId, GuildId},
+    },
+};
+use youmubot_db_sql::{models::hooks as models, Pool};
+
+use crate::{
+    async_trait, future, settings::HooksConfig, ConfigKey, Context, Error, GuildConfig, OkPrint,
+    Result, TypeMapKey,
+};
 
 /// Hook represents the asynchronous hook that is run on every message.
 #[async_trait]
 pub trait Hook: Send + Sync {
     async fn call(&mut self, ctx: &Context, message: &Message) -> Result<()>;
+
+    /// Called when the content of a message is edited, with the old message if it is known.
+    /// Does nothing by default, wrap the hook in [OnEdit] to run it on edited messages.
+    async fn call_edit(
+        &mut self,
+        _ctx: &Context,
+        _old: Option<&Message>,
+        _new: &Message,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// The name of the hook, if it can be switched off with the [HookConfig].
+    fn name(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+/// A hook with a name, so that it can be switched off with the [HookConfig].
+pub struct Named<H>(pub &'static str, pub H);
+
+#[async_trait]
+impl<H: Hook> Hook for Named<H> {
+    async fn call(&mut self, ctx: &Context, message: &Message) -> Result<()> {
+        self.1.call(ctx, message).await
+    }
+
+    async fn call_edit(
+        &mut self,
+        ctx: &Context,
+        old: Option<&Message>,
+        new: &Message,
+    ) -> Result<()> {
+        self.1.call_edit(ctx, old, new).await
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        Some(self.0)
+    }
+}
+
+/// A hook that also runs on edited messages, as if they were just sent.
+///
+/// Edits that keep the content the same are skipped, but the hook itself should avoid
+/// responding twice to what was already in the old message (see [HookCooldown]).
+pub struct OnEdit<H>(pub H);
+
+#[async_trait]
+impl<H: Hook> Hook for OnEdit<H> {
+    async fn call(&mut self, ctx: &Context, message: &Message) -> Result<()> {
+        self.0.call(ctx, message).await
+    }
+
+    async fn call_edit(
+        &mut self,
+        ctx: &Context,
+        old: Option<&Message>,
+        new: &Message,
+    ) -> Result<()> {
+        if old.is_some_and(|old| old.content == new.content) {
+            return Ok(());
+        }
+        self.0.call(ctx, new).await
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        self.0.name()
+    }
 }
 
 #[async_trait]
@@ -22,3 +109,398 @@ where
         self(ctx, message).await
     }
 }
+
+/// The switches of the message hooks in the guild, as `hook=on` for the whole guild,
+/// or `channel_id:hook=off` for a channel.
+pub const HOOK_SWITCHES: ConfigKey<HookSwitches> = ConfigKey::new(
+    "hook_switches",
+    "message hooks switched on or off, as `hook=on` or `channel_id:hook=off` separated by commas; \
+     prefer the `hooks` command",
+);
+
+/// The hook switches of a guild, by channel (`None` for the whole guild) and hook name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HookSwitches(BTreeMap<(Option<ChannelId>, String), bool>);
+
+impl HookSwitches {
+    /// The switch of the hook in the channel, or in the whole guild with `None`, if it was set.
+    pub fn get(&self, channel_id: Option<ChannelId>, hook: &str) -> Option<bool> {
+        self.0.get(&(channel_id, hook.to_lowercase())).copied()
+    }
+
+    /// Set the switch of the hook in the channel, or in the whole guild with `None`.
+    /// `None` removes the switch.
+    pub fn set(&mut self, channel_id: Option<ChannelId>, hook: &str, enabled: Option<bool>) {
+        let key = (channel_id, hook.to_lowercase());
+        match enabled {
+            Some(enabled) => self.0.insert(key, enabled),
+            None => self.0.remove(&key),
+        };
+    }
+}
+
+impl FromStr for HookSwitches {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                let (target, state) = v
+                    .split_once('=')
+                    .ok_or_else(|| format!("`{}` is not of the form `hook=on`", v))?;
+                let enabled = match state {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("`{}` is neither `on` nor `off`", state)),
+                };
+                let (channel, hook) = match target.split_once(':') {
+                    Some((channel, hook)) => {
+                        let channel = channel
+                            .parse::<NonZeroU64>()
+                            .map_err(|e| format!("invalid channel id `{}`: {}", channel, e))?;
+                        (Some(ChannelId::from(channel)), hook)
+                    }
+                    None => (None, target),
+                };
+                Ok(((channel, hook.to_lowercase()), enabled))
+            })
+            .collect::<Result<_, _>>()
+            .map(HookSwitches)
+    }
+}
+
+impl Display for HookSwitches {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, ((channel, hook), enabled)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            if let Some(channel) = channel {
+                write!(f, "{}:", channel)?;
+            }
+            write!(f, "{}={}", hook, if *enabled { "on" } else { "off" })?;
+        }
+        Ok(())
+    }
+}
+
+/// The switches of the message hooks, identified by their names, stored in the [GuildConfig].
+///
+/// Channel switches take priority over guild switches, and hooks without a switch are enabled,
+/// unless they are opt-in (see [HookConfig::enabled_or]).
+#[derive(Debug, Clone)]
+pub struct HookConfig {
+    pool: Pool,
+    config: GuildConfig,
+}
+
+impl TypeMapKey for HookConfig {
+    type Value = HookConfig;
+}
+
+impl HookConfig {
+    pub fn new(pool: Pool, config: GuildConfig) -> Self {
+        Self { pool, config }
+    }
+
+    /// Whether the hook should run in the given channel.
+    pub async fn enabled(
+        &self,
+        guild_id: Option<GuildId>,
+        channel_id: ChannelId,
+        hook: &str,
+    ) -> bool {
+        self.enabled_or(guild_id, channel_id, hook, true).await
+    }
+
+    /// Whether the hook should run in the given channel, with `default` used when there is no switch.
+    /// Useful for opt-in hooks and settings.
+    /// Hooks can only be switched in guilds.
+    pub async fn enabled_or(
+        &self,
+        guild_id: Option<GuildId>,
+        channel_id: ChannelId,
+        hook: &str,
+        default: bool,
+    ) -> bool {
+        let Some(guild_id) = guild_id else {
+            return default;
+        };
+        let Some(switches) = self
+            .config
+            .get(guild_id, HOOK_SWITCHES)
+            .await
+            .pls_ok()
+            .flatten()
+        else {
+            return default;
+        };
+        switches
+            .get(Some(channel_id), hook)
+            .or_else(|| switches.get(None, hook))
+            .unwrap_or(default)
+    }
+
+    /// Set the switch of the hook in the channel, or in the whole guild with `None`.
+    /// `None` removes the switch, going back to the guild's switch (for channels) or the hook's default.
+    pub async fn set(
+        &self,
+        guild_id: GuildId,
+        channel_id: Option<ChannelId>,
+        hook: &str,
+        enabled: Option<bool>,
+    ) -> Result<()> {
+        let mut switches = self
+            .config
+            .get(guild_id, HOOK_SWITCHES)
+            .await?
+            .unwrap_or_default();
+        switches.set(channel_id, hook, enabled);
+        self.config.set(guild_id, HOOK_SWITCHES, &switches).await
+    }
+
+    /// Move the channel switches stored before they were part of the guild configuration,
+    /// looking up the guild of each channel.
+    /// Switches of channels that cannot be found anymore are dropped.
+    pub async fn import_channel_switches(&self, http: impl CacheHttp) -> Result<()> {
+        let mut channels: BTreeMap<i64, Vec<models::HookSetting>> = BTreeMap::new();
+        for s in models::HookSetting::all(&self.pool).await? {
+            channels.entry(s.channel_id).or_default().push(s);
+        }
+        for (channel, settings) in channels {
+            let channel_id = ChannelId::new(channel as u64);
+            let guild_id = match channel_id.to_channel(&http).await {
+                Ok(Channel::Guild(c)) => Some(c.guild_id),
+                Ok(_) => None,
+                Err(serenity::Error::Http(HttpError::UnsuccessfulRequest(e)))
+                    if matches!(e.status_code.as_u16(), 403 | 404) =>
+                {
+                    None
+                }
+                Err(e) => {
+                    tracing::warn!("cannot look up channel {}: {}", channel_id, e);
+                    continue;
+                }
+            };
+            if let Some(guild_id) = guild_id {
+                let mut switches = self
+                    .config
+                    .get(guild_id, HOOK_SWITCHES)
+                    .await?
+                    .unwrap_or_default();
+                for s in &settings {
+                    // Switches set since then win.
+                    if switches.get(Some(channel_id), &s.hook).is_none() {
+                        switches.set(Some(channel_id), &s.hook, Some(s.enabled));
+                    }
+                }
+                self.config.set(guild_id, HOOK_SWITCHES, &switches).await?;
+            }
+            models::HookSetting::delete_by_channel(channel, &self.pool).await?;
+        }
+        Ok(())
+    }
+}
+
+/// The default cooldown of repeated responses.
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(60);
+/// The default number of responses per channel per minute.
+const DEFAULT_MAX_RESPONSES: usize = 10;
+/// The window of the per-channel response limit.
+const RESPONSE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Keeps the hooks from spamming: a hook responds to the same thing (e.g. a link) in a channel
+/// only once per cooldown, and each channel gets a limited number of responses per minute.
+#[derive(Debug, Clone)]
+pub struct HookCooldown {
+    /// When each thing was last responded to, by channel.
+    recent: Arc<DashMap<(ChannelId, String), Instant>>,
+    /// The times of the recent responses, by channel.
+    responses: Arc<DashMap<ChannelId, VecDeque<Instant>>>,
+    cooldown: Duration,
+    max_responses: usize,
+}
+
+impl TypeMapKey for HookCooldown {
+    type Value = HookCooldown;
+}
+
+impl HookCooldown {
+    pub fn new(cooldown: Duration, max_responses: usize) -> Self {
+        Self {
+            recent: Arc::new(DashMap::new()),
+            responses: Arc::new(DashMap::new()),
+            cooldown,
+            max_responses,
+        }
+    }
+
+    /// Read the limits from the `[hooks]` configuration section.
+    pub fn from_config(config: &HooksConfig) -> Self {
+        Self::new(
+            config
+                .cooldown
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_COOLDOWN),
+            config.max_responses.unwrap_or(DEFAULT_MAX_RESPONSES),
+        )
+    }
+
+    /// Whether a hook should respond to `key` in the channel.
+    /// If so, the response is recorded, so the same key stays quiet until the cooldown is over.
+    pub fn try_respond(&self, channel_id: ChannelId, key: &str) -> bool {
+        let now = Instant::now();
+        let recent_key = (channel_id, key.to_owned());
+        if let Some(last) = self.recent.get(&recent_key) {
+            if now.duration_since(*last) < self.cooldown {
+                return false;
+            }
+        }
+        {
+            let mut responses = self.responses.entry(channel_id).or_default();
+            while responses
+                .front()
+                .is_some_and(|t| now.duration_since(*t) >= RESPONSE_WINDOW)
+            {
+                responses.pop_front();
+            }
+            if responses.len() >= self.max_responses {
+                return false;
+            }
+            responses.push_back(now);
+        }
+        self.recent.insert(recent_key, now);
+        self.prune(now);
+        true
+    }
+
+    /// Drop the entries that no longer matter, once in a while.
+    fn prune(&self, now: Instant) {
+        const PRUNE_THRESHOLD: usize = 1024;
+        if self.recent.len() > PRUNE_THRESHOLD {
+            self.recent
+                .retain(|_, t| now.duration_since(*t) < self.cooldown);
+        }
+        if self.responses.len() > PRUNE_THRESHOLD {
+            self.responses.retain(|_, r| {
+                r.back()
+                    .is_some_and(|t| now.duration_since(*t) < RESPONSE_WINDOW)
+            });
+        }
+    }
+}
+
+/// How many failures in a row disable a hook.
+const MAX_CONSECUTIVE_FAILURES: u32 = 5;
+
+/// An error caused by the message a hook responded to, e.g. a malformed attachment,
+/// rather than by the hook itself.
+///
+/// Hooks return these wrapped in an [Error], and they don't count towards disabling the hook.
+#[derive(Debug)]
+pub struct InputError(pub Error);
+
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid input: {}", self.0)
+    }
+}
+
+impl std::error::Error for InputError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.0)
+    }
+}
+
+/// The outcome of a hook call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookOutcome {
+    Ok,
+    /// The hook returned an [InputError].
+    InputError,
+    Error,
+    Panic,
+}
+
+/// The call counters of a hook.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HookCounters {
+    pub calls: u64,
+    pub input_errors: u64,
+    pub errors: u64,
+    pub panics: u64,
+    pub consecutive_failures: u32,
+    /// Whether the hook was disabled after failing too many times in a row.
+    pub disabled: bool,
+}
+
+/// The call counters of the hooks, by name.
+///
+/// A hook that fails (errors or panics) too many times in a row is disabled, until it is
+/// enabled again with [HookStats::enable].
+/// Errors in the messages themselves ([InputError]) are not the hook's failures,
+/// so they neither count towards nor reset the streak.
+#[derive(Debug, Clone, Default)]
+pub struct HookStats {
+    counters: Arc<DashMap<&'static str, HookCounters>>,
+}
+
+impl TypeMapKey for HookStats {
+    type Value = HookStats;
+}
+
+impl HookStats {
+    /// Whether the hook was disabled for failing too many times.
+    pub fn is_disabled(&self, hook: &'static str) -> bool {
+        self.counters.get(hook).is_some_and(|c| c.disabled)
+    }
+
+    /// Record the outcome of a hook call. Returns whether the hook just got disabled.
+    pub fn record(&self, hook: &'static str, outcome: HookOutcome) -> bool {
+        let mut c = self.counters.entry(hook).or_default();
+        c.calls += 1;
+        match outcome {
+            HookOutcome::Ok => {
+                c.consecutive_failures = 0;
+                return false;
+            }
+            HookOutcome::InputError => {
+                c.input_errors += 1;
+                return false;
+            }
+            HookOutcome::Error => c.errors += 1,
+            HookOutcome::Panic => c.panics += 1,
+        }
+        c.consecutive_failures += 1;
+        if !c.disabled && c.consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+            c.disabled = true;
+            return true;
+        }
+        false
+    }
+
+    /// Enable the hook again after it was disabled. Returns whether the hook is known.
+    pub fn enable(&self, hook: &str) -> bool {
+        match self.counters.iter_mut().find(|c| *c.key() == hook) {
+            Some(mut c) => {
+                c.disabled = false;
+                c.consecutive_failures = 0;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The counters of all hooks that were called, sorted by name.
+    pub fn all(&self) -> Vec<(&'static str, HookCounters)> {
+        let mut all = self
+            .counters
+            .iter()
+            .map(|c| (*c.key(), *c.value()))
+            .collect::<Vec<_>>();
+        all.sort_by_key(|(name, _)| *name);
+        all
+    }
+}
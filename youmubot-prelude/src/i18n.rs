@@ -0,0 +1,233 @@
+//! Translations of the bot's messages, in the language chosen by each user, or else by their guild.
+//!
+//! Translations are kept in a Fluent-like format, one file per language:
+//! ```text
+//! # A comment.
+//! user-not-found = User not found.
+//! score-pp-gained = **pp gained**: **{ $pp }**pp
+//! multi-line = The first line,
+//!     and the second one.
+//! ```
+use std::{collections::HashMap, fmt::Display, str::FromStr, sync::Arc};
+
+use dashmap::DashMap;
+use serenity::model::id::{GuildId, UserId};
+use youmubot_db_sql::{models::locale::UserLocale, Pool};
+
+use crate::{ConfigKey, GuildConfig, OkPrint, Result, TypeMapKey};
+
+/// The language of a guild's messages, unless its members choose their own.
+pub const LANGUAGE: ConfigKey<Locale> = ConfigKey::new(
+    "language",
+    "the language of the bot's messages on this server, unless members choose their own with `language`",
+);
+
+/// A language of the bot's messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Vi,
+}
+
+impl Locale {
+    /// All supported languages.
+    pub const ALL: &'static [Locale] = &[Locale::En, Locale::Vi];
+
+    /// The language code, e.g. `en`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Vi => "vi",
+        }
+    }
+
+    /// The name of the language, in that language.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Locale::En => "English",
+            Locale::Vi => "Tiếng Việt",
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        Locale::ALL
+            .iter()
+            .copied()
+            .find(|l| l.code() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown language `{}`, available: {}",
+                    s,
+                    Locale::ALL
+                        .iter()
+                        .map(|l| l.code())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// The translations of a module's messages, by language and key.
+/// Messages missing in a language fall back to English, then to their key.
+#[derive(Debug, Default)]
+pub struct Bundle(HashMap<(Locale, String), String>);
+
+impl Bundle {
+    /// Parse the translation files of each language.
+    pub fn new(resources: &[(Locale, &str)]) -> Self {
+        let mut messages = HashMap::new();
+        for &(locale, source) in resources {
+            let mut current: Option<(String, String)> = None;
+            for line in source.lines() {
+                let continued = line.starts_with(char::is_whitespace) && !line.trim().is_empty();
+                match &mut current {
+                    Some((_, value)) if continued => {
+                        value.push('\n');
+                        value.push_str(line.trim());
+                        continue;
+                    }
+                    _ => (),
+                }
+                if let Some((key, value)) = current.take() {
+                    messages.insert((locale, key), value);
+                }
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                match line.split_once('=') {
+                    Some((key, value)) => {
+                        current = Some((key.trim().to_owned(), value.trim().to_owned()))
+                    }
+                    None => tracing::warn!("i18n: ignoring malformed line `{}`", line),
+                }
+            }
+            if let Some((key, value)) = current {
+                messages.insert((locale, key), value);
+            }
+        }
+        Self(messages)
+    }
+
+    /// The message with the key, without arguments.
+    pub fn get<'a>(&'a self, locale: Locale, key: &'a str) -> &'a str {
+        self.0
+            .get(&(locale, key.to_owned()))
+            .or_else(|| self.0.get(&(Locale::En, key.to_owned())))
+            .map(|v| v.as_str())
+            .unwrap_or(key)
+    }
+
+    /// The message with the key, with its `{ $name }` placeholders replaced by the arguments.
+    pub fn format(&self, locale: Locale, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let message = self.get(locale, key);
+        let mut out = String::with_capacity(message.len());
+        let mut rest = message;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let Some(len) = rest[start..].find('}') else {
+                out.push_str(&rest[start..]);
+                rest = "";
+                break;
+            };
+            let placeholder = &rest[start..start + len + 1];
+            let name = placeholder[1..placeholder.len() - 1]
+                .trim()
+                .trim_start_matches('$');
+            match args.iter().find(|(n, _)| *n == name) {
+                Some((_, value)) => out.push_str(&value.to_string()),
+                None => out.push_str(placeholder),
+            }
+            rest = &rest[start + len + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// The languages chosen by users and guilds.
+#[derive(Debug, Clone)]
+pub struct Locales {
+    pool: Pool,
+    config: GuildConfig,
+    users: Arc<DashMap<UserId, Option<Locale>>>,
+}
+
+impl TypeMapKey for Locales {
+    type Value = Locales;
+}
+
+impl Locales {
+    pub fn new(pool: Pool, config: GuildConfig) -> Self {
+        Self {
+            pool,
+            config,
+            users: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// The language of the guild's messages, or the default one outside of guilds.
+    pub async fn for_guild(&self, guild_id: Option<GuildId>) -> Locale {
+        match guild_id {
+            Some(guild_id) => self
+                .config
+                .get(guild_id, LANGUAGE)
+                .await
+                .pls_ok()
+                .flatten()
+                .unwrap_or_default(),
+            None => Locale::default(),
+        }
+    }
+
+    /// The language of the messages for the user: their own choice, or else their guild's.
+    pub async fn for_user(&self, user_id: UserId, guild_id: Option<GuildId>) -> Locale {
+        match self.user(user_id).await.pls_ok().flatten() {
+            Some(locale) => locale,
+            None => self.for_guild(guild_id).await,
+        }
+    }
+
+    /// The language the user chose, if any.
+    pub async fn user(&self, user_id: UserId) -> Result<Option<Locale>> {
+        if let Some(v) = self.users.get(&user_id) {
+            return Ok(*v.value());
+        }
+        let locale = UserLocale::by_user_id(user_id.get() as i64, &self.pool)
+            .await?
+            .and_then(|v| v.parse().ok());
+        self.users.insert(user_id, locale);
+        Ok(locale)
+    }
+
+    /// Set the user's language, or forget it with `None`.
+    pub async fn set_user(&self, user_id: UserId, locale: Option<Locale>) -> Result<()> {
+        let id = user_id.get() as i64;
+        match locale {
+            Some(locale) => {
+                UserLocale {
+                    user_id: id,
+                    locale: locale.code().to_owned(),
+                }
+                .store(&self.pool)
+                .await?
+            }
+            None => UserLocale::delete(id, &self.pool).await?,
+        }
+        self.users.insert(user_id, locale);
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+//! Interactive messages: messages with buttons, select menus and modals, whose interactions
+//! are handled by a stateful [Interactive] until it stops or times out.
+use std::time::Duration;
+
+use futures_util::StreamExt as _;
+use serenity::{
+    all::{ButtonStyle, ComponentInteraction, Event, Interaction, ModalInteraction},
+    builder::{
+        CreateActionRow, CreateButton, CreateInteractionResponse, CreateInteractionResponseMessage,
+        CreateMessage, EditMessage,
+    },
+    collector,
+    model::{
+        channel::Message,
+        id::{ChannelId, UserId},
+    },
+};
+use tokio::time as tokio_time;
+
+use crate::{Context, OkPrint, Result};
+
+const CONFIRM_ID: &str = "youmu-confirm";
+const CANCEL_ID: &str = "youmu-cancel";
+
+/// How long a confirmation waits for an answer.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Who can use the components of an interactive message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Allowed {
+    /// Everyone who can see the message.
+    Anyone,
+    /// Only the given users.
+    Users(Vec<UserId>),
+}
+
+impl Allowed {
+    /// Only the given user.
+    pub fn user(user: UserId) -> Self {
+        Self::Users(vec![user])
+    }
+
+    fn contains(&self, user: UserId) -> bool {
+        match self {
+            Allowed::Anyone => true,
+            Allowed::Users(users) => users.contains(&user),
+        }
+    }
+}
+
+/// An incoming interaction on an interactive message.
+#[derive(Debug, Clone)]
+pub enum Incoming {
+    /// A button click or a select menu choice.
+    Component(ComponentInteraction),
+    /// A submitted modal, opened by one of the message's components.
+    Modal(ModalInteraction),
+}
+
+impl Incoming {
+    fn user(&self) -> UserId {
+        match self {
+            Incoming::Component(c) => c.user.id,
+            Incoming::Modal(m) => m.user.id,
+        }
+    }
+
+    /// Respond to the interaction.
+    pub async fn respond(&self, ctx: &Context, response: CreateInteractionResponse) -> Result<()> {
+        match self {
+            Incoming::Component(c) => c.create_response(ctx, response).await?,
+            Incoming::Modal(m) => m.create_response(ctx, response).await?,
+        }
+        Ok(())
+    }
+
+    /// Acknowledge the interaction without any visible response.
+    pub async fn acknowledge(&self, ctx: &Context) -> Result<()> {
+        self.respond(ctx, CreateInteractionResponse::Acknowledge)
+            .await
+    }
+
+    /// Respond with a message only the interacting user can see.
+    pub async fn ephemeral(&self, ctx: &Context, content: impl Into<String>) -> Result<()> {
+        self.respond(
+            ctx,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
+        .await
+    }
+}
+
+/// The state of an interactive message.
+#[async_trait::async_trait]
+pub trait Interactive: Send {
+    /// Handle an interaction from an allowed user. The handler should respond to it,
+    /// e.g. with [Incoming::acknowledge], otherwise Discord shows it as failed.
+    ///
+    /// Return `false` to stop handling interactions.
+    async fn handle(
+        &mut self,
+        ctx: &Context,
+        incoming: &Incoming,
+        message: &mut Message,
+    ) -> Result<bool>;
+
+    /// Called once the interactions stop, either by the handler or by timing out.
+    /// Defaults to removing the components from the message.
+    async fn stop(&mut self, ctx: &Context, message: &mut Message) -> Result<()> {
+        message
+            .edit(ctx, EditMessage::new().components(vec![]))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Handle the interactions on the message with `handler`, until it stops or no interaction
+/// comes within `timeout`. Interactions from users that are not allowed are refused.
+///
+/// If awaited, will block until everything is done. Returns the final state of the handler.
+pub async fn run<I: Interactive>(
+    mut handler: I,
+    ctx: &Context,
+    mut message: Message,
+    allowed: Allowed,
+    timeout: Duration,
+) -> Result<I> {
+    let message_id = message.id;
+    let mut interactions = collector::collect(&ctx.shard, move |event| match event {
+        Event::InteractionCreate(e) => match &e.interaction {
+            Interaction::Component(c) if c.message.id == message_id => {
+                Some(Incoming::Component(c.clone()))
+            }
+            Interaction::Modal(m) if m.message.as_ref().is_some_and(|m| m.id == message_id) => {
+                Some(Incoming::Modal(m.clone()))
+            }
+            _ => None,
+        },
+        _ => None,
+    });
+
+    let res: Result<()> = loop {
+        let incoming = match tokio_time::timeout(timeout, interactions.next()).await {
+            Err(_) | Ok(None) => break Ok(()),
+            Ok(Some(v)) => v,
+        };
+        if !allowed.contains(incoming.user()) {
+            incoming
+                .ephemeral(ctx, "You cannot use this, sorry!")
+                .await
+                .pls_ok();
+            continue;
+        }
+        match handler.handle(ctx, &incoming, &mut message).await {
+            Ok(true) => (),
+            Ok(false) => break Ok(()),
+            Err(e) => break Err(e),
+        }
+    };
+    handler.stop(ctx, &mut message).await.pls_ok();
+    res.map(|_| handler)
+}
+
+/// A confirmation dialog, answered with its confirm or cancel button.
+struct Confirm {
+    answer: Option<bool>,
+}
+
+#[async_trait::async_trait]
+impl Interactive for Confirm {
+    async fn handle(
+        &mut self,
+        ctx: &Context,
+        incoming: &Incoming,
+        _message: &mut Message,
+    ) -> Result<bool> {
+        let Incoming::Component(c) = incoming else {
+            return Ok(true);
+        };
+        self.answer = match c.data.custom_id.as_str() {
+            CONFIRM_ID => Some(true),
+            CANCEL_ID => Some(false),
+            _ => return Ok(true),
+        };
+        incoming.acknowledge(ctx).await?;
+        Ok(false)
+    }
+}
+
+/// Ask `user` to confirm with a button, sending `prompt` to `channel`.
+/// Returns whether it was confirmed. Not answering within a minute counts as cancelling.
+///
+/// Destructive commands should ask before doing anything.
+pub async fn confirm(
+    ctx: &Context,
+    channel: ChannelId,
+    user: UserId,
+    prompt: impl Into<String>,
+) -> Result<bool> {
+    let message = channel
+        .send_message(
+            ctx,
+            CreateMessage::new()
+                .content(prompt)
+                .components(vec![CreateActionRow::Buttons(vec![
+                    CreateButton::new(CONFIRM_ID)
+                        .label("Confirm")
+                        .style(ButtonStyle::Danger),
+                    CreateButton::new(CANCEL_ID)
+                        .label("Cancel")
+                        .style(ButtonStyle::Secondary),
+                ])]),
+        )
+        .await?;
+    let confirm = run(
+        Confirm { answer: None },
+        ctx,
+        message,
+        Allowed::user(user),
+        CONFIRM_TIMEOUT,
+    )
+    .await?;
+    Ok(confirm.answer.unwrap_or(false))
+}
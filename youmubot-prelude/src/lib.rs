@@ -13,22 +13,61 @@ pub use serenity::prelude::*;
 pub use tokio::spawn as spawn_future;
 
 pub use announcer::{Announcer, AnnouncerRunner};
-pub use args::{ChannelId, Duration, RoleId, UserId, UsernameArg};
+pub use args::{ChannelId, Duration, Percentage, Position, RoleId, UserId, UsernameArg};
+pub use command_filter::CommandFilter;
+pub use config::{ConfigKey, GuildConfig};
 pub use debugging_ok::OkPrint;
+pub use errors::ErrorReporter;
 pub use flags::Flags;
-pub use hook::Hook;
+pub use health::Health;
+pub use hook::{Hook, HookConfig, HookCooldown, HookStats, HookSwitches, InputError};
+pub use i18n::{Locale, Locales};
+pub use interaction::{confirm, Interactive};
+pub use logging::LogFilter;
 pub use member_cache::MemberCache;
+pub use metrics::Metrics;
+pub use modules::{Module, Modules};
+pub use options::Options;
 pub use pagination::{paginate, paginate_fn, paginate_reply, paginate_reply_fn, Paginate};
+pub use scheduler::{Schedule, Scheduler};
+pub use send_queue::SendQueue;
+pub use settings::Config;
+pub use setup::SetupError;
+pub use shutdown::Shutdown;
+pub use singleflight::{SharedError, Singleflight};
+pub use supervisor::Supervisor;
 
 pub mod announcer;
+pub mod application;
 pub mod args;
+pub mod command_filter;
+pub mod config;
+pub mod cooldown;
+pub mod errors;
 pub mod flags;
+pub mod health;
 pub mod hook;
+pub mod i18n;
+pub mod interaction;
+pub mod logging;
 pub mod member_cache;
+pub mod metrics;
+pub mod modules;
+pub mod options;
 pub mod pagination;
+pub mod permissions;
 pub mod ratelimit;
+pub mod scheduler;
+pub mod send_queue;
+pub mod settings;
 pub mod setup;
+pub mod shutdown;
+pub mod singleflight;
+pub mod status;
+pub mod supervisor;
 pub mod table_format;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 /// The global app data.
 pub type AppData = Arc<RwLock<TypeMap>>;
@@ -54,6 +93,19 @@ pub struct Env {
     pub http: reqwest::Client,
     pub sql: youmubot_db_sql::Pool,
     pub members: Arc<MemberCache>,
+    pub hooks: HookConfig,
+    pub hook_cooldown: HookCooldown,
+    pub config: GuildConfig,
+    pub locales: Locales,
+    pub settings: Arc<Config>,
+    pub modules: Modules,
+    pub shutdown: Shutdown,
+    pub supervisor: Supervisor,
+    pub scheduler: Scheduler,
+    pub errors: ErrorReporter,
+    pub metrics: Metrics,
+    pub health: Health,
+    pub send_queue: SendQueue,
     // databases
     // pub(crate) announcer_channels: announcer::AnnouncerChannels,
 }
@@ -62,26 +114,486 @@ pub mod prelude_commands {
     use serenity::{
         framework::standard::{
             macros::{command, group},
-            CommandResult,
+            Args, CommandResult,
         },
-        model::channel::Message,
-        prelude::Context,
+        model::{channel::Message, id::GuildId},
+        prelude::{Context, Mentionable},
     };
 
     use crate::announcer::ANNOUNCERCOMMANDS_GROUP;
+    use crate::command_filter::{self, CommandFilter, DISABLED_COMMANDS};
+    use crate::permissions::{
+        CommandRoles, Override, COMMAND_ROLES, MANAGE_CHANNELS_CHECK, MANAGE_GUILD_CHECK,
+    };
+    use crate::supervisor::TaskState;
+    use crate::{
+        GuildConfig, HookStats, Locale, Locales, LogFilter, Modules, Scheduler, Supervisor,
+    };
 
     #[group("Prelude")]
     #[description = "All the commands that makes the base of Youmu"]
-    #[commands(ping)]
-    #[sub_groups(AnnouncerCommands)]
+    #[commands(
+        ping, hookstats, tasks, jobs, modules, logfilter, config, enable, disable, perms, language
+    )]
+    #[sub_groups(AnnouncerCommands, Diagnostics)]
     pub struct Prelude;
 
+    #[group]
+    #[prefix = "diag"]
+    #[owners_only]
+    #[description = "Inspect the internal state of the bot. Read-only."]
+    #[commands(diag_announcers)]
+    pub struct Diagnostics;
+
+    #[command("announcers")]
+    #[description = "Show the announcers registered in a server (this server by default), and their channels."]
+    #[usage = "[server id]"]
+    #[max_args(1)]
+    async fn diag_announcers(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+        let guild_id = match args.single::<u64>() {
+            Ok(id) => GuildId::new(id),
+            Err(_) => m
+                .guild_id
+                .ok_or_else(|| crate::Error::msg("a server id is required in DMs"))?,
+        };
+        let announcers = crate::announcer::announcers_in(&ctx.data, guild_id).await?;
+        let reply = if announcers.is_empty() {
+            format!("No announcers are registered in server `{}`.", guild_id)
+        } else {
+            let lines = announcers
+                .iter()
+                .map(|(key, channel)| format!("- `{}`: {} (`{}`)", key, channel.mention(), channel))
+                .collect::<Vec<_>>();
+            format!(
+                "Announcers registered in server `{}`:\n{}",
+                guild_id,
+                lines.join("\n")
+            )
+        };
+        m.reply(&ctx, reply).await?;
+        Ok(())
+    }
+
     #[command]
     #[description = "pong!"]
     async fn ping(ctx: &Context, m: &Message) -> CommandResult {
         m.reply(&ctx, "Pong!").await?;
         Ok(())
     }
+
+    #[command]
+    #[owners_only]
+    #[description = "Show the call and failure counts of the message hooks. \
+                     Hooks that failed too many times in a row are disabled until re-enabled with `enable <hook>`."]
+    #[usage = "[enable <hook>]"]
+    #[max_args(2)]
+    async fn hookstats(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+        let stats = ctx.data.read().await.get::<HookStats>().unwrap().clone();
+        if args.current() == Some("enable") {
+            args.advance();
+            let hook = args.single::<String>()?;
+            if !stats.enable(&hook) {
+                return Err(crate::error!("unknown hook `{}`", hook).into());
+            }
+            m.reply(&ctx, format!("Hook `{}` is enabled again.", hook))
+                .await?;
+            return Ok(());
+        }
+
+        let all = stats.all();
+        if all.is_empty() {
+            m.reply(&ctx, "No hooks were called yet.").await?;
+            return Ok(());
+        }
+        let mut s = String::from("```\n");
+        for (name, c) in all {
+            s.push_str(&format!(
+                "{:<16} {:>8} calls {:>6} bad inputs {:>6} errors {:>4} panics{}\n",
+                name,
+                c.calls,
+                c.input_errors,
+                c.errors,
+                c.panics,
+                if c.disabled { "  [disabled]" } else { "" }
+            ));
+        }
+        s.push_str("```");
+        m.reply(&ctx, s).await?;
+        Ok(())
+    }
+
+    #[command]
+    #[owners_only]
+    #[description = "Show the background tasks: whether they are running, and how many times they panicked."]
+    async fn tasks(ctx: &Context, m: &Message) -> CommandResult {
+        let supervisor = ctx.data.read().await.get::<Supervisor>().unwrap().clone();
+        let all = supervisor.health();
+        if all.is_empty() {
+            m.reply(&ctx, "No background tasks are running.").await?;
+            return Ok(());
+        }
+        let mut s = String::from("```\n");
+        for (name, h) in all {
+            let state = match h.state {
+                TaskState::Running => "running",
+                TaskState::Restarting => "restarting",
+                TaskState::Stopped => "stopped",
+            };
+            s.push_str(&format!(
+                "{:<20} {:<10} since {} {:>4} restarts\n",
+                name,
+                state,
+                h.started_at.format("%Y-%m-%d %H:%M"),
+                h.restarts
+            ));
+            if let Some((at, panic)) = &h.last_panic {
+                s.push_str(&format!(
+                    "  last panic at {}: {}\n",
+                    at.format("%Y-%m-%d %H:%M"),
+                    panic
+                ));
+            }
+        }
+        s.push_str("```");
+        m.reply(&ctx, s).await?;
+        Ok(())
+    }
+
+    #[command]
+    #[owners_only]
+    #[description = "Show the scheduled jobs: their schedules, last and next runs, and last errors."]
+    async fn jobs(ctx: &Context, m: &Message) -> CommandResult {
+        let scheduler = ctx.data.read().await.get::<Scheduler>().unwrap().clone();
+        let all = scheduler.jobs();
+        if all.is_empty() {
+            m.reply(&ctx, "No jobs are scheduled.").await?;
+            return Ok(());
+        }
+        let format = |t: Option<chrono::DateTime<chrono::Utc>>| {
+            t.map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "-".to_owned())
+        };
+        let mut s = String::from("```\n");
+        for (name, job) in all {
+            s.push_str(&format!(
+                "{:<24} {:<16} last {:<16} next {}\n",
+                name,
+                job.schedule,
+                format(job.last_run),
+                format(job.next_run)
+            ));
+            if let Some(error) = &job.last_error {
+                s.push_str(&format!("  last run failed: {}\n", error));
+            }
+        }
+        s.push_str("```");
+        m.reply(&ctx, s).await?;
+        Ok(())
+    }
+
+    #[command]
+    #[owners_only]
+    #[description = "Show the modules and whether they are enabled. \
+                     Modules are switched off in the `[features]` configuration, or with `FEATURE_<MODULE>=false`."]
+    async fn modules(ctx: &Context, m: &Message) -> CommandResult {
+        let modules = ctx.data.read().await.get::<Modules>().unwrap().clone();
+        let mut s = String::from("```\n");
+        for (module, enabled) in modules.all() {
+            s.push_str(&format!(
+                "{:<20} {:<9} {}\n",
+                module.name,
+                if enabled { "enabled" } else { "disabled" },
+                module.description
+            ));
+        }
+        s.push_str("```");
+        let unknown = modules.unknown();
+        if !unknown.is_empty() {
+            s.push_str(&format!(
+                "\nUnknown modules in the configuration: {}",
+                unknown
+                    .iter()
+                    .map(|v| format!("`{}`", v))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        m.reply(&ctx, s).await?;
+        Ok(())
+    }
+
+    #[command]
+    #[owners_only]
+    #[description = "Show or change what gets logged, with a filter in the `RUST_LOG` syntax. \
+                     The filter goes back to `RUST_LOG` on restart."]
+    #[usage = "[filter]"]
+    #[example = "info,youmubot_osu=debug"]
+    async fn logfilter(ctx: &Context, m: &Message, args: Args) -> CommandResult {
+        let filter = ctx.data.read().await.get::<LogFilter>().unwrap().clone();
+        let new_filter = args.rest().trim();
+        if new_filter.is_empty() {
+            m.reply(&ctx, format!("The log filter is `{}`.", filter.current()))
+                .await?;
+            return Ok(());
+        }
+        filter.set(new_filter)?;
+        tracing::info!(filter = new_filter, by = %m.author.name, "log filter changed");
+        m.reply(&ctx, format!("The log filter is now `{}`.", new_filter))
+            .await?;
+        Ok(())
+    }
+
+    #[command]
+    #[description = "Read or change the configuration of this server. \
+                     Without arguments, show all configuration keys and their values."]
+    #[usage = "[key] / [key] [new value, or `reset`]"]
+    #[example = "prefix y!"]
+    #[checks(manage_guild)]
+    #[only_in(guilds)]
+    async fn config(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+        let config = ctx.data.read().await.get::<GuildConfig>().unwrap().clone();
+        let guild_id = m.guild_id.unwrap();
+        let describe = |value: Option<String>| match value {
+            Some(v) => format!("`{}`", v),
+            None => "*default*".to_owned(),
+        };
+
+        let Ok(key) = args.single::<String>() else {
+            let mut s = String::from("Configuration of this server:\n");
+            for (key, description) in config.keys() {
+                let value = config.get_raw(guild_id, key).await?;
+                s.push_str(&format!(
+                    "- `{}` ({}): {}\n",
+                    key,
+                    description,
+                    describe(value)
+                ));
+            }
+            m.reply(&ctx, s).await?;
+            return Ok(());
+        };
+        if args.is_empty() {
+            if !config.keys().iter().any(|(k, _)| *k == key) {
+                return Err(crate::error!("unknown configuration key `{}`", key).into());
+            }
+            let value = config.get_raw(guild_id, &key).await?;
+            m.reply(&ctx, format!("`{}` is {}", key, describe(value)))
+                .await?;
+            return Ok(());
+        }
+        let value = match args.rest().trim() {
+            "reset" => None,
+            v => Some(v.to_owned()),
+        };
+        config.set_by_name(guild_id, &key, value.clone()).await?;
+        m.reply(&ctx, format!("`{}` is now {}", key, describe(value)))
+            .await?;
+        Ok(())
+    }
+
+    #[command]
+    #[description = "Enable a command or a command group again in a channel (this channel by default). \
+                     Without arguments, list the commands and groups disabled in the channel."]
+    #[usage = "[command or group] / [channel]"]
+    #[example = "osu / #bot-spam"]
+    #[checks(manage_channels)]
+    #[only_in(guilds)]
+    #[max_args(2)]
+    async fn enable(ctx: &Context, m: &Message, args: Args) -> CommandResult {
+        toggle_command(ctx, m, args, true).await
+    }
+
+    #[command]
+    #[description = "Disable a command or a command group in a channel (this channel by default). \
+                     Without arguments, list the commands and groups disabled in the channel."]
+    #[usage = "[command or group] / [channel]"]
+    #[example = "osu / #general"]
+    #[checks(manage_channels)]
+    #[only_in(guilds)]
+    #[max_args(2)]
+    async fn disable(ctx: &Context, m: &Message, args: Args) -> CommandResult {
+        toggle_command(ctx, m, args, false).await
+    }
+
+    #[command]
+    #[description = "Grant commands or command groups to roles, so that their members can use them \
+                     without the permissions they normally require, \
+                     or restrict them to roles, so that only their members (and administrators) can use them. \
+                     Without arguments, list the roles granted or restricted to commands on this server."]
+    #[usage = "[grant/restrict/ungrant/unrestrict] / [command or group] / [role]"]
+    #[example = "grant / config / @Moderators"]
+    #[required_permissions(ADMINISTRATOR)]
+    #[only_in(guilds)]
+    #[max_args(3)]
+    async fn perms(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+        let (config, filter) = {
+            let data = ctx.data.read().await;
+            (
+                data.get::<GuildConfig>().unwrap().clone(),
+                data.get::<CommandFilter>().unwrap().clone(),
+            )
+        };
+        let guild_id = m.guild_id.unwrap();
+        let mut roles = config
+            .get(guild_id, COMMAND_ROLES)
+            .await?
+            .unwrap_or_default();
+
+        let Ok(action) = args.single::<String>() else {
+            let lines = roles
+                .iter()
+                .map(|(name, mode, role)| format!("- `{}`: {} {}", name, mode, role.mention()))
+                .collect::<Vec<_>>();
+            let reply = if lines.is_empty() {
+                "No roles are granted or restricted to commands on this server.".to_owned()
+            } else {
+                format!("Command roles on this server:\n{}", lines.join("\n"))
+            };
+            m.reply(&ctx, reply).await?;
+            return Ok(());
+        };
+        let (add, mode) = match action.as_str() {
+            "grant" => (true, Override::Grant),
+            "restrict" => (true, Override::Restrict),
+            "ungrant" => (false, Override::Grant),
+            "unrestrict" => (false, Override::Restrict),
+            _ => return Err(crate::error!("unknown action `{}`", action).into()),
+        };
+        let name = args.single::<String>()?.to_lowercase();
+        let role = args.single::<crate::RoleId>()?.0;
+        if !filter.exists(&name) {
+            return Err(crate::error!("unknown command or group `{}`", name).into());
+        }
+        let changed = if add {
+            roles.insert(&name, mode, role)
+        } else {
+            roles.remove(&name, mode, role)
+        };
+        if changed {
+            config.set(guild_id, COMMAND_ROLES, &roles).await?;
+        }
+        m.reply(
+            &ctx,
+            format!(
+                "`{}` is {} {}.",
+                name,
+                match (add, mode) {
+                    (true, Override::Grant) => "granted to",
+                    (true, Override::Restrict) => "restricted to",
+                    (false, Override::Grant) => "no longer granted to",
+                    (false, Override::Restrict) => "no longer restricted to",
+                },
+                role.mention()
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[command]
+    #[description = "Choose the language of the bot's messages for you, or go back to the server's with `reset`. \
+                     Without arguments, show your language and the available ones. \
+                     Server managers can set the server's language with `config language`."]
+    #[usage = "[language code, or `reset`]"]
+    #[example = "vi"]
+    #[max_args(1)]
+    async fn language(ctx: &Context, m: &Message, mut args: Args) -> CommandResult {
+        let locales = ctx.data.read().await.get::<Locales>().unwrap().clone();
+        let Ok(arg) = args.single::<String>() else {
+            let current = locales.for_user(m.author.id, m.guild_id).await;
+            let available = Locale::ALL
+                .iter()
+                .map(|l| format!("`{}` ({})", l.code(), l.name()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            m.reply(
+                &ctx,
+                format!(
+                    "Your language is **{}**. Available languages: {}",
+                    current.name(),
+                    available
+                ),
+            )
+            .await?;
+            return Ok(());
+        };
+        let locale = match arg.as_str() {
+            "reset" => None,
+            code => Some(code.parse::<Locale>().map_err(crate::Error::msg)?),
+        };
+        locales.set_user(m.author.id, locale).await?;
+        let current = locales.for_user(m.author.id, m.guild_id).await;
+        m.reply(
+            &ctx,
+            format!("Your language is now **{}**.", current.name()),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn toggle_command(
+        ctx: &Context,
+        m: &Message,
+        mut args: Args,
+        enable: bool,
+    ) -> CommandResult {
+        let (config, filter) = {
+            let data = ctx.data.read().await;
+            (
+                data.get::<GuildConfig>().unwrap().clone(),
+                data.get::<CommandFilter>().unwrap().clone(),
+            )
+        };
+        let guild_id = m.guild_id.unwrap();
+        let name = args.single::<String>().ok().map(|v| v.to_lowercase());
+        let channel_id = args
+            .single::<crate::ChannelId>()
+            .map(|c| c.0)
+            .unwrap_or(m.channel_id);
+        let mut disabled = config
+            .get(guild_id, DISABLED_COMMANDS)
+            .await?
+            .unwrap_or_default();
+
+        let Some(name) = name else {
+            let names = disabled
+                .in_channel(channel_id)
+                .map(|n| format!("`{}`", n))
+                .collect::<Vec<_>>();
+            let reply = if names.is_empty() {
+                format!("Nothing is disabled in {}.", channel_id.mention())
+            } else {
+                format!("Disabled in {}: {}", channel_id.mention(), names.join(", "))
+            };
+            m.reply(&ctx, reply).await?;
+            return Ok(());
+        };
+        if !filter.exists(&name) {
+            return Err(crate::error!("unknown command or group `{}`", name).into());
+        }
+        if command_filter::ALWAYS_ENABLED.contains(&name.as_str()) {
+            return Err(crate::error!("`{}` cannot be disabled", name).into());
+        }
+        let changed = if enable {
+            disabled.remove(channel_id, &name)
+        } else {
+            disabled.insert(channel_id, &name)
+        };
+        if changed {
+            config.set(guild_id, DISABLED_COMMANDS, &disabled).await?;
+        }
+        m.reply(
+            &ctx,
+            format!(
+                "`{}` is {} in {}.",
+                name,
+                if enable { "enabled" } else { "disabled" },
+                channel_id.mention()
+            ),
+        )
+        .await?;
+        Ok(())
+    }
 }
 
 mod debugging_ok {
@@ -97,7 +609,7 @@ mod debugging_ok {
             match self {
                 Ok(v) => Some(v),
                 Err(e) => {
-                    eprintln!("Error: {:?}", e);
+                    tracing::error!("Error: {:?}", e);
                     None
                 }
             }
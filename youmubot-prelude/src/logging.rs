@@ -0,0 +1,72 @@
+//! Logging, with [tracing].
+//!
+//! Commands, hooks and API requests run in spans, so that everything they log is tagged with them.
+//! What gets logged is chosen with a filter in the `RUST_LOG` syntax (e.g. `info,youmubot_osu=debug`),
+//! read from `RUST_LOG` on startup and changed at runtime with the `logfilter` command.
+use std::sync::{Arc, RwLock};
+
+use tracing_subscriber::{
+    fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry,
+};
+
+use crate::{Result, TypeMapKey};
+
+/// The filter used when `RUST_LOG` is not set.
+pub const DEFAULT_FILTER: &str = "info,serenity=warn,tracing::span=warn";
+
+/// The log filter, that can be changed at runtime.
+#[derive(Clone)]
+pub struct LogFilter {
+    handle: reload::Handle<EnvFilter, Registry>,
+    current: Arc<RwLock<String>>,
+}
+
+impl std::fmt::Debug for LogFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<LogFilter {}>", self.current())
+    }
+}
+
+impl TypeMapKey for LogFilter {
+    type Value = LogFilter;
+}
+
+impl LogFilter {
+    /// Install the global logger, with the filter in `RUST_LOG` or [DEFAULT_FILTER].
+    /// Records of crates logging with `log` are logged as well.
+    ///
+    /// Panics if called twice.
+    pub fn init() -> Self {
+        let initial = std::env::var("RUST_LOG").unwrap_or_else(|_| DEFAULT_FILTER.to_owned());
+        let (filter, initial) = match EnvFilter::try_new(&initial) {
+            Ok(filter) => (filter, initial),
+            Err(e) => {
+                eprintln!("invalid RUST_LOG: {}", e);
+                (EnvFilter::new(DEFAULT_FILTER), DEFAULT_FILTER.to_owned())
+            }
+        };
+        let (filter, handle) = reload::Layer::new(filter);
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(fmt::layer())
+            .init();
+        Self {
+            handle,
+            current: Arc::new(RwLock::new(initial)),
+        }
+    }
+
+    /// The current filter.
+    pub fn current(&self) -> String {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Replace the filter.
+    pub fn set(&self, filter: &str) -> Result<()> {
+        let parsed =
+            EnvFilter::try_new(filter).map_err(|e| crate::error!("invalid filter: {}", e))?;
+        self.handle.reload(parsed)?;
+        *self.current.write().unwrap() = filter.to_owned();
+        Ok(())
+    }
+}
@@ -2,23 +2,33 @@ use anyhow::bail;
 use chrono::{DateTime, Utc};
 use serenity::model::{
     guild::Member,
-    id::{GuildId, UserId},
+    id::{GuildId, RoleId, UserId},
 };
 use serenity::{http::CacheHttp, prelude::*};
-use std::collections::{hash_map::Entry, HashMap};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as SyncMutex};
 use tokio::sync::Mutex;
 
 const VALID_CACHE_SECONDS: i64 = 15 * 60; // 15 minutes
 const INVALID_CACHE_SECONDS: i64 = 2 * 60; // 2 minutes
 
-type Map<K, V> = Mutex<HashMap<K, V>>;
+/// The most members Discord returns in one request.
+const CHUNK_SIZE: u64 = 1000;
+
+type Map<K, V> = SyncMutex<HashMap<K, V>>;
+
+/// The cached members of a guild. Locked while they are being fetched,
+/// so that concurrent lookups of the same guild wait for one request.
+type GuildSlot = Arc<Mutex<Option<Expiring<Option<Arc<[Member]>>>>>>;
 
 /// MemberCache resolves `does User belong to Guild` requests, and store them in a cache.
+///
+/// The cache is kept up to date with the member events of the gateway, see [MemberCache::update_member]
+/// and [MemberCache::remove_member].
 #[derive(Debug, Default)]
 pub struct MemberCache {
     per_user: Map<(UserId, GuildId), Expiring<Option<Member>>>,
-    per_guild: Map<GuildId, Expiring<Option<Arc<[Member]>>>>,
+    per_guild: Map<GuildId, GuildSlot>,
 }
 
 #[derive(Debug)]
@@ -54,7 +64,7 @@ impl MemberCache {
     ) -> Option<Member> {
         let now = Utc::now();
         // Check cache
-        if let Some(r) = self.per_user.lock().await.get(&(user_id, guild_id)) {
+        if let Some(r) = self.per_user.lock().unwrap().get(&(user_id, guild_id)) {
             if r.timeout > now {
                 return r.value.clone();
             }
@@ -64,60 +74,189 @@ impl MemberCache {
             return mems.iter().find(|m| m.user.id == user_id).cloned();
         }
         // Query
-        let mut map = self.per_user.lock().await;
-        let entry = map.entry((user_id, guild_id));
-        if let Entry::Occupied(oe) = &entry {
-            if oe.get().timeout > now {
-                return oe.get().value.clone();
-            }
-        }
         let t = guild_id.member(&cache_http, user_id).await.ok();
-        entry
-            .or_insert(Expiring::new(
+        self.per_user.lock().unwrap().insert(
+            (user_id, guild_id),
+            Expiring::new(
                 t.clone(),
                 now + chrono::Duration::seconds(VALID_CACHE_SECONDS),
-            ))
-            .value
-            .clone()
+            ),
+        );
+        t
     }
 
+    /// Get all members of the guild.
+    /// Large guilds are fetched in chunks of 1000 members.
     pub async fn query_members(
         &self,
         cache_http: impl CacheHttp,
         guild_id: GuildId,
     ) -> crate::Result<Arc<[Member]>> {
+        let slot = self.slot(guild_id);
+        let mut slot = slot.lock().await;
         let now = Utc::now();
-        let mut map = self.per_guild.lock().await;
-        let entry = map.entry(guild_id);
         // Check cache
-        if let Entry::Occupied(oe) = &entry {
-            if oe.get().timeout > now {
-                return match &oe.get().value {
-                    Some(v) => Ok(v.clone()),
-                    None => bail!("guild members for {} unavailable", guild_id),
-                };
-            }
+        if let Some(cached) = slot.as_ref().filter(|c| c.timeout > now) {
+            return match &cached.value {
+                Some(v) => Ok(v.clone()),
+                None => bail!("guild members for {} unavailable", guild_id),
+            };
         }
         // query
-        eprintln!("querying members of {}", guild_id);
-        let members: Option<Arc<[Member]>> = guild_id
-            .members(cache_http.http(), None, None)
+        tracing::debug!("querying members of {}", guild_id);
+        let members: Option<Arc<[Member]>> = fetch_members(&cache_http, guild_id)
             .await
+            .map_err(|e| tracing::warn!("cannot query members of {}: {}", guild_id, e))
             .ok()
             .map(|v| v.into());
-        match &entry
-            .or_insert(Expiring::new(
-                members.clone(),
-                now + chrono::Duration::seconds(if members.is_some() {
-                    VALID_CACHE_SECONDS
-                } else {
-                    INVALID_CACHE_SECONDS
-                }),
-            ))
-            .value
-        {
-            Some(v) => Ok(v.clone()),
+        let timeout = now
+            + chrono::Duration::seconds(if members.is_some() {
+                VALID_CACHE_SECONDS
+            } else {
+                INVALID_CACHE_SECONDS
+            });
+        *slot = Some(Expiring::new(members.clone(), timeout));
+        match members {
+            Some(v) => Ok(v),
             None => bail!("guild members for {} unavailable", guild_id),
         }
     }
+
+    /// Get the members of the guild with the given role.
+    pub async fn members_with_role(
+        &self,
+        cache_http: impl CacheHttp,
+        guild_id: GuildId,
+        role_id: RoleId,
+    ) -> crate::Result<Vec<Member>> {
+        Ok(self
+            .query_members(cache_http, guild_id)
+            .await?
+            .iter()
+            .filter(|m| m.roles.contains(&role_id))
+            .cloned()
+            .collect())
+    }
+
+    /// Find the members of the guild whose username, display name or nickname contains `query`,
+    /// ignoring case. Exact matches come first.
+    pub async fn search(
+        &self,
+        cache_http: impl CacheHttp,
+        guild_id: GuildId,
+        query: &str,
+    ) -> crate::Result<Vec<Member>> {
+        let query = query.to_lowercase();
+        let mut found = self
+            .query_members(cache_http, guild_id)
+            .await?
+            .iter()
+            .filter_map(|m| {
+                let names = [
+                    Some(m.user.name.as_str()),
+                    m.user.global_name.as_deref(),
+                    m.nick.as_deref(),
+                ];
+                let names = names.iter().flatten().map(|n| n.to_lowercase());
+                let mut exact = false;
+                let mut partial = false;
+                for name in names {
+                    exact |= name == query;
+                    partial |= name.contains(&query);
+                }
+                partial.then(|| (!exact, m.clone()))
+            })
+            .collect::<Vec<_>>();
+        found.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(found.into_iter().map(|(_, m)| m).collect())
+    }
+
+    /// Record a member that joined or was updated, from a gateway event.
+    pub async fn update_member(&self, member: Member) {
+        let (guild_id, user_id) = (member.guild_id, member.user.id);
+        if let Some(slot) = self.cached_slot(guild_id) {
+            if let Some(Some(members)) = slot.lock().await.as_mut().map(|c| &mut c.value) {
+                let mut updated = members
+                    .iter()
+                    .filter(|m| m.user.id != user_id)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                updated.push(member.clone());
+                *members = updated.into();
+            }
+        }
+        self.per_user.lock().unwrap().insert(
+            (user_id, guild_id),
+            Expiring::new(
+                Some(member),
+                Utc::now() + chrono::Duration::seconds(VALID_CACHE_SECONDS),
+            ),
+        );
+    }
+
+    /// Forget a member that left the guild, from a gateway event.
+    pub async fn remove_member(&self, guild_id: GuildId, user_id: UserId) {
+        if let Some(slot) = self.cached_slot(guild_id) {
+            if let Some(Some(members)) = slot.lock().await.as_mut().map(|c| &mut c.value) {
+                if members.iter().any(|m| m.user.id == user_id) {
+                    *members = members
+                        .iter()
+                        .filter(|m| m.user.id != user_id)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .into();
+                }
+            }
+        }
+        self.per_user.lock().unwrap().insert(
+            (user_id, guild_id),
+            Expiring::new(
+                None,
+                Utc::now() + chrono::Duration::seconds(VALID_CACHE_SECONDS),
+            ),
+        );
+    }
+
+    /// Forget everything about the guild, e.g. when the bot leaves it.
+    pub fn invalidate_guild(&self, guild_id: GuildId) {
+        self.per_guild.lock().unwrap().remove(&guild_id);
+        self.per_user
+            .lock()
+            .unwrap()
+            .retain(|(_, g), _| *g != guild_id);
+    }
+
+    /// The cached members of the guild, created if needed.
+    fn slot(&self, guild_id: GuildId) -> GuildSlot {
+        self.per_guild
+            .lock()
+            .unwrap()
+            .entry(guild_id)
+            .or_default()
+            .clone()
+    }
+
+    /// The cached members of the guild, if they were ever queried.
+    fn cached_slot(&self, guild_id: GuildId) -> Option<GuildSlot> {
+        self.per_guild.lock().unwrap().get(&guild_id).cloned()
+    }
+}
+
+/// Fetch all members of the guild, one chunk at a time.
+async fn fetch_members(
+    cache_http: impl CacheHttp,
+    guild_id: GuildId,
+) -> crate::Result<Vec<Member>> {
+    let mut members: Vec<Member> = Vec::new();
+    loop {
+        let after = members.last().map(|m| m.user.id);
+        let chunk = guild_id
+            .members(cache_http.http(), Some(CHUNK_SIZE), after)
+            .await?;
+        let last_chunk = (chunk.len() as u64) < CHUNK_SIZE;
+        members.extend(chunk);
+        if last_chunk {
+            return Ok(members);
+        }
+    }
 }
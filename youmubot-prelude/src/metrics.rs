@@ -0,0 +1,305 @@
+//! Metrics, exposed in the Prometheus text format at `/metrics` by the [status server](crate::status).
+//!
+//! Modules describe their metrics as [Metric] constants, and record into the [Metrics] of the prelude `Env`:
+//! ```ignore
+//! const REQUESTS: Metric = Metric::counter("youmubot_requests_total", "Requests sent, by outcome.");
+//! env.metrics.inc(&REQUESTS, &[("outcome", "ok")]);
+//! ```
+//! Values kept elsewhere (e.g. cache counters) are read when scraped, with [Metrics::collect].
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+
+use crate::TypeMapKey;
+
+/// The upper bounds of the histogram buckets, in seconds.
+const BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0, 1800.0,
+];
+
+/// Commands run, by command, kind (`text` or `slash`) and outcome.
+pub const COMMANDS: Metric = Metric::counter(
+    "youmubot_commands_total",
+    "Commands run, by command, kind and outcome.",
+);
+/// How long commands take to run.
+pub const COMMAND_DURATION: Metric = Metric::histogram(
+    "youmubot_command_duration_seconds",
+    "How long commands take to run, by command.",
+);
+/// How long an announcer sweep takes.
+pub const ANNOUNCER_SWEEP: Metric = Metric::histogram(
+    "youmubot_announcer_sweep_seconds",
+    "How long a sweep of an announcer takes, by announcer.",
+);
+
+/// The kind of a metric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl Kind {
+    fn name(&self) -> &'static str {
+        match self {
+            Kind::Counter => "counter",
+            Kind::Gauge => "gauge",
+            Kind::Histogram => "histogram",
+        }
+    }
+}
+
+/// The description of a metric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metric {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub kind: Kind,
+}
+
+impl Metric {
+    pub const fn counter(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            kind: Kind::Counter,
+        }
+    }
+
+    pub const fn gauge(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            kind: Kind::Gauge,
+        }
+    }
+
+    /// A histogram of durations, in seconds.
+    pub const fn histogram(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            kind: Kind::Histogram,
+        }
+    }
+}
+
+/// The labels of a series, as `(name, value)` pairs.
+pub type Labels = Vec<(&'static str, String)>;
+
+/// Reads the series of a metric kept elsewhere, when scraped.
+type Collector = Box<dyn Fn() -> Vec<(Labels, f64)> + Send + Sync>;
+
+/// The value of a series.
+#[derive(Debug, Clone)]
+enum Value {
+    Number(f64),
+    Histogram {
+        buckets: Vec<u64>,
+        sum: f64,
+        count: u64,
+    },
+}
+
+/// The metrics of the bot.
+#[derive(Clone, Default)]
+pub struct Metrics {
+    metrics: Arc<DashMap<&'static str, Metric>>,
+    series: Arc<DashMap<(&'static str, Labels), Value>>,
+    collectors: Arc<RwLock<Vec<(Metric, Collector)>>>,
+    timers: Arc<DashMap<u64, Instant>>,
+}
+
+impl std::fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<Metrics>")
+    }
+}
+
+impl TypeMapKey for Metrics {
+    type Value = Metrics;
+}
+
+fn labels_of(labels: &[(&'static str, &str)]) -> Labels {
+    labels.iter().map(|(k, v)| (*k, v.to_string())).collect()
+}
+
+impl Metrics {
+    /// Add one to the counter.
+    pub fn inc(&self, metric: &Metric, labels: &[(&'static str, &str)]) {
+        self.add(metric, labels, 1.0)
+    }
+
+    /// Add to the counter, or set the gauge.
+    pub fn add(&self, metric: &Metric, labels: &[(&'static str, &str)], v: f64) {
+        self.metrics.entry(metric.name).or_insert(*metric);
+        let mut value = self
+            .series
+            .entry((metric.name, labels_of(labels)))
+            .or_insert(Value::Number(0.0));
+        if let Value::Number(n) = &mut *value {
+            match metric.kind {
+                Kind::Gauge => *n = v,
+                _ => *n += v,
+            }
+        }
+    }
+
+    /// Record a duration in the histogram.
+    pub fn observe(&self, metric: &Metric, labels: &[(&'static str, &str)], v: Duration) {
+        self.metrics.entry(metric.name).or_insert(*metric);
+        let v = v.as_secs_f64();
+        let mut value = self
+            .series
+            .entry((metric.name, labels_of(labels)))
+            .or_insert_with(|| Value::Histogram {
+                buckets: vec![0; BUCKETS.len()],
+                sum: 0.0,
+                count: 0,
+            });
+        if let Value::Histogram {
+            buckets,
+            sum,
+            count,
+        } = &mut *value
+        {
+            for (bucket, le) in buckets.iter_mut().zip(BUCKETS) {
+                if v <= *le {
+                    *bucket += 1;
+                }
+            }
+            *sum += v;
+            *count += 1;
+        }
+    }
+
+    /// Read the series of the counter or gauge with `read` whenever the metrics are scraped.
+    pub fn collect(
+        &self,
+        metric: Metric,
+        read: impl Fn() -> Vec<(Labels, f64)> + Send + Sync + 'static,
+    ) {
+        self.collectors
+            .write()
+            .unwrap()
+            .push((metric, Box::new(read)));
+    }
+
+    /// Start timing something identified by `key`, e.g. a command by its message's id.
+    pub fn start_timer(&self, key: u64) {
+        self.timers.insert(key, Instant::now());
+    }
+
+    /// Stop timing the thing identified by `key`, returning the time since [Metrics::start_timer].
+    pub fn stop_timer(&self, key: u64) -> Option<Duration> {
+        self.timers.remove(&key).map(|(_, t)| t.elapsed())
+    }
+
+    /// Render all metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut families: BTreeMap<&'static str, (Metric, Vec<(Labels, Value)>)> = BTreeMap::new();
+        for s in self.series.iter() {
+            let (name, labels) = s.key();
+            let name = *name;
+            let Some(metric) = self.metrics.get(name).map(|m| *m) else {
+                continue;
+            };
+            families
+                .entry(name)
+                .or_insert_with(|| (metric, vec![]))
+                .1
+                .push((labels.clone(), s.value().clone()));
+        }
+        for (metric, read) in self.collectors.read().unwrap().iter() {
+            let family = &mut families
+                .entry(metric.name)
+                .or_insert_with(|| (*metric, vec![]))
+                .1;
+            family.extend(
+                read()
+                    .into_iter()
+                    .map(|(labels, v)| (labels, Value::Number(v))),
+            );
+        }
+
+        let mut out = String::new();
+        for (name, (metric, mut series)) in families {
+            series.sort_by(|a, b| a.0.cmp(&b.0));
+            writeln!(out, "# HELP {} {}", name, metric.help).unwrap();
+            writeln!(out, "# TYPE {} {}", name, metric.kind.name()).unwrap();
+            for (labels, value) in series {
+                match value {
+                    Value::Number(v) => {
+                        writeln!(out, "{}{} {}", name, format_labels(&labels, None), v).unwrap()
+                    }
+                    Value::Histogram {
+                        buckets,
+                        sum,
+                        count,
+                    } => {
+                        for (le, n) in BUCKETS.iter().zip(buckets) {
+                            let le = le.to_string();
+                            writeln!(
+                                out,
+                                "{}_bucket{} {}",
+                                name,
+                                format_labels(&labels, Some(&le)),
+                                n
+                            )
+                            .unwrap();
+                        }
+                        writeln!(
+                            out,
+                            "{}_bucket{} {}",
+                            name,
+                            format_labels(&labels, Some("+Inf")),
+                            count
+                        )
+                        .unwrap();
+                        writeln!(out, "{}_sum{} {}", name, format_labels(&labels, None), sum)
+                            .unwrap();
+                        writeln!(
+                            out,
+                            "{}_count{} {}",
+                            name,
+                            format_labels(&labels, None),
+                            count
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Format the labels as `{name="value",...}`, with the `le` label of histogram buckets if given.
+fn format_labels(labels: &Labels, le: Option<&str>) -> String {
+    let mut parts = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape(v)))
+        .collect::<Vec<_>>();
+    if let Some(le) = le {
+        parts.push(format!("le=\"{}\"", le));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", parts.join(","))
+    }
+}
+
+/// Escape a label value.
+fn escape(v: &str) -> String {
+    v.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,79 @@
+//! Modules: parts of the bot that can be switched off at runtime, in the `[features]` configuration.
+//!
+//! Each `setup` function registers the modules it sets up with [Modules::register],
+//! and skips the ones that are switched off.
+use std::sync::{Arc, RwLock};
+
+use crate::{settings::Features, TypeMapKey};
+
+/// A part of the bot that can be switched off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Module {
+    /// The name of the module, as used in the configuration.
+    pub name: &'static str,
+    /// What the module does.
+    pub description: &'static str,
+}
+
+impl Module {
+    pub const fn new(name: &'static str, description: &'static str) -> Self {
+        Self { name, description }
+    }
+}
+
+/// The registered modules and whether they are enabled.
+#[derive(Debug, Clone)]
+pub struct Modules {
+    features: Features,
+    registered: Arc<RwLock<Vec<(Module, bool)>>>,
+}
+
+impl TypeMapKey for Modules {
+    type Value = Modules;
+}
+
+impl Modules {
+    pub fn new(features: Features) -> Self {
+        Self {
+            features,
+            registered: Arc::new(RwLock::new(vec![])),
+        }
+    }
+
+    /// Register the module, returning whether it is enabled.
+    pub fn register(&self, module: Module) -> bool {
+        let enabled = self.features.enabled(module.name);
+        let mut registered = self.registered.write().unwrap();
+        if !registered.iter().any(|(m, _)| m.name == module.name) {
+            registered.push((module, enabled));
+        }
+        if !enabled {
+            tracing::info!("module `{}` is disabled", module.name);
+        }
+        enabled
+    }
+
+    /// Whether the module is registered and enabled.
+    pub fn enabled(&self, name: &str) -> bool {
+        self.registered
+            .read()
+            .unwrap()
+            .iter()
+            .any(|(m, enabled)| m.name == name && *enabled)
+    }
+
+    /// All registered modules, in the order they were registered.
+    pub fn all(&self) -> Vec<(Module, bool)> {
+        self.registered.read().unwrap().clone()
+    }
+
+    /// The names in the configuration that are not registered modules, likely typos.
+    pub fn unknown(&self) -> Vec<String> {
+        let registered = self.registered.read().unwrap();
+        self.features
+            .names()
+            .filter(|name| !registered.iter().any(|(m, _)| m.name == *name))
+            .map(|name| name.to_owned())
+            .collect()
+    }
+}
@@ -0,0 +1,107 @@
+//! Command options: `--name value` pairs mixed with positional arguments, in any order.
+//!
+//! e.g. `top --mode taiko --style grid --mods HDDT natsukagami` and `top natsukagami taiko --grid`
+//! are read the same way.
+use std::{fmt::Display, str::FromStr};
+
+use serenity::framework::standard::Args;
+
+use crate::{bail, Error, Result};
+
+/// The arguments of a command, split into options and positional arguments.
+///
+/// Options with a value are written `--name value`, and their names must be known when parsing.
+/// Everything else, including flags like `--lazer`, is kept as a positional argument,
+/// to be picked by its type with [Options::take].
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    values: Vec<(String, String)>,
+    positional: Vec<String>,
+}
+
+impl Options {
+    /// Split the arguments, `valued` being the names of the options that take a value.
+    pub fn parse(mut args: Args, valued: &[&str]) -> Result<Options> {
+        let mut opts = Options::default();
+        let mut iter = args.iter::<String>();
+        let mut tokens = iter
+            .quoted()
+            .trimmed()
+            .filter_map(|v| v.ok())
+            .filter(|v| !v.is_empty());
+        while let Some(token) = tokens.next() {
+            match token.strip_prefix("--").filter(|n| valued.contains(n)) {
+                Some(name) => {
+                    if opts.values.iter().any(|(n, _)| n == name) {
+                        bail!("option `--{}` is given twice", name);
+                    }
+                    let Some(value) = tokens.next() else {
+                        bail!("option `--{}` needs a value", name);
+                    };
+                    opts.values.push((name.to_owned(), value));
+                }
+                None => opts.positional.push(token),
+            }
+        }
+        Ok(opts)
+    }
+
+    /// The value of `--name`, if given.
+    pub fn value<T>(&mut self, name: &str) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let Some(i) = self.values.iter().position(|(n, _)| n == name) else {
+            return Ok(None);
+        };
+        let (_, value) = self.values.remove(i);
+        value
+            .parse()
+            .map(Some)
+            .map_err(|e| Error::msg(format!("invalid value for `--{}`: {}", name, e)))
+    }
+
+    /// Take the first positional argument that parses as `T`.
+    pub fn take<T: FromStr>(&mut self) -> Option<T> {
+        let (i, v) = self
+            .positional
+            .iter()
+            .enumerate()
+            .find_map(|(i, s)| s.parse().ok().map(|v| (i, v)))?;
+        self.positional.remove(i);
+        Some(v)
+    }
+
+    /// The value of `--name`, or else the first positional argument that parses as `T`.
+    pub fn get<T>(&mut self, name: &str) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.value(name)? {
+            Some(v) => Ok(Some(v)),
+            None => Ok(self.take()),
+        }
+    }
+
+    /// Whether the flag `--name` is given.
+    pub fn flag(&mut self, name: &str) -> bool {
+        let flag = format!("--{}", name);
+        match self.positional.iter().position(|s| *s == flag) {
+            Some(i) => {
+                self.positional.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Fail if some positional arguments were not taken.
+    pub fn finish(self) -> Result<()> {
+        match self.positional.first() {
+            Some(arg) => bail!("unexpected argument `{}`", arg),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! Role-based permission overrides for commands, set by the guild's administrators.
+//!
+//! A command (or a whole command group) can be:
+//! - *granted* to a role: members with the role can use it without the permissions it normally requires.
+//!   This only works for commands gated by the checks of this module instead of `required_permissions`.
+//! - *restricted* to a role: only members with one of its restricting roles (and administrators) can use it.
+//!   This applies to every command, checked by the framework's `before` hook with [check].
+use std::{collections::BTreeSet, fmt::Display, str::FromStr};
+
+use serenity::{
+    framework::standard::{macros::check, Args, CommandOptions, Reason},
+    model::{
+        channel::{Channel, Message},
+        id::RoleId,
+        permissions::Permissions,
+    },
+};
+
+use crate::{CommandFilter, ConfigKey, Context, GuildConfig, OkPrint, Result};
+
+/// The roles granted or restricted to each command.
+/// Not registered with the `config` command, so that only administrators can change it with `perms`.
+pub const COMMAND_ROLES: ConfigKey<CommandRoles> = ConfigKey::new(
+    "command_roles",
+    "roles granted or restricted to commands and groups, as `name:grant|restrict:role_id` separated by commas",
+);
+
+/// How a role overrides the permissions of a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Override {
+    /// Members with the role can use the command without its required permissions.
+    Grant,
+    /// Only members with one of the restricting roles can use the command.
+    Restrict,
+}
+
+impl FromStr for Override {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grant" => Ok(Override::Grant),
+            "restrict" => Ok(Override::Restrict),
+            _ => Err(format!("`{}` is neither `grant` nor `restrict`", s)),
+        }
+    }
+}
+
+impl Display for Override {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Override::Grant => write!(f, "grant"),
+            Override::Restrict => write!(f, "restrict"),
+        }
+    }
+}
+
+/// The role overrides of a guild, by command or group name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandRoles(BTreeSet<(String, Override, RoleId)>);
+
+impl CommandRoles {
+    /// Add the override. Returns whether it was not there before.
+    pub fn insert(&mut self, name: &str, mode: Override, role: RoleId) -> bool {
+        self.0.insert((name.to_lowercase(), mode, role))
+    }
+
+    /// Remove the override. Returns whether it was there before.
+    pub fn remove(&mut self, name: &str, mode: Override, role: RoleId) -> bool {
+        self.0.remove(&(name.to_lowercase(), mode, role))
+    }
+
+    /// All overrides, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Override, RoleId)> {
+        self.0
+            .iter()
+            .map(|(name, mode, role)| (name.as_str(), *mode, *role))
+    }
+
+    /// The roles overriding any of the given names in the given way.
+    fn roles<'a>(
+        &'a self,
+        names: &'a [&'a str],
+        mode: Override,
+    ) -> impl Iterator<Item = RoleId> + 'a {
+        self.0
+            .iter()
+            .filter(move |(name, m, _)| {
+                *m == mode && names.iter().any(|n| n.eq_ignore_ascii_case(name))
+            })
+            .map(|(_, _, role)| *role)
+    }
+}
+
+impl FromStr for CommandRoles {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                let mut parts = v.splitn(3, ':');
+                let (Some(name), Some(mode), Some(role)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(format!("`{}` is not of the form `name:mode:role_id`", v));
+                };
+                let role = role
+                    .parse::<u64>()
+                    .map_err(|e| format!("invalid role id `{}`: {}", role, e))?;
+                Ok((name.to_lowercase(), mode.parse()?, RoleId::new(role)))
+            })
+            .collect::<Result<_, _>>()
+            .map(CommandRoles)
+    }
+}
+
+impl Display for CommandRoles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (name, mode, role)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}:{}:{}", name, mode, role)?;
+        }
+        Ok(())
+    }
+}
+
+/// The command's name and the names of its groups.
+async fn names_of(ctx: &Context, command: &str) -> Vec<&'static str> {
+    let filter = ctx.data.read().await.get::<CommandFilter>().cloned();
+    filter
+        .map(|f| f.groups_of(command).to_vec())
+        .unwrap_or_default()
+}
+
+/// The role overrides of the message's guild.
+async fn command_roles(ctx: &Context, msg: &Message) -> Option<CommandRoles> {
+    let guild_id = msg.guild_id?;
+    let config = ctx.data.read().await.get::<GuildConfig>()?.clone();
+    config.get(guild_id, COMMAND_ROLES).await.pls_ok().flatten()
+}
+
+/// The permissions of the message's author in its channel, if it is a guild channel.
+async fn channel_permissions(ctx: &Context, msg: &Message) -> Option<Permissions> {
+    match msg.channel_id.to_channel(ctx).await.pls_ok()? {
+        Channel::Guild(gc) => gc.permissions_for_user(ctx, msg.author.id).pls_ok(),
+        _ => None,
+    }
+}
+
+/// Whether the message's author has any of the roles.
+fn has_any_role(msg: &Message, mut roles: impl Iterator<Item = RoleId>) -> bool {
+    let member_roles = msg.member.as_ref().map(|m| &m.roles[..]).unwrap_or(&[]);
+    roles.any(|r| member_roles.contains(&r))
+}
+
+/// Whether the message's author can use the command, given its restricting roles.
+/// To be checked by the framework's `before` hook, which should not dispatch the command otherwise.
+/// Returns the reason to tell the user if they cannot.
+pub async fn check(ctx: &Context, msg: &Message, command: &str) -> Result<(), String> {
+    let Some(roles) = command_roles(ctx, msg).await else {
+        return Ok(());
+    };
+    let mut names: Vec<&str> = names_of(ctx, command).await;
+    names.push(command);
+    let restricting = roles.roles(&names, Override::Restrict).collect::<Vec<_>>();
+    if restricting.is_empty() || has_any_role(msg, restricting.iter().copied()) {
+        return Ok(());
+    }
+    // Administrators cannot lock themselves out.
+    if channel_permissions(ctx, msg)
+        .await
+        .is_some_and(|p| p.administrator())
+    {
+        return Ok(());
+    }
+    Err(format!(
+        "🔒 `{}` is restricted to some roles on this server.",
+        command
+    ))
+}
+
+/// Whether the message's author has the permissions in the channel, or has a role granted the command.
+async fn permitted(
+    ctx: &Context,
+    msg: &Message,
+    options: &CommandOptions,
+    required: Permissions,
+) -> Result<(), Reason> {
+    if msg.guild_id.is_none() {
+        return Ok(());
+    }
+    if channel_permissions(ctx, msg)
+        .await
+        .is_some_and(|p| p.contains(required))
+    {
+        return Ok(());
+    }
+    let command = options.names.first().copied().unwrap_or_default();
+    if let Some(roles) = command_roles(ctx, msg).await {
+        let mut names = names_of(ctx, command).await;
+        names.push(command);
+        if has_any_role(msg, roles.roles(&names, Override::Grant)) {
+            return Ok(());
+        }
+    }
+    Err(Reason::User(format!(
+        "🔒 You need the {} permissions, or a role granted `{}`, to use this command.",
+        required, command
+    )))
+}
+
+/// Requires the Manage Messages permission, unless granted by a role.
+#[check]
+#[name = "manage_messages"]
+pub async fn manage_messages_check(
+    ctx: &Context,
+    msg: &Message,
+    _: &mut Args,
+    options: &CommandOptions,
+) -> Result<(), Reason> {
+    permitted(ctx, msg, options, Permissions::MANAGE_MESSAGES).await
+}
+
+/// Requires the Manage Channels permission, unless granted by a role.
+#[check]
+#[name = "manage_channels"]
+pub async fn manage_channels_check(
+    ctx: &Context,
+    msg: &Message,
+    _: &mut Args,
+    options: &CommandOptions,
+) -> Result<(), Reason> {
+    permitted(ctx, msg, options, Permissions::MANAGE_CHANNELS).await
+}
+
+/// Requires the Manage Roles permission, unless granted by a role.
+#[check]
+#[name = "manage_roles"]
+pub async fn manage_roles_check(
+    ctx: &Context,
+    msg: &Message,
+    _: &mut Args,
+    options: &CommandOptions,
+) -> Result<(), Reason> {
+    permitted(ctx, msg, options, Permissions::MANAGE_ROLES).await
+}
+
+/// Requires the Manage Server permission, unless granted by a role.
+#[check]
+#[name = "manage_guild"]
+pub async fn manage_guild_check(
+    ctx: &Context,
+    msg: &Message,
+    _: &mut Args,
+    options: &CommandOptions,
+) -> Result<(), Reason> {
+    permitted(ctx, msg, options, Permissions::MANAGE_GUILD).await
+}
+
+/// Requires the Administrator permission, unless granted by a role.
+#[check]
+#[name = "administrator"]
+pub async fn administrator_check(
+    ctx: &Context,
+    msg: &Message,
+    _: &mut Args,
+    options: &CommandOptions,
+) -> Result<(), Reason> {
+    permitted(ctx, msg, options, Permissions::ADMINISTRATOR).await
+}
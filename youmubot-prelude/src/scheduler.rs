@@ -0,0 +1,402 @@
+//! Recurring jobs, run on cron-like schedules.
+//!
+//! Modules register their jobs (pruning, cache validation, ...) with [Scheduler::register].
+//! Each job runs as a [supervised](crate::Supervisor) task at the times of its [Schedule], delayed by a
+//! random jitter so that jobs on the same schedule do not all start at once.
+//! The last run of each job is stored in the database: a run missed while the bot was down is done on start up.
+use std::{
+    fmt::Display,
+    future::Future,
+    str::FromStr,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Timelike, Utc};
+use dashmap::DashMap;
+use rand::Rng;
+use youmubot_db_sql::{models::jobs::ScheduledJob, Pool};
+
+use crate::{
+    announcer::CacheAndHttp, bail, error, Error, OkPrint, Result, Shutdown, Supervisor, TypeMapKey,
+};
+
+/// How far ahead to look for the next run of a schedule, in days.
+const MAX_LOOKAHEAD_DAYS: u32 = 366 * 5;
+
+/// When a job runs: a cron expression, in UTC.
+///
+/// The expression has five fields: minute, hour, day of month, month and day of week (0 or 7 is Sunday).
+/// Each field is `*`, a value, a range `a-b`, a list `a,b,c` of those, and may be followed by a step `/n`.
+/// As in cron, a day matches if either the day of month or the day of week matches, when both are restricted.
+/// `@hourly`, `@daily`, `@weekly` and `@monthly` are also accepted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    text: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl FromStr for Schedule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let expr = match s.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            v => v,
+        };
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            bail!(
+                "invalid schedule `{}`: expected 5 fields (minute, hour, day, month, weekday)",
+                s
+            );
+        };
+        let field = |name: &str, v: &str, min: u32, max: u32| {
+            parse_field(v, min, max)
+                .map_err(|e| error!("invalid {} in schedule `{}`: {}", name, s, e))
+        };
+        let mut weekdays_set = field("weekday", weekdays, 0, 7)?;
+        // 7 is Sunday, as 0.
+        if weekdays_set & (1 << 7) != 0 {
+            weekdays_set |= 1;
+        }
+        Ok(Self {
+            text: s.trim().to_owned(),
+            minutes: field("minute", minutes, 0, 59)?,
+            hours: field("hour", hours, 0, 23)?,
+            days: field("day", days, 1, 31)?,
+            months: field("month", months, 1, 12)?,
+            weekdays: weekdays_set,
+            any_day: days == "*",
+            any_weekday: weekdays == "*",
+        })
+    }
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(&self.text)
+    }
+}
+
+/// Parse a field of a cron expression into the set of its values, as bits.
+fn parse_field(s: &str, min: u32, max: u32) -> Result<u64> {
+    let mut set = 0u64;
+    for item in s.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .map_err(|e| error!("invalid step `{}`: {}", step, e))?;
+                if step == 0 {
+                    bail!("the step cannot be 0");
+                }
+                (range, Some(step))
+            }
+            None => (item, None),
+        };
+        let value = |v: &str| -> Result<u32> {
+            let v = v
+                .parse::<u32>()
+                .map_err(|e| error!("invalid value `{}`: {}", v, e))?;
+            if v < min || v > max {
+                bail!("{} is not between {} and {}", v, min, max);
+            }
+            Ok(v)
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (value(a)?, value(b)?),
+                // `a/n` is every n-th value from a.
+                None if step.is_some() => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if start > end {
+            bail!("the range `{}` is empty", range);
+        }
+        for v in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            set |= 1 << v;
+        }
+    }
+    Ok(set)
+}
+
+impl Schedule {
+    /// The first time strictly after `after` the schedule runs at, if any in the next few years.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        let mut date = start.date_naive();
+        for _ in 0..MAX_LOOKAHEAD_DAYS {
+            if self.matches_day(date) {
+                let from = if date == start.date_naive() {
+                    (start.hour(), start.minute())
+                } else {
+                    (0, 0)
+                };
+                if let Some((hour, minute)) = self.first_time_from(from) {
+                    return Some(Utc.from_utc_datetime(&date.and_hms_opt(hour, minute, 0)?));
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if !has(self.months, date.month()) {
+            return false;
+        }
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+        if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+
+    /// The first (hour, minute) of the schedule, from the given one of the same day.
+    fn first_time_from(&self, (hour, minute): (u32, u32)) -> Option<(u32, u32)> {
+        (hour..24).filter(|h| has(self.hours, *h)).find_map(|h| {
+            let from = if h == hour { minute } else { 0 };
+            (from..60).find(|m| has(self.minutes, *m)).map(|m| (h, m))
+        })
+    }
+}
+
+fn has(set: u64, v: u32) -> bool {
+    set & (1 << v) != 0
+}
+
+/// The state of a scheduled job.
+#[derive(Debug, Clone)]
+pub struct JobStatus {
+    pub schedule: Schedule,
+    /// When the job last started.
+    pub last_run: Option<DateTime<Utc>>,
+    /// When the job will start next, if it is waiting.
+    pub next_run: Option<DateTime<Utc>>,
+    /// The error of the last run, if it failed.
+    pub last_error: Option<String>,
+}
+
+/// Runs the registered jobs on their schedules.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    pool: Pool,
+    supervisor: Supervisor,
+    jobs: Arc<DashMap<&'static str, JobStatus>>,
+    discord: Arc<OnceLock<CacheAndHttp>>,
+}
+
+impl TypeMapKey for Scheduler {
+    type Value = Scheduler;
+}
+
+impl Scheduler {
+    pub fn new(pool: Pool, supervisor: Supervisor) -> Self {
+        Self {
+            pool,
+            supervisor,
+            jobs: Arc::new(DashMap::new()),
+            discord: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Give the jobs access to Discord, once the client is built.
+    pub fn connect(&self, discord: CacheAndHttp) {
+        self.discord.set(discord).ok();
+    }
+
+    /// The Discord client, for jobs that post messages.
+    /// `None` until the client is [connected](Scheduler::connect).
+    pub fn discord(&self) -> Option<CacheAndHttp> {
+        self.discord.get().cloned()
+    }
+
+    /// Run `job` on the schedule, each run delayed by a random duration up to `jitter`.
+    /// Failed runs are logged, and the job runs again on its next scheduled time.
+    pub fn register<F, Fut>(&self, name: &'static str, schedule: Schedule, jitter: Duration, job: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.jobs.insert(
+            name,
+            JobStatus {
+                schedule: schedule.clone(),
+                last_run: None,
+                next_run: None,
+                last_error: None,
+            },
+        );
+        let job = Arc::new(job);
+        let scheduler = self.clone();
+        self.supervisor.spawn(name, move |shutdown| {
+            scheduler
+                .clone()
+                .run(name, schedule.clone(), jitter, job.clone(), shutdown)
+        });
+    }
+
+    /// The status of all jobs, sorted by name.
+    pub fn jobs(&self) -> Vec<(&'static str, JobStatus)> {
+        let mut all = self
+            .jobs
+            .iter()
+            .map(|j| (*j.key(), j.value().clone()))
+            .collect::<Vec<_>>();
+        all.sort_by_key(|(name, _)| *name);
+        all
+    }
+
+    async fn run<F, Fut>(
+        self,
+        name: &'static str,
+        schedule: Schedule,
+        jitter: Duration,
+        job: Arc<F>,
+        shutdown: Shutdown,
+    ) where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let mut last_run = ScheduledJob::last_run(name, &self.pool)
+            .await
+            .pls_ok()
+            .flatten();
+        loop {
+            let now = Utc::now();
+            // Catch up on a run missed while the bot was down.
+            let next = match last_run.and_then(|t| schedule.next_after(t)) {
+                Some(t) if t <= now => now,
+                _ => match schedule.next_after(now) {
+                    Some(t) => t,
+                    None => {
+                        tracing::warn!(job = name, "schedule `{}` never runs", schedule);
+                        return;
+                    }
+                },
+            };
+            let next = next + random_jitter(jitter);
+            self.update(name, |s| s.next_run = Some(next));
+            let wait = (next - Utc::now()).to_std().unwrap_or_default();
+            if !shutdown.sleep(wait).await {
+                return;
+            }
+
+            let _work = shutdown.work();
+            let started = Utc::now();
+            // Stored before running, so that a job panicking is not retried right away on restart.
+            ScheduledJob {
+                name: name.to_owned(),
+                last_run: started,
+            }
+            .store(&self.pool)
+            .await
+            .pls_ok();
+            last_run = Some(started);
+            self.update(name, |s| {
+                s.last_run = Some(started);
+                s.next_run = None;
+            });
+            tracing::debug!(job = name, "running scheduled job");
+            let error = job().await.err().map(|e| format!("{:#}", e));
+            if let Some(e) = &error {
+                tracing::warn!(job = name, "scheduled job failed: {}", e);
+            }
+            self.update(name, |s| s.last_error = error);
+        }
+    }
+
+    fn update(&self, name: &'static str, f: impl FnOnce(&mut JobStatus)) {
+        if let Some(mut status) = self.jobs.get_mut(name) {
+            f(&mut status);
+        }
+    }
+}
+
+fn random_jitter(jitter: Duration) -> chrono::Duration {
+    if jitter.is_zero() {
+        return chrono::Duration::zero();
+    }
+    let ms = rand::thread_rng().gen_range(0..jitter.as_millis().max(1) as i64);
+    chrono::Duration::milliseconds(ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn next(schedule: &str, after: &str) -> DateTime<Utc> {
+        schedule
+            .parse::<Schedule>()
+            .unwrap()
+            .next_after(at(after))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!("* * * *".parse::<Schedule>().is_err());
+        assert!("60 * * * *".parse::<Schedule>().is_err());
+        assert!("*/0 * * * *".parse::<Schedule>().is_err());
+        assert!("5-1 * * * *".parse::<Schedule>().is_err());
+        assert!("0 0 0 * *".parse::<Schedule>().is_err());
+        assert_eq!("@daily".parse::<Schedule>().unwrap().to_string(), "@daily");
+        assert_eq!(parse_field("1-3,10", 0, 59).unwrap(), 0b1110 | 1 << 10);
+        assert_eq!(parse_field("*/20", 0, 59).unwrap(), 1 | 1 << 20 | 1 << 40);
+        assert_eq!(parse_field("5/30", 0, 59).unwrap(), 1 << 5 | 1 << 35);
+    }
+
+    #[test]
+    fn test_next_after() {
+        // Strictly after, even on a matching minute.
+        assert_eq!(
+            next("*/15 * * * *", "2024-01-01T10:15:00Z"),
+            at("2024-01-01T10:30:00Z")
+        );
+        assert_eq!(
+            next("@hourly", "2024-01-01T23:59:30Z"),
+            at("2024-01-02T00:00:00Z")
+        );
+        // 2024-01-01 is a Monday.
+        assert_eq!(
+            next("30 18 * * 0", "2024-01-01T12:00:00Z"),
+            at("2024-01-07T18:30:00Z")
+        );
+        assert_eq!(
+            next("0 0 * * 7", "2024-01-01T12:00:00Z"),
+            at("2024-01-07T00:00:00Z")
+        );
+        assert_eq!(
+            next("0 0 29 2 *", "2024-03-01T00:00:00Z"),
+            at("2028-02-29T00:00:00Z")
+        );
+        // Either the day of month or the day of week.
+        assert_eq!(
+            next("0 0 15 * 3", "2024-01-01T12:00:00Z"),
+            at("2024-01-03T00:00:00Z")
+        );
+        assert!("0 0 31 2 *"
+            .parse::<Schedule>()
+            .unwrap()
+            .next_after(at("2024-01-01T00:00:00Z"))
+            .is_none());
+    }
+}
@@ -0,0 +1,129 @@
+//! A queue of outgoing messages, for bursts of announcements.
+//!
+//! Messages queued to a channel are sent one by one in the order they were queued,
+//! spaced out to stay within Discord's limit of 5 messages per 5 seconds in a channel,
+//! instead of running into the limit and waiting on rejected requests.
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+
+use dashmap::DashMap;
+use serenity::{
+    builder::CreateMessage,
+    model::{channel::Message, id::ChannelId},
+};
+use tokio::{sync::oneshot, time::Instant};
+
+use crate::{announcer::CacheAndHttp, metrics::Metric, Error, Result, TypeMapKey};
+
+/// The number of messages sent in a channel per [WINDOW].
+const BURST: usize = 5;
+/// The window of Discord's per-channel rate limit.
+const WINDOW: Duration = Duration::from_secs(5);
+
+/// The number of messages waiting to be sent.
+pub const QUEUED_MESSAGES: Metric = Metric::gauge(
+    "youmubot_send_queue_messages",
+    "Announcements waiting to be sent.",
+);
+
+/// A message waiting to be sent.
+struct Outgoing {
+    http: CacheAndHttp,
+    message: CreateMessage,
+    sent: oneshot::Sender<Result<Message>>,
+}
+
+/// The queue of outgoing messages, one per channel.
+#[derive(Clone, Default)]
+pub struct SendQueue {
+    channels: Arc<DashMap<ChannelId, flume::Sender<Outgoing>>>,
+}
+
+impl std::fmt::Debug for SendQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<SendQueue {} queued>", self.queued())
+    }
+}
+
+impl TypeMapKey for SendQueue {
+    type Value = SendQueue;
+}
+
+impl SendQueue {
+    /// Queue the message, and wait until it is sent.
+    pub async fn send(
+        &self,
+        http: CacheAndHttp,
+        channel: ChannelId,
+        message: CreateMessage,
+    ) -> Result<Message> {
+        let (sent, result) = oneshot::channel();
+        self.channels
+            .entry(channel)
+            .or_insert_with(|| start_sender(channel))
+            .send(Outgoing {
+                http,
+                message,
+                sent,
+            })
+            .map_err(|_| Error::msg("the send queue is closed"))?;
+        result
+            .await
+            .map_err(|_| Error::msg("the queued message was dropped"))?
+    }
+
+    /// The number of messages waiting to be sent, in all channels.
+    pub fn queued(&self) -> usize {
+        self.channels.iter().map(|c| c.len()).sum()
+    }
+}
+
+/// Start sending the messages queued to the channel, in the background.
+fn start_sender(channel: ChannelId) -> flume::Sender<Outgoing> {
+    let (tx, rx) = flume::unbounded::<Outgoing>();
+    tokio::spawn(async move {
+        let mut sent_at = VecDeque::<Instant>::with_capacity(BURST);
+        while let Ok(m) = rx.recv_async().await {
+            if sent_at.len() >= BURST {
+                let oldest = sent_at.pop_front().unwrap();
+                tokio::time::sleep_until(oldest + WINDOW).await;
+            }
+            let result = channel
+                .send_message(&m.http, m.message)
+                .await
+                .map_err(Error::from);
+            sent_at.push_back(Instant::now());
+            // The sender might have stopped waiting.
+            m.sent.send(result).ok();
+        }
+    });
+    tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockDiscord;
+
+    #[tokio::test]
+    async fn test_send_in_order() {
+        let discord = MockDiscord::start().await.unwrap();
+        let queue = SendQueue::default();
+        let channel = ChannelId::new(10);
+        let sends = (0..3).map(|i| {
+            queue.send(
+                discord.cache_and_http(),
+                channel,
+                CreateMessage::new().content(i.to_string()),
+            )
+        });
+        for sent in futures_util::future::join_all(sends).await {
+            assert_eq!(sent.unwrap().channel_id, channel);
+        }
+        let sent = discord.sent();
+        assert_eq!(
+            sent.iter().map(|m| m.content.as_str()).collect::<Vec<_>>(),
+            ["0", "1", "2"]
+        );
+        assert_eq!(queue.queued(), 0);
+    }
+}
@@ -0,0 +1,452 @@
+//! The bot's configuration, read from `youmubot.toml` with environment variable overrides.
+//!
+//! The file is the one at `YOUMUBOT_CONFIG`, or `youmubot.toml` in the working directory, and may be absent.
+//! Every setting can be overridden by its environment variable, e.g. `TOKEN` for `discord.token`:
+//! ```toml
+//! [discord]
+//! token = "..."                    # TOKEN
+//! prefixes = ["y!", "y2!"]         # PREFIX, comma-separated
+//! log_channel = 1234567890         # LOG_CHANNEL, where command errors are reported to the owners
+//!
+//! [database]
+//! path = "/data"                   # DBPATH
+//! sql_path = "/data/youmubot.db"   # SQLPATH, defaults to `youmubot.db` inside `path`
+//! url = "sqlite:///data/bot.db"    # DATABASE_URL, sqlite: or postgres:, takes precedence over `sql_path`
+//! max_connections = 10             # SQL_MAX_CONNECTIONS
+//! min_connections = 0              # SQL_MIN_CONNECTIONS
+//! acquire_timeout = 30             # SQL_ACQUIRE_TIMEOUT, in seconds
+//!
+//! [osu]
+//! client_id = 12345                # OSU_API_CLIENT_ID
+//! client_secret = "..."            # OSU_API_CLIENT_SECRET
+//! rate_limit = 60                  # OSU_API_RATE_LIMIT, in requests per minute
+//! timeout = 15                     # OSU_API_TIMEOUT, in seconds
+//! retries = 2                      # OSU_API_RETRIES, for timeouts and server errors
+//! beatmap_files = "cache/beatmaps" # OSU_BEATMAP_FILES, where the .osu files are stored
+//! beatmap_cache_size = 2048        # OSU_BEATMAP_CACHE_SIZE, the beatmaps kept in memory
+//! beatmap_cache_ttl = 3600         # OSU_BEATMAP_CACHE_TTL, in seconds
+//! user_bests_retention = 90        # OSU_USER_BESTS_RETENTION, in days
+//! user_bests_per_user = 1000       # OSU_USER_BESTS_PER_USER
+//! asset_cache = "/data/assets"     # OSU_ASSET_CACHE, covers are linked instead of attached if unset
+//! asset_cache_size_mb = 256        # OSU_ASSET_CACHE_SIZE_MB
+//! mod_emojis = "HD=<:hd:1234>"     # OSU_MOD_EMOJIS, comma-separated, `v2` for the lazer flag
+//! ordr_api_key = "..."             # ORDR_API_KEY, for the replay renders on o!rdr
+//! render_assets = "/data/render"   # OSU_RENDER_ASSETS, images are drawn instead of embeds if set
+//!
+//! [osu.servers.gatari]             # private servers for `--server gatari`, besides ripple and akatsuki
+//! api_url = "https://..."          # where the osu! API v1 endpoints (`get_user`, ...) are
+//! website = "https://..."          # with the user profiles at /u/{id}
+//! avatars = "https://..."          # with the avatars at /{id}
+//! api_key = "..."                  # OSU_SERVER_GATARI_API_KEY, if the server needs one
+//!
+//! [twitch]                         # stream announcements, disabled unless both are set
+//! client_id = "..."                # TWITCH_CLIENT_ID
+//! client_secret = "..."            # TWITCH_CLIENT_SECRET
+//!
+//! [metrics]
+//! listen = "127.0.0.1:9100"        # METRICS_LISTEN, serves /metrics and /healthz if set
+//!
+//! [web]                            # the JSON standings of each server, for community websites
+//! listen = "0.0.0.0:8080"          # WEB_LISTEN, serves /guilds/{id}/... if set
+//! token = "..."                    # WEB_TOKEN, required with `listen`, sent as `Authorization: Bearer ...`
+//!
+//! [hooks]                          # the replies to links in messages
+//! cooldown = 60                    # HOOK_COOLDOWN, in seconds before the same link is replied to again
+//! max_responses = 10               # HOOK_MAX_RESPONSES, per channel per minute
+//!
+//! [cooldowns]                      # the limits of the expensive commands, as `uses/seconds`
+//! heavy = "2/60"                   # COOLDOWN_HEAVY, per user
+//! heavy_channel = "3/60"           # COOLDOWN_HEAVY_CHANNEL, per channel
+//! heavy_guild = "2/300"            # COOLDOWN_HEAVY_GUILD, per server
+//!
+//! [setup]                          # what to do when a part of the bot cannot be set up
+//! retries = 5                      # SETUP_RETRIES, for failures that may go away, e.g. an API being down
+//! degraded = true                  # SETUP_DEGRADED, run without the failed parts instead of exiting
+//!
+//! [features]                       # modules, all enabled unless switched off; see `y!modules`
+//! osu_announcer = false            # FEATURE_OSU_ANNOUNCER
+//! osu_hooks = false                # FEATURE_OSU_HOOKS
+//! ```
+use std::{
+    collections::BTreeMap, env::VarError, net::SocketAddr, path::PathBuf, str::FromStr,
+    time::Duration,
+};
+
+use serde::Deserialize;
+use youmubot_db_sql::PoolConfig;
+
+use crate::{bail, error, Result};
+
+/// The variable with the path to the configuration file.
+const CONFIG_VAR: &str = "YOUMUBOT_CONFIG";
+/// The configuration file used when [CONFIG_VAR] is not set, if it exists.
+const DEFAULT_CONFIG_PATH: &str = "youmubot.toml";
+/// The prefix of the variables switching modules on or off, e.g. `FEATURE_OSU_HOOKS=false`.
+const FEATURE_VAR_PREFIX: &str = "FEATURE_";
+/// The prefix of the variables overriding the cooldowns, e.g. `COOLDOWN_HEAVY=2/60`.
+const COOLDOWN_VAR_PREFIX: &str = "COOLDOWN_";
+
+/// The bot's configuration.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub discord: DiscordConfig,
+    pub database: DatabaseConfig,
+    pub osu: OsuConfig,
+    pub twitch: TwitchConfig,
+    pub metrics: MetricsConfig,
+    pub web: WebConfig,
+    pub hooks: HooksConfig,
+    pub cooldowns: CooldownsConfig,
+    pub setup: SetupConfig,
+    pub features: Features,
+}
+
+/// The Discord connection.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiscordConfig {
+    /// The bot's token.
+    pub token: String,
+    /// The command prefixes, unless a guild sets its own.
+    pub prefixes: Vec<String>,
+    /// The channel where command errors are reported, if any.
+    pub log_channel: Option<u64>,
+}
+
+impl Default for DiscordConfig {
+    fn default() -> Self {
+        Self {
+            token: String::new(),
+            prefixes: vec!["y!".to_owned(), "y2!".to_owned()],
+            log_channel: None,
+        }
+    }
+}
+
+/// The databases.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// The directory of the YAML databases.
+    pub path: PathBuf,
+    /// The SQLite database file, defaults to `youmubot.db` inside [Self::path].
+    pub sql_path: Option<PathBuf>,
+    /// The URL of the SQL database, either `sqlite:` or `postgres:`, used instead of [Self::sql_path] if set.
+    pub url: Option<String>,
+    /// The maximum number of open SQL connections.
+    pub max_connections: Option<u32>,
+    /// The number of SQL connections kept open when idle.
+    pub min_connections: Option<u32>,
+    /// How long to wait for an SQL connection before failing, in seconds.
+    pub acquire_timeout: Option<u64>,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("/data"),
+            sql_path: None,
+            url: None,
+            max_connections: None,
+            min_connections: None,
+            acquire_timeout: None,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    /// The SQLite database file.
+    pub fn sql_path(&self) -> PathBuf {
+        self.sql_path
+            .clone()
+            .unwrap_or_else(|| self.path.join("youmubot.db"))
+    }
+
+    /// The SQL connection pool settings.
+    pub fn pool(&self) -> PoolConfig {
+        let default = PoolConfig::default();
+        PoolConfig {
+            max_connections: self.max_connections.unwrap_or(default.max_connections),
+            min_connections: self.min_connections.unwrap_or(default.min_connections),
+            acquire_timeout: self
+                .acquire_timeout
+                .map(Duration::from_secs)
+                .unwrap_or(default.acquire_timeout),
+        }
+    }
+}
+
+/// The osu! API credentials and limits.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OsuConfig {
+    /// The osu! API v2 client ID.
+    pub client_id: Option<u64>,
+    /// The osu! API v2 client secret.
+    pub client_secret: Option<String>,
+    /// The number of API requests allowed per minute.
+    pub rate_limit: Option<u32>,
+    /// How long to wait for an API response, in seconds.
+    pub timeout: Option<u64>,
+    /// How many times an API request failing with a timeout or a server error is retried.
+    pub retries: Option<u32>,
+    /// The directory of the .osu files.
+    pub beatmap_files: Option<PathBuf>,
+    /// The number of beatmaps kept in memory.
+    pub beatmap_cache_size: Option<usize>,
+    /// How long beatmaps are kept in memory, in seconds.
+    pub beatmap_cache_ttl: Option<u64>,
+    /// How long the best scores of users are kept, in days.
+    pub user_bests_retention: Option<i64>,
+    /// The number of best scores kept per user.
+    pub user_bests_per_user: Option<i64>,
+    /// The directory of the beatmap covers attached to embeds, if any.
+    pub asset_cache: Option<PathBuf>,
+    /// The size limit of [Self::asset_cache], in megabytes.
+    pub asset_cache_size_mb: Option<u64>,
+    /// The emojis of the mods, in the `HD=<:hd:1234>,HR=<:hr:5678>` format.
+    pub mod_emojis: Option<String>,
+    /// The o!rdr verification key, for rendering replays.
+    pub ordr_api_key: Option<String>,
+    /// The directory of the assets to draw images with, if any.
+    pub render_assets: Option<PathBuf>,
+    /// The private servers, by name.
+    pub servers: BTreeMap<String, OsuServerConfig>,
+}
+
+/// A private osu! server, speaking the osu! API v1.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OsuServerConfig {
+    /// The base of the API.
+    pub api_url: String,
+    /// The website, with the user profiles at `/u/{id}`.
+    pub website: String,
+    /// The avatar host, with the avatars at `/{id}`.
+    pub avatars: String,
+    /// The API key, if the server needs one.
+    pub api_key: Option<String>,
+}
+
+/// The Twitch API credentials.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TwitchConfig {
+    /// The Twitch application's client ID.
+    pub client_id: Option<String>,
+    /// The Twitch application's client secret.
+    pub client_secret: Option<String>,
+}
+
+/// The metrics and health check endpoints.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// The address to serve the metrics and health checks at, if any.
+    pub listen: Option<SocketAddr>,
+}
+
+/// The JSON endpoints with the standings of each server.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebConfig {
+    /// The address to serve the endpoints at, if any.
+    pub listen: Option<SocketAddr>,
+    /// The token the requests must carry.
+    pub token: Option<String>,
+}
+
+/// The limits of the replies to links in messages.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HooksConfig {
+    /// How long before the same link is replied to again in a channel, in seconds.
+    pub cooldown: Option<u64>,
+    /// The number of replies allowed per channel per minute.
+    pub max_responses: Option<usize>,
+}
+
+/// The limits of the cooldown buckets, by name with `_` for `-`, as `uses/seconds`.
+/// Buckets not listed keep their default limits.
+/// See [crate::cooldown] for the buckets themselves.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct CooldownsConfig(BTreeMap<String, String>);
+
+impl CooldownsConfig {
+    /// The limits of the bucket, if they are set.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(&name.replace('-', "_")).map(|v| v.as_str())
+    }
+
+    /// The names of the buckets with limits set.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(|k| k.as_str())
+    }
+}
+
+/// What to do when a part of the bot cannot be set up.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SetupConfig {
+    /// How many times to retry a failure that may go away, e.g. an API being down.
+    pub retries: Option<u32>,
+    /// Whether to run without the features that failed to set up, instead of exiting.
+    /// The prelude is always required.
+    pub degraded: Option<bool>,
+}
+
+/// The modules switched on or off, by name. Modules not listed are enabled.
+/// See [crate::modules] for the modules themselves.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Features(BTreeMap<String, bool>);
+
+impl Features {
+    /// Whether the module is enabled.
+    pub fn enabled(&self, name: &str) -> bool {
+        self.0.get(name).copied().unwrap_or(true)
+    }
+
+    /// The names of the modules switched on or off.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(|k| k.as_str())
+    }
+}
+
+impl Config {
+    /// Read the configuration file, if there is one, then apply the environment variables over it.
+    pub fn load() -> Result<Self> {
+        let mut config = match std::env::var(CONFIG_VAR) {
+            Ok(path) => Self::from_file(&path)?,
+            Err(_) if std::path::Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(DEFAULT_CONFIG_PATH)?
+            }
+            Err(_) => Self::default(),
+        };
+        config.apply_env()?;
+        if config.discord.token.is_empty() {
+            bail!("Please set discord.token or TOKEN as the Discord Bot's token to be used.");
+        }
+        Ok(config)
+    }
+
+    /// Read the configuration file, without the environment variables.
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| error!("cannot read the configuration at {}: {}", path, e))?;
+        toml::from_str(&content).map_err(|e| error!("invalid configuration at {}: {}", path, e))
+    }
+
+    /// Override the settings with the environment variables that are set.
+    fn apply_env(&mut self) -> Result<()> {
+        let Config {
+            discord,
+            database,
+            osu,
+            twitch,
+            metrics,
+            web,
+            hooks,
+            cooldowns,
+            setup,
+            features,
+        } = self;
+        if let Some(v) = var::<String>("TOKEN")? {
+            discord.token = v;
+        }
+        if let Some(v) = var::<String>("PREFIX")? {
+            discord.prefixes = v.split(',').map(|v| v.trim().to_owned()).collect();
+        }
+        set(&mut discord.log_channel, "LOG_CHANNEL")?;
+
+        if let Some(v) = var("DBPATH")? {
+            database.path = v;
+        }
+        set(&mut database.sql_path, "SQLPATH")?;
+        set(&mut database.url, "DATABASE_URL")?;
+        set(&mut database.max_connections, "SQL_MAX_CONNECTIONS")?;
+        set(&mut database.min_connections, "SQL_MIN_CONNECTIONS")?;
+        set(&mut database.acquire_timeout, "SQL_ACQUIRE_TIMEOUT")?;
+
+        set(&mut osu.client_id, "OSU_API_CLIENT_ID")?;
+        set(&mut osu.client_secret, "OSU_API_CLIENT_SECRET")?;
+        set(&mut osu.rate_limit, "OSU_API_RATE_LIMIT")?;
+        set(&mut osu.timeout, "OSU_API_TIMEOUT")?;
+        set(&mut osu.retries, "OSU_API_RETRIES")?;
+        set(&mut osu.beatmap_files, "OSU_BEATMAP_FILES")?;
+        set(&mut osu.beatmap_cache_size, "OSU_BEATMAP_CACHE_SIZE")?;
+        set(&mut osu.beatmap_cache_ttl, "OSU_BEATMAP_CACHE_TTL")?;
+        set(&mut osu.user_bests_retention, "OSU_USER_BESTS_RETENTION")?;
+        set(&mut osu.user_bests_per_user, "OSU_USER_BESTS_PER_USER")?;
+        set(&mut osu.asset_cache, "OSU_ASSET_CACHE")?;
+        set(&mut osu.asset_cache_size_mb, "OSU_ASSET_CACHE_SIZE_MB")?;
+        set(&mut osu.mod_emojis, "OSU_MOD_EMOJIS")?;
+        set(&mut osu.ordr_api_key, "ORDR_API_KEY")?;
+        set(&mut osu.render_assets, "OSU_RENDER_ASSETS")?;
+        for (name, server) in osu.servers.iter_mut() {
+            set(
+                &mut server.api_key,
+                &format!("OSU_SERVER_{}_API_KEY", name.to_uppercase()),
+            )?;
+        }
+
+        set(&mut twitch.client_id, "TWITCH_CLIENT_ID")?;
+        set(&mut twitch.client_secret, "TWITCH_CLIENT_SECRET")?;
+
+        set(&mut metrics.listen, "METRICS_LISTEN")?;
+
+        set(&mut web.listen, "WEB_LISTEN")?;
+        set(&mut web.token, "WEB_TOKEN")?;
+
+        set(&mut hooks.cooldown, "HOOK_COOLDOWN")?;
+        set(&mut hooks.max_responses, "HOOK_MAX_RESPONSES")?;
+
+        set(&mut setup.retries, "SETUP_RETRIES")?;
+        set(&mut setup.degraded, "SETUP_DEGRADED")?;
+
+        for (name, _) in std::env::vars_os() {
+            // Not one of ours, as all our variables are ASCII.
+            let Ok(name) = name.into_string() else {
+                continue;
+            };
+            if let Some(module) = name.strip_prefix(FEATURE_VAR_PREFIX) {
+                if let Some(v) = var(&name)? {
+                    features.0.insert(module.to_lowercase(), v);
+                }
+            } else if let Some(bucket) = name.strip_prefix(COOLDOWN_VAR_PREFIX) {
+                if let Some(v) = var(&name)? {
+                    cooldowns.0.insert(bucket.to_lowercase(), v);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read and parse the environment variable, if it is set.
+fn var<T: FromStr>(name: &str) -> Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    match std::env::var(name) {
+        Ok(v) => v
+            .parse()
+            .map(Some)
+            .map_err(|e| error!("invalid {}: {}", name, e)),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(VarError::NotUnicode(_)) => bail!("invalid {}: not valid UTF-8", name),
+    }
+}
+
+/// Override the optional setting with the environment variable, if it is set.
+fn set<T: FromStr>(setting: &mut Option<T>, name: &str) -> Result<()>
+where
+    T::Err: std::fmt::Display,
+{
+    if let Some(v) = var(name)? {
+        *setting = Some(v);
+    }
+    Ok(())
+}
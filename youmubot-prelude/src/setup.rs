@@ -1,37 +1,137 @@
-use std::{path::Path, time::Duration};
+use std::{sync::Arc, time::Duration};
 
 use serenity::prelude::*;
 
-use crate::Env;
+use crate::{Config, Env, Error, OkPrint};
+
+/// Why a part of the bot could not be set up.
+///
+/// Setup functions return it instead of panicking, so that the binary can decide whether to
+/// retry, run without the failed part, or exit.
+#[derive(Debug, thiserror::Error)]
+pub enum SetupError {
+    /// A required setting is not set.
+    #[error("please set {0}")]
+    Missing(&'static str),
+    /// A setting is set to an invalid value. The message names the setting.
+    #[error("{0}")]
+    Invalid(Error),
+    /// A file or directory could not be opened or created.
+    #[error("cannot open {what}: {source}")]
+    Storage {
+        what: String,
+        #[source]
+        source: Error,
+    },
+    /// The SQL database could not be opened or migrated.
+    #[error("cannot set up the SQL database: {0}")]
+    Database(#[from] youmubot_db_sql::Error),
+    /// An external service could not be reached, or refused the bot.
+    #[error("cannot connect to {service}: {source}")]
+    Unavailable {
+        service: &'static str,
+        /// Whether the service may accept the bot later, e.g. it timed out rather than rejected the credentials.
+        transient: bool,
+        #[source]
+        source: Error,
+    },
+}
+
+impl SetupError {
+    /// Whether setting up again later may succeed.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            SetupError::Database(e) => e.is_busy(),
+            SetupError::Unavailable { transient, .. } => *transient,
+            _ => false,
+        }
+    }
+}
 
 /// Set up the prelude libraries.
 ///
-/// Panics on failure: Youmubot should *NOT* attempt to continue when this function fails.
-pub async fn setup_prelude(
-    db_path: impl AsRef<Path>,
-    sql_path: impl AsRef<Path>,
-    data: &mut TypeMap,
-) -> Env {
-    // Set up the announcer DB.
-    crate::announcer::AnnouncerChannels::insert_into(
-        data,
-        db_path.as_ref().join("announcers.yaml"),
-    )
-    .expect("Announcers DB set up");
+/// The SQL database is the one at `database.url` if set, otherwise the sqlite database at `database.sql_path`.
+///
+/// Youmubot cannot run without the prelude: the binary should not continue when this function fails.
+pub async fn setup_prelude(config: Config, data: &mut TypeMap) -> Result<Env, SetupError> {
+    // Set up the module registry.
+    let modules = crate::Modules::new(config.features.clone());
+    data.insert::<crate::Modules>(modules.clone());
+
+    // Set up the shutdown signal.
+    let shutdown = crate::Shutdown::new();
+    data.insert::<crate::Shutdown>(shutdown.clone());
+
+    // Set up the background task supervisor.
+    let supervisor = crate::Supervisor::new(shutdown.clone());
+    data.insert::<crate::Supervisor>(supervisor.clone());
+
+    // Set up the error reporter.
+    let errors = crate::ErrorReporter::new(
+        config
+            .discord
+            .log_channel
+            .map(serenity::model::id::ChannelId::new),
+    );
+    data.insert::<crate::ErrorReporter>(errors.clone());
+
+    // Set up the metrics and health checks, and their endpoints if configured.
+    let metrics = crate::Metrics::default();
+    data.insert::<crate::Metrics>(metrics.clone());
+    let health = crate::Health::default();
+    data.insert::<crate::Health>(health.clone());
+    if let Some(addr) = config.metrics.listen {
+        let metrics = metrics.clone();
+        let health = health.clone();
+        supervisor.spawn("status", move |shutdown| {
+            let metrics = metrics.clone();
+            let health = health.clone();
+            async move {
+                crate::status::serve(addr, metrics, health, shutdown)
+                    .await
+                    .pls_ok();
+            }
+        });
+    }
 
     // Set up the database
-    let sql_pool = youmubot_db_sql::connect(sql_path)
-        .await
-        .expect("SQL database set up");
+    let pool_config = config.database.pool();
+    let sql_pool = match &config.database.url {
+        Some(url) => youmubot_db_sql::connect_url(url, &pool_config).await,
+        None => youmubot_db_sql::connect(config.database.sql_path(), &pool_config).await,
+    }?;
+    health.register("database", {
+        let sql_pool = sql_pool.clone();
+        move || {
+            let sql_pool = sql_pool.clone();
+            async move { Ok(youmubot_db_sql::ping(&sql_pool).await?) }
+        }
+    });
+
+    // Set up the scheduled jobs.
+    let scheduler = crate::Scheduler::new(sql_pool.clone(), supervisor.clone());
+    data.insert::<crate::Scheduler>(scheduler.clone());
 
     // Set up the HTTP client.
     let http_client = reqwest::ClientBuilder::new()
         .connect_timeout(Duration::from_secs(5))
         .timeout(Duration::from_secs(60))
         .build()
-        .expect("Build be able to build HTTP client");
+        .map_err(|e| SetupError::Unavailable {
+            service: "the HTTP client's TLS backend",
+            transient: false,
+            source: e.into(),
+        })?;
     data.insert::<crate::HTTPClient>(http_client.clone());
 
+    // Set up the queue of outgoing announcements.
+    let send_queue = crate::SendQueue::default();
+    data.insert::<crate::SendQueue>(send_queue.clone());
+    metrics.collect(crate::send_queue::QUEUED_MESSAGES, {
+        let send_queue = send_queue.clone();
+        move || vec![(vec![], send_queue.queued() as f64)]
+    });
+
     // Set up the member cache.
     let member_cache = std::sync::Arc::new(crate::MemberCache::default());
     data.insert::<crate::MemberCache>(member_cache.clone());
@@ -39,11 +139,52 @@ pub async fn setup_prelude(
     // Set up the SQL client.
     data.insert::<crate::SQLClient>(sql_pool.clone());
 
+    // Set up the guild configuration.
+    let guild_config = crate::GuildConfig::new(sql_pool.clone());
+    data.insert::<crate::GuildConfig>(guild_config.clone());
+    guild_config.register(crate::command_filter::PREFIX);
+    guild_config.register(crate::command_filter::DISABLED_COMMANDS);
+    guild_config.register(crate::i18n::LANGUAGE);
+    guild_config.register(crate::hook::HOOK_SWITCHES);
+    guild_config.register(crate::announcer::ANNOUNCERS);
+
+    // Set up the announcer channels, moving the ones from the old announcer DB.
+    let announcers_path = config.database.path.join("announcers.yaml");
+    crate::announcer::import_legacy_channels(&guild_config, &announcers_path)
+        .await
+        .map_err(|e| SetupError::Storage {
+            what: announcers_path.display().to_string(),
+            source: e,
+        })?;
+
+    // Set up the hook switches.
+    let hooks = crate::HookConfig::new(sql_pool.clone(), guild_config.clone());
+    data.insert::<crate::HookConfig>(hooks.clone());
+    let hook_cooldown = crate::HookCooldown::from_config(&config.hooks);
+    data.insert::<crate::HookCooldown>(hook_cooldown.clone());
+
+    // Set up the languages.
+    let locales = crate::Locales::new(sql_pool.clone(), guild_config.clone());
+    data.insert::<crate::Locales>(locales.clone());
+
     let env = Env {
         http: http_client,
         sql: sql_pool,
         members: member_cache,
+        hooks,
+        hook_cooldown,
+        config: guild_config,
+        locales,
+        settings: Arc::new(config),
+        modules,
+        shutdown,
+        supervisor,
+        scheduler,
+        errors,
+        metrics,
+        health,
+        send_queue,
     };
 
-    env
+    Ok(env)
 }
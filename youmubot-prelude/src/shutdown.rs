@@ -0,0 +1,122 @@
+//! Graceful shutdown.
+//!
+//! When the bot is asked to stop, [Shutdown::trigger] tells background loops to stop at their next
+//! checkpoint, and [Shutdown::drain] waits for the work they marked as in-flight with [Shutdown::work]
+//! (e.g. announcements being sent) before the bot exits.
+use std::{
+    future::Future,
+    pin::pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use futures_util::future::{select, Either};
+use tokio::sync::{watch, Notify};
+
+use crate::TypeMapKey;
+
+/// The shutdown signal, shared by all background tasks.
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    signal: Arc<watch::Sender<bool>>,
+    work: Arc<InFlight>,
+}
+
+/// The number of in-flight pieces of work, and a notification when it drops to zero.
+#[derive(Debug, Default)]
+struct InFlight {
+    count: AtomicUsize,
+    done: Notify,
+}
+
+impl TypeMapKey for Shutdown {
+    type Value = Shutdown;
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (signal, _) = watch::channel(false);
+        Self {
+            signal: Arc::new(signal),
+            work: Arc::new(InFlight::default()),
+        }
+    }
+
+    /// Start shutting down.
+    pub fn trigger(&self) {
+        self.signal.send_replace(true);
+    }
+
+    /// Whether shutdown has started.
+    pub fn is_shutting_down(&self) -> bool {
+        *self.signal.borrow()
+    }
+
+    /// Wait until shutdown starts.
+    pub async fn wait(&self) {
+        let mut signal = self.signal.subscribe();
+        // The sender lives as long as `self`, so this cannot fail.
+        signal.wait_for(|v| *v).await.ok();
+    }
+
+    /// Run the future until it completes, or until shutdown starts, in which case it is dropped and `None` is returned.
+    pub async fn cancellable<F: Future>(&self, f: F) -> Option<F::Output> {
+        match select(pin!(f), pin!(self.wait())).await {
+            Either::Left((v, _)) => Some(v),
+            Either::Right(_) => None,
+        }
+    }
+
+    /// Sleep for the given duration. Returns `false` if shutdown started in the meantime.
+    pub async fn sleep(&self, duration: Duration) -> bool {
+        self.cancellable(tokio::time::sleep(duration))
+            .await
+            .is_some()
+    }
+
+    /// Mark some work as in-flight, until the returned guard is dropped.
+    /// [Shutdown::drain] waits for it to finish.
+    pub fn work(&self) -> WorkGuard {
+        self.work.count.fetch_add(1, Ordering::SeqCst);
+        WorkGuard(self.work.clone())
+    }
+
+    /// Wait for all in-flight work to finish, for at most `timeout`.
+    /// Returns `false` if some work was still going on after that.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        tokio::time::timeout(timeout, async {
+            loop {
+                // Listen before checking, to not miss the notification in between.
+                let done = self.work.done.notified();
+                if self.work.count.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                done.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+}
+
+/// Marks some work as in-flight while alive. See [Shutdown::work].
+#[derive(Debug)]
+#[must_use = "the work is only in-flight while the guard is alive"]
+pub struct WorkGuard(Arc<InFlight>);
+
+impl Drop for WorkGuard {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.done.notify_waiters();
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Coalesces concurrent requests for the same key, so that only one of them does the work.
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+use futures_util::future::{BoxFuture, FutureExt, Shared};
+
+use crate::{Error, Result};
+
+type Pending<V> = Shared<BoxFuture<'static, std::result::Result<V, Arc<Error>>>>;
+
+/// The error of a request, shared by all the callers that waited for it.
+/// Its source is the original error, so that it can still be found with [Error::chain].
+#[derive(Debug, Clone)]
+pub struct SharedError(pub Arc<Error>);
+
+impl std::fmt::Display for SharedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for SharedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.0.as_ref().as_ref())
+    }
+}
+
+/// A map of in-flight requests. Callers asking for a key that is already being fetched
+/// wait for that request instead of starting a new one.
+pub struct Singleflight<K, V> {
+    pending: Mutex<HashMap<K, Pending<V>>>,
+}
+
+impl<K, V> std::fmt::Debug for Singleflight<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<Singleflight>")
+    }
+}
+
+impl<K, V> Default for Singleflight<K, V> {
+    fn default() -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K, V> Singleflight<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone + Send + Sync + 'static,
+{
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run the request created by `f` for `key`, or join the one already in flight.
+    pub async fn run<F, Fut>(&self, key: K, f: F) -> Result<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V>> + Send + 'static,
+    {
+        let fut = {
+            let mut pending = self.pending.lock().unwrap();
+            match pending.get(&key) {
+                Some(fut) => fut.clone(),
+                None => {
+                    let fut = f().map(|r| r.map_err(Arc::new)).boxed().shared();
+                    pending.insert(key.clone(), fut.clone());
+                    fut
+                }
+            }
+        };
+        let result = fut.clone().await;
+        {
+            // Only remove our own request, a new one might have started since.
+            let mut pending = self.pending.lock().unwrap();
+            if pending.get(&key).is_some_and(|p| p.ptr_eq(&fut)) {
+                pending.remove(&key);
+            }
+        }
+        result.map_err(|e| SharedError(e).into())
+    }
+}
@@ -0,0 +1,73 @@
+//! A tiny HTTP server reporting the status of the bot, enabled with `metrics.listen`:
+//! - `GET /metrics`: the [metrics](crate::metrics), in the Prometheus text format.
+//! - `GET /healthz`: the [health checks](crate::health), one per line.
+//!   Responds with `503 Service Unavailable` if any check fails.
+use std::net::SocketAddr;
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+use crate::{health::Health, Metrics, Result, Shutdown};
+
+/// Serve the status endpoints at `http://<addr>/`, until shutdown.
+pub async fn serve(
+    addr: SocketAddr,
+    metrics: Metrics,
+    health: Health,
+    shutdown: Shutdown,
+) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!("serving metrics and health checks at http://{}/", addr);
+    while let Some(accepted) = shutdown.cancellable(listener.accept()).await {
+        let (mut stream, _) = match accepted {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!("status: cannot accept connection: {}", e);
+                continue;
+            }
+        };
+        let metrics = metrics.clone();
+        let health = health.clone();
+        tokio::spawn(async move {
+            // Only the request line matters.
+            let mut buf = [0u8; 1024];
+            let n = stream.read(&mut buf).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let response = match request.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+                ["GET", "/metrics"] => response("200 OK", &metrics.render()),
+                ["GET", "/healthz"] => {
+                    let checks = health.check().await;
+                    let healthy = checks.iter().all(|(_, error)| error.is_none());
+                    let body = checks
+                        .into_iter()
+                        .map(|(name, error)| match error {
+                            None => format!("{} ok\n", name),
+                            Some(e) => format!("{} failing: {}\n", name, e),
+                        })
+                        .collect::<String>();
+                    let status = if healthy {
+                        "200 OK"
+                    } else {
+                        "503 Service Unavailable"
+                    };
+                    response(status, &body)
+                }
+                _ => response("404 Not Found", ""),
+            };
+            stream.write_all(response.as_bytes()).await.ok();
+            stream.shutdown().await.ok();
+        });
+    }
+    Ok(())
+}
+
+fn response(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
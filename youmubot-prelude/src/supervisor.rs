@@ -0,0 +1,147 @@
+//! Supervised background tasks.
+//!
+//! Long-running tasks (announcers, pollers, ...) are started with [Supervisor::spawn] instead of a bare
+//! `tokio::spawn`: when they panic, the panic is logged and the task is restarted with backoff,
+//! and their health can be inspected with the `tasks` command.
+use std::{future::Future, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use futures_util::FutureExt;
+
+use crate::{Shutdown, TypeMapKey};
+
+/// The wait before restarting a task after its first panic, doubled on each consecutive panic.
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+/// The longest wait before restarting a task.
+const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+/// A task running for this long without panicking is considered healthy again, resetting the backoff.
+const STABLE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// What a supervised task is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskState {
+    Running,
+    /// The task panicked, and is waiting to be restarted.
+    Restarting,
+    /// The task returned, or was stopped by shutdown.
+    Stopped,
+}
+
+/// The health of a supervised task.
+#[derive(Debug, Clone)]
+pub struct TaskHealth {
+    pub state: TaskState,
+    /// When the task was (re)started last.
+    pub started_at: DateTime<Utc>,
+    /// The number of times the task was restarted.
+    pub restarts: u32,
+    /// When the task last panicked, and with which message.
+    pub last_panic: Option<(DateTime<Utc>, String)>,
+}
+
+/// Runs background tasks, restarting them when they panic.
+#[derive(Debug, Clone)]
+pub struct Supervisor {
+    tasks: Arc<DashMap<&'static str, TaskHealth>>,
+    shutdown: Shutdown,
+}
+
+impl TypeMapKey for Supervisor {
+    type Value = Supervisor;
+}
+
+impl Supervisor {
+    pub fn new(shutdown: Shutdown) -> Self {
+        Self {
+            tasks: Arc::new(DashMap::new()),
+            shutdown,
+        }
+    }
+
+    /// Run the task in the background, until it returns or shutdown starts.
+    /// `task` is called with the shutdown signal to start the task, and again to restart it after a panic.
+    pub fn spawn<F, Fut>(&self, name: &'static str, task: F)
+    where
+        F: Fn(Shutdown) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let tasks = self.tasks.clone();
+        let shutdown = self.shutdown.clone();
+        tokio::spawn(async move {
+            let mut consecutive_panics: u32 = 0;
+            loop {
+                let started_at = Utc::now();
+                tasks
+                    .entry(name)
+                    .and_modify(|h| {
+                        h.state = TaskState::Running;
+                        h.started_at = started_at;
+                    })
+                    .or_insert_with(|| TaskHealth {
+                        state: TaskState::Running,
+                        started_at,
+                        restarts: 0,
+                        last_panic: None,
+                    });
+                let panic = match std::panic::AssertUnwindSafe(task(shutdown.clone()))
+                    .catch_unwind()
+                    .await
+                {
+                    Ok(()) => None,
+                    Err(e) => Some(panic_message(e)),
+                };
+                let Some(panic) = panic else {
+                    break;
+                };
+                let ran_for = (Utc::now() - started_at).to_std().unwrap_or_default();
+                if ran_for >= STABLE_AFTER {
+                    consecutive_panics = 0;
+                }
+                let backoff = MIN_BACKOFF
+                    .saturating_mul(1 << consecutive_panics.min(16))
+                    .min(MAX_BACKOFF);
+                consecutive_panics += 1;
+                tracing::error!(
+                    task = name,
+                    "task panicked: {}; restarting in {:?}",
+                    panic,
+                    backoff
+                );
+                if let Some(mut h) = tasks.get_mut(name) {
+                    h.state = TaskState::Restarting;
+                    h.restarts += 1;
+                    h.last_panic = Some((Utc::now(), panic));
+                }
+                if !shutdown.sleep(backoff).await {
+                    break;
+                }
+            }
+            if let Some(mut h) = tasks.get_mut(name) {
+                h.state = TaskState::Stopped;
+            }
+        });
+    }
+
+    /// The health of all tasks, sorted by name.
+    pub fn health(&self) -> Vec<(&'static str, TaskHealth)> {
+        let mut all = self
+            .tasks
+            .iter()
+            .map(|h| (*h.key(), h.value().clone()))
+            .collect::<Vec<_>>();
+        all.sort_by_key(|(name, _)| *name);
+        all
+    }
+}
+
+/// The message of a caught panic.
+fn panic_message(e: Box<dyn std::any::Any + Send>) -> String {
+    match e.downcast::<String>() {
+        Ok(s) => *s,
+        Err(e) => match e.downcast::<&'static str>() {
+            Ok(s) => s.to_string(),
+            Err(_) => "<unknown panic>".to_owned(),
+        },
+    }
+}
@@ -0,0 +1,322 @@
+//! A fake Discord, to test code sending messages without connecting to Discord.
+//!
+//! [MockDiscord] serves the Discord HTTP API on a local port and records the requests sent to it.
+//! Its [Http] client, or [CacheAndHttp], is given to the code under test,
+//! and incoming messages and button clicks are made up with [MockDiscord::message] and [MockDiscord::click].
+//!
+//! serenity's `Context` can only be created by a gateway connection, so the code under test
+//! should take `impl CacheHttp` rather than a `Context`, and the [Env] made by [env].
+use std::{
+    net::SocketAddr,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use serde_json::{json, Value};
+use serenity::{
+    all::ComponentInteraction,
+    cache::Cache,
+    http::{Http, HttpBuilder},
+    model::{
+        channel::Message,
+        id::{ApplicationId, ChannelId, UserId},
+    },
+    prelude::TypeMap,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+use crate::{announcer::CacheAndHttp, Config, Env, Error, Result};
+
+/// The id of the bot's user on the mock.
+pub const BOT_ID: UserId = UserId::new(1);
+
+/// Set up the prelude with the default configuration, on a new SQLite database in `dir`.
+pub async fn env(dir: &Path) -> Result<Env> {
+    let mut config = Config::default();
+    config.database.path = dir.to_owned();
+    std::fs::create_dir_all(dir)?;
+    Ok(crate::setup::setup_prelude(config, &mut TypeMap::new()).await?)
+}
+
+/// A request received by the mock.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    /// The path of the request, e.g. `/api/v10/channels/10/messages`.
+    pub path: String,
+    /// The JSON body of the request, `null` if there is none.
+    pub body: Value,
+}
+
+/// A message sent by the bot.
+#[derive(Debug, Clone)]
+pub struct Sent {
+    pub channel_id: ChannelId,
+    pub content: String,
+    /// The embeds of the message, in the JSON format of the Discord API.
+    pub embeds: Vec<Value>,
+}
+
+/// A fake Discord HTTP API, shut down when dropped.
+pub struct MockDiscord {
+    http: Arc<Http>,
+    cache: Arc<Cache>,
+    state: Arc<State>,
+    server: JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct State {
+    requests: Mutex<Vec<Request>>,
+    next_id: AtomicU64,
+}
+
+impl MockDiscord {
+    /// Start serving the fake API on a random local port.
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(State {
+            next_id: AtomicU64::new(1000),
+            ..Default::default()
+        });
+        let server = tokio::spawn({
+            let state = state.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let state = state.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = handle(stream, &state).await {
+                            tracing::warn!("mock discord: {}", e);
+                        }
+                    });
+                }
+            }
+        });
+        let http = HttpBuilder::new("mock-token")
+            .proxy(format!("http://{}", addr))
+            .ratelimiter_disabled(true)
+            .application_id(ApplicationId::new(BOT_ID.get()))
+            .build();
+        Ok(Self {
+            http: Arc::new(http),
+            cache: Arc::new(Cache::new()),
+            state,
+            server,
+        })
+    }
+
+    /// The HTTP client talking to the mock.
+    pub fn http(&self) -> Arc<Http> {
+        self.http.clone()
+    }
+
+    /// The HTTP client talking to the mock, with an empty cache.
+    pub fn cache_and_http(&self) -> CacheAndHttp {
+        CacheAndHttp(self.cache.clone(), self.http.clone())
+    }
+
+    /// All requests received so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// The messages sent so far, in order.
+    pub fn sent(&self) -> Vec<Sent> {
+        self.requests()
+            .into_iter()
+            .filter(|r| r.method == "POST")
+            .filter_map(|r| {
+                let channel_id = message_channel(&r.path)?;
+                Some(Sent {
+                    channel_id,
+                    content: r.body["content"].as_str().unwrap_or_default().to_owned(),
+                    embeds: r.body["embeds"].as_array().cloned().unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    /// Make up a message sent by `author` to `channel`.
+    pub fn message(
+        &self,
+        channel: ChannelId,
+        author: UserId,
+        content: impl Into<String>,
+    ) -> Result<Message> {
+        let id = self.state.next_id();
+        let message = message_json(id, channel, author, &json!({ "content": content.into() }));
+        serde_json::from_value(message).map_err(Error::from)
+    }
+
+    /// Make up a click by `user` on the button `custom_id` of the message.
+    pub fn click(
+        &self,
+        message: &Message,
+        user: UserId,
+        custom_id: impl Into<String>,
+    ) -> Result<ComponentInteraction> {
+        let interaction = json!({
+            "id": self.state.next_id().to_string(),
+            "application_id": BOT_ID.to_string(),
+            "type": 3,
+            "data": { "custom_id": custom_id.into(), "component_type": 2 },
+            "channel_id": message.channel_id.to_string(),
+            "user": user_json(user),
+            "token": "mock-interaction-token",
+            "version": 1,
+            "message": serde_json::to_value(message)?,
+            "locale": "en-US",
+            "entitlements": [],
+        });
+        serde_json::from_value(interaction).map_err(Error::from)
+    }
+}
+
+impl Drop for MockDiscord {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+impl State {
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+/// The channel of a `/channels/{id}/messages[/{id}]` path.
+fn message_channel(path: &str) -> Option<ChannelId> {
+    let rest = path.split("/channels/").nth(1)?;
+    let mut parts = rest.split('/');
+    let channel = parts.next()?.parse().ok()?;
+    (parts.next() == Some("messages")).then(|| ChannelId::new(channel))
+}
+
+/// Handle one request, answering with what Discord would.
+async fn handle(mut stream: TcpStream, state: &State) -> Result<()> {
+    let mut buf = Vec::new();
+    let header_end = loop {
+        let mut chunk = [0u8; 4096];
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..header_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_owned();
+    let path = request_line.next().unwrap_or_default().to_owned();
+    let mut content_length = 0;
+    let mut content_type = String::new();
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            match name.trim().to_ascii_lowercase().as_str() {
+                "content-length" => content_length = value.trim().parse()?,
+                "content-type" => content_type = value.trim().to_owned(),
+                _ => (),
+            }
+        }
+    }
+    while buf.len() < header_end + content_length {
+        let mut chunk = [0u8; 4096];
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let body = parse_body(&content_type, &buf[header_end..]);
+
+    let response = match (method.as_str(), message_channel(&path)) {
+        ("POST", Some(channel)) => Some(message_json(state.next_id(), channel, BOT_ID, &body)),
+        ("PATCH", Some(channel)) => {
+            let id = path.rsplit('/').next().and_then(|v| v.parse().ok());
+            id.map(|id| message_json(id, channel, BOT_ID, &body))
+        }
+        _ => None,
+    };
+    state
+        .requests
+        .lock()
+        .unwrap()
+        .push(Request { method, path, body });
+
+    let (status, body) = match response {
+        Some(v) => ("200 OK", v),
+        None => (
+            "404 Not Found",
+            json!({ "message": "Not handled by the mock", "code": 0 }),
+        ),
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// The JSON body of a request. Messages with files send it as the `payload_json` part of a form.
+fn parse_body(content_type: &str, body: &[u8]) -> Value {
+    let body = String::from_utf8_lossy(body);
+    if !content_type.starts_with("multipart/") {
+        return serde_json::from_str(&body).unwrap_or(Value::Null);
+    }
+    body.split("name=\"payload_json\"")
+        .nth(1)
+        .and_then(|part| part.split_once("\r\n\r\n"))
+        .and_then(|(_, rest)| rest.split("\r\n--").next())
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or(Value::Null)
+}
+
+fn user_json(id: UserId) -> Value {
+    json!({
+        "id": id.to_string(),
+        "username": if id == BOT_ID { "youmubot".to_owned() } else { format!("user{}", id) },
+        "discriminator": "0000",
+        "global_name": null,
+        "avatar": null,
+        "bot": id == BOT_ID,
+    })
+}
+
+/// A message as returned by Discord, with the content, embeds and components of `body`.
+fn message_json(id: u64, channel: ChannelId, author: UserId, body: &Value) -> Value {
+    json!({
+        "id": id.to_string(),
+        "channel_id": channel.to_string(),
+        "author": user_json(author),
+        "content": body["content"].as_str().unwrap_or_default(),
+        "timestamp": "2024-01-01T00:00:00.000000+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": body["embeds"].as_array().cloned().unwrap_or_default(),
+        "components": body["components"].as_array().cloned().unwrap_or_default(),
+        "reactions": [],
+        "pinned": false,
+        "type": 0,
+        "flags": 0,
+    })
+}
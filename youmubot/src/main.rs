@@ -1,21 +1,43 @@
-use dotenv::var;
 use serenity::{
-    framework::standard::{
-        macros::hook, BucketBuilder, CommandResult, Configuration, DispatchError, StandardFramework,
+    client::FullEvent,
+    framework::{
+        standard::{
+            macros::hook, BucketBuilder, CommandGroup, CommandResult, Configuration, DispatchError,
+            Reason, StandardFramework,
+        },
+        Framework,
     },
+    gateway::ConnectionStage,
     model::{
+        application::Interaction,
         channel::{Channel, Message},
+        event::{GuildMemberUpdateEvent, MessageUpdateEvent},
         gateway,
+        guild::{Guild, Member, UnavailableGuild},
+        id::GuildId,
         permissions::Permissions,
+        user::User,
     },
 };
 
-use youmubot_prelude::announcer::AnnouncerHandler;
+use youmubot_prelude::announcer::{AnnouncerHandler, CacheAndHttp};
+use youmubot_prelude::application::{ApplicationCommands, Autocompletes};
+use youmubot_prelude::command_filter;
+use youmubot_prelude::cooldown;
+use youmubot_prelude::errors::ErrorSource;
+use youmubot_prelude::hook::HookOutcome;
+use youmubot_prelude::metrics;
+use youmubot_prelude::permissions;
 use youmubot_prelude::*;
 
+use tracing::Instrument;
+
 struct Handler {
     hooks: Vec<RwLock<Box<dyn Hook>>>,
     ready_hooks: Vec<fn(&Context) -> CommandResult>,
+    hook_stats: HookStats,
+    commands: ApplicationCommands,
+    autocompletes: Autocompletes,
 }
 
 impl Handler {
@@ -23,6 +45,9 @@ impl Handler {
         Handler {
             hooks: vec![],
             ready_hooks: vec![],
+            hook_stats: HookStats::default(),
+            commands: ApplicationCommands::default(),
+            autocompletes: Autocompletes::default(),
         }
     }
 
@@ -33,14 +58,88 @@ impl Handler {
     fn push_ready_hook(&mut self, f: fn(&Context) -> CommandResult) {
         self.ready_hooks.push(f);
     }
+
+    /// Run the hooks on the message, skipping the ones switched off in its channel.
+    /// For edited messages, `edited` holds the old message, if it is known.
+    ///
+    /// Each hook runs in isolation: its errors and panics are logged and counted,
+    /// and do not affect the other hooks.
+    async fn run_hooks(&self, ctx: &Context, message: &Message, edited: Option<Option<&Message>>) {
+        if shutting_down(ctx).await {
+            return;
+        }
+        let config = ctx.data.read().await.get::<HookConfig>().unwrap().clone();
+        let config = &config;
+        let stats = &self.hook_stats;
+        self.hooks
+            .iter()
+            .map(|hook| async move {
+                let mut h = hook.write().await;
+                let name = h.name().unwrap_or("unnamed");
+                if stats.is_disabled(name) {
+                    return;
+                }
+                // Hooks can be switched off per channel or guild.
+                if !config
+                    .enabled(message.guild_id, message.channel_id, name)
+                    .await
+                {
+                    return;
+                }
+                let span = tracing::info_span!(
+                    "hook",
+                    hook = name,
+                    message_id = message.id.get(),
+                    channel_id = message.channel_id.get(),
+                    edited = edited.is_some(),
+                );
+                let call = async {
+                    match edited {
+                        None => h.call(ctx, message).await,
+                        Some(old) => h.call_edit(ctx, old, message).await,
+                    }
+                }
+                .instrument(span.clone());
+                let outcome = match std::panic::AssertUnwindSafe(call).catch_unwind().await {
+                    Ok(Ok(())) => HookOutcome::Ok,
+                    // The message itself was at fault, e.g. a malformed attachment.
+                    Ok(Err(e)) if e.downcast_ref::<InputError>().is_some() => {
+                        tracing::debug!(parent: &span, "hook rejected the message: {}", e);
+                        HookOutcome::InputError
+                    }
+                    Ok(Err(e)) => {
+                        tracing::warn!(parent: &span, "hook failed: {}", e);
+                        HookOutcome::Error
+                    }
+                    Err(_) => {
+                        tracing::error!(parent: &span, "hook panicked");
+                        HookOutcome::Panic
+                    }
+                };
+                if stats.record(name, outcome) {
+                    tracing::error!(
+                        parent: &span,
+                        "hook failed too many times in a row and is disabled until re-enabled"
+                    );
+                }
+            })
+            .collect::<stream::FuturesUnordered<_>>()
+            .collect::<()>()
+            .await;
+    }
 }
 
 /// Environment to be passed into the framework
 #[derive(Debug, Clone)]
 struct Env {
     prelude: youmubot_prelude::Env,
+    /// Whether core was set up. Features that failed to set up are left out when running degraded.
+    #[cfg(feature = "core")]
+    core: bool,
     #[cfg(feature = "osu")]
-    osu: youmubot_osu::discord::OsuEnv,
+    osu: Option<youmubot_osu::discord::OsuEnv>,
+    #[cfg(feature = "codeforces")]
+    codeforces: bool,
 }
 
 impl AsRef<youmubot_prelude::Env> for Env {
@@ -49,12 +148,6 @@ impl AsRef<youmubot_prelude::Env> for Env {
     }
 }
 
-impl AsRef<youmubot_osu::discord::OsuEnv> for Env {
-    fn as_ref(&self) -> &youmubot_osu::discord::OsuEnv {
-        &self.osu
-    }
-}
-
 impl TypeMapKey for Env {
     type Value = Env;
 }
@@ -62,35 +155,99 @@ impl TypeMapKey for Env {
 #[async_trait]
 impl EventHandler for Handler {
     async fn message(&self, ctx: Context, message: Message) {
-        self.hooks
-            .iter()
-            .map(|hook| {
-                let ctx = ctx.clone();
-                let message = message.clone();
-                hook.write()
-                    .then(|mut h| async move { h.call(&ctx, &message).await })
-            })
-            .collect::<stream::FuturesUnordered<_>>()
-            .for_each(|v| async move {
-                if let Err(e) = v {
-                    eprintln!("{}", e)
-                }
-            })
+        self.run_hooks(&ctx, &message, None).await;
+    }
+
+    async fn message_update(
+        &self,
+        ctx: Context,
+        old_if_available: Option<Message>,
+        new: Option<Message>,
+        event: MessageUpdateEvent,
+    ) {
+        // Only edits of the content matter to the hooks.
+        if event.content.is_none() {
+            return;
+        }
+        let new = match new {
+            Some(new) => new,
+            None => match event.channel_id.message(&ctx, event.id).await.pls_ok() {
+                Some(new) => new,
+                None => return,
+            },
+        };
+        self.run_hooks(&ctx, &new, Some(old_if_available.as_ref()))
             .await;
     }
 
+    async fn guild_member_addition(&self, ctx: Context, new_member: Member) {
+        members_of(&ctx).await.update_member(new_member).await;
+    }
+
+    async fn guild_member_update(
+        &self,
+        ctx: Context,
+        _old_if_available: Option<Member>,
+        new: Option<Member>,
+        _event: GuildMemberUpdateEvent,
+    ) {
+        if let Some(new) = new {
+            members_of(&ctx).await.update_member(new).await;
+        }
+    }
+
+    async fn guild_member_removal(
+        &self,
+        ctx: Context,
+        guild_id: GuildId,
+        user: User,
+        _member_data_if_available: Option<Member>,
+    ) {
+        members_of(&ctx)
+            .await
+            .remove_member(guild_id, user.id)
+            .await;
+    }
+
+    async fn guild_delete(&self, ctx: Context, incomplete: UnavailableGuild, _full: Option<Guild>) {
+        // The guild is only gone if it is not just unavailable.
+        if !incomplete.unavailable {
+            members_of(&ctx).await.invalidate_guild(incomplete.id);
+        }
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        if shutting_down(&ctx).await {
+            return;
+        }
+        if !self.commands.dispatch(&ctx, &interaction).await {
+            self.autocompletes.dispatch(&ctx, &interaction).await;
+        }
+    }
+
     async fn ready(&self, ctx: Context, ready: gateway::Ready) {
         // Start ReactionWatchers for community.
+        // The core feature may have failed to set up, see [SetupPolicy].
         #[cfg(feature = "core")]
-        ctx.data
+        if let Some(watchers) = ctx
+            .data
             .read()
             .await
             .get::<youmubot_core::community::ReactionWatchers>()
-            .unwrap()
-            .init(&ctx)
-            .await;
+        {
+            watchers.init(&ctx).await;
+        }
         println!("{} is connected!", ready.user.name);
 
+        // Register the application commands.
+        self.commands.register_all(&ctx).await.pls_ok();
+
+        // Move the channel hook switches of the old settings table, now that the channels can be looked up.
+        let hooks = ctx.data.read().await.get::<HookConfig>().cloned();
+        if let Some(hooks) = hooks {
+            hooks.import_channel_switches(&ctx).await.pls_ok();
+        }
+
         for f in &self.ready_hooks {
             f(&ctx).pls_ok();
         }
@@ -108,81 +265,229 @@ async fn is_not_channel_mod(ctx: &Context, msg: &Message) -> bool {
     }
 }
 
+/// The standard framework, handling each message in a span.
+/// The span's `command` is filled in by [before_hook] if the message is a command.
+struct TracedFramework(StandardFramework);
+
+#[async_trait]
+impl Framework for TracedFramework {
+    async fn init(&mut self, client: &Client) {
+        Framework::init(&mut self.0, client).await
+    }
+
+    async fn dispatch(&self, ctx: Context, event: FullEvent) {
+        let span = match &event {
+            FullEvent::Message { new_message } => tracing::info_span!(
+                "message",
+                message_id = new_message.id.get(),
+                channel_id = new_message.channel_id.get(),
+                user_id = new_message.author.id.get(),
+                command = tracing::field::Empty,
+            ),
+            _ => tracing::Span::none(),
+        };
+        Framework::dispatch(&self.0, ctx, event)
+            .instrument(span)
+            .await
+    }
+}
+
+/// The delay before the first retry of a failed setup, doubled on each retry.
+const SETUP_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+/// The longest delay between two retries of a failed setup.
+const MAX_SETUP_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(300);
+/// How many times a failed setup is retried, unless configured.
+const DEFAULT_SETUP_RETRIES: u32 = 3;
+
+/// What to do when a feature fails to set up, from the `[setup]` configuration.
+///
+/// Failures that may go away are retried with backoff. Then, the bot either runs without the feature
+/// (its commands and hooks are left out) if it runs degraded, or exits.
+#[derive(Debug, Clone, Copy)]
+struct SetupPolicy {
+    retries: u32,
+    degraded: bool,
+}
+
+impl SetupPolicy {
+    fn new(config: &youmubot_prelude::settings::SetupConfig) -> Self {
+        Self {
+            retries: config.retries.unwrap_or(DEFAULT_SETUP_RETRIES),
+            degraded: config.degraded.unwrap_or(false),
+        }
+    }
+
+    /// Handle a failed attempt at setting up `feature`, returning whether to try again.
+    /// Waits before retrying, and exits if the bot cannot run without the feature.
+    async fn failed(&self, feature: &str, error: &SetupError, attempt: &mut u32) -> bool {
+        if error.is_transient() && *attempt < self.retries {
+            let delay =
+                (SETUP_RETRY_DELAY * 2u32.pow((*attempt).min(10))).min(MAX_SETUP_RETRY_DELAY);
+            *attempt += 1;
+            tracing::warn!(
+                "cannot set up {}: {}. Retrying in {:?} ({}/{})",
+                feature,
+                error,
+                delay,
+                attempt,
+                self.retries
+            );
+            tokio::time::sleep(delay).await;
+            return true;
+        }
+        if !self.degraded {
+            self.exit(feature, error);
+        }
+        tracing::error!("cannot set up {}: {}. Running without it.", feature, error);
+        false
+    }
+
+    /// Exit, as `feature` cannot be set up.
+    fn exit(&self, feature: &str, error: &SetupError) -> ! {
+        tracing::error!("cannot set up {}: {}", feature, error);
+        std::process::exit(1)
+    }
+}
+
+/// Run the setup expression of a feature according to the [SetupPolicy],
+/// evaluating to `None` if the bot runs without the feature.
+macro_rules! setup_feature {
+    ($policy:expr, $feature:expr, $setup:expr) => {{
+        let mut attempt = 0;
+        loop {
+            match $setup {
+                Ok(v) => break Some(v),
+                Err(e) => {
+                    if !$policy.failed($feature, &e, &mut attempt).await {
+                        break None;
+                    }
+                }
+            }
+        }
+    }};
+}
+
 #[tokio::main]
 async fn main() {
-    env_logger::init();
+    let log_filter = LogFilter::init();
     // Setup dotenv
     if let Ok(path) = dotenv::dotenv() {
         println!("Loaded dotenv from {:?}", path);
     }
+    let config = Config::load().unwrap_or_else(|e| {
+        tracing::error!("cannot load the configuration: {:#}", e);
+        std::process::exit(1)
+    });
+    let policy = SetupPolicy::new(&config.setup);
+    let cooldowns = cooldown::from_config(&config.cooldowns).unwrap_or_else(|e| {
+        tracing::error!("cannot load the configuration: {:#}", e);
+        std::process::exit(1)
+    });
 
     let mut handler = Handler::new();
-    #[cfg(feature = "core")]
-    handler.push_ready_hook(youmubot_core::ready_hook);
-    // Set up hooks
-    #[cfg(feature = "osu")]
-    {
-        handler.push_hook(youmubot_osu::discord::hook);
-        handler.push_hook(youmubot_osu::discord::dot_osu_hook);
-    }
-    #[cfg(feature = "codeforces")]
-    handler.push_hook(youmubot_cf::InfoHook);
 
     // Collect the token
-    let token = var("TOKEN").expect("Please set TOKEN as the Discord Bot's token to be used.");
+    let token = config.discord.token.clone();
+    let prefixes = config.discord.prefixes.clone();
 
     // Data to be put into context
     let mut data = TypeMap::new();
+    data.insert::<HookStats>(handler.hook_stats.clone());
+    data.insert::<LogFilter>(log_filter);
 
     // Set up announcer handler
     let mut announcers = AnnouncerHandler::new();
 
     // Setup each package starting from the prelude.
     let env = {
-        let db_path = var("DBPATH")
-            .map(std::path::PathBuf::from)
-            .unwrap_or_else(|e| {
-                println!("No DBPATH set up ({:?}), using `/data`", e);
-                std::path::PathBuf::from("/data")
-            });
-        let sql_path = var("SQLPATH")
-            .map(std::path::PathBuf::from)
-            .unwrap_or_else(|e| {
-                let res = db_path.join("youmubot.db");
-                println!("No SQLPATH set up ({:?}), using `{:?}`", e, res);
-                res
-            });
-        let prelude = setup::setup_prelude(&db_path, sql_path, &mut data).await;
+        let db_path = config.database.path.clone();
+        tracing::info!("using the databases at `{:?}`", db_path);
+        let prelude = setup::setup_prelude(config, &mut data)
+            .await
+            .unwrap_or_else(|e| policy.exit("the prelude", &e));
         // Setup core
         #[cfg(feature = "core")]
-        youmubot_core::setup(&db_path, &mut data).expect("Setup db should succeed");
+        let core = setup_feature!(policy, "core", youmubot_core::setup(&db_path, &mut data));
         // osu!
         #[cfg(feature = "osu")]
-        let osu = youmubot_osu::discord::setup(&mut data, prelude.clone(), &mut announcers)
-            .await
-            .expect("osu! is initialized");
+        let osu = setup_feature!(
+            policy,
+            "osu!",
+            youmubot_osu::discord::setup(&mut data, prelude.clone(), &mut announcers).await
+        );
         // codeforces
         #[cfg(feature = "codeforces")]
-        youmubot_cf::setup(&db_path, &mut data, &mut announcers).await;
+        let codeforces = setup_feature!(
+            policy,
+            "codeforces",
+            youmubot_cf::setup(&db_path, &mut data, &mut announcers).await
+        );
 
         Env {
             prelude,
+            #[cfg(feature = "core")]
+            core: core.is_some(),
             #[cfg(feature = "osu")]
             osu,
+            #[cfg(feature = "codeforces")]
+            codeforces: codeforces.is_some(),
         }
     };
 
-    data.insert::<Env>(env);
-
     #[cfg(feature = "core")]
-    println!("Core enabled.");
+    if env.core {
+        handler.push_ready_hook(youmubot_core::ready_hook);
+    }
+    // Set up application commands
+    #[cfg(feature = "osu")]
+    if env.osu.is_some() {
+        handler
+            .commands
+            .register(youmubot_osu::discord::ProfileCommand);
+        handler
+            .commands
+            .register(youmubot_osu::discord::ExpandBeatmapCommand);
+        handler
+            .autocompletes
+            .register("user", youmubot_osu::discord::UsernameAutocomplete);
+    }
+
+    // Set up hooks, unless their modules are switched off
     #[cfg(feature = "osu")]
-    println!("osu! enabled.");
+    if env.osu.is_some()
+        && env
+            .prelude
+            .modules
+            .register(youmubot_osu::discord::HOOKS_MODULE)
+    {
+        handler.push_hook(youmubot_prelude::hook::Named(
+            "osu-links",
+            youmubot_prelude::hook::OnEdit(youmubot_osu::discord::hook),
+        ));
+        handler.push_hook(youmubot_prelude::hook::Named(
+            "osu-files",
+            youmubot_osu::discord::dot_osu_hook,
+        ));
+    }
     #[cfg(feature = "codeforces")]
-    println!("codeforces enabled.");
+    if env.codeforces && env.prelude.modules.register(youmubot_cf::HOOKS_MODULE) {
+        handler.push_hook(youmubot_prelude::hook::Named(
+            "codeforces",
+            youmubot_cf::InfoHook,
+        ));
+    }
+
+    let shutdown = env.prelude.shutdown.clone();
+    let supervisor = env.prelude.supervisor.clone();
+    let health = env.prelude.health.clone();
+    let sql = env.prelude.sql.clone();
+    let scheduler = env.prelude.scheduler.clone();
+    let groups = command_groups(&env);
+    data.insert::<Env>(env);
+    data.insert::<CommandFilter>(CommandFilter::new(&groups));
 
     // Set up base framework
-    let fw = setup_framework(&token[..]).await;
+    let fw = setup_framework(&token[..], prefixes, &cooldowns, groups).await;
 
     // Sets up a client
     let mut client = {
@@ -198,23 +503,111 @@ async fn main() {
             | GatewayIntents::DIRECT_MESSAGE_REACTIONS;
         Client::builder(token, intents)
             .type_map(data)
-            .framework(fw)
+            .framework(TracedFramework(fw))
             .event_handler(handler)
             .await
             .unwrap()
     };
 
-    let announcers = announcers.run(&client);
-    tokio::spawn(announcers.scan(std::time::Duration::from_secs(300)));
+    scheduler.connect(CacheAndHttp::from_client(&client));
+
+    let announcers = std::sync::Arc::new(announcers.run(&client));
+    supervisor.spawn("announcers", move |shutdown| {
+        let announcers = announcers.clone();
+        async move {
+            announcers
+                .scan(std::time::Duration::from_secs(300), shutdown)
+                .await
+        }
+    });
+
+    // The bot is healthy only when all shards are connected to the gateway.
+    health.register("discord", {
+        let shard_manager = client.shard_manager.clone();
+        move || {
+            let shard_manager = shard_manager.clone();
+            async move {
+                let runners = shard_manager.runners.lock().await;
+                if runners.is_empty() {
+                    bail!("no shards are running");
+                }
+                let down = runners
+                    .iter()
+                    .filter(|(_, r)| r.stage != ConnectionStage::Connected)
+                    .map(|(id, r)| format!("shard {} is {}", id, r.stage))
+                    .collect::<Vec<_>>();
+                if down.is_empty() {
+                    Ok(())
+                } else {
+                    Err(Error::msg(down.join(", ")))
+                }
+            }
+        }
+    });
+
+    // Shut down gracefully on SIGTERM and SIGINT.
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        wait_for_signal().await;
+        tracing::info!("shutting down...");
+        shutdown.trigger();
+        if !shutdown.drain(SHUTDOWN_TIMEOUT).await {
+            tracing::warn!(
+                "some work is still going on after {:?}, stopping anyway",
+                SHUTDOWN_TIMEOUT
+            );
+        }
+        shard_manager.shutdown_all().await;
+    });
 
     println!("Starting...");
     if let Err(v) = client.start().await {
         panic!("{}", v)
     }
+    // Wait for the pending database writes.
+    sql.close().await;
+    tracing::info!("bye!");
+}
+
+/// How long to wait for in-flight work (e.g. announcements) when shutting down.
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Wait for SIGTERM or SIGINT.
+async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate()).expect("SIGTERM handler");
+        tokio::select! {
+            _ = terminate.recv() => (),
+            _ = tokio::signal::ctrl_c() => (),
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await.expect("Ctrl-C handler");
+}
+
+/// The member cache, to be kept up to date with the member events.
+async fn members_of(ctx: &Context) -> std::sync::Arc<MemberCache> {
+    ctx.data.read().await.get::<MemberCache>().unwrap().clone()
+}
+
+/// Whether the bot is shutting down, and should not handle new messages.
+async fn shutting_down(ctx: &Context) -> bool {
+    ctx.data
+        .read()
+        .await
+        .get::<Shutdown>()
+        .is_some_and(|s| s.is_shutting_down())
 }
 
 // Sets up a framework for a client
-async fn setup_framework(token: &str) -> StandardFramework {
+async fn setup_framework(
+    token: &str,
+    prefixes: Vec<String>,
+    cooldowns: &[cooldown::Cooldown],
+    groups: Vec<&'static CommandGroup>,
+) -> StandardFramework {
     let http = serenity::http::Http::new(token);
     // Collect owners
     let owner = http
@@ -250,51 +643,113 @@ async fn setup_framework(token: &str) -> StandardFramework {
                 .time_span(30)
                 .limit(1),
         )
-        .await
-        .group(&prelude_commands::PRELUDE_GROUP);
+        .await;
+    let fw = cooldown::apply(fw, cooldowns).await;
     fw.configure(
         Configuration::new()
             .with_whitespace(false)
-            .prefixes(
-                var("PREFIX")
-                    .map(|v| v.split(',').map(|v| v.trim().to_owned()).collect())
-                    .unwrap_or_else(|_| vec!["y!".to_owned(), "y2!".to_owned()]),
-            )
+            .prefixes(prefixes)
+            .dynamic_prefix(command_filter::guild_prefix)
             .delimiters(vec![" / ", "/ ", " /", "/"])
             .owners([owner.id].iter().cloned().collect()),
     );
-    // groups here
+    groups.into_iter().fold(fw, |fw, group| fw.group(group))
+}
+
+/// All command groups of the enabled features that were set up.
+fn command_groups(env: &Env) -> Vec<&'static CommandGroup> {
+    let mut groups = vec![&prelude_commands::PRELUDE_GROUP];
     #[cfg(feature = "core")]
-    let fw = fw
-        .group(&youmubot_core::ADMIN_GROUP)
-        .group(&youmubot_core::FUN_GROUP)
-        .group(&youmubot_core::COMMUNITY_GROUP);
+    if env.core {
+        tracing::info!("core enabled");
+        groups.extend([
+            &youmubot_core::ADMIN_GROUP,
+            &youmubot_core::FUN_GROUP,
+            &youmubot_core::COMMUNITY_GROUP,
+        ]);
+    }
     #[cfg(feature = "osu")]
-    let fw = fw.group(&youmubot_osu::discord::OSU_GROUP);
+    if env.osu.is_some() {
+        tracing::info!("osu! enabled");
+        groups.extend([
+            &youmubot_osu::discord::OSU_GROUP,
+            &youmubot_osu::discord::OSUBACKUP_GROUP,
+        ]);
+    }
     #[cfg(feature = "codeforces")]
-    let fw = fw.group(&youmubot_cf::CODEFORCES_GROUP);
-    fw
+    if env.codeforces {
+        tracing::info!("codeforces enabled");
+        groups.push(&youmubot_cf::CODEFORCES_GROUP);
+    }
+    groups
 }
 
 // Hooks!
 
 #[hook]
-async fn before_hook(_: &Context, msg: &Message, command_name: &str) -> bool {
-    println!(
-        "Got command '{}' by user '{}'",
-        command_name, msg.author.name
-    );
+async fn before_hook(ctx: &Context, msg: &Message, command_name: &str) -> bool {
+    tracing::Span::current().record("command", command_name);
+    tracing::info!(user = %msg.author.name, "got command '{}'", command_name);
+    if shutting_down(ctx).await {
+        return false;
+    }
+    // Commands can be disabled per channel.
+    if !command_filter::check(ctx, msg, command_name).await {
+        return false;
+    }
+    // Commands can be restricted to roles.
+    if let Err(reason) = permissions::check(ctx, msg, command_name).await {
+        msg.reply(&ctx, reason).await.ok();
+        return false;
+    }
+    if let Some(metrics) = ctx.data.read().await.get::<Metrics>() {
+        metrics.start_timer(msg.id.get());
+    }
     true
 }
 
 #[hook]
 async fn after_hook(ctx: &Context, msg: &Message, command_name: &str, error: CommandResult) {
+    if let Some(metrics) = ctx.data.read().await.get::<Metrics>() {
+        let outcome = if error.is_ok() { "ok" } else { "error" };
+        metrics.inc(
+            &metrics::COMMANDS,
+            &[
+                ("command", command_name),
+                ("kind", "text"),
+                ("outcome", outcome),
+            ],
+        );
+        if let Some(duration) = metrics.stop_timer(msg.id.get()) {
+            metrics.observe(
+                &metrics::COMMAND_DURATION,
+                &[("command", command_name)],
+                duration,
+            );
+        }
+    }
     match error {
-        Ok(()) => println!("Processed command '{}'", command_name),
+        Ok(()) => tracing::info!("processed command '{}'", command_name),
         Err(why) => {
-            let reply = format!("Command '{}' returned error {:?}", command_name, why);
-            msg.reply(&ctx, &reply).await.ok();
-            println!("{}", reply)
+            let reporter = ctx
+                .data
+                .read()
+                .await
+                .get::<ErrorReporter>()
+                .unwrap()
+                .clone();
+            let reply = reporter
+                .report(
+                    ctx,
+                    ErrorSource {
+                        command: command_name,
+                        user: &msg.author.name,
+                        channel_id: msg.channel_id,
+                    },
+                    &*why,
+                )
+                .await;
+            msg.reply(&ctx, reply).await.ok();
         }
     }
 }
@@ -304,10 +759,10 @@ async fn on_dispatch_error(ctx: &Context, msg: &Message, error: DispatchError, _
     msg.reply(
         &ctx,
         &match error {
-            DispatchError::Ratelimited(rl) => format!(
-                "⏳ You are being rate-limited! Try this again in **{}**.",
-                youmubot_prelude::Duration(rl.rate_limit),
-            ),
+            DispatchError::Ratelimited(rl) => match cooldown::reply(&rl) {
+                Some(reply) => reply,
+                None => return,
+            },
             DispatchError::NotEnoughArguments { min, given } => {
                 format!(
                     "😕 The command needs at least **{}** arguments, I only got **{}**!",
@@ -319,6 +774,7 @@ async fn on_dispatch_error(ctx: &Context, msg: &Message, error: DispatchError, _
                 max, given
             ),
             DispatchError::OnlyForGuilds => "🔇 This command cannot be used in DMs.".to_owned(),
+            DispatchError::CheckFailed(_, Reason::User(reason)) => reason,
             _ => return,
         },
     )